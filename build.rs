@@ -0,0 +1,25 @@
+//! Generates the gRPC messages and server of `proto/aurora_mcp.proto`
+//! when the `grpc` feature is on, with `protoc` from `PROTOC` or else a
+//! vendored one.
+
+#[cfg(feature = "grpc")]
+fn main() -> std::io::Result<()> {
+    println!("cargo:rerun-if-changed=proto/aurora_mcp.proto");
+    println!("cargo:rerun-if-env-changed=PROTOC");
+    let protoc = match std::env::var_os("PROTOC") {
+        Some(protoc) => protoc.into(),
+        None => protoc_bin_vendored::protoc_bin_path().map_err(std::io::Error::other)?,
+    };
+    let mut config = tonic_prost_build::Config::new();
+    config.protoc_executable(protoc);
+    tonic_prost_build::configure()
+        .build_client(false)
+        .build_server(true)
+        .build_transport(false)
+        .compile_with_config(config, &["proto/aurora_mcp.proto"], &["proto"])
+}
+
+#[cfg(not(feature = "grpc"))]
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,433 @@
+//! Load test of a running server: `aurora-mcp bench --url <mcp endpoint>
+//! --tool <name>` opens `concurrency` sessions over the streamable HTTP
+//! transport, each calling the tool back to back for `duration`, and
+//! reports throughput, latency percentiles and how the calls failed. Each
+//! worker speaks plain HTTP/1.1 on a connection of its own, so the numbers
+//! cover the transport as deployed rather than an in-process shortcut.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use axum::body::Bytes;
+use axum::http::header::{ACCEPT, CONTENT_TYPE, HOST};
+use axum::http::{HeaderValue, Method, Request, Uri};
+use http_body_util::{BodyExt, Full};
+use hyper::client::conn::http1::{self, SendRequest};
+use hyper_util::rt::TokioIo;
+use rmcp::model::JsonObject;
+use serde_json::{Value, json};
+
+use crate::error::{Error, Result};
+use crate::queue::SERVER_BUSY;
+
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Distinct failure messages kept in a report; the rest are counted
+/// under one entry.
+const MAX_ERROR_KINDS: usize = 20;
+
+/// What to run.
+#[derive(Debug, Clone)]
+pub struct Bench {
+    /// The MCP endpoint, such as `http://127.0.0.1:8000/mcp`.
+    pub url: Uri,
+    pub tool: String,
+    pub arguments: JsonObject,
+    /// Sessions calling at once.
+    pub concurrency: usize,
+    /// How long calls are started for.
+    pub duration: Duration,
+    /// How long a call may take before it counts as failed.
+    pub timeout: Duration,
+}
+
+/// Outcome of a run.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub elapsed: Duration,
+    /// Latencies of the completed calls, errors included, sorted.
+    pub latencies: Vec<Duration>,
+    pub succeeded: usize,
+    /// Calls whose result had `isError` set.
+    pub tool_errors: usize,
+    /// Calls refused with a server busy error.
+    pub busy: usize,
+    /// Calls that failed otherwise: protocol errors, HTTP errors,
+    /// timeouts and lost connections.
+    pub failed: usize,
+    /// Failure messages and how often each occurred.
+    pub errors: BTreeMap<String, usize>,
+}
+
+impl Report {
+    pub fn calls(&self) -> usize {
+        self.succeeded + self.tool_errors + self.busy + self.failed
+    }
+
+    /// The latency below which `percent` of the calls completed.
+    pub fn percentile(&self, percent: f64) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let rank = (percent / 100.0 * self.latencies.len() as f64).ceil() as usize;
+        Some(self.latencies[rank.clamp(1, self.latencies.len()) - 1])
+    }
+
+    fn merge(&mut self, other: Report) {
+        self.latencies.extend(other.latencies);
+        self.succeeded += other.succeeded;
+        self.tool_errors += other.tool_errors;
+        self.busy += other.busy;
+        self.failed += other.failed;
+        for (message, count) in other.errors {
+            self.error(message, count);
+        }
+    }
+
+    fn error(&mut self, message: String, count: usize) {
+        if self.errors.len() >= MAX_ERROR_KINDS && !self.errors.contains_key(&message) {
+            *self.errors.entry("(other errors)".to_string()).or_default() += count;
+        } else {
+            *self.errors.entry(message).or_default() += count;
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let calls = self.calls();
+        let seconds = self.elapsed.as_secs_f64();
+        let rate = |count: usize| {
+            if calls == 0 {
+                0.0
+            } else {
+                count as f64 * 100.0 / calls as f64
+            }
+        };
+        writeln!(
+            f,
+            "{calls} calls in {seconds:.1}s ({:.1} calls/s)",
+            if seconds > 0.0 {
+                calls as f64 / seconds
+            } else {
+                0.0
+            }
+        )?;
+        writeln!(
+            f,
+            "succeeded {} ({:.2}%), tool errors {} ({:.2}%), busy {} ({:.2}%), failed {} ({:.2}%)",
+            self.succeeded,
+            rate(self.succeeded),
+            self.tool_errors,
+            rate(self.tool_errors),
+            self.busy,
+            rate(self.busy),
+            self.failed,
+            rate(self.failed),
+        )?;
+        if let (Some(min), Some(max)) = (self.latencies.first(), self.latencies.last()) {
+            let mean = self.latencies.iter().sum::<Duration>() / self.latencies.len() as u32;
+            write!(f, "latency: min {}, mean {}", millis(*min), millis(mean))?;
+            for percent in [50.0, 90.0, 95.0, 99.0, 99.9] {
+                if let Some(latency) = self.percentile(percent) {
+                    write!(f, ", p{percent} {}", millis(latency))?;
+                }
+            }
+            writeln!(f, ", max {}", millis(*max))?;
+        }
+        for (message, count) in &self.errors {
+            writeln!(f, "{count:>8} × {message}")?;
+        }
+        Ok(())
+    }
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Runs `bench`. Fails only if no session could be opened at all.
+pub async fn run(bench: &Bench) -> Result<Report> {
+    let mut sessions = Vec::with_capacity(bench.concurrency);
+    let mut report = Report::default();
+    for _ in 0..bench.concurrency {
+        match Session::open(&bench.url, bench.timeout).await {
+            Ok(session) => sessions.push(session),
+            Err(err) => report.error(format!("initialize: {err}"), 1),
+        }
+    }
+    if sessions.is_empty() {
+        return Err(Error::Io(std::io::Error::other(format!(
+            "no session could be opened at {}: {}",
+            bench.url,
+            report.errors.keys().next().map_or("", String::as_str)
+        ))));
+    }
+    let started = Instant::now();
+    let deadline = started + bench.duration;
+    let workers: Vec<_> = sessions
+        .into_iter()
+        .map(|session| tokio::spawn(work(session, bench.clone(), deadline)))
+        .collect();
+    for worker in workers {
+        match worker.await {
+            Ok(worker_report) => report.merge(worker_report),
+            Err(err) => report.error(format!("worker panicked: {err}"), 1),
+        }
+    }
+    report.elapsed = started.elapsed();
+    report.latencies.sort();
+    Ok(report)
+}
+
+/// Calls the tool over `session` until `deadline`, reconnecting when the
+/// session is lost.
+async fn work(mut session: Session, bench: Bench, deadline: Instant) -> Report {
+    let mut report = Report::default();
+    let params = json!({ "name": bench.tool, "arguments": bench.arguments });
+    while Instant::now() < deadline {
+        let started = Instant::now();
+        let response = session.request("tools/call", params.clone()).await;
+        let latency = started.elapsed();
+        match response {
+            Ok(message) => {
+                report.latencies.push(latency);
+                if let Some(error) = message.get("error") {
+                    if error["code"].as_i64() == Some(SERVER_BUSY.0.into()) {
+                        report.busy += 1;
+                    } else {
+                        report.failed += 1;
+                    }
+                    let text = error["message"].as_str().unwrap_or("JSON-RPC error");
+                    report.error(text.to_string(), 1);
+                } else if message["result"]["isError"] == Value::Bool(true) {
+                    report.tool_errors += 1;
+                } else {
+                    report.succeeded += 1;
+                }
+            }
+            Err(err) => {
+                report.failed += 1;
+                report.error(err.to_string(), 1);
+                if session.is_closed() {
+                    match Session::open(&bench.url, bench.timeout).await {
+                        Ok(reopened) => session = reopened,
+                        Err(err) => {
+                            report.error(format!("reconnect: {err}"), 1);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    session.close().await;
+    report
+}
+
+/// An MCP session on an HTTP/1.1 connection of its own.
+struct Session {
+    sender: SendRequest<Full<Bytes>>,
+    url: Uri,
+    host: HeaderValue,
+    id: Option<HeaderValue>,
+    next_id: u64,
+    timeout: Duration,
+}
+
+impl Session {
+    /// Connects and initializes a session.
+    async fn open(url: &Uri, timeout: Duration) -> Result<Self> {
+        if url.scheme_str() != Some("http") {
+            return Err(Error::InvalidArgument(format!(
+                "{url}: only http:// endpoints can be benchmarked"
+            )));
+        }
+        let Some(authority) = url.authority() else {
+            return Err(Error::InvalidArgument(format!("{url} has no host")));
+        };
+        let port = authority.port_u16().unwrap_or(80);
+        let stream = tokio::net::TcpStream::connect((authority.host(), port)).await?;
+        stream.set_nodelay(true)?;
+        let (sender, connection) = http1::handshake(TokioIo::new(stream))
+            .await
+            .map_err(std::io::Error::other)?;
+        tokio::spawn(connection);
+        let host = HeaderValue::from_str(authority.as_str())
+            .map_err(|err| Error::InvalidArgument(format!("{url}: {err}")))?;
+        let mut session = Self {
+            sender,
+            url: url.clone(),
+            host,
+            id: None,
+            next_id: 0,
+            timeout,
+        };
+        session
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": {},
+                    "clientInfo": {
+                        "name": "aurora-mcp-bench",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                }),
+            )
+            .await?;
+        session
+            .post(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .await?;
+        Ok(session)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    /// Sends a request and returns the response message, which may hold
+    /// a JSON-RPC error.
+    async fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let (content_type, body) = self.post(message).await?;
+        let messages = if content_type.starts_with("text/event-stream") {
+            sse_messages(&body)
+        } else {
+            serde_json::from_slice(&body).into_iter().collect()
+        };
+        messages
+            .into_iter()
+            .find(|message| message["id"] == id)
+            .ok_or_else(|| {
+                Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    format!("no response to {method}"),
+                ))
+            })
+    }
+
+    /// Posts `message`, returning the response's content type and body.
+    async fn post(&mut self, message: Value) -> Result<(String, Bytes)> {
+        let mut request = Request::post(self.url.clone())
+            .header(HOST, self.host.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .header(ACCEPT, "application/json, text/event-stream");
+        if let Some(id) = &self.id {
+            request = request.header(SESSION_HEADER, id.clone());
+        }
+        let body = serde_json::to_vec(&message).map_err(std::io::Error::other)?;
+        let request = request
+            .body(Full::new(Bytes::from(body)))
+            .map_err(std::io::Error::other)?;
+        let exchange = async {
+            self.sender.ready().await?;
+            let response = self.sender.send_request(request).await?;
+            let (parts, body) = response.into_parts();
+            let body = body.collect().await?.to_bytes();
+            Ok::<_, hyper::Error>((parts, body))
+        };
+        let (parts, body) = tokio::time::timeout(self.timeout, exchange)
+            .await
+            .map_err(|_| {
+                Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!("no response within {}s", self.timeout.as_secs()),
+                ))
+            })?
+            .map_err(std::io::Error::other)?;
+        if !parts.status.is_success() {
+            return Err(Error::Http {
+                url: self.url.to_string(),
+                status: parts.status.as_u16(),
+                message: String::from_utf8_lossy(&body).trim().to_string(),
+            });
+        }
+        if let Some(id) = parts.headers.get(SESSION_HEADER) {
+            self.id = Some(id.clone());
+        }
+        let content_type = parts
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        Ok((content_type, body))
+    }
+
+    /// Ends the session on the server, if the connection still stands.
+    async fn close(mut self) {
+        let Some(id) = self.id.take() else {
+            return;
+        };
+        let Ok(request) = Request::builder()
+            .method(Method::DELETE)
+            .uri(self.url.clone())
+            .header(HOST, self.host.clone())
+            .header(SESSION_HEADER, id)
+            .body(Full::default())
+        else {
+            return;
+        };
+        if self.sender.ready().await.is_ok() {
+            let _ = tokio::time::timeout(self.timeout, self.sender.send_request(request)).await;
+        }
+    }
+}
+
+/// The JSON messages of the `data` fields of an event stream.
+fn sse_messages(body: &[u8]) -> Vec<Value> {
+    let text = String::from_utf8_lossy(body);
+    text.split("\n\n")
+        .filter_map(|event| {
+            let data = event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|data| data.strip_prefix(' ').unwrap_or(data))
+                .collect::<Vec<_>>()
+                .join("\n");
+            serde_json::from_str(&data).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(millis: &[u64]) -> Report {
+        Report {
+            latencies: millis.iter().copied().map(Duration::from_millis).collect(),
+            ..Report::default()
+        }
+    }
+
+    #[test]
+    fn percentile_of_no_calls_is_none() {
+        assert_eq!(report(&[]).percentile(50.0), None);
+        assert_eq!(report(&[]).percentile(99.0), None);
+    }
+
+    #[test]
+    fn percentile_of_one_call_is_that_call() {
+        let report = report(&[7]);
+        for percent in [0.0, 50.0, 99.0, 100.0] {
+            assert_eq!(report.percentile(percent), Some(Duration::from_millis(7)));
+        }
+    }
+
+    #[test]
+    fn percentile_uses_the_nearest_rank() {
+        let report = report(&(1..=10).collect::<Vec<_>>());
+        let at = |percent| report.percentile(percent).map(|d| d.as_millis());
+        assert_eq!(at(0.0), Some(1));
+        assert_eq!(at(50.0), Some(5));
+        assert_eq!(at(51.0), Some(6));
+        assert_eq!(at(90.0), Some(9));
+        assert_eq!(at(99.0), Some(10));
+        assert_eq!(at(100.0), Some(10));
+    }
+}
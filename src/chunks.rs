@@ -0,0 +1,114 @@
+//! `fetch_chunk`: reading a large artifact piece by piece through a tool.
+//! Tools that produce RPMs, core dumps, profiles or reports return a
+//! `file://` link to them (with the size) rather than their bytes. Clients
+//! with resource support read the link with `?offset=&length=`; this tool
+//! does the same for those that only call tools, such as the REST and
+//! gRPC facades, keeping each reply within client message limits.
+
+use std::path::PathBuf;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{Peer, RoleServer, tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::device::files::{self, ByteRange};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+use crate::workspace::{self, Filter, WorkspaceContent};
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FetchChunkParams {
+    /// The artifact: a `file://` link a tool returned, or an absolute path.
+    pub handle: String,
+    /// Byte to start at.
+    #[serde(default)]
+    pub offset: u64,
+    /// Bytes to read (default and most: `max_read_bytes` of the
+    /// `[workspace]` config, 1 MiB unless changed).
+    #[serde(default)]
+    pub length: Option<u64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Chunk {
+    /// `file://` URI of the artifact.
+    pub handle: String,
+    pub mime_type: String,
+    /// Size of the whole artifact.
+    pub size: u64,
+    pub offset: u64,
+    /// Bytes in this chunk.
+    pub length: u64,
+    /// Offset of the next chunk; absent after the last one.
+    pub next_offset: Option<u64>,
+    /// The chunk's bytes, base64-encoded.
+    pub data: String,
+}
+
+#[tool_router(router = chunks_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Read a large artifact a tool linked to (RPM, core dump, profile, report) in chunks: pass the file:// link as handle, with offset and length. Returns base64 data, the total size and next_offset until the end. Use it instead of resources/read when the client cannot read resources.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn fetch_chunk(
+        &self,
+        Parameters(params): Parameters<FetchChunkParams>,
+        peer: Peer<RoleServer>,
+    ) -> Result<Json<Chunk>> {
+        let path = match workspace::parse_uri(&params.handle) {
+            Some((path, _)) => path,
+            None if params.handle.starts_with('/') => PathBuf::from(&params.handle),
+            None => {
+                return Err(Error::InvalidArgument(format!(
+                    "'{}' is not a file:// link or an absolute path",
+                    params.handle
+                )));
+            }
+        };
+        let client = self.client_roots().get(&peer).await?;
+        let roots = workspace::roots(&self.state().config, client.as_deref());
+        let filter = Filter {
+            range: ByteRange {
+                offset: params.offset,
+                length: params.length,
+            },
+            ..Filter::default()
+        };
+        let options = &self.state().config.workspace;
+        let (data, offset, size, truncated) =
+            match workspace::read(options, &roots, &path, &filter)? {
+                Some(WorkspaceContent::File {
+                    data,
+                    offset,
+                    size,
+                    truncated,
+                }) => (data, offset, size, truncated),
+                Some(WorkspaceContent::Directory { .. }) => {
+                    return Err(Error::InvalidArgument(format!(
+                        "{} is a directory",
+                        path.display()
+                    )));
+                }
+                None => {
+                    return Err(Error::InvalidArgument(format!(
+                        "{} does not exist",
+                        path.display()
+                    )));
+                }
+            };
+        let length = data.len() as u64;
+        Ok(Json(Chunk {
+            handle: workspace::uri(&path),
+            mime_type: files::mime_type(&path.to_string_lossy(), false).to_string(),
+            size,
+            offset,
+            length,
+            next_offset: truncated.then_some(offset + length),
+            data: BASE64.encode(data),
+        }))
+    }
+}
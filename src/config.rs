@@ -0,0 +1,162 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::device::{DbusOptions, DeviceConfig, FilesOptions, MonitorOptions, SshOptions};
+use crate::docs::DocsOptions;
+use crate::error::{Error, Result};
+use crate::listener::HttpOptions;
+use crate::prompts::PromptsOptions;
+use crate::queue::CallOptions;
+use crate::sdk::{BuildServiceOptions, SdkOptions, StoreOptions};
+use crate::secrets::SecretStore;
+use crate::webhooks::WebhooksOptions;
+use crate::workspace::WorkspaceOptions;
+
+/// Server configuration, read from `config.toml`.
+///
+/// ```toml
+/// [[devices]]
+/// name = "phone"
+/// host = "192.168.2.15"
+/// identity_file = "~/AuroraOS/vmshare/ssh/private_keys/sdk"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// File the config was loaded from, if any.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    /// Where collected artifacts (crash bundles, logs) are stored.
+    /// Defaults to `$XDG_DATA_HOME/aurora-mcp`.
+    #[serde(default)]
+    pub data_dir: Option<PathBuf>,
+    /// Secrets file (default: `secrets.toml` next to the config file).
+    #[serde(default)]
+    pub secrets_file: Option<PathBuf>,
+    #[serde(default)]
+    pub ssh: SshOptions,
+    #[serde(default)]
+    pub dbus: DbusOptions,
+    #[serde(default)]
+    pub monitor: MonitorOptions,
+    #[serde(default)]
+    pub files: FilesOptions,
+    #[serde(default)]
+    pub sdk: SdkOptions,
+    #[serde(default)]
+    pub store: StoreOptions,
+    #[serde(default)]
+    pub build_service: BuildServiceOptions,
+    #[serde(default)]
+    pub docs: DocsOptions,
+    #[serde(default)]
+    pub prompts: PromptsOptions,
+    #[serde(default)]
+    pub workspace: WorkspaceOptions,
+    #[serde(default)]
+    pub webhooks: WebhooksOptions,
+    #[serde(default)]
+    pub http: HttpOptions,
+    #[serde(default)]
+    pub calls: CallOptions,
+    #[serde(default)]
+    pub devices: Vec<DeviceConfig>,
+}
+
+impl Config {
+    /// Loads the config from `path`, or from the default location when
+    /// `path` is `None`. A missing default config yields an empty one.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if !required && err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(err) => {
+                return Err(Error::Config(format!("{}: {err}", path.display())));
+            }
+        };
+        let mut config: Self = toml::from_str(&text)
+            .map_err(|err| Error::Config(format!("{}: {err}", path.display())))?;
+        config.path = Some(path);
+        Ok(config)
+    }
+
+    /// Appends a `[[devices]]` entry to the config file, creating it at the
+    /// default location if the server started without one. Existing content
+    /// and comments are left untouched.
+    pub fn append_device(&self, device: &DeviceConfig) -> Result<PathBuf> {
+        let path = self
+            .path
+            .clone()
+            .or_else(default_path)
+            .ok_or_else(|| Error::Config("no config path; set HOME or --config".to_string()))?;
+        let body = toml::to_string(device).map_err(|err| Error::Config(err.to_string()))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        writeln!(file, "\n[[devices]]\n{}", body.trim_end())?;
+        Ok(path)
+    }
+
+    pub fn secrets(&self) -> SecretStore {
+        let path = match &self.secrets_file {
+            Some(path) => expand_tilde(path),
+            None => self
+                .path
+                .clone()
+                .or_else(default_path)
+                .and_then(|path| Some(path.parent()?.join("secrets.toml")))
+                .unwrap_or_else(|| PathBuf::from("secrets.toml")),
+        };
+        SecretStore::new(path)
+    }
+
+    pub fn data_dir(&self) -> PathBuf {
+        if let Some(dir) = &self.data_dir {
+            return expand_tilde(dir);
+        }
+        std::env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home_dir().map(|home| home.join(".local").join("share")))
+            .unwrap_or_else(std::env::temp_dir)
+            .join("aurora-mcp")
+    }
+}
+
+/// `$XDG_CONFIG_HOME/aurora-mcp/config.toml`, falling back to `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".config")))?;
+    Some(base.join("aurora-mcp").join("config.toml"))
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Expands a leading `~/` to the user's home directory.
+pub fn expand_tilde(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
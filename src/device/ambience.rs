@@ -0,0 +1,198 @@
+//! `device_ambience`: listing and switching ambiences, which set the
+//! device's colour scheme (light or dark) and theme.
+
+use std::time::Duration;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::dbus::{self, Bus};
+use super::{Device, ssh};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+const AMBIENCE_DIR: &str = "/usr/share/ambience";
+
+/// Separates the ambience files in the listing output.
+const FILE_MARKER: &str = "--aurora-mcp-ambience--";
+
+/// Time for the home screen to apply a new ambience before it is read back.
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorScheme {
+    /// Light text on a dark background.
+    Dark,
+    /// Dark text on a light background.
+    Light,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AmbienceParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Switch to this ambience, by name (e.g. `sailfish`) or display name.
+    #[serde(default)]
+    pub ambience: Option<String>,
+    /// Switch to the first ambience with this colour scheme. Ignored when
+    /// `ambience` is given.
+    #[serde(default)]
+    pub scheme: Option<ColorScheme>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AmbienceReport {
+    pub device: String,
+    /// Ambience file in use, if the device reports it.
+    pub active: Option<String>,
+    /// The ambience switched to during this call.
+    pub switched_to: Option<String>,
+    pub ambiences: Vec<Ambience>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Ambience {
+    /// File stem, e.g. `sailfish` for `sailfish/sailfish.ambience`.
+    pub name: String,
+    pub display_name: Option<String>,
+    pub file: String,
+    pub scheme: Option<ColorScheme>,
+}
+
+/// Fields of an `.ambience` file (JSON) that are reported.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AmbienceFile {
+    display_name: Option<String>,
+    color_scheme: Option<String>,
+}
+
+fn parse_ambience(file: &str, json: &str) -> Ambience {
+    let name = file
+        .rsplit('/')
+        .next()
+        .unwrap_or(file)
+        .trim_end_matches(".ambience")
+        .to_string();
+    let parsed: Option<AmbienceFile> = serde_json::from_str(json).ok();
+    let scheme = parsed
+        .as_ref()
+        .and_then(|parsed| parsed.color_scheme.as_deref())
+        .and_then(|scheme| match scheme {
+            "lighttext" => Some(ColorScheme::Dark),
+            "darktext" => Some(ColorScheme::Light),
+            _ => None,
+        });
+    Ambience {
+        name,
+        display_name: parsed.and_then(|parsed| parsed.display_name),
+        file: file.to_string(),
+        scheme,
+    }
+}
+
+async fn read(device: &Device) -> Result<(Option<String>, Vec<Ambience>)> {
+    let command = ssh::sections(&[
+        &format!(
+            "for f in {AMBIENCE_DIR}/*/*.ambience; do [ -r \"$f\" ] && echo \"{FILE_MARKER} $f\" && cat \"$f\"; done; true"
+        ),
+        &format!(
+            "{}dconf read /desktop/jolla/theme/active_ambience 2>/dev/null || true",
+            dbus::SESSION_ENV
+        ),
+    ]);
+    let stdout = device.exec_checked(&command).await?;
+    let [files, active] = ssh::split_sections(&stdout)[..] else {
+        return Err(Error::Parse {
+            device: device.name().to_string(),
+            message: "missing output sections".to_string(),
+        });
+    };
+    let ambiences = files
+        .split(&format!("{FILE_MARKER} "))
+        .filter_map(|chunk| {
+            let (file, json) = chunk.split_once('\n')?;
+            Some(parse_ambience(file.trim(), json))
+        })
+        .collect();
+    // dconf prints GVariant strings in single quotes.
+    let active = active.trim().trim_matches('\'');
+    let active = active.strip_prefix("file://").unwrap_or(active);
+    Ok(((!active.is_empty()).then(|| active.to_string()), ambiences))
+}
+
+#[tool_router(router = ambience_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "List a device's ambiences with their colour scheme (light or dark) and the active one. Set ambience (by name) or scheme=light|dark to switch, e.g. to capture screenshots of an app under both appearances."
+    )]
+    pub async fn device_ambience(
+        &self,
+        Parameters(params): Parameters<AmbienceParams>,
+    ) -> Result<Json<AmbienceReport>> {
+        let device = self.devices().get(&params.device)?;
+        let (active, ambiences) = read(&device).await?;
+        let target = match (&params.ambience, params.scheme) {
+            (Some(name), _) => Some(
+                ambiences
+                    .iter()
+                    .find(|a| &a.name == name || a.display_name.as_ref() == Some(name))
+                    .ok_or_else(|| {
+                        Error::InvalidArgument(format!(
+                            "no ambience '{name}'; available: {}",
+                            ambiences
+                                .iter()
+                                .map(|a| a.name.as_str())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ))
+                    })?,
+            ),
+            (None, Some(scheme)) => Some(
+                ambiences
+                    .iter()
+                    .find(|a| a.scheme == Some(scheme))
+                    .ok_or_else(|| {
+                        let scheme = match scheme {
+                            ColorScheme::Dark => "dark",
+                            ColorScheme::Light => "light",
+                        };
+                        Error::InvalidArgument(format!(
+                            "device '{}' has no {scheme} ambience",
+                            params.device
+                        ))
+                    })?,
+            ),
+            (None, None) => None,
+        };
+        let Some(target) = target.cloned() else {
+            return Ok(Json(AmbienceReport {
+                device: params.device,
+                active,
+                switched_to: None,
+                ambiences,
+            }));
+        };
+
+        dbus::call(
+            &device,
+            Bus::Session,
+            "com.jolla.ambienced",
+            "/com/jolla/ambienced",
+            "com.jolla.ambienced.setAmbience",
+            &[format!("string:file://{}", target.file)],
+        )
+        .await?;
+        tokio::time::sleep(SETTLE_TIME).await;
+        let (active, ambiences) = read(&device).await?;
+        Ok(Json(AmbienceReport {
+            device: params.device,
+            active,
+            switched_to: Some(target.name),
+            ambiences,
+        }))
+    }
+}
@@ -0,0 +1,364 @@
+//! Android apps on devices with Aurora AppSupport: installing APKs,
+//! listing packages and starting or stopping apps inside the Android
+//! container.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::apps::{AppState, parse_pids};
+use super::multi::{MultiDeviceReport, fan_out};
+use super::{Device, ssh, tail_lines};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+const INSTALL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Booting the container takes a while on first start.
+const RUNTIME_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// systemd units of the Android runtime on different releases, most
+/// recent first.
+const RUNTIME_UNITS: &[&str] = &["appsupport.service", "aliendalvik.service"];
+
+/// Packages that ship the runtime.
+const RUNTIME_PACKAGES: &[&str] = &["appsupport", "aliendalvik"];
+
+/// Attaching to a stopped container boots Android first.
+const ANDROID_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ApkInstallParams {
+    /// Device names from the config.
+    pub devices: Vec<String>,
+    /// Path to the APK on the host.
+    pub apk: PathBuf,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ApkInstallResult {
+    pub bytes_uploaded: u64,
+    pub output_tail: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AndroidAppsParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Include preinstalled system packages.
+    #[serde(default)]
+    pub include_system: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AndroidAppList {
+    pub device: String,
+    pub apps: Vec<AndroidApp>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AndroidApp {
+    /// Package name, e.g. `org.example.app`.
+    pub package: String,
+    pub version_code: Option<u64>,
+    /// APK path inside the Android container.
+    pub apk_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AndroidAction {
+    Launch,
+    Stop,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AndroidAppParams {
+    /// Device names from the config.
+    pub devices: Vec<String>,
+    /// Android package name, e.g. `org.example.app`.
+    pub package: String,
+    pub action: AndroidAction,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AndroidAppState {
+    pub running: bool,
+    pub pids: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RuntimeAction {
+    /// Only report the state.
+    #[default]
+    Status,
+    Start,
+    Stop,
+    Restart,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AndroidRuntimeParams {
+    /// Device name from the config.
+    pub device: String,
+    #[serde(default)]
+    pub action: RuntimeAction,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AndroidRuntimeStatus {
+    pub device: String,
+    /// AppSupport is installed on the device.
+    pub installed: bool,
+    /// Installed runtime package and version, e.g. `appsupport 1.2.0`.
+    pub package: Option<String>,
+    /// systemd unit running the container.
+    pub unit: Option<String>,
+    /// Started at boot.
+    pub enabled: bool,
+    pub running: bool,
+    /// systemd state, e.g. `active (running)` or `inactive (dead)`.
+    pub state: Option<String>,
+    /// Android release inside the container, when running.
+    pub android_version: Option<String>,
+    /// The action performed before reporting, if any.
+    pub action: Option<String>,
+}
+
+/// Wraps `command` to run in the Android container's shell, through
+/// `appsupport-attach.sh` or, on older releases, `lxc-attach`.
+pub fn android_shell(command: &str) -> String {
+    let quoted = ssh::quote(command);
+    format!(
+        "if command -v appsupport-attach.sh >/dev/null 2>&1; \
+         then appsupport-attach.sh /system/bin/sh -c {quoted}; \
+         elif command -v lxc-attach >/dev/null 2>&1; \
+         then lxc-attach -n aliendalvik -- /system/bin/sh -c {quoted}; \
+         else echo 'Android AppSupport is not installed on this device' >&2; exit 127; fi"
+    )
+}
+
+fn validate_package(package: &str) -> Result<()> {
+    let valid = package.contains('.')
+        && package.split('.').all(|part| {
+            !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidArgument(format!(
+            "'{package}' is not an Android package name"
+        )))
+    }
+}
+
+/// Parses a `pm list packages -f --show-versioncode` line, e.g.
+/// `package:/data/app/org.example-1/base.apk=org.example versionCode:42`.
+fn parse_package_line(line: &str) -> Option<AndroidApp> {
+    let rest = line.trim().strip_prefix("package:")?;
+    let (rest, version_code) = match rest.split_once(" versionCode:") {
+        Some((rest, code)) => (rest, code.trim().parse().ok()),
+        None => (rest, None),
+    };
+    let (apk_path, package) = match rest.rsplit_once('=') {
+        Some((path, package)) => (Some(path.to_string()), package),
+        None => (None, rest),
+    };
+    Some(AndroidApp {
+        package: package.to_string(),
+        version_code,
+        apk_path,
+    })
+}
+
+async fn runtime_status(device: &Device) -> Result<AndroidRuntimeStatus> {
+    let command = ssh::sections(&[
+        &format!(
+            "u=$(for u in {}; do systemctl cat $u >/dev/null 2>&1 && {{ echo $u; break; }}; done); echo \"$u\"",
+            RUNTIME_UNITS.join(" ")
+        ),
+        &format!(
+            "rpm -q --qf '%{{NAME}} %{{VERSION}}\\n' {} 2>/dev/null | grep -v 'not installed'; true",
+            RUNTIME_PACKAGES.join(" ")
+        ),
+        "[ -n \"$u\" ] && systemctl show -p ActiveState -p SubState -p UnitFileState \"$u\"; true",
+        &format!(
+            "[ -n \"$u\" ] && systemctl -q is-active \"$u\" && {{ {}; }} 2>/dev/null; true",
+            android_shell("getprop ro.build.version.release")
+        ),
+    ]);
+    let stdout = device
+        .exec_privileged(&command, ANDROID_TIMEOUT)
+        .await?
+        .into_stdout(device.name())?;
+    let [unit, package, show, version] = ssh::split_sections(&stdout)[..] else {
+        return Err(Error::Parse {
+            device: device.name().to_string(),
+            message: "missing output sections".to_string(),
+        });
+    };
+    let non_empty = |value: &str| Some(value.trim().to_string()).filter(|v| !v.is_empty());
+    let property = |key: &str| {
+        show.lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+            .unwrap_or("")
+            .trim()
+    };
+    let unit = non_empty(unit);
+    let package = package.lines().next().and_then(non_empty);
+    let active = property("ActiveState");
+    Ok(AndroidRuntimeStatus {
+        device: device.name().to_string(),
+        installed: unit.is_some() || package.is_some(),
+        package,
+        enabled: property("UnitFileState") == "enabled",
+        running: active == "active",
+        state: (!active.is_empty()).then(|| format!("{active} ({})", property("SubState"))),
+        android_version: non_empty(version),
+        unit,
+        action: None,
+    })
+}
+
+async fn install(device: Device, apk: &Path) -> Result<ApkInstallResult> {
+    let file_name = apk
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| Error::InvalidArgument(format!("{} is not a file", apk.display())))?;
+    let remote = format!("/tmp/aurora-mcp-{file_name}");
+    let bytes_uploaded = device.upload(apk, &remote).await?;
+    let quoted = ssh::quote(&remote);
+    // apkd-install hands the APK to the running container; without it the
+    // file is streamed into `pm install`.
+    let command = format!(
+        "if command -v apkd-install >/dev/null 2>&1; then apkd-install {quoted} 2>&1; \
+         else cat {quoted} | {} 2>&1; fi; status=$?; rm -f {quoted}; exit $status",
+        android_shell(&format!("pm install -r -S {bytes_uploaded}"))
+    );
+    let output = device.exec_privileged(&command, INSTALL_TIMEOUT).await?;
+    let output_tail = tail_lines(&output.stdout, 30);
+    // `pm` reports failures such as INSTALL_FAILED_VERSION_DOWNGRADE with
+    // exit status 0.
+    let failed = output_tail.iter().any(|line| line.contains("Failure"));
+    if !output.success() || failed {
+        return Err(Error::RemoteCommand {
+            device: device.name().to_string(),
+            status: output.status,
+            stderr: output_tail.join("\n"),
+        });
+    }
+    Ok(ApkInstallResult {
+        bytes_uploaded,
+        output_tail,
+    })
+}
+
+#[tool_router(router = android_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Upload a local APK and install it through Aurora AppSupport on one or more devices concurrently. Existing installs are upgraded in place."
+    )]
+    pub async fn android_install_apk(
+        &self,
+        Parameters(params): Parameters<ApkInstallParams>,
+    ) -> Result<Json<MultiDeviceReport<ApkInstallResult>>> {
+        if !params.apk.is_file() {
+            return Err(Error::InvalidArgument(format!(
+                "{} does not exist",
+                params.apk.display()
+            )));
+        }
+        let apk = &params.apk;
+        fan_out(self, &params.devices, |device| install(device, apk))
+            .await
+            .map(Json)
+    }
+
+    #[tool(
+        description = "List Android apps installed in a device's AppSupport container, with version codes. System packages are left out unless include_system=true.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn android_apps(
+        &self,
+        Parameters(params): Parameters<AndroidAppsParams>,
+    ) -> Result<Json<AndroidAppList>> {
+        let device = self.devices().get(&params.device)?;
+        let filter = if params.include_system { "" } else { " -3" };
+        let command = android_shell(&format!("pm list packages -f --show-versioncode{filter}"));
+        let stdout = device
+            .exec_privileged(&command, ANDROID_TIMEOUT)
+            .await?
+            .into_stdout(device.name())?;
+        let mut apps: Vec<AndroidApp> = stdout.lines().filter_map(parse_package_line).collect();
+        apps.sort_by(|a, b| a.package.cmp(&b.package));
+        Ok(Json(AndroidAppList {
+            device: params.device,
+            apps,
+        }))
+    }
+
+    #[tool(
+        description = "Launch or stop an Android app by package name on one or more devices with AppSupport, and report whether it is running afterwards."
+    )]
+    pub async fn android_app_control(
+        &self,
+        Parameters(params): Parameters<AndroidAppParams>,
+    ) -> Result<Json<MultiDeviceReport<AndroidAppState>>> {
+        validate_package(&params.package)?;
+        let package = &params.package;
+        let action = match params.action {
+            AndroidAction::Launch => format!(
+                "monkey -p {package} -c android.intent.category.LAUNCHER 1 >/dev/null 2>&1; sleep 2"
+            ),
+            AndroidAction::Stop => format!("am force-stop {package}"),
+        };
+        let command = android_shell(&format!("{action}; pidof {package} || true"));
+        let command = command.as_str();
+        fan_out(self, &params.devices, |device| async move {
+            device
+                .exec_privileged(command, ANDROID_TIMEOUT)
+                .await?
+                .into_stdout(device.name())
+                .map(|out| {
+                    let AppState { running, pids } = parse_pids(&out);
+                    AndroidAppState { running, pids }
+                })
+        })
+        .await
+        .map(Json)
+    }
+
+    #[tool(
+        description = "Report whether the Android runtime (AppSupport) is installed, enabled and running on a device, with its Android version. Set action to start, stop or restart the runtime first."
+    )]
+    pub async fn android_runtime(
+        &self,
+        Parameters(params): Parameters<AndroidRuntimeParams>,
+    ) -> Result<Json<AndroidRuntimeStatus>> {
+        let device = self.devices().get(&params.device)?;
+        let verb = match params.action {
+            RuntimeAction::Status => return runtime_status(&device).await.map(Json),
+            RuntimeAction::Start => "start",
+            RuntimeAction::Stop => "stop",
+            RuntimeAction::Restart => "restart",
+        };
+        let Some(unit) = runtime_status(&device).await?.unit else {
+            return Err(Error::InvalidArgument(format!(
+                "Android AppSupport is not installed on device '{}'",
+                params.device
+            )));
+        };
+        device
+            .exec_privileged(&format!("systemctl {verb} {unit}"), RUNTIME_TIMEOUT)
+            .await?
+            .into_stdout(device.name())?;
+        let mut status = runtime_status(&device).await?;
+        status.action = Some(verb.to_string());
+        Ok(Json(status))
+    }
+}
@@ -0,0 +1,106 @@
+//! `launch_app` and `stop_app`: starting and stopping installed
+//! applications on one or more devices.
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::multi::{MultiDeviceReport, fan_out};
+use super::ssh;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AppParams {
+    /// Device names from the config.
+    pub devices: Vec<String>,
+    /// Application binary name under /usr/bin, e.g. `ru.example.myapp`.
+    pub app: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AppState {
+    pub running: bool,
+    pub pids: Vec<u32>,
+}
+
+fn binary_path(app: &str) -> Result<String> {
+    if app.is_empty() || app.contains('/') {
+        return Err(Error::InvalidArgument(format!(
+            "'{app}' is not a binary name"
+        )));
+    }
+    Ok(ssh::quote(&format!("/usr/bin/{app}")))
+}
+
+/// Checks that `app` is a plain binary name, `[A-Za-z0-9._-]` only, so it
+/// can name files on the device and locally as it is.
+pub(crate) fn check_app_name(app: &str) -> Result<()> {
+    let plain = app
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b"._-".contains(&b));
+    if !plain || app.is_empty() || app == "." || app == ".." {
+        return Err(Error::InvalidArgument(format!(
+            "'{app}' is not a binary name; use letters, digits, '.', '_' and '-'"
+        )));
+    }
+    Ok(())
+}
+
+pub(super) fn parse_pids(stdout: &str) -> AppState {
+    let pids: Vec<u32> = stdout
+        .split_whitespace()
+        .filter_map(|pid| pid.parse().ok())
+        .collect();
+    AppState {
+        running: !pids.is_empty(),
+        pids,
+    }
+}
+
+#[tool_router(router = apps_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Launch an installed application on one or more devices (through the invoker when available) and report whether it is running afterwards."
+    )]
+    pub async fn launch_app(
+        &self,
+        Parameters(params): Parameters<AppParams>,
+    ) -> Result<Json<MultiDeviceReport<AppState>>> {
+        let binary = binary_path(&params.app)?;
+        let command = format!(
+            "if command -v invoker >/dev/null 2>&1; \
+             then setsid invoker --type=silica-qt5 --single-instance {binary} >/dev/null 2>&1 </dev/null & \
+             else setsid {binary} >/dev/null 2>&1 </dev/null & fi; \
+             sleep 2; pidof {binary} || true"
+        );
+        let command = command.as_str();
+        fan_out(self, &params.devices, |device| async move {
+            device
+                .exec_checked(command)
+                .await
+                .map(|out| parse_pids(&out))
+        })
+        .await
+        .map(Json)
+    }
+
+    #[tool(description = "Stop a running application on one or more devices.")]
+    pub async fn stop_app(
+        &self,
+        Parameters(params): Parameters<AppParams>,
+    ) -> Result<Json<MultiDeviceReport<AppState>>> {
+        let binary = binary_path(&params.app)?;
+        let command = format!("pkill -f -- {binary}; sleep 1; pidof {binary} || true");
+        let command = command.as_str();
+        fan_out(self, &params.devices, |device| async move {
+            device
+                .exec_checked(command)
+                .await
+                .map(|out| parse_pids(&out))
+        })
+        .await
+        .map(Json)
+    }
+}
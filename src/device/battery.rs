@@ -0,0 +1,143 @@
+//! Battery and thermal readings from `/sys`, served as the
+//! `battery://{device}` resource.
+
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{Device, ssh};
+use crate::error::{Error, Result};
+
+pub const URI_SCHEME: &str = "battery://";
+
+/// Settings for subscribed device resources, from the `[monitor]` config
+/// table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MonitorOptions {
+    /// How often subscribed resources are re-read from the device.
+    pub interval_secs: u64,
+}
+
+impl Default for MonitorOptions {
+    fn default() -> Self {
+        Self { interval_secs: 30 }
+    }
+}
+
+impl MonitorOptions {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.max(1))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct BatteryStatus {
+    pub device: String,
+    /// False on devices without a battery, such as the emulator.
+    pub present: bool,
+    pub level_percent: Option<u8>,
+    /// `Charging`, `Discharging`, `Full` or `Not charging`.
+    pub status: Option<String>,
+    /// A charger (USB or mains) is connected.
+    pub charger_online: bool,
+    pub temperature_c: Option<f64>,
+    pub voltage_v: Option<f64>,
+    /// Negative while discharging on most kernels.
+    pub current_ma: Option<f64>,
+    pub health: Option<String>,
+    pub thermal_zones: Vec<ThermalZone>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct ThermalZone {
+    pub zone: String,
+    pub temperature_c: f64,
+}
+
+impl BatteryStatus {
+    /// Whether `other` differs in a way worth notifying subscribers about.
+    /// Voltage and current fluctuate constantly and are ignored.
+    pub fn changed(&self, other: &BatteryStatus) -> bool {
+        self.level_percent != other.level_percent
+            || self.status != other.status
+            || self.charger_online != other.charger_online
+            || self.temperature_c.map(f64::round) != other.temperature_c.map(f64::round)
+    }
+}
+
+pub fn uri(device: &str) -> String {
+    format!("{URI_SCHEME}{device}")
+}
+
+/// Device name of a `battery://` URI.
+pub fn parse_uri(uri: &str) -> Option<&str> {
+    uri.strip_prefix(URI_SCHEME)
+        .filter(|name| !name.is_empty() && !name.contains('/'))
+}
+
+pub async fn read(device: &Device) -> Result<BatteryStatus> {
+    let script = ssh::sections(&[
+        "for d in /sys/class/power_supply/*; do \
+           t=$(cat \"$d/type\" 2>/dev/null); \
+           for f in capacity status temp voltage_now current_now health online; do \
+             [ -r \"$d/$f\" ] && echo \"$t $f $(cat \"$d/$f\" 2>/dev/null)\"; \
+           done; \
+         done; true",
+        "for z in /sys/class/thermal/thermal_zone*; do \
+           [ -r \"$z/temp\" ] && echo \"$(cat \"$z/type\" 2>/dev/null) $(cat \"$z/temp\")\"; \
+         done; true",
+    ]);
+    let stdout = device.exec_checked(&script).await?;
+    let [supplies, thermal] = ssh::split_sections(&stdout)[..] else {
+        return Err(Error::Parse {
+            device: device.name().to_string(),
+            message: "missing output sections".to_string(),
+        });
+    };
+    Ok(parse(device.name(), supplies, thermal))
+}
+
+fn parse(device: &str, supplies: &str, thermal: &str) -> BatteryStatus {
+    let rows: Vec<(&str, &str, &str)> = supplies
+        .lines()
+        .filter_map(|line| {
+            let (kind, rest) = line.split_once(' ')?;
+            let (key, value) = rest.split_once(' ').unwrap_or((rest, ""));
+            Some((kind, key, value.trim()))
+        })
+        .collect();
+    let battery = |key: &str| {
+        rows.iter()
+            .find(|(kind, k, _)| *kind == "Battery" && *k == key)
+            .map(|(_, _, value)| value.to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let number = |key: &str| battery(key).and_then(|value| value.parse::<f64>().ok());
+    BatteryStatus {
+        device: device.to_string(),
+        present: rows.iter().any(|(kind, _, _)| *kind == "Battery"),
+        level_percent: battery("capacity").and_then(|value| value.parse().ok()),
+        status: battery("status"),
+        charger_online: rows
+            .iter()
+            .any(|(kind, key, value)| *kind != "Battery" && *key == "online" && *value == "1"),
+        // The power supply class reports tenths of a degree, volts and
+        // amperes in micro units.
+        temperature_c: number("temp").map(|t| t / 10.0),
+        voltage_v: number("voltage_now").map(|v| v / 1e6),
+        current_ma: number("current_now").map(|c| c / 1e3),
+        health: battery("health"),
+        thermal_zones: thermal
+            .lines()
+            .filter_map(|line| {
+                let (zone, temp) = line.rsplit_once(' ')?;
+                Some(ThermalZone {
+                    zone: zone.to_string(),
+                    temperature_c: temp.trim().parse::<f64>().ok()? / 1000.0,
+                })
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,138 @@
+//! `clipboard_get` and `clipboard_set`: reading and writing the device
+//! clipboard of the running home screen session.
+//!
+//! `wl-paste`/`wl-copy` are used when installed; otherwise a short QML
+//! script reads or sets Silica's `Clipboard.text` through `qmlscene`.
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{DeviceParams, ssh};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Lets clients started over SSH connect to the home screen compositor.
+const GUI_ENV: &str = "export XDG_RUNTIME_DIR=\"${XDG_RUNTIME_DIR:-/run/user/$(id -u)}\" \
+                       WAYLAND_DISPLAY=\"${WAYLAND_DISPLAY:-wayland-0}\" QT_QPA_PLATFORM=wayland; ";
+
+/// Prefixes the JSON-encoded clipboard text in the QML script's output.
+const MARKER: &str = "AURORA_MCP_CLIPBOARD:";
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ClipboardSetParams {
+    /// Device name from the config.
+    pub device: String,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ClipboardContent {
+    pub device: String,
+    pub text: String,
+    /// `wl-clipboard` or `silica`.
+    pub backend: String,
+}
+
+/// Runs `qml` with `qmlscene`, written to a temp file through a heredoc.
+/// `background` keeps it running after the SSH command returns.
+fn qmlscene(qml: &str, background: bool) -> String {
+    let run = if background {
+        "setsid qmlscene \"$f\" >/dev/null 2>&1 </dev/null & sleep 1"
+    } else {
+        "qmlscene \"$f\" 2>&1; rm -f \"$f\""
+    };
+    format!(
+        "f=$(mktemp /tmp/aurora-mcp-XXXXXX.qml); cat > \"$f\" <<'AURORA_MCP_QML'\n{qml}\nAURORA_MCP_QML\n{run}"
+    )
+}
+
+fn get_command() -> String {
+    let qml = format!(
+        "import QtQuick 2.0\nimport Sailfish.Silica 1.0\nQtObject {{\n    \
+         Component.onCompleted: {{ console.log(\"{MARKER}\" + JSON.stringify(Clipboard.text)); Qt.quit() }}\n}}"
+    );
+    format!(
+        "{GUI_ENV}if command -v wl-paste >/dev/null 2>&1; then echo wl-clipboard; wl-paste -n 2>/dev/null; \
+         else echo silica; {}; fi",
+        qmlscene(&qml, false)
+    )
+}
+
+fn set_command(text: &str) -> String {
+    let literal = serde_json::Value::String(text.to_string()).to_string();
+    // Wayland selections are served by the client that set them, so the
+    // script stays alive until something else takes the clipboard.
+    let qml = format!(
+        "import QtQuick 2.0\nimport Sailfish.Silica 1.0\nQtObject {{\n    \
+         property string value: {literal}\n    \
+         property var watcher: Connections {{ target: Clipboard; onTextChanged: if (Clipboard.text !== value) Qt.quit() }}\n    \
+         Component.onCompleted: Clipboard.text = value\n}}"
+    );
+    format!(
+        "{GUI_ENV}if command -v wl-copy >/dev/null 2>&1; then echo wl-clipboard; printf %s {} | wl-copy; \
+         else echo silica; {}; fi",
+        ssh::quote(text),
+        qmlscene(&qml, true)
+    )
+}
+
+fn parse_get(device: &str, stdout: &str) -> Result<(String, String)> {
+    let (backend, rest) = stdout.split_once('\n').unwrap_or((stdout, ""));
+    let text = match backend {
+        "silica" => {
+            let line = rest
+                .lines()
+                .rev()
+                .find_map(|line| Some(&line[line.find(MARKER)? + MARKER.len()..]))
+                .ok_or_else(|| Error::Parse {
+                    device: device.to_string(),
+                    message: format!("qmlscene did not report the clipboard: {}", rest.trim()),
+                })?;
+            serde_json::from_str(line).map_err(|err| Error::Parse {
+                device: device.to_string(),
+                message: format!("clipboard text: {err}"),
+            })?
+        }
+        _ => rest.to_string(),
+    };
+    Ok((backend.to_string(), text))
+}
+
+#[tool_router(router = clipboard_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Read the text currently on a device's clipboard.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn clipboard_get(
+        &self,
+        Parameters(params): Parameters<DeviceParams>,
+    ) -> Result<Json<ClipboardContent>> {
+        let device = self.devices().get(&params.device)?;
+        let stdout = device.exec_checked(&get_command()).await?;
+        let (backend, text) = parse_get(device.name(), &stdout)?;
+        Ok(Json(ClipboardContent {
+            device: params.device,
+            text,
+            backend,
+        }))
+    }
+
+    #[tool(
+        description = "Put text on a device's clipboard, e.g. to paste it into an app under test."
+    )]
+    pub async fn clipboard_set(
+        &self,
+        Parameters(params): Parameters<ClipboardSetParams>,
+    ) -> Result<Json<ClipboardContent>> {
+        let device = self.devices().get(&params.device)?;
+        let stdout = device.exec_checked(&set_command(&params.text)).await?;
+        Ok(Json(ClipboardContent {
+            device: params.device,
+            text: params.text,
+            backend: stdout.lines().next().unwrap_or("").to_string(),
+        }))
+    }
+}
@@ -0,0 +1,201 @@
+//! `device_clock`: device time and timezone compared against the host,
+//! with optional correction.
+
+use std::time::{Duration, Instant};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{Device, ssh};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+use crate::util::unix_now;
+
+/// Drift beyond this is reported as skew and corrected on request.
+const MAX_DRIFT_SECS: f64 = 2.0;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ClockParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Set the device clock to host time when it has drifted.
+    #[serde(default)]
+    pub correct: bool,
+    /// Also switch the device to this timezone, e.g. `Europe/Moscow`.
+    #[serde(default)]
+    pub set_timezone: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ClockReport {
+    pub device: String,
+    pub device_unix: f64,
+    pub host_unix: f64,
+    /// Device clock minus host clock, in seconds; positive when the device
+    /// is ahead.
+    pub drift_secs: f64,
+    /// SSH round trip of the measurement; drift is accurate to about half
+    /// of it.
+    pub round_trip_ms: u64,
+    pub skewed: bool,
+    pub device_timezone: Option<String>,
+    /// e.g. `+0300`.
+    pub device_utc_offset: String,
+    pub host_timezone: Option<String>,
+    pub host_utc_offset: Option<String>,
+    pub ntp_synchronized: Option<bool>,
+    /// The clock was set during this call; the other fields are measured
+    /// afterwards.
+    pub corrected: bool,
+    pub timezone_changed: bool,
+}
+
+struct Measurement {
+    device_unix: f64,
+    host_unix: f64,
+    round_trip: Duration,
+    timezone: Option<String>,
+    utc_offset: String,
+    ntp_synchronized: Option<bool>,
+}
+
+async fn measure(device: &Device) -> Result<Measurement> {
+    let command = ssh::sections(&[
+        "date +%s.%N",
+        "date +%z",
+        "timedatectl show -p Timezone -p NTPSynchronized 2>/dev/null || \
+         readlink /etc/localtime | sed 's|.*zoneinfo/|Timezone=|'",
+    ]);
+    let started = Instant::now();
+    let sent = unix_now().as_secs_f64();
+    let stdout = device.exec_checked(&command).await?;
+    let round_trip = started.elapsed();
+    let [now, offset, settings] = ssh::split_sections(&stdout)[..] else {
+        return Err(Error::Parse {
+            device: device.name().to_string(),
+            message: "missing output sections".to_string(),
+        });
+    };
+    let device_unix = now.trim().parse().map_err(|_| Error::Parse {
+        device: device.name().to_string(),
+        message: format!("unexpected date output '{}'", now.trim()),
+    })?;
+    let setting = |key: &str| {
+        settings
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    Ok(Measurement {
+        device_unix,
+        // The device read its clock somewhere in the round trip; assume
+        // the middle.
+        host_unix: sent + round_trip.as_secs_f64() / 2.0,
+        round_trip,
+        timezone: setting("Timezone"),
+        utc_offset: offset.trim().to_string(),
+        ntp_synchronized: setting("NTPSynchronized").map(|value| value == "yes"),
+    })
+}
+
+/// The host's zone name from `$TZ`, `/etc/localtime` or `/etc/timezone`.
+fn host_timezone() -> Option<String> {
+    if let Ok(tz) = std::env::var("TZ")
+        && !tz.is_empty()
+    {
+        return Some(tz.trim_start_matches(':').to_string());
+    }
+    if let Ok(target) = std::fs::read_link("/etc/localtime")
+        && let Some((_, zone)) = target.to_string_lossy().split_once("zoneinfo/")
+    {
+        return Some(zone.to_string());
+    }
+    std::fs::read_to_string("/etc/timezone")
+        .ok()
+        .map(|zone| zone.trim().to_string())
+        .filter(|zone| !zone.is_empty())
+}
+
+async fn host_utc_offset() -> Option<String> {
+    let output = tokio::process::Command::new("date")
+        .arg("+%z")
+        .output()
+        .await
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn valid_timezone(zone: &str) -> bool {
+    !zone.is_empty()
+        && !zone.starts_with('/')
+        && !zone.contains("..")
+        && zone
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/_+-".contains(c))
+}
+
+#[tool_router(router = clock_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Compare a device's clock and timezone with the host and report drift and NTP state. Set correct=true to set the device clock to host time when it is off by more than 2 seconds, and set_timezone to change the zone. Clock skew is a common cause of TLS and token errors in tests."
+    )]
+    pub async fn device_clock(
+        &self,
+        Parameters(params): Parameters<ClockParams>,
+    ) -> Result<Json<ClockReport>> {
+        let device = self.devices().get(&params.device)?;
+        let mut timezone_changed = false;
+        if let Some(zone) = &params.set_timezone {
+            if !valid_timezone(zone) {
+                return Err(Error::InvalidArgument(format!(
+                    "'{zone}' is not a timezone name"
+                )));
+            }
+            let zone = ssh::quote(zone);
+            let command = format!(
+                "timedatectl set-timezone {zone} 2>/dev/null || \
+                 ln -sf /usr/share/zoneinfo/{zone} /etc/localtime"
+            );
+            device
+                .exec_privileged(&command, ssh::DEFAULT_TIMEOUT)
+                .await?
+                .into_stdout(device.name())?;
+            timezone_changed = true;
+        }
+
+        let mut measured = measure(&device).await?;
+        let mut corrected = false;
+        if params.correct && (measured.device_unix - measured.host_unix).abs() > MAX_DRIFT_SECS {
+            let command = format!("date -u -s @{:.3} >/dev/null", unix_now().as_secs_f64());
+            device
+                .exec_privileged(&command, ssh::DEFAULT_TIMEOUT)
+                .await?
+                .into_stdout(device.name())?;
+            corrected = true;
+            measured = measure(&device).await?;
+        }
+
+        let drift_secs = measured.device_unix - measured.host_unix;
+        Ok(Json(ClockReport {
+            device: params.device,
+            device_unix: measured.device_unix,
+            host_unix: measured.host_unix,
+            drift_secs,
+            round_trip_ms: measured.round_trip.as_millis() as u64,
+            skewed: drift_secs.abs() > MAX_DRIFT_SECS,
+            device_timezone: measured.timezone,
+            device_utc_offset: measured.utc_offset,
+            host_timezone: host_timezone(),
+            host_utc_offset: host_utc_offset().await,
+            ntp_synchronized: measured.ntp_synchronized,
+            corrected,
+            timezone_changed,
+        }))
+    }
+}
@@ -0,0 +1,151 @@
+//! Per-device connection health and the `device_connections` tool.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::CommandOutput;
+use crate::error::Result;
+use crate::server::AuroraServer;
+use crate::util::unix_now;
+
+/// Counters updated by every SSH invocation against a device.
+#[derive(Debug, Default)]
+pub struct ConnectionStats {
+    commands: AtomicU64,
+    failures: AtomicU64,
+    consecutive_failures: AtomicU64,
+    /// Unix seconds of the last successful connection; 0 if never.
+    last_success: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+impl ConnectionStats {
+    /// Records the outcome of one invocation. Remote commands that ran and
+    /// failed still count as a working connection. Returns whether the
+    /// connection changed state: `Some(false)` when it failed after having
+    /// worked, `Some(true)` when it worked again after failing.
+    pub fn record(&self, result: &Result<CommandOutput>) -> Option<bool> {
+        self.commands.fetch_add(1, Ordering::Relaxed);
+        let error = match result {
+            Ok(output) if output.connection_failed() => Some(output.stderr.trim().to_string()),
+            Ok(_) => None,
+            Err(err) => Some(err.to_string()),
+        };
+        let mut last_error = self.last_error.lock().unwrap_or_else(|e| e.into_inner());
+        match error {
+            Some(error) => {
+                self.failures.fetch_add(1, Ordering::Relaxed);
+                let before = self.consecutive_failures.fetch_add(1, Ordering::Relaxed);
+                *last_error = Some(error);
+                (before == 0 && self.last_success.load(Ordering::Relaxed) != 0).then_some(false)
+            }
+            None => {
+                let before = self.consecutive_failures.swap(0, Ordering::Relaxed);
+                self.last_success
+                    .store(unix_now().as_secs(), Ordering::Relaxed);
+                (before > 0).then_some(true)
+            }
+        }
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let last_success = self.last_success.load(Ordering::Relaxed);
+        StatsSnapshot {
+            commands: self.commands.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            consecutive_failures: self.consecutive_failures.load(Ordering::Relaxed),
+            last_success_unix: (last_success != 0).then_some(last_success),
+            last_error: self.last_error(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StatsSnapshot {
+    pub commands: u64,
+    pub failures: u64,
+    pub consecutive_failures: u64,
+    pub last_success_unix: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ConnectionsParams {
+    /// Only report this device.
+    #[serde(default)]
+    pub device: Option<String>,
+    /// Close the shared connections first, forcing a fresh connect on the
+    /// next command.
+    #[serde(default)]
+    pub reset: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ConnectionsReport {
+    pub devices: Vec<ConnectionStatus>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ConnectionStatus {
+    pub device: String,
+    pub host: String,
+    /// `connected` (shared connection open), `idle` (no open connection,
+    /// last attempt fine), `failing` or `unknown` (never used).
+    pub state: String,
+    pub shared_connection_open: bool,
+    #[serde(flatten)]
+    pub stats: StatsSnapshot,
+}
+
+#[tool_router(router = connections_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Report SSH connection state for devices: whether the shared keep-alive connection is open, command and failure counts, and the last error. Set reset=true to drop shared connections."
+    )]
+    pub async fn device_connections(
+        &self,
+        Parameters(params): Parameters<ConnectionsParams>,
+    ) -> Result<Json<ConnectionsReport>> {
+        let devices = match &params.device {
+            Some(name) => vec![self.devices().get(name)?],
+            None => self.devices().list(),
+        };
+        let mut report = Vec::with_capacity(devices.len());
+        for device in devices {
+            if params.reset {
+                device.control("exit").await;
+            }
+            let open = device.control("check").await;
+            let stats = device.stats().snapshot();
+            let state = if open {
+                "connected"
+            } else if stats.consecutive_failures > 0 {
+                "failing"
+            } else if stats.commands > 0 {
+                "idle"
+            } else {
+                "unknown"
+            };
+            report.push(ConnectionStatus {
+                device: device.name().to_string(),
+                host: device.config().host.clone(),
+                state: state.to_string(),
+                shared_connection_open: open,
+                stats,
+            });
+        }
+        Ok(Json(ConnectionsReport { devices: report }))
+    }
+}
@@ -0,0 +1,205 @@
+//! `collect_crash_report`: pulls core dumps and a journal excerpt for an app
+//! into a local bundle directory.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rmcp::handler::server::tool::cached_schema_for_type;
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::CallToolResult;
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{Device, ssh, tail_lines};
+use crate::error::{Error, Result, tool_result};
+use crate::resources::file_link;
+use crate::server::AuroraServer;
+
+/// Where crash-reporter and systemd-coredump leave their files.
+const CORE_DIRS: &[&str] = &["/var/cache/core-dumps", "/var/lib/systemd/coredump"];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CrashReportParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Binary name of the crashed app, e.g. `ru.example.myapp`.
+    pub app: String,
+    /// How many of the newest core dumps to fetch (default 1).
+    #[serde(default)]
+    pub max_cores: Option<usize>,
+    /// Skip core dumps larger than this many MiB (default 64).
+    #[serde(default)]
+    pub max_core_size_mb: Option<u64>,
+    /// Journal lines mentioning the app to keep (default 200).
+    #[serde(default)]
+    pub journal_lines: Option<usize>,
+    /// Local directory for the bundle. Defaults to a new directory under
+    /// `<data_dir>/crashes`.
+    #[serde(default)]
+    pub output_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CrashBundle {
+    pub device: String,
+    pub app: String,
+    /// Local directory holding the collected files.
+    pub directory: PathBuf,
+    pub core_dumps: Vec<CoreDump>,
+    /// Core dumps found on the device but not fetched, with the reason.
+    pub skipped: Vec<String>,
+    /// Path of the saved journal excerpt.
+    pub journal_file: PathBuf,
+    /// The last lines of the excerpt, for a quick look.
+    pub journal_tail: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CoreDump {
+    pub remote_path: String,
+    pub local_path: PathBuf,
+    pub size_bytes: u64,
+    /// Modification time on the device, seconds since the epoch.
+    pub modified: u64,
+}
+
+struct RemoteFile {
+    path: String,
+    size: u64,
+    modified: u64,
+}
+
+pub async fn collect(server: &AuroraServer, params: CrashReportParams) -> Result<CrashBundle> {
+    let device = server.devices().get(&params.device)?;
+    if params.app.is_empty() || params.app.contains('/') {
+        return Err(Error::InvalidArgument(format!(
+            "'{}' is not a binary name",
+            params.app
+        )));
+    }
+
+    let directory = match params.output_dir {
+        Some(dir) => dir,
+        None => {
+            let stamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            server
+                .state()
+                .config
+                .data_dir()
+                .join("crashes")
+                .join(format!("{}-{}-{stamp}", params.device, params.app))
+        }
+    };
+    std::fs::create_dir_all(&directory)?;
+
+    let mut cores = list_cores(&device, &params.app).await?;
+    cores.sort_by_key(|core| std::cmp::Reverse(core.modified));
+    let max_cores = params.max_cores.unwrap_or(1);
+    let max_size = params.max_core_size_mb.unwrap_or(64) * 1024 * 1024;
+
+    let mut core_dumps = Vec::new();
+    let mut skipped = Vec::new();
+    for core in cores {
+        if core_dumps.len() >= max_cores {
+            skipped.push(format!("{}: over max_cores", core.path));
+        } else if core.size > max_size {
+            skipped.push(format!(
+                "{}: {} bytes exceeds size limit",
+                core.path, core.size
+            ));
+        } else {
+            let local_path = directory.join(file_name(&core.path));
+            match device.download(&core.path, &local_path).await {
+                Ok(size_bytes) => core_dumps.push(CoreDump {
+                    remote_path: core.path,
+                    local_path,
+                    size_bytes,
+                    modified: core.modified,
+                }),
+                Err(err) => skipped.push(format!("{}: {err}", core.path)),
+            }
+        }
+    }
+
+    let lines = params.journal_lines.unwrap_or(200);
+    let journal = device
+        .exec(&format!(
+            "journalctl --no-pager -o short-iso -n 5000 | grep -F -- {} | tail -n {lines}",
+            ssh::quote(&params.app)
+        ))
+        .await?
+        .stdout;
+    let journal_file = directory.join("journal.log");
+    std::fs::write(&journal_file, &journal)?;
+    let journal_tail = tail_lines(&journal, 20);
+
+    Ok(CrashBundle {
+        device: params.device,
+        app: params.app,
+        directory,
+        core_dumps,
+        skipped,
+        journal_file,
+        journal_tail,
+    })
+}
+
+async fn list_cores(device: &Device, app: &str) -> Result<Vec<RemoteFile>> {
+    let globs = CORE_DIRS
+        .iter()
+        .map(|dir| format!("{dir}/*{}*", ssh::quote(app)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let command =
+        format!("for f in {globs}; do [ -f \"$f\" ] && stat -c '%s %Y %n' \"$f\"; done; true");
+    let stdout = device.exec_checked(&command).await?;
+    Ok(stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, ' ');
+            Some(RemoteFile {
+                size: fields.next()?.parse().ok()?,
+                modified: fields.next()?.parse().ok()?,
+                path: fields.next()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+fn file_name(remote: &str) -> &str {
+    Path::new(remote)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("core")
+}
+
+#[tool_router(router = crash_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Collect crash data for an app from a device: the newest core dumps plus a journal excerpt, saved into a local bundle directory. Returns a summary and links to the collected files.",
+        output_schema = cached_schema_for_type::<CrashBundle>()
+    )]
+    pub async fn collect_crash_report(
+        &self,
+        Parameters(params): Parameters<CrashReportParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        tool_result(collect(self, params).await.and_then(|bundle| {
+            let mut links = vec![file_link(&bundle.journal_file, Some("text/plain"))];
+            links.extend(
+                bundle
+                    .core_dumps
+                    .iter()
+                    .map(|core| file_link(&core.local_path, Some("application/octet-stream"))),
+            );
+            let mut result = CallToolResult::structured(
+                serde_json::to_value(&bundle).map_err(std::io::Error::other)?,
+            );
+            result.content.extend(links);
+            Ok(result)
+        }))
+    }
+}
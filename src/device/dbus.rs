@@ -0,0 +1,515 @@
+//! D-Bus exploration on devices: listing services, introspecting objects
+//! and calling allowlisted methods through `dbus-send`.
+
+use std::collections::BTreeMap;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{Device, ssh};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Points `dbus-send` at the login user's session bus, which a
+/// non-interactive SSH session does not set up.
+pub const SESSION_ENV: &str = "export DBUS_SESSION_BUS_ADDRESS=\"${DBUS_SESSION_BUS_ADDRESS:-unix:path=/run/user/$(id -u)/dbus/user_bus_socket}\"; ";
+
+/// Argument types understood by `dbus-send`.
+const ARG_TYPES: &[&str] = &[
+    "string", "int16", "uint16", "int32", "uint32", "int64", "uint64", "double", "byte", "boolean",
+    "objpath", "variant", "array", "dict",
+];
+
+/// D-Bus settings from the `[dbus]` config table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DbusOptions {
+    /// Methods `dbus_call` may invoke, as `interface.Method` or
+    /// `interface.*`. Replaces the default read-only list when set.
+    pub allowed_methods: Vec<String>,
+}
+
+impl Default for DbusOptions {
+    fn default() -> Self {
+        let methods = [
+            "org.freedesktop.DBus.Introspectable.Introspect",
+            "org.freedesktop.DBus.Peer.Ping",
+            "org.freedesktop.DBus.Properties.Get",
+            "org.freedesktop.DBus.Properties.GetAll",
+            "net.connman.Manager.GetProperties",
+            "net.connman.Manager.GetServices",
+            "net.connman.Manager.GetTechnologies",
+            "net.connman.Service.GetProperties",
+            "net.connman.Technology.GetProperties",
+            "org.ofono.Manager.GetModems",
+            "org.ofono.Modem.GetProperties",
+            "org.ofono.NetworkRegistration.GetProperties",
+            "org.ofono.SimManager.GetProperties",
+        ];
+        Self {
+            allowed_methods: methods.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+}
+
+impl DbusOptions {
+    pub fn allows(&self, method: &str) -> bool {
+        self.allowed_methods
+            .iter()
+            .any(|pattern| match pattern.strip_suffix(".*") {
+                Some(interface) => method
+                    .rsplit_once('.')
+                    .is_some_and(|(iface, _)| iface == interface),
+                None => pattern == method,
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Bus {
+    /// The login user's session bus (lipstick, apps, notifications).
+    #[default]
+    Session,
+    /// The system bus (connman, ofono, systemd).
+    System,
+}
+
+/// Calls `method` (`interface.Member`) on `service` at `path` and returns
+/// the reply with the `method return` header line stripped. `args` use
+/// `dbus-send` syntax, e.g. `string:foo` or `array:string:a,b`.
+pub async fn call(
+    device: &Device,
+    bus: Bus,
+    service: &str,
+    path: &str,
+    method: &str,
+    args: &[String],
+) -> Result<String> {
+    let command = send_command(bus, service, path, method, args)?;
+    let reply = device.exec_checked(&command).await?;
+    Ok(reply
+        .lines()
+        .skip_while(|line| line.starts_with("method return"))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// The `dbus-send --print-reply` command line behind [`call`], for tools
+/// that batch several calls into one script.
+pub fn send_command(
+    bus: Bus,
+    service: &str,
+    path: &str,
+    method: &str,
+    args: &[String],
+) -> Result<String> {
+    for arg in args {
+        validate_arg(arg)?;
+    }
+    let (env, flag) = match bus {
+        Bus::Session => (SESSION_ENV, "--session"),
+        Bus::System => ("", "--system"),
+    };
+    let mut command = format!(
+        "{env}dbus-send {flag} --print-reply --dest={} {} {}",
+        ssh::quote(service),
+        ssh::quote(path),
+        ssh::quote(method)
+    );
+    for arg in args {
+        command.push(' ');
+        command.push_str(&ssh::quote(arg));
+    }
+    Ok(command)
+}
+
+fn validate_arg(arg: &str) -> Result<()> {
+    match arg.split_once(':') {
+        Some((kind, _)) if ARG_TYPES.contains(&kind) => Ok(()),
+        _ => Err(Error::InvalidArgument(format!(
+            "argument '{arg}' must be '<type>:<value>' with type one of {}",
+            ARG_TYPES.join(", ")
+        ))),
+    }
+}
+
+/// Values of `string "..."` lines in a `--print-reply` dump.
+pub fn reply_strings(reply: &str) -> Vec<String> {
+    reply
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("string \""))
+        .map(|value| value.strip_suffix('"').unwrap_or(value).to_string())
+        .collect()
+}
+
+/// Flattens the `a{sv}` dictionaries in a `--print-reply` dump into
+/// key/value pairs. Scalars keep their printed value (strings unquoted);
+/// arrays of scalars are joined with `,`. Keys of nested dictionaries are
+/// included too, the first occurrence winning.
+pub fn reply_properties(reply: &str) -> BTreeMap<String, String> {
+    let mut properties = BTreeMap::new();
+    let mut lines = reply.lines().map(str::trim).peekable();
+    while let Some(line) = lines.next() {
+        if line != "dict entry(" {
+            continue;
+        }
+        let Some(key) = lines.next().and_then(scalar) else {
+            continue;
+        };
+        let Some(value) = lines.next().and_then(|line| line.strip_prefix("variant")) else {
+            continue;
+        };
+        let value = value.trim();
+        let value = if value == "array [" {
+            let mut items = Vec::new();
+            while let Some(item) = lines.next_if(|line| *line != "]" && *line != "dict entry(") {
+                items.extend(scalar(item));
+            }
+            items.join(",")
+        } else {
+            scalar(value).unwrap_or_default()
+        };
+        properties.entry(key).or_insert(value);
+    }
+    properties
+}
+
+/// The value of a printed scalar such as `string "x"` or `byte 67`.
+fn scalar(line: &str) -> Option<String> {
+    let (kind, value) = line.split_once(char::is_whitespace)?;
+    let value = value.trim();
+    match kind {
+        "string" | "object" => Some(
+            value
+                .trim_start_matches("path ")
+                .trim_matches('"')
+                .to_string(),
+        ),
+        "array" | "dict" | "struct" | "variant" => None,
+        _ => Some(value.to_string()),
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListServicesParams {
+    /// Device name from the config.
+    pub device: String,
+    #[serde(default)]
+    pub bus: Bus,
+    /// Only services whose name contains this substring.
+    #[serde(default)]
+    pub filter: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ServiceList {
+    pub device: String,
+    pub bus: Bus,
+    pub services: Vec<DbusService>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DbusService {
+    pub name: String,
+    /// Currently owned by a running process.
+    pub active: bool,
+    /// Can be started on demand by the bus.
+    pub activatable: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct IntrospectParams {
+    /// Device name from the config.
+    pub device: String,
+    #[serde(default)]
+    pub bus: Bus,
+    /// Well-known bus name, e.g. `net.connman`.
+    pub service: String,
+    /// Object path (default `/`).
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Introspection {
+    pub service: String,
+    pub path: String,
+    pub interfaces: Vec<DbusInterface>,
+    /// Full paths of child objects, for further introspection.
+    pub children: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DbusInterface {
+    pub name: String,
+    pub methods: Vec<DbusMember>,
+    pub signals: Vec<DbusMember>,
+    pub properties: Vec<DbusProperty>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DbusMember {
+    pub name: String,
+    pub args: Vec<DbusArg>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DbusArg {
+    pub name: Option<String>,
+    /// D-Bus type signature, e.g. `a{sv}`.
+    #[serde(rename = "type")]
+    pub signature: String,
+    /// `in` or `out`; signals only have outputs.
+    pub direction: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DbusProperty {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub signature: String,
+    /// `read`, `write` or `readwrite`.
+    pub access: String,
+}
+
+impl DbusInterface {
+    /// The interface as a Markdown reference section: method and signal
+    /// signatures, then a table of properties.
+    pub fn markdown(&self) -> String {
+        let mut text = format!("## `{}`\n", self.name);
+        for (heading, members) in [("Methods", &self.methods), ("Signals", &self.signals)] {
+            if members.is_empty() {
+                continue;
+            }
+            text.push_str(&format!("\n### {heading}\n\n"));
+            for member in members {
+                let args: Vec<String> = member
+                    .args
+                    .iter()
+                    .map(|arg| {
+                        let direction = match arg.direction.as_deref() {
+                            Some("out") if heading == "Methods" => "out ",
+                            _ => "",
+                        };
+                        match &arg.name {
+                            Some(name) => format!("{direction}{} {name}", arg.signature),
+                            None => format!("{direction}{}", arg.signature),
+                        }
+                    })
+                    .collect();
+                text.push_str(&format!("- `{}({})`\n", member.name, args.join(", ")));
+            }
+        }
+        if !self.properties.is_empty() {
+            text.push_str("\n### Properties\n\n| Name | Type | Access |\n|---|---|---|\n");
+            for property in &self.properties {
+                text.push_str(&format!(
+                    "| `{}` | `{}` | {} |\n",
+                    property.name, property.signature, property.access
+                ));
+            }
+        }
+        text
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CallParams {
+    /// Device name from the config.
+    pub device: String,
+    #[serde(default)]
+    pub bus: Bus,
+    /// Well-known bus name, e.g. `org.ofono`.
+    pub service: String,
+    /// Object path, e.g. `/ril_0`.
+    pub path: String,
+    /// Fully qualified method, e.g. `org.ofono.Modem.GetProperties`.
+    pub method: String,
+    /// Arguments in `dbus-send` syntax: `string:foo`, `uint32:5`,
+    /// `array:string:a,b`, `variant:boolean:true`.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CallReply {
+    pub method: String,
+    /// Reply as printed by `dbus-send --print-reply`.
+    pub reply: String,
+}
+
+/// The `<interface>` elements of an introspection `<node>`.
+pub(crate) fn parse_interfaces(node: roxmltree::Node) -> Vec<DbusInterface> {
+    let attr = |node: roxmltree::Node, name| node.attribute(name).unwrap_or("").to_string();
+    let members = |iface: roxmltree::Node, tag: &str| {
+        iface
+            .children()
+            .filter(|node| node.has_tag_name(tag))
+            .map(|member| DbusMember {
+                name: attr(member, "name"),
+                args: member
+                    .children()
+                    .filter(|node| node.has_tag_name("arg"))
+                    .map(|arg| DbusArg {
+                        name: arg.attribute("name").map(str::to_string),
+                        signature: attr(arg, "type"),
+                        direction: arg.attribute("direction").map(str::to_string),
+                    })
+                    .collect(),
+            })
+            .collect()
+    };
+    node.children()
+        .filter(|node| node.has_tag_name("interface"))
+        .map(|iface| DbusInterface {
+            name: attr(iface, "name"),
+            methods: members(iface, "method"),
+            signals: members(iface, "signal"),
+            properties: iface
+                .children()
+                .filter(|node| node.has_tag_name("property"))
+                .map(|prop| DbusProperty {
+                    name: attr(prop, "name"),
+                    signature: attr(prop, "type"),
+                    access: attr(prop, "access"),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+pub(crate) fn parse_introspection(
+    device: &str,
+    service: String,
+    path: String,
+    xml: &str,
+) -> Result<Introspection> {
+    let parse_error = |message: String| Error::Parse {
+        device: device.to_string(),
+        message,
+    };
+    let start = xml
+        .find("<!DOCTYPE")
+        .or_else(|| xml.find("<node"))
+        .ok_or_else(|| parse_error("reply contains no introspection XML".to_string()))?;
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    // `--print-reply` wraps the document in `string "..."`.
+    let xml = xml[start..].trim_end().trim_end_matches('"');
+    let doc = roxmltree::Document::parse_with_options(xml, options)
+        .map_err(|err| parse_error(format!("introspection XML: {err}")))?;
+    let root = doc.root_element();
+    let interfaces = parse_interfaces(root);
+    let children = root
+        .children()
+        .filter(|node| node.has_tag_name("node"))
+        .filter_map(|node| node.attribute("name"))
+        .map(|name| format!("{}/{name}", path.trim_end_matches('/')))
+        .collect();
+    Ok(Introspection {
+        service,
+        path,
+        interfaces,
+        children,
+    })
+}
+
+#[tool_router(router = dbus_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "List well-known D-Bus services on a device's session or system bus, including ones that are activatable but not running.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn dbus_list_services(
+        &self,
+        Parameters(params): Parameters<ListServicesParams>,
+    ) -> Result<Json<ServiceList>> {
+        let device = self.devices().get(&params.device)?;
+        let list = |method: &'static str| {
+            call(
+                &device,
+                params.bus,
+                "org.freedesktop.DBus",
+                "/org/freedesktop/DBus",
+                method,
+                &[],
+            )
+        };
+        let active = reply_strings(&list("org.freedesktop.DBus.ListNames").await?);
+        let activatable = reply_strings(&list("org.freedesktop.DBus.ListActivatableNames").await?);
+        let mut names: Vec<&String> = active.iter().chain(&activatable).collect();
+        names.sort();
+        names.dedup();
+        let services = names
+            .into_iter()
+            .filter(|name| !name.starts_with(':'))
+            .filter(|name| params.filter.as_ref().is_none_or(|f| name.contains(f)))
+            .map(|name| DbusService {
+                name: name.clone(),
+                active: active.contains(name),
+                activatable: activatable.contains(name),
+            })
+            .collect();
+        Ok(Json(ServiceList {
+            device: params.device,
+            bus: params.bus,
+            services,
+        }))
+    }
+
+    #[tool(
+        description = "Introspect a D-Bus object on a device: interfaces with their methods, signals and properties, plus child object paths. Start at path `/` and walk down.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn dbus_introspect(
+        &self,
+        Parameters(params): Parameters<IntrospectParams>,
+    ) -> Result<Json<Introspection>> {
+        let device = self.devices().get(&params.device)?;
+        let path = params.path.unwrap_or_else(|| "/".to_string());
+        let reply = call(
+            &device,
+            params.bus,
+            &params.service,
+            &path,
+            "org.freedesktop.DBus.Introspectable.Introspect",
+            &[],
+        )
+        .await?;
+        parse_introspection(device.name(), params.service, path, &reply).map(Json)
+    }
+
+    #[tool(
+        description = "Call a D-Bus method on a device and return the reply. Only methods in the server's `[dbus] allowed_methods` list may be called; by default that is read-only getters of connman, ofono and the standard Properties interface."
+    )]
+    pub async fn dbus_call(
+        &self,
+        Parameters(params): Parameters<CallParams>,
+    ) -> Result<Json<CallReply>> {
+        if !self.state().config.dbus.allows(&params.method) {
+            return Err(Error::InvalidArgument(format!(
+                "method '{}' is not in the [dbus] allowed_methods list",
+                params.method
+            )));
+        }
+        let device = self.devices().get(&params.device)?;
+        let reply = call(
+            &device,
+            params.bus,
+            &params.service,
+            &params.path,
+            &params.method,
+            &params.args,
+        )
+        .await?;
+        Ok(Json(CallReply {
+            method: params.method,
+            reply,
+        }))
+    }
+}
@@ -0,0 +1,104 @@
+//! `deploy_rpm`: upload a locally built RPM and install it on devices.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::multi::{MultiDeviceReport, fan_out};
+use super::{Device, ssh, tail_lines};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+const INSTALL_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DeployParams {
+    /// Device names from the config; the package is installed on all of
+    /// them concurrently.
+    pub devices: Vec<String>,
+    /// Path of the RPM on this machine.
+    pub rpm: PathBuf,
+    /// Installer to use: `pkcon` (default, resolves dependencies) or `rpm`
+    /// (plain `rpm -U --force`).
+    #[serde(default)]
+    pub method: InstallMethod,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallMethod {
+    #[default]
+    Pkcon,
+    Rpm,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DeployResult {
+    pub bytes_uploaded: u64,
+    pub output_tail: Vec<String>,
+}
+
+/// Uploads `rpm` to `device` and installs it with `method`.
+pub(crate) async fn deploy(
+    device: Device,
+    rpm: &Path,
+    method: InstallMethod,
+) -> Result<DeployResult> {
+    let file_name = rpm
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| Error::InvalidArgument(format!("{} is not a file", rpm.display())))?;
+    let remote = format!("/tmp/aurora-mcp-{file_name}");
+    let bytes_uploaded = device.upload(rpm, &remote).await?;
+    let quoted = ssh::quote(&remote);
+    let install = match method {
+        InstallMethod::Pkcon => format!("pkcon -y install-local {quoted}"),
+        InstallMethod::Rpm => format!("rpm -U --force {quoted}"),
+    };
+    let output = device
+        .exec_privileged(
+            &format!("{install} 2>&1; status=$?; rm -f {quoted}; exit $status"),
+            INSTALL_TIMEOUT,
+        )
+        .await?;
+    let output_tail = tail_lines(&output.stdout, 30);
+    if !output.success() {
+        return Err(Error::RemoteCommand {
+            device: device.name().to_string(),
+            status: output.status,
+            stderr: output_tail.join("\n"),
+        });
+    }
+    Ok(DeployResult {
+        bytes_uploaded,
+        output_tail,
+    })
+}
+
+#[tool_router(router = deploy_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Upload a local RPM and install it on one or more devices concurrently, returning per-device install results."
+    )]
+    pub async fn deploy_rpm(
+        &self,
+        Parameters(params): Parameters<DeployParams>,
+    ) -> Result<Json<MultiDeviceReport<DeployResult>>> {
+        if !params.rpm.is_file() {
+            return Err(Error::InvalidArgument(format!(
+                "{} does not exist",
+                params.rpm.display()
+            )));
+        }
+        let rpm = &params.rpm;
+        fan_out(self, &params.devices, |device| {
+            deploy(device, rpm, params.method)
+        })
+        .await
+        .map(Json)
+    }
+}
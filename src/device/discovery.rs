@@ -0,0 +1,260 @@
+//! `discover_devices` and `register_device`: finding Aurora devices on USB
+//! networking, the emulator port and mDNS, and adding them to the registry.
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tokio::task::JoinSet;
+
+use super::DeviceConfig;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Address a device in developer mode takes on its USB network.
+const USB_DEVICE_ADDR: Ipv4Addr = Ipv4Addr::new(192, 168, 2, 15);
+/// Host port the Aurora emulator forwards to its SSH server.
+const EMULATOR_SSH_PORT: u16 = 2223;
+/// Upper bound on hosts probed per subnet, to keep scans short.
+const MAX_SUBNET_HOSTS: u32 = 1024;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DiscoverParams {
+    /// Extra IPv4 subnets to scan for SSH, in CIDR form (e.g.
+    /// `192.168.1.0/24`). At most 1024 hosts each.
+    #[serde(default)]
+    pub subnets: Vec<String>,
+    /// Also browse mDNS for `_ssh._tcp` services via avahi-browse
+    /// (default true).
+    #[serde(default)]
+    pub mdns: Option<bool>,
+    /// Per-host connect timeout in milliseconds (default 400).
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Discovery {
+    pub candidates: Vec<Candidate>,
+    /// Discovery methods that could not run, with the reason.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Candidate {
+    pub host: String,
+    pub port: u16,
+    /// `usb`, `emulator`, `subnet` or `mdns`.
+    pub source: String,
+    /// Host name announced over mDNS.
+    pub hostname: Option<String>,
+    /// SSH identification string, e.g. `SSH-2.0-OpenSSH_8.9`.
+    pub ssh_banner: Option<String>,
+    /// Name of the registered device already pointing at this address.
+    pub registered_as: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RegisterParams {
+    /// Name the device will be addressed by in other tools.
+    pub name: String,
+    pub host: String,
+    /// SSH port (default 22).
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Login user (default `defaultuser`).
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Private key for the login, e.g. the SDK key.
+    #[serde(default)]
+    pub identity_file: Option<PathBuf>,
+    /// Also append the device to the config file so it survives restarts.
+    #[serde(default)]
+    pub persist: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Registered {
+    pub name: String,
+    /// Config file the device was written to, when `persist` was set.
+    pub config_file: Option<PathBuf>,
+}
+
+pub async fn discover(server: &AuroraServer, params: DiscoverParams) -> Result<Discovery> {
+    let timeout = Duration::from_millis(params.timeout_ms.unwrap_or(400));
+    let mut warnings = Vec::new();
+
+    let mut targets: Vec<(SocketAddr, &'static str)> = vec![
+        ((USB_DEVICE_ADDR, 22).into(), "usb"),
+        ((Ipv4Addr::LOCALHOST, EMULATOR_SSH_PORT).into(), "emulator"),
+    ];
+    for subnet in &params.subnets {
+        match subnet_hosts(subnet) {
+            Ok(hosts) => targets.extend(hosts.map(|ip| ((ip, 22).into(), "subnet"))),
+            Err(err) => warnings.push(err.to_string()),
+        }
+    }
+
+    let mut probes = JoinSet::new();
+    for (addr, source) in targets {
+        probes.spawn(async move { (addr, source, probe(addr, timeout).await) });
+    }
+    let mut candidates = Vec::new();
+    while let Some(joined) = probes.join_next().await {
+        let Ok((addr, source, Some(banner))) = joined else {
+            continue;
+        };
+        candidates.push(Candidate {
+            host: addr.ip().to_string(),
+            port: addr.port(),
+            source: source.to_string(),
+            hostname: None,
+            ssh_banner: Some(banner),
+            registered_as: None,
+        });
+    }
+
+    if params.mdns.unwrap_or(true) {
+        match browse_mdns().await {
+            Ok(found) => {
+                for candidate in found {
+                    if !candidates
+                        .iter()
+                        .any(|c| c.host == candidate.host && c.port == candidate.port)
+                    {
+                        candidates.push(candidate);
+                    }
+                }
+            }
+            Err(err) => warnings.push(format!("mDNS: {err}")),
+        }
+    }
+
+    let devices = server.devices().list();
+    for candidate in &mut candidates {
+        candidate.registered_as = devices
+            .iter()
+            .find(|d| d.config().host == candidate.host && d.config().port == candidate.port)
+            .map(|d| d.name().to_string());
+    }
+    candidates.sort_by(|a, b| (&a.source, &a.host).cmp(&(&b.source, &b.host)));
+    Ok(Discovery {
+        candidates,
+        warnings,
+    })
+}
+
+/// Connects to `addr` and reads the SSH identification line.
+async fn probe(addr: SocketAddr, timeout: Duration) -> Option<String> {
+    let mut stream = tokio::time::timeout(timeout, TcpStream::connect(addr))
+        .await
+        .ok()?
+        .ok()?;
+    let mut buf = [0u8; 256];
+    let read = tokio::time::timeout(Duration::from_secs(2), stream.read(&mut buf))
+        .await
+        .ok()?
+        .ok()?;
+    let banner = String::from_utf8_lossy(&buf[..read]);
+    let line = banner.lines().next()?.trim();
+    line.starts_with("SSH-").then(|| line.to_string())
+}
+
+fn subnet_hosts(cidr: &str) -> Result<impl Iterator<Item = Ipv4Addr>> {
+    let invalid = || Error::InvalidArgument(format!("'{cidr}' is not an IPv4 CIDR subnet"));
+    let (addr, prefix) = cidr.split_once('/').ok_or_else(invalid)?;
+    let addr: Ipv4Addr = addr.parse().map_err(|_| invalid())?;
+    let prefix: u32 = prefix.parse().map_err(|_| invalid())?;
+    if !(1..=32).contains(&prefix) {
+        return Err(invalid());
+    }
+    let size = 1u32 << (32 - prefix);
+    if size > MAX_SUBNET_HOSTS {
+        return Err(Error::InvalidArgument(format!(
+            "subnet {cidr} has more than {MAX_SUBNET_HOSTS} hosts"
+        )));
+    }
+    let network = u32::from(addr) & !(size - 1);
+    // Skip the network and broadcast addresses unless the "subnet" is a host.
+    let hosts = if size > 2 { 1..size - 1 } else { 0..size };
+    Ok(hosts.map(move |offset| Ipv4Addr::from(network + offset)))
+}
+
+/// Resolves `_ssh._tcp` services with `avahi-browse` in parsable mode.
+async fn browse_mdns() -> std::result::Result<Vec<Candidate>, String> {
+    let output = tokio::time::timeout(
+        Duration::from_secs(5),
+        tokio::process::Command::new("avahi-browse")
+            .args(["--resolve", "--terminate", "--parsable", "_ssh._tcp"])
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| "avahi-browse timed out".to_string())?
+    .map_err(|err| format!("cannot run avahi-browse: {err}"))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .filter_map(|line| {
+            // =;iface;IPv4;name;_ssh._tcp;local;host.local;addr;port;txt
+            let fields: Vec<&str> = line.split(';').collect();
+            if fields.first() != Some(&"=") || fields.get(2) != Some(&"IPv4") {
+                return None;
+            }
+            Some(Candidate {
+                host: fields.get(7)?.to_string(),
+                port: fields.get(8)?.parse().ok()?,
+                source: "mdns".to_string(),
+                hostname: fields.get(6).map(|h| h.to_string()),
+                ssh_banner: None,
+                registered_as: None,
+            })
+        })
+        .collect())
+}
+
+#[tool_router(router = discovery_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Find Aurora devices reachable over SSH: the USB developer-mode address, the emulator port, optional subnets and mDNS announcements. Candidates can be added with register_device.",
+        annotations(read_only_hint = true, open_world_hint = true)
+    )]
+    pub async fn discover_devices(
+        &self,
+        Parameters(params): Parameters<DiscoverParams>,
+    ) -> Result<Json<Discovery>> {
+        discover(self, params).await.map(Json)
+    }
+
+    #[tool(
+        description = "Add a device to the registry at runtime, optionally persisting it to the config file."
+    )]
+    pub async fn register_device(
+        &self,
+        Parameters(params): Parameters<RegisterParams>,
+    ) -> Result<Json<Registered>> {
+        let device = self.devices().insert(DeviceConfig {
+            name: params.name,
+            host: params.host,
+            port: params.port.unwrap_or(22),
+            user: params.user.unwrap_or_else(|| "defaultuser".to_string()),
+            identity_file: params.identity_file,
+            root_user: None,
+        })?;
+        let config_file = if params.persist {
+            Some(self.state().config.append_device(device.config())?)
+        } else {
+            None
+        };
+        Ok(Json(Registered {
+            name: device.name().to_string(),
+            config_file,
+        }))
+    }
+}
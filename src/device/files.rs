@@ -0,0 +1,356 @@
+//! Read-only access to parts of a device's filesystem, served as
+//! `device://{device}/{path}` resources. Large files are read in chunks
+//! with `?offset=<byte>&length=<bytes>`.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+
+use super::{Device, ssh};
+use crate::error::{Error, Result};
+
+pub const URI_SCHEME: &str = "device://";
+
+/// Which parts of device filesystems are exposed, from the `[files]`
+/// config table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilesOptions {
+    /// Directories that may be browsed, including everything below them.
+    /// `~` is the device user's home, which holds app data under
+    /// `~/.local/share` and `~/.config`.
+    pub roots: Vec<String>,
+    /// Files larger than this are cut off when read.
+    pub max_read_bytes: u64,
+}
+
+impl Default for FilesOptions {
+    fn default() -> Self {
+        Self {
+            roots: vec![
+                "~".to_string(),
+                "/usr/share".to_string(),
+                "/var/log".to_string(),
+            ],
+            max_read_bytes: 512 * 1024,
+        }
+    }
+}
+
+impl FilesOptions {
+    /// The roots for `device`, with `~` expanded.
+    pub fn roots(&self, device: &Device) -> Vec<String> {
+        let home = home_dir(device);
+        self.roots
+            .iter()
+            .map(|root| match root.strip_prefix('~') {
+                Some(rest) => format!("{home}{rest}"),
+                None => root.clone(),
+            })
+            .map(|root| match root.trim_end_matches('/') {
+                "" => "/".to_string(),
+                root => root.to_string(),
+            })
+            .collect()
+    }
+
+    fn allows(&self, device: &Device, path: &str) -> bool {
+        self.roots(device).iter().any(|root| {
+            root == "/"
+                || path == root
+                || path
+                    .strip_prefix(root.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+}
+
+fn home_dir(device: &Device) -> String {
+    match device.config().user.as_str() {
+        "root" => "/root".to_string(),
+        user => format!("/home/{user}"),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DirEntry {
+    pub name: String,
+    /// `file`, `directory`, `symlink` or `other`.
+    pub kind: String,
+    pub size: u64,
+    pub modified_unix: u64,
+    pub uri: String,
+}
+
+#[derive(Debug)]
+pub enum FileContent {
+    Directory(Vec<DirEntry>),
+    File {
+        /// The bytes from `offset` on.
+        data: Vec<u8>,
+        offset: u64,
+        /// Full size on the device; larger than `offset` plus `data` when
+        /// cut off.
+        size: u64,
+        truncated: bool,
+    },
+}
+
+/// Part of a file to read, from the `offset` and `length` query
+/// parameters of its URI, so large files can be read in chunks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ByteRange {
+    pub offset: u64,
+    /// Bytes to read, at most the configured `max_read_bytes`.
+    pub length: Option<u64>,
+}
+
+impl ByteRange {
+    /// Takes `key` if it is a range parameter; `None` if it is not, or if
+    /// its value is not a number.
+    pub(crate) fn parse_param(&mut self, key: &str, value: &str) -> Option<()> {
+        match key {
+            "offset" => self.offset = value.parse().ok()?,
+            "length" => self.length = Some(value.parse().ok()?),
+            _ => return None,
+        }
+        Some(())
+    }
+
+    /// Bytes to read when at most `max` may be.
+    pub fn limit(&self, max: u64) -> u64 {
+        self.length.map_or(max, |length| length.min(max))
+    }
+}
+
+/// Decoded `key=value` pairs of a URI query.
+pub(crate) fn query_pairs(query: &str) -> Option<Vec<(&str, String)>> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Some((key, decode_path(value)?))
+        })
+        .collect()
+}
+
+pub fn uri(device: &str, path: &str) -> String {
+    format!("{URI_SCHEME}{device}{}", encode_path(path))
+}
+
+/// Device name, absolute path and byte range of a `device://` URI.
+pub fn parse_uri(uri: &str) -> Option<(&str, String, ByteRange)> {
+    let rest = uri.strip_prefix(URI_SCHEME)?;
+    let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let (device, path) = match rest.split_once('/') {
+        Some((device, path)) => (device, format!("/{path}")),
+        None => (rest, "/".to_string()),
+    };
+    if device.is_empty() {
+        return None;
+    }
+    let mut range = ByteRange::default();
+    for (key, value) in query_pairs(query)? {
+        range.parse_param(key, &value)?;
+    }
+    Some((device, decode_path(&path)?, range))
+}
+
+/// Percent-encodes characters that are not safe in a URI path.
+pub(crate) fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~+@,=".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+pub(crate) fn decode_path(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Collapses `.` and empty components; `..` is refused rather than
+/// resolved so a path cannot climb out of its root textually.
+fn normalize(path: &str) -> Result<String> {
+    let mut parts = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                return Err(Error::InvalidArgument(format!(
+                    "'{path}' must not contain '..'"
+                )));
+            }
+            part => parts.push(part),
+        }
+    }
+    Ok(format!("/{}", parts.join("/")))
+}
+
+fn kind(file_type: &str) -> &'static str {
+    match file_type {
+        "directory" => "directory",
+        "symbolic link" => "symlink",
+        t if t.starts_with("regular") => "file",
+        _ => "other",
+    }
+}
+
+/// Reads a file or lists a directory at `path`. Returns `None` if it does
+/// not exist. Paths outside the configured roots, also after resolving
+/// symlinks, are refused.
+pub async fn read(
+    device: &Device,
+    options: &FilesOptions,
+    path: &str,
+    range: ByteRange,
+) -> Result<Option<FileContent>> {
+    let path = normalize(path)?;
+    let outside = || {
+        Error::InvalidArgument(format!(
+            "'{path}' is outside the browsable directories ({})",
+            options.roots(device).join(", ")
+        ))
+    };
+    if !options.allows(device, &path) {
+        return Err(outside());
+    }
+    let quoted = ssh::quote(&path);
+    let command = ssh::sections(&[
+        &format!("readlink -f -- {quoted} || true"),
+        &format!("stat -L -c '%F|%s' -- {quoted} 2>/dev/null || true"),
+    ]);
+    let stdout = device.exec_checked(&command).await?;
+    let [resolved, stat] = ssh::split_sections(&stdout)[..] else {
+        return Err(Error::Parse {
+            device: device.name().to_string(),
+            message: "missing output sections".to_string(),
+        });
+    };
+    let Some((file_type, size)) = stat.trim().split_once('|') else {
+        return Ok(None);
+    };
+    let resolved = resolved.trim();
+    if !resolved.is_empty() && !options.allows(device, resolved) {
+        return Err(outside());
+    }
+
+    if file_type == "directory" {
+        let command = format!(
+            "cd {quoted} && for f in * .[!.]* ..?*; do \
+             [ -e \"$f\" ] || [ -L \"$f\" ] && stat -c '%F|%s|%Y|%n' -- \"$f\"; done; true"
+        );
+        let stdout = device.exec_checked(&command).await?;
+        let mut entries: Vec<DirEntry> = stdout
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(4, '|');
+                let file_type = fields.next()?;
+                let size = fields.next()?.parse().ok()?;
+                let modified_unix = fields.next()?.parse().ok()?;
+                let name = fields.next()?.to_string();
+                Some(DirEntry {
+                    uri: uri(
+                        device.name(),
+                        &format!("{}/{name}", path.trim_end_matches('/')),
+                    ),
+                    kind: kind(file_type).to_string(),
+                    name,
+                    size,
+                    modified_unix,
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        return Ok(Some(FileContent::Directory(entries)));
+    }
+    if kind(file_type) != "file" {
+        return Err(Error::InvalidArgument(format!(
+            "'{path}' is a {file_type}, not a regular file"
+        )));
+    }
+
+    let size: u64 = size.parse().unwrap_or_default();
+    let limit = range.limit(options.max_read_bytes);
+    let command = match range.offset {
+        0 => format!("head -c {limit} -- {quoted} | base64"),
+        offset => format!(
+            "tail -c +{} -- {quoted} | head -c {limit} | base64",
+            offset + 1
+        ),
+    };
+    let encoded = device.exec_checked(&command).await?;
+    let encoded: String = encoded.split_whitespace().collect();
+    let data = BASE64.decode(encoded).map_err(|err| Error::Parse {
+        device: device.name().to_string(),
+        message: format!("bad base64 from device: {err}"),
+    })?;
+    Ok(Some(FileContent::File {
+        truncated: range.offset + (data.len() as u64) < size,
+        offset: range.offset,
+        size,
+        data,
+    }))
+}
+
+/// MIME type of a file from its name, for files that are not plain text.
+pub fn mime_type(path: &str, is_text: bool) -> &'static str {
+    let extension = path.rsplit_once('.').map(|(_, ext)| ext).unwrap_or("");
+    match extension.to_ascii_lowercase().as_str() {
+        "json" => "application/json",
+        "xml" | "ts" => "application/xml",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "qml" | "js" => "text/plain",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "rpm" => "application/x-rpm",
+        "gz" => "application/gzip",
+        "xz" => "application/x-xz",
+        "zst" => "application/zstd",
+        "zip" => "application/zip",
+        _ if is_text => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Whether a file is binary by its name alone, so that a chunk of it is
+/// not mistaken for text when it happens to decode as UTF-8.
+pub fn is_binary(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    // Core dumps are `core`, `core.<pid>` or `core.<app>.<pid>...`.
+    name == "core"
+        || name.starts_with("core.")
+        || !matches!(
+            mime_type(name, false),
+            "application/octet-stream"
+                | "application/json"
+                | "application/xml"
+                | "image/svg+xml"
+                | "text/html"
+                | "text/css"
+                | "text/plain"
+        )
+}
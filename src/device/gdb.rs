@@ -0,0 +1,867 @@
+//! Remote debugging sessions: gdbserver on the device, reached through an
+//! SSH forward by a host gdb that the tools drive over GDB/MI.
+
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+use super::ssh;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Limit for MI commands that answer right away.
+const MI_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_PORT: u16 = 10000;
+const DEFAULT_WAIT_SECS: u64 = 30;
+const DEFAULT_MAX_FRAMES: usize = 30;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GdbStartParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Program on the device, e.g. `/usr/bin/ru.example.myapp`.
+    pub program: String,
+    /// Arguments for a program gdbserver starts.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Attach to the running instance of `program` instead of starting it.
+    #[serde(default)]
+    pub attach: bool,
+    /// Local copy of the program with debug symbols, e.g. the project's
+    /// build output (default: gdb reads the program from the device).
+    #[serde(default)]
+    pub symbols: Option<String>,
+    /// Local sysroot for shared libraries (default: gdb fetches them from
+    /// the device, which is slower).
+    #[serde(default)]
+    pub sysroot: Option<String>,
+    /// Port gdbserver listens on, on the device's loopback (default 10000).
+    #[serde(default)]
+    pub port: Option<u16>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GdbSessionParams {
+    /// Session id from `gdb_session_start`.
+    pub session: u32,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GdbBreakParams {
+    /// Session id from `gdb_session_start`.
+    pub session: u32,
+    /// `file:line`, a function such as `Greeter::greet`, or `*address`.
+    pub location: String,
+    /// Only stop when this expression is true.
+    #[serde(default)]
+    pub condition: Option<String>,
+    /// Delete the breakpoint after its first hit.
+    #[serde(default)]
+    pub temporary: bool,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GdbAction {
+    Continue,
+    /// Step over calls.
+    Next,
+    /// Step into calls.
+    Step,
+    /// Run until the current function returns.
+    Finish,
+    /// Stop the running program.
+    Interrupt,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GdbExecParams {
+    /// Session id from `gdb_session_start`.
+    pub session: u32,
+    pub action: GdbAction,
+    /// How long to wait for the program to stop (default 30). It keeps
+    /// running after that; `interrupt` stops it.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GdbBacktraceParams {
+    /// Session id from `gdb_session_start`.
+    pub session: u32,
+    /// gdb thread id (default: the current thread).
+    #[serde(default)]
+    pub thread: Option<u32>,
+    /// Frames to list (default 30).
+    #[serde(default)]
+    pub max_frames: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GdbEvaluateParams {
+    /// Session id from `gdb_session_start`.
+    pub session: u32,
+    /// C++ expressions, e.g. `this->m_count`, `*item`, `list.size()`.
+    pub expressions: Vec<String>,
+    /// gdb thread id (default: the current thread).
+    #[serde(default)]
+    pub thread: Option<u32>,
+    /// Frame level, 0 being the innermost (default 0).
+    #[serde(default)]
+    pub frame: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct GdbSessionInfo {
+    pub id: u32,
+    pub device: String,
+    pub program: String,
+    /// PID of gdbserver on the device.
+    pub gdbserver_pid: Option<u32>,
+    /// Port forward carrying the connection, see `device_tunnels`.
+    pub tunnel_id: u32,
+    /// The stop gdb reported on connecting, if any.
+    pub stopped: Option<StopEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct GdbFrame {
+    pub level: Option<u32>,
+    pub function: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub address: Option<String>,
+    /// Shared library of frames without line information.
+    pub library: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct StopEvent {
+    /// e.g. `breakpoint-hit`, `end-stepping-range`, `signal-received`,
+    /// `exited-normally`.
+    pub reason: Option<String>,
+    pub signal: Option<String>,
+    pub breakpoint: Option<String>,
+    pub thread: Option<String>,
+    pub exit_code: Option<String>,
+    pub frame: Option<GdbFrame>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Breakpoint {
+    pub number: String,
+    /// False while the location is in a library not loaded yet.
+    pub resolved: bool,
+    pub function: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub address: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ExecResult {
+    /// True when the program had not stopped within the timeout.
+    pub running: bool,
+    pub stopped: Option<StopEvent>,
+    /// gdb and program console output meanwhile.
+    pub console: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Variable {
+    pub name: String,
+    pub value: Option<String>,
+    pub r#type: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Backtrace {
+    pub thread: Option<u32>,
+    pub frames: Vec<GdbFrame>,
+    /// Arguments and locals of the innermost frame; aggregates show only
+    /// their type.
+    pub locals: Vec<Variable>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Evaluation {
+    pub expression: String,
+    pub value: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct EvaluationList {
+    pub results: Vec<Evaluation>,
+}
+
+/// A result record and what gdb printed before it.
+struct MiResponse {
+    class: String,
+    results: Value,
+    console: String,
+    stopped: Option<Value>,
+}
+
+/// A host gdb speaking GDB/MI on its stdin and stdout.
+struct Gdb {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    token: u64,
+}
+
+impl Gdb {
+    fn spawn(program: &std::path::Path) -> Result<Self> {
+        let mut child = Command::new(program)
+            .args(["--interpreter=mi2", "-q", "-nx"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|source| Error::Spawn {
+                program: program.display().to_string(),
+                source,
+            })?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped")).lines();
+        Ok(Self {
+            child,
+            stdin,
+            stdout,
+            token: 0,
+        })
+    }
+
+    /// Sends `command` and reads up to its result record.
+    async fn command(&mut self, command: &str) -> Result<MiResponse> {
+        if command.contains(['\n', '\r']) {
+            return Err(Error::InvalidArgument(
+                "a gdb/mi command is one line".to_string(),
+            ));
+        }
+        self.token += 1;
+        let token = self.token.to_string();
+        tracing::debug!(%command, "gdb/mi");
+        self.stdin
+            .write_all(format!("{token}{command}\n").as_bytes())
+            .await?;
+        let mut console = String::new();
+        let mut stopped = None;
+        let read = async {
+            while let Some(line) = self.stdout.next_line().await? {
+                if let Some(record) = line.strip_prefix(token.as_str())
+                    && let Some(record) = record.strip_prefix('^')
+                {
+                    let (class, rest) = record.split_once(',').unwrap_or((record, ""));
+                    return Ok(Some((class.to_string(), parse_results(rest))));
+                }
+                read_record(&line, &mut console, &mut stopped);
+            }
+            Ok::<_, std::io::Error>(None)
+        };
+        let result =
+            tokio::time::timeout(MI_TIMEOUT, read)
+                .await
+                .map_err(|_| Error::CommandTimeout {
+                    program: "gdb".to_string(),
+                    seconds: MI_TIMEOUT.as_secs(),
+                })??;
+        let Some((class, results)) = result else {
+            return Err(Error::InvalidArgument("gdb exited".to_string()));
+        };
+        if class == "error" {
+            let message = results["msg"].as_str().unwrap_or("error").to_string();
+            return Err(Error::InvalidArgument(format!("gdb: {message}")));
+        }
+        Ok(MiResponse {
+            class,
+            results,
+            console,
+            stopped,
+        })
+    }
+
+    /// Reads until the program stops, for at most `timeout`.
+    async fn wait_stopped(
+        &mut self,
+        timeout: Duration,
+        console: &mut String,
+    ) -> Result<Option<Value>> {
+        let read = async {
+            while let Some(line) = self.stdout.next_line().await? {
+                let mut stopped = None;
+                read_record(&line, console, &mut stopped);
+                if stopped.is_some() {
+                    return Ok(stopped);
+                }
+            }
+            Ok::<_, std::io::Error>(None)
+        };
+        match tokio::time::timeout(timeout, read).await {
+            Ok(stopped) => Ok(stopped?),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+/// Collects stream output into `console` and a `*stopped` record into
+/// `stopped`.
+fn read_record(line: &str, console: &mut String, stopped: &mut Option<Value>) {
+    if let Some(text) = line.strip_prefix(['~', '@']) {
+        console.push_str(parse_value(&mut text.as_bytes()).as_str().unwrap_or(""));
+    } else if let Some(rest) = line.strip_prefix("*stopped") {
+        *stopped = Some(parse_results(rest.trim_start_matches(',')));
+    }
+}
+
+/// `a="1",b={c="2"},d=["3"]` as a JSON object.
+fn parse_results(text: &str) -> Value {
+    let mut input = text.as_bytes();
+    let mut map = Map::new();
+    parse_result_list(&mut input, &mut map, b'\0');
+    Value::Object(map)
+}
+
+fn parse_result_list(input: &mut &[u8], map: &mut Map<String, Value>, end: u8) {
+    while let Some(&c) = input.first() {
+        if c == end {
+            break;
+        }
+        if c == b',' {
+            *input = &input[1..];
+            continue;
+        }
+        let key_end = input.iter().position(|&c| c == b'=').unwrap_or(input.len());
+        let key = String::from_utf8_lossy(&input[..key_end]).into_owned();
+        *input = input.get(key_end + 1..).unwrap_or(&[]);
+        let value = parse_value(input);
+        map.insert(key, value);
+    }
+}
+
+fn parse_value(input: &mut &[u8]) -> Value {
+    match input.first() {
+        Some(b'"') => {
+            let mut bytes = Vec::new();
+            let mut at = 1;
+            while at < input.len() {
+                match input[at] {
+                    b'"' => break,
+                    b'\\' if at + 1 < input.len() => {
+                        at += 1;
+                        bytes.push(match input[at] {
+                            b'n' => b'\n',
+                            b't' => b'\t',
+                            other => other,
+                        });
+                    }
+                    other => bytes.push(other),
+                }
+                at += 1;
+            }
+            *input = input.get(at + 1..).unwrap_or(&[]);
+            Value::String(String::from_utf8_lossy(&bytes).into_owned())
+        }
+        Some(b'{') => {
+            *input = &input[1..];
+            let mut map = Map::new();
+            parse_result_list(input, &mut map, b'}');
+            *input = input.get(1..).unwrap_or(&[]);
+            Value::Object(map)
+        }
+        Some(b'[') => {
+            *input = &input[1..];
+            let mut items = Vec::new();
+            while let Some(&c) = input.first() {
+                match c {
+                    b']' => break,
+                    b',' => *input = &input[1..],
+                    b'"' | b'{' | b'[' => items.push(parse_value(input)),
+                    // `frame={...}` items: keep the values.
+                    _ => {
+                        let key_end = input.iter().position(|&c| c == b'=').unwrap_or(input.len());
+                        *input = input.get(key_end + 1..).unwrap_or(&[]);
+                        items.push(parse_value(input));
+                    }
+                }
+            }
+            *input = input.get(1..).unwrap_or(&[]);
+            Value::Array(items)
+        }
+        _ => {
+            *input = &[];
+            Value::Null
+        }
+    }
+}
+
+/// `text` as an MI C string. Control characters are refused: a newline
+/// would end the MI command and start another, CLI ones like `shell`
+/// included.
+fn mi_quote(text: &str) -> Result<String> {
+    if let Some(c) = text.chars().find(|c| c.is_control()) {
+        return Err(Error::InvalidArgument(format!(
+            "'{}' contains the control character {c:?}",
+            text.escape_debug()
+        )));
+    }
+    Ok(format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+fn string(value: &Value, key: &str) -> Option<String> {
+    value.get(key)?.as_str().map(str::to_string)
+}
+
+fn number<T: std::str::FromStr>(value: &Value, key: &str) -> Option<T> {
+    value.get(key)?.as_str()?.parse().ok()
+}
+
+fn frame(value: &Value) -> GdbFrame {
+    GdbFrame {
+        level: number(value, "level"),
+        function: string(value, "func"),
+        file: string(value, "fullname").or_else(|| string(value, "file")),
+        line: number(value, "line"),
+        address: string(value, "addr"),
+        library: string(value, "from"),
+    }
+}
+
+fn stop_event(value: &Value) -> StopEvent {
+    StopEvent {
+        reason: string(value, "reason"),
+        signal: string(value, "signal-name"),
+        breakpoint: string(value, "bkptno"),
+        thread: string(value, "thread-id"),
+        exit_code: string(value, "exit-code"),
+        frame: value.get("frame").map(frame),
+    }
+}
+
+struct Session {
+    info: GdbSessionInfo,
+    gdb: Gdb,
+}
+
+/// Debugging sessions, shared by all clients. They end with the server.
+#[derive(Clone, Default)]
+pub struct GdbSessions {
+    inner: Arc<Mutex<GdbTable>>,
+}
+
+impl std::fmt::Debug for GdbSessions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GdbSessions").finish_non_exhaustive()
+    }
+}
+
+#[derive(Default)]
+struct GdbTable {
+    next_id: u32,
+    sessions: BTreeMap<u32, Arc<tokio::sync::Mutex<Session>>>,
+}
+
+impl GdbSessions {
+    fn table(&self) -> std::sync::MutexGuard<'_, GdbTable> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn insert(&self, mut session: Session) -> GdbSessionInfo {
+        let mut table = self.table();
+        table.next_id += 1;
+        session.info.id = table.next_id;
+        let info = session.info.clone();
+        table
+            .sessions
+            .insert(info.id, Arc::new(tokio::sync::Mutex::new(session)));
+        info
+    }
+
+    fn get(&self, id: u32) -> Result<Arc<tokio::sync::Mutex<Session>>> {
+        self.table()
+            .sessions
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| Error::InvalidArgument(format!("no debugging session with id {id}")))
+    }
+
+    fn remove(&self, id: u32) -> Result<Arc<tokio::sync::Mutex<Session>>> {
+        self.table()
+            .sessions
+            .remove(&id)
+            .ok_or_else(|| Error::InvalidArgument(format!("no debugging session with id {id}")))
+    }
+}
+
+/// Starts gdbserver on the device, returning its PID.
+async fn start_gdbserver(server: &AuroraServer, params: &GdbStartParams, port: u16) -> Result<u32> {
+    let device = server.devices().get(&params.device)?;
+    let check = device.exec("command -v gdbserver").await?;
+    if !check.success() {
+        return Err(Error::InvalidArgument(format!(
+            "gdbserver is not installed on {}; install gdb-gdbserver",
+            params.device
+        )));
+    }
+    let listen = format!("127.0.0.1:{port}");
+    let launch = if params.attach {
+        let name = params.program.rsplit('/').next().unwrap_or(&params.program);
+        let pid = device
+            .exec(&format!("pidof -s {}", ssh::quote(name)))
+            .await?
+            .stdout
+            .trim()
+            .to_string();
+        if pid.is_empty() {
+            return Err(Error::InvalidArgument(format!(
+                "{} is not running on {}",
+                params.program, params.device
+            )));
+        }
+        format!("gdbserver --attach {listen} {pid}")
+    } else {
+        let args: String = params
+            .args
+            .iter()
+            .map(|arg| format!(" {}", ssh::quote(arg)))
+            .collect();
+        format!(
+            "gdbserver --once {listen} {}{args}",
+            ssh::quote(&params.program)
+        )
+    };
+    let log = format!("/tmp/aurora-mcp-gdbserver-{port}.log");
+    let output = device
+        .exec(&format!(
+            "nohup {launch} >{log} 2>&1 </dev/null & pid=$!; i=0; \
+while [ $i -lt 20 ] && ! grep -q 'Listening on port' {log}; do \
+kill -0 $pid 2>/dev/null || break; sleep 0.5; i=$((i+1)); done; \
+if grep -q 'Listening on port' {log}; then echo $pid; else cat {log}; exit 1; fi"
+        ))
+        .await?;
+    if !output.success() {
+        return Err(Error::RemoteCommand {
+            device: params.device.clone(),
+            status: output.status,
+            stderr: output.stdout.trim().to_string(),
+        });
+    }
+    output.stdout.trim().parse().map_err(|_| {
+        Error::InvalidArgument(format!(
+            "unexpected gdbserver output: {}",
+            output.stdout.trim()
+        ))
+    })
+}
+
+/// Connects a new gdb to the forwarded gdbserver.
+async fn connect(
+    server: &AuroraServer,
+    params: &GdbStartParams,
+    host_port: &str,
+) -> Result<(Gdb, Option<StopEvent>)> {
+    let mut gdb = Gdb::spawn(&server.state().config.sdk.gdb()?)?;
+    gdb.command("-gdb-set pagination off").await?;
+    gdb.command("-gdb-set confirm off").await?;
+    let sysroot = params.sysroot.as_deref().unwrap_or("target:");
+    gdb.command(&format!("-gdb-set sysroot {}", mi_quote(sysroot)?))
+        .await?;
+    if let Some(symbols) = &params.symbols {
+        gdb.command(&format!("-file-exec-and-symbols {}", mi_quote(symbols)?))
+            .await?;
+    }
+    let connected = gdb
+        .command(&format!("-target-select remote {host_port}"))
+        .await?;
+    let mut stopped = connected.stopped;
+    if stopped.is_none() {
+        let mut console = String::new();
+        stopped = gdb
+            .wait_stopped(Duration::from_secs(2), &mut console)
+            .await?;
+    }
+    Ok((gdb, stopped.as_ref().map(stop_event)))
+}
+
+#[tool_router(router = gdb_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Start a remote debugging session: launches the program under gdbserver on the device (or attaches to its running instance with attach=true), forwards the port over SSH and connects a host gdb. Returns the session id for gdb_break, gdb_exec, gdb_backtrace and gdb_evaluate; end it with gdb_session_stop."
+    )]
+    pub async fn gdb_session_start(
+        &self,
+        Parameters(params): Parameters<GdbStartParams>,
+    ) -> Result<Json<GdbSessionInfo>> {
+        let device = self.devices().get(&params.device)?;
+        if device.is_mock() {
+            return Err(Error::InvalidArgument(format!(
+                "device '{}' is simulated; debugging needs an SSH connection",
+                params.device
+            )));
+        }
+        if !params.program.starts_with('/') {
+            return Err(Error::InvalidArgument(format!(
+                "'{}' is not an absolute path on the device",
+                params.program
+            )));
+        }
+        let port = params.port.unwrap_or(DEFAULT_PORT);
+        let gdbserver_pid = start_gdbserver(self, &params, port).await?;
+        let tunnels = &self.state().tunnels;
+        let connected = match tunnels.open_local(&device, port).await {
+            Ok(tunnel) => match connect(self, &params, &tunnel.listen).await {
+                Ok((gdb, stopped)) => Ok((gdb, stopped, tunnel.id)),
+                Err(err) => {
+                    let _ = tunnels.close(tunnel.id).await;
+                    Err(err)
+                }
+            },
+            Err(err) => Err(err),
+        };
+        let (gdb, stopped, tunnel_id) = match connected {
+            Ok(connected) => connected,
+            Err(err) => {
+                let _ = device.exec(&format!("kill {gdbserver_pid}")).await;
+                return Err(err);
+            }
+        };
+        let session = Session {
+            info: GdbSessionInfo {
+                id: 0,
+                device: params.device,
+                program: params.program,
+                gdbserver_pid: Some(gdbserver_pid),
+                tunnel_id,
+                stopped,
+            },
+            gdb,
+        };
+        Ok(Json(self.state().gdb_sessions.insert(session)))
+    }
+
+    #[tool(
+        description = "Set a breakpoint in a debugging session at file:line, a function or *address, optionally conditional or temporary."
+    )]
+    pub async fn gdb_break(
+        &self,
+        Parameters(params): Parameters<GdbBreakParams>,
+    ) -> Result<Json<Breakpoint>> {
+        let session = self.state().gdb_sessions.get(params.session)?;
+        let mut session = session.lock().await;
+        let mut command = String::from("-break-insert -f");
+        if params.temporary {
+            command.push_str(" -t");
+        }
+        if let Some(condition) = &params.condition {
+            command.push_str(&format!(" -c {}", mi_quote(condition)?));
+        }
+        command.push_str(&format!(" {}", mi_quote(&params.location)?));
+        let response = session.gdb.command(&command).await?;
+        let bkpt = &response.results["bkpt"];
+        Ok(Json(Breakpoint {
+            number: string(bkpt, "number").unwrap_or_default(),
+            resolved: string(bkpt, "pending").is_none(),
+            function: string(bkpt, "func"),
+            file: string(bkpt, "fullname").or_else(|| string(bkpt, "file")),
+            line: number(bkpt, "line"),
+            address: string(bkpt, "addr"),
+        }))
+    }
+
+    #[tool(
+        description = "Resume or step the program in a debugging session (continue, next, step, finish) or stop it (interrupt), then wait for it to stop. Returns the stop reason, signal, breakpoint and location, or running=true if it did not stop within timeout_secs."
+    )]
+    pub async fn gdb_exec(
+        &self,
+        Parameters(params): Parameters<GdbExecParams>,
+    ) -> Result<Json<ExecResult>> {
+        let session = self.state().gdb_sessions.get(params.session)?;
+        let mut session = session.lock().await;
+        let command = match params.action {
+            GdbAction::Continue => "-exec-continue",
+            GdbAction::Next => "-exec-next",
+            GdbAction::Step => "-exec-step",
+            GdbAction::Finish => "-exec-finish",
+            GdbAction::Interrupt => "-exec-interrupt",
+        };
+        let response = session.gdb.command(command).await?;
+        let mut console = response.console;
+        let mut stopped = response.stopped;
+        if stopped.is_none() && (response.class == "running" || response.class == "done") {
+            let timeout = Duration::from_secs(params.timeout_secs.unwrap_or(DEFAULT_WAIT_SECS));
+            stopped = session.gdb.wait_stopped(timeout, &mut console).await?;
+        }
+        let stopped = stopped.as_ref().map(stop_event);
+        if let Some(event) = &stopped {
+            session.info.stopped = Some(event.clone());
+        }
+        Ok(Json(ExecResult {
+            running: stopped.is_none(),
+            stopped,
+            console,
+        }))
+    }
+
+    #[tool(
+        description = "Backtrace of a stopped program in a debugging session, with the arguments and locals of the innermost frame.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn gdb_backtrace(
+        &self,
+        Parameters(params): Parameters<GdbBacktraceParams>,
+    ) -> Result<Json<Backtrace>> {
+        let session = self.state().gdb_sessions.get(params.session)?;
+        let mut session = session.lock().await;
+        let thread = params
+            .thread
+            .map(|thread| format!(" --thread {thread}"))
+            .unwrap_or_default();
+        let max = params.max_frames.unwrap_or(DEFAULT_MAX_FRAMES).max(1);
+        let response = session
+            .gdb
+            .command(&format!("-stack-list-frames{thread} 0 {}", max - 1))
+            .await?;
+        let frames = response.results["stack"]
+            .as_array()
+            .map(|frames| frames.iter().map(frame).collect())
+            .unwrap_or_default();
+        let response = session
+            .gdb
+            .command(&format!(
+                "-stack-list-variables{thread} --frame 0 --simple-values"
+            ))
+            .await?;
+        let locals = response.results["variables"]
+            .as_array()
+            .map(|variables| {
+                variables
+                    .iter()
+                    .map(|variable| Variable {
+                        name: string(variable, "name").unwrap_or_default(),
+                        value: string(variable, "value"),
+                        r#type: string(variable, "type"),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Json(Backtrace {
+            thread: params.thread,
+            frames,
+            locals,
+        }))
+    }
+
+    #[tool(
+        description = "Evaluate C++ expressions (variables, members, dereferences, calls) in a stopped program's frame in a debugging session. Reports a value or an error per expression. Calls run code in the program and can change its state."
+    )]
+    pub async fn gdb_evaluate(
+        &self,
+        Parameters(params): Parameters<GdbEvaluateParams>,
+    ) -> Result<Json<EvaluationList>> {
+        let session = self.state().gdb_sessions.get(params.session)?;
+        let mut session = session.lock().await;
+        let mut context = String::new();
+        if let Some(thread) = params.thread {
+            context.push_str(&format!(" --thread {thread}"));
+        }
+        if params.thread.is_some() || params.frame.is_some() {
+            context.push_str(&format!(" --frame {}", params.frame.unwrap_or(0)));
+        }
+        let mut results = Vec::new();
+        for expression in params.expressions {
+            let evaluated = match mi_quote(&expression) {
+                Ok(quoted) => {
+                    let command = format!("-data-evaluate-expression{context} {quoted}");
+                    session.gdb.command(&command).await
+                }
+                Err(err) => Err(err),
+            };
+            results.push(match evaluated {
+                Ok(response) => Evaluation {
+                    value: string(&response.results, "value"),
+                    error: None,
+                    expression,
+                },
+                Err(err) => Evaluation {
+                    value: None,
+                    error: Some(err.to_string()),
+                    expression,
+                },
+            });
+        }
+        Ok(Json(EvaluationList { results }))
+    }
+
+    #[tool(
+        description = "End a debugging session: quits gdb, stops gdbserver (and a program it started) on the device and closes the port forward."
+    )]
+    pub async fn gdb_session_stop(
+        &self,
+        Parameters(params): Parameters<GdbSessionParams>,
+    ) -> Result<Json<GdbSessionInfo>> {
+        let session = self.state().gdb_sessions.remove(params.session)?;
+        let mut session = session.lock().await;
+        let _ =
+            tokio::time::timeout(Duration::from_secs(5), session.gdb.command("-gdb-exit")).await;
+        let _ = session.gdb.child.start_kill();
+        let _ = session.gdb.child.wait().await;
+        if let Some(pid) = session.info.gdbserver_pid
+            && let Ok(device) = self.devices().get(&session.info.device)
+        {
+            let _ = device.exec(&format!("kill {pid} 2>/dev/null")).await;
+        }
+        let _ = self.state().tunnels.close(session.info.tunnel_id).await;
+        Ok(Json(session.info.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn quotes_mi_strings() {
+        assert_eq!(mi_quote("main").unwrap(), r#""main""#);
+        assert_eq!(mi_quote(r#"s == "a\b""#).unwrap(), r#""s == \"a\\b\"""#);
+        for text in ["1\nshell touch /tmp/x", "x\r", "a\tb", "\u{7}"] {
+            assert!(matches!(mi_quote(text), Err(Error::InvalidArgument(_))));
+        }
+    }
+
+    #[test]
+    fn parses_result_records() {
+        let results = parse_results(
+            r#"bkpt={number="1",type="breakpoint",addr="0x0040",func="main",file="main.cpp",line="7"},thread-ids=["1","2"]"#,
+        );
+        assert_eq!(results["bkpt"]["number"], "1");
+        assert_eq!(results["bkpt"]["line"], "7");
+        assert_eq!(results["thread-ids"], json!(["1", "2"]));
+
+        let results =
+            parse_results(r#"stack=[frame={level="0",func="f"},frame={level="1",func="main"}]"#);
+        assert_eq!(results["stack"][1]["func"], "main");
+
+        let results = parse_results(r#"value="\"quoted\"\n\tnext""#);
+        assert_eq!(results["value"], "\"quoted\"\n\tnext");
+
+        assert_eq!(parse_results(""), json!({}));
+        // Truncated output keeps what was read.
+        assert_eq!(parse_results(r#"value="1"#)["value"], "1");
+    }
+}
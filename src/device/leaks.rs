@@ -0,0 +1,497 @@
+//! `check_leaks`: runs an app under valgrind memcheck or heaptrack on a
+//! device (typically the x86_64 emulator) and summarizes the leaks found
+//! when it exits.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use rmcp::handler::server::tool::cached_schema_for_type;
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::CallToolResult;
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{apps, ssh};
+use crate::error::{Error, Result, tool_result};
+use crate::resources::file_link;
+use crate::server::AuroraServer;
+
+const DEFAULT_DURATION_SECS: u64 = 30;
+const MAX_DURATION_SECS: u64 = 600;
+const DEFAULT_TOP: usize = 10;
+/// Frames kept per allocation stack.
+const MAX_FRAMES: usize = 12;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LeakTool {
+    /// valgrind memcheck: exact, also reports invalid reads and writes, but
+    /// runs the app 20-50 times slower.
+    #[default]
+    Memcheck,
+    /// heaptrack: fast enough for interactive use, leaks only.
+    Heaptrack,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct LeakCheckParams {
+    /// Device name from the config, typically the emulator.
+    pub device: String,
+    /// Binary name of the app under /usr/bin, e.g. `ru.example.myapp`.
+    pub app: String,
+    #[serde(default)]
+    pub tool: LeakTool,
+    /// Arguments for the app.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Shell commands run on the device once the app started, to drive it,
+    /// e.g. `dbus-send` calls or `sleep 5; ...`. Apps start slowly under
+    /// memcheck, so let the script wait for them.
+    #[serde(default)]
+    pub script: Option<String>,
+    /// How long the app runs before it is stopped and the leaks are
+    /// collected, including the script (default 30, at most 600).
+    #[serde(default)]
+    pub duration_secs: Option<u64>,
+    /// How many leaks to return, largest first (default 10).
+    #[serde(default)]
+    pub top: Option<usize>,
+    /// Local directory for the results. Defaults to a new directory under
+    /// `<data_dir>/leaks`.
+    #[serde(default)]
+    pub output_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LeakFrame {
+    pub function: String,
+    /// `file:line` when the binary has debug info, else the library.
+    pub location: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Leak {
+    pub bytes: u64,
+    /// Blocks (memcheck) or allocation calls (heaptrack) leaked.
+    pub count: u64,
+    /// memcheck's `definitely`, `indirectly` or `possibly` lost.
+    pub kind: Option<String>,
+    /// The allocation stack, innermost frame first.
+    pub stack: Vec<LeakFrame>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LeakReport {
+    pub device: String,
+    pub app: String,
+    pub tool: LeakTool,
+    pub pid: u32,
+    pub duration_secs: u64,
+    /// Bytes leaked in total: definitely and indirectly lost for memcheck.
+    pub leaked_bytes: u64,
+    /// memcheck's leak summary, e.g. `definitely lost` to bytes.
+    pub by_kind: BTreeMap<String, u64>,
+    pub leaks: Vec<Leak>,
+    /// Memory errors memcheck reported besides leaks, e.g. invalid reads.
+    pub errors: Vec<String>,
+    /// Output of the interaction script.
+    pub script_output: Option<String>,
+    /// Local directory holding the results.
+    pub directory: PathBuf,
+    /// The full memcheck log or heaptrack_print report.
+    pub report_file: PathBuf,
+    /// heaptrack's recording, for heaptrack_gui.
+    pub data_file: Option<PathBuf>,
+}
+
+fn parse_number(text: &str) -> Option<u64> {
+    text.replace(',', "").parse().ok()
+}
+
+/// heaptrack_print sizes such as `512B`, `1.23K` or `4.50M`.
+fn parse_size(text: &str) -> Option<u64> {
+    let (number, unit) = text
+        .find(|c: char| c.is_ascii_alphabetic())
+        .map(|at| text.split_at(at))
+        .unwrap_or((text, ""));
+    let factor = match unit {
+        "" | "B" => 1.0,
+        "K" => 1e3,
+        "M" => 1e6,
+        "G" => 1e9,
+        _ => return None,
+    };
+    Some((number.parse::<f64>().ok()? * factor) as u64)
+}
+
+/// `0x4C2AB80: malloc (in /usr/lib/valgrind/vgpreload_memcheck.so)` or
+/// `0x10A2F3: Model::load() (model.cpp:42)`.
+fn parse_memcheck_frame(text: &str) -> LeakFrame {
+    let text = text.split_once(": ").map_or(text, |(_, rest)| rest);
+    match text.rsplit_once(" (") {
+        Some((function, location)) => LeakFrame {
+            function: function.to_string(),
+            location: Some(
+                location
+                    .trim_end_matches(')')
+                    .trim_start_matches("in ")
+                    .to_string(),
+            ),
+        },
+        None => LeakFrame {
+            function: text.to_string(),
+            location: None,
+        },
+    }
+}
+
+/// Leaks, memory errors and the leak summary of a memcheck log.
+fn parse_memcheck(log: &str) -> (Vec<Leak>, Vec<String>, BTreeMap<String, u64>) {
+    let mut leaks = Vec::new();
+    let mut errors = Vec::new();
+    let mut by_kind = BTreeMap::new();
+    let mut current: Option<Leak> = None;
+    let mut error: Option<String> = None;
+    // Lines are `==PID== text`; records are separated by `==PID== `.
+    for line in log.lines() {
+        let Some(text) = line
+            .strip_prefix("==")
+            .and_then(|rest| rest.split_once("== "))
+            .map(|(_, text)| text)
+        else {
+            continue;
+        };
+        let frame = text
+            .trim_start()
+            .strip_prefix("at ")
+            .or_else(|| text.trim_start().strip_prefix("by "));
+        if let Some(frame) = frame {
+            if let Some(leak) = &mut current {
+                if leak.stack.len() < MAX_FRAMES {
+                    leak.stack.push(parse_memcheck_frame(frame));
+                }
+            } else if let Some(message) = error.take() {
+                let frame = parse_memcheck_frame(frame);
+                errors.push(match frame.location {
+                    Some(location) => format!("{message} in {} ({location})", frame.function),
+                    None => format!("{message} in {}", frame.function),
+                });
+            }
+            continue;
+        }
+        leaks.extend(current.take());
+        error = None;
+        let text = text.trim();
+        if let Some((size, rest)) = text.split_once(" bytes in ")
+            && let Some((count, rest)) = rest.split_once(" blocks are ")
+            && let Some((kind, _)) = rest.split_once(" in loss record")
+        {
+            let bytes = size.split_whitespace().next().and_then(parse_number);
+            current = Some(Leak {
+                bytes: bytes.unwrap_or_default(),
+                count: parse_number(count).unwrap_or_default(),
+                kind: Some(kind.trim_end_matches(" lost").to_string()),
+                stack: Vec::new(),
+            });
+        } else if let Some((kind, rest)) = text.split_once(": ")
+            && matches!(
+                kind,
+                "definitely lost" | "indirectly lost" | "possibly lost" | "still reachable"
+            )
+        {
+            let bytes = rest.split(" bytes").next().and_then(parse_number);
+            by_kind.insert(kind.to_string(), bytes.unwrap_or_default());
+        } else if [
+            "Invalid ",
+            "Conditional jump",
+            "Use of uninitialised",
+            "Mismatched free",
+            "Syscall param",
+            "Source and destination overlap",
+        ]
+        .iter()
+        .any(|prefix| text.starts_with(prefix))
+        {
+            error = Some(text.to_string());
+        }
+    }
+    leaks.extend(current);
+    (leaks, errors, by_kind)
+}
+
+/// The `MEMORY LEAKS` section and total of heaptrack_print output:
+///
+/// ```text
+/// 1.23K leaked over 5 calls from
+///   QString::reallocData(unsigned int, bool)
+///     at /usr/include/qt5/QtCore/qstring.h:123
+///     in /usr/lib64/libQt5Core.so.5
+/// ```
+fn parse_heaptrack(report: &str) -> (Vec<Leak>, u64) {
+    let mut leaks = Vec::new();
+    let mut total = 0;
+    let mut in_leaks = false;
+    for line in report.lines() {
+        if let Some(size) = line.strip_prefix("total memory leaked: ") {
+            total = parse_size(size.trim()).unwrap_or_default();
+            continue;
+        }
+        if !line.starts_with(' ') && !line.is_empty() && !line.contains(" leaked over ") {
+            in_leaks = line.trim() == "MEMORY LEAKS";
+            continue;
+        }
+        if !in_leaks {
+            continue;
+        }
+        if !line.starts_with(' ')
+            && let Some((size, rest)) = line.split_once(" leaked over ")
+        {
+            leaks.push(Leak {
+                bytes: parse_size(size.trim()).unwrap_or_default(),
+                count: rest
+                    .split_whitespace()
+                    .next()
+                    .and_then(parse_number)
+                    .unwrap_or_default(),
+                kind: None,
+                stack: Vec::new(),
+            });
+            continue;
+        }
+        let Some(leak) = leaks.last_mut() else {
+            continue;
+        };
+        let text = line.trim();
+        if let Some(location) = text
+            .strip_prefix("at ")
+            .or_else(|| text.strip_prefix("in "))
+        {
+            if let Some(frame) = leak.stack.last_mut()
+                && frame.location.is_none()
+            {
+                frame.location = Some(location.to_string());
+            }
+        } else if !text.is_empty()
+            && !text.contains(" leaked over ")
+            && leak.stack.len() < MAX_FRAMES
+        {
+            leak.stack.push(LeakFrame {
+                function: text.to_string(),
+                location: None,
+            });
+        }
+    }
+    (leaks, total)
+}
+
+pub async fn check(server: &AuroraServer, params: LeakCheckParams) -> Result<LeakReport> {
+    let device = server.devices().get(&params.device)?;
+    apps::check_app_name(&params.app)?;
+    let duration_secs = params.duration_secs.unwrap_or(DEFAULT_DURATION_SECS);
+    if duration_secs == 0 || duration_secs > MAX_DURATION_SECS {
+        return Err(Error::InvalidArgument(format!(
+            "duration_secs must be between 1 and {MAX_DURATION_SECS}"
+        )));
+    }
+    let top = params.top.unwrap_or(DEFAULT_TOP);
+
+    let directory = match params.output_dir {
+        Some(dir) => dir,
+        None => {
+            let stamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            server
+                .state()
+                .config
+                .data_dir()
+                .join("leaks")
+                .join(format!("{}-{}-{stamp}", params.device, params.app))
+        }
+    };
+    std::fs::create_dir_all(&directory)?;
+
+    let remote = format!("/tmp/aurora-mcp-leaks-{}", params.app);
+    // Quoted as a prefix, so that `{files}.*.zst` still globs.
+    let files = ssh::quote(&remote);
+    let binary = ssh::quote(&format!("/usr/bin/{}", params.app));
+    let args: String = params
+        .args
+        .iter()
+        .map(|arg| format!(" {}", ssh::quote(arg)))
+        .collect();
+    let (program, launch) = match params.tool {
+        LeakTool::Memcheck => (
+            "valgrind",
+            format!(
+                "valgrind --tool=memcheck --leak-check=full --show-leak-kinds=definite,indirect,possible \
+                 --num-callers=30 --log-file={files}.log {binary}{args}"
+            ),
+        ),
+        LeakTool::Heaptrack => ("heaptrack", format!("heaptrack -o {files} {binary}{args}")),
+    };
+    let started = Instant::now();
+    let output = device
+        .exec(&format!(
+            "command -v {program} >/dev/null 2>&1 || {{ echo '{program} is not installed; install the {program} package' >&2; exit 127; }}; \
+             rm -f {files}.log {files}.out {files}.txt {files}.*.gz {files}.*.zst; \
+             nohup {launch} >{files}.out 2>&1 </dev/null & pid=$!; sleep 3; \
+             kill -0 \"$pid\" 2>/dev/null || {{ cat {files}.out {files}.log >&2 2>/dev/null; exit 5; }}; \
+             echo \"$pid\""
+        ))
+        .await?;
+    if !output.success() {
+        return Err(Error::RemoteCommand {
+            device: params.device,
+            status: output.status,
+            stderr: output.stderr.trim().to_string(),
+        });
+    }
+    let pid: u32 = output.stdout.trim().parse().map_err(|_| Error::Parse {
+        device: params.device.clone(),
+        message: format!(
+            "expected the pid of {program}, got '{}'",
+            output.stdout.trim()
+        ),
+    })?;
+
+    let script_output = match &params.script {
+        Some(script) => {
+            let budget = Duration::from_secs(duration_secs).saturating_sub(started.elapsed());
+            let output = device
+                .exec_with_timeout(script, budget.max(Duration::from_secs(5)))
+                .await;
+            Some(match output {
+                Ok(output) if output.success() => output.stdout,
+                Ok(output) => format!(
+                    "{}{}\n(exited with status {})",
+                    output.stdout, output.stderr, output.status
+                ),
+                Err(err) => err.to_string(),
+            })
+        }
+        None => None,
+    };
+    tokio::time::sleep(Duration::from_secs(duration_secs).saturating_sub(started.elapsed())).await;
+
+    // memcheck runs the app in its own process; heaptrack is a wrapper
+    // script with the app as a child. Stopping the app makes either write
+    // its report, which can take a while under memcheck.
+    let target = match params.tool {
+        LeakTool::Memcheck => pid.to_string(),
+        LeakTool::Heaptrack => format!("$(pidof -s {binary})"),
+    };
+    let wait = format!(
+        "kill -TERM {target} 2>/dev/null; i=0; \
+         while kill -0 {pid} 2>/dev/null && [ $i -lt 240 ]; do sleep 0.5; i=$((i+1)); done; \
+         kill -KILL {pid} 2>/dev/null"
+    );
+    let stop = match params.tool {
+        LeakTool::Memcheck => format!("{wait} || true"),
+        LeakTool::Heaptrack => format!(
+            "{wait}; f=$(ls -t {files}.*.zst {files}.*.gz 2>/dev/null | head -n 1); \
+             [ -n \"$f\" ] || {{ cat {files}.out >&2; exit 1; }}; \
+             heaptrack_print -f \"$f\" --print-leaks=1 --print-peaks=0 --print-allocators=0 \
+             --print-temporary=0 -n {top} >{files}.txt 2>&1; echo \"$f\""
+        ),
+    };
+    let output = device
+        .exec_with_timeout(&stop, Duration::from_secs(180))
+        .await?;
+    if !output.success() {
+        return Err(Error::RemoteCommand {
+            device: params.device,
+            status: output.status,
+            stderr: output.stderr.trim().to_string(),
+        });
+    }
+
+    let (report_file, data_file) = match params.tool {
+        LeakTool::Memcheck => {
+            let report_file = directory.join("memcheck.log");
+            device
+                .download(&format!("{remote}.log"), &report_file)
+                .await?;
+            (report_file, None)
+        }
+        LeakTool::Heaptrack => {
+            let report_file = directory.join("heaptrack.txt");
+            device
+                .download(&format!("{remote}.txt"), &report_file)
+                .await?;
+            let data = output.stdout.trim();
+            let data_file = directory.join(data.rsplit('/').next().unwrap_or("heaptrack.zst"));
+            device.download(data, &data_file).await?;
+            (report_file, Some(data_file))
+        }
+    };
+    let _ = device
+        .exec(&format!(
+            "rm -f {files}.log {files}.out {files}.txt {files}.*.gz {files}.*.zst || true"
+        ))
+        .await;
+
+    let report = std::fs::read_to_string(&report_file)?;
+    let (mut leaks, errors, by_kind, leaked_bytes) = match params.tool {
+        LeakTool::Memcheck => {
+            let (leaks, errors, by_kind) = parse_memcheck(&report);
+            let leaked = ["definitely lost", "indirectly lost"]
+                .iter()
+                .filter_map(|kind| by_kind.get(*kind))
+                .sum();
+            (leaks, errors, by_kind, leaked)
+        }
+        LeakTool::Heaptrack => {
+            let (leaks, total) = parse_heaptrack(&report);
+            (leaks, Vec::new(), BTreeMap::new(), total)
+        }
+    };
+    leaks.sort_by_key(|leak| std::cmp::Reverse(leak.bytes));
+    leaks.truncate(top);
+
+    Ok(LeakReport {
+        device: params.device,
+        app: params.app,
+        tool: params.tool,
+        pid,
+        duration_secs,
+        leaked_bytes,
+        by_kind,
+        leaks,
+        errors,
+        script_output,
+        directory,
+        report_file,
+        data_file,
+    })
+}
+
+#[tool_router(router = leaks_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Check an app for memory leaks: launches it on a device (typically the emulator) under valgrind memcheck or heaptrack, runs an optional interaction script, stops it after duration_secs and returns the leaked bytes, the largest leaks with their allocation stacks and, for memcheck, other memory errors. Links the full report.",
+        output_schema = cached_schema_for_type::<LeakReport>()
+    )]
+    pub async fn check_leaks(
+        &self,
+        Parameters(params): Parameters<LeakCheckParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        tool_result(check(self, params).await.and_then(|report| {
+            let mut links = vec![file_link(&report.report_file, Some("text/plain"))];
+            links.extend(
+                report
+                    .data_file
+                    .iter()
+                    .map(|data| file_link(data, Some("application/octet-stream"))),
+            );
+            let mut result = CallToolResult::structured(
+                serde_json::to_value(&report).map_err(std::io::Error::other)?,
+            );
+            result.content.extend(links);
+            Ok(result)
+        }))
+    }
+}
@@ -0,0 +1,207 @@
+//! Device UI language: reading the locale, the languages the OS ships, and
+//! switching between them for localization testing.
+
+use std::time::Duration;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{Device, DeviceParams, services, ssh};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Session-wide locale settings read by the user session at start.
+const LOCALE_CONF: &str = "/var/lib/environment/nemo/locale.conf";
+
+/// One `.conf` file per language offered in Settings.
+const LANGUAGES_DIR: &str = "/usr/share/jolla-supported-languages";
+
+const FILE_MARKER: &str = "--aurora-mcp-language--";
+
+/// Time for the home screen to come back after a restart.
+const RESTART_SETTLE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetLocaleParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Language or locale, e.g. `ru`, `en_US` or `ru_RU.utf8`. Short forms
+    /// are matched against the languages the device supports.
+    pub locale: String,
+    /// Restart the home screen so running UI picks up the language
+    /// (default true). Apps started afterwards use the new locale.
+    #[serde(default)]
+    pub restart_session: Option<bool>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LocaleReport {
+    pub device: String,
+    /// `LANG` from the session locale settings, e.g. `ru_RU.utf8`.
+    pub lang: Option<String>,
+    /// Other `LC_*` overrides, e.g. `LC_TIME=en_GB.utf8`.
+    pub overrides: Vec<String>,
+    pub supported: Vec<Language>,
+    /// `LANG` before this call changed it; empty if it was unset.
+    pub changed_from: Option<String>,
+    pub session_restarted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Language {
+    /// Native name, e.g. `Русский`.
+    pub name: String,
+    pub locale: String,
+}
+
+fn valid_locale(locale: &str) -> bool {
+    !locale.is_empty()
+        && locale.len() <= 32
+        && locale
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_.@-".contains(c))
+}
+
+/// Resolves `requested` to a supported locale: an exact match, or the
+/// first locale starting with it (`ru` matches `ru_RU.utf8`).
+fn resolve<'a>(requested: &str, supported: &'a [Language]) -> Option<&'a str> {
+    supported
+        .iter()
+        .find(|language| language.locale == requested)
+        .or_else(|| {
+            supported.iter().find(|language| {
+                language.locale.starts_with(requested)
+                    && language.locale[requested.len()..].starts_with(['_', '.', '@'])
+            })
+        })
+        .map(|language| language.locale.as_str())
+}
+
+async fn read(device: &Device) -> Result<LocaleReport> {
+    let command = ssh::sections(&[
+        &format!("cat {LOCALE_CONF} 2>/dev/null || true"),
+        &format!(
+            "for f in {LANGUAGES_DIR}/*.conf; do [ -r \"$f\" ] && echo \"{FILE_MARKER} $f\" && cat \"$f\"; done; true"
+        ),
+    ]);
+    let stdout = device.exec_checked(&command).await?;
+    let [conf, languages] = ssh::split_sections(&stdout)[..] else {
+        return Err(Error::Parse {
+            device: device.name().to_string(),
+            message: "missing output sections".to_string(),
+        });
+    };
+    let mut lang = None;
+    let mut overrides = Vec::new();
+    for line in conf.lines().map(str::trim) {
+        match line.split_once('=') {
+            Some(("LANG", value)) => lang = Some(value.trim_matches('"').to_string()),
+            Some((key, _)) if key.starts_with("LC_") => overrides.push(line.to_string()),
+            _ => {}
+        }
+    }
+    let mut supported: Vec<Language> = languages
+        .split(FILE_MARKER)
+        .filter_map(|chunk| {
+            let value = |key: &str| {
+                chunk.lines().find_map(|line| {
+                    line.trim()
+                        .strip_prefix(key)?
+                        .strip_prefix('=')
+                        .map(|v| v.trim().to_string())
+                })
+            };
+            Some(Language {
+                locale: value("LocaleCode")?,
+                name: value("Name").unwrap_or_default(),
+            })
+        })
+        .collect();
+    supported.sort_by(|a, b| a.locale.cmp(&b.locale));
+    Ok(LocaleReport {
+        device: device.name().to_string(),
+        lang,
+        overrides,
+        supported,
+        changed_from: None,
+        session_restarted: false,
+    })
+}
+
+#[tool_router(router = locale_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Report a device's UI language (LANG and LC_* overrides) and the languages it supports.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn device_locale(
+        &self,
+        Parameters(params): Parameters<DeviceParams>,
+    ) -> Result<Json<LocaleReport>> {
+        let device = self.devices().get(&params.device)?;
+        read(&device).await.map(Json)
+    }
+
+    #[tool(
+        description = "Change a device's UI language, e.g. locale=ru or locale=en_US, and restart the home screen so it takes effect. Use it to run the same UI checks under several languages."
+    )]
+    pub async fn device_set_locale(
+        &self,
+        Parameters(params): Parameters<SetLocaleParams>,
+    ) -> Result<Json<LocaleReport>> {
+        let device = self.devices().get(&params.device)?;
+        if !valid_locale(&params.locale) {
+            return Err(Error::InvalidArgument(format!(
+                "'{}' is not a locale name",
+                params.locale
+            )));
+        }
+        let before = read(&device).await?;
+        let locale = if before.supported.is_empty() {
+            params.locale.clone()
+        } else {
+            resolve(&params.locale, &before.supported)
+                .ok_or_else(|| {
+                    Error::InvalidArgument(format!(
+                        "device '{}' does not support '{}'; supported: {}",
+                        params.device,
+                        params.locale,
+                        before
+                            .supported
+                            .iter()
+                            .map(|language| language.locale.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+                })?
+                .to_string()
+        };
+
+        let command = format!(
+            "mkdir -p {dir} && {{ grep -v '^LANG=' {LOCALE_CONF} 2>/dev/null; echo LANG={locale}; }} > {LOCALE_CONF}.tmp \
+             && mv {LOCALE_CONF}.tmp {LOCALE_CONF}",
+            dir = LOCALE_CONF.rsplit_once('/').map_or("/", |(dir, _)| dir),
+        );
+        device
+            .exec_privileged(&command, ssh::DEFAULT_TIMEOUT)
+            .await?
+            .into_stdout(device.name())?;
+
+        let restart = params.restart_session.unwrap_or(true);
+        if restart {
+            let command = format!(
+                "{}systemctl --user set-environment LANG={locale} && systemctl --user restart lipstick.service",
+                services::user_env()
+            );
+            device.exec_checked(&command).await?;
+            tokio::time::sleep(RESTART_SETTLE).await;
+        }
+
+        let mut report = read(&device).await?;
+        report.changed_from = before.lang.or(Some(String::new()));
+        report.session_restarted = restart;
+        Ok(Json(report))
+    }
+}
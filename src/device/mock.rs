@@ -0,0 +1,1419 @@
+//! In-memory device simulator behind `--mock-devices`.
+//!
+//! A simulated device answers the shell commands the device tools send
+//! with canned or stateful output: installed packages, repositories,
+//! running apps, user services, files and the clipboard live in memory, so
+//! the tool suite can run in CI and demos without hardware. Commands it
+//! does not recognise fail with status 127.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+use super::{CommandOutput, png, ssh};
+use crate::error::{Error, Result};
+use crate::sdk::rpm::parse_rpm_name;
+use crate::util::unix_now;
+
+/// Device names used when `--mock-devices` runs without configured devices.
+pub const DEFAULT_DEVICES: &[&str] = &["mock-phone", "mock-emulator"];
+
+const DEMO_APP: &str = "ru.auroraos.demo";
+
+#[derive(Debug)]
+pub struct Simulator {
+    device: String,
+    state: Mutex<SimState>,
+}
+
+#[derive(Debug)]
+struct SimState {
+    /// Installed package name to version.
+    packages: BTreeMap<String, String>,
+    /// Repository name to URL and whether it is enabled.
+    repositories: BTreeMap<String, (String, bool)>,
+    /// Running application binary to PID.
+    apps: BTreeMap<String, u32>,
+    /// User unit name to whether it is active.
+    units: BTreeMap<String, bool>,
+    files: BTreeMap<String, Vec<u8>>,
+    clipboard: String,
+    timezone: String,
+    /// Seconds the simulated clock is ahead of the host.
+    clock_offset: f64,
+    next_pid: u32,
+    notifications: u32,
+    /// Path of the active ambience file.
+    ambience: String,
+    /// Android package to version code, inside the AppSupport container.
+    android_packages: BTreeMap<String, u64>,
+    /// Running Android package to PID.
+    android_running: BTreeMap<String, u32>,
+    /// The Android container is up.
+    android_runtime: bool,
+}
+
+impl Default for SimState {
+    fn default() -> Self {
+        let packages = [
+            ("sailfishsilica-qt5", "1.2.85-1"),
+            ("qt5-qtdeclarative", "5.6.3-1"),
+            ("connman", "1.32-1"),
+            ("ofono", "1.29-1"),
+            (DEMO_APP, "1.0.0-1"),
+        ];
+        let repositories = [
+            (
+                "adaptation0",
+                "https://repo.example.invalid/aurora/adaptation/",
+                true,
+            ),
+            ("aurora", "https://repo.example.invalid/aurora/os/", true),
+            (
+                "aurora-updates",
+                "https://repo.example.invalid/aurora/updates/",
+                false,
+            ),
+        ];
+        let mut files = BTreeMap::new();
+        files.insert(
+            format!("/home/defaultuser/.config/{DEMO_APP}/{DEMO_APP}.conf"),
+            b"[General]\ntheme=dark\nsyncInterval=15\n".to_vec(),
+        );
+        files.insert(
+            format!("/home/defaultuser/.local/share/{DEMO_APP}/{DEMO_APP}/notes.sqlite"),
+            vec![0; 96 * 1024],
+        );
+        files.insert(
+            format!("/home/defaultuser/.cache/{DEMO_APP}/{DEMO_APP}/qmlcache/main.qmlc"),
+            vec![0; 320 * 1024],
+        );
+        files.insert("/var/lib/rpm/Packages".to_string(), vec![0; 640 * 1024]);
+        files.insert(
+            "/etc/ssh/sshd_config".to_string(),
+            b"# Aurora OS sshd configuration\nPermitRootLogin no\nUsePAM yes\n".to_vec(),
+        );
+        files.insert(
+            "/home/defaultuser/.ssh/authorized_keys".to_string(),
+            b"ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIMockKeyMockKeyMockKeyMockKey developer@host\n"
+                .to_vec(),
+        );
+        // sshd on all addresses, a QML debugger on loopback and gdbserver on
+        // all IPv6 addresses.
+        files.insert(
+            "/proc/net/tcp".to_string(),
+            b"  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n\
+              0: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 1201\n\
+              1: 0100007F:2710 00000000:0000 0A 00000000:00000000 00:00000000 00000000 100000        0 4410\n"
+                .to_vec(),
+        );
+        files.insert(
+            "/proc/net/tcp6".to_string(),
+            b"  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n\
+              0: 00000000000000000000000000000000:0929 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000 100000        0 4420\n"
+                .to_vec(),
+        );
+        files.insert(
+            format!("/var/lib/systemd/coredump/core.{DEMO_APP}.100000.1f2e.1760000000.zst"),
+            b"mock core dump\n".to_vec(),
+        );
+        for (name, display_name, scheme) in [
+            ("sailfish", "Sailfish", "lighttext"),
+            ("aurora-light", "Aurora Light", "darktext"),
+        ] {
+            files.insert(
+                format!("/usr/share/ambience/{name}/{name}.ambience"),
+                format!("{{\"displayName\": \"{display_name}\", \"colorScheme\": \"{scheme}\"}}\n")
+                    .into_bytes(),
+            );
+        }
+        for (code, name, locale) in [
+            ("en", "English", "en_US.utf8"),
+            ("ru", "Русский", "ru_RU.utf8"),
+        ] {
+            files.insert(
+                format!("/usr/share/jolla-supported-languages/{code}.conf"),
+                format!("[{code}]\nName={name}\nLocaleCode={locale}\n").into_bytes(),
+            );
+        }
+        files.insert(
+            "/var/lib/environment/nemo/locale.conf".to_string(),
+            b"LANG=ru_RU.utf8\nLC_TIME=ru_RU.utf8\n".to_vec(),
+        );
+        files.insert(
+            "/home/defaultuser/Pictures/Screenshots/Screenshot_mock.png".to_string(),
+            synthetic_png(180, 360),
+        );
+        Self {
+            packages: packages
+                .iter()
+                .map(|(name, version)| (name.to_string(), version.to_string()))
+                .collect(),
+            repositories: repositories
+                .iter()
+                .map(|(name, url, enabled)| (name.to_string(), (url.to_string(), *enabled)))
+                .collect(),
+            apps: BTreeMap::new(),
+            units: [(format!("{DEMO_APP}.sync.service"), true)]
+                .into_iter()
+                .collect(),
+            files,
+            clipboard: String::new(),
+            timezone: "Europe/Moscow".to_string(),
+            clock_offset: 0.0,
+            next_pid: 4200,
+            notifications: 0,
+            ambience: "/usr/share/ambience/sailfish/sailfish.ambience".to_string(),
+            android_packages: [("org.example.android".to_string(), 42)]
+                .into_iter()
+                .collect(),
+            android_running: BTreeMap::new(),
+            android_runtime: true,
+        }
+    }
+}
+
+struct Reply {
+    status: i32,
+    stdout: String,
+    stderr: String,
+}
+
+fn ok(stdout: impl Into<String>) -> Reply {
+    Reply {
+        status: 0,
+        stdout: stdout.into(),
+        stderr: String::new(),
+    }
+}
+
+fn fail(status: i32, stderr: impl Into<String>) -> Reply {
+    Reply {
+        status,
+        stdout: String::new(),
+        stderr: stderr.into(),
+    }
+}
+
+impl Simulator {
+    pub fn new(device: &str) -> Self {
+        Self {
+            device: device.to_string(),
+            state: Mutex::new(SimState::default()),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, SimState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Runs a script built by the device tools, section by section.
+    pub fn exec(&self, user: &str, command: &str) -> CommandOutput {
+        let sections = ssh::script_sections(command);
+        let mut output = CommandOutput {
+            status: 0,
+            stdout: String::new(),
+            stderr: String::new(),
+        };
+        for (i, section) in sections.iter().enumerate() {
+            if i > 0 {
+                if !output.stdout.is_empty() && !output.stdout.ends_with('\n') {
+                    output.stdout.push('\n');
+                }
+                output.stdout.push_str(&ssh::section_marker_line());
+            }
+            let reply = self.run(user, strip_env(section));
+            output.status = reply.status;
+            output.stdout.push_str(&reply.stdout);
+            output.stderr.push_str(&reply.stderr);
+        }
+        output
+    }
+
+    pub fn download(&self, remote: &str, local: &Path) -> Result<u64> {
+        let data = match self.state().files.get(remote) {
+            Some(data) => data.clone(),
+            None if remote.ends_with(".png") => synthetic_png(180, 360),
+            None => {
+                return Err(Error::RemoteCommand {
+                    device: self.device.clone(),
+                    status: 1,
+                    stderr: format!("cat: {remote}: No such file or directory"),
+                });
+            }
+        };
+        std::fs::write(local, &data)?;
+        Ok(data.len() as u64)
+    }
+
+    pub fn upload(&self, local: &Path, remote: &str) -> Result<u64> {
+        let data = std::fs::read(local)?;
+        let size = data.len() as u64;
+        self.state().files.insert(remote.to_string(), data);
+        Ok(size)
+    }
+
+    fn run(&self, user: &str, c: &str) -> Reply {
+        if let Some(command) = c.strip_suffix(" || true") {
+            let reply = self.run(user, command);
+            return ok(reply.stdout);
+        }
+        let mut words = shell_words(c);
+        words.retain(|word| !is_redirect(word));
+        let word = |i: usize| words.get(i).map(String::as_str).unwrap_or("");
+        let mut state = self.state();
+
+        // Device information and processes.
+        if c == "true" || c.is_empty() {
+            return ok("");
+        }
+        if c == "cat /proc/loadavg" {
+            return ok("0.42 0.35 0.30 2/412 12345\n");
+        }
+        if c == "cat /proc/meminfo" {
+            return ok(
+                "MemTotal:        3884512 kB\nMemFree:          912344 kB\nMemAvailable:    1822104 kB\n",
+            );
+        }
+        if c.starts_with("ps -eo") {
+            let mut ps = String::from(
+                "    1     0 root      0.0  9120 systemd /usr/lib/systemd/systemd --system\n  \
+                 412     1 root      0.3 12044 connmand /usr/sbin/connmand -n\n  \
+                 420     1 defaultuser 2.1 98420 lipstick /usr/bin/lipstick -plugin evdevtouch\n  \
+                 433     1 radio     0.1  8120 ofonod /usr/sbin/ofonod -n\n",
+            );
+            for (app, pid) in &state.apps {
+                ps.push_str(&format!(
+                    "{pid:>5}   420 defaultuser 4.5 65536 {} /usr/bin/{app}\n",
+                    &app[..app.len().min(15)]
+                ));
+            }
+            return ok(ps);
+        }
+        if c.starts_with("uname") {
+            return ok("Linux mock 5.4.0-aurora #1 SMP PREEMPT aarch64 GNU/Linux\n");
+        }
+        if c == "id" || c == "id -u" {
+            return ok(match (c, user) {
+                ("id -u", "root") => "0\n".to_string(),
+                ("id -u", _) => "100000\n".to_string(),
+                (_, user) => format!("uid=100000({user}) gid=100000({user})\n"),
+            });
+        }
+        if c == "cat /etc/os-release" {
+            return ok(
+                "NAME=\"Aurora OS\"\nID=auroraos\nVERSION_ID=5.1.3.85\nPRETTY_NAME=\"Aurora OS 5.1.3 (mock)\"\n",
+            );
+        }
+        if c == "echo \"$HOME\"" {
+            return ok(format!("{}\n", home(user)));
+        }
+        if c == "command -v devel-su" {
+            return ok("/usr/bin/devel-su\n");
+        }
+        if word(0) == "echo" && !c.contains(['|', ';', '>']) {
+            return ok(format!("{}\n", words[1..].join(" ")));
+        }
+
+        // Packages and repositories.
+        if c == "ssu lr" {
+            let mut out = String::from("Enabled repositories (global):\n");
+            for (name, (url, _)) in state.repositories.iter().filter(|(_, (_, on))| *on) {
+                out.push_str(&format!(" - {name:<20} ... {url}\n"));
+            }
+            out.push_str("\nDisabled repositories (global, might be overridden by user config):\n");
+            for (name, (url, _)) in state.repositories.iter().filter(|(_, (_, on))| !*on) {
+                out.push_str(&format!(" - {name:<20} ... {url}\n"));
+            }
+            return ok(out);
+        }
+        if word(0) == "ssu" && word(1) == "ar" {
+            state
+                .repositories
+                .insert(word(2).to_string(), (word(3).to_string(), true));
+            return ok("");
+        }
+        if word(0) == "ssu" && word(1) == "rr" {
+            return match state.repositories.remove(word(2)) {
+                Some(_) => ok(""),
+                None => fail(1, format!("Repository {} not found\n", word(2))),
+            };
+        }
+        if word(0) == "pkcon" && word(2) == "refresh" {
+            return ok("Refreshing cache\nLoading cache\nFinished\n");
+        }
+        if (word(0) == "pkcon" && word(2) == "install-local")
+            || (word(0) == "rpm" && word(1) == "-U")
+        {
+            let path = words
+                .iter()
+                .find(|word| word.ends_with(".rpm"))
+                .cloned()
+                .unwrap_or_default();
+            if state.files.remove(&path).is_none() {
+                return fail(1, format!("File {path} not found\n"));
+            }
+            let (name, version) = rpm_name_version(&path);
+            state.packages.insert(name.clone(), version.clone());
+            return ok(format!(
+                "Installing\nInstalled {name}-{version}\nFinished\n"
+            ));
+        }
+        if word(0) == "rpm" && word(1) == "-e" {
+            return match state.packages.remove(word(2)) {
+                Some(_) => ok(""),
+                None => fail(1, format!("error: package {} is not installed\n", word(2))),
+            };
+        }
+        if word(0) == "pkcon" && (word(2) == "install" || word(2) == "remove") {
+            let mut out = String::new();
+            for package in &words[3..] {
+                if word(2) == "install" {
+                    state
+                        .packages
+                        .entry(package.clone())
+                        .or_insert_with(|| "1.0.0-1".to_string());
+                    out.push_str(&format!("Installed {package}\n"));
+                } else if state.packages.remove(package).is_some() {
+                    out.push_str(&format!("Removed {package}\n"));
+                } else {
+                    return fail(4, format!("Package not found: {package}\n"));
+                }
+            }
+            out.push_str("Finished\n");
+            return ok(out);
+        }
+        if c.starts_with("rpm -q") && !c.contains("--qf") {
+            let mut out = String::new();
+            for (name, version) in &state.packages {
+                out.push_str(&format!("{name}-{version}.aarch64\n"));
+            }
+            return ok(out);
+        }
+
+        // Android AppSupport.
+        if c.contains("apkd-install") {
+            let path = words
+                .iter()
+                .find(|word| word.ends_with(".apk"))
+                .cloned()
+                .unwrap_or_default();
+            if state.files.remove(&path).is_none() {
+                return fail(1, format!("{path}: No such file\n"));
+            }
+            let file = path.rsplit('/').next().unwrap_or(&path);
+            let package = file
+                .strip_prefix("aurora-mcp-")
+                .unwrap_or(file)
+                .trim_end_matches(".apk")
+                .to_string();
+            state.android_packages.insert(package, 1);
+            return ok("Success\n");
+        }
+        if c.starts_with("u=$(") && c.contains("appsupport.service") {
+            return ok("appsupport.service\n");
+        }
+        if c.starts_with("rpm -q --qf") && c.contains("appsupport") {
+            return ok("appsupport 1.4.0\n");
+        }
+        if c.starts_with("rpm -q --qf '%{VERSION}-%{RELEASE} %{ARCH}") {
+            return match state.packages.get(word(4)) {
+                Some(version) => ok(format!("{version} aarch64\n")),
+                None => fail(1, format!("package {} is not installed\n", word(4))),
+            };
+        }
+        if c.starts_with("[ -n \"$u\" ] && systemctl show") {
+            let (active, sub) = if state.android_runtime {
+                ("active", "running")
+            } else {
+                ("inactive", "dead")
+            };
+            return ok(format!(
+                "ActiveState={active}\nSubState={sub}\nUnitFileState=enabled\n"
+            ));
+        }
+        if c.starts_with("[ -n \"$u\" ] && systemctl -q is-active") {
+            return ok(if state.android_runtime { "11\n" } else { "" });
+        }
+        if word(0) == "systemctl" && word(2) == "appsupport.service" {
+            if user != "root" {
+                return fail(1, "Failed: Access denied\n");
+            }
+            state.android_runtime = word(1) != "stop";
+            if !state.android_runtime {
+                state.android_running.clear();
+            }
+            return ok("");
+        }
+        if c.contains("appsupport-attach.sh") {
+            if !state.android_runtime {
+                return fail(1, "lxc-attach: appsupport: Failed to get init pid\n");
+            }
+            let inner = words
+                .iter()
+                .skip_while(|word| *word != "-c")
+                .nth(1)
+                .cloned()
+                .unwrap_or_default();
+            return android(&mut state, &inner);
+        }
+
+        // Applications.
+        if c.contains("invoker") {
+            let Some(app) = usr_bin(&words) else {
+                return fail(127, "no binary given\n");
+            };
+            if !state.packages.contains_key(&app) {
+                return ok("");
+            }
+            let pid = match state.apps.get(&app) {
+                Some(pid) => *pid,
+                None => {
+                    state.next_pid += 1;
+                    let pid = state.next_pid;
+                    state.apps.insert(app, pid);
+                    pid
+                }
+            };
+            return ok(format!("{pid}\n"));
+        }
+        if word(0) == "pkill" {
+            if let Some(app) = usr_bin(&words) {
+                state.apps.remove(&app);
+            }
+            return ok("");
+        }
+
+        if c.contains("perf record") {
+            let app = c
+                .split_once("pidof -s ")
+                .and_then(|(_, rest)| shell_words(rest).into_iter().next())
+                .map(|binary| binary.trim_end_matches(')').to_string())
+                .and_then(|binary| binary.strip_prefix("/usr/bin/").map(str::to_string))
+                .unwrap_or_default();
+            let Some(pid) = state.apps.get(&app).copied() else {
+                return fail(3, format!("{app} is not running\n"));
+            };
+            let remote = format!("/tmp/aurora-mcp-perf-{app}");
+            state
+                .files
+                .insert(format!("{remote}.data"), b"PERFILE2".to_vec());
+            state
+                .files
+                .insert(format!("{remote}.txt"), perf_script(&app, pid).into_bytes());
+            return ok(format!("{pid}\n"));
+        }
+
+        if c.contains("setsid qmlscene") {
+            let Some((_, rest)) = c.split_once("cd ") else {
+                return fail(1, "no directory given\n");
+            };
+            let dir = shell_words(rest).into_iter().next().unwrap_or_default();
+            state.next_pid += 1;
+            let pid = state.next_pid;
+            state.apps.insert(format!("qmlscene-{pid}"), pid);
+            let log = qml_errors(&state.files, &dir);
+            state
+                .files
+                .insert(format!("{dir}/.preview.log"), log.into_bytes());
+            return ok(format!("{pid}\n"));
+        }
+        if word(0) == "kill" && !c.contains([';', '|', '&']) {
+            let (check, pid) = match word(1) {
+                "-0" => (true, word(2)),
+                pid => (false, pid),
+            };
+            let pid: u32 = pid.parse().unwrap_or_default();
+            let Some(app) = state
+                .apps
+                .iter()
+                .find(|(_, running)| **running == pid)
+                .map(|(app, _)| app.clone())
+            else {
+                return fail(1, format!("kill: ({pid}) - No such process\n"));
+            };
+            if !check {
+                state.apps.remove(&app);
+            }
+            return ok("");
+        }
+        if word(0) == "mkdir" && word(1) == "-p" && !c.contains([';', '|', '&']) {
+            return ok("");
+        }
+        if c.contains("valgrind --tool=memcheck") {
+            let Some(app) = usr_bin(&words) else {
+                return fail(127, "no binary given\n");
+            };
+            if !state.packages.contains_key(&app) {
+                return fail(
+                    5,
+                    format!("valgrind: /usr/bin/{app}: No such file or directory\n"),
+                );
+            }
+            state.next_pid += 1;
+            let pid = state.next_pid;
+            state.files.insert(
+                format!("/tmp/aurora-mcp-leaks-{app}.log"),
+                memcheck_log(&app, pid).into_bytes(),
+            );
+            return ok(format!("{pid}\n"));
+        }
+
+        // Logs and crashes.
+        if c.starts_with("journalctl --no-pager") {
+            let pattern = c
+                .split_once("grep -F -- ")
+                .map(|(_, rest)| shell_words(rest).into_iter().next().unwrap_or_default());
+            let lines = journal(&state)
+                .into_iter()
+                .filter(|line| pattern.as_ref().is_none_or(|p| line.contains(p.as_str())))
+                .collect::<Vec<_>>();
+            return ok(lines.join("\n") + "\n");
+        }
+        if c.starts_with("for f in") && c.contains("stat -c") {
+            let app = c
+                .split_once("core-dumps/*")
+                .and_then(|(_, rest)| rest.split('*').next())
+                .map(|app| app.trim_matches('\''))
+                .unwrap_or("");
+            let mut out = String::new();
+            for (path, data) in &state.files {
+                let in_core_dir = path.starts_with("/var/cache/core-dumps/")
+                    || path.starts_with("/var/lib/systemd/coredump/");
+                if in_core_dir && !app.is_empty() && path.contains(app) {
+                    out.push_str(&format!("{} 1760000000 {path}\n", data.len()));
+                }
+            }
+            return ok(out);
+        }
+
+        // Network.
+        if c == "ip -o addr show" {
+            return ok("1: lo    inet 127.0.0.1/8 scope host lo\n\
+                       2: rndis0    inet 192.168.2.15/24 brd 192.168.2.255 scope global rndis0\n\
+                       3: wlan0    inet 192.168.1.23/24 brd 192.168.1.255 scope global wlan0\n");
+        }
+        if c == "ip route show default" {
+            return ok("default via 192.168.1.1 dev wlan0 \n");
+        }
+        if c == "cat /etc/resolv.conf" {
+            return ok("nameserver 192.168.1.1\n");
+        }
+        if word(0) == "ping" {
+            let host = words
+                .iter()
+                .rev()
+                .find(|w| *w != "2>&1")
+                .cloned()
+                .unwrap_or_default();
+            return ok(format!(
+                "PING {host} (203.0.113.10): 56 data bytes\n\n--- {host} ping statistics ---\n\
+                 3 packets transmitted, 3 packets received, 0% packet loss\n\
+                 round-trip min/avg/max = 18.1/21.4/25.0 ms\n"
+            ));
+        }
+        if word(0) == "curl" {
+            return ok("200 0.084");
+        }
+
+        // D-Bus.
+        if c.contains("org.freedesktop.Notifications.Notify") {
+            state.notifications += 1;
+            return ok(format!("gdbus\n(uint32 {},)\n", state.notifications));
+        }
+        if c.contains("com.jolla.ambienced.setAmbience") {
+            let file = words
+                .iter()
+                .find_map(|word| word.strip_prefix("string:file://"))
+                .unwrap_or_default();
+            if !state.files.contains_key(file) {
+                return fail(
+                    1,
+                    "Error org.freedesktop.DBus.Error.InvalidArgs: no such ambience\n",
+                );
+            }
+            state.ambience = file.to_string();
+            return ok(
+                "method return time=1760000000.0 sender=:1.7 -> destination=:1.99 serial=9 reply_serial=2\n",
+            );
+        }
+        if c.contains("org.nemomobile.lipstick.saveScreenshot") {
+            let Some(path) = words.iter().find_map(|word| word.strip_prefix("string:")) else {
+                return fail(1, "Error org.freedesktop.DBus.Error.InvalidArgs: no path\n");
+            };
+            state
+                .files
+                .insert(path.to_string(), synthetic_png(180, 360));
+            return ok(
+                "method return time=1760000000.0 sender=:1.3 -> destination=:1.99 serial=11 reply_serial=2\n   boolean true\n",
+            );
+        }
+        if word(0) == "dbus-send" {
+            return dbus_reply(c);
+        }
+
+        // Clipboard.
+        if c.contains("wl-paste") {
+            return ok(format!("wl-clipboard\n{}", state.clipboard));
+        }
+        if c.contains("wl-copy") {
+            let text = c
+                .split_once("printf %s ")
+                .and_then(|(_, rest)| shell_words(rest).into_iter().next())
+                .unwrap_or_default();
+            state.clipboard = text;
+            return ok("wl-clipboard\n");
+        }
+
+        // systemd user units.
+        if c.starts_with("systemctl --user list-units") {
+            let mut out = String::new();
+            for (unit, active) in &state.units {
+                if *active || c.contains("--all") {
+                    let (active, sub) = if *active {
+                        ("active", "running")
+                    } else {
+                        ("inactive", "dead")
+                    };
+                    out.push_str(&format!(
+                        "{unit} loaded {active} {sub} Mock service {unit}\n"
+                    ));
+                }
+            }
+            return ok(out);
+        }
+        if c.starts_with("systemctl --user show") {
+            let unit = word(3);
+            let Some(active) = state.units.get(unit).copied() else {
+                return ok(format!(
+                    "Id={unit}\nLoadState=not-found\nActiveState=inactive\nSubState=dead\nMainPID=0\n"
+                ));
+            };
+            let (state_name, sub, pid) = if active {
+                ("active", "running", 4100)
+            } else {
+                ("inactive", "dead", 0)
+            };
+            return ok(format!(
+                "Id={unit}\nDescription=Mock service {unit}\nLoadState=loaded\nActiveState={state_name}\n\
+                 SubState={sub}\nUnitFileState=enabled\nMainPID={pid}\nExecMainStatus=0\nNRestarts=0\n\
+                 ActiveEnterTimestamp=Thu 2026-10-15 09:00:00 MSK\n"
+            ));
+        }
+        if c.starts_with("systemctl --user set-environment") {
+            return ok("");
+        }
+        if word(0) == "systemctl" && word(1) == "--user" {
+            let unit = word(3);
+            let Some(active) = state.units.get_mut(unit) else {
+                return fail(
+                    5,
+                    format!("Failed to {} {unit}: Unit {unit} not found.\n", word(2)),
+                );
+            };
+            *active = word(2) != "stop";
+            return ok("");
+        }
+        if c.starts_with("journalctl --user") {
+            let unit = word(3);
+            return ok(format!(
+                "2026-10-15T09:00:00+0300 mock {unit}[4100]: Started\n\
+                 2026-10-15T09:15:00+0300 mock {unit}[4100]: Sync finished, 12 items\n"
+            ));
+        }
+
+        // Battery and sensors.
+        if c.contains("/sys/class/power_supply") {
+            return ok(
+                "Battery capacity 76\nBattery status Discharging\nBattery temp 312\n\
+                       Battery voltage_now 3912000\nBattery current_now -412000\nBattery health Good\n\
+                       USB online 0\n",
+            );
+        }
+        if c.contains("/sys/class/thermal") {
+            return ok("cpu-thermal 41200\nbattery 31200\n");
+        }
+
+        // Wi-Fi.
+        if c == "connmanctl technologies" {
+            return ok(
+                "/net/connman/technology/wifi\n  Name = WiFi\n  Type = wifi\n  Powered = True\n  Connected = True\n",
+            );
+        }
+        if c.starts_with("connmanctl scan") {
+            return ok("");
+        }
+        if c == "connmanctl services" {
+            return ok(
+                "*AO MockLab              wifi_00aa_4d6f636b4c6162_managed_psk\n    \
+                       GuestNet             wifi_00aa_47756573744e6574_managed_none\n",
+            );
+        }
+        if c.contains("connmanctl connect") || c.starts_with("connmanctl services ") {
+            let service = c
+                .split_once("connmanctl services ")
+                .map(|(_, rest)| rest.trim().to_string())
+                .unwrap_or_default();
+            return ok(format!(
+                "/net/connman/service/{service}\n  State = online\n  \
+                 IPv4 = [ Method=dhcp, Address=192.168.1.23, Netmask=255.255.255.0, Gateway=192.168.1.1 ]\n"
+            ));
+        }
+        if c.contains("/var/lib/connman/") {
+            return ok("");
+        }
+
+        // Clock.
+        if c == "date +%s.%N" {
+            return ok(format!(
+                "{:.9}\n",
+                unix_now().as_secs_f64() + state.clock_offset
+            ));
+        }
+        if c == "date +%z" {
+            return ok("+0300\n");
+        }
+        if c.starts_with("timedatectl show") {
+            return ok(format!(
+                "Timezone={}\nNTPSynchronized=yes\n",
+                state.timezone
+            ));
+        }
+        if c.starts_with("timedatectl set-timezone") {
+            state.timezone = word(2).to_string();
+            return ok("");
+        }
+        if c.starts_with("date -u -s @") {
+            if user != "root" {
+                return fail(1, "date: cannot set date: Operation not permitted\n");
+            }
+            let target: f64 = word(3)
+                .trim_start_matches('@')
+                .parse()
+                .unwrap_or_else(|_| unix_now().as_secs_f64());
+            state.clock_offset = target - unix_now().as_secs_f64();
+            return ok("");
+        }
+
+        // Storage.
+        if c.starts_with("df -P -k") {
+            return ok(
+                "Filesystem 1024-blocks Used Available Capacity Mounted on\n\
+                 /dev/mapper/root 2539312 1803912 735400 72% /\n\
+                 /dev/mapper/home 10190136 9476824 713312 94% /home\n\
+                 tmpfs 1942256 5120 1937136 1% /tmp\n",
+            );
+        }
+        if let Some(at) = words.iter().position(|word| word == "du") {
+            let base = match word(0) {
+                "cd" => format!("{}/", word(1).replace("$HOME", home(user))),
+                _ => String::new(),
+            };
+            let mut out = String::new();
+            for arg in words[at + 1..]
+                .iter()
+                .filter(|arg| !arg.starts_with('-') && *arg != "true")
+            {
+                let arg = arg.trim_end_matches(';').replace("$HOME", home(user));
+                let paths = match arg.strip_suffix("/*") {
+                    Some(dir) => children(&state.files, &format!("{base}{dir}"))
+                        .into_iter()
+                        .map(|(name, _)| format!("{dir}/{name}"))
+                        .collect(),
+                    None => vec![arg],
+                };
+                for path in paths {
+                    let prefix = format!("{base}{path}/");
+                    let bytes: usize = state
+                        .files
+                        .iter()
+                        .filter(|(file, _)| file.starts_with(&prefix))
+                        .map(|(_, data)| data.len())
+                        .sum();
+                    if bytes > 0 {
+                        out.push_str(&format!("{}\t{path}\n", bytes.div_ceil(1024)));
+                    }
+                }
+            }
+            return ok(out);
+        }
+        if word(0) == "cd" && word(2) == "&&" && word(3) == "rm" {
+            let dir = format!("{}/", word(1).replace("$HOME", home(user)));
+            let names: Vec<&str> = words[6..]
+                .iter()
+                .map(|name| name.trim_end_matches(';'))
+                .filter(|name| *name != "true")
+                .collect();
+            state.files.retain(|path, _| {
+                let Some(rest) = path.strip_prefix(&dir) else {
+                    return true;
+                };
+                let name = rest.split('/').next().unwrap_or(rest);
+                !names
+                    .iter()
+                    .any(|pattern| *pattern == name || *pattern == "*")
+            });
+            return ok("");
+        }
+
+        // Directory dumps: `for f in GLOB; do ... echo "MARKER $f" && cat "$f"; done`.
+        if c.starts_with("for f in ") && c.contains("&& cat \"$f\"") {
+            let marker = c
+                .split_once("echo \"")
+                .and_then(|(_, rest)| rest.split_once(" $f"))
+                .map(|(marker, _)| marker)
+                .unwrap_or_default();
+            let mut out = String::new();
+            for (path, data) in &state.files {
+                if glob_match(word(3).trim_end_matches(';'), path) {
+                    out.push_str(&format!("{marker} {path}\n"));
+                    out.push_str(&String::from_utf8_lossy(data));
+                }
+            }
+            return ok(out);
+        }
+        if c.contains("/var/lib/environment/nemo/locale.conf.tmp") {
+            if user != "root" {
+                return fail(1, "sh: can't create locale.conf.tmp: Permission denied\n");
+            }
+            let path = "/var/lib/environment/nemo/locale.conf";
+            let lang = words
+                .iter()
+                .find_map(|word| word.strip_prefix("LANG="))
+                .unwrap_or_default()
+                .trim_end_matches(';');
+            let mut conf: String = state
+                .files
+                .get(path)
+                .map(|data| String::from_utf8_lossy(data).into_owned())
+                .unwrap_or_default()
+                .lines()
+                .filter(|line| !line.starts_with("LANG="))
+                .map(|line| format!("{line}\n"))
+                .collect();
+            conf.push_str(&format!("LANG={lang}\n"));
+            state.files.insert(path.to_string(), conf.into_bytes());
+            return ok("");
+        }
+        if c.starts_with("dconf read /desktop/jolla/theme/active_ambience") {
+            return ok(format!("'file://{}'\n", state.ambience));
+        }
+
+        // Files.
+        if word(0) == "cat" && !c.contains(['|', '<', ';']) && !c.contains(" >") {
+            let mut reply = ok("");
+            for path in words[1..].iter().filter(|word| *word != "--") {
+                let path = path.replace("$HOME", home(user));
+                match state.files.get(&path) {
+                    Some(data) => reply.stdout.push_str(&String::from_utf8_lossy(data)),
+                    None => {
+                        reply.status = 1;
+                        reply
+                            .stderr
+                            .push_str(&format!("cat: {path}: No such file or directory\n"));
+                    }
+                }
+            }
+            return reply;
+        }
+        if word(0) == "ls" {
+            let dir = words
+                .iter()
+                .skip(1)
+                .find(|w| !w.starts_with('-'))
+                .cloned()
+                .unwrap_or_default();
+            let names: Vec<String> = children(&state.files, &dir)
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
+            return ok(names.join("\n") + "\n");
+        }
+        if word(0) == "readlink" {
+            return ok(format!("{}\n", word(3)));
+        }
+        if word(0) == "stat" && word(1) == "-L" {
+            let path = word(5);
+            let prefix = format!("{}/", path.trim_end_matches('/'));
+            return ok(match state.files.get(path) {
+                Some(data) => format!("regular file|{}\n", data.len()),
+                None if state.files.keys().any(|p| p.starts_with(&prefix)) => {
+                    "directory|4096\n".to_string()
+                }
+                None => String::new(),
+            });
+        }
+        if word(0) == "cd" && c.contains("stat -c '%F|%s|%Y|%n'") {
+            let mut out = String::new();
+            for (name, size) in children(&state.files, word(1)) {
+                match size {
+                    Some(size) => out.push_str(&format!("regular file|{size}|1760000000|{name}\n")),
+                    None => out.push_str(&format!("directory|4096|1760000000|{name}\n")),
+                }
+            }
+            return ok(out);
+        }
+        if word(0) == "head" && word(1) == "-c" && word(6) == "base64" {
+            let limit = word(2).parse().unwrap_or(usize::MAX);
+            return match state.files.get(word(4)) {
+                Some(data) => ok(BASE64.encode(&data[..data.len().min(limit)]) + "\n"),
+                None => fail(1, format!("head: {}: No such file or directory\n", word(4))),
+            };
+        }
+        if word(0) == "tail" && word(1) == "-c" && word(6) == "head" && word(10) == "base64" {
+            let start = word(2).trim_start_matches('+').parse().unwrap_or(1usize);
+            let limit = word(8).parse().unwrap_or(usize::MAX);
+            return match state.files.get(word(4)) {
+                Some(data) => {
+                    let start = start.saturating_sub(1).min(data.len());
+                    let end = data.len().min(start.saturating_add(limit));
+                    ok(BASE64.encode(&data[start..end]) + "\n")
+                }
+                None => fail(1, format!("tail: {}: No such file or directory\n", word(4))),
+            };
+        }
+
+        fail(
+            127,
+            format!(
+                "{}: mock device does not simulate this command: {c}\n",
+                self.device
+            ),
+        )
+    }
+}
+
+/// `section` without leading `export ...;` and `umask ...;` commands that
+/// set up the environment.
+fn strip_env(section: &str) -> &str {
+    let mut section = section.trim();
+    while section.starts_with("export ") || section.starts_with("umask ") {
+        match section.split_once("; ") {
+            Some((_, rest)) => section = rest.trim_start(),
+            None => break,
+        }
+    }
+    section
+}
+
+/// Commands run in the simulated Android container's shell.
+fn android(state: &mut SimState, command: &str) -> Reply {
+    let mut out = String::new();
+    for part in command.split(';') {
+        let words = shell_words(part);
+        let word = |i: usize| words.get(i).map(String::as_str).unwrap_or("");
+        match (word(0), word(1)) {
+            ("pm", "list") => {
+                for (package, version) in &state.android_packages {
+                    out.push_str(&format!(
+                        "package:/data/app/{package}-1/base.apk={package} versionCode:{version}\n"
+                    ));
+                }
+            }
+            ("monkey", _) => {
+                let package = word(2).to_string();
+                if !state.android_packages.contains_key(&package) {
+                    return fail(
+                        1,
+                        format!("** No activities found to run, monkey aborted.\n{out}"),
+                    );
+                }
+                if !state.android_running.contains_key(&package) {
+                    state.next_pid += 1;
+                    let pid = state.next_pid;
+                    state.android_running.insert(package, pid);
+                }
+            }
+            ("am", "force-stop") => {
+                state.android_running.remove(word(2));
+            }
+            ("pidof", package) => {
+                if let Some(pid) = state.android_running.get(package) {
+                    out.push_str(&format!("{pid}\n"));
+                }
+            }
+            _ => {}
+        }
+    }
+    ok(out)
+}
+
+/// Whether `path` matches `pattern`, where `*` matches within one path
+/// component.
+fn glob_match(pattern: &str, path: &str) -> bool {
+    let (pattern_parts, path_parts): (Vec<&str>, Vec<&str>) =
+        (pattern.split('/').collect(), path.split('/').collect());
+    pattern_parts.len() == path_parts.len()
+        && pattern_parts
+            .iter()
+            .zip(&path_parts)
+            .all(|(pattern, part)| {
+                let mut pieces = pattern.split('*');
+                let first = pieces.next().unwrap_or("");
+                let Some(mut rest) = part.strip_prefix(first) else {
+                    return false;
+                };
+                let pieces: Vec<&str> = pieces.collect();
+                for (i, piece) in pieces.iter().enumerate() {
+                    if i == pieces.len() - 1 {
+                        return rest.ends_with(piece);
+                    }
+                    match rest.find(piece) {
+                        Some(at) => rest = &rest[at + piece.len()..],
+                        None => return false,
+                    }
+                }
+                rest.is_empty()
+            })
+}
+
+/// Home directory of `user` on the simulated device.
+fn home(user: &str) -> &'static str {
+    match user {
+        "root" => "/root",
+        _ => "/home/defaultuser",
+    }
+}
+
+/// Direct children of `dir`, with their size for files and `None` for
+/// directories.
+fn children(files: &BTreeMap<String, Vec<u8>>, dir: &str) -> Vec<(String, Option<usize>)> {
+    let prefix = format!("{}/", dir.trim_end_matches('/'));
+    let mut children: Vec<(String, Option<usize>)> = Vec::new();
+    for (path, data) in files {
+        let Some(rest) = path.strip_prefix(&prefix) else {
+            continue;
+        };
+        let child = match rest.split_once('/') {
+            Some((name, _)) => (name.to_string(), None),
+            None => (rest.to_string(), Some(data.len())),
+        };
+        if !children.contains(&child) {
+            children.push(child);
+        }
+    }
+    children
+}
+
+/// Canned system journal, with the simulated apps' lines.
+fn journal(state: &SimState) -> Vec<String> {
+    let mut lines = vec![
+        "2026-10-15T09:00:01+0300 mock systemd[1]: Started Home screen.".to_string(),
+        "2026-10-15T09:00:05+0300 mock connmand[412]: wlan0 {add} address 192.168.1.23/24"
+            .to_string(),
+        format!(
+            "2026-10-15T09:10:11+0300 mock {DEMO_APP}[4101]: [W] unknown:0 - QML Image: Cannot open: file:///usr/share/{DEMO_APP}/missing.png"
+        ),
+        format!(
+            "2026-10-15T09:10:12+0300 mock systemd-coredump[4190]: Process 4101 ({DEMO_APP}) of user 100000 dumped core."
+        ),
+    ];
+    for (app, pid) in &state.apps {
+        lines.push(format!(
+            "2026-10-15T09:20:00+0300 mock {app}[{pid}]: [D] main:42 - started"
+        ));
+    }
+    lines
+}
+
+fn dbus_reply(c: &str) -> Reply {
+    let header = "method return time=1760000000.0 sender=:1.3 -> destination=:1.99 serial=7 reply_serial=2\n";
+    let strings = |items: &[&str]| {
+        let body: String = items
+            .iter()
+            .map(|item| format!("      string \"{item}\"\n"))
+            .collect();
+        format!("{header}   array [\n{body}   ]\n")
+    };
+    let dict = |entries: &[(&str, &str)]| {
+        let body: String = entries
+            .iter()
+            .map(|(key, value)| {
+                format!(
+                    "      dict entry(\n         string \"{key}\"\n         variant             {value}\n      )\n"
+                )
+            })
+            .collect();
+        format!("{header}   array [\n{body}   ]\n")
+    };
+    let reply = if c.contains("ListActivatableNames") {
+        strings(&[
+            "org.freedesktop.DBus",
+            "org.freedesktop.Notifications",
+            "com.jolla.lipstick",
+        ])
+    } else if c.contains("ListNames") {
+        strings(&[
+            "org.freedesktop.DBus",
+            ":1.3",
+            "org.freedesktop.Notifications",
+            "com.jolla.lipstick",
+            "net.connman",
+            "org.ofono",
+        ])
+    } else if c.contains("Introspect") {
+        format!(
+            "{header}   string \"<!DOCTYPE node PUBLIC \"-//freedesktop//DTD D-BUS Object Introspection 1.0//EN\"\n\
+             \"http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd\">\n\
+             <node>\n  <interface name=\"org.freedesktop.DBus.Introspectable\">\n    \
+             <method name=\"Introspect\"><arg name=\"xml\" type=\"s\" direction=\"out\"/></method>\n  </interface>\n  \
+             <interface name=\"org.freedesktop.DBus.Properties\">\n    \
+             <method name=\"GetAll\"><arg name=\"interface\" type=\"s\" direction=\"in\"/><arg name=\"props\" type=\"a{{sv}}\" direction=\"out\"/></method>\n  </interface>\n  \
+             <node name=\"mock\"/>\n</node>\n\"\n"
+        )
+    } else if c.contains("org.ofono.Manager.GetModems") {
+        format!(
+            "{header}   array [\n      struct {{\n         object path \"/ril_0\"\n         array [\n            \
+             dict entry(\n               string \"Powered\"\n               variant                   boolean true\n            )\n         \
+             ]\n      }}\n   ]\n"
+        )
+    } else if c.contains("org.ofono.Modem.GetProperties") {
+        dict(&[
+            ("Powered", "boolean true"),
+            ("Online", "boolean true"),
+            ("Manufacturer", "string \"Mock\""),
+            ("Model", "string \"Mock Modem\""),
+        ])
+    } else if c.contains("org.ofono.SimManager.GetProperties") {
+        dict(&[
+            ("Present", "boolean true"),
+            ("PinRequired", "string \"none\""),
+            ("ServiceProviderName", "string \"Mock Mobile\""),
+            ("MobileCountryCode", "string \"250\""),
+            ("MobileNetworkCode", "string \"99\""),
+        ])
+    } else if c.contains("org.ofono.NetworkRegistration.GetProperties") {
+        dict(&[
+            ("Status", "string \"registered\""),
+            ("Name", "string \"Mock Mobile\""),
+            ("Technology", "string \"lte\""),
+            ("Strength", "byte 72"),
+            ("MobileCountryCode", "string \"250\""),
+            ("MobileNetworkCode", "string \"99\""),
+        ])
+    } else if c.contains("org.ofono.ConnectionManager.GetProperties") {
+        dict(&[
+            ("Attached", "boolean true"),
+            ("Powered", "boolean true"),
+            ("RoamingAllowed", "boolean false"),
+            ("Bearer", "string \"lte\""),
+        ])
+    } else if c.contains("org.ofono.ConnectionManager.GetContexts") {
+        format!(
+            "{header}   array [\n      struct {{\n         object path \"/ril_0/context1\"\n         array [\n            \
+             dict entry(\n               string \"Active\"\n               variant                   boolean true\n            )\n            \
+             dict entry(\n               string \"AccessPointName\"\n               variant                   string \"internet\"\n            )\n            \
+             dict entry(\n               string \"Settings\"\n               variant                   array [\n                  \
+             dict entry(\n                     string \"Interface\"\n                     variant                         string \"rmnet_data0\"\n                  )\n                  \
+             dict entry(\n                     string \"Address\"\n                     variant                         string \"10.64.12.7\"\n                  )\n               \
+             ]\n            )\n         ]\n      }}\n   ]\n"
+        )
+    } else if c.contains("GetProperties") || c.contains("Properties.GetAll") {
+        dict(&[("State", "string \"online\"")])
+    } else if c.contains("Peer.Ping") {
+        header.to_string()
+    } else {
+        return fail(
+            1,
+            "Error org.freedesktop.DBus.Error.UnknownMethod: not simulated by the mock device\n",
+        );
+    };
+    ok(reply)
+}
+
+/// Application name of the first `/usr/bin/<app>` word.
+fn usr_bin(words: &[String]) -> Option<String> {
+    words
+        .iter()
+        .find_map(|word| word.strip_prefix("/usr/bin/"))
+        .map(str::to_string)
+}
+
+/// `perf script` output with a few samples of a QML app's event loop.
+fn perf_script(app: &str, pid: u32) -> String {
+    let sample = |time: u32, frames: &[&str]| {
+        let mut out = format!("{app} {pid} 1234.{time:06}: 10101010 cpu-clock:u:\n");
+        for (i, frame) in frames.iter().enumerate() {
+            out.push_str(&format!("\t    7f8a{i:06x} {frame}\n"));
+        }
+        out.push('\n');
+        out
+    };
+    let mut out = String::new();
+    for time in 0..6 {
+        out.push_str(&sample(
+            time,
+            &[
+                "QQuickItem::polish()+0x1c (/usr/lib64/libQt5Quick.so.5)",
+                "QQuickWindowPrivate::polishItems()+0x88 (/usr/lib64/libQt5Quick.so.5)",
+                "QCoreApplication::exec()+0x98 (/usr/lib64/libQt5Core.so.5)",
+                &format!("main+0x40 (/usr/bin/{app})"),
+            ],
+        ));
+    }
+    for time in 6..9 {
+        out.push_str(&sample(
+            time,
+            &[
+                "[unknown] (/usr/lib64/libQt5Qml.so.5)",
+                "QQmlJavaScriptExpression::evaluate(QV4::CallData*, bool*)+0x120 (/usr/lib64/libQt5Qml.so.5)",
+                "QCoreApplication::exec()+0x98 (/usr/lib64/libQt5Core.so.5)",
+                &format!("main+0x40 (/usr/bin/{app})"),
+            ],
+        ));
+    }
+    out.push_str(&sample(9, &["__libc_poll+0x30 (/usr/lib64/libc-2.30.so)"]));
+    out
+}
+
+/// qmlscene's complaints about the QML files below `dir`: the mock only
+/// checks that braces balance.
+fn qml_errors(files: &BTreeMap<String, Vec<u8>>, dir: &str) -> String {
+    let prefix = format!("{dir}/");
+    let mut out = String::new();
+    for (path, data) in files.range(prefix.clone()..) {
+        if !path.starts_with(&prefix) {
+            break;
+        }
+        if !path.ends_with(".qml") {
+            continue;
+        }
+        let text = String::from_utf8_lossy(data);
+        let opened = text.matches('{').count();
+        let closed = text.matches('}').count();
+        if opened != closed {
+            let line = text.lines().count();
+            out.push_str(&format!("file://{path}:{line}:1: Expected token `}}'\n"));
+        }
+    }
+    out
+}
+
+/// A memcheck log of an app that leaks a model row and reads past a buffer.
+fn memcheck_log(app: &str, pid: u32) -> String {
+    [
+        "Memcheck, a memory error detector".to_string(),
+        format!("Command: /usr/bin/{app}"),
+        String::new(),
+        "Invalid read of size 4".to_string(),
+        "   at 0x10A2F3: Model::data(QModelIndex const&, int) const (model.cpp:58)".to_string(),
+        "   by 0x5A1B2C0: QQmlDelegateModelItem::resolveModelData() (in /usr/lib64/libQt5QmlModels.so.5)".to_string(),
+        String::new(),
+        "Process terminating with default action of signal 15 (SIGTERM)".to_string(),
+        String::new(),
+        "HEAP SUMMARY:".to_string(),
+        "    in use at exit: 412,336 bytes in 5,120 blocks".to_string(),
+        String::new(),
+        "48 bytes in 2 blocks are possibly lost in loss record 310 of 2,001".to_string(),
+        "   at 0x4C2AB80: malloc (in /usr/lib64/valgrind/vgpreload_memcheck-amd64-linux.so)".to_string(),
+        "   by 0x5F00A10: QArrayData::allocate(unsigned long, unsigned long, unsigned long, QFlags<QArrayData::AllocationOption>) (in /usr/lib64/libQt5Core.so.5)".to_string(),
+        String::new(),
+        "1,920 (320 direct, 1,600 indirect) bytes in 10 blocks are definitely lost in loss record 1,990 of 2,001".to_string(),
+        "   at 0x4C2C1A0: operator new(unsigned long) (in /usr/lib64/valgrind/vgpreload_memcheck-amd64-linux.so)".to_string(),
+        "   by 0x10B112: Model::addRow(QString const&) (model.cpp:31)".to_string(),
+        "   by 0x10B4E0: Model::load() (model.cpp:17)".to_string(),
+        "   by 0x10A010: main (main.cpp:12)".to_string(),
+        String::new(),
+        "LEAK SUMMARY:".to_string(),
+        "   definitely lost: 320 bytes in 10 blocks".to_string(),
+        "   indirectly lost: 1,600 bytes in 20 blocks".to_string(),
+        "     possibly lost: 48 bytes in 2 blocks".to_string(),
+        "   still reachable: 410,368 bytes in 5,088 blocks".to_string(),
+        String::new(),
+        "ERROR SUMMARY: 3 errors from 3 contexts (suppressed: 0 from 0)".to_string(),
+    ]
+    .iter()
+    .map(|line| format!("=={pid}== {line}\n"))
+    .collect()
+}
+
+/// Package name and version-release from an RPM file name such as
+/// `/tmp/aurora-mcp-ru.example.app-1.2.0-1.aarch64.rpm`.
+fn rpm_name_version(path: &str) -> (String, String) {
+    let file = path.rsplit('/').next().unwrap_or(path);
+    let file = file.strip_prefix("aurora-mcp-").unwrap_or(file);
+    match parse_rpm_name(file) {
+        Some((name, version, _arch)) => (name, version),
+        None => {
+            let stem = file.strip_suffix(".rpm").unwrap_or(file);
+            (stem.to_string(), "1.0.0-1".to_string())
+        }
+    }
+}
+
+/// `2>&1`, `>/dev/null` and the like.
+fn is_redirect(word: &str) -> bool {
+    word.trim_start_matches(|c: char| c.is_ascii_digit())
+        .starts_with(['>', '<'])
+}
+
+/// Splits a command line into words, honouring single and double quotes
+/// and backslash escapes as produced by [`ssh::quote`].
+fn shell_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                for c in chars.by_ref() {
+                    if c == '\'' {
+                        break;
+                    }
+                    word.push(c);
+                }
+            }
+            '"' => {
+                in_word = true;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => word.extend(chars.next()),
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                word.extend(chars.next());
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// An RGB PNG with a status bar and a vertical gradient, standing in for a
+/// screenshot.
+pub fn synthetic_png(width: u32, height: u32) -> Vec<u8> {
+    let mut rgb = Vec::with_capacity((width * 3 * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = if y < height / 20 {
+                [20, 20, 28]
+            } else {
+                let t = (y * 255 / height) as u8;
+                [t / 3, 40 + t / 2, 120 + (x * 100 / width) as u8]
+            };
+            rgb.extend_from_slice(&pixel);
+        }
+    }
+    png::encode_rgb(width, height, &rgb)
+}
@@ -0,0 +1,370 @@
+//! Aurora OS devices reachable over SSH (phones, tablets, the emulator).
+
+pub mod ambience;
+pub mod android;
+pub mod apps;
+pub mod battery;
+pub mod clipboard;
+pub mod clock;
+pub mod connections;
+pub mod crash;
+pub mod dbus;
+pub mod deploy;
+pub mod discovery;
+pub mod files;
+pub mod gdb;
+pub mod leaks;
+pub mod locale;
+pub mod mock;
+pub mod modem;
+pub mod multi;
+pub mod network;
+pub mod notifications;
+pub mod packages;
+pub mod png;
+pub mod preview;
+pub mod processes;
+pub mod profile;
+pub mod screenshot;
+pub mod security;
+pub mod services;
+pub mod shell;
+pub mod ssh;
+pub mod storage;
+pub mod tunnels;
+pub mod wifi;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use rmcp::handler::server::router::tool::ToolRouter;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::events::{Event, EventBus};
+use crate::server::AuroraServer;
+
+pub use battery::MonitorOptions;
+pub use connections::ConnectionStats;
+pub use dbus::DbusOptions;
+pub use files::FilesOptions;
+pub use ssh::{CommandOutput, SshOptions};
+pub use tunnels::Tunnels;
+
+/// A device entry from the `[[devices]]` config table.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DeviceConfig {
+    pub name: String,
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default = "default_user")]
+    pub user: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<PathBuf>,
+    /// Login used for privileged operations (package installs, repository
+    /// changes), e.g. `root` on the emulator. Defaults to `user`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root_user: Option<String>,
+}
+
+fn default_port() -> u16 {
+    22
+}
+
+fn default_user() -> String {
+    "defaultuser".to_string()
+}
+
+/// Parameters of tools that only need to know which device to talk to.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DeviceParams {
+    /// Device name from the config.
+    pub device: String,
+}
+
+/// A configured device. Cheap to clone; clones share connection stats.
+#[derive(Debug, Clone)]
+pub struct Device {
+    config: Arc<DeviceConfig>,
+    ssh: Arc<SshOptions>,
+    stats: Arc<ConnectionStats>,
+    events: EventBus,
+    backend: Backend,
+}
+
+/// Where a device's commands run.
+#[derive(Debug, Clone)]
+enum Backend {
+    Ssh,
+    Mock(Arc<mock::Simulator>),
+}
+
+impl Device {
+    /// A device reached over SSH, publishing connection changes on
+    /// `events`.
+    pub fn new(config: DeviceConfig, ssh: Arc<SshOptions>, events: EventBus) -> Self {
+        Self {
+            config: Arc::new(config),
+            ssh,
+            stats: Arc::default(),
+            events,
+            backend: Backend::Ssh,
+        }
+    }
+
+    /// A device backed by the in-memory simulator instead of SSH.
+    pub fn mock(config: DeviceConfig, events: EventBus) -> Self {
+        let simulator = Arc::new(mock::Simulator::new(&config.name));
+        Self {
+            config: Arc::new(config),
+            ssh: Arc::default(),
+            stats: Arc::default(),
+            events,
+            backend: Backend::Mock(simulator),
+        }
+    }
+
+    pub fn is_mock(&self) -> bool {
+        matches!(self.backend, Backend::Mock(_))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    pub fn config(&self) -> &DeviceConfig {
+        &self.config
+    }
+
+    pub fn ssh_options(&self) -> &SshOptions {
+        &self.ssh
+    }
+
+    pub fn stats(&self) -> &ConnectionStats {
+        &self.stats
+    }
+
+    /// Records the outcome of one command in the connection stats,
+    /// publishing a [`Event::DeviceConnection`] when the connection failed
+    /// or recovered.
+    pub fn record(&self, result: &Result<CommandOutput>) {
+        if let Some(connected) = self.stats.record(result) {
+            self.events.publish(Event::DeviceConnection {
+                device: self.name().to_string(),
+                connected,
+                error: (!connected).then(|| self.stats.last_error()).flatten(),
+            });
+        }
+    }
+
+    /// Runs `command` through the device's login shell.
+    pub async fn exec(&self, command: &str) -> Result<CommandOutput> {
+        self.exec_as(&self.config.user, command, ssh::DEFAULT_TIMEOUT)
+            .await
+    }
+
+    pub async fn exec_with_timeout(
+        &self,
+        command: &str,
+        timeout: Duration,
+    ) -> Result<CommandOutput> {
+        self.exec_as(&self.config.user, command, timeout).await
+    }
+
+    /// Runs `command` as the configured `root_user`, with a custom timeout
+    /// since privileged operations tend to be slow.
+    pub async fn exec_privileged(&self, command: &str, timeout: Duration) -> Result<CommandOutput> {
+        let user = self.config.root_user.as_ref().unwrap_or(&self.config.user);
+        self.exec_as(user, command, timeout).await
+    }
+
+    async fn exec_as(&self, user: &str, command: &str, timeout: Duration) -> Result<CommandOutput> {
+        match &self.backend {
+            Backend::Ssh => ssh::exec(self, user, command, timeout).await,
+            Backend::Mock(simulator) => {
+                tracing::debug!(device = %self.name(), %user, %command, "mock exec");
+                let result = Ok(simulator.exec(user, command));
+                self.record(&result);
+                result
+            }
+        }
+    }
+
+    /// Like [`Device::exec`], but fails on a non-zero exit status and
+    /// returns stdout.
+    pub async fn exec_checked(&self, command: &str) -> Result<String> {
+        let output = self.exec(command).await?;
+        output.into_stdout(self.name())
+    }
+
+    /// Copies a file from the device to `local`, returning its size.
+    pub async fn download(&self, remote: &str, local: &Path) -> Result<u64> {
+        match &self.backend {
+            Backend::Ssh => ssh::download(self, remote, local, ssh::TRANSFER_TIMEOUT).await,
+            Backend::Mock(simulator) => simulator.download(remote, local),
+        }
+    }
+
+    /// Copies `local` to `remote` on the device, returning its size.
+    pub async fn upload(&self, local: &Path, remote: &str) -> Result<u64> {
+        match &self.backend {
+            Backend::Ssh => ssh::upload(self, local, remote, ssh::TRANSFER_TIMEOUT).await,
+            Backend::Mock(simulator) => simulator.upload(local, remote),
+        }
+    }
+
+    /// Sends a control command (`check`, `exit`) to the shared SSH
+    /// connection. Simulated devices are always connected.
+    pub async fn control(&self, operation: &str) -> bool {
+        match &self.backend {
+            Backend::Ssh => ssh::control(self, operation).await,
+            Backend::Mock(_) => operation == "check",
+        }
+    }
+}
+
+/// Devices known to the server, keyed by name. Clones share the same set,
+/// so devices registered at runtime are visible to every session.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceRegistry {
+    devices: Arc<RwLock<BTreeMap<String, Device>>>,
+    ssh: Arc<SshOptions>,
+    events: EventBus,
+    /// Devices are simulated (`--mock-devices`), including ones added later.
+    mock: bool,
+}
+
+impl DeviceRegistry {
+    pub fn new(
+        configs: impl IntoIterator<Item = DeviceConfig>,
+        ssh: SshOptions,
+        events: EventBus,
+    ) -> Self {
+        let ssh = Arc::new(ssh);
+        let devices = configs
+            .into_iter()
+            .map(|config| {
+                let device = Device::new(config, ssh.clone(), events.clone());
+                (device.name().to_string(), device)
+            })
+            .collect();
+        Self {
+            devices: Arc::new(RwLock::new(devices)),
+            ssh,
+            events,
+            mock: false,
+        }
+    }
+
+    /// A registry of simulated devices. Without configured devices it
+    /// offers [`mock::DEFAULT_DEVICES`].
+    pub fn mock(configs: impl IntoIterator<Item = DeviceConfig>, events: EventBus) -> Self {
+        let mut configs: Vec<DeviceConfig> = configs.into_iter().collect();
+        if configs.is_empty() {
+            configs = mock::DEFAULT_DEVICES
+                .iter()
+                .map(|name| DeviceConfig {
+                    name: name.to_string(),
+                    host: format!("{name}.invalid"),
+                    port: default_port(),
+                    user: default_user(),
+                    identity_file: None,
+                    root_user: Some("root".to_string()),
+                })
+                .collect();
+        }
+        let devices = configs
+            .into_iter()
+            .map(|config| {
+                let device = Device::mock(config, events.clone());
+                (device.name().to_string(), device)
+            })
+            .collect();
+        Self {
+            devices: Arc::new(RwLock::new(devices)),
+            ssh: Arc::default(),
+            events,
+            mock: true,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<Device> {
+        let devices = self.devices.read().unwrap_or_else(|e| e.into_inner());
+        devices.get(name).cloned().ok_or_else(|| {
+            let known = devices.keys().cloned().collect::<Vec<_>>();
+            let known = if known.is_empty() {
+                "none".to_string()
+            } else {
+                known.join(", ")
+            };
+            Error::UnknownDevice(name.to_string(), known)
+        })
+    }
+
+    pub fn list(&self) -> Vec<Device> {
+        let devices = self.devices.read().unwrap_or_else(|e| e.into_inner());
+        devices.values().cloned().collect()
+    }
+
+    /// Adds a device, failing if the name is already taken.
+    pub fn insert(&self, config: DeviceConfig) -> Result<Device> {
+        let mut devices = self.devices.write().unwrap_or_else(|e| e.into_inner());
+        if devices.contains_key(&config.name) {
+            return Err(Error::InvalidArgument(format!(
+                "device '{}' already exists",
+                config.name
+            )));
+        }
+        let device = if self.mock {
+            Device::mock(config, self.events.clone())
+        } else {
+            Device::new(config, self.ssh.clone(), self.events.clone())
+        };
+        devices.insert(device.name().to_string(), device.clone());
+        Ok(device)
+    }
+}
+
+/// The last `n` lines of `text`, for compact summaries of long output.
+pub fn tail_lines(text: &str, n: usize) -> Vec<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    lines[lines.len().saturating_sub(n)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+pub(crate) fn router() -> ToolRouter<AuroraServer> {
+    AuroraServer::processes_router()
+        + AuroraServer::crash_router()
+        + AuroraServer::network_router()
+        + AuroraServer::packages_router()
+        + AuroraServer::discovery_router()
+        + AuroraServer::connections_router()
+        + AuroraServer::shell_router()
+        + AuroraServer::deploy_router()
+        + AuroraServer::apps_router()
+        + AuroraServer::dbus_router()
+        + AuroraServer::notifications_router()
+        + AuroraServer::clipboard_router()
+        + AuroraServer::services_router()
+        + AuroraServer::wifi_router()
+        + AuroraServer::modem_router()
+        + AuroraServer::clock_router()
+        + AuroraServer::tunnels_router()
+        + AuroraServer::android_router()
+        + AuroraServer::ambience_router()
+        + AuroraServer::locale_router()
+        + AuroraServer::storage_router()
+        + AuroraServer::security_router()
+        + AuroraServer::gdb_router()
+        + AuroraServer::profile_router()
+        + AuroraServer::leaks_router()
+        + AuroraServer::preview_router()
+        + AuroraServer::screenshot_router()
+}
@@ -0,0 +1,224 @@
+//! `modem_status`: SIM, network registration and mobile data state from
+//! oFono.
+
+use std::collections::BTreeMap;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::dbus::{self, Bus};
+use super::ssh;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ModemParams {
+    /// Device name from the config.
+    pub device: String,
+    /// oFono modem path, e.g. `/ril_0` (default: the first modem). Dual-SIM
+    /// devices have one modem per slot.
+    #[serde(default)]
+    pub modem: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ModemStatus {
+    pub device: String,
+    pub modem: String,
+    /// All modems oFono knows about.
+    pub modems: Vec<String>,
+    pub powered: bool,
+    /// Radio on; false in flight mode.
+    pub online: bool,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub sim: SimStatus,
+    pub registration: RegistrationStatus,
+    pub data: DataStatus,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SimStatus {
+    pub present: bool,
+    /// `none` when unlocked, otherwise the code needed, e.g. `pin` or `puk`.
+    pub pin_required: Option<String>,
+    pub provider: Option<String>,
+    pub mcc: Option<String>,
+    pub mnc: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RegistrationStatus {
+    /// `registered`, `roaming`, `searching`, `unregistered`, `denied` or
+    /// `unknown`; absent when the interface is not up.
+    pub status: Option<String>,
+    pub operator: Option<String>,
+    /// `gsm`, `umts`, `lte`, `nr`, ...
+    pub technology: Option<String>,
+    pub strength_percent: Option<u8>,
+    pub mcc: Option<String>,
+    pub mnc: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DataStatus {
+    /// Attached to the packet network.
+    pub attached: bool,
+    /// Mobile data switched on by the user.
+    pub enabled: bool,
+    pub roaming_allowed: bool,
+    pub bearer: Option<String>,
+    pub active_context: Option<DataContext>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DataContext {
+    pub path: String,
+    pub apn: Option<String>,
+    pub interface: Option<String>,
+    pub address: Option<String>,
+}
+
+/// `GetProperties` of an oFono interface on `modem`, as a script fragment
+/// that prints nothing if the interface is not available.
+fn properties(modem: &str, interface: &str) -> Result<String> {
+    let command = dbus::send_command(
+        Bus::System,
+        "org.ofono",
+        modem,
+        &format!("org.ofono.{interface}.GetProperties"),
+        &[],
+    )?;
+    Ok(format!("{command} 2>/dev/null || true"))
+}
+
+/// Splits a `GetContexts` reply into per-context property sets.
+fn parse_contexts(reply: &str) -> Vec<(String, BTreeMap<String, String>)> {
+    reply
+        .split("object path \"")
+        .skip(1)
+        .filter_map(|chunk| {
+            let (path, rest) = chunk.split_once('"')?;
+            Some((path.to_string(), dbus::reply_properties(rest)))
+        })
+        .collect()
+}
+
+#[tool_router(router = modem_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Cellular status of a device from oFono: SIM presence and lock state, network registration, operator, radio technology, signal strength and mobile data state. Use it to check connectivity preconditions before a test.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn modem_status(
+        &self,
+        Parameters(params): Parameters<ModemParams>,
+    ) -> Result<Json<ModemStatus>> {
+        let device = self.devices().get(&params.device)?;
+        let reply = dbus::call(
+            &device,
+            Bus::System,
+            "org.ofono",
+            "/",
+            "org.ofono.Manager.GetModems",
+            &[],
+        )
+        .await?;
+        let modems: Vec<String> = parse_contexts(&reply)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        let modem = match params.modem {
+            Some(modem) if modems.contains(&modem) => modem,
+            Some(modem) => {
+                return Err(Error::InvalidArgument(format!(
+                    "no modem '{modem}'; available: {}",
+                    modems.join(", ")
+                )));
+            }
+            None => modems.first().cloned().ok_or_else(|| Error::Parse {
+                device: params.device.clone(),
+                message: "oFono reports no modems".to_string(),
+            })?,
+        };
+
+        let contexts = dbus::send_command(
+            Bus::System,
+            "org.ofono",
+            &modem,
+            "org.ofono.ConnectionManager.GetContexts",
+            &[],
+        )?;
+        let script = ssh::sections(&[
+            &properties(&modem, "Modem")?,
+            &properties(&modem, "SimManager")?,
+            &properties(&modem, "NetworkRegistration")?,
+            &properties(&modem, "ConnectionManager")?,
+            &format!("{contexts} 2>/dev/null || true"),
+        ]);
+        let stdout = device.exec_checked(&script).await?;
+        let [modem_out, sim_out, reg_out, data_out, contexts_out] =
+            ssh::split_sections(&stdout)[..]
+        else {
+            return Err(Error::Parse {
+                device: params.device,
+                message: "missing output sections".to_string(),
+            });
+        };
+        let (modem_props, sim, reg, data) = (
+            dbus::reply_properties(modem_out),
+            dbus::reply_properties(sim_out),
+            dbus::reply_properties(reg_out),
+            dbus::reply_properties(data_out),
+        );
+        let get = |props: &BTreeMap<String, String>, key: &str| {
+            props.get(key).filter(|value| !value.is_empty()).cloned()
+        };
+        let flag = |props: &BTreeMap<String, String>, key: &str| {
+            props.get(key).is_some_and(|value| value == "true")
+        };
+        let active_context = parse_contexts(contexts_out)
+            .into_iter()
+            .find(|(_, props)| flag(props, "Active"))
+            .map(|(path, props)| DataContext {
+                path,
+                apn: get(&props, "AccessPointName"),
+                interface: get(&props, "Interface"),
+                address: get(&props, "Address"),
+            });
+
+        Ok(Json(ModemStatus {
+            device: params.device,
+            modems,
+            powered: flag(&modem_props, "Powered"),
+            online: flag(&modem_props, "Online"),
+            manufacturer: get(&modem_props, "Manufacturer"),
+            model: get(&modem_props, "Model"),
+            sim: SimStatus {
+                present: flag(&sim, "Present"),
+                pin_required: get(&sim, "PinRequired"),
+                provider: get(&sim, "ServiceProviderName"),
+                mcc: get(&sim, "MobileCountryCode"),
+                mnc: get(&sim, "MobileNetworkCode"),
+            },
+            registration: RegistrationStatus {
+                status: get(&reg, "Status"),
+                operator: get(&reg, "Name"),
+                technology: get(&reg, "Technology"),
+                strength_percent: get(&reg, "Strength").and_then(|s| s.parse().ok()),
+                mcc: get(&reg, "MobileCountryCode"),
+                mnc: get(&reg, "MobileNetworkCode"),
+            },
+            data: DataStatus {
+                attached: flag(&data, "Attached"),
+                enabled: flag(&data, "Powered"),
+                roaming_allowed: flag(&data, "RoamingAllowed"),
+                bearer: get(&data, "Bearer"),
+                active_context,
+            },
+            modem,
+        }))
+    }
+}
@@ -0,0 +1,80 @@
+//! Running one operation on several devices at once.
+
+use std::future::Future;
+use std::time::Instant;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use super::Device;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Per-device outcomes of an operation run across several devices, in the
+/// order the devices were given.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MultiDeviceReport<T> {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<DeviceOutcome<T>>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DeviceOutcome<T> {
+    pub device: String,
+    pub ok: bool,
+    pub result: Option<T>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Runs `op` concurrently on every named device. Unknown device names and
+/// failures are reported per device rather than failing the whole call.
+pub async fn fan_out<T, F, Fut>(
+    server: &AuroraServer,
+    names: &[String],
+    op: F,
+) -> Result<MultiDeviceReport<T>>
+where
+    F: Fn(Device) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    if names.is_empty() {
+        return Err(Error::InvalidArgument("no devices given".to_string()));
+    }
+    let runs = names.iter().map(|name| {
+        let device = server.devices().get(name);
+        let op = &op;
+        async move {
+            let started = Instant::now();
+            let result = match device {
+                Ok(device) => op(device).await,
+                Err(err) => Err(err),
+            };
+            let duration_ms = started.elapsed().as_millis() as u64;
+            match result {
+                Ok(value) => DeviceOutcome {
+                    device: name.clone(),
+                    ok: true,
+                    result: Some(value),
+                    error: None,
+                    duration_ms,
+                },
+                Err(err) => DeviceOutcome {
+                    device: name.clone(),
+                    ok: false,
+                    result: None,
+                    error: Some(err.to_string()),
+                    duration_ms,
+                },
+            }
+        }
+    });
+    let results = futures::future::join_all(runs).await;
+    let succeeded = results.iter().filter(|outcome| outcome.ok).count();
+    Ok(MultiDeviceReport {
+        succeeded,
+        failed: results.len() - succeeded,
+        results,
+    })
+}
@@ -0,0 +1,220 @@
+//! `device_network`: interface, routing and DNS state plus a connectivity
+//! probe, as seen from the device.
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::ssh;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+const DEFAULT_PROBE_HOST: &str = "ya.ru";
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct NetworkParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Host to ping from the device (default `ya.ru`).
+    #[serde(default)]
+    pub probe_host: Option<String>,
+    /// Optional URL fetched with curl from the device, e.g. the app's backend.
+    #[serde(default)]
+    pub probe_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NetworkReport {
+    pub device: String,
+    pub interfaces: Vec<InterfaceAddress>,
+    pub default_route: Option<DefaultRoute>,
+    pub nameservers: Vec<String>,
+    pub ping: PingResult,
+    pub http: Option<HttpProbe>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct InterfaceAddress {
+    pub interface: String,
+    /// `inet` or `inet6`.
+    pub family: String,
+    /// Address with prefix length, e.g. `192.168.2.15/24`.
+    pub address: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DefaultRoute {
+    pub gateway: Option<String>,
+    pub interface: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PingResult {
+    pub host: String,
+    /// Address the host resolved to, if resolution succeeded.
+    pub resolved: Option<String>,
+    pub transmitted: u32,
+    pub received: u32,
+    pub avg_rtt_ms: Option<f64>,
+    /// Raw ping output when the probe could not be parsed or failed.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct HttpProbe {
+    pub url: String,
+    /// HTTP status code; 0 when no response was received.
+    pub status: u16,
+    pub total_time_s: Option<f64>,
+    pub error: Option<String>,
+}
+
+pub async fn diagnose(server: &AuroraServer, params: NetworkParams) -> Result<NetworkReport> {
+    let device = server.devices().get(&params.device)?;
+    let host = params
+        .probe_host
+        .unwrap_or_else(|| DEFAULT_PROBE_HOST.to_string());
+    let ping = format!("ping -c 3 -W 2 {} 2>&1", ssh::quote(&host));
+    let curl = match &params.probe_url {
+        Some(url) => format!(
+            "curl -sS -o /dev/null -m 10 -w '%{{http_code}} %{{time_total}}' {} 2>&1",
+            ssh::quote(url)
+        ),
+        None => "true".to_string(),
+    };
+    let command = ssh::sections(&[
+        "ip -o addr show",
+        "ip route show default",
+        "cat /etc/resolv.conf",
+        &ping,
+        &curl,
+    ]);
+    let stdout = device.exec(&command).await?.stdout;
+    let [addrs, route, resolv, ping_out, curl_out] = ssh::split_sections(&stdout)[..] else {
+        return Err(Error::Parse {
+            device: params.device,
+            message: "missing output sections".to_string(),
+        });
+    };
+
+    Ok(NetworkReport {
+        device: params.device,
+        interfaces: addrs.lines().filter_map(parse_ip_addr_line).collect(),
+        default_route: route.lines().next().and_then(parse_default_route),
+        nameservers: resolv
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("nameserver"))
+            .map(|server| server.trim().to_string())
+            .collect(),
+        ping: parse_ping(host, ping_out),
+        http: params.probe_url.map(|url| parse_curl(url, curl_out)),
+    })
+}
+
+/// Parses `2: wlan0    inet 192.168.2.15/24 brd ... scope global wlan0`.
+pub(crate) fn parse_ip_addr_line(line: &str) -> Option<InterfaceAddress> {
+    let mut fields = line.split_whitespace().skip(1);
+    let interface = fields.next()?.trim_end_matches(':').to_string();
+    let family = fields.next()?.to_string();
+    if family != "inet" && family != "inet6" {
+        return None;
+    }
+    let address = fields.next()?.to_string();
+    Some(InterfaceAddress {
+        interface,
+        family,
+        address,
+    })
+}
+
+/// Parses `default via 192.168.2.1 dev wlan0 ...`.
+fn parse_default_route(line: &str) -> Option<DefaultRoute> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.first() != Some(&"default") {
+        return None;
+    }
+    let after = |key: &str| {
+        fields
+            .windows(2)
+            .find(|pair| pair[0] == key)
+            .map(|pair| pair[1].to_string())
+    };
+    Some(DefaultRoute {
+        gateway: after("via"),
+        interface: after("dev"),
+    })
+}
+
+/// Understands both iputils and busybox ping summaries.
+fn parse_ping(host: String, output: &str) -> PingResult {
+    let resolved = output
+        .lines()
+        .next()
+        .filter(|line| line.starts_with("PING"))
+        .and_then(|line| Some(line.split_once('(')?.1.split_once(')')?.0.to_string()));
+    let counts = output.lines().find(|line| line.contains("transmitted"));
+    let count = |label: &str| {
+        counts.and_then(|line| {
+            line.split(',')
+                .find(|part| part.contains(label))?
+                .split_whitespace()
+                .next()?
+                .parse()
+                .ok()
+        })
+    };
+    let transmitted = count("transmitted");
+    let received = count("received");
+    let avg_rtt_ms = output
+        .lines()
+        .find(|line| line.contains("min/avg/max"))
+        .and_then(|line| {
+            line.split_once('=')?
+                .1
+                .trim()
+                .split('/')
+                .nth(1)?
+                .parse()
+                .ok()
+        });
+    let error = match (transmitted, received) {
+        (Some(_), Some(received)) if received > 0 => None,
+        _ => Some(output.trim().to_string()),
+    };
+    PingResult {
+        host,
+        resolved,
+        transmitted: transmitted.unwrap_or(0),
+        received: received.unwrap_or(0),
+        avg_rtt_ms,
+        error,
+    }
+}
+
+fn parse_curl(url: String, output: &str) -> HttpProbe {
+    let output = output.trim();
+    let mut fields = output.rsplit('\n').next().unwrap_or("").split_whitespace();
+    let status = fields.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+    let total_time_s = fields.next().and_then(|s| s.parse().ok());
+    HttpProbe {
+        url,
+        status,
+        total_time_s,
+        error: (status == 0).then(|| output.to_string()),
+    }
+}
+
+#[tool_router(router = network_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Network diagnostics from a device: interface addresses, default route, DNS servers, a ping probe and an optional HTTP probe of a URL. Use it when an app cannot reach its backend.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn device_network(
+        &self,
+        Parameters(params): Parameters<NetworkParams>,
+    ) -> Result<Json<NetworkReport>> {
+        diagnose(self, params).await.map(Json)
+    }
+}
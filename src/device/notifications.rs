@@ -0,0 +1,140 @@
+//! `send_notification`: posting a test notification through the device's
+//! `org.freedesktop.Notifications` service.
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{dbus, ssh};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct NotificationParams {
+    /// Device name from the config.
+    pub device: String,
+    pub summary: String,
+    #[serde(default)]
+    pub body: String,
+    /// Theme icon name (e.g. `icon-lock-information`) or absolute image path.
+    #[serde(default)]
+    pub icon: String,
+    /// Application name shown with the notification (default `aurora-mcp`).
+    #[serde(default)]
+    pub app_name: Option<String>,
+    /// 0 = low, 1 = normal (default), 2 = critical.
+    #[serde(default)]
+    pub urgency: Option<u8>,
+    /// Expiry in milliseconds; -1 (default) lets the home screen decide.
+    #[serde(default)]
+    pub timeout_ms: Option<i32>,
+    /// Also show the notification as a banner popup, not only in the
+    /// events view (default true).
+    #[serde(default)]
+    pub preview: Option<bool>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NotificationResult {
+    pub device: String,
+    /// Id assigned by the notification manager, if it could be read.
+    pub id: Option<u32>,
+    /// `gdbus` or `notificationtool`, whichever the device has.
+    pub sent_with: String,
+}
+
+/// A GVariant text-format string literal.
+fn gvariant_string(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn command(params: &NotificationParams) -> Result<String> {
+    let urgency = params.urgency.unwrap_or(1);
+    if urgency > 2 {
+        return Err(Error::InvalidArgument(format!(
+            "urgency must be 0, 1 or 2, got {urgency}"
+        )));
+    }
+    let app_name = params.app_name.as_deref().unwrap_or("aurora-mcp");
+    let timeout = params.timeout_ms.unwrap_or(-1);
+    let preview = params.preview.unwrap_or(true);
+
+    let mut hints = vec![format!("'urgency': <byte {urgency}>")];
+    if preview {
+        hints.push(format!(
+            "'x-nemo-preview-summary': <{}>",
+            gvariant_string(&params.summary)
+        ));
+        hints.push(format!(
+            "'x-nemo-preview-body': <{}>",
+            gvariant_string(&params.body)
+        ));
+    }
+    let gdbus = format!(
+        "gdbus call --session --dest org.freedesktop.Notifications \
+         --object-path /org/freedesktop/Notifications \
+         --method org.freedesktop.Notifications.Notify {} 0 {} {} {} '[]' {} {timeout}",
+        ssh::quote(app_name),
+        ssh::quote(&params.icon),
+        ssh::quote(&params.summary),
+        ssh::quote(&params.body),
+        ssh::quote(&format!("{{{}}}", hints.join(", "))),
+    );
+
+    let mut tool = format!(
+        "notificationtool -o add --application={} --urgency={urgency} --timeout={timeout}",
+        ssh::quote(app_name)
+    );
+    if !params.icon.is_empty() {
+        tool.push_str(&format!(" --icon={}", ssh::quote(&params.icon)));
+    }
+    tool.push_str(&format!(
+        " {} {}",
+        ssh::quote(&params.summary),
+        ssh::quote(&params.body)
+    ));
+    if preview {
+        tool.push_str(&format!(
+            " {} {}",
+            ssh::quote(&params.summary),
+            ssh::quote(&params.body)
+        ));
+    }
+
+    Ok(format!(
+        "{}if command -v gdbus >/dev/null 2>&1; then echo gdbus; {gdbus}; \
+         else echo notificationtool; {tool}; fi",
+        dbus::SESSION_ENV
+    ))
+}
+
+/// Picks the notification id out of `(uint32 42,)` or a trailing number in
+/// `notificationtool` output.
+fn parse_id(output: &str) -> Option<u32> {
+    output
+        .split(|c: char| !c.is_ascii_digit())
+        .rfind(|part| !part.is_empty())?
+        .parse()
+        .ok()
+}
+
+#[tool_router(router = notifications_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Post a test notification on a device through its Notifications D-Bus service, with custom summary, body, icon and urgency. Shows a banner popup unless preview=false."
+    )]
+    pub async fn send_notification(
+        &self,
+        Parameters(params): Parameters<NotificationParams>,
+    ) -> Result<Json<NotificationResult>> {
+        let device = self.devices().get(&params.device)?;
+        let stdout = device.exec_checked(&command(&params)?).await?;
+        let (sent_with, reply) = stdout.split_once('\n').unwrap_or((stdout.as_str(), ""));
+        Ok(Json(NotificationResult {
+            device: params.device,
+            id: parse_id(reply),
+            sent_with: sent_with.trim().to_string(),
+        }))
+    }
+}
@@ -0,0 +1,264 @@
+//! Repository (`ssu`) and package (`pkcon`) management on a device.
+
+use std::time::Duration;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{Device, DeviceParams, ssh, tail_lines};
+use crate::error::{Error, Result, require_confirmation};
+use crate::server::AuroraServer;
+
+const PKCON_TIMEOUT: Duration = Duration::from_secs(600);
+const OUTPUT_TAIL_LINES: usize = 40;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RepoAddParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Repository alias.
+    pub name: String,
+    /// Repository base URL.
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RepoRemoveParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Repository alias.
+    pub name: String,
+    /// Must be true to actually remove the repository.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PackagesParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Package names.
+    pub packages: Vec<String>,
+    /// Must be true for removals.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RepoList {
+    pub device: String,
+    pub repositories: Vec<Repository>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Repository {
+    pub name: String,
+    pub url: Option<String>,
+    pub enabled: bool,
+    /// `global` or `user`, as reported by ssu.
+    pub scope: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct OperationResult {
+    pub device: String,
+    pub action: String,
+    pub success: bool,
+    pub exit_status: i32,
+    /// Last lines of the combined command output.
+    pub output_tail: Vec<String>,
+}
+
+async fn run(device: &Device, action: String, command: &str) -> Result<OperationResult> {
+    let output = device
+        .exec_privileged(&format!("{command} 2>&1"), PKCON_TIMEOUT)
+        .await?;
+    Ok(OperationResult {
+        device: device.name().to_string(),
+        action,
+        success: output.success(),
+        exit_status: output.status,
+        output_tail: tail_lines(&output.stdout, OUTPUT_TAIL_LINES),
+    })
+}
+
+fn parse_ssu_lr(output: &str) -> Vec<Repository> {
+    let mut repositories = Vec::new();
+    let (mut enabled, mut scope) = (true, String::from("global"));
+    for line in output.lines() {
+        if let Some(item) = line.trim_start().strip_prefix("- ") {
+            let mut fields = item.split_whitespace();
+            let Some(name) = fields.next() else { continue };
+            repositories.push(Repository {
+                name: name.to_string(),
+                url: fields
+                    .last()
+                    .filter(|f| f.contains("://"))
+                    .map(str::to_string),
+                enabled,
+                scope: scope.clone(),
+            });
+        } else if line.contains("repositories") {
+            enabled = !line.trim_start().starts_with("Disabled");
+            scope = if line.contains("(user") {
+                "user"
+            } else {
+                "global"
+            }
+            .to_string();
+        }
+    }
+    repositories
+}
+
+/// The packages as pkcon arguments. Names are RPM package names, so none
+/// can pass for an option such as `--allow-untrusted`.
+fn package_args(packages: &[String]) -> Result<String> {
+    if packages.is_empty() {
+        return Err(Error::InvalidArgument("no packages given".to_string()));
+    }
+    if let Some(name) = packages.iter().find(|name| {
+        name.is_empty()
+            || name.starts_with('-')
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "._+-".contains(c))
+    }) {
+        return Err(Error::InvalidArgument(format!(
+            "'{name}' is not a package name; use letters, digits, '.', '_', '+' and '-', not starting with '-'"
+        )));
+    }
+    Ok(packages
+        .iter()
+        .map(|p| ssh::quote(p))
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+#[tool_router(router = packages_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "List ssu repositories configured on a device, enabled and disabled.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn device_repos(
+        &self,
+        Parameters(params): Parameters<DeviceParams>,
+    ) -> Result<Json<RepoList>> {
+        let device = self.devices().get(&params.device)?;
+        let output = device.exec_checked("ssu lr").await?;
+        Ok(Json(RepoList {
+            device: params.device,
+            repositories: parse_ssu_lr(&output),
+        }))
+    }
+
+    #[tool(description = "Add an ssu repository on a device.")]
+    pub async fn device_repo_add(
+        &self,
+        Parameters(params): Parameters<RepoAddParams>,
+    ) -> Result<Json<OperationResult>> {
+        let device = self.devices().get(&params.device)?;
+        let command = format!(
+            "ssu ar {} {}",
+            ssh::quote(&params.name),
+            ssh::quote(&params.url)
+        );
+        run(&device, format!("add repository {}", params.name), &command)
+            .await
+            .map(Json)
+    }
+
+    #[tool(
+        description = "Remove an ssu repository from a device. Requires confirm=true.",
+        annotations(destructive_hint = true)
+    )]
+    pub async fn device_repo_remove(
+        &self,
+        Parameters(params): Parameters<RepoRemoveParams>,
+    ) -> Result<Json<OperationResult>> {
+        let device = self.devices().get(&params.device)?;
+        let action = format!("remove repository {} from {}", params.name, params.device);
+        require_confirmation(params.confirm, || action.clone())?;
+        let command = format!("ssu rr {}", ssh::quote(&params.name));
+        run(&device, action, &command).await.map(Json)
+    }
+
+    #[tool(description = "Refresh package metadata on a device (pkcon refresh).")]
+    pub async fn device_pkg_refresh(
+        &self,
+        Parameters(params): Parameters<DeviceParams>,
+    ) -> Result<Json<OperationResult>> {
+        let device = self.devices().get(&params.device)?;
+        run(&device, "refresh".to_string(), "pkcon -y refresh")
+            .await
+            .map(Json)
+    }
+
+    #[tool(description = "Install packages by name on a device with pkcon.")]
+    pub async fn device_pkg_install(
+        &self,
+        Parameters(params): Parameters<PackagesParams>,
+    ) -> Result<Json<OperationResult>> {
+        let device = self.devices().get(&params.device)?;
+        let args = package_args(&params.packages)?;
+        let action = format!("install {}", params.packages.join(" "));
+        run(&device, action, &format!("pkcon -y install {args}"))
+            .await
+            .map(Json)
+    }
+
+    #[tool(
+        description = "Remove packages from a device with pkcon. Requires confirm=true.",
+        annotations(destructive_hint = true)
+    )]
+    pub async fn device_pkg_remove(
+        &self,
+        Parameters(params): Parameters<PackagesParams>,
+    ) -> Result<Json<OperationResult>> {
+        let device = self.devices().get(&params.device)?;
+        let args = package_args(&params.packages)?;
+        let action = format!(
+            "remove {} from {}",
+            params.packages.join(" "),
+            params.device
+        );
+        require_confirmation(params.confirm, || action.clone())?;
+        run(&device, action, &format!("pkcon -y remove {args}"))
+            .await
+            .map(Json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn passes_package_names() {
+        assert_eq!(
+            package_args(&names(&["ru.example.app", "libstdc++", "qt5-qtbase"])).unwrap(),
+            "ru.example.app libstdc++ qt5-qtbase"
+        );
+    }
+
+    #[test]
+    fn refuses_options_and_other_words() {
+        for name in ["--allow-untrusted", "-y", "a b", "a;reboot", "", "$(id)"] {
+            assert!(
+                matches!(
+                    package_args(&names(&[name])),
+                    Err(Error::InvalidArgument(_))
+                ),
+                "{name}"
+            );
+        }
+        assert!(package_args(&[]).is_err());
+    }
+}
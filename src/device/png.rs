@@ -0,0 +1,591 @@
+//! Just enough PNG for screenshots: decoding any non-interlaced PNG to
+//! RGBA and encoding RGB images, with a small inflate and a run-length
+//! deflate so no image crate is needed.
+
+use crate::error::{Error, Result};
+
+/// An 8-bit RGBA image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// Rows of RGBA pixels, top to bottom.
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let at = ((y * self.width + x) * 4) as usize;
+        [
+            self.pixels[at],
+            self.pixels[at + 1],
+            self.pixels[at + 2],
+            self.pixels[at + 3],
+        ]
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::InvalidArgument(format!("invalid PNG: {message}"))
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + u32::from(byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order of the code length code lengths in a dynamic block header.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bits: u32,
+    count: u32,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, n: u32) -> Result<u32> {
+        while self.count < n {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or_else(|| invalid("truncated image data"))?;
+            self.pos += 1;
+            self.bits |= u32::from(byte) << self.count;
+            self.count += 8;
+        }
+        let value = self.bits & ((1u64 << n) - 1) as u32;
+        self.bits >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+}
+
+/// A canonical Huffman code, decoded a bit at a time.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= reader.bits(1)? as i32;
+            let count = i32::from(self.counts[len]);
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("bad Huffman code"))
+    }
+}
+
+fn too_large() -> Error {
+    invalid("more image data than the header describes")
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    limit: usize,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<()> {
+    loop {
+        let symbol = literals.decode(reader)?;
+        match symbol {
+            0..=255 if out.len() < limit => out.push(symbol as u8),
+            0..=255 => return Err(too_large()),
+            256 => return Ok(()),
+            _ => {
+                let i = usize::from(symbol - 257);
+                if i >= LENGTH_BASE.len() {
+                    return Err(invalid("bad length code"));
+                }
+                let length =
+                    usize::from(LENGTH_BASE[i]) + reader.bits(u32::from(LENGTH_EXTRA[i]))? as usize;
+                let d = usize::from(distances.decode(reader)?);
+                if d >= DISTANCE_BASE.len() {
+                    return Err(invalid("bad distance code"));
+                }
+                let distance = usize::from(DISTANCE_BASE[d])
+                    + reader.bits(u32::from(DISTANCE_EXTRA[d]))? as usize;
+                if distance > out.len() {
+                    return Err(invalid("distance too far back"));
+                }
+                if out.len() + length > limit {
+                    return Err(too_large());
+                }
+                let start = out.len() - distance;
+                for k in 0..length {
+                    out.push(out[start + k]);
+                }
+            }
+        }
+    }
+}
+
+/// Decompresses a zlib stream, failing once the output would exceed
+/// `limit` bytes, so a small malicious stream cannot exhaust memory.
+fn inflate(zlib: &[u8], limit: usize) -> Result<Vec<u8>> {
+    if zlib.len() < 2 || zlib[0] & 0x0f != 8 {
+        return Err(invalid("not a deflate stream"));
+    }
+    let mut reader = BitReader {
+        data: &zlib[2..],
+        pos: 0,
+        bits: 0,
+        count: 0,
+    };
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.bits = 0;
+                reader.count = 0;
+                let header = reader
+                    .data
+                    .get(reader.pos..reader.pos + 4)
+                    .ok_or_else(|| invalid("truncated stored block"))?;
+                let len = usize::from(u16::from_le_bytes([header[0], header[1]]));
+                let start = reader.pos + 4;
+                let block = reader
+                    .data
+                    .get(start..start + len)
+                    .ok_or_else(|| invalid("truncated stored block"))?;
+                if out.len() + block.len() > limit {
+                    return Err(too_large());
+                }
+                out.extend_from_slice(block);
+                reader.pos = start + len;
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                let literals = Huffman::new(&lengths);
+                let distances = Huffman::new(&[5; 30]);
+                inflate_block(&mut reader, &mut out, limit, &literals, &distances)?;
+            }
+            2 => {
+                let nlen = reader.bits(5)? as usize + 257;
+                let ndist = reader.bits(5)? as usize + 1;
+                let ncode = reader.bits(4)? as usize + 4;
+                let mut code_lengths = [0u8; 19];
+                for &i in &CODE_LENGTH_ORDER[..ncode] {
+                    code_lengths[i] = reader.bits(3)? as u8;
+                }
+                let code = Huffman::new(&code_lengths);
+                let mut lengths = Vec::with_capacity(nlen + ndist);
+                while lengths.len() < nlen + ndist {
+                    let (value, repeat) = match code.decode(&mut reader)? {
+                        symbol @ 0..=15 => (symbol as u8, 1),
+                        16 => {
+                            let previous = *lengths
+                                .last()
+                                .ok_or_else(|| invalid("repeat without a length"))?;
+                            (previous, 3 + reader.bits(2)? as usize)
+                        }
+                        17 => (0, 3 + reader.bits(3)? as usize),
+                        _ => (0, 11 + reader.bits(7)? as usize),
+                    };
+                    lengths.extend(std::iter::repeat_n(value, repeat));
+                }
+                if lengths.len() > nlen + ndist {
+                    return Err(invalid("too many code lengths"));
+                }
+                let literals = Huffman::new(&lengths[..nlen]);
+                let distances = Huffman::new(&lengths[nlen..]);
+                inflate_block(&mut reader, &mut out, limit, &literals, &distances)?;
+            }
+            _ => return Err(invalid("bad block type")),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let (pa, pb, pc) = (
+        (p - i16::from(a)).abs(),
+        (p - i16::from(b)).abs(),
+        (p - i16::from(c)).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Decodes a PNG into RGBA. Interlaced images are not supported.
+pub fn decode(data: &[u8]) -> Result<Image> {
+    if !data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Err(invalid("missing signature"));
+    }
+    let mut pos = 8;
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut zlib = Vec::new();
+    while pos + 8 <= data.len() {
+        let len = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap_or_default()) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        let body = data
+            .get(pos + 8..pos + 8 + len)
+            .ok_or_else(|| invalid("truncated chunk"))?;
+        match kind {
+            b"IHDR" if len == 13 => header = Some(body),
+            b"PLTE" => palette = body,
+            b"tRNS" => transparency = body,
+            b"IDAT" => zlib.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += 12 + len;
+    }
+    let header = header.ok_or_else(|| invalid("missing IHDR"))?;
+    let width = u32::from_be_bytes(header[0..4].try_into().unwrap_or_default());
+    let height = u32::from_be_bytes(header[4..8].try_into().unwrap_or_default());
+    let (depth, color, interlace) = (header[8], header[9], header[12]);
+    if interlace != 0 {
+        return Err(invalid("interlaced images are not supported"));
+    }
+    let channels = match color {
+        0 | 3 => 1,
+        2 => 3,
+        4 => 2,
+        6 => 4,
+        _ => return Err(invalid("unknown color type")),
+    };
+    if !matches!(depth, 1 | 2 | 4 | 8 | 16) || (depth < 8 && channels != 1) {
+        return Err(invalid("unsupported bit depth"));
+    }
+    let bits_per_pixel = usize::from(depth) * channels;
+    let stride = (width as usize * bits_per_pixel).div_ceil(8);
+    let bpp = bits_per_pixel.div_ceil(8);
+    let size = (stride + 1)
+        .checked_mul(height as usize)
+        .ok_or_else(|| invalid("image too large"))?;
+    let raw = inflate(&zlib, size)?;
+    if raw.len() < size {
+        return Err(invalid("not enough image data"));
+    }
+
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+    let mut previous = vec![0u8; stride];
+    let mut row = vec![0u8; stride];
+    for y in 0..height as usize {
+        let line = &raw[y * (stride + 1)..(y + 1) * (stride + 1)];
+        let filter = line[0];
+        for i in 0..stride {
+            let left = if i >= bpp { row[i - bpp] } else { 0 };
+            let up = previous[i];
+            let up_left = if i >= bpp { previous[i - bpp] } else { 0 };
+            let predictor = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(invalid("unknown filter")),
+            };
+            row[i] = line[i + 1].wrapping_add(predictor);
+        }
+        for x in 0..width as usize {
+            let sample = |channel: usize| -> u8 {
+                match depth {
+                    8 => row[x * channels + channel],
+                    16 => row[(x * channels + channel) * 2],
+                    _ => {
+                        let bit = x * usize::from(depth);
+                        let max = (1u16 << depth) - 1;
+                        let value =
+                            (row[bit / 8] >> (8 - usize::from(depth) - bit % 8)) as u16 & max;
+                        if color == 3 {
+                            value as u8
+                        } else {
+                            (value * 255 / max) as u8
+                        }
+                    }
+                }
+            };
+            let rgba = match color {
+                0 => {
+                    let v = sample(0);
+                    [v, v, v, 255]
+                }
+                2 => [sample(0), sample(1), sample(2), 255],
+                3 => {
+                    let index = usize::from(if depth == 8 { row[x] } else { sample(0) });
+                    let rgb = palette
+                        .get(index * 3..index * 3 + 3)
+                        .ok_or_else(|| invalid("palette index out of range"))?;
+                    [
+                        rgb[0],
+                        rgb[1],
+                        rgb[2],
+                        transparency.get(index).copied().unwrap_or(255),
+                    ]
+                }
+                4 => {
+                    let v = sample(0);
+                    [v, v, v, sample(1)]
+                }
+                _ => [sample(0), sample(1), sample(2), sample(3)],
+            };
+            pixels.extend_from_slice(&rgba);
+        }
+        std::mem::swap(&mut previous, &mut row);
+    }
+    Ok(Image {
+        width,
+        height,
+        pixels,
+    })
+}
+
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, n: u32) {
+        self.bits |= value << self.count;
+        self.count += n;
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Writes a Huffman code, which deflate stores most significant bit
+    /// first.
+    fn code(&mut self, code: u32, n: u32) {
+        self.write(code.reverse_bits() >> (32 - n), n);
+    }
+
+    fn literal(&mut self, symbol: u32) {
+        match symbol {
+            0..=143 => self.code(0x30 + symbol, 8),
+            144..=255 => self.code(0x190 + symbol - 144, 9),
+            256..=279 => self.code(symbol - 256, 7),
+            _ => self.code(0xc0 + symbol - 280, 8),
+        }
+    }
+}
+
+/// Compresses `data` as one fixed-Huffman block whose only matches are
+/// runs of the previous byte, which suits filtered screenshots well.
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter {
+        out: vec![0x78, 0x01],
+        bits: 0,
+        count: 0,
+    };
+    writer.write(1, 1);
+    writer.write(1, 2);
+    let mut i = 0;
+    while i < data.len() {
+        let mut run = 0;
+        if i > 0 {
+            while run < 258 && i + run < data.len() && data[i + run] == data[i - 1] {
+                run += 1;
+            }
+        }
+        if run >= 3 {
+            let code = LENGTH_BASE
+                .iter()
+                .rposition(|&base| usize::from(base) <= run)
+                .unwrap_or_default();
+            writer.literal(257 + code as u32);
+            writer.write(
+                (run - usize::from(LENGTH_BASE[code])) as u32,
+                u32::from(LENGTH_EXTRA[code]),
+            );
+            // Distance 1: code 0, no extra bits.
+            writer.code(0, 5);
+            i += run;
+        } else {
+            writer.literal(u32::from(data[i]));
+            i += 1;
+        }
+    }
+    writer.literal(256);
+    writer.write(0, (8 - writer.count) % 8);
+    let mut out = writer.out;
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Encodes 8-bit RGB rows (`width * 3` bytes each) as a PNG.
+pub fn encode_rgb(width: u32, height: u32, rgb: &[u8]) -> Vec<u8> {
+    let stride = width as usize * 3;
+    let mut raw = Vec::with_capacity((stride + 1) * height as usize);
+    for row in rgb.chunks(stride).take(height as usize) {
+        // Sub filter: flat areas become runs of zeros.
+        raw.push(1);
+        for i in 0..stride {
+            let left = if i >= 3 { row[i - 3] } else { 0 };
+            raw.push(row[i].wrapping_sub(left));
+        }
+    }
+
+    let mut ihdr = Vec::new();
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]); // 8-bit RGB
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    for (kind, data) in [
+        (b"IHDR", &ihdr),
+        (b"IDAT", &deflate(&raw)),
+        (b"IEND", &Vec::new()),
+    ] {
+        png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        let start = png.len();
+        png.extend_from_slice(kind);
+        png.extend_from_slice(data);
+        let crc = crc32(&png[start..]);
+        png.extend_from_slice(&crc.to_be_bytes());
+    }
+    png
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(text: &str) -> Vec<u8> {
+        (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_rgb() {
+        let (width, height) = (5, 3);
+        let rgb: Vec<u8> = (0..width * height * 3)
+            .map(|i| (i * 37 % 256) as u8)
+            .collect();
+        let image = decode(&encode_rgb(width, height, &rgb)).unwrap();
+        assert_eq!((image.width, image.height), (width, height));
+        for y in 0..height {
+            for x in 0..width {
+                let at = ((y * width + x) * 3) as usize;
+                assert_eq!(image.pixel(x, y), [rgb[at], rgb[at + 1], rgb[at + 2], 255]);
+            }
+        }
+    }
+
+    #[test]
+    fn inflates_stored_and_fixed_blocks() {
+        let text = b"hello hello hello png";
+        let stored = hex("7801011500eaff68656c6c6f2068656c6c6f2068656c6c6f20706e67573507e2");
+        let fixed = hex("78dacb48cdc9c957c840220bf2d201573507e2");
+        assert_eq!(inflate(&stored, 1024).unwrap(), text);
+        assert_eq!(inflate(&fixed, 1024).unwrap(), text);
+    }
+
+    #[test]
+    fn inflates_dynamic_blocks() {
+        let text: Vec<u8> = (0..60u8)
+            .flat_map(|i| {
+                std::iter::repeat_n(i % 7 + b'a', usize::from(i % 5 + 1)).chain(*b"aurora ")
+            })
+            .collect();
+        let dynamic = hex(
+            "78dae5d0c10dc0200c03c055ba5a480b4f2424f62f958cedce803ff875728898a38fb84a41c94cb47b05f5f9825ef1b686122b1b714610954d54213f8610156db129620449e1169b622711a2a22d3e452711a2a22d36c54e1274eaefbe5678e618",
+        );
+        assert_eq!(dynamic[2] >> 1 & 3, 2);
+        assert_eq!(inflate(&dynamic, text.len()).unwrap(), text);
+    }
+
+    #[test]
+    fn stops_at_the_limit() {
+        let fixed = hex("78dacb48cdc9c957c840220bf2d201573507e2");
+        assert!(inflate(&fixed, 10).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let png = encode_rgb(4, 4, &[200; 48]);
+        for len in [0, 7, 20, png.len() / 2, png.len() - 20] {
+            assert!(decode(&png[..len]).is_err(), "decoded {len} bytes");
+        }
+        let fixed = hex("78dacb48cdc9c957c840220bf2d201573507e2");
+        assert!(inflate(&fixed[..8], 1024).is_err());
+    }
+}
@@ -0,0 +1,539 @@
+//! QML live preview: shows a project's QML file on a device with
+//! `qmlscene` and relaunches it whenever a file under the project's `qml/`
+//! directory changes, reporting load errors as logging notifications.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::model::{LoggingLevel, LoggingMessageNotificationParam};
+use rmcp::{Peer, RoleServer, tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::task::AbortHandle;
+
+use super::{Device, ssh, tail_lines};
+use crate::error::{Error, Result};
+use crate::sdk::project::ProjectInfo;
+use crate::server::AuroraServer;
+
+/// How often the project's QML directory is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How long a relaunched scene gets to report load errors.
+const SETTLE: Duration = Duration::from_millis(1500);
+/// Logger name of the notifications.
+const LOGGER: &str = "qml-preview";
+/// The generated scene that hosts files other than an application window.
+const WRAPPER: &str = ".preview.qml";
+const LOG: &str = ".preview.log";
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PreviewStartParams {
+    /// Device name from the config, e.g. the emulator.
+    pub device: String,
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// QML file to show, relative to the project, e.g.
+    /// `qml/pages/MainPage.qml` (default: the app's main QML file). Pages
+    /// and components are hosted in an application window.
+    #[serde(default)]
+    pub file: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PreviewParams {
+    /// Preview id from `qml_preview_start`.
+    pub preview: u32,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct LoadError {
+    /// Relative to the project's QML directory when it is one of its files.
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PreviewInfo {
+    pub id: u32,
+    pub device: String,
+    /// The file shown, relative to the project's QML directory.
+    pub file: String,
+    /// Where the QML files are copied on the device.
+    pub remote_dir: String,
+    /// PID of `qmlscene` on the device.
+    pub pid: Option<u32>,
+    /// Relaunches after file changes so far.
+    pub reloads: u32,
+    pub running: bool,
+    /// Errors of the latest launch.
+    pub errors: Vec<LoadError>,
+    /// The end of the scene's output when it exited without a QML error.
+    pub output_tail: Vec<String>,
+}
+
+/// Sent as the data of a `notifications/message` after every relaunch.
+#[derive(Debug, Serialize)]
+struct ReloadEvent<'a> {
+    preview: u32,
+    reload: u32,
+    changed: &'a [String],
+    running: bool,
+    errors: &'a [LoadError],
+}
+
+struct Preview {
+    info: Arc<Mutex<PreviewInfo>>,
+    device: Device,
+    watcher: AbortHandle,
+}
+
+/// Running previews, shared by all sessions. They stop with the server.
+#[derive(Clone, Default)]
+pub struct Previews {
+    inner: Arc<Mutex<PreviewTable>>,
+}
+
+impl std::fmt::Debug for Previews {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Previews").finish_non_exhaustive()
+    }
+}
+
+#[derive(Default)]
+struct PreviewTable {
+    next_id: u32,
+    previews: BTreeMap<u32, Preview>,
+}
+
+impl Drop for PreviewTable {
+    fn drop(&mut self) {
+        for preview in self.previews.values() {
+            preview.watcher.abort();
+        }
+    }
+}
+
+impl Previews {
+    fn table(&self) -> std::sync::MutexGuard<'_, PreviewTable> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn next_id(&self) -> u32 {
+        let mut table = self.table();
+        table.next_id += 1;
+        table.next_id
+    }
+
+    fn info(&self, id: u32) -> Result<(Arc<Mutex<PreviewInfo>>, Device)> {
+        self.table()
+            .previews
+            .get(&id)
+            .map(|preview| (preview.info.clone(), preview.device.clone()))
+            .ok_or_else(|| Error::InvalidArgument(format!("no QML preview with id {id}")))
+    }
+
+    fn remove(&self, id: u32) -> Result<Preview> {
+        self.table()
+            .previews
+            .remove(&id)
+            .ok_or_else(|| Error::InvalidArgument(format!("no QML preview with id {id}")))
+    }
+}
+
+fn lock(info: &Mutex<PreviewInfo>) -> std::sync::MutexGuard<'_, PreviewInfo> {
+    info.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Modification times of the files below `dir`, by relative path.
+fn snapshot(dir: &Path) -> BTreeMap<String, SystemTime> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+            } else if let (Ok(relative), Ok(modified)) = (
+                path.strip_prefix(dir),
+                entry.metadata().and_then(|meta| meta.modified()),
+            ) {
+                files.insert(relative.to_string_lossy().into_owned(), modified);
+            }
+        }
+    }
+    files
+}
+
+/// The root object type of a QML document, e.g. `Page`.
+fn root_type(text: &str) -> Option<&str> {
+    text.lines()
+        .map(str::trim)
+        .find(|line| {
+            !line.is_empty()
+                && !line.starts_with("import ")
+                && !line.starts_with("pragma ")
+                && !line.starts_with("//")
+                && !line.starts_with("/*")
+                && !line.starts_with('*')
+        })
+        .map(|line| {
+            line.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                .next()
+                .unwrap_or("")
+        })
+        .map(|name| name.rsplit('.').next().unwrap_or(name))
+}
+
+/// A scene hosting `file`, or `None` when it is a window itself.
+fn wrapper(file: &str, text: &str, silica: bool) -> Option<String> {
+    let root = root_type(text).unwrap_or("");
+    let source = serde_json::to_string(file).unwrap_or_default();
+    match root {
+        "ApplicationWindow" | "Window" => None,
+        "Page" | "Dialog" if silica => Some(format!(
+            "import QtQuick 2.0\nimport Sailfish.Silica 1.0\n\n\
+             ApplicationWindow {{\n    initialPage: Qt.resolvedUrl({source})\n}}\n"
+        )),
+        _ if silica => Some(format!(
+            "import QtQuick 2.0\nimport Sailfish.Silica 1.0\n\n\
+             ApplicationWindow {{\n    initialPage: Component {{\n        Page {{\n            \
+             Loader {{\n                anchors.fill: parent\n                \
+             source: Qt.resolvedUrl({source})\n            }}\n        }}\n    }}\n}}\n"
+        )),
+        _ => Some(format!(
+            "import QtQuick 2.0\nimport QtQuick.Window 2.0\n\n\
+             Window {{\n    visible: true\n    width: 540\n    height: 960\n\n    \
+             Loader {{\n        anchors.fill: parent\n        source: Qt.resolvedUrl({source})\n    }}\n}}\n"
+        )),
+    }
+}
+
+/// A leading `12:` of `text` and the rest.
+fn split_number(text: &str) -> (Option<u32>, &str) {
+    match text.split_once(':') {
+        Some((number, rest))
+            if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            (number.parse().ok(), rest)
+        }
+        _ => (None, text),
+    }
+}
+
+/// QML errors in `qmlscene` output, e.g.
+/// `file:///tmp/aurora-mcp-preview-1/pages/MainPage.qml:12:5: Cannot assign to non-existent property "foo"`.
+fn parse_errors(output: &str, remote_dir: &str) -> Vec<LoadError> {
+    let own = format!("file://{remote_dir}/");
+    let mut errors: Vec<LoadError> = Vec::new();
+    for line in output.lines() {
+        let Some(at) = line.find("file://") else {
+            continue;
+        };
+        let location = &line[at..];
+        let Some(qml) = location.find(".qml") else {
+            continue;
+        };
+        let (path, rest) = location.split_at(qml + 4);
+        let rest = rest.strip_prefix(':').unwrap_or(rest);
+        let (line_number, rest) = split_number(rest);
+        let (column, rest) = split_number(rest);
+        let message = rest.trim_start_matches(':');
+        let file = path
+            .strip_prefix(&own)
+            .map(str::to_string)
+            .unwrap_or_else(|| path.trim_start_matches("file://").to_string());
+        let error = LoadError {
+            file: Some(file),
+            line: line_number,
+            column,
+            message: message.trim().to_string(),
+        };
+        // qmlscene repeats some errors through the component that failed.
+        if !errors
+            .iter()
+            .any(|e| e.file == error.file && e.line == error.line && e.message == error.message)
+        {
+            errors.push(error);
+        }
+    }
+    errors
+}
+
+/// Creates the directories of `files` and copies them to `remote_dir`.
+async fn upload(device: &Device, dir: &Path, remote_dir: &str, files: &[String]) -> Result<()> {
+    let dirs: BTreeSet<String> = files
+        .iter()
+        .map(|file| match file.rsplit_once('/') {
+            Some((parent, _)) => format!("{remote_dir}/{parent}"),
+            None => remote_dir.to_string(),
+        })
+        .collect();
+    let dirs: Vec<String> = dirs.iter().map(|dir| ssh::quote(dir)).collect();
+    device
+        .exec_checked(&format!("mkdir -p {}", dirs.join(" ")))
+        .await?;
+    for file in files {
+        device
+            .upload(&dir.join(file), &format!("{remote_dir}/{file}"))
+            .await?;
+    }
+    Ok(())
+}
+
+/// (Re)starts `qmlscene` on `entry` and collects its load errors.
+async fn launch(device: &Device, info: &Mutex<PreviewInfo>, entry: &str) -> Result<()> {
+    let (remote_dir, old) = {
+        let info = lock(info);
+        (info.remote_dir.clone(), info.pid)
+    };
+    let dir = ssh::quote(&remote_dir);
+    if let Some(pid) = old {
+        let _ = device
+            .exec(&format!("kill {pid} 2>/dev/null || true"))
+            .await;
+    }
+    let output = device
+        .exec(&format!(
+            "command -v qmlscene >/dev/null 2>&1 || {{ echo 'qmlscene is not installed; install qt5-qtdeclarative-qmlscene' >&2; exit 127; }}; \
+             cd {dir} || exit 1; setsid qmlscene -I {dir} {dir}/{entry} >{dir}/{LOG} 2>&1 </dev/null & echo $!",
+            entry = ssh::quote(entry),
+        ))
+        .await?;
+    if !output.success() {
+        return Err(Error::RemoteCommand {
+            device: device.name().to_string(),
+            status: output.status,
+            stderr: output.stderr.trim().to_string(),
+        });
+    }
+    let pid = output.stdout.trim().parse().ok();
+    tokio::time::sleep(SETTLE).await;
+    let running = match pid {
+        Some(pid) => device
+            .exec(&format!("kill -0 {pid} 2>/dev/null"))
+            .await
+            .is_ok_and(|output| output.success()),
+        None => false,
+    };
+    let log = device
+        .exec(&format!("cat {dir}/{LOG}"))
+        .await
+        .map(|output| output.stdout)
+        .unwrap_or_default();
+    let errors = parse_errors(&log, &remote_dir);
+    let mut info = lock(info);
+    info.pid = pid;
+    info.running = running;
+    info.output_tail = if running || !errors.is_empty() {
+        Vec::new()
+    } else {
+        tail_lines(&log, 20)
+    };
+    info.errors = errors;
+    Ok(())
+}
+
+/// Polls `dir` for changes and relaunches the preview after each.
+async fn watch(
+    device: Device,
+    info: Arc<Mutex<PreviewInfo>>,
+    dir: PathBuf,
+    entry: String,
+    peer: Peer<RoleServer>,
+) {
+    let mut known = snapshot(&dir);
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let current = snapshot(&dir);
+        let changed: Vec<String> = current
+            .iter()
+            .filter(|(file, modified)| known.get(*file) != Some(modified))
+            .map(|(file, _)| file.clone())
+            .collect();
+        known = current;
+        if changed.is_empty() {
+            continue;
+        }
+        let remote_dir = lock(&info).remote_dir.clone();
+        let result = match upload(&device, &dir, &remote_dir, &changed).await {
+            Ok(()) => launch(&device, &info, &entry).await,
+            Err(err) => Err(err),
+        };
+        let (level, data) = {
+            let mut info = lock(&info);
+            info.reloads += 1;
+            if let Err(err) = &result {
+                info.running = false;
+                info.errors = vec![LoadError {
+                    file: None,
+                    line: None,
+                    column: None,
+                    message: err.to_string(),
+                }];
+            }
+            let level = if info.errors.is_empty() && info.running {
+                LoggingLevel::Info
+            } else {
+                LoggingLevel::Error
+            };
+            let event = ReloadEvent {
+                preview: info.id,
+                reload: info.reloads,
+                changed: &changed,
+                running: info.running,
+                errors: &info.errors,
+            };
+            (level, serde_json::to_value(&event).unwrap_or_default())
+        };
+        let param = LoggingMessageNotificationParam {
+            level,
+            logger: Some(LOGGER.to_string()),
+            data,
+        };
+        if let Err(err) = peer.notify_logging_message(param).await {
+            tracing::debug!("QML preview notification: {err}");
+        }
+    }
+}
+
+#[tool_router(router = preview_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Start a live QML preview on a device: copies the project's qml/ directory to the device and shows a QML file (default: the app's main QML file; pages and components are hosted in a window) with qmlscene. Every change under qml/ is copied over and the scene relaunched; each reload is reported as a logging notification (logger qml-preview) with the QML load errors. Returns the preview id; end it with qml_preview_stop."
+    )]
+    pub async fn qml_preview_start(
+        &self,
+        Parameters(params): Parameters<PreviewStartParams>,
+        peer: Peer<RoleServer>,
+    ) -> Result<Json<PreviewInfo>> {
+        let device = self.devices().get(&params.device)?;
+        let project = self.state().config.sdk.project(params.project.as_deref())?;
+        let project_info = ProjectInfo::inspect(&project);
+        let Some(qml_dir) = project_info.qml_dir.clone() else {
+            return Err(Error::InvalidArgument(format!(
+                "{} has no qml directory",
+                project.display()
+            )));
+        };
+        let file_path = match &params.file {
+            Some(file) => project.join(file),
+            None => qml_dir.join(format!("{}.qml", project_info.name)),
+        };
+        let Ok(file) = file_path.strip_prefix(&qml_dir) else {
+            return Err(Error::InvalidArgument(format!(
+                "{} is not under {}",
+                file_path.display(),
+                qml_dir.display()
+            )));
+        };
+        let file = file.to_string_lossy().into_owned();
+        let text = std::fs::read_to_string(&file_path).map_err(|err| {
+            Error::InvalidArgument(format!("cannot read {}: {err}", file_path.display()))
+        })?;
+
+        let previews = &self.state().previews;
+        let id = previews.next_id();
+        let remote_dir = format!("/tmp/aurora-mcp-preview-{id}");
+        let files: Vec<String> = snapshot(&qml_dir).into_keys().collect();
+        upload(&device, &qml_dir, &remote_dir, &files).await?;
+        let entry = match wrapper(&file, &text, project_info.uses_silica) {
+            Some(scene) => {
+                let local = std::env::temp_dir().join(format!("aurora-mcp-preview-{id}.qml"));
+                std::fs::write(&local, scene)?;
+                let uploaded = device
+                    .upload(&local, &format!("{remote_dir}/{WRAPPER}"))
+                    .await;
+                let _ = std::fs::remove_file(&local);
+                uploaded?;
+                WRAPPER.to_string()
+            }
+            None => file.clone(),
+        };
+
+        let info = Arc::new(Mutex::new(PreviewInfo {
+            id,
+            device: params.device,
+            file,
+            remote_dir: remote_dir.clone(),
+            pid: None,
+            reloads: 0,
+            running: false,
+            errors: Vec::new(),
+            output_tail: Vec::new(),
+        }));
+        if let Err(err) = launch(&device, &info, &entry).await {
+            let _ = device
+                .exec(&format!("rm -rf {} || true", ssh::quote(&remote_dir)))
+                .await;
+            return Err(err);
+        }
+        let watcher = tokio::spawn(watch(device.clone(), info.clone(), qml_dir, entry, peer));
+        let snapshot = lock(&info).clone();
+        previews.table().previews.insert(
+            id,
+            Preview {
+                info,
+                device,
+                watcher: watcher.abort_handle(),
+            },
+        );
+        Ok(Json(snapshot))
+    }
+
+    #[tool(
+        description = "State of a QML preview: whether the scene is running, how often it was reloaded and the load errors of the latest launch.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn qml_preview_status(
+        &self,
+        Parameters(params): Parameters<PreviewParams>,
+    ) -> Result<Json<PreviewInfo>> {
+        let (info, device) = self.state().previews.info(params.preview)?;
+        let pid = lock(&info).pid;
+        if let Some(pid) = pid {
+            let running = device
+                .exec(&format!("kill -0 {pid} 2>/dev/null"))
+                .await
+                .is_ok_and(|output| output.success());
+            lock(&info).running = running;
+        }
+        Ok(Json(lock(&info).clone()))
+    }
+
+    #[tool(
+        description = "Stop a QML preview: stops watching the project, closes the scene and removes its files from the device."
+    )]
+    pub async fn qml_preview_stop(
+        &self,
+        Parameters(params): Parameters<PreviewParams>,
+    ) -> Result<Json<PreviewInfo>> {
+        let preview = self.state().previews.remove(params.preview)?;
+        preview.watcher.abort();
+        let mut info = lock(&preview.info).clone();
+        if let Some(pid) = info.pid {
+            let _ = preview
+                .device
+                .exec(&format!("kill {pid} 2>/dev/null || true"))
+                .await;
+        }
+        let _ = preview
+            .device
+            .exec(&format!("rm -rf {} || true", ssh::quote(&info.remote_dir)))
+            .await;
+        info.running = false;
+        Ok(Json(info))
+    }
+}
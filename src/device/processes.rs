@@ -0,0 +1,155 @@
+//! `device_processes`: a `ps`/`top`-style snapshot of a device.
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::ssh;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ProcessesParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Only include processes whose binary name contains this string.
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// Sort order: `cpu` (default) or `rss`.
+    #[serde(default)]
+    pub sort_by: SortBy,
+    /// Maximum number of processes to return (default 50).
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    #[default]
+    Cpu,
+    Rss,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ProcessSnapshot {
+    pub device: String,
+    /// 1, 5 and 15 minute load averages.
+    pub load_average: [f64; 3],
+    pub memory_total_kb: Option<u64>,
+    pub memory_available_kb: Option<u64>,
+    /// Number of processes matching the filter, before `limit` is applied.
+    pub matched: usize,
+    pub processes: Vec<ProcessInfo>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub ppid: u32,
+    pub user: String,
+    pub cpu_percent: f64,
+    pub rss_kb: u64,
+    pub name: String,
+    pub command: String,
+}
+
+impl ProcessInfo {
+    /// Binary name, preferring argv[0] since `comm` is truncated to 15 bytes.
+    pub fn binary(&self) -> &str {
+        self.command
+            .split_whitespace()
+            .next()
+            .and_then(|argv0| argv0.rsplit('/').next())
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&self.name)
+    }
+}
+
+pub async fn snapshot(server: &AuroraServer, params: ProcessesParams) -> Result<ProcessSnapshot> {
+    let device = server.devices().get(&params.device)?;
+    let command = ssh::sections(&[
+        "cat /proc/loadavg",
+        "cat /proc/meminfo",
+        "ps -eo pid=,ppid=,user=,pcpu=,rss=,comm=,args=",
+    ]);
+    let stdout = device.exec_checked(&command).await?;
+    let parse_error = |message: &str| Error::Parse {
+        device: params.device.clone(),
+        message: message.to_string(),
+    };
+
+    let [loadavg, meminfo, ps] = ssh::split_sections(&stdout)[..] else {
+        return Err(parse_error("missing output sections"));
+    };
+
+    let mut load_average = [0.0; 3];
+    for (slot, value) in load_average.iter_mut().zip(loadavg.split_whitespace()) {
+        *slot = value
+            .parse()
+            .map_err(|_| parse_error("bad /proc/loadavg"))?;
+    }
+
+    let mut processes: Vec<ProcessInfo> = ps.lines().filter_map(parse_ps_line).collect();
+    if let Some(filter) = params.filter.as_deref() {
+        processes.retain(|p| p.binary().contains(filter) || p.name.contains(filter));
+    }
+    match params.sort_by {
+        SortBy::Cpu => processes.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent)),
+        SortBy::Rss => processes.sort_by_key(|p| std::cmp::Reverse(p.rss_kb)),
+    }
+    let matched = processes.len();
+    processes.truncate(params.limit.unwrap_or(50));
+
+    Ok(ProcessSnapshot {
+        device: params.device,
+        load_average,
+        memory_total_kb: meminfo_kb(meminfo, "MemTotal"),
+        memory_available_kb: meminfo_kb(meminfo, "MemAvailable"),
+        matched,
+        processes,
+    })
+}
+
+fn parse_ps_line(line: &str) -> Option<ProcessInfo> {
+    let mut fields = line.split_whitespace();
+    let pid = fields.next()?.parse().ok()?;
+    let ppid = fields.next()?.parse().ok()?;
+    let user = fields.next()?.to_string();
+    let cpu_percent = fields.next()?.parse().ok()?;
+    let rss_kb = fields.next()?.parse().ok()?;
+    let name = fields.next()?.to_string();
+    let command = fields.collect::<Vec<_>>().join(" ");
+    Some(ProcessInfo {
+        pid,
+        ppid,
+        user,
+        cpu_percent,
+        rss_kb,
+        name,
+        command,
+    })
+}
+
+/// Reads a `Key:   1234 kB` entry from `/proc/meminfo`.
+pub fn meminfo_kb(meminfo: &str, key: &str) -> Option<u64> {
+    meminfo.lines().find_map(|line| {
+        let value = line.strip_prefix(key)?.strip_prefix(':')?;
+        value.split_whitespace().next()?.parse().ok()
+    })
+}
+
+#[tool_router(router = processes_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "List processes on a device with CPU and RSS usage, optionally filtered by binary name. Use it to confirm an app started and check its resource use.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn device_processes(
+        &self,
+        Parameters(params): Parameters<ProcessesParams>,
+    ) -> Result<Json<ProcessSnapshot>> {
+        snapshot(self, params).await.map(Json)
+    }
+}
@@ -0,0 +1,361 @@
+//! `profile_app`: samples a running app with `perf` on the device and
+//! summarizes the call stacks into folded stacks and hot functions.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rmcp::handler::server::tool::cached_schema_for_type;
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::CallToolResult;
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::{apps, ssh};
+use crate::error::{Error, Result, tool_result};
+use crate::resources::file_link;
+use crate::server::AuroraServer;
+
+const DEFAULT_DURATION_SECS: u64 = 10;
+const MAX_DURATION_SECS: u64 = 300;
+const DEFAULT_FREQUENCY: u32 = 99;
+const DEFAULT_TOP: usize = 20;
+
+/// Flame graph renderers reading folded stacks on stdin, in order of
+/// preference.
+const FLAMEGRAPH_TOOLS: &[&str] = &["inferno-flamegraph", "flamegraph.pl"];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ProfileParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Binary name of the running app, e.g. `ru.example.myapp`.
+    pub app: String,
+    /// How long to record (default 10, at most 300).
+    #[serde(default)]
+    pub duration_secs: Option<u64>,
+    /// Sampling frequency in Hz (default 99).
+    #[serde(default)]
+    pub frequency: Option<u32>,
+    /// How many hot functions and stacks to return (default 20).
+    #[serde(default)]
+    pub top: Option<usize>,
+    /// Local directory for the results. Defaults to a new directory under
+    /// `<data_dir>/profiles`.
+    #[serde(default)]
+    pub output_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct HotFunction {
+    pub function: String,
+    /// File name of the binary or library it is in.
+    pub library: Option<String>,
+    pub samples: u64,
+    pub percent: f64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct HotStack {
+    /// Frames from the outermost to the sampled one, `;`-separated.
+    pub stack: String,
+    pub samples: u64,
+    pub percent: f64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ProfileReport {
+    pub device: String,
+    pub app: String,
+    pub pid: u32,
+    pub duration_secs: u64,
+    pub samples: u64,
+    /// Local directory holding the results.
+    pub directory: PathBuf,
+    /// The raw recording, for `perf report -i` with the device's binaries.
+    pub perf_data: PathBuf,
+    /// One `frame;frame;... count` line per distinct stack, the input of
+    /// flame graph renderers.
+    pub folded_file: PathBuf,
+    /// Rendered when `inferno-flamegraph` or `flamegraph.pl` is on the
+    /// host's `PATH`.
+    pub flamegraph: Option<PathBuf>,
+    /// Functions by samples spent in the function itself.
+    pub top_self: Vec<HotFunction>,
+    /// Functions by samples with the function anywhere on the stack.
+    pub top_total: Vec<HotFunction>,
+    pub hot_stacks: Vec<HotStack>,
+}
+
+/// A frame of `perf script` output, e.g.
+/// `7f8a1b2c3d QString::arg(int) const+0x1c (/usr/lib64/libQt5Core.so.5)`,
+/// as the function name and library.
+fn parse_frame(line: &str) -> Option<(String, Option<String>)> {
+    let (_, rest) = line.trim().split_once(' ')?;
+    let (symbol, dso) = match rest.rsplit_once(" (") {
+        Some((symbol, dso)) => (symbol, dso.strip_suffix(')')),
+        None => (rest, None),
+    };
+    let library = dso
+        .filter(|dso| !dso.starts_with('['))
+        .map(|dso| dso.rsplit('/').next().unwrap_or(dso).to_string());
+    let symbol = match symbol.rfind("+0x") {
+        Some(at) => &symbol[..at],
+        None => symbol,
+    };
+    let function = if symbol == "[unknown]" {
+        format!("[{}]", library.as_deref().unwrap_or("unknown"))
+    } else {
+        symbol.to_string()
+    };
+    Some((function, library))
+}
+
+/// Stacks of `perf script` output, innermost frame first.
+fn parse_samples(script: &str) -> Vec<Vec<(String, Option<String>)>> {
+    let mut samples = Vec::new();
+    let mut current: Option<Vec<_>> = None;
+    for line in script.lines() {
+        if line.trim().is_empty() {
+            samples.extend(current.take().filter(|stack: &Vec<_>| !stack.is_empty()));
+        } else if line.starts_with(char::is_whitespace) {
+            if let Some(stack) = &mut current
+                && let Some(frame) = parse_frame(line)
+            {
+                stack.push(frame);
+            }
+        } else if !line.starts_with('#') {
+            samples.extend(
+                current
+                    .replace(Vec::new())
+                    .filter(|stack| !stack.is_empty()),
+            );
+        }
+    }
+    samples.extend(current.filter(|stack| !stack.is_empty()));
+    samples
+}
+
+fn percent(samples: u64, total: u64) -> f64 {
+    (samples as f64 * 1000.0 / total.max(1) as f64).round() / 10.0
+}
+
+fn top_functions(
+    counts: BTreeMap<(String, Option<String>), u64>,
+    total: u64,
+    top: usize,
+) -> Vec<HotFunction> {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+        .into_iter()
+        .take(top)
+        .map(|((function, library), samples)| HotFunction {
+            function,
+            library,
+            samples,
+            percent: percent(samples, total),
+        })
+        .collect()
+}
+
+/// Renders `folded` with the first flame graph tool on `PATH`.
+async fn render_flamegraph(folded: &str, title: &str, svg: &Path) -> Option<PathBuf> {
+    for tool in FLAMEGRAPH_TOOLS {
+        let Ok(mut child) = Command::new(tool)
+            .args(["--title", title])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+        else {
+            continue;
+        };
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let input = folded.to_string();
+        let write = tokio::spawn(async move { stdin.write_all(input.as_bytes()).await });
+        let output = child.wait_with_output().await.ok()?;
+        let _ = write.await;
+        if output.status.success() && !output.stdout.is_empty() {
+            std::fs::write(svg, &output.stdout).ok()?;
+            return Some(svg.to_path_buf());
+        }
+    }
+    None
+}
+
+pub async fn profile(server: &AuroraServer, params: ProfileParams) -> Result<ProfileReport> {
+    let device = server.devices().get(&params.device)?;
+    apps::check_app_name(&params.app)?;
+    let duration_secs = params.duration_secs.unwrap_or(DEFAULT_DURATION_SECS);
+    if duration_secs == 0 || duration_secs > MAX_DURATION_SECS {
+        return Err(Error::InvalidArgument(format!(
+            "duration_secs must be between 1 and {MAX_DURATION_SECS}"
+        )));
+    }
+    let frequency = params.frequency.unwrap_or(DEFAULT_FREQUENCY).max(1);
+
+    let directory = match params.output_dir {
+        Some(dir) => dir,
+        None => {
+            let stamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            server
+                .state()
+                .config
+                .data_dir()
+                .join("profiles")
+                .join(format!("{}-{}-{stamp}", params.device, params.app))
+        }
+    };
+    std::fs::create_dir_all(&directory)?;
+
+    // `perf script` runs on the device, where the binaries are, so the
+    // stacks come back symbolized.
+    let remote = format!("/tmp/aurora-mcp-perf-{}", params.app);
+    let (data, log, txt) = (
+        ssh::quote(&format!("{remote}.data")),
+        ssh::quote(&format!("{remote}.log")),
+        ssh::quote(&format!("{remote}.txt")),
+    );
+    let binary = ssh::quote(&format!("/usr/bin/{}", params.app));
+    let command = format!(
+        "command -v perf >/dev/null 2>&1 || {{ echo 'perf is not installed; install the perf package' >&2; exit 127; }}; \
+         pid=$(pidof -s {binary}) || {{ echo {not_running} >&2; exit 3; }}; \
+         perf record -F {frequency} -g -p \"$pid\" -o {data} -- sleep {duration_secs} >{log} 2>&1 \
+         && perf script -i {data} >{txt} 2>>{log} \
+         || {{ cat {log} >&2; exit 4; }}; \
+         echo \"$pid\"",
+        not_running = ssh::quote(&format!("{} is not running", params.app)),
+    );
+    let output = device
+        .exec_with_timeout(&command, Duration::from_secs(duration_secs + 120))
+        .await?;
+    if !output.success() {
+        return Err(Error::RemoteCommand {
+            device: params.device,
+            status: output.status,
+            stderr: output.stderr.trim().to_string(),
+        });
+    }
+    let pid = output.stdout.trim().parse().unwrap_or_default();
+
+    let perf_data = directory.join("perf.data");
+    let script_file = directory.join("perf.script.txt");
+    let downloaded = async {
+        device
+            .download(&format!("{remote}.data"), &perf_data)
+            .await?;
+        device
+            .download(&format!("{remote}.txt"), &script_file)
+            .await
+    }
+    .await;
+    let _ = device
+        .exec(&format!("rm -f {data} {txt} {log} || true"))
+        .await;
+    downloaded?;
+
+    let script = std::fs::read_to_string(&script_file)?;
+    let stacks = parse_samples(&script);
+    let total = stacks.len() as u64;
+    if total == 0 {
+        return Err(Error::InvalidArgument(format!(
+            "perf recorded no samples of {} in {duration_secs}s; is it busy?",
+            params.app
+        )));
+    }
+
+    let mut folded: BTreeMap<String, u64> = BTreeMap::new();
+    let mut self_counts = BTreeMap::new();
+    let mut total_counts = BTreeMap::new();
+    for stack in &stacks {
+        let line = stack
+            .iter()
+            .rev()
+            .map(|(function, _)| function.replace(';', ":"))
+            .collect::<Vec<_>>()
+            .join(";");
+        *folded.entry(line).or_default() += 1;
+        *self_counts.entry(stack[0].clone()).or_default() += 1;
+        for frame in stack.iter().collect::<BTreeSet<_>>() {
+            *total_counts.entry(frame.clone()).or_default() += 1;
+        }
+    }
+    let folded_text: String = folded
+        .iter()
+        .map(|(stack, count)| format!("{stack} {count}\n"))
+        .collect();
+    let folded_file = directory.join("stacks.folded");
+    std::fs::write(&folded_file, &folded_text)?;
+    let flamegraph = render_flamegraph(
+        &folded_text,
+        &format!("{} on {}", params.app, params.device),
+        &directory.join("flamegraph.svg"),
+    )
+    .await;
+
+    let top = params.top.unwrap_or(DEFAULT_TOP);
+    let mut hot_stacks: Vec<_> = folded.into_iter().collect();
+    hot_stacks.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(ProfileReport {
+        device: params.device,
+        app: params.app,
+        pid,
+        duration_secs,
+        samples: total,
+        directory,
+        perf_data,
+        folded_file,
+        flamegraph,
+        top_self: top_functions(self_counts, total, top),
+        top_total: top_functions(total_counts, total, top),
+        hot_stacks: hot_stacks
+            .into_iter()
+            .take(top)
+            .map(|(stack, samples)| HotStack {
+                stack,
+                samples,
+                percent: percent(samples, total),
+            })
+            .collect(),
+    })
+}
+
+#[tool_router(router = profile_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Profile a running app on a device: records call stacks with perf for duration_secs, pulls the recording and returns the hottest functions (self and total), the hottest stacks, and links to folded stacks and, when a renderer is installed on the host, a flame graph SVG.",
+        output_schema = cached_schema_for_type::<ProfileReport>()
+    )]
+    pub async fn profile_app(
+        &self,
+        Parameters(params): Parameters<ProfileParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        tool_result(profile(self, params).await.and_then(|report| {
+            let mut links = vec![file_link(&report.folded_file, Some("text/plain"))];
+            links.extend(
+                report
+                    .flamegraph
+                    .iter()
+                    .map(|svg| file_link(svg, Some("image/svg+xml"))),
+            );
+            links.push(file_link(
+                &report.perf_data,
+                Some("application/octet-stream"),
+            ));
+            let mut result = CallToolResult::structured(
+                serde_json::to_value(&report).map_err(std::io::Error::other)?,
+            );
+            result.content.extend(links);
+            Ok(result)
+        }))
+    }
+}
@@ -0,0 +1,338 @@
+//! `compare_screenshots`: captures the device screen through lipstick and
+//! compares it with a stored baseline, pixel by pixel and with a windowed
+//! SSIM that tolerates antialiasing and compression noise.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use rmcp::handler::server::tool::cached_schema_for_type;
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::{CallToolResult, Content};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::dbus::{self, Bus};
+use super::{Device, png};
+use crate::error::{Error, Result, tool_result};
+use crate::resources::file_link;
+use crate::server::AuroraServer;
+
+/// Where lipstick writes the capture on the device.
+const REMOTE_SCREENSHOT: &str = "/tmp/aurora-mcp-screenshot.png";
+const DEFAULT_THRESHOLD: u8 = 24;
+const DEFAULT_MIN_SIMILARITY: f64 = 0.99;
+/// Side of the square windows SSIM is computed over.
+const WINDOW: u32 = 8;
+/// Diff images up to this size are also returned inline.
+const MAX_INLINE_IMAGE: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    fn contains(&self, x: u32, y: u32) -> bool {
+        x >= self.x && y >= self.y && x - self.x < self.width && y - self.y < self.height
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CompareScreenshotsParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Baseline name, e.g. `main-page-dark`; stored as `<name>.png`.
+    pub baseline: String,
+    /// Directory of baselines. Defaults to `<data_dir>/baselines`.
+    #[serde(default)]
+    pub baseline_dir: Option<PathBuf>,
+    /// Largest per-channel difference (0-255) still counted as unchanged
+    /// (default 24).
+    #[serde(default)]
+    pub threshold: Option<u8>,
+    /// Areas left out of the comparison, e.g. the status bar clock.
+    #[serde(default)]
+    pub ignore: Vec<Region>,
+    /// Replace the baseline with the current screen instead of comparing.
+    #[serde(default)]
+    pub update: bool,
+    /// Similarity (0-1) at which the screens count as matching (default
+    /// 0.99).
+    #[serde(default)]
+    pub min_similarity: Option<f64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ScreenshotComparison {
+    pub device: String,
+    pub baseline: PathBuf,
+    pub screenshot: PathBuf,
+    /// The screen dimmed to grey with changed pixels in red; absent when
+    /// the baseline was just created.
+    pub diff: Option<PathBuf>,
+    /// True when there was no baseline yet or `update` was set, so the
+    /// current screen became the baseline.
+    pub baseline_created: bool,
+    pub width: u32,
+    pub height: u32,
+    /// Pixels with a channel differing by more than the threshold.
+    pub changed_pixels: u64,
+    pub changed_percent: f64,
+    /// Bounding box of the changed pixels.
+    pub changed_region: Option<Region>,
+    /// Mean structural similarity of the luma over 8x8 windows, 1.0 for
+    /// identical screens.
+    pub similarity: f64,
+    /// Whether `similarity` reaches `min_similarity`.
+    pub matches: bool,
+}
+
+fn luma([r, g, b, _]: [u8; 4]) -> f64 {
+    0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64
+}
+
+/// Mean SSIM of the luma of `a` and `b` over non-overlapping windows,
+/// skipping windows entirely inside `ignore`.
+fn ssim(a: &png::Image, b: &png::Image, ignore: &[Region]) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+    let mut total = 0.0;
+    let mut windows = 0u64;
+    for wy in (0..a.height).step_by(WINDOW as usize) {
+        for wx in (0..a.width).step_by(WINDOW as usize) {
+            let (mut n, mut sum_a, mut sum_b) = (0.0, 0.0, 0.0);
+            let (mut sq_a, mut sq_b, mut prod) = (0.0, 0.0, 0.0);
+            for y in wy..(wy + WINDOW).min(a.height) {
+                for x in wx..(wx + WINDOW).min(a.width) {
+                    if ignore.iter().any(|region| region.contains(x, y)) {
+                        continue;
+                    }
+                    let (la, lb) = (luma(a.pixel(x, y)), luma(b.pixel(x, y)));
+                    n += 1.0;
+                    sum_a += la;
+                    sum_b += lb;
+                    sq_a += la * la;
+                    sq_b += lb * lb;
+                    prod += la * lb;
+                }
+            }
+            if n == 0.0 {
+                continue;
+            }
+            let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+            let var_a = (sq_a / n - mean_a * mean_a).max(0.0);
+            let var_b = (sq_b / n - mean_b * mean_b).max(0.0);
+            let cov = prod / n - mean_a * mean_b;
+            total += ((2.0 * mean_a * mean_b + C1) * (2.0 * cov + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2));
+            windows += 1;
+        }
+    }
+    if windows == 0 {
+        1.0
+    } else {
+        (total / windows as f64).clamp(0.0, 1.0)
+    }
+}
+
+struct PixelDiff {
+    changed: u64,
+    compared: u64,
+    region: Option<Region>,
+    /// The diff image as RGB.
+    rgb: Vec<u8>,
+}
+
+fn pixel_diff(
+    baseline: &png::Image,
+    current: &png::Image,
+    threshold: u8,
+    ignore: &[Region],
+) -> PixelDiff {
+    let mut diff = PixelDiff {
+        changed: 0,
+        compared: 0,
+        region: None,
+        rgb: Vec::with_capacity((current.width * current.height * 3) as usize),
+    };
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+    for y in 0..current.height {
+        for x in 0..current.width {
+            let (a, b) = (baseline.pixel(x, y), current.pixel(x, y));
+            if ignore.iter().any(|region| region.contains(x, y)) {
+                diff.rgb.extend_from_slice(&[0, 0, 64]);
+                continue;
+            }
+            diff.compared += 1;
+            if a.iter().zip(&b).any(|(a, b)| a.abs_diff(*b) > threshold) {
+                diff.changed += 1;
+                (min_x, min_y) = (min_x.min(x), min_y.min(y));
+                (max_x, max_y) = (max_x.max(x), max_y.max(y));
+                diff.rgb.extend_from_slice(&[255, 0, 0]);
+            } else {
+                let grey = (64.0 + luma(b) / 3.0) as u8;
+                diff.rgb.extend_from_slice(&[grey, grey, grey]);
+            }
+        }
+    }
+    if diff.changed > 0 {
+        diff.region = Some(Region {
+            x: min_x,
+            y: min_y,
+            width: max_x - min_x + 1,
+            height: max_y - min_y + 1,
+        });
+    }
+    diff
+}
+
+fn read_png(path: &Path) -> Result<png::Image> {
+    png::decode(&std::fs::read(path)?)
+        .map_err(|e| Error::InvalidArgument(format!("{}: {e}", path.display())))
+}
+
+/// Saves the device screen to `local` through lipstick's screenshot API.
+async fn capture(device: &Device, local: &Path) -> Result<()> {
+    dbus::call(
+        device,
+        Bus::Session,
+        "org.nemomobile.lipstick",
+        "/org/nemomobile/lipstick/screenshot",
+        "org.nemomobile.lipstick.saveScreenshot",
+        &[format!("string:{REMOTE_SCREENSHOT}")],
+    )
+    .await?;
+    let downloaded = device.download(REMOTE_SCREENSHOT, local).await;
+    let _ = device
+        .exec(&format!("rm -f {REMOTE_SCREENSHOT} || true"))
+        .await;
+    downloaded.map(|_| ())
+}
+
+pub async fn compare(
+    server: &AuroraServer,
+    params: CompareScreenshotsParams,
+) -> Result<ScreenshotComparison> {
+    let device = server.devices().get(&params.device)?;
+    if params.baseline.is_empty()
+        || params.baseline.starts_with('.')
+        || !params
+            .baseline
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+    {
+        return Err(Error::InvalidArgument(format!(
+            "baseline '{}' must be a name of letters, digits, '.', '_' and '-'",
+            params.baseline
+        )));
+    }
+    let min_similarity = params.min_similarity.unwrap_or(DEFAULT_MIN_SIMILARITY);
+    if !(0.0..=1.0).contains(&min_similarity) {
+        return Err(Error::InvalidArgument(
+            "min_similarity must be between 0 and 1".to_string(),
+        ));
+    }
+    let threshold = params.threshold.unwrap_or(DEFAULT_THRESHOLD);
+
+    let data_dir = server.state().config.data_dir();
+    let baseline_dir = params
+        .baseline_dir
+        .unwrap_or_else(|| data_dir.join("baselines"));
+    let screenshots = data_dir.join("screenshots");
+    std::fs::create_dir_all(&baseline_dir)?;
+    std::fs::create_dir_all(&screenshots)?;
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let stem = format!("{}-{}-{stamp}", params.device, params.baseline);
+    let screenshot = screenshots.join(format!("{stem}.png"));
+    capture(&device, &screenshot).await?;
+    let current = read_png(&screenshot)?;
+
+    let baseline = baseline_dir.join(format!("{}.png", params.baseline));
+    if params.update || !baseline.exists() {
+        std::fs::copy(&screenshot, &baseline)?;
+        return Ok(ScreenshotComparison {
+            device: params.device,
+            baseline,
+            screenshot,
+            diff: None,
+            baseline_created: true,
+            width: current.width,
+            height: current.height,
+            changed_pixels: 0,
+            changed_percent: 0.0,
+            changed_region: None,
+            similarity: 1.0,
+            matches: true,
+        });
+    }
+    let expected = read_png(&baseline)?;
+    if (expected.width, expected.height) != (current.width, current.height) {
+        return Err(Error::InvalidArgument(format!(
+            "the screen is {}x{} but baseline '{}' is {}x{}; capture it again with update=true",
+            current.width, current.height, params.baseline, expected.width, expected.height
+        )));
+    }
+
+    let diff = pixel_diff(&expected, &current, threshold, &params.ignore);
+    let similarity = (ssim(&expected, &current, &params.ignore) * 10000.0).round() / 10000.0;
+    let diff_file = screenshots.join(format!("{stem}.diff.png"));
+    std::fs::write(
+        &diff_file,
+        png::encode_rgb(current.width, current.height, &diff.rgb),
+    )?;
+    Ok(ScreenshotComparison {
+        device: params.device,
+        baseline,
+        screenshot,
+        diff: Some(diff_file),
+        baseline_created: false,
+        width: current.width,
+        height: current.height,
+        changed_pixels: diff.changed,
+        changed_percent: (diff.changed as f64 * 10000.0 / diff.compared.max(1) as f64).round()
+            / 100.0,
+        changed_region: diff.region,
+        similarity,
+        matches: similarity >= min_similarity,
+    })
+}
+
+#[tool_router(router = screenshot_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Capture the device screen and compare it with a stored baseline PNG: returns the share of changed pixels, their bounding box, an SSIM similarity score and whether it reaches min_similarity, plus a diff image with changes in red. The first call for a baseline name (or update=true) stores the current screen as the baseline. Use ignore to mask regions such as the status bar clock.",
+        output_schema = cached_schema_for_type::<ScreenshotComparison>()
+    )]
+    pub async fn compare_screenshots(
+        &self,
+        Parameters(params): Parameters<CompareScreenshotsParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        tool_result(compare(self, params).await.and_then(|report| {
+            let mut content = vec![
+                file_link(&report.baseline, Some("image/png")),
+                file_link(&report.screenshot, Some("image/png")),
+            ];
+            if let Some(diff) = &report.diff {
+                content.push(file_link(diff, Some("image/png")));
+                let image = std::fs::read(diff)?;
+                if image.len() <= MAX_INLINE_IMAGE {
+                    content.push(Content::image(BASE64.encode(image), "image/png"));
+                }
+            }
+            let mut result = CallToolResult::structured(
+                serde_json::to_value(&report).map_err(std::io::Error::other)?,
+            );
+            result.content.extend(content);
+            Ok(result)
+        }))
+    }
+}
@@ -0,0 +1,370 @@
+//! `device_security_audit`: developer mode, SSH authentication and
+//! listening ports, summed up as a risk report.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use super::network::{InterfaceAddress, parse_ip_addr_line};
+use super::{DeviceParams, ssh};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// TCP state `LISTEN` in `/proc/net/tcp`.
+const TCP_LISTEN: &str = "0A";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SecurityReport {
+    pub device: String,
+    /// Highest severity among the findings.
+    pub risk: Severity,
+    /// `devel-su` is installed, i.e. developer mode is enabled.
+    pub developer_mode: bool,
+    pub ssh: SshPosture,
+    pub listening: Vec<ListeningPort>,
+    pub findings: Vec<Finding>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SshPosture {
+    /// False when the sshd config could not be read; the settings below are
+    /// then unknown.
+    pub config_readable: bool,
+    /// Effective setting, with OpenSSH defaults for unset options.
+    pub password_authentication: Option<bool>,
+    pub pubkey_authentication: Option<bool>,
+    pub permit_empty_passwords: Option<bool>,
+    /// `yes`, `no`, `prohibit-password` or `forced-commands-only`.
+    pub permit_root_login: Option<String>,
+    /// Keys in the device user's `~/.ssh/authorized_keys`.
+    pub authorized_keys: usize,
+    /// sshd accepts connections on a non-loopback address.
+    pub exposed: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ListeningPort {
+    pub address: String,
+    pub port: u16,
+    /// Reachable from other hosts: bound to a wildcard or non-loopback
+    /// address.
+    pub exposed: bool,
+    /// Well-known service on this port, if any.
+    pub service: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Finding {
+    pub severity: Severity,
+    pub title: String,
+    pub detail: String,
+    pub recommendation: String,
+}
+
+/// The value of the first occurrence of `option`; sshd uses the first one.
+fn sshd_option<'a>(config: &'a str, option: &str) -> Option<&'a str> {
+    config.lines().find_map(|line| {
+        let line = line.trim();
+        if line.starts_with('#') {
+            return None;
+        }
+        let (key, value) = line.split_once(char::is_whitespace)?;
+        key.eq_ignore_ascii_case(option).then(|| value.trim())
+    })
+}
+
+fn yes(value: &str) -> bool {
+    value.eq_ignore_ascii_case("yes")
+}
+
+/// Decodes a `/proc/net/tcp{,6}` address: hex words in host (little
+/// endian) byte order, then a hex port.
+fn parse_proc_address(field: &str) -> Option<(IpAddr, u16)> {
+    let (address, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let mut bytes = Vec::with_capacity(16);
+    for chunk in address.as_bytes().chunks(8) {
+        let word = u32::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+        bytes.extend(word.to_le_bytes());
+    }
+    let ip = match bytes.len() {
+        4 => IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3])),
+        16 => {
+            let octets: [u8; 16] = bytes.try_into().ok()?;
+            let ip = Ipv6Addr::from(octets);
+            ip.to_ipv4_mapped().map_or(IpAddr::V6(ip), IpAddr::V4)
+        }
+        _ => return None,
+    };
+    Some((ip, port))
+}
+
+fn service(port: u16) -> Option<&'static str> {
+    Some(match port {
+        22 => "ssh",
+        53 => "dns",
+        80 | 8080 => "http",
+        443 => "https",
+        1234 | 2345 => "gdbserver",
+        5037 | 5555 => "adb",
+        5900 => "vnc",
+        _ => return None,
+    })
+}
+
+fn parse_listening(output: &str) -> Vec<ListeningPort> {
+    let mut ports: Vec<ListeningPort> = Vec::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [_, local, _, state, ..] = fields[..] else {
+            continue;
+        };
+        if state != TCP_LISTEN {
+            continue;
+        }
+        let Some((ip, port)) = parse_proc_address(local) else {
+            continue;
+        };
+        let address = ip.to_string();
+        if ports.iter().any(|p| p.port == port && p.address == address) {
+            continue;
+        }
+        ports.push(ListeningPort {
+            exposed: !ip.is_loopback(),
+            service: service(port).map(str::to_string),
+            address,
+            port,
+        });
+    }
+    ports.sort_by_key(|p| (p.port, p.address.clone()));
+    ports
+}
+
+/// Non-loopback interfaces with an address, e.g. `wlan0` or `rndis0`.
+fn reachable_interfaces(interfaces: &[InterfaceAddress]) -> Vec<String> {
+    let mut names: Vec<String> = interfaces
+        .iter()
+        .filter(|address| address.interface != "lo" && address.family == "inet")
+        .map(|address| address.interface.clone())
+        .collect();
+    names.dedup();
+    names
+}
+
+fn findings(
+    developer_mode: bool,
+    ssh: &SshPosture,
+    listening: &[ListeningPort],
+    interfaces: &[String],
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut add = |severity, title: &str, detail: String, recommendation: &str| {
+        findings.push(Finding {
+            severity,
+            title: title.to_string(),
+            detail,
+            recommendation: recommendation.to_string(),
+        });
+    };
+    let wireless: Vec<&String> = interfaces
+        .iter()
+        .filter(|name| name.starts_with("wlan"))
+        .collect();
+
+    if developer_mode {
+        add(
+            Severity::Medium,
+            "Developer mode is enabled",
+            "devel-su is installed, so anyone with the developer password can become root."
+                .to_string(),
+            "Turn developer mode off in Settings before handing the device out, or at least set a strong developer password.",
+        );
+    }
+    if !ssh.config_readable {
+        add(
+            Severity::Info,
+            "sshd config not readable",
+            "/etc/ssh/sshd_config could not be read without root; SSH settings are unknown."
+                .to_string(),
+            "Check the settings manually via devel-su.",
+        );
+    }
+    if ssh.exposed && ssh.password_authentication == Some(true) {
+        let (severity, reach) = if wireless.is_empty() {
+            (Severity::Medium, "over USB networking".to_string())
+        } else {
+            (
+                Severity::High,
+                format!(
+                    "over Wi-Fi ({})",
+                    wireless
+                        .iter()
+                        .map(|name| name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            )
+        };
+        add(
+            severity,
+            "SSH accepts passwords from the network",
+            format!(
+                "sshd listens on a non-loopback address and allows password logins, reachable {reach}."
+            ),
+            "Set PasswordAuthentication no and use key authentication, or turn off remote connections in developer settings.",
+        );
+    }
+    if ssh.permit_empty_passwords == Some(true) {
+        add(
+            Severity::High,
+            "SSH permits empty passwords",
+            "PermitEmptyPasswords is yes.".to_string(),
+            "Set PermitEmptyPasswords no.",
+        );
+    }
+    if ssh.permit_root_login.as_deref().is_some_and(yes) {
+        add(
+            Severity::High,
+            "SSH permits root logins with a password",
+            "PermitRootLogin is yes.".to_string(),
+            "Set PermitRootLogin prohibit-password or no.",
+        );
+    }
+    if ssh.authorized_keys > 0 {
+        add(
+            Severity::Info,
+            "Authorized SSH keys present",
+            format!(
+                "{} key(s) in ~/.ssh/authorized_keys can log in without a password.",
+                ssh.authorized_keys
+            ),
+            "Remove keys of hosts that should no longer have access.",
+        );
+    }
+    let others: Vec<&ListeningPort> = listening
+        .iter()
+        .filter(|port| port.exposed && port.service.as_deref() != Some("ssh"))
+        .collect();
+    if !others.is_empty() {
+        let severity = if others
+            .iter()
+            .any(|port| matches!(port.service.as_deref(), Some("gdbserver" | "adb" | "vnc")))
+        {
+            Severity::High
+        } else {
+            Severity::Low
+        };
+        add(
+            severity,
+            "Services listening on the network",
+            others
+                .iter()
+                .map(|port| {
+                    let address = port
+                        .address
+                        .parse()
+                        .map(|ip| SocketAddr::new(ip, port.port).to_string())
+                        .unwrap_or_else(|_| format!("{}:{}", port.address, port.port));
+                    match &port.service {
+                        Some(service) => format!("{address} ({service})"),
+                        None => address,
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            "Stop debug servers and other services that do not need to be reachable from other hosts.",
+        );
+    }
+    findings.sort_by_key(|finding| std::cmp::Reverse(finding.severity));
+    findings
+}
+
+#[tool_router(router = security_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Audit a device's security posture: developer mode, SSH password vs key authentication, root and empty-password logins, authorized keys and TCP ports open to the network. Returns findings with severities and an overall risk, e.g. to check a test device before a demo.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn device_security_audit(
+        &self,
+        Parameters(params): Parameters<DeviceParams>,
+    ) -> Result<Json<SecurityReport>> {
+        let device = self.devices().get(&params.device)?;
+        let command = ssh::sections(&[
+            "command -v devel-su || true",
+            "cat /etc/ssh/sshd_config 2>/dev/null || true",
+            "cat \"$HOME/.ssh/authorized_keys\" 2>/dev/null || true",
+            "cat /proc/net/tcp /proc/net/tcp6 2>/dev/null || true",
+            "ip -o addr show",
+        ]);
+        let stdout = device.exec_checked(&command).await?;
+        let [devel_su, sshd_config, keys, tcp, addrs] = ssh::split_sections(&stdout)[..] else {
+            return Err(Error::Parse {
+                device: device.name().to_string(),
+                message: "missing output sections".to_string(),
+            });
+        };
+
+        let listening = parse_listening(tcp);
+        let interfaces: Vec<InterfaceAddress> =
+            addrs.lines().filter_map(parse_ip_addr_line).collect();
+        let config_readable = !sshd_config.trim().is_empty();
+        let option = |name: &str, default: &str| {
+            config_readable.then(|| {
+                sshd_option(sshd_config, name)
+                    .unwrap_or(default)
+                    .to_string()
+            })
+        };
+        let ssh_ports: Vec<u16> = match sshd_option(sshd_config, "Port") {
+            Some(port) => port.parse().ok().into_iter().collect(),
+            None => vec![22],
+        };
+        let ssh = SshPosture {
+            config_readable,
+            password_authentication: option("PasswordAuthentication", "yes").map(|v| yes(&v)),
+            pubkey_authentication: option("PubkeyAuthentication", "yes").map(|v| yes(&v)),
+            permit_empty_passwords: option("PermitEmptyPasswords", "no").map(|v| yes(&v)),
+            permit_root_login: option("PermitRootLogin", "prohibit-password"),
+            authorized_keys: keys
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .count(),
+            exposed: listening
+                .iter()
+                .any(|port| port.exposed && ssh_ports.contains(&port.port)),
+        };
+        let developer_mode = !devel_su.trim().is_empty();
+        let findings = findings(
+            developer_mode,
+            &ssh,
+            &listening,
+            &reachable_interfaces(&interfaces),
+        );
+        Ok(Json(SecurityReport {
+            device: params.device,
+            risk: findings
+                .iter()
+                .map(|finding| finding.severity)
+                .max()
+                .unwrap_or(Severity::Info),
+            developer_mode,
+            ssh,
+            listening,
+            findings,
+        }))
+    }
+}
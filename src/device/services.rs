@@ -0,0 +1,247 @@
+//! systemd `--user` units on a device: listing, starting and stopping
+//! them, and reading their status and journal.
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{Device, dbus, ssh};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Properties read by `systemctl show` for [`UnitStatus`].
+const STATUS_PROPERTIES: &str = "Id,Description,LoadState,ActiveState,SubState,UnitFileState,MainPID,ExecMainStatus,NRestarts,ActiveEnterTimestamp";
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListUnitsParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Include inactive and failed units, not only loaded active ones.
+    #[serde(default)]
+    pub all: bool,
+    /// Only units whose name contains this substring.
+    #[serde(default)]
+    pub filter: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UnitList {
+    pub device: String,
+    pub units: Vec<UnitSummary>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UnitSummary {
+    pub unit: String,
+    pub load: String,
+    /// `active`, `inactive`, `failed`, ...
+    pub active: String,
+    /// Finer state, e.g. `running` or `exited`.
+    pub sub: String,
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UnitParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Unit name, e.g. `ru.example.sync.service`.
+    pub unit: String,
+    /// Journal lines to include (default 50, 0 to skip).
+    #[serde(default)]
+    pub log_lines: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitAction {
+    Start,
+    Stop,
+    Restart,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UnitControlParams {
+    /// Device name from the config.
+    pub device: String,
+    pub unit: String,
+    pub action: UnitAction,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UnitStatus {
+    pub device: String,
+    pub unit: String,
+    pub description: String,
+    pub load_state: String,
+    pub active_state: String,
+    pub sub_state: String,
+    /// `enabled`, `disabled`, `static`, ... Empty for transient units.
+    pub unit_file_state: String,
+    pub main_pid: Option<u32>,
+    /// Exit status of the last main process run.
+    pub exit_status: Option<i32>,
+    pub restarts: Option<u32>,
+    pub active_since: Option<String>,
+    pub log: Vec<String>,
+}
+
+/// Environment `systemctl --user` and `journalctl --user` need in a
+/// non-interactive SSH session.
+pub fn user_env() -> String {
+    format!(
+        "export XDG_RUNTIME_DIR=\"${{XDG_RUNTIME_DIR:-/run/user/$(id -u)}}\"; {}",
+        dbus::SESSION_ENV
+    )
+}
+
+fn unit_arg(unit: &str) -> Result<String> {
+    let valid = !unit.is_empty()
+        && unit
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "@._:-\\".contains(c));
+    if !valid {
+        return Err(Error::InvalidArgument(format!(
+            "'{unit}' is not a unit name"
+        )));
+    }
+    Ok(ssh::quote(unit))
+}
+
+/// Parses `--plain --no-legend` rows: `unit load active sub description`,
+/// with columns padded by runs of spaces.
+fn parse_unit_line(line: &str) -> Option<UnitSummary> {
+    let mut rest = line.trim_start_matches(['●', '*', ' ']);
+    let mut next = || {
+        let (field, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        rest = tail.trim_start();
+        (!field.is_empty()).then(|| field.to_string())
+    };
+    let unit = next()?;
+    let load = next()?;
+    let active = next()?;
+    let sub = next()?;
+    Some(UnitSummary {
+        unit,
+        load,
+        active,
+        sub,
+        description: rest.trim().to_string(),
+    })
+}
+
+async fn status(device: &Device, unit: &str, log_lines: usize) -> Result<UnitStatus> {
+    let quoted = unit_arg(unit)?;
+    let journal = if log_lines == 0 {
+        "true".to_string()
+    } else {
+        format!("journalctl --user -u {quoted} -n {log_lines} --no-pager -o short-iso 2>&1")
+    };
+    let command = format!(
+        "{}{}",
+        user_env(),
+        ssh::sections(&[
+            &format!("systemctl --user show {quoted} -p {STATUS_PROPERTIES}"),
+            &journal,
+        ])
+    );
+    let stdout = device.exec_checked(&command).await?;
+    let [show, log] = ssh::split_sections(&stdout)[..] else {
+        return Err(Error::Parse {
+            device: device.name().to_string(),
+            message: "missing output sections".to_string(),
+        });
+    };
+    let property = |key: &str| {
+        show.lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+            .unwrap_or("")
+            .to_string()
+    };
+    let nonzero = |value: String| value.parse().ok().filter(|v| *v != 0);
+    let active_since = property("ActiveEnterTimestamp");
+    Ok(UnitStatus {
+        device: device.name().to_string(),
+        unit: unit.to_string(),
+        description: property("Description"),
+        load_state: property("LoadState"),
+        active_state: property("ActiveState"),
+        sub_state: property("SubState"),
+        unit_file_state: property("UnitFileState"),
+        main_pid: nonzero(property("MainPID")),
+        exit_status: property("ExecMainStatus").parse().ok(),
+        restarts: property("NRestarts").parse().ok(),
+        active_since: (!active_since.is_empty()).then_some(active_since),
+        log: log
+            .lines()
+            .filter(|line| !line.starts_with("-- "))
+            .map(str::to_string)
+            .collect(),
+    })
+}
+
+#[tool_router(router = services_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "List systemd user services on a device with their load and activity state. Set all=true to include inactive and failed units.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn user_services(
+        &self,
+        Parameters(params): Parameters<ListUnitsParams>,
+    ) -> Result<Json<UnitList>> {
+        let device = self.devices().get(&params.device)?;
+        let all = if params.all { " --all" } else { "" };
+        let command = format!(
+            "{}systemctl --user list-units --type=service --no-legend --no-pager --plain{all}",
+            user_env()
+        );
+        let stdout = device.exec_checked(&command).await?;
+        let units = stdout
+            .lines()
+            .filter_map(parse_unit_line)
+            .filter(|unit| params.filter.as_ref().is_none_or(|f| unit.unit.contains(f)))
+            .collect();
+        Ok(Json(UnitList {
+            device: params.device,
+            units,
+        }))
+    }
+
+    #[tool(
+        description = "Status of a systemd user unit on a device (state, main PID, exit status, restart count) with its recent journal lines.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn user_service_status(
+        &self,
+        Parameters(params): Parameters<UnitParams>,
+    ) -> Result<Json<UnitStatus>> {
+        let device = self.devices().get(&params.device)?;
+        status(&device, &params.unit, params.log_lines.unwrap_or(50))
+            .await
+            .map(Json)
+    }
+
+    #[tool(
+        description = "Start, stop or restart a systemd user unit on a device and return its status afterwards."
+    )]
+    pub async fn user_service_control(
+        &self,
+        Parameters(params): Parameters<UnitControlParams>,
+    ) -> Result<Json<UnitStatus>> {
+        let device = self.devices().get(&params.device)?;
+        let action = match params.action {
+            UnitAction::Start => "start",
+            UnitAction::Stop => "stop",
+            UnitAction::Restart => "restart",
+        };
+        let command = format!(
+            "{}systemctl --user {action} {}",
+            user_env(),
+            unit_arg(&params.unit)?
+        );
+        device.exec_checked(&command).await?;
+        status(&device, &params.unit, 20).await.map(Json)
+    }
+}
@@ -0,0 +1,84 @@
+//! `device_shell`: arbitrary shell commands on one or more devices.
+
+use std::time::Duration;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::multi::{MultiDeviceReport, fan_out};
+use crate::error::Result;
+use crate::server::AuroraServer;
+
+/// Output beyond this many bytes per stream is cut off.
+const MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ShellParams {
+    /// Device names from the config; the command runs on all of them
+    /// concurrently.
+    pub devices: Vec<String>,
+    /// Command line for the device's POSIX shell.
+    pub command: String,
+    /// Run as the device's `root_user` instead of the normal login.
+    #[serde(default)]
+    pub as_root: bool,
+    /// Timeout in seconds (default 60).
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ShellOutput {
+    pub exit_status: i32,
+    pub stdout: String,
+    pub stderr: String,
+    /// Whether stdout or stderr were cut to the output limit.
+    pub truncated: bool,
+}
+
+/// Cuts `text` to at most `max` bytes on a character boundary.
+pub fn truncate(mut text: String, max: usize) -> (String, bool) {
+    if text.len() <= max {
+        return (text, false);
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    (text, true)
+}
+
+#[tool_router(router = shell_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Run a shell command on one or more devices concurrently and return exit status and output per device.",
+        annotations(destructive_hint = true, open_world_hint = true)
+    )]
+    pub async fn device_shell(
+        &self,
+        Parameters(params): Parameters<ShellParams>,
+    ) -> Result<Json<MultiDeviceReport<ShellOutput>>> {
+        let timeout = Duration::from_secs(params.timeout_secs.unwrap_or(60));
+        let command = params.command.as_str();
+        fan_out(self, &params.devices, |device| async move {
+            let output = if params.as_root {
+                device.exec_privileged(command, timeout).await?
+            } else {
+                device.exec_with_timeout(command, timeout).await?
+            };
+            let (stdout, cut_out) = truncate(output.stdout, MAX_OUTPUT_BYTES);
+            let (stderr, cut_err) = truncate(output.stderr, MAX_OUTPUT_BYTES);
+            Ok(ShellOutput {
+                exit_status: output.status,
+                stdout,
+                stderr,
+                truncated: cut_out || cut_err,
+            })
+        })
+        .await
+        .map(Json)
+    }
+}
@@ -0,0 +1,305 @@
+//! Command execution on devices through the system `ssh` client.
+
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::process::Command;
+
+use super::Device;
+use crate::config::expand_tilde;
+use crate::error::{Error, Result};
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+pub const TRANSFER_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone)]
+pub struct CommandOutput {
+    pub status: i32,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    /// ssh exits with 255 when the connection itself failed.
+    pub fn connection_failed(&self) -> bool {
+        self.status == 255
+    }
+
+    pub fn success(&self) -> bool {
+        self.status == 0
+    }
+
+    pub fn into_stdout(self, device: &str) -> Result<String> {
+        if self.success() {
+            Ok(self.stdout)
+        } else {
+            Err(Error::RemoteCommand {
+                device: device.to_string(),
+                status: self.status,
+                stderr: self.stderr.trim().to_string(),
+            })
+        }
+    }
+}
+
+/// SSH client settings from the `[ssh]` config table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SshOptions {
+    /// Share one connection per device between all tools (OpenSSH
+    /// `ControlMaster`). A dropped master is re-established on next use.
+    pub multiplex: bool,
+    /// Seconds an idle shared connection is kept open.
+    pub control_persist_secs: u64,
+    /// Keep-alive probe interval; a connection is dropped after three
+    /// unanswered probes.
+    pub keep_alive_secs: u64,
+    /// Directory for control sockets (default: a per-user temp directory).
+    pub control_dir: Option<PathBuf>,
+}
+
+impl Default for SshOptions {
+    fn default() -> Self {
+        Self {
+            multiplex: true,
+            control_persist_secs: 600,
+            keep_alive_secs: 15,
+            control_dir: None,
+        }
+    }
+}
+
+impl SshOptions {
+    fn control_dir(&self) -> PathBuf {
+        match &self.control_dir {
+            Some(dir) => expand_tilde(dir),
+            None => {
+                let user = std::env::var("USER").unwrap_or_default();
+                std::env::temp_dir().join(format!("aurora-mcp-ssh-{user}"))
+            }
+        }
+    }
+}
+
+/// Base `ssh` invocation logging into `device` as `user`, without the
+/// remote command.
+pub fn command(device: &Device, user: &str) -> Command {
+    base_command(device, user, device.ssh_options().multiplex, &[])
+}
+
+/// A dedicated connection for long-lived port forwards, so they neither
+/// depend on nor keep alive the shared master. `flag` is `-L` or `-R`.
+pub fn forward_command(device: &Device, flag: &str, spec: &str) -> Command {
+    base_command(
+        device,
+        &device.config().user,
+        false,
+        &["-o", "ExitOnForwardFailure=yes", flag, spec],
+    )
+}
+
+fn base_command(device: &Device, user: &str, multiplex: bool, extra: &[&str]) -> Command {
+    let config = device.config();
+    let options = device.ssh_options();
+    let mut cmd = Command::new("ssh");
+    cmd.args(["-o", "BatchMode=yes"])
+        .args(["-o", "StrictHostKeyChecking=accept-new"])
+        .args(["-o", "ConnectTimeout=10"])
+        .arg("-o")
+        .arg(format!("ServerAliveInterval={}", options.keep_alive_secs))
+        .args(["-o", "ServerAliveCountMax=3"]);
+    if multiplex {
+        let dir = options.control_dir();
+        if let Err(err) = std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&dir)
+        {
+            tracing::warn!(dir = %dir.display(), %err, "cannot create ssh control directory");
+        }
+        cmd.args(["-o", "ControlMaster=auto"])
+            .arg("-o")
+            .arg(format!("ControlPath={}/%C", dir.display()))
+            .arg("-o")
+            .arg(format!("ControlPersist={}", options.control_persist_secs));
+    }
+    cmd.args(extra);
+    cmd.arg("-p").arg(config.port.to_string());
+    if let Some(identity) = &config.identity_file {
+        cmd.arg("-i").arg(expand_tilde(identity));
+    }
+    cmd.arg(format!("{user}@{}", config.host));
+    cmd
+}
+
+/// Sends a control command (`check`, `exit`) to the device's shared
+/// connection. Returns whether ssh reported success.
+pub async fn control(device: &Device, operation: &str) -> bool {
+    if !device.ssh_options().multiplex {
+        return false;
+    }
+    let mut cmd = self::command(device, &device.config().user);
+    cmd.arg("-O")
+        .arg(operation)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+    matches!(
+        tokio::time::timeout(Duration::from_secs(5), cmd.status()).await,
+        Ok(Ok(status)) if status.success()
+    )
+}
+
+pub async fn exec(
+    device: &Device,
+    user: &str,
+    command: &str,
+    timeout: Duration,
+) -> Result<CommandOutput> {
+    let mut cmd = self::command(device, user);
+    cmd.arg("--")
+        .arg(command)
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    tracing::debug!(device = %device.name(), %user, %command, "ssh exec");
+    let result = tokio::time::timeout(timeout, cmd.output())
+        .await
+        .map_err(|_| Error::Timeout {
+            device: device.name().to_string(),
+            seconds: timeout.as_secs(),
+        })
+        .and_then(|output| {
+            output.map_err(|source| Error::Ssh {
+                device: device.name().to_string(),
+                source,
+            })
+        })
+        .map(|output| CommandOutput {
+            status: output.status.code().unwrap_or(-1),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
+    device.record(&result);
+    result
+}
+
+/// Streams the remote file at `remote` into the local file `local`.
+/// Returns the number of bytes written.
+pub async fn download(
+    device: &Device,
+    remote: &str,
+    local: &Path,
+    timeout: Duration,
+) -> Result<u64> {
+    let file = std::fs::File::create(local)?;
+    let command = format!("cat -- {}", quote(remote));
+    if let Err(err) = transfer(device, &command, Stdio::null(), file.into(), timeout).await {
+        let _ = std::fs::remove_file(local);
+        return Err(err);
+    }
+    Ok(std::fs::metadata(local)?.len())
+}
+
+/// Streams the local file `local` to `remote` on the device.
+/// Returns the number of bytes sent.
+pub async fn upload(device: &Device, local: &Path, remote: &str, timeout: Duration) -> Result<u64> {
+    let file = std::fs::File::open(local)?;
+    let size = file.metadata()?.len();
+    let command = format!("cat > {}", quote(remote));
+    transfer(device, &command, file.into(), Stdio::null(), timeout).await?;
+    Ok(size)
+}
+
+/// Runs `command` with the given stdio wired straight to ssh, failing on a
+/// non-zero exit status.
+async fn transfer(
+    device: &Device,
+    command: &str,
+    stdin: Stdio,
+    stdout: Stdio,
+    timeout: Duration,
+) -> Result<()> {
+    let mut cmd = self::command(device, &device.config().user);
+    cmd.arg("--")
+        .arg(command)
+        .stdin(stdin)
+        .stdout(stdout)
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    tracing::debug!(device = %device.name(), %command, "ssh transfer");
+    // `Command::output` would replace our stdio with pipes.
+    let child = cmd.spawn().map_err(|source| Error::Ssh {
+        device: device.name().to_string(),
+        source,
+    })?;
+    let result = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| Error::Timeout {
+            device: device.name().to_string(),
+            seconds: timeout.as_secs(),
+        })
+        .and_then(|output| Ok(output?))
+        .map(|output| CommandOutput {
+            status: output.status.code().unwrap_or(-1),
+            stdout: String::new(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
+    device.record(&result);
+    result?.into_stdout(device.name()).map(drop)
+}
+
+/// Quotes `arg` for the remote POSIX shell.
+pub fn quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"-_./=:@,+".contains(&b))
+    {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+const SECTION_MARKER: &str = "--aurora-mcp-section--";
+
+/// Joins several commands into one remote invocation whose output can be
+/// split back apart with [`split_sections`]. Each command runs even if an
+/// earlier one fails.
+pub fn sections(commands: &[&str]) -> String {
+    commands.join(&format!("; echo {SECTION_MARKER}; "))
+}
+
+/// Splits the stdout of a [`sections`] script into per-command outputs.
+///
+/// Only lines consisting solely of the marker count, so command output that
+/// merely mentions it (e.g. `ps` listing this very script) is left intact.
+pub fn split_sections(stdout: &str) -> Vec<&str> {
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    for line in stdout.split_inclusive('\n') {
+        if line.trim_end_matches('\n') == SECTION_MARKER {
+            sections.push(&stdout[start..offset]);
+            start = offset + line.len();
+        }
+        offset += line.len();
+    }
+    sections.push(&stdout[start..]);
+    sections
+}
+
+/// Splits a [`sections`] script back into its commands.
+pub(crate) fn script_sections(script: &str) -> Vec<&str> {
+    script
+        .split(&format!("; echo {SECTION_MARKER}; "))
+        .collect()
+}
+
+/// The line a [`sections`] script prints between command outputs.
+pub(crate) fn section_marker_line() -> String {
+    format!("{SECTION_MARKER}\n")
+}
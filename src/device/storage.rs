@@ -0,0 +1,296 @@
+//! Disk usage on a device: free space per filesystem and what app data,
+//! caches and the RPM database take up, with optional cache clearing.
+
+use std::collections::BTreeMap;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{Device, DeviceParams, ssh};
+use crate::error::{Error, Result, require_confirmation};
+use crate::server::AuroraServer;
+
+/// Filesystems fuller than this are flagged.
+const LOW_SPACE_PERCENT: u8 = 90;
+
+/// Filesystems with less than this available are flagged regardless of
+/// their size; an RPM install needs about this much headroom.
+const LOW_SPACE_KB: u64 = 300 * 1024;
+
+/// Per-app entries reported, largest first.
+const MAX_APPS: usize = 25;
+
+/// Mount points that deploys and app data depend on.
+const MOUNTS: &[&str] = &["/", "/home", "/tmp"];
+
+/// Directories summed up, with the kind they are reported as. `$HOME` is
+/// expanded by the device shell.
+const AREAS: &[(&str, &str)] = &[
+    ("$HOME/.local/share", "app_data"),
+    ("$HOME/.config", "app_config"),
+    ("$HOME/.cache", "cache"),
+    ("/var/lib/rpm", "rpm_database"),
+    ("/var/cache", "system_cache"),
+    ("/var/log", "logs"),
+];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ClearCachesParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Clear only these entries of `~/.cache`, e.g. `ru.auroraos.demo`
+    /// (default: all of them).
+    #[serde(default)]
+    pub apps: Vec<String>,
+    /// Must be true to actually delete the caches.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StorageReport {
+    pub device: String,
+    pub filesystems: Vec<Filesystem>,
+    pub directories: Vec<DirectoryUsage>,
+    /// Largest apps by data, config and cache, from the entries under
+    /// `~/.local/share`, `~/.config` and `~/.cache`.
+    pub apps: Vec<AppUsage>,
+    /// A filesystem is nearly full; installs and deploys are likely to fail.
+    pub low_space: bool,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Filesystem {
+    pub mount: String,
+    pub source: String,
+    pub size_kb: u64,
+    pub used_kb: u64,
+    pub available_kb: u64,
+    pub use_percent: u8,
+    pub low_space: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DirectoryUsage {
+    pub path: String,
+    /// `app_data`, `app_config`, `cache`, `rpm_database`, `system_cache`
+    /// or `logs`.
+    pub kind: String,
+    pub size_kb: u64,
+}
+
+#[derive(Debug, Default, Serialize, JsonSchema)]
+pub struct AppUsage {
+    /// Directory name, usually the app's organisation or full id.
+    pub name: String,
+    pub data_kb: u64,
+    pub config_kb: u64,
+    pub cache_kb: u64,
+}
+
+impl AppUsage {
+    fn total_kb(&self) -> u64 {
+        self.data_kb + self.config_kb + self.cache_kb
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ClearCachesResult {
+    pub device: String,
+    pub cleared: Vec<String>,
+    pub cache_before_kb: u64,
+    pub cache_after_kb: u64,
+    pub freed_kb: u64,
+}
+
+/// Parses `df -P -k` output, skipping the header.
+fn parse_df(output: &str) -> Vec<Filesystem> {
+    let mut filesystems: Vec<Filesystem> = Vec::new();
+    for line in output.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [source, size, used, available, percent, mount, ..] = fields[..] else {
+            continue;
+        };
+        if filesystems.iter().any(|fs| fs.mount == mount) {
+            continue;
+        }
+        let (Ok(size_kb), Ok(used_kb), Ok(available_kb)) =
+            (size.parse(), used.parse(), available.parse())
+        else {
+            continue;
+        };
+        let use_percent = percent.trim_end_matches('%').parse().unwrap_or_default();
+        filesystems.push(Filesystem {
+            mount: mount.to_string(),
+            source: source.to_string(),
+            size_kb,
+            used_kb,
+            available_kb,
+            use_percent,
+            low_space: use_percent >= LOW_SPACE_PERCENT || available_kb < LOW_SPACE_KB,
+        });
+    }
+    filesystems
+}
+
+/// Parses `du -s -k` output into (path, size) pairs.
+fn parse_du(output: &str) -> Vec<(String, u64)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (size, path) = line.split_once('\t')?;
+            Some((path.to_string(), size.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+async fn cache_size(device: &Device) -> Result<u64> {
+    let stdout = device
+        .exec_checked("du -s -k \"$HOME/.cache\" 2>/dev/null; true")
+        .await?;
+    Ok(parse_du(&stdout).first().map_or(0, |(_, size)| *size))
+}
+
+#[tool_router(router = storage_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Report free space per filesystem (/, /home, /tmp) and the disk usage of app data, configs, caches, the RPM database and logs on a device, with the largest apps. Flags low space, a common cause of failed deploys.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn device_storage(
+        &self,
+        Parameters(params): Parameters<DeviceParams>,
+    ) -> Result<Json<StorageReport>> {
+        let device = self.devices().get(&params.device)?;
+        let areas: Vec<&str> = AREAS.iter().map(|(path, _)| *path).collect();
+        let command = ssh::sections(&[
+            &format!("df -P -k {} 2>/dev/null; true", MOUNTS.join(" ")),
+            "echo \"$HOME\"",
+            &format!(
+                "du -s -k {} 2>/dev/null; true",
+                areas
+                    .iter()
+                    .map(|path| format!("\"{path}\""))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            "cd \"$HOME\" && du -s -k .local/share/* .config/* .cache/* 2>/dev/null; true",
+        ]);
+        let stdout = device.exec_checked(&command).await?;
+        let [df, home, areas_du, apps_du] = ssh::split_sections(&stdout)[..] else {
+            return Err(Error::Parse {
+                device: device.name().to_string(),
+                message: "missing output sections".to_string(),
+            });
+        };
+
+        let filesystems = parse_df(df);
+        let home = home.trim();
+        let directories = parse_du(areas_du)
+            .into_iter()
+            .filter_map(|(path, size_kb)| {
+                let (_, kind) = AREAS
+                    .iter()
+                    .find(|(area, _)| area.replace("$HOME", home) == path.trim_end_matches('/'))?;
+                Some(DirectoryUsage {
+                    path,
+                    kind: kind.to_string(),
+                    size_kb,
+                })
+            })
+            .collect();
+
+        let mut apps: BTreeMap<String, AppUsage> = BTreeMap::new();
+        for (path, size_kb) in parse_du(apps_du) {
+            let Some((area, name)) = path.rsplit_once('/') else {
+                continue;
+            };
+            let app = apps.entry(name.to_string()).or_insert_with(|| AppUsage {
+                name: name.to_string(),
+                ..AppUsage::default()
+            });
+            match area {
+                ".local/share" => app.data_kb += size_kb,
+                ".config" => app.config_kb += size_kb,
+                ".cache" => app.cache_kb += size_kb,
+                _ => {}
+            }
+        }
+        let mut apps: Vec<AppUsage> = apps.into_values().collect();
+        apps.sort_by_key(|app| std::cmp::Reverse(app.total_kb()));
+        apps.truncate(MAX_APPS);
+
+        let warnings: Vec<String> = filesystems
+            .iter()
+            .filter(|fs| fs.low_space)
+            .map(|fs| {
+                format!(
+                    "{} is {}% full with {} MiB available",
+                    fs.mount,
+                    fs.use_percent,
+                    fs.available_kb / 1024
+                )
+            })
+            .collect();
+        Ok(Json(StorageReport {
+            device: params.device,
+            low_space: !warnings.is_empty(),
+            filesystems,
+            directories,
+            apps,
+            warnings,
+        }))
+    }
+
+    #[tool(
+        description = "Delete cached files under ~/.cache on a device, for all apps or the given ones, and report the space freed. Apps rebuild their caches (e.g. compiled QML) on next start. Requires confirm=true.",
+        annotations(destructive_hint = true)
+    )]
+    pub async fn device_clear_caches(
+        &self,
+        Parameters(params): Parameters<ClearCachesParams>,
+    ) -> Result<Json<ClearCachesResult>> {
+        let device = self.devices().get(&params.device)?;
+        if let Some(app) = params
+            .apps
+            .iter()
+            .find(|app| app.is_empty() || app.contains('/') || app.starts_with('.'))
+        {
+            return Err(Error::InvalidArgument(format!(
+                "'{app}' is not a cache directory name"
+            )));
+        }
+        let targets = if params.apps.is_empty() {
+            "all app caches".to_string()
+        } else {
+            params.apps.join(", ")
+        };
+        require_confirmation(params.confirm, || {
+            format!("delete {targets} on {}", params.device)
+        })?;
+
+        let cache_before_kb = cache_size(&device).await?;
+        let command = if params.apps.is_empty() {
+            "cd \"$HOME/.cache\" && rm -rf -- * .[!.]*; true".to_string()
+        } else {
+            let names: Vec<String> = params.apps.iter().map(|app| ssh::quote(app)).collect();
+            format!("cd \"$HOME/.cache\" && rm -rf -- {}", names.join(" "))
+        };
+        device.exec_checked(&command).await?;
+        let cache_after_kb = cache_size(&device).await?;
+        Ok(Json(ClearCachesResult {
+            device: params.device,
+            cleared: if params.apps.is_empty() {
+                vec!["*".to_string()]
+            } else {
+                params.apps
+            },
+            freed_kb: cache_before_kb.saturating_sub(cache_after_kb),
+            cache_before_kb,
+            cache_after_kb,
+        }))
+    }
+}
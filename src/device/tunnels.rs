@@ -0,0 +1,299 @@
+//! Port forwards between the host and devices over dedicated SSH
+//! connections, e.g. to reach an on-device debug HTTP server or gdbserver
+//! from host-side tools.
+
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Child;
+
+use super::{Device, ssh};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+use crate::util::unix_now;
+
+/// How long to wait for the forward to be established.
+const OPEN_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Printed by the remote keep-alive command once the session, and with
+/// `ExitOnForwardFailure` the forward, is up.
+const READY_MARKER: &str = "aurora-mcp-tunnel-ready";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Listen on the host, connect to a port on the device (`ssh -L`).
+    #[default]
+    Local,
+    /// Listen on the device, connect to a port on the host (`ssh -R`).
+    Remote,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TunnelOpenParams {
+    /// Device name from the config.
+    pub device: String,
+    #[serde(default)]
+    pub direction: Direction,
+    /// Port on the device: the target of a local forward, the listening
+    /// port of a remote one.
+    pub device_port: u16,
+    /// Port on the host: the listening port of a local forward (default: a
+    /// free port), the target of a remote one (required).
+    #[serde(default)]
+    pub host_port: Option<u16>,
+    /// Address the host side listens on for local forwards (default
+    /// `127.0.0.1`).
+    #[serde(default)]
+    pub bind_address: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TunnelCloseParams {
+    /// Tunnel id from `device_tunnel_open`.
+    pub id: u32,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct TunnelInfo {
+    pub id: u32,
+    pub device: String,
+    pub direction: Direction,
+    /// Where connections are accepted: `host:port` on the host for local
+    /// forwards, on the device for remote ones.
+    pub listen: String,
+    /// Where accepted connections are forwarded to.
+    pub target: String,
+    /// PID of the ssh process carrying the tunnel.
+    pub pid: Option<u32>,
+    /// False once the ssh process has exited, e.g. after the device went
+    /// away.
+    pub alive: bool,
+    pub opened_unix: u64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TunnelList {
+    pub tunnels: Vec<TunnelInfo>,
+}
+
+#[derive(Debug)]
+struct Tunnel {
+    info: TunnelInfo,
+    /// Holds the remote command's stdin open; dropping the child kills ssh.
+    child: Child,
+}
+
+/// Open tunnels, shared by all sessions. They are closed with the server.
+#[derive(Debug, Clone, Default)]
+pub struct Tunnels {
+    inner: Arc<Mutex<TunnelTable>>,
+}
+
+#[derive(Debug, Default)]
+struct TunnelTable {
+    next_id: u32,
+    tunnels: BTreeMap<u32, Tunnel>,
+}
+
+impl Tunnels {
+    fn table(&self) -> std::sync::MutexGuard<'_, TunnelTable> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn insert(&self, mut info: TunnelInfo, child: Child) -> TunnelInfo {
+        let mut table = self.table();
+        table.next_id += 1;
+        info.id = table.next_id;
+        table.tunnels.insert(
+            info.id,
+            Tunnel {
+                info: info.clone(),
+                child,
+            },
+        );
+        info
+    }
+
+    pub fn list(&self) -> Vec<TunnelInfo> {
+        let mut table = self.table();
+        table
+            .tunnels
+            .values_mut()
+            .map(|tunnel| {
+                tunnel.info.alive = matches!(tunnel.child.try_wait(), Ok(None));
+                tunnel.info.clone()
+            })
+            .collect()
+    }
+
+    /// Opens a local forward from a free host port to `device_port` on
+    /// `device`, as `device_tunnel_open` does.
+    pub(crate) async fn open_local(&self, device: &Device, device_port: u16) -> Result<TunnelInfo> {
+        let host_port = free_port("127.0.0.1")?;
+        let spec = format!("127.0.0.1:{host_port}:127.0.0.1:{device_port}");
+        let child = open(device, "-L", &spec).await?;
+        let info = TunnelInfo {
+            id: 0,
+            device: device.name().to_string(),
+            direction: Direction::Local,
+            listen: format!("127.0.0.1:{host_port}"),
+            target: format!("127.0.0.1:{device_port} on {}", device.name()),
+            pid: child.id(),
+            alive: true,
+            opened_unix: unix_now().as_secs(),
+        };
+        Ok(self.insert(info, child))
+    }
+
+    /// Stops and forgets tunnel `id`.
+    pub async fn close(&self, id: u32) -> Result<TunnelInfo> {
+        let tunnel = self.table().tunnels.remove(&id);
+        let Some(mut tunnel) = tunnel else {
+            return Err(Error::InvalidArgument(format!(
+                "no open tunnel with id {id}"
+            )));
+        };
+        let _ = tunnel.child.start_kill();
+        let _ = tunnel.child.wait().await;
+        tunnel.info.alive = false;
+        Ok(tunnel.info)
+    }
+}
+
+/// A free TCP port on `address`, as picked by the OS.
+fn free_port(address: &str) -> Result<u16> {
+    let listener = std::net::TcpListener::bind((address, 0))?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// Starts ssh with the forward and waits until it is established.
+async fn open(device: &Device, flag: &str, spec: &str) -> Result<Child> {
+    let mut cmd = ssh::forward_command(device, flag, spec);
+    cmd.arg("--")
+        .arg(format!("echo {READY_MARKER}; exec cat >/dev/null"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    tracing::debug!(device = %device.name(), %flag, %spec, "ssh forward");
+    let mut child = cmd.spawn().map_err(|source| Error::Ssh {
+        device: device.name().to_string(),
+        source,
+    })?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let ready = async {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line.trim() == READY_MARKER {
+                return true;
+            }
+        }
+        false
+    };
+    match tokio::time::timeout(OPEN_TIMEOUT, ready).await {
+        Ok(true) => Ok(child),
+        Ok(false) => {
+            let output = child.wait_with_output().await?;
+            Err(Error::RemoteCommand {
+                device: device.name().to_string(),
+                status: output.status.code().unwrap_or(-1),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            })
+        }
+        Err(_) => Err(Error::Timeout {
+            device: device.name().to_string(),
+            seconds: OPEN_TIMEOUT.as_secs(),
+        }),
+    }
+}
+
+#[tool_router(router = tunnels_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Open an SSH port forward for a device. direction=local (default) listens on a host port and forwards to device_port on the device, e.g. to reach an on-device debug server or gdbserver; direction=remote listens on device_port on the device and forwards to host_port on the host. Returns the tunnel id and the address it listens on; close it with device_tunnel_close."
+    )]
+    pub async fn device_tunnel_open(
+        &self,
+        Parameters(params): Parameters<TunnelOpenParams>,
+    ) -> Result<Json<TunnelInfo>> {
+        let device = self.devices().get(&params.device)?;
+        if device.is_mock() {
+            return Err(Error::InvalidArgument(format!(
+                "device '{}' is simulated; tunnels need an SSH connection",
+                params.device
+            )));
+        }
+        if params.device_port == 0 {
+            return Err(Error::InvalidArgument(
+                "device_port must be a port number".to_string(),
+            ));
+        }
+        let (flag, spec, listen, target) = match params.direction {
+            Direction::Local => {
+                let bind = params.bind_address.as_deref().unwrap_or("127.0.0.1");
+                let host_port = match params.host_port {
+                    Some(0) | None => free_port(bind)?,
+                    Some(port) => port,
+                };
+                (
+                    "-L",
+                    format!("{bind}:{host_port}:127.0.0.1:{}", params.device_port),
+                    format!("{bind}:{host_port}"),
+                    format!("127.0.0.1:{} on {}", params.device_port, params.device),
+                )
+            }
+            Direction::Remote => {
+                let Some(host_port) = params.host_port.filter(|port| *port != 0) else {
+                    return Err(Error::InvalidArgument(
+                        "remote forwards need the host_port to forward to".to_string(),
+                    ));
+                };
+                (
+                    "-R",
+                    format!("127.0.0.1:{}:127.0.0.1:{host_port}", params.device_port),
+                    format!("127.0.0.1:{} on {}", params.device_port, params.device),
+                    format!("127.0.0.1:{host_port}"),
+                )
+            }
+        };
+
+        let child = open(&device, flag, &spec).await?;
+        let info = TunnelInfo {
+            id: 0,
+            device: params.device,
+            direction: params.direction,
+            listen,
+            target,
+            pid: child.id(),
+            alive: true,
+            opened_unix: unix_now().as_secs(),
+        };
+        Ok(Json(self.state().tunnels.insert(info, child)))
+    }
+
+    #[tool(description = "Close a port forward opened with device_tunnel_open.")]
+    pub async fn device_tunnel_close(
+        &self,
+        Parameters(params): Parameters<TunnelCloseParams>,
+    ) -> Result<Json<TunnelInfo>> {
+        self.state().tunnels.close(params.id).await.map(Json)
+    }
+
+    #[tool(
+        description = "List open port forwards and whether each is still alive.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn device_tunnels(&self) -> Result<Json<TunnelList>> {
+        Ok(Json(TunnelList {
+            tunnels: self.state().tunnels.list(),
+        }))
+    }
+}
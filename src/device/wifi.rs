@@ -0,0 +1,234 @@
+//! Wi-Fi through ConnMan: scanning, the active connection and connecting
+//! to a network with credentials from the secrets store.
+
+use std::time::Duration;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{Device, ssh};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WifiParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Trigger a fresh scan first (default true).
+    #[serde(default)]
+    pub rescan: Option<bool>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WifiReport {
+    pub device: String,
+    pub powered: bool,
+    /// The network the device is connected to, if any.
+    pub active: Option<WifiNetwork>,
+    pub networks: Vec<WifiNetwork>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct WifiNetwork {
+    /// SSID; empty for hidden networks.
+    pub ssid: String,
+    /// ConnMan service id, e.g. `wifi_0011..._managed_psk`.
+    pub service: String,
+    /// `none`, `wep`, `psk` or `ieee8021x`.
+    pub security: String,
+    /// Has been connected before and is remembered.
+    pub saved: bool,
+    pub autoconnect: bool,
+    /// `online`, `ready` or `idle`.
+    pub state: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WifiConnectParams {
+    /// Device name from the config.
+    pub device: String,
+    /// Network name. Its passphrase is read from the secret `wifi.<ssid>`.
+    pub ssid: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WifiConnectResult {
+    pub device: String,
+    pub ssid: String,
+    pub service: String,
+    /// ConnMan service state after connecting, e.g. `online`.
+    pub state: String,
+    pub ipv4_address: Option<String>,
+    /// Whether a passphrase from the secrets store was provisioned.
+    pub used_secret: bool,
+}
+
+/// Parses a `connmanctl services` row: four flag columns, the name and the
+/// service id, e.g. `*AO LabNet               wifi_00..._managed_psk`.
+fn parse_service_line(line: &str) -> Option<WifiNetwork> {
+    let service = line.split_whitespace().last()?;
+    if !service.starts_with("wifi_") {
+        return None;
+    }
+    let flags: Vec<char> = line.chars().take(4).collect();
+    let flag = |i: usize| flags.get(i).copied().unwrap_or(' ');
+    let ssid = line
+        .get(4..line.len() - service.len())
+        .unwrap_or("")
+        .trim()
+        .to_string();
+    let security = service.rsplit('_').next().unwrap_or("").to_string();
+    let state = match flag(2) {
+        'O' => "online",
+        'R' => "ready",
+        _ => "idle",
+    };
+    Some(WifiNetwork {
+        ssid,
+        service: service.to_string(),
+        security,
+        saved: flag(0) == '*',
+        autoconnect: flag(1) == 'A',
+        state: state.to_string(),
+    })
+}
+
+async fn scan(device: &Device, rescan: bool) -> Result<(bool, Vec<WifiNetwork>)> {
+    let scan = if rescan {
+        "connmanctl scan wifi >/dev/null 2>&1 || true"
+    } else {
+        "true"
+    };
+    let command = ssh::sections(&["connmanctl technologies", scan, "connmanctl services"]);
+    let stdout = device.exec_checked(&command).await?;
+    let [technologies, _, services] = ssh::split_sections(&stdout)[..] else {
+        return Err(Error::Parse {
+            device: device.name().to_string(),
+            message: "missing output sections".to_string(),
+        });
+    };
+    // Technologies are blocks of `Key = Value` lines, each headed by its
+    // object path.
+    let powered = technologies
+        .split("/net/connman/technology/")
+        .find(|block| block.starts_with("wifi"))
+        .is_some_and(|block| {
+            block
+                .lines()
+                .any(|line| line.trim().replace(' ', "") == "Powered=True")
+        });
+    Ok((
+        powered,
+        services.lines().filter_map(parse_service_line).collect(),
+    ))
+}
+
+/// A ConnMan provisioning file for `ssid`. Values are escaped for GKeyFile.
+fn provisioning_file(ssid: &str, passphrase: &str) -> String {
+    let escape = |value: &str| {
+        value
+            .replace('\\', "\\\\")
+            .replace('\n', "\\n")
+            .replace('\t', "\\t")
+    };
+    format!(
+        "[service_aurora_mcp]\nType = wifi\nName = {}\nPassphrase = {}\n",
+        escape(ssid),
+        escape(passphrase)
+    )
+}
+
+#[tool_router(router = wifi_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "List Wi-Fi networks visible to a device, with security type and saved/autoconnect flags, and report the active connection.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn wifi_networks(
+        &self,
+        Parameters(params): Parameters<WifiParams>,
+    ) -> Result<Json<WifiReport>> {
+        let device = self.devices().get(&params.device)?;
+        let (powered, networks) = scan(&device, params.rescan.unwrap_or(true)).await?;
+        let active = networks
+            .iter()
+            .find(|network| network.state != "idle")
+            .cloned();
+        Ok(Json(WifiReport {
+            device: params.device,
+            powered,
+            active,
+            networks,
+        }))
+    }
+
+    #[tool(
+        description = "Connect a device to a Wi-Fi network by SSID. The passphrase comes from the server's secrets store (key `wifi.<ssid>`), never from the call. Warning: if the device's SSH connection runs over Wi-Fi, switching networks drops it."
+    )]
+    pub async fn wifi_connect(
+        &self,
+        Parameters(params): Parameters<WifiConnectParams>,
+    ) -> Result<Json<WifiConnectResult>> {
+        let device = self.devices().get(&params.device)?;
+        let (_, networks) = scan(&device, true).await?;
+        let network = networks
+            .into_iter()
+            .find(|network| network.ssid == params.ssid)
+            .ok_or_else(|| {
+                Error::InvalidArgument(format!(
+                    "network '{}' is not visible to device '{}'",
+                    params.ssid, params.device
+                ))
+            })?;
+
+        let used_secret = network.security != "none";
+        if used_secret {
+            let passphrase = self
+                .state()
+                .config
+                .secrets()
+                .require(&format!("wifi.{}", params.ssid))?;
+            let command = format!(
+                "umask 077; mkdir -p /var/lib/connman && cat > /var/lib/connman/aurora-mcp-wifi.config <<'AURORA_MCP_EOF'\n{}AURORA_MCP_EOF",
+                provisioning_file(&params.ssid, &passphrase)
+            );
+            device
+                .exec_privileged(&command, ssh::DEFAULT_TIMEOUT)
+                .await?
+                .into_stdout(device.name())?;
+        }
+
+        let service = ssh::quote(&network.service);
+        let command = format!(
+            "connmanctl connect {service} 2>&1 | grep -v 'Already connected' >&2; \
+             connmanctl services {service}"
+        );
+        let details = device
+            .exec_with_timeout(&command, CONNECT_TIMEOUT)
+            .await?
+            .into_stdout(device.name())?;
+        let property = |key: &str| {
+            details.lines().find_map(|line| {
+                let (k, v) = line.trim().split_once(" = ")?;
+                (k == key).then(|| v.trim().to_string())
+            })
+        };
+        let ipv4_address = property("IPv4").and_then(|ipv4| {
+            ipv4.split(['[', ']', ','])
+                .find_map(|part| part.trim().strip_prefix("Address="))
+                .map(str::to_string)
+        });
+        Ok(Json(WifiConnectResult {
+            device: params.device,
+            ssid: params.ssid,
+            service: network.service,
+            state: property("State").unwrap_or_default(),
+            ipv4_address,
+            used_secret,
+        }))
+    }
+}
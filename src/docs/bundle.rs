@@ -0,0 +1,657 @@
+//! Offline documentation bundles: versioned archives of the documentation
+//! downloaded from `bundle_url` in the `[docs]` config table and unpacked
+//! under the data directory, one directory per OS release, so the docs
+//! resources and `search_docs` work without network access. Each bundle
+//! keeps a `SHA256SUMS` of its files (the archive's own, or one made when
+//! it was unpacked) for `verify_docs_bundle`.
+//!
+//! ```toml
+//! [docs]
+//! bundle_url = "https://docs.example.com/bundles/aurora-docs-{version}.tar.gz"
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use super::DocsOptions;
+use crate::config::{Config, expand_tilde};
+use crate::error::{Error, Result};
+use crate::sdk::compat::target_os_version;
+use crate::sdk::engine::{parse_tools, query};
+use crate::sdk::silica::OsVersion;
+use crate::server::AuroraServer;
+
+const MANIFEST: &str = "bundle.json";
+const CHECKSUMS: &str = "SHA256SUMS";
+/// Files listed at most, per kind, in a verification report.
+const MAX_LISTED: usize = 50;
+
+/// Serializes bundle downloads, so that two sessions asking for the same
+/// release do not unpack it over each other.
+#[derive(Debug, Clone, Default)]
+pub struct BundleInstalls {
+    lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+/// What `bundle.json` records about an unpacked bundle.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Manifest {
+    pub version: String,
+    pub url: String,
+    /// SHA-256 of the downloaded archive.
+    pub sha256: String,
+    /// Size of the archive in bytes.
+    pub size: u64,
+    /// Unix time it was unpacked.
+    pub installed_at: u64,
+    /// Documentation files it holds.
+    pub files: usize,
+}
+
+/// A bundle under the bundle directory.
+#[derive(Debug, Clone)]
+pub struct InstalledBundle {
+    pub version: String,
+    pub dir: PathBuf,
+    pub manifest: Option<Manifest>,
+}
+
+impl DocsOptions {
+    /// Where bundles are unpacked.
+    pub fn bundle_dir(&self, config: &Config) -> PathBuf {
+        match &self.bundle_dir {
+            Some(dir) => expand_tilde(dir),
+            None => config.data_dir().join("docs"),
+        }
+    }
+
+    /// `bundle_url` for `version`.
+    fn bundle_url(&self, version: &str) -> Result<String> {
+        let template = self.bundle_url.as_deref().ok_or_else(|| {
+            Error::Config(
+                "set bundle_url in the [docs] config table to download documentation bundles"
+                    .to_string(),
+            )
+        })?;
+        let release: Vec<&str> = version.split('.').take(2).collect();
+        Ok(template
+            .replace("{version}", version)
+            .replace("{release}", &release.join(".")))
+    }
+}
+
+/// Bundles under the bundle directory, oldest release first.
+pub fn installed(config: &Config) -> Vec<InstalledBundle> {
+    let Ok(entries) = std::fs::read_dir(config.docs.bundle_dir(config)) else {
+        return Vec::new();
+    };
+    let mut bundles: Vec<InstalledBundle> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let version = entry.file_name().to_string_lossy().into_owned();
+            OsVersion::parse(&version).ok()?;
+            let dir = entry.path();
+            let manifest = std::fs::read_to_string(dir.join(MANIFEST))
+                .ok()
+                .and_then(|text| serde_json::from_str(&text).ok());
+            Some(InstalledBundle {
+                version,
+                dir,
+                manifest,
+            })
+        })
+        .collect();
+    bundles.sort_by_cached_key(|bundle| OsVersion::parse(&bundle.version).ok());
+    bundles
+}
+
+/// The bundle for release `wanted`: an exact match, else the most specific
+/// bundle covering it (`5.1` for `5.1.3.85`).
+pub fn covering<'a>(bundles: &'a [InstalledBundle], wanted: &str) -> Option<&'a InstalledBundle> {
+    bundles
+        .iter()
+        .find(|bundle| bundle.version == wanted)
+        .or_else(|| {
+            bundles
+                .iter()
+                .filter(|bundle| wanted.starts_with(&format!("{}.", bundle.version)))
+                .max_by_key(|bundle| bundle.version.len())
+        })
+}
+
+/// The bundle to serve for release `wanted`: the one covering it, else the
+/// newest.
+pub fn select<'a>(
+    bundles: &'a [InstalledBundle],
+    wanted: Option<&str>,
+) -> Option<&'a InstalledBundle> {
+    wanted
+        .and_then(|wanted| covering(bundles, wanted))
+        .or(bundles.last())
+}
+
+/// The directory of the bundle for release `version`, downloading it if
+/// none covers the release and `bundle_url` is set.
+pub async fn versioned_root(server: &AuroraServer, version: &str) -> Result<PathBuf> {
+    OsVersion::parse(version)?;
+    let config = &server.state().config;
+    if let Some(bundle) = covering(&installed(config), version) {
+        return Ok(bundle.dir.clone());
+    }
+    if config.docs.bundle_url.is_none() {
+        return Err(Error::InvalidArgument(format!(
+            "no documentation bundle for {version} is installed; set bundle_url in the [docs] config table to download it"
+        )));
+    }
+    Ok(install(server, version.to_string(), false).await?.dir)
+}
+
+/// The release bundles are served for when a tool is not given one: the
+/// `version` of the `[docs]` table, else the release of the `[sdk]` target.
+pub fn configured_version(config: &Config) -> Option<String> {
+    config.docs.version.clone().or_else(|| {
+        config
+            .sdk
+            .target
+            .as_deref()
+            .and_then(target_os_version)
+            .map(str::to_string)
+    })
+}
+
+/// `requested`, else the configured release, else the release of the
+/// SDK's latest build target.
+async fn resolve_version(server: &AuroraServer, requested: Option<&str>) -> Result<String> {
+    let config = &server.state().config;
+    let version = match requested
+        .map(str::to_string)
+        .or_else(|| configured_version(config))
+    {
+        Some(version) => version,
+        None => {
+            let mut command = Command::new(config.sdk.sfdk()?);
+            command.args(["tools", "list"]);
+            let (_, tools) = query(command).await?;
+            let (_, targets) = parse_tools(&tools);
+            targets
+                .iter()
+                .find(|target| target.flags.iter().any(|flag| flag == "latest"))
+                .or(targets.first())
+                .and_then(|target| target_os_version(&target.name))
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    Error::InvalidArgument(
+                        "pass version; no build target of the installed SDK names an OS release"
+                            .to_string(),
+                    )
+                })?
+        }
+    };
+    OsVersion::parse(&version)?;
+    Ok(version)
+}
+
+/// Fetches `url` with the host's `curl`, into `output` or as text.
+async fn fetch(options: &DocsOptions, url: &str, output: Option<&Path>) -> Result<String> {
+    let mut command = Command::new(&options.curl);
+    command
+        .args(["-sSL", "-w", "\n%{http_code}", "--max-time"])
+        .arg(options.timeout_secs.max(1).to_string());
+    if let Some(output) = output {
+        command.arg("-o").arg(output);
+    }
+    command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let program = options.curl.display().to_string();
+    let timeout = Duration::from_secs(options.timeout_secs.max(1) + 10);
+    let result = match tokio::time::timeout(timeout, command.output()).await {
+        Ok(result) => result.map_err(|source| Error::Spawn { program, source })?,
+        Err(_) => {
+            return Err(Error::CommandTimeout {
+                program,
+                seconds: timeout.as_secs(),
+            });
+        }
+    };
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    let (body, status) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+    let status: u16 = status.trim().parse().unwrap_or_default();
+    if !result.status.success() || !(200..300).contains(&status) {
+        if let Some(output) = output {
+            let _ = std::fs::remove_file(output);
+        }
+        let message = String::from_utf8_lossy(&result.stderr).trim().to_string();
+        return Err(Error::Http {
+            url: url.to_string(),
+            status,
+            message: match (message.is_empty(), body.trim().is_empty()) {
+                (false, _) => message,
+                (true, false) => body.trim().chars().take(200).collect(),
+                (true, true) => "nothing to download".to_string(),
+            },
+        });
+    }
+    Ok(body.to_string())
+}
+
+/// The published checksum of the archive at `url`, from `<url>.sha256`;
+/// `None` if there is none.
+async fn remote_sha256(options: &DocsOptions, url: &str) -> Result<Option<String>> {
+    match fetch(options, &format!("{url}.sha256"), None).await {
+        Ok(text) => Ok(text
+            .split_whitespace()
+            .next()
+            .filter(|sum| sum.len() == 64 && sum.chars().all(|c| c.is_ascii_hexdigit()))
+            .map(str::to_ascii_lowercase)),
+        Err(Error::Http { status: 404, .. }) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+async fn run(mut command: Command, program: &str) -> Result<String> {
+    let output = command
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|source| Error::Spawn {
+            program: program.to_string(),
+            source,
+        })?;
+    if !output.status.success() {
+        return Err(Error::InvalidArgument(format!(
+            "{program} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+async fn sha256(file: &Path) -> Result<String> {
+    let mut command = Command::new("sha256sum");
+    command.arg(file);
+    let output = run(command, "sha256sum").await?;
+    output
+        .split_whitespace()
+        .next()
+        .map(str::to_string)
+        .ok_or_else(|| Error::InvalidArgument(format!("no checksum for {}", file.display())))
+}
+
+/// Files under `dir`, relative and prefixed with `./` as `sha256sum`
+/// lists them, without the bundle's own bookkeeping files.
+fn bundle_files(dir: &Path) -> Vec<String> {
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<String>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(root, &path, files);
+            } else if let Ok(relative) = path.strip_prefix(root) {
+                files.push(format!("./{}", relative.display()));
+            }
+        }
+    }
+    let mut files = Vec::new();
+    walk(dir, dir, &mut files);
+    files.retain(|file| file != &format!("./{MANIFEST}") && file != &format!("./{CHECKSUMS}"));
+    files.sort();
+    files
+}
+
+/// The archive's top directory when it unpacked into a single one.
+fn content_root(dir: &Path) -> PathBuf {
+    let entries: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    match entries.as_slice() {
+        [only] if only.is_dir() => only.clone(),
+        _ => dir.to_path_buf(),
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DownloadDocsBundleParams {
+    /// OS release, e.g. `5.1.3.85` (default: `version` from the `[docs]`
+    /// config, else the release of the `[sdk]` target, else that of the
+    /// SDK's latest build target).
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Download again even if the installed bundle matches the published
+    /// checksum.
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DocsBundleReport {
+    pub version: String,
+    pub dir: PathBuf,
+    /// `installed`, `updated` or `up-to-date`.
+    pub status: String,
+    /// Whether the archive matched a published checksum.
+    pub checksum_verified: bool,
+    pub manifest: Manifest,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct VerifyDocsBundleParams {
+    /// Release of the bundle to verify (default: the one being served).
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DocsBundleVerification {
+    pub version: String,
+    pub dir: PathBuf,
+    /// Every listed file is present and unchanged, and nothing was added.
+    pub ok: bool,
+    pub files_checked: usize,
+    /// Files whose content changed.
+    pub modified: Vec<String>,
+    /// Listed files that are gone.
+    pub missing: Vec<String>,
+    /// Files that are not in `SHA256SUMS`.
+    pub unexpected: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListDocsBundlesParams {
+    /// Also ask the server whether a newer build of each bundle is
+    /// published.
+    #[serde(default)]
+    pub check_updates: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DocsBundleEntry {
+    pub version: String,
+    pub dir: PathBuf,
+    /// Whether the docs resources and search use this bundle.
+    pub served: bool,
+    pub manifest: Option<Manifest>,
+    /// Whether the published checksum differs from the installed archive's;
+    /// `None` when not checked or nothing is published.
+    pub update_available: Option<bool>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DocsBundles {
+    pub bundle_dir: PathBuf,
+    /// Release bundles are picked for.
+    pub wanted_version: Option<String>,
+    /// The `path` of the `[docs]` config, which is served instead of any
+    /// bundle when set.
+    pub local_copy: Option<PathBuf>,
+    pub bundles: Vec<DocsBundleEntry>,
+}
+
+/// Download and unpack the bundle for release `version` unless the
+/// installed one is current or `force` is set.
+async fn install(server: &AuroraServer, version: String, force: bool) -> Result<DocsBundleReport> {
+    let _installing = server.state().docs_installs.lock.lock().await;
+    let config = &server.state().config;
+    let options = &config.docs;
+    let url = options.bundle_url(&version)?;
+    let root = options.bundle_dir(config);
+    let dir = root.join(&version);
+    std::fs::create_dir_all(&root)?;
+    let existing: Option<Manifest> = std::fs::read_to_string(dir.join(MANIFEST))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok());
+
+    let published = remote_sha256(options, &url).await?;
+    if !force
+        && let (Some(manifest), Some(published)) = (&existing, &published)
+        && manifest.sha256 == *published
+    {
+        return Ok(DocsBundleReport {
+            version,
+            dir,
+            status: "up-to-date".to_string(),
+            checksum_verified: true,
+            manifest: manifest.clone(),
+        });
+    }
+
+    let archive_name = url
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty() && !name.contains(['?', '#']))
+        .unwrap_or("bundle.tar");
+    let archive = root.join(format!(".download-{version}-{archive_name}"));
+    fetch(options, &url, Some(&archive)).await?;
+    let size = std::fs::metadata(&archive)?.len();
+    let sum = sha256(&archive).await?;
+    if let Some(published) = &published
+        && *published != sum
+    {
+        let _ = std::fs::remove_file(&archive);
+        return Err(Error::InvalidArgument(format!(
+            "{url} has SHA-256 {sum} but {published} is published; the download is corrupt or the bundle is being replaced, try again later"
+        )));
+    }
+    if !force
+        && let Some(manifest) = &existing
+        && manifest.sha256 == sum
+    {
+        let _ = std::fs::remove_file(&archive);
+        return Ok(DocsBundleReport {
+            version,
+            dir,
+            status: "up-to-date".to_string(),
+            checksum_verified: published.is_some(),
+            manifest: manifest.clone(),
+        });
+    }
+
+    let staging = root.join(format!(".unpack-{version}"));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    std::fs::create_dir_all(&staging)?;
+    let mut command = Command::new("tar");
+    command.arg("-xf").arg(&archive).arg("-C").arg(&staging);
+    let unpacked = run(command, "tar").await;
+    let _ = std::fs::remove_file(&archive);
+    if let Err(err) = unpacked {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(err);
+    }
+    let content = content_root(&staging);
+    if !content.join(CHECKSUMS).is_file() {
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(format!(
+                "find . -type f ! -path ./{CHECKSUMS} ! -path ./{MANIFEST} -print0 | sort -z | xargs -0 -r sha256sum > {CHECKSUMS}"
+            ))
+            .current_dir(&content);
+        run(command, "sha256sum").await?;
+    }
+    let manifest = Manifest {
+        version: version.clone(),
+        url,
+        sha256: sum,
+        size,
+        installed_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0),
+        files: bundle_files(&content).len(),
+    };
+    let json = serde_json::to_string_pretty(&manifest)
+        .map_err(|err| Error::Io(std::io::Error::other(err)))?;
+    std::fs::write(content.join(MANIFEST), json)?;
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::rename(&content, &dir)?;
+    if staging.exists() {
+        let _ = std::fs::remove_dir_all(&staging);
+    }
+    Ok(DocsBundleReport {
+        version,
+        dir,
+        status: if existing.is_some() {
+            "updated"
+        } else {
+            "installed"
+        }
+        .to_string(),
+        checksum_verified: published.is_some(),
+        manifest,
+    })
+}
+
+#[tool_router(router = bundle_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Download the offline Aurora OS documentation bundle for an OS release (default: the release of the configured build target or the installed SDK) from bundle_url in [docs], check it against the published .sha256, and unpack it under the data directory, replacing an older copy of the same release. Skips the download when the installed bundle is current. The docs resources and search_docs then work without network access."
+    )]
+    pub async fn download_docs_bundle(
+        &self,
+        Parameters(params): Parameters<DownloadDocsBundleParams>,
+    ) -> Result<Json<DocsBundleReport>> {
+        let version = resolve_version(self, params.version.as_deref()).await?;
+        install(self, version, params.force).await.map(Json)
+    }
+
+    #[tool(
+        description = "Verify an unpacked documentation bundle against its SHA256SUMS: reports modified, missing and unexpected files. Defaults to the bundle currently served.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn verify_docs_bundle(
+        &self,
+        Parameters(params): Parameters<VerifyDocsBundleParams>,
+    ) -> Result<Json<DocsBundleVerification>> {
+        let config = &self.state().config;
+        let bundles = installed(config);
+        let bundle = match &params.version {
+            Some(version) => bundles.iter().find(|bundle| bundle.version == *version),
+            None => select(&bundles, configured_version(config).as_deref()),
+        }
+        .ok_or_else(|| {
+            Error::InvalidArgument(match &params.version {
+                Some(version) => format!("no documentation bundle for {version} is installed"),
+                None => {
+                    "no documentation bundle is installed; run download_docs_bundle".to_string()
+                }
+            })
+        })?;
+        let checksums = std::fs::read_to_string(bundle.dir.join(CHECKSUMS)).map_err(|err| {
+            Error::InvalidArgument(format!(
+                "{}: {err}; download the bundle again",
+                bundle.dir.join(CHECKSUMS).display()
+            ))
+        })?;
+        let listed: Vec<String> = checksums
+            .lines()
+            .filter_map(|line| line.split_once(char::is_whitespace))
+            .map(|(_, file)| file.trim_start().trim_start_matches('*').to_string())
+            .collect();
+
+        let mut command = Command::new("sha256sum");
+        command
+            .args(["-c", CHECKSUMS])
+            .current_dir(&bundle.dir)
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+        let output = command.output().await.map_err(|source| Error::Spawn {
+            program: "sha256sum".to_string(),
+            source,
+        })?;
+        let mut modified = Vec::new();
+        let mut missing = Vec::new();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let Some((file, result)) = line.rsplit_once(": ") else {
+                continue;
+            };
+            match result {
+                "OK" => {}
+                "FAILED open or read" => missing.push(file.to_string()),
+                _ => modified.push(file.to_string()),
+            }
+        }
+        let unexpected: Vec<String> = bundle_files(&bundle.dir)
+            .into_iter()
+            .filter(|file| {
+                let bare = file.trim_start_matches("./");
+                !listed
+                    .iter()
+                    .any(|listed| listed.trim_start_matches("./") == bare)
+            })
+            .collect();
+        let ok = modified.is_empty() && missing.is_empty() && unexpected.is_empty();
+        let truncate = |mut files: Vec<String>| {
+            files.truncate(MAX_LISTED);
+            files
+        };
+        Ok(Json(DocsBundleVerification {
+            version: bundle.version.clone(),
+            dir: bundle.dir.clone(),
+            ok,
+            files_checked: listed.len(),
+            modified: truncate(modified),
+            missing: truncate(missing),
+            unexpected: truncate(unexpected),
+        }))
+    }
+
+    #[tool(
+        description = "List the downloaded documentation bundles and which one the docs resources and search_docs serve. check_updates asks the bundle server whether a newer build of each is published.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn list_docs_bundles(
+        &self,
+        Parameters(params): Parameters<ListDocsBundlesParams>,
+    ) -> Result<Json<DocsBundles>> {
+        let config = &self.state().config;
+        let options = &config.docs;
+        let wanted_version = configured_version(config);
+        let bundles = installed(config);
+        let local_copy = options.path.as_deref().map(expand_tilde);
+        let served = match &local_copy {
+            Some(_) => None,
+            None => select(&bundles, wanted_version.as_deref()).map(|bundle| bundle.dir.clone()),
+        };
+        let mut entries = Vec::new();
+        for bundle in &bundles {
+            let update_available = match (&bundle.manifest, params.check_updates) {
+                (Some(manifest), true) => remote_sha256(options, &manifest.url)
+                    .await?
+                    .map(|published| published != manifest.sha256),
+                _ => None,
+            };
+            entries.push(DocsBundleEntry {
+                version: bundle.version.clone(),
+                served: served.as_ref() == Some(&bundle.dir),
+                dir: bundle.dir.clone(),
+                manifest: bundle.manifest.clone(),
+                update_available,
+            });
+        }
+        Ok(Json(DocsBundles {
+            bundle_dir: options.bundle_dir(config),
+            wanted_version,
+            local_copy,
+            bundles: entries,
+        }))
+    }
+}
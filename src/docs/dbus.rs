@@ -0,0 +1,273 @@
+//! D-Bus API reference as `aurora-dbus://` resources, rendered from
+//! introspection XML: the platform interfaces a build target's sysroot
+//! ships, `aurora-dbus://sdk/<interface>`, and what a service exposes live
+//! on a device, `aurora-dbus://device/<device>/<bus>/<service>/<path>`.
+//! Both render the interfaces as `dbus_introspect` parses them, so the
+//! reference and the tools agree.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+use rmcp::handler::server::wrapper::Parameters;
+
+use crate::config::Config;
+use crate::device::dbus::{Bus, DbusInterface, IntrospectParams, ListServicesParams};
+use crate::device::files::{decode_path, encode_path};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+pub const URI_SCHEME: &str = "aurora-dbus://";
+
+/// Where a sysroot keeps the interface XML of its D-Bus services.
+const INTERFACES_DIR: &str = "usr/share/dbus-1/interfaces";
+
+#[derive(Debug)]
+pub enum DbusUri {
+    /// An interface of the build target; empty for the index.
+    Sdk(String),
+    /// A device's bus: its services, or with `service` an object.
+    Device {
+        device: String,
+        bus: Bus,
+        service: Option<String>,
+        path: String,
+    },
+}
+
+pub fn sdk_uri(interface: &str) -> String {
+    format!("{URI_SCHEME}sdk/{interface}")
+}
+
+pub fn device_uri(device: &str, bus: Bus, service: Option<&str>, path: &str) -> String {
+    let bus = bus_name(bus);
+    match service {
+        Some(service) => format!(
+            "{URI_SCHEME}device/{device}/{bus}/{service}{}",
+            encode_path(path)
+        ),
+        None => format!("{URI_SCHEME}device/{device}/{bus}/"),
+    }
+}
+
+fn bus_name(bus: Bus) -> &'static str {
+    match bus {
+        Bus::Session => "session",
+        Bus::System => "system",
+    }
+}
+
+pub fn parse_uri(uri: &str) -> Option<DbusUri> {
+    let rest = uri.strip_prefix(URI_SCHEME)?;
+    if let Some(interface) = rest.strip_prefix("sdk") {
+        if !interface.is_empty() && !interface.starts_with('/') {
+            return None;
+        }
+        return Some(DbusUri::Sdk(interface.trim_matches('/').to_string()));
+    }
+    let rest = rest.strip_prefix("device/")?;
+    let (device, rest) = rest.split_once('/')?;
+    let (bus, rest) = rest.split_once('/').unwrap_or((rest, ""));
+    let bus = match bus {
+        "session" => Bus::Session,
+        "system" => Bus::System,
+        _ => return None,
+    };
+    let (service, path) = match rest.split_once('/') {
+        Some((service, path)) => (service, format!("/{}", path.trim_end_matches('/'))),
+        None => (rest, "/".to_string()),
+    };
+    if device.is_empty() {
+        return None;
+    }
+    Some(DbusUri::Device {
+        device: device.to_string(),
+        bus,
+        service: (!service.is_empty()).then(|| service.to_string()),
+        path: decode_path(&path)?,
+    })
+}
+
+/// The build target whose interfaces are served and its interface
+/// directory: the `[sdk]` target, else the last installed one by name.
+pub fn sdk_target(config: &Config) -> Result<(String, PathBuf)> {
+    if let Some(target) = config.sdk.target(None)? {
+        let dir = config.sdk.sysroot(&target)?.join(INTERFACES_DIR);
+        return Ok((target, dir));
+    }
+    let sysroots = config.sdk.sysroots_dir()?;
+    let target = config
+        .sdk
+        .installed_targets()
+        .into_iter()
+        .rfind(|target| sysroots.join(target).join(INTERFACES_DIR).is_dir())
+        .ok_or_else(|| {
+            Error::Config(format!(
+                "no build target with D-Bus interfaces under {}; set target or sysroots in \
+                 the [sdk] config table",
+                sysroots.display()
+            ))
+        })?;
+    let dir = sysroots.join(&target).join(INTERFACES_DIR);
+    Ok((target, dir))
+}
+
+/// The build target and the interfaces its sysroot describes, by name.
+/// Files that do not parse are skipped.
+pub fn sdk_interfaces(config: &Config) -> Result<(String, Vec<DbusInterface>)> {
+    let (target, dir) = sdk_target(config)?;
+    let mut interfaces = Vec::new();
+    for entry in std::fs::read_dir(&dir)?.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "xml") {
+            continue;
+        }
+        let Ok(xml) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let options = roxmltree::ParsingOptions {
+            allow_dtd: true,
+            ..Default::default()
+        };
+        match roxmltree::Document::parse_with_options(&xml, options) {
+            Ok(doc) => interfaces.extend(crate::device::dbus::parse_interfaces(doc.root_element())),
+            Err(err) => tracing::debug!(file = %path.display(), %err, "skipping interface XML"),
+        }
+    }
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+    interfaces.dedup_by(|a, b| a.name == b.name);
+    Ok((target, interfaces))
+}
+
+/// Hash of what the `sdk` resources render from: the build target and the
+/// names, sizes and modification times of its interface files.
+pub fn sdk_revision(config: &Config) -> Result<u64> {
+    let (target, dir) = sdk_target(config)?;
+    let mut hasher = DefaultHasher::new();
+    target.hash(&mut hasher);
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect();
+    files.sort();
+    for file in files {
+        if let Ok(meta) = std::fs::metadata(&file) {
+            file.hash(&mut hasher);
+            meta.len().hash(&mut hasher);
+            meta.modified().ok().hash(&mut hasher);
+        }
+    }
+    Ok(hasher.finish())
+}
+
+/// Markdown for `uri`; `None` for an unknown interface.
+pub async fn read(server: &AuroraServer, uri: DbusUri) -> Result<Option<String>> {
+    match uri {
+        DbusUri::Sdk(name) => {
+            let (target, interfaces) = sdk_interfaces(&server.state().config)?;
+            if name.is_empty() {
+                let mut text = format!("# D-Bus interfaces of {target}\n\n");
+                for interface in &interfaces {
+                    text.push_str(&format!(
+                        "- [`{}`]({}): {}\n",
+                        interface.name,
+                        sdk_uri(&interface.name),
+                        summary(interface)
+                    ));
+                }
+                return Ok(Some(text));
+            }
+            Ok(interfaces
+                .iter()
+                .find(|interface| interface.name == name)
+                .map(|interface| {
+                    format!(
+                        "# D-Bus interface `{name}`\n\nAs shipped by {target}.\n\n{}",
+                        interface.markdown()
+                    )
+                }))
+        }
+        DbusUri::Device {
+            device,
+            bus,
+            service: None,
+            ..
+        } => {
+            let list = server
+                .dbus_list_services(Parameters(ListServicesParams {
+                    device: device.clone(),
+                    bus,
+                    filter: None,
+                }))
+                .await?
+                .0;
+            let mut text = format!(
+                "# D-Bus services on the {} bus of {device}\n\n",
+                bus_name(bus)
+            );
+            for service in &list.services {
+                let state = if service.active {
+                    "running"
+                } else {
+                    "activatable"
+                };
+                text.push_str(&format!(
+                    "- [`{}`]({}), {state}\n",
+                    service.name,
+                    device_uri(&device, bus, Some(&service.name), "/")
+                ));
+            }
+            Ok(Some(text))
+        }
+        DbusUri::Device {
+            device,
+            bus,
+            service: Some(service),
+            path,
+        } => {
+            let introspection = server
+                .dbus_introspect(Parameters(IntrospectParams {
+                    device: device.clone(),
+                    bus,
+                    service: service.clone(),
+                    path: Some(path.clone()),
+                }))
+                .await?
+                .0;
+            let mut text = format!(
+                "# `{service}` at `{path}` on {device}\n\nIntrospected live on the {} bus.\n",
+                bus_name(bus)
+            );
+            for interface in &introspection.interfaces {
+                text.push('\n');
+                text.push_str(&interface.markdown());
+            }
+            if !introspection.children.is_empty() {
+                text.push_str("\n## Child objects\n\n");
+                for child in &introspection.children {
+                    text.push_str(&format!(
+                        "- [`{child}`]({})\n",
+                        device_uri(&device, bus, Some(&service), child)
+                    ));
+                }
+            }
+            Ok(Some(text))
+        }
+    }
+}
+
+fn summary(interface: &DbusInterface) -> String {
+    let mut parts = Vec::new();
+    for (count, one, many) in [
+        (interface.methods.len(), "method", "methods"),
+        (interface.signals.len(), "signal", "signals"),
+        (interface.properties.len(), "property", "properties"),
+    ] {
+        match count {
+            0 => {}
+            1 => parts.push(format!("1 {one}")),
+            count => parts.push(format!("{count} {many}")),
+        }
+    }
+    parts.join(", ")
+}
@@ -0,0 +1,401 @@
+//! HTML to Markdown conversion for documentation pages: keeps headings,
+//! paragraphs, lists, tables, code and links, and drops navigation,
+//! scripts and styling so the text reads cleanly.
+
+/// Elements skipped with everything inside them.
+const SKIPPED: &[&str] = &[
+    "script", "style", "nav", "header", "footer", "aside", "noscript", "svg", "form", "button",
+    "head",
+];
+
+/// Elements that start a new paragraph.
+const BLOCKS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "main",
+    "table",
+    "blockquote",
+    "dl",
+    "figure",
+    "ul",
+    "ol",
+    "body",
+];
+
+/// The title of an HTML page: its `<title>`, else its first `<h1>`.
+pub fn html_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    ["title", "h1"].iter().find_map(|tag| {
+        let start = lower.find(&format!("<{tag}"))?;
+        let start = start + lower[start..].find('>')? + 1;
+        let end = start + lower[start..].find(&format!("</{tag}"))?;
+        let text = from_html(&html[start..end], None);
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    })
+}
+
+/// Markdown for `html`. When the page has a `<main>` or `<article>`
+/// element only its content is kept, which leaves site chrome out. With
+/// `base`, the topic path of the page's directory, relative links to other
+/// pages become `aurora-doc://` URIs.
+pub fn from_html(html: &str, base: Option<&str>) -> String {
+    let lower = html.to_ascii_lowercase();
+    let html = ["main", "article"]
+        .iter()
+        .find_map(|tag| {
+            let start = lower.find(&format!("<{tag}"))?;
+            let end = lower.rfind(&format!("</{tag}>"))?;
+            (end > start).then(|| &html[start..end])
+        })
+        .unwrap_or(html);
+
+    let mut writer = Writer {
+        base: base.map(str::to_string),
+        ..Writer::default()
+    };
+    let mut rest = html;
+    while let Some(open) = rest.find('<') {
+        writer.text(&rest[..open]);
+        rest = &rest[open..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(close) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[1..close];
+        rest = &rest[close + 1..];
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if !closing && SKIPPED.contains(&name.as_str()) {
+            let end = format!("</{name}");
+            let lower = rest.to_ascii_lowercase();
+            rest = match lower.find(&end) {
+                Some(at) => rest[at..].find('>').map_or("", |gt| &rest[at + gt + 1..]),
+                None => "",
+            };
+            continue;
+        }
+        writer.tag(&name, tag, closing);
+    }
+    writer.text(rest);
+    writer.finish()
+}
+
+#[derive(Default)]
+struct Writer {
+    out: String,
+    base: Option<String>,
+    /// Depth of `<pre>` elements; whitespace is kept inside them.
+    pre: usize,
+    /// Open `<ol>` counters and `None` for `<ul>`.
+    lists: Vec<Option<usize>>,
+    /// Output position and target of open links.
+    links: Vec<(usize, Option<String>)>,
+    /// Cells written in the current table row.
+    cells: usize,
+    /// Whether the row header separator has been written.
+    table_header: bool,
+}
+
+impl Writer {
+    fn text(&mut self, text: &str) {
+        let text = decode_entities(text);
+        if self.pre > 0 {
+            self.out.push_str(&text);
+            return;
+        }
+        for (index, word) in text.split_whitespace().enumerate() {
+            let starts_with_space = index > 0 || text.starts_with(char::is_whitespace);
+            if starts_with_space
+                && !self.out.ends_with([' ', '\n', '[', '('])
+                && !self.out.is_empty()
+            {
+                self.out.push(' ');
+            }
+            self.out.push_str(word);
+        }
+        if text.ends_with(char::is_whitespace)
+            && !self.out.is_empty()
+            && !self.out.ends_with([' ', '\n'])
+        {
+            self.out.push(' ');
+        }
+    }
+
+    fn block(&mut self) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push_str(if self.out.ends_with('\n') {
+                "\n"
+            } else {
+                "\n\n"
+            });
+        }
+    }
+
+    fn line(&mut self) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn tag(&mut self, name: &str, tag: &str, closing: bool) {
+        match (name, closing) {
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                self.block();
+                let level = name[1..].parse().unwrap_or(1);
+                self.out.push_str(&"#".repeat(level));
+                self.out.push(' ');
+            }
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => self.block(),
+            ("pre", false) => {
+                self.block();
+                self.out.push_str("```\n");
+                self.pre += 1;
+            }
+            ("pre", true) => {
+                self.pre = self.pre.saturating_sub(1);
+                self.line();
+                self.out.push_str("```");
+                self.block();
+            }
+            ("code" | "kbd" | "tt", _) if self.pre == 0 => self.out.push('`'),
+            ("strong" | "b", _) => self.out.push_str("**"),
+            ("em" | "i", _) => self.out.push('*'),
+            ("br", _) => self.line(),
+            ("hr", _) => {
+                self.block();
+                self.out.push_str("---");
+                self.block();
+            }
+            ("ul" | "ol", false) => {
+                if self.lists.is_empty() {
+                    self.block();
+                }
+                self.lists.push((name == "ol").then_some(0));
+            }
+            ("ul" | "ol", true) => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.block();
+                }
+            }
+            ("li", false) => {
+                self.line();
+                let depth = self.lists.len().saturating_sub(1);
+                self.out.push_str(&"  ".repeat(depth));
+                match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        self.out.push_str(&format!("{number}. "));
+                    }
+                    _ => self.out.push_str("- "),
+                }
+            }
+            ("dt", false) => {
+                self.line();
+                self.out.push_str("**");
+            }
+            ("dt", true) => self.out.push_str("**"),
+            ("dd", false) => {
+                self.line();
+                self.out.push_str(": ");
+            }
+            ("tr", false) => {
+                self.line();
+                self.cells = 0;
+            }
+            ("tr", true) => {
+                self.out.push_str(" |");
+                if !self.table_header {
+                    self.out.push('\n');
+                    self.out.push_str(&"|---".repeat(self.cells.max(1)));
+                    self.out.push('|');
+                    self.table_header = true;
+                }
+            }
+            ("td" | "th", false) => {
+                self.out
+                    .push_str(if self.cells == 0 { "| " } else { " | " });
+                self.cells += 1;
+            }
+            ("table", false) => {
+                self.block();
+                self.table_header = false;
+            }
+            ("a", false) => {
+                let href = attribute(tag, "href")
+                    .filter(|href| !href.starts_with('#') && !href.starts_with("javascript:"))
+                    .map(|href| match &self.base {
+                        Some(base) => doc_link(base, &href).unwrap_or(href),
+                        None => href,
+                    });
+                self.links.push((self.out.len(), href));
+            }
+            ("a", true) => {
+                if let Some((start, Some(href))) = self.links.pop() {
+                    let text = self.out[start..].trim().to_string();
+                    if !text.is_empty() {
+                        self.out.truncate(start);
+                        self.out.push_str(&format!("[{text}]({href})"));
+                    }
+                }
+            }
+            ("img", _) => {
+                if let Some(alt) = attribute(tag, "alt").filter(|alt| !alt.is_empty()) {
+                    self.out.push_str(&format!("[{alt}]"));
+                }
+            }
+            (name, _) if BLOCKS.contains(&name) => self.block(),
+            _ => {}
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut out = String::new();
+        let mut blank = false;
+        for line in self.out.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                blank = !out.is_empty();
+                continue;
+            }
+            if blank {
+                out.push('\n');
+                blank = false;
+            }
+            out.push_str(line);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// The `aurora-doc://` URI a relative link from a page in topic `base`
+/// points at; `None` for links out of the documentation.
+fn doc_link(base: &str, href: &str) -> Option<String> {
+    if href.contains("://") || href.starts_with(['/', '?']) || href.starts_with("mailto:") {
+        return None;
+    }
+    let (target, fragment) = match href.split_once('#') {
+        Some((target, fragment)) => (target, Some(fragment)),
+        None => (href, None),
+    };
+    let mut parts: Vec<&str> = base.split('/').filter(|part| !part.is_empty()).collect();
+    for part in target.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    let last = parts.pop()?;
+    let stem = [".html", ".htm", ".md", ".markdown", ".txt"]
+        .iter()
+        .find_map(|ext| last.strip_suffix(ext))?;
+    if !stem.eq_ignore_ascii_case("index") && !stem.eq_ignore_ascii_case("readme")
+        || parts.is_empty()
+    {
+        parts.push(stem);
+    }
+    let mut uri = super::uri(None, &parts.join("/").replace(' ', "-"));
+    if let Some(fragment) = fragment {
+        uri.push('#');
+        uri.push_str(fragment);
+    }
+    Some(uri)
+}
+
+/// Value of attribute `name` in the text of a start tag.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(at) = lower[from..].find(name) {
+        let start = from + at;
+        from = start + name.len();
+        let before = lower[..start].chars().next_back();
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let rest = tag[from..].trim_start();
+        let Some(rest) = rest.strip_prefix('=') else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        let value = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => rest[1..].split(quote).next().unwrap_or(""),
+            _ => rest
+                .split(|c: char| c.is_whitespace() || c == '>')
+                .next()
+                .unwrap_or(""),
+        };
+        return Some(decode_entities(value));
+    }
+    None
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let decoded = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            "mdash" => Some('—'),
+            "ndash" => Some('–'),
+            "hellip" => Some('…'),
+            "laquo" => Some('«'),
+            "raquo" => Some('»'),
+            "rarr" => Some('→'),
+            _ => {
+                let number = entity.strip_prefix('#')?;
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => number.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+        match (entity, decoded) {
+            (Some(entity), Some(c)) => {
+                out.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,423 @@
+//! Aurora OS developer documentation as MCP resources. A built-in set of
+//! pages covers the essentials; a local copy of the full documentation
+//! (Markdown or HTML, e.g. a saved copy of the developer portal) can be
+//! indexed from the `[docs]` config table and takes precedence. Pages are
+//! addressed by topic path, `aurora-doc://packaging/desktop-file`, and a
+//! topic's URI reads as an index of what is under it. HTML is converted to
+//! Markdown when read. Without a configured copy, the downloaded offline
+//! bundle for the project's OS release is served instead; a release in
+//! front of the path, `aurora-doc://4.0/packaging/desktop-file`, asks for
+//! that release's bundle, downloading it if needed.
+//!
+//! ```toml
+//! [docs]
+//! path = "~/AuroraOS/docs"
+//! ```
+
+pub mod bundle;
+pub mod dbus;
+pub mod markdown;
+pub mod search;
+pub mod snippets;
+
+use std::collections::BTreeMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::router::tool::ToolRouter;
+use serde::Deserialize;
+
+use crate::config::{Config, expand_tilde};
+use crate::error::Result;
+use crate::sdk::silica::OsVersion;
+use crate::server::AuroraServer;
+
+pub const URI_SCHEME: &str = "aurora-doc://";
+
+/// Extensions of indexed documentation files.
+const EXTENSIONS: &[&str] = &["md", "markdown", "html", "htm", "txt"];
+/// How deep topics nest in a local copy.
+const MAX_DEPTH: usize = 8;
+/// How much of a file is read to find its title when listing.
+const TITLE_BYTES: u64 = 8192;
+
+/// Pages shipped with the server, by topic path.
+const BUILTIN: &[(&str, &str)] = &[
+    (
+        "getting-started/sdk",
+        include_str!("pages/getting-started/sdk.md"),
+    ),
+    (
+        "getting-started/project-layout",
+        include_str!("pages/getting-started/project-layout.md"),
+    ),
+    (
+        "user-interface/silica-pages",
+        include_str!("pages/user-interface/silica-pages.md"),
+    ),
+    (
+        "packaging/rpm-spec",
+        include_str!("pages/packaging/rpm-spec.md"),
+    ),
+    (
+        "packaging/desktop-file",
+        include_str!("pages/packaging/desktop-file.md"),
+    ),
+    (
+        "packaging/store-requirements",
+        include_str!("pages/packaging/store-requirements.md"),
+    ),
+    (
+        "platform/permissions",
+        include_str!("pages/platform/permissions.md"),
+    ),
+    ("platform/dbus", include_str!("pages/platform/dbus.md")),
+    (
+        "platform/python-apps",
+        include_str!("pages/platform/python-apps.md"),
+    ),
+];
+
+/// Documentation settings, from the `[docs]` config table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DocsOptions {
+    /// Directory with a local copy of the documentation; its subdirectories
+    /// become topics. Served instead of any downloaded bundle.
+    pub path: Option<PathBuf>,
+    /// Where offline bundles are downloaded from: `{version}` stands for
+    /// the OS release, e.g. `5.1.3.85`, `{release}` for its first two parts.
+    pub bundle_url: Option<String>,
+    /// Where bundles are unpacked (default: `docs` in the data directory).
+    pub bundle_dir: Option<PathBuf>,
+    /// Release whose bundle is served (default: that of the `[sdk]` target,
+    /// else the newest bundle).
+    pub version: Option<String>,
+    /// `curl` binary (default: `curl` on `PATH`).
+    pub curl: PathBuf,
+    /// Limit for a single download.
+    pub timeout_secs: u64,
+}
+
+impl Default for DocsOptions {
+    fn default() -> Self {
+        Self {
+            path: None,
+            bundle_url: None,
+            bundle_dir: None,
+            version: None,
+            curl: PathBuf::from("curl"),
+            timeout_secs: 600,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Source {
+    Builtin(&'static str),
+    /// A file of the local copy and its directory's topic path, which
+    /// relative links start from.
+    File(PathBuf, String),
+}
+
+/// A documentation page.
+#[derive(Debug, Clone)]
+pub struct Page {
+    /// Topic path, e.g. `packaging/desktop-file`.
+    pub path: String,
+    pub title: String,
+    source: Source,
+}
+
+impl Page {
+    /// The page's URI, for `version`'s bundle if given.
+    pub fn uri(&self, version: Option<&str>) -> String {
+        uri(version, &self.path)
+    }
+
+    /// The file of the local copy the page comes from.
+    pub fn file(&self) -> Option<&Path> {
+        match &self.source {
+            Source::Builtin(_) => None,
+            Source::File(file, _) => Some(file),
+        }
+    }
+
+    /// The page as Markdown, its links to other pages pointing into
+    /// `version`'s bundle if given.
+    pub fn markdown(&self, version: Option<&str>) -> Result<String> {
+        match &self.source {
+            Source::Builtin(text) => Ok(text.to_string()),
+            Source::File(file, dir) => {
+                let text = String::from_utf8_lossy(&std::fs::read(file)?).into_owned();
+                Ok(if is_html(file) {
+                    let base = match version {
+                        Some(version) => format!("{version}/{dir}"),
+                        None => dir.clone(),
+                    };
+                    markdown::from_html(&text, Some(base.trim_end_matches('/')))
+                } else {
+                    text
+                })
+            }
+        }
+    }
+}
+
+pub fn uri(version: Option<&str>, path: &str) -> String {
+    match version {
+        Some(version) => format!("{URI_SCHEME}{version}/{path}"),
+        None => format!("{URI_SCHEME}{path}"),
+    }
+}
+
+/// A parsed `aurora-doc://` URI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DocUri<'a> {
+    /// The OS release asked for, when the path starts with one.
+    pub version: Option<&'a str>,
+    /// Topic path without any `#fragment`; empty for the root.
+    pub path: &'a str,
+}
+
+pub fn parse_uri(uri: &str) -> Option<DocUri<'_>> {
+    let path = uri.strip_prefix(URI_SCHEME)?;
+    let path = path.split('#').next().unwrap_or(path).trim_matches('/');
+    if path.split('/').any(|part| part == "..") {
+        return None;
+    }
+    let (first, rest) = path.split_once('/').unwrap_or((path, ""));
+    if first.starts_with(|c: char| c.is_ascii_digit()) && OsVersion::parse(first).is_ok() {
+        return Some(DocUri {
+            version: Some(first),
+            path: rest,
+        });
+    }
+    Some(DocUri {
+        version: None,
+        path,
+    })
+}
+
+/// The directory pages are served from besides the built-in ones: the
+/// configured local copy, else the bundle for the configured release.
+pub fn local_root(config: &Config) -> Option<PathBuf> {
+    if let Some(path) = &config.docs.path {
+        return Some(expand_tilde(path));
+    }
+    let bundles = bundle::installed(config);
+    bundle::select(&bundles, bundle::configured_version(config).as_deref())
+        .map(|bundle| bundle.dir.clone())
+}
+
+/// Every page, sorted by path. Pages under `root` replace built-in ones
+/// with the same path.
+pub fn pages(root: Option<&Path>) -> Vec<Page> {
+    let mut pages: BTreeMap<String, Page> = BUILTIN
+        .iter()
+        .map(|(path, text)| {
+            let page = Page {
+                path: path.to_string(),
+                title: markdown_title(text).unwrap_or_else(|| topic_title(path)),
+                source: Source::Builtin(text),
+            };
+            (path.to_string(), page)
+        })
+        .collect();
+    if let Some(root) = root {
+        let mut files = Vec::new();
+        collect_files(root, 0, &mut files);
+        for file in files {
+            let Some(path) = page_path(root, &file) else {
+                continue;
+            };
+            let title = file_title(&file).unwrap_or_else(|| topic_title(&path));
+            let dir = file
+                .parent()
+                .and_then(|dir| dir.strip_prefix(root).ok())
+                .map(|dir| dir.to_string_lossy().replace(' ', "-"))
+                .unwrap_or_default();
+            pages.insert(
+                path.clone(),
+                Page {
+                    path,
+                    title,
+                    source: Source::File(file, dir),
+                },
+            );
+        }
+    }
+    pages.into_values().collect()
+}
+
+/// Topic paths that have pages under them, sorted, parents first.
+pub fn topics(pages: &[Page]) -> Vec<String> {
+    let mut topics: Vec<String> = pages
+        .iter()
+        .flat_map(|page| {
+            let parts: Vec<&str> = page.path.split('/').collect();
+            (1..parts.len())
+                .map(|len| parts[..len].join("/"))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    topics.sort();
+    topics.dedup();
+    topics
+}
+
+/// Title for a topic or page without one: its last path part, spelled
+/// out.
+pub fn topic_title(path: &str) -> String {
+    let last = path.rsplit('/').next().unwrap_or(path);
+    let words = last.replace(['-', '_'], " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => "Aurora OS documentation".to_string(),
+    }
+}
+
+/// Markdown for the page or topic at `path`: the page itself, or an index
+/// of the subtopics and pages under the topic. `None` if there is neither.
+/// `version` is the release `root` is the bundle of, for links.
+pub fn read(root: Option<&Path>, version: Option<&str>, path: &str) -> Result<Option<String>> {
+    let pages = pages(root);
+    if let Some(page) = pages.iter().find(|page| page.path == path) {
+        return page.markdown(version).map(Some);
+    }
+    let prefix = if path.is_empty() {
+        String::new()
+    } else {
+        format!("{path}/")
+    };
+    let depth = prefix.matches('/').count();
+    let subtopics: Vec<String> = topics(&pages)
+        .into_iter()
+        .filter(|topic| topic.starts_with(&prefix) && topic.matches('/').count() == depth)
+        .collect();
+    let children: Vec<&Page> = pages
+        .iter()
+        .filter(|page| page.path.starts_with(&prefix) && page.path.matches('/').count() == depth)
+        .collect();
+    if subtopics.is_empty() && children.is_empty() {
+        return Ok(None);
+    }
+    let mut text = match (path.is_empty(), version) {
+        (true, Some(version)) => format!("# Aurora OS {version} documentation\n"),
+        _ => format!("# {}\n", topic_title(path)),
+    };
+    if !subtopics.is_empty() {
+        text.push_str("\n## Topics\n\n");
+        for topic in &subtopics {
+            text.push_str(&format!(
+                "- [{}]({})\n",
+                topic_title(topic),
+                uri(version, topic)
+            ));
+        }
+    }
+    if !children.is_empty() {
+        text.push_str("\n## Pages\n\n");
+        for page in children {
+            text.push_str(&format!("- [{}]({})\n", page.title, page.uri(version)));
+        }
+    }
+    Ok(Some(text))
+}
+
+/// Hash of what `read` renders from: `version` and the paths, sizes and
+/// modification times of the files under `root`. Built-in pages never
+/// change.
+pub fn revision(root: Option<&Path>, version: Option<&str>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    version.hash(&mut hasher);
+    if let Some(root) = root {
+        let mut files = Vec::new();
+        collect_files(root, 0, &mut files);
+        files.sort();
+        for file in files {
+            if let Ok(meta) = std::fs::metadata(&file) {
+                file.hash(&mut hasher);
+                meta.len().hash(&mut hasher);
+                meta.modified().ok().hash(&mut hasher);
+            }
+        }
+    }
+    hasher.finish()
+}
+
+fn collect_files(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, depth + 1, files);
+        } else if path
+            .extension()
+            .is_some_and(|ext| EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// Topic path of a file of the local copy: its relative path without the
+/// extension, with `index` and `README` pages standing for their directory.
+fn page_path(root: &Path, file: &Path) -> Option<String> {
+    let relative = file.strip_prefix(root).ok()?.with_extension("");
+    let mut parts: Vec<String> = relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy().replace(' ', "-"))
+        .collect();
+    if parts.len() > 1
+        && parts.last().is_some_and(|last| {
+            last.eq_ignore_ascii_case("index") || last.eq_ignore_ascii_case("readme")
+        })
+    {
+        parts.pop();
+    }
+    let path = parts.join("/");
+    (!path.is_empty()).then_some(path)
+}
+
+fn is_html(file: &Path) -> bool {
+    file.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"))
+}
+
+fn markdown_title(text: &str) -> Option<String> {
+    text.lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .map(|title| title.trim().to_string())
+}
+
+/// Title from the start of a documentation file.
+fn file_title(file: &Path) -> Option<String> {
+    let mut head = Vec::new();
+    std::fs::File::open(file)
+        .ok()?
+        .take(TITLE_BYTES)
+        .read_to_end(&mut head)
+        .ok()?;
+    let text = String::from_utf8_lossy(&head);
+    if is_html(file) {
+        markdown::html_title(&text)
+    } else {
+        markdown_title(&text)
+    }
+}
+
+pub(crate) fn router() -> ToolRouter<AuroraServer> {
+    AuroraServer::bundle_router() + AuroraServer::search_router() + AuroraServer::snippets_router()
+}
@@ -0,0 +1,603 @@
+//! Full-text search over the documentation: a tantivy index of page
+//! sections under the data directory, built on first use and rebuilt when
+//! the pages change. Text is split into terms by [`tokens`], so identifiers
+//! also match by their parts, and tantivy ranks sections by BM25.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tantivy::collector::TopDocs;
+use tantivy::query::{BooleanQuery, BoostQuery, Occur, Query, TermQuery};
+use tantivy::schema::{
+    Field, IndexRecordOption, STORED, STRING, Schema, TextFieldIndexing, TextOptions, Value,
+};
+use tantivy::tokenizer::{TextAnalyzer, WhitespaceTokenizer};
+use tantivy::{
+    IndexReader, IndexWriter, ReloadPolicy, Score, Searcher, TantivyDocument, TantivyError, Term,
+};
+
+use super::Page;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Directory of the indexes, under the data directory.
+const INDEX_DIR: &str = "docs-index";
+/// Bumped when the schema or the terms change, so older indexes are
+/// rebuilt.
+const INDEX_FORMAT: u32 = 1;
+/// Analyzer of the fields holding [`tokens`] joined by spaces.
+const TERMS: &str = "aurora_terms";
+/// Headings and page titles weigh this much more than body text.
+const TITLE_BOOST: Score = 3.0;
+/// Memory the index writer may use, tantivy's minimum.
+const WRITER_MEMORY: usize = 15_000_000;
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 50;
+const SNIPPET_CHARS: usize = 240;
+/// Terms shorter than this match only whole words.
+const MIN_PREFIX_LEN: usize = 4;
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "be", "by", "can", "do", "does", "for", "from", "how", "i",
+    "in", "is", "it", "of", "on", "or", "that", "the", "this", "to", "what", "when", "with",
+];
+
+/// The open search index, shared by all sessions, with the fingerprint of
+/// the pages it was built from.
+#[derive(Clone, Default)]
+pub struct DocsIndex {
+    inner: Arc<Mutex<Option<BuiltIndex>>>,
+}
+
+type BuiltIndex = (u64, Arc<Index>);
+
+impl std::fmt::Debug for DocsIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DocsIndex").finish_non_exhaustive()
+    }
+}
+
+impl DocsIndex {
+    /// The index of the current pages in `data_dir`, built there if none
+    /// matches them.
+    /// `version` is the release `root` is the bundle of, if one was asked
+    /// for.
+    pub fn get(
+        &self,
+        data_dir: &Path,
+        root: Option<&Path>,
+        version: Option<&str>,
+    ) -> Result<Arc<Index>> {
+        let pages = super::pages(root);
+        let fingerprint = fingerprint(&pages, version);
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((built, index)) = inner.as_ref()
+            && *built == fingerprint
+        {
+            return Ok(index.clone());
+        }
+        let dir = data_dir.join(INDEX_DIR);
+        let index = Arc::new(Index::open_or_build(
+            &dir,
+            &pages,
+            root,
+            version,
+            fingerprint,
+        )?);
+        *inner = Some((fingerprint, index.clone()));
+        Ok(index)
+    }
+}
+
+/// Hash of the index format, the page paths and, for files, their size and
+/// modification time.
+fn fingerprint(pages: &[Page], version: Option<&str>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    INDEX_FORMAT.hash(&mut hasher);
+    version.hash(&mut hasher);
+    for page in pages {
+        page.path.hash(&mut hasher);
+        if let Some(file) = page.file() {
+            file.hash(&mut hasher);
+            if let Ok(meta) = std::fs::metadata(file) {
+                meta.len().hash(&mut hasher);
+                meta.modified().ok().hash(&mut hasher);
+            }
+        }
+    }
+    hasher.finish()
+}
+
+fn index_error(err: TantivyError) -> Error {
+    Error::Io(std::io::Error::other(err))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Fields {
+    uri: Field,
+    page_title: Field,
+    heading: Field,
+    text: Field,
+    /// Terms of the page title and heading.
+    title_terms: Field,
+    /// Terms of the section's text.
+    body_terms: Field,
+    /// The page's topic path and its ancestors, for filtering by topic.
+    topics: Field,
+}
+
+impl Fields {
+    fn schema() -> Schema {
+        let terms = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(TERMS)
+                .set_index_option(IndexRecordOption::WithFreqs),
+        );
+        let mut schema = Schema::builder();
+        schema.add_text_field("uri", STORED);
+        schema.add_text_field("page_title", STORED);
+        schema.add_text_field("heading", STORED);
+        schema.add_text_field("text", STORED);
+        schema.add_text_field("title_terms", terms.clone());
+        schema.add_text_field("body_terms", terms);
+        schema.add_text_field("topics", STRING);
+        schema.build()
+    }
+
+    fn of(schema: &Schema) -> Result<Self> {
+        let field = |name| schema.get_field(name).map_err(index_error);
+        Ok(Self {
+            uri: field("uri")?,
+            page_title: field("page_title")?,
+            heading: field("heading")?,
+            text: field("text")?,
+            title_terms: field("title_terms")?,
+            body_terms: field("body_terms")?,
+            topics: field("topics")?,
+        })
+    }
+}
+
+pub struct Index {
+    reader: IndexReader,
+    fields: Fields,
+}
+
+impl Index {
+    /// The index of `fingerprint` in `dir`, built if it is not there.
+    /// Indexes are built beside it and moved into place once committed, so
+    /// servers sharing the data directory never open a partial one.
+    fn open_or_build(
+        dir: &Path,
+        pages: &[Page],
+        root: Option<&Path>,
+        version: Option<&str>,
+        fingerprint: u64,
+    ) -> Result<Self> {
+        // Indexes of other pages of the same source are stale.
+        let source = {
+            let mut hasher = DefaultHasher::new();
+            (root, version).hash(&mut hasher);
+            format!("{:016x}-", hasher.finish())
+        };
+        let name = format!("{source}{fingerprint:016x}");
+        let path = dir.join(&name);
+        if path.join("meta.json").is_file() {
+            match Self::open(&path) {
+                Ok(index) => return Ok(index),
+                Err(err) => {
+                    tracing::warn!("rebuilding the docs index {}: {err}", path.display());
+                    std::fs::remove_dir_all(&path)?;
+                }
+            }
+        }
+        let staging = dir.join(format!(".{name}-{}", std::process::id()));
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+        std::fs::create_dir_all(&staging)?;
+        if let Err(err) = Self::build(&staging, pages, version) {
+            let _ = std::fs::remove_dir_all(&staging);
+            return Err(err);
+        }
+        if std::fs::rename(&staging, &path).is_err() {
+            // Another server moved the same index into place first.
+            let _ = std::fs::remove_dir_all(&staging);
+        }
+        for entry in std::fs::read_dir(dir)?.filter_map(|entry| entry.ok()) {
+            let other = entry.file_name().to_string_lossy().into_owned();
+            if other.starts_with(&source) && other != name {
+                let _ = std::fs::remove_dir_all(entry.path());
+            }
+        }
+        Self::open(&path)
+    }
+
+    fn open(path: &Path) -> Result<Self> {
+        let index = tantivy::Index::open_in_dir(path).map_err(index_error)?;
+        register_terms(&index);
+        let fields = Fields::of(&index.schema())?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()
+            .map_err(index_error)?;
+        Ok(Self { reader, fields })
+    }
+
+    /// Writes the index of `pages` to `path`, one document per section.
+    fn build(path: &Path, pages: &[Page], version: Option<&str>) -> Result<()> {
+        let index = tantivy::Index::create_in_dir(path, Fields::schema()).map_err(index_error)?;
+        register_terms(&index);
+        let fields = Fields::of(&index.schema())?;
+        let mut writer: IndexWriter = index
+            .writer_with_num_threads(1, WRITER_MEMORY)
+            .map_err(index_error)?;
+        for page in pages {
+            let text = page.markdown(version)?;
+            for (heading, body) in sections(&text) {
+                let mut document = TantivyDocument::new();
+                let uri = match &heading {
+                    Some(heading) => format!("{}#{}", page.uri(version), slug(heading)),
+                    None => page.uri(version),
+                };
+                document.add_text(fields.uri, uri);
+                document.add_text(fields.page_title, &page.title);
+                let mut titles = tokens(&page.title);
+                if let Some(heading) = &heading {
+                    document.add_text(fields.heading, heading);
+                    titles.extend(tokens(heading));
+                }
+                document.add_text(fields.title_terms, titles.join(" "));
+                document.add_text(fields.body_terms, tokens(&body).join(" "));
+                document.add_text(fields.text, body);
+                let mut topic = String::new();
+                for part in page.path.split('/') {
+                    if !topic.is_empty() {
+                        topic.push('/');
+                    }
+                    topic.push_str(part);
+                    document.add_text(fields.topics, &topic);
+                }
+                writer.add_document(document).map_err(index_error)?;
+            }
+        }
+        writer.commit().map_err(index_error)?;
+        writer.wait_merging_threads().map_err(index_error)?;
+        Ok(())
+    }
+
+    /// Sections indexed.
+    fn sections(&self) -> usize {
+        self.reader.searcher().num_docs() as usize
+    }
+
+    /// Terms of the index starting with `prefix`.
+    fn with_prefix(&self, searcher: &Searcher, prefix: &str) -> Result<Vec<String>> {
+        let mut terms = Vec::new();
+        for segment in searcher.segment_readers() {
+            for field in [self.fields.title_terms, self.fields.body_terms] {
+                let inverted = segment.inverted_index(field).map_err(index_error)?;
+                let mut stream = inverted.terms().range().ge(prefix).into_stream()?;
+                while stream.advance() {
+                    match std::str::from_utf8(stream.key()) {
+                        Ok(term) if term.starts_with(prefix) => terms.push(term.to_string()),
+                        _ => break,
+                    }
+                }
+            }
+        }
+        terms.sort();
+        terms.dedup();
+        Ok(terms)
+    }
+
+    /// Sections matching `query`, best first.
+    fn search(&self, query: &str, topic: Option<&str>, limit: usize) -> Result<Vec<SearchHit>> {
+        let searcher = self.reader.searcher();
+        let fields = self.fields;
+        let in_index = |term: &str| -> Result<u64> {
+            let mut count = 0;
+            for field in [fields.title_terms, fields.body_terms] {
+                count += searcher
+                    .doc_freq(&Term::from_field_text(field, term))
+                    .map_err(index_error)?;
+            }
+            Ok(count)
+        };
+        // Whole words, else words starting with the term, so `notif` finds
+        // `notifications`. Rarest first, to pick the snippet's term.
+        let mut terms: Vec<(u64, String)> = Vec::new();
+        for term in tokens(query) {
+            let count = in_index(&term)?;
+            if count > 0 {
+                terms.push((count, term));
+            } else if term.chars().count() >= MIN_PREFIX_LEN {
+                for term in self.with_prefix(&searcher, &term)? {
+                    terms.push((in_index(&term)?, term));
+                }
+            }
+        }
+        terms.sort();
+        terms.dedup();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let term_query = |field, term: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(
+                Term::from_field_text(field, term),
+                IndexRecordOption::WithFreqs,
+            ))
+        };
+        let mut clauses = Vec::new();
+        for (_, term) in &terms {
+            clauses.push((Occur::Should, term_query(fields.body_terms, term)));
+            let title = BoostQuery::new(term_query(fields.title_terms, term), TITLE_BOOST);
+            clauses.push((Occur::Should, Box::new(title) as Box<dyn Query>));
+        }
+        let mut matching: Box<dyn Query> = Box::new(BooleanQuery::new(clauses));
+        if let Some(topic) = topic {
+            let topic = TermQuery::new(
+                Term::from_field_text(fields.topics, topic),
+                IndexRecordOption::Basic,
+            );
+            matching = Box::new(BooleanQuery::new(vec![
+                (Occur::Must, matching),
+                (Occur::Must, Box::new(topic)),
+            ]));
+        }
+        // Sections containing the query as typed rank first, so with a
+        // phrase every match is a candidate.
+        let phrase = query.trim().to_lowercase();
+        let candidates = if phrase.contains(' ') {
+            self.sections().max(1)
+        } else {
+            limit
+        };
+        let top = searcher
+            .search(&matching, &TopDocs::with_limit(candidates))
+            .map_err(index_error)?;
+
+        let mut ranked = Vec::new();
+        for (score, address) in top {
+            let document: TantivyDocument = searcher.doc(address).map_err(index_error)?;
+            let text = |field| {
+                document
+                    .get_first(field)
+                    .and_then(|value| value.as_str())
+                    .map(str::to_string)
+            };
+            let body = text(fields.text).unwrap_or_default();
+            let exact = phrase.contains(' ') && body.to_lowercase().contains(&phrase);
+            let score = f64::from(score) * if exact { 2.0 } else { 1.0 };
+            let heading = text(fields.heading);
+            let words = tokens(&format!("{} {body}", heading.as_deref().unwrap_or("")));
+            let term = terms
+                .iter()
+                .map(|(_, term)| term.as_str())
+                .find(|term| words.iter().any(|word| word == term));
+            ranked.push((
+                score,
+                SearchHit {
+                    uri: text(fields.uri).unwrap_or_default(),
+                    title: text(fields.page_title).unwrap_or_default(),
+                    section: heading,
+                    score: (score * 100.0).round() / 100.0,
+                    snippet: snippet(&body, term),
+                },
+            ));
+        }
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Ok(ranked.into_iter().take(limit).map(|(_, hit)| hit).collect())
+    }
+}
+
+/// Lets `index` analyze the fields holding [`tokens`].
+fn register_terms(index: &tantivy::Index) {
+    index
+        .tokenizers()
+        .register(TERMS, TextAnalyzer::from(WhitespaceTokenizer::default()));
+}
+
+/// The page split at its headings: `(heading, text)`, the first without a
+/// heading if the page starts with text. Headings inside code blocks are
+/// code.
+fn sections(markdown: &str) -> Vec<(Option<String>, String)> {
+    let mut sections = vec![(None, String::new())];
+    let mut in_code = false;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        let heading = (!in_code)
+            .then(|| line.strip_prefix('#'))
+            .flatten()
+            .map(|rest| rest.trim_start_matches('#'))
+            .filter(|rest| rest.starts_with(' '));
+        match heading {
+            Some(heading) => sections.push((Some(heading.trim().to_string()), String::new())),
+            None => {
+                let text = &mut sections.last_mut().expect("never empty").1;
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+    }
+    sections.retain(|(heading, text)| heading.is_some() || !text.trim().is_empty());
+    sections
+}
+
+/// Lower-case words of `text` without stop words. Mixed-case identifiers
+/// also yield their parts: `SilicaListView` gives `silicalistview`,
+/// `silica`, `list` and `view`.
+pub(super) fn tokens(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for word in text.split(|c: char| !c.is_alphanumeric() && c != '_') {
+        if word.is_empty() {
+            continue;
+        }
+        let lower = word.to_lowercase();
+        if !STOP_WORDS.contains(&lower.as_str()) {
+            tokens.push(lower.clone());
+        }
+        let mut parts = Vec::new();
+        let mut part = String::new();
+        let mut previous_lower = false;
+        for c in word.chars() {
+            if (c == '_' || (c.is_uppercase() && previous_lower)) && !part.is_empty() {
+                parts.push(std::mem::take(&mut part));
+            }
+            if c != '_' {
+                part.extend(c.to_lowercase());
+            }
+            previous_lower = c.is_lowercase() || c.is_ascii_digit();
+        }
+        parts.push(part);
+        if parts.len() > 1 {
+            tokens.extend(
+                parts.into_iter().filter(|part| {
+                    part.chars().count() > 1 && !STOP_WORDS.contains(&part.as_str())
+                }),
+            );
+        }
+    }
+    tokens
+}
+
+/// GitHub-style anchor of a heading.
+fn slug(heading: &str) -> String {
+    heading
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' | '-' => Some('-'),
+            c if c.is_alphanumeric() || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// About `SNIPPET_CHARS` of `text` around the first occurrence of `term`,
+/// whitespace collapsed.
+fn snippet(text: &str, term: Option<&str>) -> String {
+    let words: Vec<&str> = text
+        .split_whitespace()
+        .filter(|word| !word.starts_with("```"))
+        .collect();
+    let hit = term
+        .and_then(|term| {
+            words
+                .iter()
+                .position(|word| word.to_lowercase().contains(term))
+        })
+        .unwrap_or(0);
+    let mut start = hit.saturating_sub(8);
+    let mut length = 0;
+    let mut end = start;
+    while end < words.len() && length < SNIPPET_CHARS {
+        length += words[end].chars().count() + 1;
+        end += 1;
+    }
+    while start > 0 && length < SNIPPET_CHARS {
+        start -= 1;
+        length += words[start].chars().count() + 1;
+    }
+    let mut snippet = words[start..end].join(" ");
+    if start > 0 {
+        snippet.insert_str(0, "… ");
+    }
+    if end < words.len() {
+        snippet.push_str(" …");
+    }
+    snippet
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SearchDocsParams {
+    /// What to look for, e.g. "show a notification" or "PullDownMenu".
+    pub query: String,
+    /// Only pages under this topic, e.g. `packaging`.
+    #[serde(default)]
+    pub topic: Option<String>,
+    /// Search the documentation of this OS release, e.g. `4.0`, downloading
+    /// its bundle if needed (default: the bundle served for the project).
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Most results to return (default 10, at most 50).
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SearchHit {
+    /// Resource URI of the page, with the section's anchor.
+    pub uri: String,
+    /// Page title.
+    pub title: String,
+    /// Heading of the matching section.
+    pub section: Option<String>,
+    pub score: f64,
+    pub snippet: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SearchDocsResult {
+    pub query: String,
+    pub hits: Vec<SearchHit>,
+    /// Sections searched.
+    pub sections: usize,
+}
+
+#[tool_router(router = search_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Search the Aurora OS developer documentation (built-in pages plus the local copy or offline bundle) for a question or API name, optionally for a specific OS release. Returns ranked sections with snippets and aurora-doc:// URIs to read with resources/read.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn search_docs(
+        &self,
+        Parameters(params): Parameters<SearchDocsParams>,
+    ) -> Result<Json<SearchDocsResult>> {
+        if tokens(&params.query).is_empty() {
+            return Err(Error::InvalidArgument(
+                "the query has no words to search for".to_string(),
+            ));
+        }
+        let state = self.state();
+        let root = match &params.version {
+            Some(version) => Some(super::bundle::versioned_root(self, version).await?),
+            None => super::local_root(&state.config),
+        };
+        let data_dir = state.config.data_dir();
+        let version = params.version.clone();
+        let docs_index = state.docs_index.clone();
+        let query = params.query.clone();
+        let topic = params
+            .topic
+            .as_deref()
+            .map(|topic| topic.trim_matches('/').to_string())
+            .filter(|topic| !topic.is_empty());
+        let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let (hits, sections) = tokio::task::spawn_blocking(move || {
+            let index = docs_index.get(&data_dir, root.as_deref(), version.as_deref())?;
+            Ok::<_, Error>((
+                index.search(&query, topic.as_deref(), limit)?,
+                index.sections(),
+            ))
+        })
+        .await
+        .map_err(|err| Error::Io(std::io::Error::other(err)))??;
+        Ok(Json(SearchDocsResult {
+            hits,
+            sections,
+            query: params.query,
+        }))
+    }
+}
@@ -0,0 +1,318 @@
+//! Vetted code snippets for common tasks — Silica page patterns, D-Bus,
+//! notifications, file access within the sandbox — served as
+//! `aurora-snippet://<id>` resources and found by task with
+//! `find_snippet`, so that generated code starts from a pattern known to
+//! work on the device.
+
+use std::collections::BTreeSet;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::search::tokens;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+pub const URI_SCHEME: &str = "aurora-snippet://";
+
+const DEFAULT_LIMIT: usize = 5;
+const MAX_LIMIT: usize = 20;
+/// How much more a match in the title or tags counts than one in the
+/// description or code.
+const TITLE_WEIGHT: usize = 3;
+
+#[derive(Debug)]
+pub struct Snippet {
+    /// Path-like name, `<area>/<name>`.
+    pub id: &'static str,
+    pub title: &'static str,
+    /// Fence language of the code, e.g. `qml` or `cpp`.
+    pub language: &'static str,
+    /// Tasks the snippet is for.
+    pub tags: &'static [&'static str],
+    pub description: &'static str,
+    /// Topic paths of related documentation pages.
+    pub docs: &'static [&'static str],
+    pub code: &'static str,
+}
+
+pub const SNIPPETS: &[Snippet] = &[
+    Snippet {
+        id: "silica/flickable-page",
+        title: "Page with a pulley menu",
+        language: "qml",
+        tags: &["ui", "page", "menu", "layout"],
+        description: "A Silica page whose content scrolls in a SilicaFlickable with a PullDownMenu, a PageHeader and Theme-based margins. contentHeight must follow the content for the pulley menu and scrolling to work.",
+        docs: &["user-interface/silica-pages"],
+        code: include_str!("snippets/silica/flickable-page.qml"),
+    },
+    Snippet {
+        id: "silica/list-remorse",
+        title: "List with context menu and remorse delete",
+        language: "qml",
+        tags: &["ui", "list", "menu", "delete"],
+        description: "SilicaListView with ListItem delegates, a ContextMenu whose Remove action goes through remorseDelete, a ViewPlaceholder for the empty list and an Add pulley action.",
+        docs: &["user-interface/silica-pages"],
+        code: include_str!("snippets/silica/list-remorse.qml"),
+    },
+    Snippet {
+        id: "silica/dialog",
+        title: "Accept/cancel dialog",
+        language: "qml",
+        tags: &["ui", "dialog", "form", "input"],
+        description: "A Dialog with a DialogHeader that can only be accepted once its TextField is filled in, accepting from the keyboard's enter key, and how the opener reads the result.",
+        docs: &["user-interface/silica-pages"],
+        code: include_str!("snippets/silica/dialog.qml"),
+    },
+    Snippet {
+        id: "silica/cover",
+        title: "Application cover with actions",
+        language: "qml",
+        tags: &["ui", "cover", "home-screen"],
+        description: "A CoverBackground showing the app's state on the home screen, with CoverActionList actions using the standard cover icons.",
+        docs: &["user-interface/silica-pages"],
+        code: include_str!("snippets/silica/cover.qml"),
+    },
+    Snippet {
+        id: "dbus/qml-interface",
+        title: "Calling a D-Bus service from QML",
+        language: "qml",
+        tags: &["dbus", "ipc", "signals"],
+        description: "Nemo.DBus DBusInterface making an asynchronous typedCall with result and error callbacks, and a second interface receiving a D-Bus signal through a function named after it (signalsEnabled).",
+        docs: &["platform/dbus"],
+        code: include_str!("snippets/dbus/qml-interface.qml"),
+    },
+    Snippet {
+        id: "dbus/cpp-adaptor",
+        title: "Exposing a D-Bus service from C++",
+        language: "cpp",
+        tags: &["dbus", "ipc", "service", "activation"],
+        description: "A QDBusAbstractAdaptor publishing an object on the session bus under the app's name, with error reporting for registration and the service file that lets D-Bus start the app.",
+        docs: &["platform/dbus"],
+        code: include_str!("snippets/dbus/cpp-adaptor.cpp"),
+    },
+    Snippet {
+        id: "notifications/qml",
+        title: "Notification from QML",
+        language: "qml",
+        tags: &["notifications", "dbus"],
+        description: "A Nemo.Notifications Notification with preview banner text and a default remote action that calls back into the app over D-Bus when tapped.",
+        docs: &["platform/dbus"],
+        code: include_str!("snippets/notifications/notification.qml"),
+    },
+    Snippet {
+        id: "notifications/cpp",
+        title: "Notification from C++",
+        language: "cpp",
+        tags: &["notifications", "dbus"],
+        description: "Publishing a notification with nemonotifications-qt5, including the pkg-config dependency for the .pro and spec files and a remote action for opening the app.",
+        docs: &["packaging/rpm-spec"],
+        code: include_str!("snippets/notifications/notification.cpp"),
+    },
+    Snippet {
+        id: "files/app-data",
+        title: "Files in the application's sandbox",
+        language: "cpp",
+        tags: &["files", "storage", "sandbox", "permissions"],
+        description: "Saving and locating files with QStandardPaths in the data and cache directories the sandbox always allows, which follow OrganizationName and ApplicationName, and the permission user directories such as Documents need.",
+        docs: &["platform/permissions", "packaging/desktop-file"],
+        code: include_str!("snippets/files/app-data.cpp"),
+    },
+    Snippet {
+        id: "files/picker",
+        title: "Picking a document",
+        language: "qml",
+        tags: &["files", "ui", "picker", "permissions"],
+        description: "Sailfish.Pickers DocumentPickerPage opened from a ValueButton, reading the chosen file's path. The app needs the Documents permission to open what is picked.",
+        docs: &["platform/permissions"],
+        code: include_str!("snippets/files/picker.qml"),
+    },
+];
+
+impl Snippet {
+    pub fn uri(&self) -> String {
+        format!("{URI_SCHEME}{}", self.id)
+    }
+
+    /// The snippet as Markdown: description, code and related pages.
+    pub fn markdown(&self) -> String {
+        let mut text = format!(
+            "# {}\n\n{}\n\nTags: {}\n\n```{}\n{}",
+            self.title,
+            self.description,
+            self.tags.join(", "),
+            self.language,
+            self.code
+        );
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str("```\n");
+        if !self.docs.is_empty() {
+            text.push_str("\n## See also\n\n");
+            for path in self.docs {
+                text.push_str(&format!(
+                    "- [{}]({})\n",
+                    super::topic_title(path),
+                    super::uri(None, path)
+                ));
+            }
+        }
+        text
+    }
+
+    fn score(&self, terms: &[String]) -> usize {
+        let title = tokens(&format!(
+            "{} {} {}",
+            self.title,
+            self.id,
+            self.tags.join(" ")
+        ));
+        let body = tokens(&format!("{} {}", self.description, self.code));
+        terms
+            .iter()
+            .map(|term| {
+                let in_title = title.iter().filter(|token| *token == term).count();
+                let in_body = body.iter().filter(|token| *token == term).count();
+                // A term repeated all over the code says little more than
+                // one that appears a few times.
+                in_title * TITLE_WEIGHT + in_body.min(TITLE_WEIGHT)
+            })
+            .sum()
+    }
+}
+
+/// Id of an `aurora-snippet://` URI; empty for the index.
+pub fn parse_uri(uri: &str) -> Option<&str> {
+    uri.strip_prefix(URI_SCHEME).map(|id| id.trim_matches('/'))
+}
+
+/// Markdown for the snippet `id`, or for the index of snippets by tag when
+/// `id` is empty or an area such as `silica`.
+pub fn read(id: &str) -> Option<String> {
+    if let Some(snippet) = SNIPPETS.iter().find(|snippet| snippet.id == id) {
+        return Some(snippet.markdown());
+    }
+    let prefix = format!("{id}/");
+    let snippets: Vec<&Snippet> = SNIPPETS
+        .iter()
+        .filter(|snippet| id.is_empty() || snippet.id.starts_with(&prefix))
+        .collect();
+    if snippets.is_empty() {
+        return None;
+    }
+    let mut text = "# Code snippets\n".to_string();
+    for tag in all_tags() {
+        let tagged: Vec<&&Snippet> = snippets
+            .iter()
+            .filter(|snippet| snippet.tags.contains(&tag))
+            .collect();
+        if tagged.is_empty() {
+            continue;
+        }
+        text.push_str(&format!("\n## {tag}\n\n"));
+        for snippet in tagged {
+            text.push_str(&format!("- [{}]({})\n", snippet.title, snippet.uri()));
+        }
+    }
+    Some(text)
+}
+
+fn all_tags() -> BTreeSet<&'static str> {
+    SNIPPETS
+        .iter()
+        .flat_map(|snippet| snippet.tags.iter().copied())
+        .collect()
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FindSnippetParams {
+    /// The task, e.g. "show a notification" or "list with delete".
+    #[serde(default)]
+    pub query: Option<String>,
+    /// Only snippets with this tag, e.g. `dbus`.
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Only snippets in this language: `qml` or `cpp`.
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Most snippets to return (default 5, at most 20).
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SnippetHit {
+    pub id: String,
+    /// Resource URI of the snippet.
+    pub uri: String,
+    pub title: String,
+    pub language: String,
+    pub tags: Vec<String>,
+    pub description: String,
+    /// `None` when listing without a query.
+    pub score: Option<usize>,
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FindSnippetResult {
+    pub hits: Vec<SnippetHit>,
+    /// Every tag in the collection, for narrowing a search.
+    pub tags: Vec<String>,
+}
+
+#[tool_router(router = snippets_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Find vetted Aurora OS code snippets (Silica pages, lists, dialogs, covers, D-Bus clients and services, notifications, sandboxed file access) for a task, by query, tag or language. Returns the code with a description; reuse and adapt these patterns instead of writing platform code from scratch. Snippets are also aurora-snippet:// resources.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn find_snippet(
+        &self,
+        Parameters(params): Parameters<FindSnippetParams>,
+    ) -> Result<Json<FindSnippetResult>> {
+        let terms = params.query.as_deref().map(tokens).unwrap_or_default();
+        if params.query.is_some() && terms.is_empty() {
+            return Err(Error::InvalidArgument(
+                "the query has no words to search for".to_string(),
+            ));
+        }
+        let tag = params.tag.as_deref().map(str::to_lowercase);
+        let language = params.language.as_deref().map(str::to_lowercase);
+        let mut hits: Vec<(Option<usize>, &Snippet)> = SNIPPETS
+            .iter()
+            .filter(|snippet| {
+                tag.as_ref()
+                    .is_none_or(|tag| snippet.tags.contains(&tag.as_str()))
+            })
+            .filter(|snippet| {
+                language
+                    .as_ref()
+                    .is_none_or(|language| snippet.language == language)
+            })
+            .map(|snippet| ((!terms.is_empty()).then(|| snippet.score(&terms)), snippet))
+            .filter(|(score, _)| *score != Some(0))
+            .collect();
+        hits.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        hits.truncate(params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT));
+        Ok(Json(FindSnippetResult {
+            hits: hits
+                .into_iter()
+                .map(|(score, snippet)| SnippetHit {
+                    id: snippet.id.to_string(),
+                    uri: snippet.uri(),
+                    title: snippet.title.to_string(),
+                    language: snippet.language.to_string(),
+                    tags: snippet.tags.iter().map(|tag| tag.to_string()).collect(),
+                    description: snippet.description.to_string(),
+                    score,
+                    code: snippet.code.to_string(),
+                })
+                .collect(),
+            tags: all_tags().into_iter().map(str::to_string).collect(),
+        }))
+    }
+}
@@ -0,0 +1,61 @@
+// greeteradaptor.h
+#include <QDBusAbstractAdaptor>
+#include <QDBusConnection>
+#include <QDBusError>
+#include <QDebug>
+
+class Greeter : public QObject
+{
+    Q_OBJECT
+public:
+    using QObject::QObject;
+
+    QString greet(const QString &name) { return tr("Hello, %1").arg(name); }
+};
+
+class GreeterAdaptor : public QDBusAbstractAdaptor
+{
+    Q_OBJECT
+    Q_CLASSINFO("D-Bus Interface", "ru.example.app.Greeter")
+
+public:
+    explicit GreeterAdaptor(Greeter *greeter)
+        : QDBusAbstractAdaptor(greeter), m_greeter(greeter)
+    {
+    }
+
+public slots:
+    QString Greet(const QString &name)
+    {
+        emit Greeted(name);
+        return m_greeter->greet(name);
+    }
+
+signals:
+    void Greeted(const QString &name);
+
+private:
+    Greeter *m_greeter;
+};
+
+// main.cpp, after creating the application
+static bool registerService(Greeter *greeter)
+{
+    new GreeterAdaptor(greeter);
+    QDBusConnection bus = QDBusConnection::sessionBus();
+    if (!bus.registerObject(QStringLiteral("/ru/example/app"), greeter)) {
+        qWarning() << "Cannot register object:" << bus.lastError().message();
+        return false;
+    }
+    if (!bus.registerService(QStringLiteral("ru.example.app"))) {
+        qWarning() << "Cannot register service:" << bus.lastError().message();
+        return false;
+    }
+    return true;
+}
+
+// /usr/share/dbus-1/services/ru.example.app.service, for D-Bus activation:
+//
+//     [D-BUS Service]
+//     Name=ru.example.app
+//     Exec=/usr/bin/invoker --type=silica-qt5 --single-instance /usr/bin/ru.example.app
@@ -0,0 +1,36 @@
+import QtQuick 2.0
+import Nemo.DBus 2.0
+
+Item {
+    property string displayState: "unknown"
+
+    DBusInterface {
+        id: mce
+
+        bus: DBus.SystemBus
+        service: "com.nokia.mce"
+        path: "/com/nokia/mce/signal"
+        iface: "com.nokia.mce.signal"
+        signalsEnabled: true
+
+        // Signal handlers are named after the D-Bus signal.
+        function display_status_ind(state) {
+            displayState = state
+        }
+    }
+
+    DBusInterface {
+        id: mceRequest
+
+        bus: DBus.SystemBus
+        service: "com.nokia.mce"
+        path: "/com/nokia/mce/request"
+        iface: "com.nokia.mce.request"
+    }
+
+    Component.onCompleted: {
+        mceRequest.typedCall("get_display_status", [],
+                             function(state) { displayState = state },
+                             function(error, message) { console.warn("mce:", error, message) })
+    }
+}
@@ -0,0 +1,33 @@
+#include <QDir>
+#include <QFile>
+#include <QStandardPaths>
+
+// main.cpp: must match OrganizationName and ApplicationName in the
+// [X-Application] section of the desktop file.
+//     QCoreApplication::setOrganizationName(QStringLiteral("ru.example"));
+//     QCoreApplication::setApplicationName(QStringLiteral("app"));
+
+// ~/.local/share/ru.example/app/notes.txt, writable without any permission.
+bool saveNotes(const QByteArray &text)
+{
+    const QString dir = QStandardPaths::writableLocation(QStandardPaths::AppDataLocation);
+    if (!QDir().mkpath(dir))
+        return false;
+    QFile file(dir + QStringLiteral("/notes.txt"));
+    if (!file.open(QIODevice::WriteOnly | QIODevice::Truncate))
+        return false;
+    return file.write(text) == text.size();
+}
+
+// ~/.cache/ru.example/app, for data that can be rebuilt.
+QString cacheDir()
+{
+    return QStandardPaths::writableLocation(QStandardPaths::CacheLocation);
+}
+
+// ~/Documents needs Permissions=Documents in the desktop file; without it
+// the directory is not visible in the sandbox and opening files fails.
+QString documentsDir()
+{
+    return QStandardPaths::writableLocation(QStandardPaths::DocumentsLocation);
+}
@@ -0,0 +1,32 @@
+import QtQuick 2.0
+import Sailfish.Silica 1.0
+import Sailfish.Pickers 1.0
+
+Page {
+    property string selectedFile
+
+    Column {
+        width: parent.width
+
+        PageHeader {
+            title: qsTr("Import")
+        }
+
+        ValueButton {
+            label: qsTr("Document")
+            value: selectedFile ? selectedFile : qsTr("None")
+            onClicked: pageStack.push(documentPicker)
+        }
+    }
+
+    Component {
+        id: documentPicker
+
+        DocumentPickerPage {
+            title: qsTr("Select a document")
+            onSelectedContentPropertiesChanged: {
+                selectedFile = selectedContentProperties.filePath
+            }
+        }
+    }
+}
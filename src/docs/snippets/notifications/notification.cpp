@@ -0,0 +1,19 @@
+// PKGCONFIG += nemonotifications-qt5
+// BuildRequires: pkgconfig(nemonotifications-qt5)
+#include <nemonotifications-qt5/notification.h>
+
+void notifyDownloadFinished(const QString &fileName)
+{
+    Notification notification;
+    notification.setAppName(QObject::tr("Example"));
+    notification.setSummary(QObject::tr("Download finished"));
+    notification.setBody(QObject::tr("%1 is ready").arg(fileName));
+    notification.setPreviewSummary(notification.summary());
+    notification.setPreviewBody(notification.body());
+    notification.setRemoteAction(Notification::remoteAction(
+        QStringLiteral("default"), QObject::tr("Open"),
+        QStringLiteral("ru.example.app"), QStringLiteral("/ru/example/app"),
+        QStringLiteral("ru.example.app.Ui"), QStringLiteral("openFile"),
+        { fileName }));
+    notification.publish();
+}
@@ -0,0 +1,31 @@
+import QtQuick 2.0
+import Nemo.Notifications 1.0
+
+Notification {
+    id: notification
+
+    appName: qsTr("Example")
+    summary: qsTr("Download finished")
+    body: qsTr("report.pdf is ready")
+    previewSummary: summary
+    previewBody: body
+    expireTimeout: 5000
+    isTransient: false
+
+    // Opens the app through its D-Bus service when the notification is tapped.
+    remoteActions: [ {
+        "name": "default",
+        "displayName": qsTr("Open"),
+        "service": "ru.example.app",
+        "path": "/ru/example/app",
+        "iface": "ru.example.app.Ui",
+        "method": "openFile",
+        "arguments": [ "report.pdf" ]
+    } ]
+
+    onClicked: console.log("notification clicked")
+    onClosed: console.log("notification closed, reason", reason)
+}
+
+// Show it with notification.publish(); publishing again with the same
+// replacesId updates it instead of adding another one.
@@ -0,0 +1,36 @@
+import QtQuick 2.0
+import Sailfish.Silica 1.0
+
+CoverBackground {
+    Column {
+        anchors.centerIn: parent
+        width: parent.width - 2 * Theme.paddingLarge
+        spacing: Theme.paddingSmall
+
+        Label {
+            width: parent.width
+            horizontalAlignment: Text.AlignHCenter
+            text: counter.value
+            font.pixelSize: Theme.fontSizeHuge
+        }
+
+        Label {
+            width: parent.width
+            horizontalAlignment: Text.AlignHCenter
+            text: qsTr("Count")
+            color: Theme.secondaryColor
+            truncationMode: TruncationMode.Fade
+        }
+    }
+
+    CoverActionList {
+        CoverAction {
+            iconSource: "image://theme/icon-cover-new"
+            onTriggered: counter.increment()
+        }
+        CoverAction {
+            iconSource: "image://theme/icon-cover-refresh"
+            onTriggered: counter.reset()
+        }
+    }
+}
@@ -0,0 +1,38 @@
+import QtQuick 2.0
+import Sailfish.Silica 1.0
+
+Dialog {
+    id: dialog
+
+    property string name
+
+    canAccept: nameField.text.trim().length > 0
+    onAccepted: name = nameField.text.trim()
+
+    Column {
+        width: parent.width
+
+        DialogHeader {
+            acceptText: qsTr("Save")
+            cancelText: qsTr("Cancel")
+        }
+
+        TextField {
+            id: nameField
+
+            width: parent.width
+            label: qsTr("Name")
+            placeholderText: qsTr("Enter a name")
+            text: dialog.name
+            focus: true
+            EnterKey.enabled: dialog.canAccept
+            EnterKey.iconSource: "image://theme/icon-m-enter-accept"
+            EnterKey.onClicked: dialog.accept()
+        }
+    }
+}
+
+// Opening it and reading the result:
+//
+//     var dialog = pageStack.push(Qt.resolvedUrl("NameDialog.qml"), { name: current })
+//     dialog.accepted.connect(function() { current = dialog.name })
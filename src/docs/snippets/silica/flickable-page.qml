@@ -0,0 +1,46 @@
+import QtQuick 2.0
+import Sailfish.Silica 1.0
+
+Page {
+    id: page
+
+    allowedOrientations: Orientation.All
+
+    SilicaFlickable {
+        anchors.fill: parent
+        contentHeight: column.height
+
+        PullDownMenu {
+            MenuItem {
+                text: qsTr("Settings")
+                onClicked: pageStack.push(Qt.resolvedUrl("SettingsPage.qml"))
+            }
+            MenuItem {
+                text: qsTr("Refresh")
+                onClicked: model.reload()
+            }
+        }
+
+        Column {
+            id: column
+
+            width: page.width
+            spacing: Theme.paddingLarge
+
+            PageHeader {
+                title: qsTr("Overview")
+            }
+
+            Label {
+                x: Theme.horizontalPageMargin
+                width: parent.width - 2 * Theme.horizontalPageMargin
+                text: qsTr("Pull down for more actions")
+                color: Theme.highlightColor
+                font.pixelSize: Theme.fontSizeMedium
+                wrapMode: Text.Wrap
+            }
+        }
+
+        VerticalScrollDecorator { }
+    }
+}
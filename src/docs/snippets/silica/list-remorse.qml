@@ -0,0 +1,55 @@
+import QtQuick 2.0
+import Sailfish.Silica 1.0
+
+Page {
+    ListModel {
+        id: itemsModel
+    }
+
+    SilicaListView {
+        id: listView
+
+        anchors.fill: parent
+        model: itemsModel
+        header: PageHeader {
+            title: qsTr("Items")
+        }
+
+        delegate: ListItem {
+            id: listItem
+
+            contentHeight: Theme.itemSizeMedium
+            menu: ContextMenu {
+                MenuItem {
+                    text: qsTr("Remove")
+                    onClicked: listItem.remorseDelete(function() { itemsModel.remove(index) })
+                }
+            }
+            onClicked: pageStack.push(Qt.resolvedUrl("ItemPage.qml"), { name: model.name })
+
+            Label {
+                x: Theme.horizontalPageMargin
+                width: parent.width - 2 * Theme.horizontalPageMargin
+                anchors.verticalCenter: parent.verticalCenter
+                text: model.name
+                truncationMode: TruncationMode.Fade
+                color: listItem.highlighted ? Theme.highlightColor : Theme.primaryColor
+            }
+        }
+
+        ViewPlaceholder {
+            enabled: listView.count === 0
+            text: qsTr("No items yet")
+            hintText: qsTr("Pull down to add one")
+        }
+
+        PullDownMenu {
+            MenuItem {
+                text: qsTr("Add")
+                onClicked: itemsModel.append({ name: qsTr("Item %1").arg(itemsModel.count + 1) })
+            }
+        }
+
+        VerticalScrollDecorator { }
+    }
+}
@@ -0,0 +1,106 @@
+use rmcp::model::{CallToolResult, Content, IntoContents};
+
+/// Errors surfaced by tools. They are rendered as tool error results so the
+/// assistant sees the message instead of an opaque protocol error.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("unknown device '{0}'; configured devices: {1}")]
+    UnknownDevice(String, String),
+
+    #[error("failed to run ssh for device '{device}': {source}")]
+    Ssh {
+        device: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("command on device '{device}' timed out after {seconds}s")]
+    Timeout { device: String, seconds: u64 },
+
+    #[error("command on device '{device}' exited with status {status}: {stderr}")]
+    RemoteCommand {
+        device: String,
+        status: i32,
+        stderr: String,
+    },
+
+    #[error("unexpected output from device '{device}': {message}")]
+    Parse { device: String, message: String },
+
+    #[error(
+        "{0} changes device or project state; call the tool again with confirm=true to proceed"
+    )]
+    ConfirmationRequired(String),
+
+    #[error("failed to run {program}: {source}")]
+    Spawn {
+        program: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("{program} timed out after {seconds}s")]
+    CommandTimeout { program: String, seconds: u64 },
+
+    #[error("{url} returned HTTP {status}: {message}")]
+    Http {
+        url: String,
+        status: u16,
+        message: String,
+    },
+
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("config error: {0}")]
+    Config(String),
+
+    #[error("secret '{key}' is not set; export {env} or add it to {file}")]
+    MissingSecret {
+        key: String,
+        env: String,
+        file: String,
+    },
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl IntoContents for Error {
+    fn into_contents(self) -> Vec<Content> {
+        vec![Content::text(self.to_string())]
+    }
+}
+
+/// Gate for destructive tools: they take a `confirm` flag and refuse to act
+/// until the caller has seen what will happen and set it.
+pub fn require_confirmation(confirm: bool, action: impl FnOnce() -> String) -> Result<()> {
+    if confirm {
+        Ok(())
+    } else {
+        Err(Error::ConfirmationRequired(action()))
+    }
+}
+
+/// Renders a tool outcome built by hand as a `tools/call` response, turning
+/// [`Error`] into a tool error result like the derived handlers do.
+pub fn tool_result(
+    result: Result<CallToolResult>,
+) -> std::result::Result<CallToolResult, rmcp::ErrorData> {
+    Ok(result.unwrap_or_else(|err| CallToolResult::error(err.into_contents())))
+}
+
+/// Protocol errors for non-tool requests such as `resources/read`, where
+/// there is no error result to carry the message.
+impl From<Error> for rmcp::ErrorData {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::UnknownDevice(..) | Error::InvalidArgument(_) => {
+                Self::invalid_params(err.to_string(), None)
+            }
+            _ => Self::internal_error(err.to_string(), None),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! The server's event bus: what happens across sessions (tool calls,
+//! sessions opening and closing, devices losing and regaining their
+//! connection) is published here once, and whatever reacts to it
+//! (webhooks, logging notifications to clients) subscribes instead of
+//! hooking into tool dispatch or the SSH layer itself.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use rmcp::model::LoggingLevel;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Events a slow subscriber may fall behind by before it misses some.
+const CAPACITY: usize = 256;
+
+/// Something that happened in the server.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    ToolStarted {
+        session: u64,
+        tool: String,
+    },
+    ToolFinished {
+        session: u64,
+        tool: String,
+        #[serde(rename = "duration_secs", serialize_with = "serialize_secs")]
+        duration: Duration,
+        /// False for a tool error result as well as a protocol error.
+        success: bool,
+    },
+    SessionOpened {
+        session: u64,
+        /// The client's name from its `initialize` request.
+        client: Option<String>,
+    },
+    SessionClosed {
+        session: u64,
+    },
+    /// A device's SSH connection started failing after it had worked, or
+    /// worked again after failing.
+    DeviceConnection {
+        device: String,
+        connected: bool,
+        error: Option<String>,
+    },
+}
+
+impl Event {
+    /// The session the event belongs to; `None` for server-wide ones.
+    pub fn session(&self) -> Option<u64> {
+        match self {
+            Event::ToolStarted { session, .. }
+            | Event::ToolFinished { session, .. }
+            | Event::SessionOpened { session, .. }
+            | Event::SessionClosed { session } => Some(*session),
+            Event::DeviceConnection { .. } => None,
+        }
+    }
+
+    /// How loud the event is as a logging notification.
+    pub fn level(&self) -> LoggingLevel {
+        match self {
+            Event::ToolFinished { success: false, .. }
+            | Event::DeviceConnection {
+                connected: false, ..
+            } => LoggingLevel::Warning,
+            Event::DeviceConnection {
+                connected: true, ..
+            } => LoggingLevel::Info,
+            _ => LoggingLevel::Debug,
+        }
+    }
+}
+
+fn serialize_secs<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// A broadcast channel of [`Event`]s. Clones publish to and subscribe on
+/// the same channel; publishing without subscribers is a no-op.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(CAPACITY).0,
+        }
+    }
+}
+
+impl EventBus {
+    pub fn publish(&self, event: Event) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Runs `handle` on every event published from now on, one at a time,
+    /// in a background task that ends with the bus. Returns the task's
+    /// handle so the subscriber can stop it earlier.
+    pub fn subscribe<F, Fut>(&self, mut handle: F) -> tokio::task::AbortHandle
+    where
+        F: FnMut(Event) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let mut receiver = self.sender.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => handle(event).await,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::debug!("event subscriber missed {missed} events");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+        .abort_handle()
+    }
+}
+
+/// A new session id, unique within the process.
+pub fn next_session_id() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
@@ -0,0 +1,1017 @@
+//! Canned results behind `--mock-all`: the tools whose effects reach past
+//! the host and the devices (building in the Build Engine or with the
+//! Rust and Flutter cross toolchains, running tests, clang-tidy, the RPM
+//! validator and gdb, the remote build service, the store's developer
+//! portal, installing builds on devices) answer with plausible results
+//! after a realistic delay instead of running,
+//! so the server can be demoed and clients developed without an SDK,
+//! accounts or hardware. Device tools run against the simulated devices of
+//! `--mock-devices`, which `--mock-all` implies; every other tool works on
+//! the host as usual.
+//!
+//! Results are built from the tools' own report types, so they match the
+//! tools' output schemas. Arguments are read leniently: missing ones fall
+//! back to the config, then to a demo project, but `confirm` gates still
+//! apply.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rmcp::ErrorData;
+use rmcp::model::{CallToolRequestParam, CallToolResult, Content, JsonObject};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::device::deploy::DeployResult;
+use crate::device::multi::{DeviceOutcome, MultiDeviceReport};
+use crate::error::{Error, Result, require_confirmation, tool_result};
+use crate::progress;
+use crate::resources::file_link;
+use crate::sdk::build::BuildReport;
+use crate::sdk::cargo::{self, RustBuildReport, RustPackage};
+use crate::sdk::coredump::{Frame, SymbolizedCore, ThreadBacktrace};
+use crate::sdk::cpp::{CppAnalysis, FileFindings, TidyFinding};
+use crate::sdk::flutter::{FlutterBuildReport, FlutterDeployReport, FlutterMode, TargetPlatform};
+use crate::sdk::lint::Level;
+use crate::sdk::matrix::{self, MatrixBuild, MatrixReport};
+use crate::sdk::obs::{
+    DownloadedRpm, RemoteBuild, RemoteBuildStatus, RemoteDownload, RemoteSubmission, UploadedSource,
+};
+use crate::sdk::publish::{
+    StoreListing, StoreStatus, StoreSubmission, SubmissionStatus, UploadedScreenshot,
+};
+use crate::sdk::rpm::parse_rpm_name;
+use crate::sdk::sandbox::{Action, FileChange};
+use crate::sdk::unittest::{TestCase, TestRun, TestStatus, TestSuite};
+use crate::sdk::validate::{ValidationIssue, ValidationReport};
+use crate::server::AuroraServer;
+
+/// Tools answered from fixtures, with how long each pretends to take.
+const LATENCY_MS: &[(&str, u64)] = &[
+    ("build_project", 4000),
+    ("build_matrix", 7000),
+    ("build_rust", 3500),
+    ("package_rust_binary", 300),
+    ("flutter_build", 5000),
+    ("flutter_deploy", 6500),
+    ("run_tests", 2500),
+    ("analyze_cpp", 3000),
+    ("validate_rpm", 1500),
+    ("symbolicate_core", 2000),
+    ("deploy_rpm", 1500),
+    ("remote_build_submit", 2500),
+    ("remote_build_status", 600),
+    ("remote_build_download", 1800),
+    ("store_upload", 3000),
+    ("store_set_listing", 1200),
+    ("store_submission_status", 500),
+];
+
+const DEMO_PACKAGE: &str = "ru.auroraos.demo";
+const DEMO_VERSION: &str = "1.0.0";
+const DEFAULT_TARGET: &str = "AuroraOS-5.1.3.85-MB2-armv7hl";
+const MATRIX_TARGETS: &[&str] = &[
+    "AuroraOS-5.1.3.85-MB2-armv7hl",
+    "AuroraOS-5.1.3.85-MB2-aarch64",
+    "AuroraOS-5.1.3.85-MB2-x86_64",
+];
+const BUILD_SERVICE_PROJECT: &str = "home:developer:aurora";
+
+/// The canned result of `request` when fixtures are on and cover the tool.
+pub async fn call(
+    server: &AuroraServer,
+    request: &CallToolRequestParam,
+) -> Option<std::result::Result<CallToolResult, ErrorData>> {
+    if !server.state().fixtures {
+        return None;
+    }
+    let (tool, latency) = LATENCY_MS.iter().find(|(tool, _)| *tool == request.name)?;
+    let empty = JsonObject::new();
+    let args = Args(request.arguments.as_ref().unwrap_or(&empty));
+    let confirmed = match *tool {
+        "remote_build_submit" => require_confirmation(args.flag("confirm"), || {
+            format!(
+                "uploading {} {DEMO_VERSION} to {} on the build service, which starts builds there",
+                package(server, args),
+                build_service_project(server)
+            )
+        }),
+        "store_upload" => require_confirmation(args.flag("confirm"), || {
+            let (package, version, arch) = uploaded_rpm(server, args);
+            format!("submitting {package} {version} ({arch}) to the store")
+        }),
+        _ => Ok(()),
+    };
+    if let Err(err) = confirmed {
+        return Some(tool_result(Err(err)));
+    }
+    let latency = Duration::from_millis(*latency);
+    match streamed_log(server, args, tool, latency) {
+        // Like a real build, stream the log while it "runs".
+        Some(log) => {
+            let pause = latency / log.len() as u32;
+            for line in log {
+                tokio::time::sleep(pause).await;
+                progress::emit(line);
+            }
+        }
+        None => tokio::time::sleep(latency).await,
+    }
+    let result = match *tool {
+        "build_project" => build(server, args, latency),
+        "build_matrix" => build_matrix(server, args, latency),
+        "build_rust" => build_rust(server, args, latency),
+        "package_rust_binary" => package_rust_binary(server, args),
+        "flutter_build" => flutter_build(server, args, latency),
+        "flutter_deploy" => flutter_deploy(server, args, latency),
+        "run_tests" => run_tests(server, args, latency),
+        "analyze_cpp" => analyze_cpp(server, args),
+        "validate_rpm" => validate_rpm(server, args),
+        "symbolicate_core" => symbolicate_core(server, args),
+        "deploy_rpm" => deploy(args, latency),
+        "remote_build_submit" => remote_submit(server, args),
+        "remote_build_status" => remote_status(server, args),
+        "remote_build_download" => remote_download(server, args),
+        "store_upload" => store_upload(server, args),
+        "store_set_listing" => store_listing(args),
+        "store_submission_status" => store_status(server, args),
+        _ => unreachable!("every tool in LATENCY_MS has a fixture"),
+    };
+    Some(tool_result(result))
+}
+
+#[derive(Clone, Copy)]
+struct Args<'a>(&'a JsonObject);
+
+impl Args<'_> {
+    fn str(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(Value::as_str)
+    }
+
+    fn flag(&self, key: &str) -> bool {
+        self.0.get(key).and_then(Value::as_bool).unwrap_or(false)
+    }
+
+    fn strings(&self, key: &str) -> Vec<String> {
+        self.0
+            .get(key)
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn structured(value: impl Serialize) -> Result<CallToolResult> {
+    Ok(CallToolResult::structured(
+        serde_json::to_value(value).map_err(std::io::Error::other)?,
+    ))
+}
+
+/// The project directory, as the real tools resolve it, else as given.
+fn project(server: &AuroraServer, args: Args) -> PathBuf {
+    let sdk = &server.state().config.sdk;
+    sdk.project(args.str("project"))
+        .unwrap_or_else(|_| PathBuf::from(args.str("project").unwrap_or(DEMO_PACKAGE)))
+}
+
+/// The package: the `package` argument, else the project directory's name.
+fn package(server: &AuroraServer, args: Args) -> String {
+    if let Some(package) = args.str("package") {
+        return package.to_string();
+    }
+    project(server, args)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .filter(|name| name.contains('.'))
+        .unwrap_or_else(|| DEMO_PACKAGE.to_string())
+}
+
+fn build_service_project(server: &AuroraServer) -> String {
+    server
+        .state()
+        .config
+        .build_service
+        .project
+        .clone()
+        .unwrap_or_else(|| BUILD_SERVICE_PROJECT.to_string())
+}
+
+/// The build target: the argument, else the configured one.
+fn target(server: &AuroraServer, args: Args) -> String {
+    args.str("target")
+        .map(str::to_string)
+        .or_else(|| server.state().config.sdk.target.clone())
+        .unwrap_or_else(|| DEFAULT_TARGET.to_string())
+}
+
+/// `armv7hl` of `AuroraOS-5.1.3.85-MB2-armv7hl`.
+fn arch(target: &str) -> &str {
+    target.rsplit('-').next().unwrap_or(target)
+}
+
+fn rpm_name(package: &str, arch: &str) -> String {
+    format!("{package}-{DEMO_VERSION}-1.{arch}.rpm")
+}
+
+/// The lines of a successful `sfdk build` of `package` for `target`.
+fn build_log(package: &str, target: &str) -> Vec<String> {
+    let arch = arch(target);
+    [
+        format!("Building target platforms: {arch}-meego-linux"),
+        format!("Building for target {target}"),
+        "Executing(%build): /bin/sh -e /var/tmp/rpm-tmp.build".to_string(),
+        "qmake -makefile -spec linux-g++ CONFIG+=release".to_string(),
+        "g++ -c -pipe -O2 -g -fPIC -std=gnu++1y -o main.o ../src/main.cpp".to_string(),
+        format!("g++ -Wl,-O1 -o {package} main.o -lsailfishapp -lQt5Quick -lQt5Qml -lQt5Core"),
+        "Executing(%install): /bin/sh -e /var/tmp/rpm-tmp.install".to_string(),
+        "Processing files: files".to_string(),
+        format!("Wrote: RPMS/{}", rpm_name(package, arch)),
+        "Executing(%clean): /bin/sh -e /var/tmp/rpm-tmp.clean".to_string(),
+    ]
+    .into()
+}
+
+/// The log a build streams while it "runs"; `None` for other tools.
+fn streamed_log(
+    server: &AuroraServer,
+    args: Args,
+    tool: &str,
+    latency: Duration,
+) -> Option<Vec<String>> {
+    match tool {
+        "build_project" => Some(build_log(&package(server, args), &target(server, args))),
+        "build_rust" => Some(rust_log(server, args, latency)),
+        "flutter_build" | "flutter_deploy" => Some(flutter_log(server, args)),
+        _ => None,
+    }
+}
+
+/// Writes `lines` to `file` so that links to the log resolve.
+fn write_log(file: &Path, lines: &[String]) -> Result<()> {
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(file, lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+fn build(server: &AuroraServer, args: Args, latency: Duration) -> Result<CallToolResult> {
+    let project = project(server, args);
+    let package = package(server, args);
+    let target = target(server, args);
+    let log = build_log(&package, &target);
+    let log_file = crate::sdk::log_file(server, &project, "build");
+    write_log(&log_file, &log)?;
+    let report = BuildReport {
+        rpms: vec![project.join("RPMS").join(rpm_name(&package, arch(&target)))],
+        project,
+        target: Some(target),
+        success: true,
+        exit_status: 0,
+        duration_secs: latency.as_secs(),
+        errors: Vec::new(),
+        log_tail: log,
+        log_file,
+    };
+    let mut result = structured(&report)?;
+    result
+        .content
+        .push(file_link(&report.log_file, Some("text/plain")));
+    Ok(result)
+}
+
+fn build_matrix(server: &AuroraServer, args: Args, latency: Duration) -> Result<CallToolResult> {
+    let project = project(server, args);
+    let package = package(server, args);
+    let mut targets = args.strings("targets");
+    if targets.is_empty() {
+        targets = server.state().config.sdk.matrix_targets.clone();
+    }
+    if targets.is_empty() {
+        targets = MATRIX_TARGETS.iter().map(|t| t.to_string()).collect();
+    }
+    let data_dir = server.state().config.data_dir();
+    let mut builds = Vec::new();
+    for target in targets {
+        let arch = arch(&target).to_string();
+        let build_dir = data_dir.join("matrix").join(&package).join(&target);
+        let log_file = crate::sdk::log_file(server, &project, &format!("matrix-{arch}"));
+        write_log(&log_file, &build_log(&package, &target))?;
+        builds.push(MatrixBuild {
+            rpms: vec![build_dir.join("RPMS").join(rpm_name(&package, &arch))],
+            target,
+            arch,
+            success: true,
+            exit_status: 0,
+            duration_secs: latency.as_secs() / 2,
+            errors: Vec::new(),
+            build_dir,
+            log_file,
+        });
+    }
+    let report = MatrixReport {
+        project,
+        success: true,
+        succeeded: builds.len(),
+        failed: 0,
+        duration_secs: latency.as_secs(),
+        table: matrix::table(&builds),
+        builds,
+    };
+    let mut result = structured(&report)?;
+    result.content.push(Content::text(report.table.clone()));
+    result.content.extend(
+        report
+            .builds
+            .iter()
+            .map(|build| file_link(&build.log_file, Some("text/plain"))),
+    );
+    Ok(result)
+}
+
+/// The crate directory: the `crate_dir` argument in the project, else the
+/// project.
+fn crate_dir(server: &AuroraServer, args: Args) -> PathBuf {
+    let project = project(server, args);
+    match args.str("crate_dir") {
+        Some(dir) => project.join(dir),
+        None => project,
+    }
+}
+
+/// The Rust binary: the `bin` or `binary` argument, else the last part of
+/// the package name.
+fn rust_binary(server: &AuroraServer, args: Args) -> String {
+    args.str("bin")
+        .or_else(|| args.str("binary"))
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            let package = package(server, args);
+            package.rsplit('.').next().unwrap_or(&package).to_string()
+        })
+}
+
+fn rust_profile(args: Args) -> &'static str {
+    if args.flag("debug") {
+        "debug"
+    } else {
+        "release"
+    }
+}
+
+/// The lines of a successful `cargo build` of the crate.
+fn rust_log(server: &AuroraServer, args: Args, latency: Duration) -> Vec<String> {
+    let binary = rust_binary(server, args);
+    let profile = if args.flag("debug") {
+        "`dev` profile [unoptimized + debuginfo]"
+    } else {
+        "`release` profile [optimized]"
+    };
+    vec![
+        "   Compiling libc v0.2.155".to_string(),
+        "   Compiling cfg-if v1.0.0".to_string(),
+        "   Compiling log v0.4.22".to_string(),
+        format!(
+            "   Compiling {binary} v{DEMO_VERSION} ({})",
+            crate_dir(server, args).display()
+        ),
+        format!(
+            "    Finished {profile} target(s) in {:.2}s",
+            latency.as_secs_f64()
+        ),
+    ]
+}
+
+fn build_rust(server: &AuroraServer, args: Args, latency: Duration) -> Result<CallToolResult> {
+    let crate_dir = crate_dir(server, args);
+    let target = target(server, args);
+    let (_, triple, _) = cargo::architecture(&target)?;
+    let log = rust_log(server, args, latency);
+    let log_file = crate::sdk::log_file(server, &crate_dir, "build");
+    write_log(&log_file, &log)?;
+    let binary = crate_dir
+        .join("target")
+        .join(triple)
+        .join(rust_profile(args))
+        .join(rust_binary(server, args));
+    let report = RustBuildReport {
+        crate_dir,
+        target,
+        triple: triple.to_string(),
+        success: true,
+        exit_status: 0,
+        duration_secs: latency.as_secs(),
+        binaries: vec![binary],
+        errors: Vec::new(),
+        warnings: 0,
+        log_file,
+        log_tail: log,
+    };
+    let mut result = structured(&report)?;
+    result
+        .content
+        .push(file_link(&report.log_file, Some("text/plain")));
+    Ok(result)
+}
+
+fn package_rust_binary(server: &AuroraServer, args: Args) -> Result<CallToolResult> {
+    let package = package(server, args);
+    let target = target(server, args);
+    let (arch, triple, _) = cargo::architecture(&target)?;
+    let name = rust_binary(server, args);
+    let binary = crate_dir(server, args)
+        .join("target")
+        .join(triple)
+        .join(rust_profile(args))
+        .join(&name);
+    let install_dir = args
+        .str("install_dir")
+        .unwrap_or(cargo::DEFAULT_INSTALL_DIR)
+        .trim_end_matches('/');
+    let installed_as = format!("{install_dir}/{name}");
+    let bin_dir = cargo::BIN_DIR;
+    let install =
+        format!("install -D -m 755 {bin_dir}/%{{_target_cpu}}/{name} %{{buildroot}}{installed_as}");
+    structured(RustPackage {
+        changes: vec![
+            FileChange {
+                path: PathBuf::from(bin_dir).join(arch).join(&name),
+                action: Action::Created,
+                content: format!("{} (2841736 bytes)", binary.display()),
+            },
+            FileChange {
+                path: PathBuf::from("rpm").join(format!("{package}.spec")),
+                action: Action::Updated,
+                content: format!("{install}\n{installed_as}"),
+            },
+        ],
+        binary,
+        installed_as,
+        written: args.flag("write"),
+        notes: Vec::new(),
+    })
+}
+
+/// The Flutter build's platform: the argument, else that of the build
+/// target.
+fn flutter_platform(server: &AuroraServer, args: Args) -> TargetPlatform {
+    args.0
+        .get("target_platform")
+        .and_then(|platform| serde_json::from_value(platform.clone()).ok())
+        .or_else(|| TargetPlatform::of_target(&target(server, args)))
+        .unwrap_or(TargetPlatform::Arm)
+}
+
+fn flutter_mode(args: Args) -> FlutterMode {
+    args.0
+        .get("mode")
+        .and_then(|mode| serde_json::from_value(mode.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// The RPM a Flutter build writes, relative to the project.
+fn flutter_rpm(server: &AuroraServer, args: Args) -> PathBuf {
+    let platform = flutter_platform(server, args);
+    let (arch, mode) = (
+        match platform {
+            TargetPlatform::Arm => "armv7hl",
+            TargetPlatform::Arm64 => "aarch64",
+            TargetPlatform::X64 => "x86_64",
+        },
+        match flutter_mode(args) {
+            FlutterMode::Debug => "debug",
+            FlutterMode::Profile => "profile",
+            FlutterMode::Release => "release",
+        },
+    );
+    Path::new("build/aurora")
+        .join(platform.flag())
+        .join(mode)
+        .join("RPMS")
+        .join(rpm_name(&package(server, args), arch))
+}
+
+/// The lines of a successful `flutter-aurora build aurora`.
+fn flutter_log(server: &AuroraServer, args: Args) -> Vec<String> {
+    let package = package(server, args);
+    vec![
+        "Resolving dependencies...".to_string(),
+        "Got dependencies!".to_string(),
+        format!(
+            "Building Aurora OS application for {}...",
+            flutter_platform(server, args).flag()
+        ),
+        "Compiling lib/main.dart for the Aurora OS...".to_string(),
+        "-- Configuring done".to_string(),
+        "-- Generating done".to_string(),
+        format!("[100%] Built target {package}"),
+        format!("✓ Built {}", flutter_rpm(server, args).display()),
+    ]
+}
+
+fn flutter_report(
+    server: &AuroraServer,
+    args: Args,
+    latency: Duration,
+) -> Result<FlutterBuildReport> {
+    let project = project(server, args);
+    let log = flutter_log(server, args);
+    let log_file = crate::sdk::log_file(server, &project, "build");
+    write_log(&log_file, &log)?;
+    Ok(FlutterBuildReport {
+        rpms: vec![project.join(flutter_rpm(server, args))],
+        project,
+        target_platform: flutter_platform(server, args),
+        mode: flutter_mode(args),
+        success: true,
+        exit_status: 0,
+        duration_secs: latency.as_secs(),
+        errors: Vec::new(),
+        warnings: 0,
+        log_file,
+        log_tail: log,
+    })
+}
+
+fn flutter_build(server: &AuroraServer, args: Args, latency: Duration) -> Result<CallToolResult> {
+    let report = flutter_report(server, args, latency)?;
+    let mut result = structured(&report)?;
+    result
+        .content
+        .push(file_link(&report.log_file, Some("text/plain")));
+    Ok(result)
+}
+
+fn flutter_deploy(server: &AuroraServer, args: Args, latency: Duration) -> Result<CallToolResult> {
+    // The build takes most of the time.
+    let build = flutter_report(server, args, latency * 3 / 4)?;
+    let rpm = build.rpms.first().cloned();
+    let install = match &rpm {
+        Some(rpm) => Some(install(args, rpm, latency / 4)?),
+        None => None,
+    };
+    structured(FlutterDeployReport {
+        build,
+        rpm,
+        install,
+    })
+}
+
+fn test_case(name: &str, status: TestStatus, message: Option<&str>) -> TestCase {
+    TestCase {
+        name: format!("DemoTest::{name}"),
+        status,
+        message: message.map(str::to_string),
+        file: (status != TestStatus::Pass).then(|| "tests/tst_demo.cpp".to_string()),
+        line: (status != TestStatus::Pass).then_some(64),
+    }
+}
+
+fn run_tests(server: &AuroraServer, args: Args, latency: Duration) -> Result<CallToolResult> {
+    let project = project(server, args);
+    let cases = vec![
+        test_case("initTestCase()", TestStatus::Pass, None),
+        test_case("testAdd()", TestStatus::Pass, None),
+        test_case("testRemove()", TestStatus::Pass, None),
+        test_case(
+            "testSync()",
+            TestStatus::Skip,
+            Some("no network in the test environment"),
+        ),
+        test_case("cleanupTestCase()", TestStatus::Pass, None),
+    ];
+    let log = cases
+        .iter()
+        .map(|case| {
+            let status = match case.status {
+                TestStatus::Skip => "SKIP   ",
+                _ => "PASS   ",
+            };
+            format!("{status}: {}", case.name)
+        })
+        .chain(["Totals: 4 passed, 0 failed, 1 skipped, 0 blacklisted, 12ms".to_string()])
+        .collect::<Vec<_>>();
+    let log_file = crate::sdk::log_file(server, &project, "tests");
+    write_log(&log_file, &log)?;
+    let report = TestRun {
+        runner: if args.str("device").is_some() {
+            "device"
+        } else {
+            "sysroot"
+        }
+        .to_string(),
+        success: true,
+        passed: 4,
+        failed: 0,
+        skipped: 1,
+        duration_secs: latency.as_secs(),
+        suites: vec![TestSuite {
+            name: "tst_demo".to_string(),
+            exit_status: Some(0),
+            passed: 4,
+            failed: 0,
+            skipped: 1,
+            cases,
+        }],
+        log_file,
+    };
+    let mut result = structured(&report)?;
+    result
+        .content
+        .push(file_link(&report.log_file, Some("text/plain")));
+    Ok(result)
+}
+
+fn analyze_cpp(server: &AuroraServer, args: Args) -> Result<CallToolResult> {
+    let project = project(server, args);
+    let checks = args
+        .str("checks")
+        .map(str::to_string)
+        .unwrap_or_else(|| server.state().config.sdk.clang_tidy_checks.clone());
+    let mut sources = args.strings("files");
+    if sources.is_empty() {
+        sources = vec!["src/main.cpp".to_string(), "src/model.cpp".to_string()];
+    }
+    let file = project.join(&sources[sources.len() - 1]);
+    let findings = vec![
+        TidyFinding {
+            line: 42,
+            column: 19,
+            level: Level::Warning,
+            message: "'items' used after it was moved".to_string(),
+            check: Some("bugprone-use-after-move".to_string()),
+        },
+        TidyFinding {
+            line: 57,
+            column: 36,
+            level: Level::Warning,
+            message: "the parameter 'name' is copied for each invocation but only used as a const reference; consider making it a const reference".to_string(),
+            check: Some("performance-unnecessary-value-param".to_string()),
+        },
+    ];
+    let log_file = crate::sdk::log_file(server, &project, "clang-tidy");
+    write_log(
+        &log_file,
+        &findings
+            .iter()
+            .map(|finding| {
+                format!(
+                    "{}:{}:{}: warning: {} [{}]",
+                    file.display(),
+                    finding.line,
+                    finding.column,
+                    finding.message,
+                    finding.check.as_deref().unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>(),
+    )?;
+    structured(CppAnalysis {
+        command: format!(
+            "sfdk build-shell clang-tidy -p {} --checks=-*,{checks} --quiet {}",
+            project.display(),
+            sources.join(" ")
+        ),
+        compile_commands: project.join("compile_commands.json"),
+        files_analyzed: sources.len(),
+        success: true,
+        exit_status: 0,
+        total: findings.len(),
+        files: vec![FileFindings { file, findings }],
+        log_file,
+    })
+}
+
+fn validate_rpm(server: &AuroraServer, args: Args) -> Result<CallToolResult> {
+    let project = project(server, args);
+    let package = package(server, args);
+    let target = target(server, args);
+    let rpm = match args.str("rpm") {
+        Some(rpm) => project.join(rpm),
+        None => project.join("RPMS").join(rpm_name(&package, arch(&target))),
+    };
+    let issue = ValidationIssue {
+        level: Level::Warning,
+        category: Some("Icons".to_string()),
+        subject: Some(format!(
+            "/usr/share/icons/hicolor/172x172/apps/{package}.png"
+        )),
+        message: "icon is missing; the launcher scales a smaller one".to_string(),
+    };
+    let log_file = crate::sdk::log_file(server, &project, "validate");
+    write_log(
+        &log_file,
+        &[
+            format!("Validating {}", rpm.display()),
+            format!(
+                "WARNING [{}] {}",
+                issue.subject.as_deref().unwrap_or_default(),
+                issue.message
+            ),
+            "Validation passed with 1 warning".to_string(),
+        ],
+    )?;
+    let report = ValidationReport {
+        rpm,
+        target: Some(target),
+        passed: true,
+        exit_status: 0,
+        errors: 0,
+        warnings: 1,
+        issues: vec![issue],
+        log_file,
+    };
+    let mut result = structured(&report)?;
+    result
+        .content
+        .push(file_link(&report.log_file, Some("text/plain")));
+    Ok(result)
+}
+
+/// A frame with source information, or in `library` without.
+fn frame(
+    index: usize,
+    function: &str,
+    source: Option<(&str, usize, &str)>,
+    library: Option<&str>,
+) -> Frame {
+    Frame {
+        index,
+        address: Some(format!("0x0000aaaad1e2{:04x}", 0x4a0 + index * 0x38)),
+        function: function.to_string(),
+        file: source.map(|(file, _, _)| file.to_string()),
+        line: source.map(|(_, line, _)| line),
+        source: source.map(|(_, _, text)| text.to_string()),
+        library: library.map(str::to_string),
+    }
+}
+
+/// The main thread, crashed in the app's model.
+fn crashed_thread() -> ThreadBacktrace {
+    ThreadBacktrace {
+        thread: 1,
+        frames: vec![
+            frame(
+                0,
+                "DemoModel::remove(int)",
+                Some(("src/model.cpp", 57, "    m_items.removeAt(index);")),
+                None,
+            ),
+            frame(
+                1,
+                "QQmlObjectOrGadget::metacall",
+                None,
+                Some("libQt5Qml.so.5"),
+            ),
+            frame(
+                2,
+                "main(int, char**)",
+                Some(("src/main.cpp", 18, "    return application->exec();")),
+                None,
+            ),
+        ],
+    }
+}
+
+fn symbolicate_core(server: &AuroraServer, args: Args) -> Result<CallToolResult> {
+    let project = project(server, args);
+    let package = package(server, args);
+    let arch = arch(&target(server, args)).to_string();
+    let worker = ThreadBacktrace {
+        thread: 2,
+        frames: vec![
+            frame(0, "__GI___poll", None, Some("libc.so.6")),
+            frame(
+                1,
+                "QEventDispatcherUNIX::processEvents",
+                None,
+                Some("libQt5Core.so.5"),
+            ),
+        ],
+    };
+    let log_file = crate::sdk::log_file(server, &project, "gdb");
+    write_log(
+        &log_file,
+        &[
+            "Program terminated with signal SIGSEGV, Segmentation fault.".to_string(),
+            "#0  DemoModel::remove (this=0xaaaad2f01c20, index=3) at src/model.cpp:57".to_string(),
+            "57\t    m_items.removeAt(index);".to_string(),
+        ],
+    )?;
+    let rpms = project.join("RPMS");
+    let report = SymbolizedCore {
+        core: PathBuf::from(args.str("core").unwrap_or("core.demo.zst")),
+        binary: args
+            .str("binary")
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("/usr/bin/{package}")),
+        signal: Some("SIGSEGV, Segmentation fault".to_string()),
+        crashed: Some(crashed_thread()),
+        threads: vec![crashed_thread(), worker],
+        debug_packages: vec![
+            rpms.join(format!("{package}-debuginfo-{DEMO_VERSION}-1.{arch}.rpm")),
+            rpms.join(format!("{package}-debugsource-{DEMO_VERSION}-1.{arch}.rpm")),
+        ],
+        unresolved_frames: 0,
+        warnings: Vec::new(),
+        log_file,
+    };
+    let mut result = structured(&report)?;
+    result
+        .content
+        .push(file_link(&report.log_file, Some("text/plain")));
+    Ok(result)
+}
+
+fn deploy(args: Args, latency: Duration) -> Result<CallToolResult> {
+    let rpm = args
+        .str("rpm")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(rpm_name(DEMO_PACKAGE, "armv7hl")));
+    structured(install(args, &rpm, latency)?)
+}
+
+/// Installing `rpm` on each device of the `devices` argument.
+fn install(args: Args, rpm: &Path, latency: Duration) -> Result<MultiDeviceReport<DeployResult>> {
+    let devices = args.strings("devices");
+    if devices.is_empty() {
+        return Err(Error::InvalidArgument("no devices given".to_string()));
+    }
+    let name = rpm
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let size = std::fs::metadata(rpm)
+        .map(|meta| meta.len())
+        .unwrap_or(1_482_311);
+    let results = devices
+        .into_iter()
+        .map(|device| DeviceOutcome {
+            device,
+            ok: true,
+            result: Some(DeployResult {
+                bytes_uploaded: size,
+                output_tail: vec![
+                    "Resolving".to_string(),
+                    "Installing".to_string(),
+                    format!("Installing /tmp/aurora-mcp-{name}"),
+                    "Finished".to_string(),
+                ],
+            }),
+            error: None,
+            duration_ms: latency.as_millis() as u64,
+        })
+        .collect::<Vec<_>>();
+    Ok(MultiDeviceReport {
+        succeeded: results.len(),
+        failed: 0,
+        results,
+    })
+}
+
+fn remote_submit(server: &AuroraServer, args: Args) -> Result<CallToolResult> {
+    let package = package(server, args);
+    structured(RemoteSubmission {
+        build_project: build_service_project(server),
+        version: DEMO_VERSION.to_string(),
+        created: false,
+        revision: Some("7".to_string()),
+        uploaded: vec![
+            UploadedSource {
+                name: format!("{package}.spec"),
+                size: 1_204,
+            },
+            UploadedSource {
+                name: format!("{package}-{DEMO_VERSION}.tar.bz2"),
+                size: 48_310,
+            },
+        ],
+        removed: Vec::new(),
+        package,
+    })
+}
+
+fn remote_status(server: &AuroraServer, args: Args) -> Result<CallToolResult> {
+    let builds = ["armv7hl", "aarch64", "x86_64"]
+        .into_iter()
+        .map(|arch| RemoteBuild {
+            repository: "AuroraOS_5.1".to_string(),
+            arch: arch.to_string(),
+            code: "succeeded".to_string(),
+            details: None,
+            outdated: false,
+            log_file: None,
+            errors: Vec::new(),
+            log_tail: Vec::new(),
+        })
+        .collect();
+    structured(RemoteBuildStatus {
+        build_project: build_service_project(server),
+        package: package(server, args),
+        builds,
+        finished: true,
+        succeeded: true,
+        waited_secs: 0,
+    })
+}
+
+fn remote_download(server: &AuroraServer, args: Args) -> Result<CallToolResult> {
+    let package = package(server, args);
+    let output_dir = args
+        .str("output_dir")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| project(server, args).join("RPMS").join("remote"));
+    let files = ["armv7hl", "aarch64", "x86_64"]
+        .into_iter()
+        .filter(|arch| args.str("arch").is_none_or(|wanted| wanted == *arch))
+        .map(|arch| DownloadedRpm {
+            repository: "AuroraOS_5.1".to_string(),
+            arch: arch.to_string(),
+            path: output_dir
+                .join("AuroraOS_5.1")
+                .join(rpm_name(&package, arch)),
+            size: 1_482_311,
+        })
+        .collect();
+    structured(RemoteDownload {
+        build_project: build_service_project(server),
+        package,
+        files,
+        skipped: Vec::new(),
+    })
+}
+
+/// Package, `version-release` and arch of the RPM `store_upload` is given.
+fn uploaded_rpm(server: &AuroraServer, args: Args) -> (String, String, String) {
+    args.str("rpm").and_then(parse_rpm_name).unwrap_or_else(|| {
+        (
+            package(server, args),
+            format!("{DEMO_VERSION}-1"),
+            "armv7hl".to_string(),
+        )
+    })
+}
+
+fn store_upload(server: &AuroraServer, args: Args) -> Result<CallToolResult> {
+    let (package, version, arch) = uploaded_rpm(server, args);
+    let response = json!({ "submission_id": "sub-20417", "status": "pending" });
+    structured(StoreSubmission {
+        package,
+        version,
+        arch,
+        submission_id: Some("sub-20417".to_string()),
+        status: Some("pending".to_string()),
+        response,
+    })
+}
+
+fn store_listing(args: Args) -> Result<CallToolResult> {
+    let release_notes = args
+        .0
+        .get("release_notes")
+        .and_then(Value::as_object)
+        .map(|notes| notes.keys().cloned().collect())
+        .unwrap_or_default();
+    let screenshots = args
+        .strings("screenshots")
+        .into_iter()
+        .enumerate()
+        .map(|(n, file)| UploadedScreenshot {
+            file: PathBuf::from(file),
+            size: Some("1080x2400".to_string()),
+            id: Some(format!("shot-{}", n + 1)),
+        })
+        .collect();
+    structured(StoreListing {
+        package: args.str("package").unwrap_or(DEMO_PACKAGE).to_string(),
+        version: args.str("version").unwrap_or(DEMO_VERSION).to_string(),
+        release_notes,
+        screenshots_deleted: args.flag("replace_screenshots"),
+        screenshots,
+    })
+}
+
+fn store_status(server: &AuroraServer, args: Args) -> Result<CallToolResult> {
+    let submission = SubmissionStatus {
+        id: Some(args.str("submission_id").unwrap_or("sub-20417").to_string()),
+        version: Some(format!("{DEMO_VERSION}-1")),
+        status: Some("in_review".to_string()),
+        comments: Vec::new(),
+    };
+    let response = json!([{
+        "id": submission.id,
+        "version": submission.version,
+        "status": submission.status,
+        "comments": [],
+    }]);
+    structured(StoreStatus {
+        package: package(server, args),
+        submissions: vec![submission],
+        response,
+    })
+}
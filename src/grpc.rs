@@ -0,0 +1,216 @@
+//! gRPC transport, behind the `grpc` feature: the `aurora_mcp.v1.Tools`
+//! service of `proto/aurora_mcp.proto`, with `ListTools`, `CallTool` and
+//! `StreamToolOutput`. Calls run through in-process MCP sessions like the
+//! REST facade's. The messages and the service trait are generated from
+//! the proto file.
+
+use std::net::SocketAddr;
+
+use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender, unbounded};
+use rmcp::model::{
+    CallToolRequestParam, CallToolResult, Content, ErrorCode, JsonObject,
+    LoggingMessageNotificationParam, ProgressNotificationParam, RawContent,
+};
+use rmcp::service::NotificationContext;
+use rmcp::{ClientHandler, ErrorData, RoleClient, ServiceError};
+use tonic::{Request, Response, Status};
+
+use self::proto::tool_output::Event;
+use self::proto::tools_server::{Tools, ToolsServer};
+use self::proto::{
+    CallToolRequest, CallToolResponse, Content as ContentItem, ListToolsRequest, ListToolsResponse,
+    LogMessage, Progress, ToolInfo, ToolOutput,
+};
+use crate::local;
+use crate::queue::SERVER_BUSY;
+use crate::server::AuroraServer;
+
+/// The messages and server of `proto/aurora_mcp.proto`, generated by
+/// `build.rs`.
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/aurora_mcp.v1.rs"));
+}
+
+/// Serves the `Tools` service on `addr` until Ctrl-C.
+pub async fn serve(server: AuroraServer, addr: SocketAddr) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(ToolsServer::new(ToolsService { server }))
+        .serve_with_shutdown(addr, async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+}
+
+/// The `aurora_mcp.v1.Tools` service.
+#[derive(Debug, Clone)]
+pub struct ToolsService {
+    server: AuroraServer,
+}
+
+#[tonic::async_trait]
+impl Tools for ToolsService {
+    async fn list_tools(
+        &self,
+        _request: Request<ListToolsRequest>,
+    ) -> Result<Response<ListToolsResponse>, Status> {
+        let tools = self
+            .server
+            .tools()
+            .into_iter()
+            .map(|tool| ToolInfo {
+                name: tool.name.to_string(),
+                title: tool.title.clone().unwrap_or_default(),
+                description: tool.description.as_deref().unwrap_or_default().to_string(),
+                input_schema_json: to_json(&tool.input_schema),
+                output_schema_json: tool.output_schema.as_ref().map(to_json).unwrap_or_default(),
+            })
+            .collect();
+        Ok(Response::new(ListToolsResponse { tools }))
+    }
+
+    async fn call_tool(
+        &self,
+        request: Request<CallToolRequest>,
+    ) -> Result<Response<CallToolResponse>, Status> {
+        let param = call_param(&self.server, request.into_inner())?;
+        let client = local::connect(&self.server, ()).await.map_err(status)?;
+        let result = client.call_tool(param).await;
+        let _ = client.cancel().await;
+        result
+            .map(|result| Response::new(response(result)))
+            .map_err(service_status)
+    }
+
+    type StreamToolOutputStream = UnboundedReceiver<Result<ToolOutput, Status>>;
+
+    async fn stream_tool_output(
+        &self,
+        request: Request<CallToolRequest>,
+    ) -> Result<Response<Self::StreamToolOutputStream>, Status> {
+        let param = call_param(&self.server, request.into_inner())?;
+        let (tx, rx) = unbounded();
+        let client = local::connect(&self.server, Forward(tx.clone()))
+            .await
+            .map_err(status)?;
+        tokio::spawn(async move {
+            let output = client
+                .call_tool(param)
+                .await
+                .map(|result| ToolOutput {
+                    event: Some(Event::Result(response(result))),
+                })
+                .map_err(service_status);
+            let _ = tx.unbounded_send(output);
+            let _ = client.cancel().await;
+        });
+        Ok(Response::new(rx))
+    }
+}
+
+/// Client side of a streamed call: passes the session's progress and log
+/// notifications on to the stream.
+struct Forward(UnboundedSender<Result<ToolOutput, Status>>);
+
+impl ClientHandler for Forward {
+    async fn on_progress(
+        &self,
+        params: ProgressNotificationParam,
+        _context: NotificationContext<RoleClient>,
+    ) {
+        let _ = self.0.unbounded_send(Ok(ToolOutput {
+            event: Some(Event::Progress(Progress {
+                progress: params.progress,
+                total: params.total,
+                message: params.message.unwrap_or_default(),
+            })),
+        }));
+    }
+
+    async fn on_logging_message(
+        &self,
+        params: LoggingMessageNotificationParam,
+        _context: NotificationContext<RoleClient>,
+    ) {
+        let level = serde_json::to_value(params.level)
+            .ok()
+            .and_then(|level| level.as_str().map(str::to_string))
+            .unwrap_or_default();
+        let _ = self.0.unbounded_send(Ok(ToolOutput {
+            event: Some(Event::Log(LogMessage {
+                level,
+                logger: params.logger.unwrap_or_default(),
+                data_json: params.data.to_string(),
+            })),
+        }));
+    }
+}
+
+fn call_param(
+    server: &AuroraServer,
+    request: CallToolRequest,
+) -> Result<CallToolRequestParam, Status> {
+    if !server.has_tool(&request.name) {
+        return Err(Status::not_found(format!("no tool '{}'", request.name)));
+    }
+    let arguments = if request.arguments_json.trim().is_empty() {
+        None
+    } else {
+        let arguments: JsonObject =
+            serde_json::from_str(&request.arguments_json).map_err(|err| {
+                Status::invalid_argument(format!("arguments must be a JSON object: {err}"))
+            })?;
+        Some(arguments)
+    };
+    Ok(CallToolRequestParam {
+        name: request.name.into(),
+        arguments,
+    })
+}
+
+fn response(result: CallToolResult) -> CallToolResponse {
+    CallToolResponse {
+        content: result.content.iter().map(content_item).collect(),
+        structured_json: result
+            .structured_content
+            .as_ref()
+            .map(to_json)
+            .unwrap_or_default(),
+        is_error: result.is_error == Some(true),
+    }
+}
+
+fn content_item(content: &Content) -> ContentItem {
+    let (kind, text) = match &content.raw {
+        RawContent::Text(text) => ("text", text.text.clone()),
+        RawContent::Image(_) => ("image", String::new()),
+        RawContent::Audio(_) => ("audio", String::new()),
+        RawContent::Resource(_) => ("resource", String::new()),
+        RawContent::ResourceLink(_) => ("resource_link", String::new()),
+    };
+    ContentItem {
+        kind: kind.to_string(),
+        text,
+        json: to_json(content),
+    }
+}
+
+fn to_json(value: &impl serde::Serialize) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn status(err: ErrorData) -> Status {
+    if err.code == ErrorCode::INVALID_PARAMS {
+        Status::invalid_argument(err.message)
+    } else if err.code == SERVER_BUSY {
+        Status::unavailable(err.message)
+    } else {
+        Status::internal(err.message)
+    }
+}
+
+fn service_status(err: ServiceError) -> Status {
+    match err {
+        ServiceError::McpError(err) => status(err),
+        err => Status::internal(err.to_string()),
+    }
+}
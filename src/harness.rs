@@ -0,0 +1,190 @@
+//! Test client for integration tests of the server: a real MCP session in
+//! memory, with helpers for calling tools and reading resources, so every
+//! tool can be exercised end to end without spawning the binary.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), rmcp::ServiceError> {
+//! use aurora_mcp::Config;
+//! use aurora_mcp::harness::TestClient;
+//!
+//! let client = TestClient::mock(Config::default()).await?;
+//! let repos = client
+//!     .call_tool_text("device_repos", serde_json::json!({"device": "mock-phone"}))
+//!     .await?;
+//! assert!(repos.contains("mock-phone"));
+//! client.close().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::PathBuf;
+
+use rmcp::model::{
+    CallToolRequestParam, CallToolResult, ClientCapabilities, ClientInfo, ErrorData,
+    ListRootsResult, ReadResourceRequestParam, ReadResourceResult, ResourceContents, Root,
+    RootsCapabilities,
+};
+use rmcp::service::{RequestContext, RunningService};
+use rmcp::{ClientHandler, Peer, RoleClient, ServiceError};
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::local;
+use crate::server::{AppState, AuroraServer};
+use crate::workspace;
+
+/// A client connected to a session of an in-process server.
+pub struct TestClient {
+    server: AuroraServer,
+    client: RunningService<RoleClient, Roots>,
+}
+
+/// The client side of the session, declaring roots when given some.
+struct Roots(Option<Vec<PathBuf>>);
+
+impl ClientHandler for Roots {
+    async fn list_roots(
+        &self,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<ListRootsResult, ErrorData> {
+        let roots = self
+            .0
+            .iter()
+            .flatten()
+            .map(|path| Root {
+                uri: workspace::uri(path),
+                name: None,
+            })
+            .collect();
+        Ok(ListRootsResult { roots })
+    }
+
+    fn get_info(&self) -> ClientInfo {
+        let mut info = ClientInfo::default();
+        info.client_info.name = "aurora-mcp-harness".to_string();
+        if self.0.is_some() {
+            info.capabilities = ClientCapabilities {
+                roots: Some(RootsCapabilities::default()),
+                ..Default::default()
+            };
+        }
+        info
+    }
+}
+
+impl TestClient {
+    /// Connects to a server over `config` whose devices are simulated.
+    pub async fn mock(config: Config) -> Result<Self, ServiceError> {
+        Self::connect(&AuroraServer::new(AppState::mock(config))).await
+    }
+
+    /// Connects to a new session of `server`, as a client without roots.
+    pub async fn connect(server: &AuroraServer) -> Result<Self, ServiceError> {
+        Self::start(server, None).await
+    }
+
+    /// Connects to a new session of `server`, as a client whose roots are
+    /// `roots`.
+    pub async fn with_roots(
+        server: &AuroraServer,
+        roots: impl IntoIterator<Item = PathBuf>,
+    ) -> Result<Self, ServiceError> {
+        Self::start(server, Some(roots.into_iter().collect())).await
+    }
+
+    async fn start(
+        server: &AuroraServer,
+        roots: Option<Vec<PathBuf>>,
+    ) -> Result<Self, ServiceError> {
+        let client = local::connect(server, Roots(roots))
+            .await
+            .map_err(ServiceError::McpError)?;
+        Ok(Self {
+            server: server.clone(),
+            client,
+        })
+    }
+
+    pub fn server(&self) -> &AuroraServer {
+        &self.server
+    }
+
+    /// The session, for requests the helpers do not cover.
+    pub fn peer(&self) -> &Peer<RoleClient> {
+        self.client.peer()
+    }
+
+    /// Calls `name` with `arguments`, a JSON object or `null` for none.
+    /// A tool error comes back as a result with `is_error` set.
+    pub async fn call_tool(
+        &self,
+        name: &str,
+        arguments: Value,
+    ) -> Result<CallToolResult, ServiceError> {
+        let arguments = match arguments {
+            Value::Null => None,
+            Value::Object(arguments) => Some(arguments),
+            other => {
+                return Err(ServiceError::McpError(ErrorData::invalid_params(
+                    format!("arguments must be a JSON object, not {other}"),
+                    None,
+                )));
+            }
+        };
+        self.client
+            .call_tool(CallToolRequestParam {
+                name: name.to_string().into(),
+                arguments,
+            })
+            .await
+    }
+
+    /// Calls `name` and returns the text of its result; a tool error is an
+    /// `Err` carrying the error's text.
+    pub async fn call_tool_text(
+        &self,
+        name: &str,
+        arguments: Value,
+    ) -> Result<String, ServiceError> {
+        let result = self.call_tool(name, arguments).await?;
+        let text = result
+            .content
+            .iter()
+            .filter_map(|content| content.as_text().map(|text| text.text.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if result.is_error == Some(true) {
+            return Err(ServiceError::McpError(ErrorData::internal_error(
+                text, None,
+            )));
+        }
+        Ok(text)
+    }
+
+    pub async fn read_resource(&self, uri: &str) -> Result<ReadResourceResult, ServiceError> {
+        self.client
+            .read_resource(ReadResourceRequestParam {
+                uri: uri.to_string(),
+            })
+            .await
+    }
+
+    /// The text of the resource at `uri`; blobs are skipped.
+    pub async fn read_resource_text(&self, uri: &str) -> Result<String, ServiceError> {
+        let result = self.read_resource(uri).await?;
+        Ok(result
+            .contents
+            .into_iter()
+            .filter_map(|contents| match contents {
+                ResourceContents::TextResourceContents { text, .. } => Some(text),
+                ResourceContents::BlobResourceContents { .. } => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    /// Ends the session.
+    pub async fn close(self) {
+        let _ = self.client.cancel().await;
+    }
+}
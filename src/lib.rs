@@ -0,0 +1,32 @@
+//! MCP server exposing Aurora OS development tooling: device access over SSH,
+//! SDK helpers and documentation.
+
+pub mod bench;
+pub mod chunks;
+pub mod config;
+pub mod device;
+pub mod docs;
+pub mod error;
+pub mod events;
+pub mod fixtures;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod harness;
+pub mod listener;
+pub mod local;
+pub mod progress;
+pub mod prompts;
+pub mod queue;
+pub mod record;
+pub mod resources;
+pub mod rest;
+pub mod sdk;
+pub mod secrets;
+pub mod server;
+pub mod util;
+pub mod webhooks;
+pub mod workspace;
+
+pub use config::Config;
+pub use error::{Error, Result};
+pub use server::AuroraServer;
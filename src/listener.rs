@@ -0,0 +1,190 @@
+//! Connection limits for the HTTP listener. Past `max_connections` open
+//! connections, new ones wait in the kernel's backlog until one closes,
+//! and connections are taken at most `accepts_per_second` at a time, so a
+//! storm of reconnecting clients slows down instead of starving the
+//! sessions already established.
+
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use axum::serve::Listener;
+use serde::Deserialize;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// Limits of the HTTP listener, from the `[http]` config table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpOptions {
+    /// Connections open at once (MCP sessions keep one open for their
+    /// event stream); 0 for no limit.
+    pub max_connections: usize,
+    /// Connections accepted per second, in bursts of as many; 0 for no
+    /// limit.
+    pub accepts_per_second: u32,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            max_connections: 512,
+            accepts_per_second: 100,
+        }
+    }
+}
+
+/// A listener accepting within the limits of [`HttpOptions`].
+#[derive(Debug)]
+pub struct Limited<L> {
+    inner: L,
+    max_connections: usize,
+    permits: Option<Arc<Semaphore>>,
+    bucket: Option<Bucket>,
+    /// Whether the last accept found every connection taken, so the
+    /// warning is logged once per episode.
+    saturated: bool,
+}
+
+impl<L: Listener> Limited<L> {
+    pub fn new(inner: L, options: &HttpOptions) -> Self {
+        Self {
+            inner,
+            max_connections: options.max_connections,
+            permits: (options.max_connections > 0)
+                .then(|| Arc::new(Semaphore::new(options.max_connections))),
+            bucket: (options.accepts_per_second > 0)
+                .then(|| Bucket::new(options.accepts_per_second)),
+            saturated: false,
+        }
+    }
+}
+
+impl<L: Listener> Listener for Limited<L> {
+    type Io = Connection<L::Io>;
+    type Addr = L::Addr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        let permit = match &self.permits {
+            Some(permits) => Some(match permits.clone().try_acquire_owned() {
+                Ok(permit) => {
+                    self.saturated = false;
+                    permit
+                }
+                Err(_) => {
+                    if !self.saturated {
+                        tracing::warn!(
+                            "all {} connections are in use; new ones wait",
+                            self.max_connections
+                        );
+                        self.saturated = true;
+                    }
+                    permits
+                        .clone()
+                        .acquire_owned()
+                        .await
+                        .expect("the connection semaphore is never closed")
+                }
+            }),
+            None => None,
+        };
+        if let Some(bucket) = &mut self.bucket {
+            bucket.take().await;
+        }
+        let (io, addr) = self.inner.accept().await;
+        (
+            Connection {
+                io,
+                _permit: permit,
+            },
+            addr,
+        )
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.inner.local_addr()
+    }
+}
+
+/// An accepted connection, releasing its slot when dropped.
+#[derive(Debug)]
+pub struct Connection<T> {
+    io: T,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Connection<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Connection<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+}
+
+/// Token bucket of accepts: refills at `rate` per second up to `rate`.
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl Bucket {
+    fn new(rate: u32) -> Self {
+        Self {
+            rate: f64::from(rate),
+            tokens: f64::from(rate),
+            refilled: Instant::now(),
+        }
+    }
+
+    /// Waits for a token and takes it.
+    async fn take(&mut self) {
+        let now = Instant::now();
+        self.tokens = (self.tokens + now.duration_since(self.refilled).as_secs_f64() * self.rate)
+            .min(self.rate);
+        self.refilled = now;
+        if self.tokens < 1.0 {
+            let wait = Duration::from_secs_f64((1.0 - self.tokens) / self.rate);
+            tokio::time::sleep(wait).await;
+            self.tokens = 1.0;
+            self.refilled = Instant::now();
+        }
+        self.tokens -= 1.0;
+    }
+}
@@ -0,0 +1,42 @@
+//! In-process MCP sessions: a client connected in memory to a fresh
+//! session of the server, for the facades that call tools on behalf of
+//! callers that do not speak MCP. Tools run exactly as they would for an
+//! MCP client without roots.
+
+use rmcp::service::RunningService;
+use rmcp::{ClientHandler, ErrorData, RoleClient, ServiceExt};
+use tokio::io::DuplexStream;
+
+use crate::server::AuroraServer;
+
+/// Room for messages in flight between a client and its session.
+const SESSION_BUFFER: usize = 64 * 1024;
+
+/// Starts a session of `server` and connects `handler` to it as the
+/// client. The session ends when the client is dropped or cancelled.
+pub async fn connect<C: ClientHandler>(
+    server: &AuroraServer,
+    handler: C,
+) -> Result<RunningService<RoleClient, C>, ErrorData> {
+    handler
+        .serve(open(server))
+        .await
+        .map_err(|err| ErrorData::internal_error(err.to_string(), None))
+}
+
+/// Starts a session of `server` and returns the client's end of its
+/// transport, carrying newline-delimited JSON-RPC. The session ends when
+/// the stream is dropped.
+pub fn open(server: &AuroraServer) -> DuplexStream {
+    let (client_io, server_io) = tokio::io::duplex(SESSION_BUFFER);
+    let session = server.new_session();
+    tokio::spawn(async move {
+        match session.serve(server_io).await {
+            Ok(running) => {
+                let _ = running.waiting().await;
+            }
+            Err(err) => tracing::debug!("in-process session: {err}"),
+        }
+    });
+    client_io
+}
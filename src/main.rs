@@ -1,3 +1,316 @@
-fn main() {
-    println!("Hello, world!");
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use aurora_mcp::bench::{self, Bench};
+use aurora_mcp::listener::Limited;
+use aurora_mcp::record::{self, Recorded, RecordingSessions};
+use aurora_mcp::server::AppState;
+use aurora_mcp::{AuroraServer, Config};
+use clap::{Parser, Subcommand, ValueEnum};
+use rmcp::ServiceExt;
+use rmcp::model::JsonObject;
+use rmcp::transport::async_rw::AsyncRwTransport;
+use rmcp::transport::streamable_http_server::{
+    SessionManager, StreamableHttpService, session::local::LocalSessionManager,
+};
+use tracing_subscriber::EnvFilter;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "MCP server for Aurora OS development")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path to config.toml (default: ~/.config/aurora-mcp/config.toml).
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = Transport::Stdio)]
+    transport: Transport,
+
+    /// Listen address for the HTTP and gRPC transports.
+    #[arg(long, default_value = "127.0.0.1:8000")]
+    bind: SocketAddr,
+
+    /// Simulate devices in memory instead of connecting over SSH, for CI
+    /// and demos. Without configured devices, `mock-phone` and
+    /// `mock-emulator` are provided.
+    #[arg(long, global = true)]
+    mock_devices: bool,
+
+    /// Like `--mock-devices`, and builds (Build Engine, Rust, Flutter),
+    /// tests, C++ analysis, RPM validation, core symbolication, remote
+    /// builds, store publishing and deployments return canned results after
+    /// a realistic delay, for demos and client development without an SDK
+    /// or hardware.
+    #[arg(long, global = true)]
+    mock_all: bool,
+
+    /// Record every JSON-RPC message of each session to a file in this
+    /// directory, for `replay`.
+    #[arg(long)]
+    record: Option<PathBuf>,
+
+    /// Threads running tasks (default: one per CPU core).
+    #[arg(long, global = true, value_parser = clap::value_parser!(u16).range(1..))]
+    worker_threads: Option<u16>,
+
+    /// Most threads for blocking work such as indexing and file diffs
+    /// (default: 512).
+    #[arg(long, global = true, value_parser = clap::value_parser!(u16).range(1..))]
+    blocking_threads: Option<u16>,
+
+    /// Run every task on the main thread. Enough for stdio, which serves a
+    /// single client, and the lightest on low-core devices.
+    #[arg(long, global = true, conflicts_with = "worker_threads")]
+    current_thread: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Re-run a session recorded with `--record`: send its client messages
+    /// to a fresh session and print that session's messages as recording
+    /// lines.
+    Replay {
+        /// A session file written by `--record`.
+        file: PathBuf,
+
+        /// How long to wait for each server reply.
+        #[arg(long, default_value_t = 120)]
+        timeout_secs: u64,
+    },
+    /// Load-test a server over HTTP: call a tool from many sessions at
+    /// once and report throughput, latency percentiles and error rates.
+    Bench {
+        /// The server's MCP endpoint.
+        #[arg(long, default_value = "http://127.0.0.1:8000/mcp")]
+        url: axum::http::Uri,
+
+        /// The tool to call.
+        #[arg(long)]
+        tool: String,
+
+        /// The tool's arguments, as a JSON object.
+        #[arg(long, default_value = "{}", value_parser = parse_arguments)]
+        arguments: JsonObject,
+
+        /// Sessions calling at once.
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: u16,
+
+        /// How long to keep calling, like `30s`, `500ms` or `2m`.
+        #[arg(long, default_value = "10s", value_parser = parse_duration)]
+        duration: Duration,
+
+        /// How long a call may take before it counts as failed.
+        #[arg(long, default_value_t = 60)]
+        timeout_secs: u64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Transport {
+    Stdio,
+    Http,
+    /// The `Tools` service of `proto/aurora_mcp.proto`.
+    #[cfg(feature = "grpc")]
+    Grpc,
+}
+
+fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .init();
+
+    let cli = Cli::parse();
+    let mut runtime = if cli.current_thread {
+        tokio::runtime::Builder::new_current_thread()
+    } else {
+        tokio::runtime::Builder::new_multi_thread()
+    };
+    if let Some(threads) = cli.worker_threads {
+        runtime.worker_threads(threads.into());
+    }
+    if let Some(threads) = cli.blocking_threads {
+        runtime.max_blocking_threads(threads.into());
+    }
+    runtime
+        .enable_all()
+        .build()
+        .context("failed to start the async runtime")?
+        .block_on(run(cli))
+}
+
+async fn run(cli: Cli) -> anyhow::Result<()> {
+    if let Some(Command::Bench {
+        url,
+        tool,
+        arguments,
+        concurrency,
+        duration,
+        timeout_secs,
+    }) = cli.command
+    {
+        let bench = Bench {
+            url,
+            tool,
+            arguments,
+            concurrency: concurrency.into(),
+            duration,
+            timeout: Duration::from_secs(timeout_secs),
+        };
+        let report = bench::run(&bench)
+            .await
+            .with_context(|| format!("failed to benchmark {}", bench.url))?;
+        print!("{report}");
+        return Ok(());
+    }
+    let config = Config::load(cli.config.as_deref())?;
+    let http = config.http.clone();
+    let state = if cli.mock_all {
+        AppState::mock_all(config)
+    } else if cli.mock_devices {
+        AppState::mock(config)
+    } else {
+        AppState::new(config)
+    };
+    state.webhooks.start(&state.events);
+    let server = AuroraServer::new(state);
+
+    if let Some(Command::Replay { file, timeout_secs }) = &cli.command {
+        let entries = record::read(file)?;
+        let timeout = Duration::from_secs(*timeout_secs);
+        let replay = record::replay(&server, &entries, timeout, std::io::stdout().lock())
+            .await
+            .with_context(|| format!("failed to replay {}", file.display()))?;
+        eprintln!(
+            "sent {} messages; {} of {} responses differ from the recording{}",
+            replay.sent,
+            replay.differing.len(),
+            replay.compared,
+            if replay.differing.is_empty() {
+                String::new()
+            } else {
+                format!(" (ids {})", serde_json::Value::from(replay.differing))
+            }
+        );
+        return Ok(());
+    }
+    if let Some(dir) = &cli.record {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+
+    match cli.transport {
+        Transport::Stdio => {
+            let (stdin, stdout) = rmcp::transport::stdio();
+            let transport = AsyncRwTransport::new_server(stdin, stdout);
+            let service = match &cli.record {
+                Some(dir) => server.serve(Recorded::new(transport, dir)).await,
+                None => server.serve(transport).await,
+            }
+            .context("failed to start stdio transport")?;
+            service.waiting().await?;
+        }
+        Transport::Http => {
+            let rest = aurora_mcp::rest::router(server.clone());
+            let sessions = LocalSessionManager::default();
+            let router = match cli.record {
+                Some(dir) => mcp_router(server, RecordingSessions::new(sessions, dir)),
+                None => mcp_router(server, sessions),
+            }
+            .merge(rest);
+            let listener = tokio::net::TcpListener::bind(cli.bind)
+                .await
+                .with_context(|| format!("failed to bind {}", cli.bind))?;
+            let listener = Limited::new(listener, &http);
+            tracing::info!(
+                "listening on http://{0}/mcp, tools also at http://{0}/api/tools/<name>",
+                cli.bind
+            );
+            axum::serve(listener, router)
+                .with_graceful_shutdown(async {
+                    let _ = tokio::signal::ctrl_c().await;
+                })
+                .await?;
+        }
+        #[cfg(feature = "grpc")]
+        Transport::Grpc => {
+            tracing::info!("serving gRPC on {}", cli.bind);
+            aurora_mcp::grpc::serve(server, cli.bind)
+                .await
+                .with_context(|| format!("failed to serve gRPC on {}", cli.bind))?;
+        }
+    }
+    Ok(())
+}
+
+/// The streamable HTTP transport at `/mcp`, its sessions kept by `sessions`.
+fn mcp_router<M: SessionManager>(server: AuroraServer, sessions: M) -> axum::Router {
+    let service = StreamableHttpService::new(
+        move || Ok(server.new_session()),
+        sessions.into(),
+        Default::default(),
+    );
+    axum::Router::new().nest_service("/mcp", service)
+}
+
+/// A tool's arguments from the command line.
+fn parse_arguments(text: &str) -> Result<JsonObject, String> {
+    serde_json::from_str(text).map_err(|err| format!("not a JSON object: {err}"))
+}
+
+/// A duration like `30s`, `500ms`, `2m` or `1h`; a bare number is seconds.
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{text}' is not a duration like 30s"))?;
+    let seconds = match unit.trim() {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        unit => return Err(format!("unknown unit '{unit}'; use ms, s, m or h")),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("45"), Ok(Duration::from_secs(45)));
+    }
+
+    #[test]
+    fn refuses_invalid_durations() {
+        assert_eq!(
+            parse_duration("soon"),
+            Err("'soon' is not a duration like 30s".to_string())
+        );
+        assert_eq!(
+            parse_duration(""),
+            Err("'' is not a duration like 30s".to_string())
+        );
+        assert_eq!(
+            parse_duration("3d"),
+            Err("unknown unit 'd'; use ms, s, m or h".to_string())
+        );
+        assert!(parse_duration("1.2.3s").is_err());
+    }
 }
@@ -0,0 +1,108 @@
+//! Partial output of running tools. A tool call whose request carries a
+//! progress token gets an [`Output`] for the duration of the call, and
+//! whatever the tool emits through it reaches the client while the tool is
+//! still running, as `notifications/progress` whose `message` holds the new
+//! lines. Commands run with `sdk::run_logged` (builds, tests, analyzers)
+//! stream their log this way without further ado; other tools call
+//! [`emit`].
+//!
+//! `progress` counts the lines sent so far; there is no `total`. Lines
+//! that pile up while a notification is in flight go out together.
+
+use std::time::Duration;
+
+use rmcp::model::{Meta, ProgressNotificationParam, ProgressToken};
+use rmcp::{Peer, RoleServer};
+use tokio::sync::mpsc;
+
+/// Size past which a batch of lines takes no more.
+const MAX_MESSAGE_BYTES: usize = 16 * 1024;
+
+/// How long a finished call waits for its queued lines to go out.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+
+tokio::task_local! {
+    static OUTPUT: Output;
+}
+
+/// Where the current tool call's partial output goes.
+#[derive(Debug, Clone, Default)]
+pub struct Output {
+    sender: Option<mpsc::UnboundedSender<String>>,
+}
+
+impl Output {
+    /// Whether emitted lines go anywhere.
+    pub fn is_active(&self) -> bool {
+        self.sender.is_some()
+    }
+
+    pub fn line(&self, line: impl Into<String>) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(line.into());
+        }
+    }
+}
+
+/// Runs the tool call `future` of a request with `meta`, sending what it
+/// emits to `peer`. Lines still queued when the call ends are sent before
+/// this returns, so they reach the client ahead of the result.
+pub async fn run<F: Future>(peer: Peer<RoleServer>, meta: &Meta, future: F) -> F::Output {
+    let Some(token) = meta.get_progress_token() else {
+        return future.await;
+    };
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let forwarding = tokio::spawn(forward(peer, token, receiver));
+    let output = Output {
+        sender: Some(sender),
+    };
+    let result = OUTPUT.scope(output, future).await;
+    // Forwarding ends once the last handle to the output is gone, which
+    // may outlive the call in a task the tool left running.
+    let _ = tokio::time::timeout(FLUSH_TIMEOUT, forwarding).await;
+    result
+}
+
+/// The output of the tool call being run; inactive outside of one.
+pub fn current() -> Output {
+    OUTPUT.try_with(Output::clone).unwrap_or_default()
+}
+
+/// Sends `line` to the client of the tool call being run, if it asked for
+/// progress.
+pub fn emit(line: impl Into<String>) {
+    let _ = OUTPUT.try_with(|output| output.line(line));
+}
+
+/// Sends the lines of `receiver` as progress notifications until the call's
+/// output is dropped.
+async fn forward(
+    peer: Peer<RoleServer>,
+    token: ProgressToken,
+    mut receiver: mpsc::UnboundedReceiver<String>,
+) {
+    let mut sent = 0u64;
+    while let Some(first) = receiver.recv().await {
+        let mut message = first;
+        let mut lines = 1;
+        while message.len() < MAX_MESSAGE_BYTES
+            && let Ok(line) = receiver.try_recv()
+        {
+            message.push('\n');
+            message.push_str(&line);
+            lines += 1;
+        }
+        sent += lines;
+        let notified = peer
+            .notify_progress(ProgressNotificationParam {
+                progress_token: token.clone(),
+                progress: sent as f64,
+                total: None,
+                message: Some(message),
+            })
+            .await;
+        if notified.is_err() {
+            break;
+        }
+    }
+}
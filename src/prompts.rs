@@ -0,0 +1,691 @@
+//! MCP prompts for common workflows, in English and Russian. Each one
+//! gathers the files and documentation pages the task needs as embedded
+//! resources and lays out which tools to run in what order, so the model
+//! starts with the right context instead of discovering it call by call.
+//! Arguments with a known set of values complete through
+//! `completion/complete`, from the SDK's metadata where there is some.
+//!
+//! The language is the prompt's `language` argument, else the `[prompts]`
+//! config table's, else the server's locale:
+//!
+//! ```toml
+//! [prompts]
+//! language = "ru"
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::{
+    ArgumentInfo, CompletionInfo, GetPromptResult, PromptMessage, PromptMessageRole,
+};
+use rmcp::{ErrorData, prompt, prompt_router};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::docs::{self, snippets};
+use crate::error::Error;
+use crate::sdk::compat::target_os_version;
+use crate::sdk::project::ProjectInfo;
+use crate::sdk::scaffold::ProjectKind;
+use crate::sdk::silica::OsVersion;
+use crate::sdk::spec::GenerateSpecParams;
+use crate::sdk::{licenses, releases};
+use crate::server::AuroraServer;
+
+/// Largest file embedded into a prompt.
+const MAX_EMBEDDED_BYTES: u64 = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Ru,
+}
+
+impl Language {
+    /// `en`, `ru`, or a locale such as `ru_RU.UTF-8`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_ascii_lowercase();
+        if value.starts_with("ru") {
+            Some(Self::Ru)
+        } else if value.starts_with("en")
+            || value == "c"
+            || value.starts_with("c.")
+            || value == "posix"
+        {
+            Some(Self::En)
+        } else {
+            None
+        }
+    }
+
+    /// The language of the server's locale, if it is one of the supported.
+    fn from_env() -> Option<Self> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::parse(&value))
+    }
+}
+
+/// Prompt settings, from the `[prompts]` config table.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PromptsOptions {
+    /// Language of prompts that do not ask for one (default: from the
+    /// server's locale, else English).
+    pub language: Option<Language>,
+}
+
+impl AuroraServer {
+    /// The language `requested` by a prompt argument, else the configured
+    /// one, else the locale's.
+    fn prompt_language(&self, requested: Option<&str>) -> Result<Language, Error> {
+        match requested.filter(|value| !value.trim().is_empty()) {
+            Some(value) => Language::parse(value).ok_or_else(|| {
+                Error::InvalidArgument(format!("unsupported language '{value}'; use en or ru"))
+            }),
+            None => Ok(self
+                .state()
+                .config
+                .prompts
+                .language
+                .or_else(Language::from_env)
+                .unwrap_or_default()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReviewSpecArgs {
+    /// Project directory (default: the `[sdk]` project from the config).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Spec file relative to the project (default: the one in `rpm/`).
+    #[serde(default)]
+    pub spec: Option<String>,
+    /// `en` or `ru` (default: the server's setting).
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TriageCrashArgs {
+    /// Device the app crashed on, as named in the server config.
+    pub device: String,
+    /// Application name, e.g. `ru.example.app`.
+    #[serde(default)]
+    pub app: Option<String>,
+    /// Journal excerpt around the crash, if already at hand.
+    #[serde(default)]
+    pub journal: Option<String>,
+    /// `en` or `ru` (default: the server's setting).
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MigrateToSilicaArgs {
+    /// QML file to migrate, relative to the project.
+    pub file: String,
+    /// Project directory (default: the `[sdk]` project from the config).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// `en` or `ru` (default: the server's setting).
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WriteRpmSpecArgs {
+    /// Oldest Aurora OS release the package must install on, e.g. `4.0.2`.
+    #[serde(default)]
+    pub os_version: Option<String>,
+    /// SPDX license expression, e.g. `BSD-3-Clause`.
+    #[serde(default)]
+    pub license: Option<String>,
+    /// `silica-app`, `qml-app`, `python-app`, `library` or `dbus-service`
+    /// (default: judged from the project's sources).
+    #[serde(default)]
+    pub app_type: Option<ProjectKind>,
+    /// Project directory (default: the `[sdk]` project from the config).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// `en` or `ru` (default: the server's setting).
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+#[prompt_router(vis = "pub(crate)")]
+impl AuroraServer {
+    #[prompt(
+        name = "review-spec-for-store",
+        description = "Review a project's RPM spec for Aurora store submission: embeds the spec and the packaging and store requirement pages, and plans the lint, compliance and validator runs. / Проверка spec-файла проекта перед публикацией в магазине Aurora."
+    )]
+    pub async fn review_spec_for_store(
+        &self,
+        Parameters(args): Parameters<ReviewSpecArgs>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let language = self.prompt_language(args.language.as_deref())?;
+        let dir = self.state().config.sdk.project(args.project.as_deref())?;
+        let spec = match &args.spec {
+            Some(spec) => dir.join(spec),
+            None => ProjectInfo::inspect(&dir).spec_file.ok_or_else(|| {
+                Error::InvalidArgument(format!(
+                    "no .spec file in {}; pass spec or create one with generate_spec",
+                    dir.join("rpm").display()
+                ))
+            })?,
+        };
+        let project = dir.display();
+        let (text, description) = match language {
+            Language::En => (
+                format!(
+                    "Review the RPM spec of the Aurora OS project in {project} for submission \
+                     to the Aurora store. The spec and the relevant documentation follow.\n\n\
+                     1. Run lint_spec and check_store_compliance on the project and go through \
+                     every finding.\n\
+                     2. Run validate_desktop and check_permissions: the desktop file's \
+                     [X-Application] permissions must match what the code uses.\n\
+                     3. If a built RPM exists under RPMS/, run validate_rpm on it; otherwise \
+                     build_project first.\n\
+                     4. Check the spec by hand against the store requirements below for what \
+                     the tools do not cover: summary and description quality, license, \
+                     %changelog.\n\n\
+                     Report the problems grouped as blocking (the store would reject the \
+                     package) and advisory, each with the spec line and the exact fix."
+                ),
+                format!("Store review of {}", spec.display()),
+            ),
+            Language::Ru => (
+                format!(
+                    "Проверь RPM spec-файл проекта Aurora OS в {project} перед публикацией \
+                     в магазине Aurora. Ниже приложены spec-файл и нужная документация.\n\n\
+                     1. Запусти lint_spec и check_store_compliance для проекта и разбери \
+                     каждое замечание.\n\
+                     2. Запусти validate_desktop и check_permissions: разрешения в секции \
+                     [X-Application] desktop-файла должны соответствовать тому, что \
+                     использует код.\n\
+                     3. Если в RPMS/ есть собранный RPM, запусти для него validate_rpm; \
+                     иначе сначала build_project.\n\
+                     4. Вручную сверь spec-файл с требованиями магазина ниже в том, что \
+                     инструменты не проверяют: качество Summary и описания, лицензия, \
+                     %changelog.\n\n\
+                     Перечисли проблемы в двух группах: блокирующие (магазин отклонит \
+                     пакет) и рекомендации, для каждой укажи строку spec-файла и точное \
+                     исправление."
+                ),
+                format!("Проверка {} для магазина", spec.display()),
+            ),
+        };
+        let mut messages = vec![PromptMessage::new_text(PromptMessageRole::User, text)];
+        messages.push(embed_file(&spec)?);
+        for page in ["packaging/rpm-spec", "packaging/store-requirements"] {
+            messages.extend(embed_doc(self, page)?);
+        }
+        Ok(GetPromptResult {
+            description: Some(description),
+            messages,
+        })
+    }
+
+    #[prompt(
+        name = "triage-crash",
+        description = "Triage an application crash on a device: collect the core dump and journal, symbolize the backtrace and find the cause. / Разбор падения приложения на устройстве: дамп памяти, журнал, символизированный стек и причина."
+    )]
+    pub async fn triage_crash(
+        &self,
+        Parameters(args): Parameters<TriageCrashArgs>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let language = self.prompt_language(args.language.as_deref())?;
+        self.devices().get(&args.device)?;
+        let device = &args.device;
+        let (mut text, description) = match language {
+            Language::En => {
+                let app = args.app.as_deref().unwrap_or("The app");
+                (
+                    format!(
+                        "{app} crashed on device {device}. Find out why.\n\n\
+                         1. Run collect_crash_report on {device} to pull the newest core dump \
+                         and a journal excerpt.\n\
+                         2. Run symbolicate_core on the core dump; if debug symbols are \
+                         missing, find_debuginfo locates the matching -debuginfo RPMs \
+                         (index_debuginfo indexes a build's RPMS/ directory).\n\
+                         3. Read the crashing thread's frames in the project's sources and \
+                         the journal lines just before the crash: QML errors, failed D-Bus \
+                         calls and sandbox denials often explain a crash that looks like a \
+                         bug in the code.\n\
+                         4. If the cause is unclear, reproduce with gdb_session_start and \
+                         breakpoints around the crashing frame.\n\n\
+                         Answer with the cause, the evidence (frames and journal lines), \
+                         and a fix."
+                    ),
+                    match &args.app {
+                        Some(app) => format!("Crash triage of {app} on {device}"),
+                        None => format!("Crash triage on {device}"),
+                    },
+                )
+            }
+            Language::Ru => {
+                let app = args.app.as_deref().unwrap_or("Приложение");
+                (
+                    format!(
+                        "{app} упало на устройстве {device}. Выясни почему.\n\n\
+                         1. Запусти collect_crash_report для {device}, чтобы получить \
+                         последний дамп памяти и фрагмент журнала.\n\
+                         2. Запусти symbolicate_core для дампа; если нет отладочных \
+                         символов, find_debuginfo найдёт подходящие -debuginfo RPM \
+                         (index_debuginfo индексирует каталог RPMS/ сборки).\n\
+                         3. Изучи кадры упавшего потока в исходниках проекта и строки \
+                         журнала перед падением: ошибки QML, неудачные вызовы D-Bus и \
+                         запреты песочницы часто объясняют падение, похожее на ошибку \
+                         в коде.\n\
+                         4. Если причина неясна, воспроизведи падение через \
+                         gdb_session_start с точками останова вокруг упавшего кадра.\n\n\
+                         Ответь, указав причину, доказательства (кадры стека и строки \
+                         журнала) и исправление."
+                    ),
+                    match &args.app {
+                        Some(app) => format!("Разбор падения {app} на {device}"),
+                        None => format!("Разбор падения на {device}"),
+                    },
+                )
+            }
+        };
+        if let Some(journal) = args
+            .journal
+            .as_deref()
+            .filter(|text| !text.trim().is_empty())
+        {
+            let heading = match language {
+                Language::En => "Journal excerpt from the user:",
+                Language::Ru => "Фрагмент журнала от пользователя:",
+            };
+            text.push_str(&format!(
+                "\n\n{heading}\n\n```\n{}\n```",
+                journal.trim_end()
+            ));
+        }
+        let mut messages = vec![PromptMessage::new_text(PromptMessageRole::User, text)];
+        messages.extend(embed_doc(self, "platform/permissions")?);
+        Ok(GetPromptResult {
+            description: Some(description),
+            messages,
+        })
+    }
+
+    #[prompt(
+        name = "migrate-to-silica",
+        description = "Migrate a QML page from QtQuick.Controls to Sailfish Silica: embeds the page, the Silica guidelines and a reference page pattern, and plans the checks to run afterwards. / Перевод QML-страницы с QtQuick.Controls на Sailfish Silica."
+    )]
+    pub async fn migrate_to_silica(
+        &self,
+        Parameters(args): Parameters<MigrateToSilicaArgs>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let language = self.prompt_language(args.language.as_deref())?;
+        let dir = self.state().config.sdk.project(args.project.as_deref())?;
+        let file = dir.join(&args.file);
+        let name = &args.file;
+        let (text, description) = match language {
+            Language::En => (
+                format!(
+                    "Migrate {name} from QtQuick.Controls to Sailfish Silica so it follows \
+                     the Aurora OS look and guidelines. The page, the Silica guidelines and \
+                     a reference page follow.\n\n\
+                     - Replace the controls with their Silica equivalents (ApplicationWindow \
+                     and StackView with the Silica ApplicationWindow and pageStack, ToolBar \
+                     or menus with PullDownMenu/PushUpMenu, ListView with SilicaListView, \
+                     Button, TextField, Switch with TextSwitch, ComboBox, Slider, Dialog \
+                     with a Silica Dialog).\n\
+                     - Put the content in a Silica flickable with a PageHeader, and take \
+                     sizes, margins, colours and fonts from Theme instead of fixed values.\n\
+                     - Keep the page's behaviour and public properties unchanged.\n\n\
+                     Use find_snippet for other patterns you need. Afterwards run lint_qml, \
+                     check_ui_guidelines and check_silica_api on the project, fix what they \
+                     report, and offer qml_preview_start to look at the result on a device."
+                ),
+                format!("Silica migration of {name}"),
+            ),
+            Language::Ru => (
+                format!(
+                    "Переведи {name} с QtQuick.Controls на Sailfish Silica, чтобы страница \
+                     соответствовала стилю и рекомендациям Aurora OS. Ниже приложены \
+                     страница, рекомендации по Silica и образец страницы.\n\n\
+                     - Замени элементы управления их аналогами из Silica (ApplicationWindow \
+                     и StackView на ApplicationWindow из Silica и pageStack, ToolBar и меню \
+                     на PullDownMenu/PushUpMenu, ListView на SilicaListView, Button, \
+                     TextField, Switch на TextSwitch, ComboBox, Slider, Dialog на Dialog \
+                     из Silica).\n\
+                     - Размести содержимое во flickable-элементе Silica с PageHeader, а \
+                     размеры, отступы, цвета и шрифты бери из Theme вместо фиксированных \
+                     значений.\n\
+                     - Сохрани поведение страницы и её публичные свойства.\n\n\
+                     Другие шаблоны ищи через find_snippet. После этого запусти lint_qml, \
+                     check_ui_guidelines и check_silica_api для проекта, исправь найденное \
+                     и предложи qml_preview_start, чтобы посмотреть результат на \
+                     устройстве."
+                ),
+                format!("Перевод {name} на Silica"),
+            ),
+        };
+        let mut messages = vec![PromptMessage::new_text(PromptMessageRole::User, text)];
+        messages.push(embed_file(&file)?);
+        messages.extend(embed_doc(self, "user-interface/silica-pages")?);
+        if let Some(snippet) = snippets::SNIPPETS
+            .iter()
+            .find(|snippet| snippet.id == "silica/flickable-page")
+        {
+            messages.push(embed_text(snippet.uri(), snippet.markdown()));
+        }
+        Ok(GetPromptResult {
+            description: Some(description),
+            messages,
+        })
+    }
+
+    #[prompt(
+        name = "write-rpm-spec",
+        description = "Write or update a project's RPM spec for a minimum Aurora OS release, license and app type: embeds a generated starting point, the current spec and the packaging page. The arguments complete from the SDK's releases, targets and known licenses. / Написание spec-файла RPM для минимальной версии Aurora OS, лицензии и типа приложения."
+    )]
+    pub async fn write_rpm_spec(
+        &self,
+        Parameters(args): Parameters<WriteRpmSpecArgs>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let language = self.prompt_language(args.language.as_deref())?;
+        if let Some(version) = &args.os_version {
+            OsVersion::parse(version)?;
+        }
+        let dir = self.state().config.sdk.project(args.project.as_deref())?;
+        let generated = self
+            .generate_spec(Parameters(GenerateSpecParams {
+                project: Some(dir.display().to_string()),
+                name: None,
+                summary: None,
+                version: None,
+                license: args.license.clone(),
+                write: false,
+                confirm: false,
+            }))
+            .await?
+            .0;
+        let name = &generated.project.name;
+        let spec_path = match &generated.project.spec_file {
+            Some(spec) => spec
+                .strip_prefix(&dir)
+                .unwrap_or(spec)
+                .display()
+                .to_string(),
+            None => format!("rpm/{name}.spec"),
+        };
+        let project = dir.display();
+        let mut lines = Vec::new();
+        let (description, skeleton_note) = match language {
+            Language::En => {
+                lines.push(format!(
+                    "Write {spec_path} for the Aurora OS project in {project}. A spec \
+                     generated from the project's sources follows as a starting point, with \
+                     the current spec if there is one and the packaging guide."
+                ));
+                if let Some(version) = &args.os_version {
+                    lines.push(format!(
+                        "- The package must install on Aurora OS {version} and later: require \
+                         only packages that release ships and check with check_compatibility \
+                         (min_os_version={version})."
+                    ));
+                }
+                if let Some(license) = &args.license {
+                    lines.push(format!(
+                        "- License: {license}. Run check_licenses to confirm bundled \
+                         third-party code allows it."
+                    ));
+                }
+                lines.push(match args.app_type {
+                    Some(kind) => format!(
+                        "- App type: {}. {}",
+                        kind.name(),
+                        app_type_hint(kind, language)
+                    ),
+                    None => "- Judge the app type from the sources: C++ with Silica QML, \
+                             QML only, Python through PyOtherSide, a library or a D-Bus \
+                             service."
+                        .to_string(),
+                });
+                lines.push(
+                    "- Keep the current spec's %changelog and any hand-written sections \
+                     that still apply.\n\n\
+                     Then run lint_spec and check_store_compliance, fix what they report, \
+                     and build_project to confirm the package builds. Show the final spec."
+                        .to_string(),
+                );
+                (format!("RPM spec for {name}"), "Generated starting point")
+            }
+            Language::Ru => {
+                lines.push(format!(
+                    "Напиши {spec_path} для проекта Aurora OS в {project}. Ниже приложены \
+                     spec-файл, сгенерированный по исходникам проекта, как отправная точка, \
+                     текущий spec-файл, если он есть, и руководство по упаковке."
+                ));
+                if let Some(version) = &args.os_version {
+                    lines.push(format!(
+                        "- Пакет должен устанавливаться на Aurora OS {version} и новее: \
+                         требуй только пакеты из этой версии и проверь через \
+                         check_compatibility (min_os_version={version})."
+                    ));
+                }
+                if let Some(license) = &args.license {
+                    lines.push(format!(
+                        "- Лицензия: {license}. Запусти check_licenses, чтобы убедиться, что \
+                         включённый сторонний код её допускает."
+                    ));
+                }
+                lines.push(match args.app_type {
+                    Some(kind) => format!(
+                        "- Тип приложения: {}. {}",
+                        kind.name(),
+                        app_type_hint(kind, language)
+                    ),
+                    None => "- Определи тип приложения по исходникам: C++ с QML на Silica, \
+                             только QML, Python через PyOtherSide, библиотека или служба \
+                             D-Bus."
+                        .to_string(),
+                });
+                lines.push(
+                    "- Сохрани %changelog текущего spec-файла и написанные вручную секции, \
+                     которые ещё актуальны.\n\n\
+                     Затем запусти lint_spec и check_store_compliance, исправь найденное и \
+                     выполни build_project, чтобы убедиться, что пакет собирается. Покажи \
+                     итоговый spec-файл."
+                        .to_string(),
+                );
+                (
+                    format!("Spec-файл RPM для {name}"),
+                    "Сгенерированная отправная точка",
+                )
+            }
+        };
+        // The generated spec is not a file yet, so it goes in as text
+        // rather than under the URI the real spec is embedded with.
+        let mut messages = vec![
+            PromptMessage::new_text(PromptMessageRole::User, lines.join("\n")),
+            PromptMessage::new_text(
+                PromptMessageRole::User,
+                format!("{skeleton_note}:\n\n```spec\n{}```", generated.spec),
+            ),
+        ];
+        if let Some(spec) = &generated.project.spec_file {
+            messages.push(embed_file(spec)?);
+        }
+        messages.extend(embed_doc(self, "packaging/rpm-spec")?);
+        if let Some(version) = &args.os_version
+            && let Some(notes) = releases::read(&self.state().config, version)?
+        {
+            messages.push(embed_text(releases::uri(version), notes));
+        }
+        Ok(GetPromptResult {
+            description: Some(description),
+            messages,
+        })
+    }
+}
+
+/// What a spec needs for an app of `kind`.
+fn app_type_hint(kind: ProjectKind, language: Language) -> &'static str {
+    match (kind, language) {
+        (ProjectKind::SilicaApp, Language::En) => {
+            "Build with qmake or CMake and require sailfishsilica-qt5 and the Qt modules the \
+             code uses."
+        }
+        (ProjectKind::SilicaApp, Language::Ru) => {
+            "Сборка через qmake или CMake, зависимости sailfishsilica-qt5 и используемые \
+             модули Qt."
+        }
+        (ProjectKind::QmlApp, Language::En) => {
+            "Nothing to compile: install the QML under %{_datadir}/%{name} and run it with \
+             the stock launcher; require sailfishsilica-qt5."
+        }
+        (ProjectKind::QmlApp, Language::Ru) => {
+            "Компилировать нечего: QML ставится в %{_datadir}/%{name} и запускается \
+             стандартным загрузчиком; зависимость sailfishsilica-qt5."
+        }
+        (ProjectKind::PythonApp, Language::En) => {
+            "BuildArch: noarch, require pyotherside-qml-plugin-python3-qt5 and python3 \
+             packages for the imports, byte-compile the sources. The store does not accept \
+             such apps."
+        }
+        (ProjectKind::PythonApp, Language::Ru) => {
+            "BuildArch: noarch, зависимости pyotherside-qml-plugin-python3-qt5 и пакеты \
+             python3 для импортов, байт-компиляция исходников. Магазин такие приложения не \
+             принимает."
+        }
+        (ProjectKind::Library, Language::En) => {
+            "Split headers and the .pc file into a -devel subpackage and run ldconfig in \
+             %post and %postun."
+        }
+        (ProjectKind::Library, Language::Ru) => {
+            "Заголовки и .pc-файл вынеси в подпакет -devel, в %post и %postun запускай \
+             ldconfig."
+        }
+        (ProjectKind::DbusService, Language::En) => {
+            "Package the D-Bus service file and the systemd user unit that activate it, and \
+             require dbus and systemd."
+        }
+        (ProjectKind::DbusService, Language::Ru) => {
+            "Включи в пакет файл службы D-Bus и пользовательский юнит systemd, которые её \
+             активируют; зависимости dbus и systemd."
+        }
+    }
+}
+
+impl AuroraServer {
+    /// Suggestions for `argument` of `prompt` starting with what was typed.
+    pub(crate) fn complete_prompt_argument(
+        &self,
+        prompt: &str,
+        argument: &ArgumentInfo,
+    ) -> CompletionInfo {
+        let config = &self.state().config;
+        let candidates: Vec<String> = match (prompt, argument.name.as_str()) {
+            (_, "language") => vec!["en".to_string(), "ru".to_string()],
+            ("triage-crash", "device") => self
+                .devices()
+                .list()
+                .iter()
+                .map(|device| device.name().to_string())
+                .collect(),
+            ("write-rpm-spec", "os_version") => {
+                // Installed targets first: those are what the project can
+                // be built for here.
+                let mut versions: Vec<String> = config
+                    .sdk
+                    .installed_targets()
+                    .iter()
+                    .filter_map(|target| target_os_version(target))
+                    .map(str::to_string)
+                    .collect();
+                versions.extend(
+                    releases::releases(config)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|release| release.version),
+                );
+                versions.sort_by_cached_key(|version| {
+                    std::cmp::Reverse(OsVersion::parse(version).ok())
+                });
+                versions.dedup();
+                versions
+            }
+            ("write-rpm-spec", "license") => licenses::spdx_ids(),
+            ("write-rpm-spec", "app_type") => ProjectKind::ALL
+                .iter()
+                .map(|kind| kind.name().to_string())
+                .collect(),
+            _ => Vec::new(),
+        };
+        let typed = argument.value.to_lowercase();
+        let values: Vec<String> = candidates
+            .into_iter()
+            .filter(|candidate| candidate.to_lowercase().starts_with(&typed))
+            .collect();
+        let total = values.len();
+        let values: Vec<String> = values
+            .into_iter()
+            .take(CompletionInfo::MAX_VALUES)
+            .collect();
+        CompletionInfo {
+            has_more: Some(total > values.len()),
+            total: u32::try_from(total).ok(),
+            values,
+        }
+    }
+}
+
+/// A text resource message.
+fn embed_text(uri: String, text: String) -> PromptMessage {
+    PromptMessage::new_resource(
+        PromptMessageRole::User,
+        uri,
+        Some("text/markdown".to_string()),
+        Some(text),
+        None,
+        None,
+        None,
+    )
+}
+
+/// A local file as a resource message.
+fn embed_file(path: &Path) -> Result<PromptMessage, ErrorData> {
+    let size = std::fs::metadata(path)
+        .map_err(|err| Error::InvalidArgument(format!("{}: {err}", path.display())))?
+        .len();
+    if size > MAX_EMBEDDED_BYTES {
+        return Err(Error::InvalidArgument(format!(
+            "{} is {size} bytes, too large to embed (at most {MAX_EMBEDDED_BYTES})",
+            path.display()
+        ))
+        .into());
+    }
+    let text = std::fs::read_to_string(path).map_err(Error::Io)?;
+    Ok(PromptMessage::new_resource(
+        PromptMessageRole::User,
+        format!("file://{}", absolute(path).display()),
+        Some("text/plain".to_string()),
+        Some(text),
+        None,
+        None,
+        None,
+    ))
+}
+
+/// The documentation page at `path` as a resource message, if there is one.
+fn embed_doc(server: &AuroraServer, path: &str) -> Result<Option<PromptMessage>, ErrorData> {
+    let root = docs::local_root(&server.state().config);
+    let text = docs::read(root.as_deref(), None, path)?;
+    Ok(text.map(|text| embed_text(docs::uri(None, path), text)))
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
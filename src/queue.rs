@@ -0,0 +1,159 @@
+//! Admission of tool calls. At most `max_concurrent` calls run at once;
+//! past that, up to `queue_depth` more wait for a slot in arrival order,
+//! and further calls are turned away at once with a [`SERVER_BUSY`] error
+//! saying when to retry, rather than piling up behind the others.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rmcp::ErrorData;
+use rmcp::model::ErrorCode;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// JSON-RPC error code of a call turned away because the queue is full;
+/// its `data` holds `retry_after_ms`.
+pub const SERVER_BUSY: ErrorCode = ErrorCode(-32001);
+
+/// Bounds of the retry hint.
+const MIN_RETRY_AFTER: Duration = Duration::from_secs(1);
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Weight of the latest call in the running mean of call durations.
+const DURATION_WEIGHT: f64 = 0.2;
+
+/// Limits on running tool calls, from the `[calls]` config table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CallOptions {
+    /// Tool calls running at once, across sessions and transports; 0 for
+    /// no limit.
+    pub max_concurrent: usize,
+    /// Calls waiting for a slot before more are refused.
+    pub queue_depth: usize,
+}
+
+impl Default for CallOptions {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 32,
+            queue_depth: 64,
+        }
+    }
+}
+
+/// The slots of running calls and the calls waiting for one.
+#[derive(Debug, Clone, Default)]
+pub struct CallQueue {
+    inner: Option<Arc<Inner>>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    slots: Arc<Semaphore>,
+    max_concurrent: usize,
+    queue_depth: usize,
+    queued: AtomicUsize,
+    /// Running mean of call durations; zero before the first.
+    mean: Mutex<Duration>,
+}
+
+/// A running call's slot, freed when dropped.
+#[derive(Debug)]
+pub struct Slot {
+    queue: Option<Arc<Inner>>,
+    _permit: Option<OwnedSemaphorePermit>,
+    started: Instant,
+}
+
+impl CallQueue {
+    pub fn new(options: &CallOptions) -> Self {
+        let inner = (options.max_concurrent > 0).then(|| {
+            Arc::new(Inner {
+                slots: Arc::new(Semaphore::new(options.max_concurrent)),
+                max_concurrent: options.max_concurrent,
+                queue_depth: options.queue_depth,
+                queued: AtomicUsize::new(0),
+                mean: Mutex::new(Duration::ZERO),
+            })
+        });
+        Self { inner }
+    }
+
+    /// Takes a slot, waiting in the queue if all are in use. Fails with
+    /// [`SERVER_BUSY`] when the queue is full too.
+    pub async fn enter(&self) -> Result<Slot, ErrorData> {
+        let Some(inner) = &self.inner else {
+            return Ok(Slot {
+                queue: None,
+                _permit: None,
+                started: Instant::now(),
+            });
+        };
+        let permit = match inner.slots.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                let queued = inner.queued.fetch_add(1, Ordering::AcqRel);
+                if queued >= inner.queue_depth {
+                    inner.queued.fetch_sub(1, Ordering::AcqRel);
+                    return Err(inner.busy(queued));
+                }
+                let permit = inner.slots.clone().acquire_owned().await;
+                inner.queued.fetch_sub(1, Ordering::AcqRel);
+                permit.expect("the call semaphore is never closed")
+            }
+        };
+        Ok(Slot {
+            queue: Some(inner.clone()),
+            _permit: Some(permit),
+            started: Instant::now(),
+        })
+    }
+}
+
+impl Inner {
+    fn busy(&self, queued: usize) -> ErrorData {
+        let retry_after = self.retry_after(queued);
+        let seconds = retry_after.as_secs_f64().ceil() as u64;
+        ErrorData::new(
+            SERVER_BUSY,
+            format!(
+                "server busy: {} tool calls running and {queued} queued; retry after {seconds}s",
+                self.max_concurrent
+            ),
+            Some(json!({
+                "retry_after_ms": retry_after.as_millis() as u64,
+                "running": self.max_concurrent,
+                "queued": queued,
+            })),
+        )
+    }
+
+    /// About when a slot frees up for a call arriving behind `queued`
+    /// others: as many rounds of the running calls, at their mean duration.
+    fn retry_after(&self, queued: usize) -> Duration {
+        let mean = *self.mean.lock().unwrap_or_else(|e| e.into_inner());
+        let rounds = (queued / self.max_concurrent + 1) as u32;
+        mean.saturating_mul(rounds)
+            .clamp(MIN_RETRY_AFTER, MAX_RETRY_AFTER)
+    }
+
+    fn record(&self, duration: Duration) {
+        let mut mean = self.mean.lock().unwrap_or_else(|e| e.into_inner());
+        *mean = if mean.is_zero() {
+            duration
+        } else {
+            mean.mul_f64(1.0 - DURATION_WEIGHT) + duration.mul_f64(DURATION_WEIGHT)
+        };
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        if let Some(queue) = &self.queue {
+            queue.record(self.started.elapsed());
+        }
+    }
+}
@@ -0,0 +1,396 @@
+//! Session recordings: with `--record <dir>`, every JSON-RPC message of a
+//! session, in either direction, is appended to a JSON Lines file in
+//! `dir`, one file per session. `aurora-mcp replay <file>` feeds the
+//! client's messages of such a file to a fresh session in the recorded
+//! order, so a session a user reports can be reproduced against any build.
+//!
+//! Each line is `{"ms": <since session start>, "from": "client" | "server",
+//! "message": <JSON-RPC message>}`.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use futures::Stream;
+use rmcp::RoleServer;
+use rmcp::model::{ClientJsonRpcMessage, ServerJsonRpcMessage};
+use rmcp::service::{RxJsonRpcMessage, TxJsonRpcMessage};
+use rmcp::transport::Transport;
+use rmcp::transport::common::server_side_http::ServerSseMessage;
+use rmcp::transport::streamable_http_server::{SessionId, SessionManager};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+use crate::error::{Error, Result};
+use crate::local;
+use crate::server::AuroraServer;
+
+/// Which end of the session sent a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Client,
+    Server,
+}
+
+/// One line of a recording.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry<M = Value> {
+    pub ms: u64,
+    pub from: Side,
+    pub message: M,
+}
+
+/// An open recording file.
+#[derive(Debug, Clone)]
+struct Recording {
+    file: Arc<Mutex<File>>,
+    started: Instant,
+}
+
+impl Recording {
+    /// Creates a new, uniquely named file in `dir`.
+    fn create(dir: &Path) -> std::io::Result<Self> {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        let unix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let name = format!(
+            "{unix}-{}-{}.jsonl",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = dir.join(name);
+        let file = File::options().create_new(true).append(true).open(&path)?;
+        tracing::info!("recording session to {}", path.display());
+        Ok(Self {
+            file: Arc::new(Mutex::new(file)),
+            started: Instant::now(),
+        })
+    }
+
+    fn write(&self, from: Side, message: &impl Serialize) {
+        let entry = Entry {
+            ms: self.started.elapsed().as_millis() as u64,
+            from,
+            message,
+        };
+        let mut line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(err) => {
+                tracing::warn!("recording: {err}");
+                return;
+            }
+        };
+        line.push('\n');
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(err) = file.write_all(line.as_bytes()) {
+            tracing::warn!("recording: {err}");
+        }
+    }
+}
+
+/// A server transport that records what passes through it.
+pub struct Recorded<T> {
+    inner: T,
+    recording: Option<Recording>,
+}
+
+impl<T> Recorded<T> {
+    /// Records `inner` to a new file in `dir`. If the file cannot be
+    /// created the session goes unrecorded rather than failing.
+    pub fn new(inner: T, dir: &Path) -> Self {
+        let recording = Recording::create(dir)
+            .inspect_err(|err| {
+                tracing::warn!("cannot record session in {}: {err}", dir.display());
+            })
+            .ok();
+        Self { inner, recording }
+    }
+}
+
+impl<T: Transport<RoleServer>> Transport<RoleServer> for Recorded<T> {
+    type Error = T::Error;
+
+    fn send(
+        &mut self,
+        item: TxJsonRpcMessage<RoleServer>,
+    ) -> impl Future<Output = std::result::Result<(), Self::Error>> + Send + 'static {
+        if let Some(recording) = &self.recording {
+            recording.write(Side::Server, &item);
+        }
+        self.inner.send(item)
+    }
+
+    async fn receive(&mut self) -> Option<RxJsonRpcMessage<RoleServer>> {
+        let message = self.inner.receive().await;
+        if let (Some(recording), Some(message)) = (&self.recording, &message) {
+            recording.write(Side::Client, message);
+        }
+        message
+    }
+
+    fn close(&mut self) -> impl Future<Output = std::result::Result<(), Self::Error>> + Send {
+        self.inner.close()
+    }
+}
+
+/// Session manager of the HTTP transport that records every session of
+/// the one it wraps.
+#[derive(Debug)]
+pub struct RecordingSessions<M> {
+    inner: M,
+    dir: PathBuf,
+}
+
+impl<M> RecordingSessions<M> {
+    pub fn new(inner: M, dir: PathBuf) -> Self {
+        Self { inner, dir }
+    }
+}
+
+impl<M: SessionManager> SessionManager for RecordingSessions<M> {
+    type Error = M::Error;
+    type Transport = Recorded<M::Transport>;
+
+    async fn create_session(
+        &self,
+    ) -> std::result::Result<(SessionId, Self::Transport), Self::Error> {
+        let (id, transport) = self.inner.create_session().await?;
+        Ok((id, Recorded::new(transport, &self.dir)))
+    }
+
+    fn initialize_session(
+        &self,
+        id: &SessionId,
+        message: ClientJsonRpcMessage,
+    ) -> impl Future<Output = std::result::Result<ServerJsonRpcMessage, Self::Error>> + Send {
+        self.inner.initialize_session(id, message)
+    }
+
+    fn has_session(
+        &self,
+        id: &SessionId,
+    ) -> impl Future<Output = std::result::Result<bool, Self::Error>> + Send {
+        self.inner.has_session(id)
+    }
+
+    fn close_session(
+        &self,
+        id: &SessionId,
+    ) -> impl Future<Output = std::result::Result<(), Self::Error>> + Send {
+        self.inner.close_session(id)
+    }
+
+    fn create_stream(
+        &self,
+        id: &SessionId,
+        message: ClientJsonRpcMessage,
+    ) -> impl Future<
+        Output = std::result::Result<
+            impl Stream<Item = ServerSseMessage> + Send + Sync + 'static,
+            Self::Error,
+        >,
+    > + Send {
+        self.inner.create_stream(id, message)
+    }
+
+    fn accept_message(
+        &self,
+        id: &SessionId,
+        message: ClientJsonRpcMessage,
+    ) -> impl Future<Output = std::result::Result<(), Self::Error>> + Send {
+        self.inner.accept_message(id, message)
+    }
+
+    fn create_standalone_stream(
+        &self,
+        id: &SessionId,
+    ) -> impl Future<
+        Output = std::result::Result<
+            impl Stream<Item = ServerSseMessage> + Send + Sync + 'static,
+            Self::Error,
+        >,
+    > + Send {
+        self.inner.create_standalone_stream(id)
+    }
+
+    fn resume(
+        &self,
+        id: &SessionId,
+        last_event_id: String,
+    ) -> impl Future<
+        Output = std::result::Result<
+            impl Stream<Item = ServerSseMessage> + Send + Sync + 'static,
+            Self::Error,
+        >,
+    > + Send {
+        self.inner.resume(id, last_event_id)
+    }
+}
+
+/// Reads a recording.
+pub fn read(path: &Path) -> Result<Vec<Entry>> {
+    let file = File::open(path)?;
+    let mut entries = Vec::new();
+    for (n, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).map_err(|err| {
+            Error::InvalidArgument(format!("{} line {}: {err}", path.display(), n + 1))
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// What a replay did.
+#[derive(Debug, Default)]
+pub struct Replay {
+    /// Client messages sent.
+    pub sent: usize,
+    /// Server responses compared with the recorded ones.
+    pub compared: usize,
+    /// Ids of the responses whose result or error differs from the
+    /// recording.
+    pub differing: Vec<Value>,
+}
+
+/// Replays the client side of `entries` against a new session of `server`
+/// and writes the session's messages to `out` as recording lines.
+///
+/// Messages go out in the recorded order. Before a message that the
+/// recording shows the server answering first, the replay waits up to
+/// `timeout` for the server's answer; a client response to a server
+/// request waits for that request.
+pub async fn replay(
+    server: &AuroraServer,
+    entries: &[Entry],
+    timeout: Duration,
+    out: impl Write,
+) -> Result<Replay> {
+    let (read, mut write) = tokio::io::split(local::open(server));
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut lines = tokio::io::BufReader::new(read).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            match serde_json::from_str::<Value>(&line) {
+                Ok(message) => {
+                    if tx.send(message).is_err() {
+                        break;
+                    }
+                }
+                Err(err) => tracing::debug!("replay: unparseable server message: {err}"),
+            }
+        }
+    });
+    let mut session = Session {
+        rx,
+        unmatched: Vec::new(),
+        out,
+        started: Instant::now(),
+        timeout,
+    };
+    let mut replay = Replay::default();
+    for entry in entries {
+        let message = &entry.message;
+        match entry.from {
+            Side::Client => {
+                if is_response(message) {
+                    session
+                        .wait_for(&message["id"], |reply| !is_response(reply))
+                        .await?;
+                }
+                let mut line = message.to_string();
+                line.push('\n');
+                write.write_all(line.as_bytes()).await?;
+                session.log(Side::Client, message)?;
+                replay.sent += 1;
+            }
+            Side::Server if is_response(message) => {
+                let reply = session.wait_for(&message["id"], is_response).await?;
+                replay.compared += 1;
+                if reply.get("result") != message.get("result")
+                    || reply.get("error") != message.get("error")
+                {
+                    replay.differing.push(message["id"].clone());
+                }
+            }
+            Side::Server => {}
+        }
+    }
+    Ok(replay)
+}
+
+/// The replay's side of the session.
+struct Session<W> {
+    rx: mpsc::UnboundedReceiver<Value>,
+    /// Server requests and responses not waited for yet.
+    unmatched: Vec<Value>,
+    out: W,
+    started: Instant,
+    timeout: Duration,
+}
+
+impl<W: Write> Session<W> {
+    /// The server message with `id` that passes `kind`, waiting for it if
+    /// it has not come yet.
+    async fn wait_for(&mut self, id: &Value, kind: fn(&Value) -> bool) -> Result<Value> {
+        let matches = |message: &Value| message.get("id") == Some(id) && kind(message);
+        if let Some(at) = self.unmatched.iter().position(matches) {
+            return Ok(self.unmatched.remove(at));
+        }
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        loop {
+            let message = match tokio::time::timeout_at(deadline, self.rx.recv()).await {
+                Ok(Some(message)) => message,
+                Ok(None) => {
+                    return Err(Error::Io(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        format!("the session ended before message {id}"),
+                    )));
+                }
+                Err(_) => {
+                    return Err(Error::Io(std::io::Error::new(
+                        std::io::ErrorKind::TimedOut,
+                        format!(
+                            "no message {id} from the server within {}s",
+                            self.timeout.as_secs()
+                        ),
+                    )));
+                }
+            };
+            self.log(Side::Server, &message)?;
+            if matches(&message) {
+                return Ok(message);
+            }
+            if message.get("id").is_some() {
+                self.unmatched.push(message);
+            }
+        }
+    }
+
+    fn log(&mut self, from: Side, message: &Value) -> Result<()> {
+        let entry = Entry {
+            ms: self.started.elapsed().as_millis() as u64,
+            from,
+            message,
+        };
+        let line = serde_json::to_string(&entry).map_err(std::io::Error::other)?;
+        writeln!(self.out, "{line}")?;
+        Ok(())
+    }
+}
+
+/// A JSON-RPC response or error, as opposed to a request or notification.
+fn is_response(message: &Value) -> bool {
+    message.get("method").is_none()
+}
@@ -0,0 +1,819 @@
+//! MCP resources: listing, reading and subscriptions, plus helpers for
+//! referencing server-side artifacts from tool results. Rendered Markdown
+//! resources are cached until what they are rendered from changes, and
+//! carry an etag a client can revalidate with.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD as BASE64_URL};
+use rmcp::model::{
+    AnnotateAble, Content, ListResourcesResult, Meta, RawResource, RawResourceTemplate,
+    ReadResourceResult, Resource, ResourceContents, ResourceTemplate,
+    ResourceUpdatedNotificationParam,
+};
+use rmcp::{ErrorData, Peer, RoleServer};
+use serde::{Deserialize, Serialize};
+use tokio::task::AbortHandle;
+
+use crate::device::files::{self, ByteRange, FileContent};
+use crate::device::{Device, battery};
+use crate::docs::dbus::{self, DbusUri};
+use crate::docs::{self, snippets};
+use crate::sdk::releases;
+use crate::server::AuroraServer;
+use crate::workspace::{self, WorkspaceContent};
+
+/// A `resource_link` content item pointing at a local file.
+pub fn file_link(path: &Path, mime_type: Option<&str>) -> Content {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+    let size = std::fs::metadata(path)
+        .ok()
+        .and_then(|meta| u32::try_from(meta.len()).ok());
+    Content::resource_link(RawResource {
+        uri: workspace::uri(path),
+        name,
+        title: None,
+        description: None,
+        mime_type: mime_type.map(str::to_string),
+        size,
+        icons: None,
+    })
+}
+
+/// Directories `server` may serve as `file://` resources to the client
+/// behind `peer`.
+async fn workspace_roots(
+    server: &AuroraServer,
+    peer: &Peer<RoleServer>,
+) -> Result<Vec<std::path::PathBuf>, ErrorData> {
+    let client = server.client_roots().get(peer).await?;
+    Ok(workspace::roots(&server.state().config, client.as_deref()))
+}
+
+/// Concrete resources for the configured devices, the workspace and the
+/// documentation, each with the tags `resources/list` filters on.
+pub async fn list(
+    server: &AuroraServer,
+    peer: &Peer<RoleServer>,
+) -> Result<Vec<(Resource, Vec<String>)>, ErrorData> {
+    let artifacts = server.state().config.data_dir().canonicalize().ok();
+    let workspace = workspace_roots(server, peer)
+        .await?
+        .into_iter()
+        .map(|root| {
+            let (tag, name, title, description) = if artifacts.as_ref() == Some(&root) {
+                (
+                    "artifacts",
+                    "artifacts".to_string(),
+                    "Server artifacts".to_string(),
+                    "Screenshots, crash bundles, profiles and other files written by tools",
+                )
+            } else {
+                let name = root
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| root.display().to_string());
+                (
+                    "workspace",
+                    format!("workspace:{name}"),
+                    format!("Workspace {}", root.display()),
+                    "Project directory listing; add ?ext=qml,cpp&recursive=true to list \
+                     matching files of all subdirectories",
+                )
+            };
+            let resource = RawResource {
+                uri: workspace::uri(&root),
+                name,
+                title: Some(title),
+                description: Some(description.to_string()),
+                mime_type: Some("application/json".to_string()),
+                size: None,
+                icons: None,
+            };
+            (resource.no_annotation(), vec![tag.to_string()])
+        });
+    let devices = server.devices().list();
+    let battery = devices.iter().map(|device| {
+        let resource = RawResource {
+            uri: battery::uri(device.name()),
+            name: format!("{}-battery", device.name()),
+            title: Some(format!("Battery of {}", device.name())),
+            description: Some(
+                "Battery level, charging state and temperatures; subscribe for updates".to_string(),
+            ),
+            mime_type: Some("application/json".to_string()),
+            size: None,
+            icons: None,
+        };
+        let tags = ["device", "battery", device.name()];
+        (resource.no_annotation(), tags.map(str::to_string).to_vec())
+    });
+    let roots = devices.iter().flat_map(|device| {
+        server
+            .state()
+            .config
+            .files
+            .roots(device)
+            .into_iter()
+            .map(|root| {
+                let resource = RawResource {
+                    uri: files::uri(device.name(), &root),
+                    name: format!("{}:{root}", device.name()),
+                    title: Some(format!("{root} on {}", device.name())),
+                    description: Some(
+                        "Directory listing with links to files and subdirectories".to_string(),
+                    ),
+                    mime_type: Some("application/json".to_string()),
+                    size: None,
+                    icons: None,
+                };
+                let tags = ["device", "files", device.name()];
+                (resource.no_annotation(), tags.map(str::to_string).to_vec())
+            })
+    });
+    let root = docs::local_root(&server.state().config);
+    let pages = docs::pages(root.as_deref());
+    let topics = docs::topics(&pages).into_iter().map(|topic| {
+        let resource = RawResource {
+            uri: docs::uri(None, &topic),
+            name: format!("docs:{topic}"),
+            title: Some(docs::topic_title(&topic)),
+            description: Some("Index of the documentation pages on this topic".to_string()),
+            mime_type: Some("text/markdown".to_string()),
+            size: None,
+            icons: None,
+        };
+        (resource.no_annotation(), doc_tags(&topic))
+    });
+    let pages = pages.into_iter().map(|page| {
+        let tags = doc_tags(&page.path);
+        let resource = RawResource {
+            uri: page.uri(None),
+            name: format!("docs:{}", page.path),
+            title: Some(page.title),
+            description: Some("Aurora OS developer documentation".to_string()),
+            mime_type: Some("text/markdown".to_string()),
+            size: None,
+            icons: None,
+        };
+        (resource.no_annotation(), tags)
+    });
+    let snippets = snippets::SNIPPETS.iter().map(|snippet| {
+        let resource = RawResource {
+            uri: snippet.uri(),
+            name: format!("snippet:{}", snippet.id),
+            title: Some(snippet.title.to_string()),
+            description: Some(snippet.description.to_string()),
+            mime_type: Some("text/markdown".to_string()),
+            size: None,
+            icons: None,
+        };
+        let tags = ["snippet", snippet.language]
+            .iter()
+            .chain(snippet.tags)
+            .map(|tag| tag.to_string())
+            .collect();
+        (resource.no_annotation(), tags)
+    });
+    // Without a build target the index is left out; reading it says why.
+    let dbus_index = dbus::sdk_target(&server.state().config)
+        .ok()
+        .map(|(target, _)| {
+            let resource = RawResource {
+                uri: dbus::sdk_uri(""),
+                name: "dbus:sdk".to_string(),
+                title: Some(format!("D-Bus interfaces of {target}")),
+                description: Some(
+                    "Reference of the platform D-Bus interfaces the build target ships".to_string(),
+                ),
+                mime_type: Some("text/markdown".to_string()),
+                size: None,
+                icons: None,
+            };
+            let tags = ["dbus", "docs"];
+            (resource.no_annotation(), tags.map(str::to_string).to_vec())
+        });
+    // A broken `compat_database` is reported by the tools using it; the
+    // listing just leaves the releases out.
+    let releases = releases::releases(&server.state().config)
+        .unwrap_or_default()
+        .into_iter()
+        .rev()
+        .map(|release| {
+            let resource = RawResource {
+                uri: releases::uri(&release.version),
+                name: format!("release:{}", release.version),
+                title: Some(format!("Aurora OS {} release notes", release.version)),
+                description: Some(format!(
+                    "Qt {}, Silica {}, and the platform API changes of the release",
+                    release.qt, release.silica
+                )),
+                mime_type: Some("text/markdown".to_string()),
+                size: None,
+                icons: None,
+            };
+            (resource.no_annotation(), vec!["release".to_string()])
+        });
+    Ok(workspace
+        .chain(battery)
+        .chain(roots)
+        .chain(topics)
+        .chain(pages)
+        .chain(snippets)
+        .chain(dbus_index)
+        .chain(releases)
+        .collect())
+}
+
+/// Tags of a documentation page or topic: `docs` and each part of its path.
+fn doc_tags(path: &str) -> Vec<String> {
+    std::iter::once("docs")
+        .chain(path.split('/'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// How many resources one `resources/list` page holds.
+const PAGE_SIZE: usize = 200;
+
+/// Narrows `resources/list`, from the `filter` object of the request's
+/// `_meta`: `{"filter": {"prefix": "aurora-doc://packaging/", "tag": "dbus"}}`.
+/// The cursor of the next page carries it on.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct ListFilter {
+    /// Only URIs starting with this.
+    pub prefix: Option<String>,
+    /// Only resources of this MIME type.
+    pub mime_type: Option<String>,
+    /// Only resources with this tag: `docs` or a documentation topic,
+    /// `snippet` or a snippet tag, `dbus`, `release`, `device`, a device name,
+    /// `workspace` or `artifacts`.
+    pub tag: Option<String>,
+}
+
+impl ListFilter {
+    fn matches(&self, resource: &Resource, tags: &[String]) -> bool {
+        self.prefix
+            .as_ref()
+            .is_none_or(|prefix| resource.uri.starts_with(prefix.as_str()))
+            && self
+                .mime_type
+                .as_ref()
+                .is_none_or(|mime| resource.mime_type.as_ref() == Some(mime))
+            && self
+                .tag
+                .as_ref()
+                .is_none_or(|tag| tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+    }
+}
+
+/// Where a `resources/list` page starts, and the filter it applies.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Cursor {
+    offset: usize,
+    #[serde(flatten)]
+    filter: ListFilter,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        BASE64_URL.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    fn decode(cursor: &str) -> Option<Self> {
+        serde_json::from_slice(&BASE64_URL.decode(cursor).ok()?).ok()
+    }
+}
+
+/// One page of the resources `list` finds, narrowed by the filter in
+/// `meta` or continuing from `cursor`.
+pub async fn list_page(
+    server: &AuroraServer,
+    peer: &Peer<RoleServer>,
+    meta: &Meta,
+    cursor: Option<&str>,
+) -> Result<ListResourcesResult, ErrorData> {
+    let cursor = match cursor {
+        Some(cursor) => Cursor::decode(cursor)
+            .ok_or_else(|| ErrorData::invalid_params(format!("bad cursor '{cursor}'"), None))?,
+        None => Cursor {
+            offset: 0,
+            filter: match meta.get("filter") {
+                Some(filter) => serde_json::from_value(filter.clone()).map_err(|err| {
+                    ErrorData::invalid_params(format!("bad resources/list filter: {err}"), None)
+                })?,
+                None => ListFilter::default(),
+            },
+        },
+    };
+    let mut resources: Vec<Resource> = list(server, peer)
+        .await?
+        .into_iter()
+        .filter(|(resource, tags)| cursor.filter.matches(resource, tags))
+        .map(|(resource, _)| resource)
+        .skip(cursor.offset)
+        .take(PAGE_SIZE + 1)
+        .collect();
+    let next_cursor = (resources.len() > PAGE_SIZE).then(|| {
+        resources.truncate(PAGE_SIZE);
+        Cursor {
+            offset: cursor.offset + PAGE_SIZE,
+            filter: cursor.filter,
+        }
+        .encode()
+    });
+    Ok(ListResourcesResult {
+        resources,
+        next_cursor,
+    })
+}
+
+pub fn templates() -> Vec<ResourceTemplate> {
+    vec![
+        RawResourceTemplate {
+            uri_template: format!("{}{{device}}", battery::URI_SCHEME),
+            name: "device-battery".to_string(),
+            title: Some("Device battery".to_string()),
+            description: Some(
+                "Battery level, charging state and temperatures of a device".to_string(),
+            ),
+            mime_type: Some("application/json".to_string()),
+        }
+        .no_annotation(),
+        RawResourceTemplate {
+            uri_template: format!("{}{{device}}/{{+path}}", files::URI_SCHEME),
+            name: "device-file".to_string(),
+            title: Some("Device file".to_string()),
+            description: Some(
+                "A file or directory on a device, within the browsable roots of the \
+                 server config (home, /usr/share and /var/log by default). Large files \
+                 are read in chunks: add ?offset=<byte>&length=<bytes>, and follow the \
+                 `next` URI in the result's _meta."
+                    .to_string(),
+            ),
+            mime_type: None,
+        }
+        .no_annotation(),
+        RawResourceTemplate {
+            uri_template: format!("{}{{+path}}", workspace::URI_SCHEME),
+            name: "workspace-file".to_string(),
+            title: Some("Workspace file".to_string()),
+            description: Some(
+                "A file or directory of the active project or a server artifact, by \
+                 absolute path, within the client's roots and the [workspace] roots of \
+                 the server config. Directory listings take ?ext=qml,cpp (extensions), \
+                 ?max_size=<bytes> and ?recursive=true. Binary files are base64 blobs; \
+                 large files are read in chunks: add ?offset=<byte>&length=<bytes>, and \
+                 follow the `next` URI in the result's _meta."
+                    .to_string(),
+            ),
+            mime_type: None,
+        }
+        .no_annotation(),
+        RawResourceTemplate {
+            uri_template: format!("{}{{+path}}", docs::URI_SCHEME),
+            name: "aurora-doc".to_string(),
+            title: Some("Aurora OS documentation".to_string()),
+            description: Some(
+                "A documentation page or topic as Markdown, e.g. packaging/desktop-file; \
+                 a topic reads as an index of its pages and the root lists every topic"
+                    .to_string(),
+            ),
+            mime_type: Some("text/markdown".to_string()),
+        }
+        .no_annotation(),
+        RawResourceTemplate {
+            uri_template: format!("{}{{version}}/{{+path}}", docs::URI_SCHEME),
+            name: "aurora-doc-versioned".to_string(),
+            title: Some("Aurora OS documentation for a release".to_string()),
+            description: Some(
+                "A documentation page or topic as Markdown for a specific OS release, \
+                 e.g. 4.0/packaging/desktop-file, from that release's offline bundle; \
+                 the bundle is downloaded if it is not installed"
+                    .to_string(),
+            ),
+            mime_type: Some("text/markdown".to_string()),
+        }
+        .no_annotation(),
+        RawResourceTemplate {
+            uri_template: format!("{}{{+id}}", snippets::URI_SCHEME),
+            name: "aurora-snippet".to_string(),
+            title: Some("Code snippet".to_string()),
+            description: Some(
+                "A vetted code snippet as Markdown, e.g. dbus/cpp-adaptor; an area such \
+                 as silica, or the root, reads as an index of snippets by tag"
+                    .to_string(),
+            ),
+            mime_type: Some("text/markdown".to_string()),
+        }
+        .no_annotation(),
+        RawResourceTemplate {
+            uri_template: format!("{}sdk/{{interface}}", dbus::URI_SCHEME),
+            name: "dbus-interface".to_string(),
+            title: Some("D-Bus interface reference".to_string()),
+            description: Some(
+                "A platform D-Bus interface as Markdown, e.g. org.nemo.ssu, from the \
+                 introspection XML in the build target's sysroot; the root lists every \
+                 interface"
+                    .to_string(),
+            ),
+            mime_type: Some("text/markdown".to_string()),
+        }
+        .no_annotation(),
+        RawResourceTemplate {
+            uri_template: format!(
+                "{}device/{{device}}/{{bus}}/{{service}}/{{+path}}",
+                dbus::URI_SCHEME
+            ),
+            name: "dbus-object".to_string(),
+            title: Some("D-Bus object on a device".to_string()),
+            description: Some(
+                "What a D-Bus object exposes, introspected live and rendered as Markdown, \
+                 e.g. mydevice/system/net.connman/ with bus session or system; without \
+                 a service, lists the services on the bus"
+                    .to_string(),
+            ),
+            mime_type: Some("text/markdown".to_string()),
+        }
+        .no_annotation(),
+        RawResourceTemplate {
+            uri_template: format!("{}{{version}}", releases::URI_SCHEME),
+            name: "aurora-release".to_string(),
+            title: Some("Aurora OS release notes".to_string()),
+            description: Some(
+                "Release notes of an OS release, e.g. 5.0, with the platform APIs added, \
+                 removed and changed since the previous one; the root lists every release"
+                    .to_string(),
+            ),
+            mime_type: Some("text/markdown".to_string()),
+        }
+        .no_annotation(),
+    ]
+}
+
+/// The contents of `uri`. Markdown resources carry an `etag` in their
+/// `_meta`; when the request's `_meta` has it as `ifNoneMatch`, the text
+/// is left out and `notModified` set instead.
+pub async fn read(
+    server: &AuroraServer,
+    peer: &Peer<RoleServer>,
+    meta: &Meta,
+    uri: &str,
+) -> Result<ReadResourceResult, ErrorData> {
+    if let Some((path, filter)) = workspace::parse_uri(uri) {
+        let roots = workspace_roots(server, peer).await?;
+        let options = &server.state().config.workspace;
+        let Some(content) = workspace::read(options, &roots, &path, &filter)? else {
+            return Err(not_found(uri));
+        };
+        let contents = match content {
+            WorkspaceContent::Directory { entries, truncated } => {
+                let listing = serde_json::json!({
+                    "path": path,
+                    "entries": entries,
+                    "truncated": truncated,
+                });
+                let text = serde_json::to_string_pretty(&listing)
+                    .map_err(|err| ErrorData::internal_error(err.to_string(), None))?;
+                ResourceContents::TextResourceContents {
+                    uri: uri.to_string(),
+                    mime_type: Some("application/json".to_string()),
+                    text,
+                    meta: None,
+                }
+            }
+            WorkspaceContent::File {
+                data,
+                offset,
+                size,
+                truncated,
+            } => file_contents(uri, &path.to_string_lossy(), data, offset, size, truncated),
+        };
+        return Ok(ReadResourceResult {
+            contents: vec![contents],
+        });
+    }
+    if let Some(doc) = docs::parse_uri(uri) {
+        let root = match doc.version {
+            Some(version) => Some(docs::bundle::versioned_root(server, version).await?),
+            None => docs::local_root(&server.state().config),
+        };
+        let revision = docs::revision(root.as_deref(), doc.version);
+        let render = async { docs::read(root.as_deref(), doc.version, doc.path) };
+        return rendered(server, uri, meta, Some(revision), render).await;
+    }
+    if let Some(id) = snippets::parse_uri(uri) {
+        return rendered(server, uri, meta, Some(0), async { Ok(snippets::read(id)) }).await;
+    }
+    if let Some(dbus_uri) = dbus::parse_uri(uri) {
+        // Live introspection is rendered on every read; without a build
+        // target, reading reports why.
+        let revision = match &dbus_uri {
+            DbusUri::Sdk(_) => dbus::sdk_revision(&server.state().config).ok(),
+            DbusUri::Device { .. } => None,
+        };
+        let render = dbus::read(server, dbus_uri);
+        return rendered(server, uri, meta, revision, render).await;
+    }
+    if let Some(version) = releases::parse_uri(uri) {
+        let config = &server.state().config;
+        let revision = releases::revision(config);
+        let render = async { releases::read(config, version) };
+        return rendered(server, uri, meta, Some(revision), render).await;
+    }
+    if let Some((name, path, range)) = files::parse_uri(uri) {
+        let device = server.devices().get(name)?;
+        return read_file(server, &device, uri, &path, range).await;
+    }
+    let Some(name) = battery::parse_uri(uri) else {
+        return Err(not_found(uri));
+    };
+    let device = server.devices().get(name)?;
+    let status = battery::read(&device).await?;
+    let text = serde_json::to_string_pretty(&status)
+        .map_err(|err| ErrorData::internal_error(err.to_string(), None))?;
+    Ok(ReadResourceResult {
+        contents: vec![ResourceContents::TextResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some("application/json".to_string()),
+            text,
+            meta: None,
+        }],
+    })
+}
+
+/// How many rendered resources a `RenderCache` keeps.
+const RENDER_CACHE_ENTRIES: usize = 512;
+
+/// Rendered Markdown of documentation, snippet, D-Bus reference and release
+/// notes resources by URI, with the revision of what it was rendered from,
+/// so reading an unchanged resource again does not render it again.
+#[derive(Debug, Clone, Default)]
+pub struct RenderCache {
+    entries: Arc<Mutex<HashMap<String, Arc<Rendered>>>>,
+}
+
+#[derive(Debug)]
+struct Rendered {
+    /// `None` for a resource that is rendered on every read.
+    revision: Option<u64>,
+    /// Hash of the text.
+    etag: String,
+    text: String,
+}
+
+impl RenderCache {
+    fn get(&self, uri: &str, revision: u64) -> Option<Arc<Rendered>> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(uri)
+            .filter(|rendered| rendered.revision == Some(revision))
+            .cloned()
+    }
+
+    /// Records `text` as `uri` rendered at `revision`, if it has one.
+    fn insert(&self, uri: &str, revision: Option<u64>, text: String) -> Arc<Rendered> {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let rendered = Arc::new(Rendered {
+            revision,
+            etag: format!("{:016x}", hasher.finish()),
+            text,
+        });
+        if revision.is_some() {
+            let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+            if entries.len() >= RENDER_CACHE_ENTRIES && !entries.contains_key(uri) {
+                entries.clear();
+            }
+            entries.insert(uri.to_string(), rendered.clone());
+        }
+        rendered
+    }
+}
+
+/// `uri` as a Markdown resource: from the cache while `revision` is
+/// unchanged, else from `render`. Without a revision it is rendered every
+/// time, but the client can still skip the transfer by its etag.
+async fn rendered(
+    server: &AuroraServer,
+    uri: &str,
+    meta: &Meta,
+    revision: Option<u64>,
+    render: impl Future<Output = crate::error::Result<Option<String>>>,
+) -> Result<ReadResourceResult, ErrorData> {
+    let cache = &server.state().render_cache;
+    let rendered = match revision.and_then(|revision| cache.get(uri, revision)) {
+        Some(rendered) => rendered,
+        None => {
+            let Some(text) = render.await? else {
+                return Err(not_found(uri));
+            };
+            cache.insert(uri, revision, text)
+        }
+    };
+    let not_modified =
+        meta.get("ifNoneMatch").and_then(|etag| etag.as_str()) == Some(rendered.etag.as_str());
+    let mut result_meta = Meta::new();
+    result_meta.insert("etag".to_string(), rendered.etag.clone().into());
+    if not_modified {
+        result_meta.insert("notModified".to_string(), true.into());
+    }
+    Ok(ReadResourceResult {
+        contents: vec![ResourceContents::TextResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some("text/markdown".to_string()),
+            text: if not_modified {
+                String::new()
+            } else {
+                rendered.text.clone()
+            },
+            meta: Some(result_meta),
+        }],
+    })
+}
+
+async fn read_file(
+    server: &AuroraServer,
+    device: &Device,
+    uri: &str,
+    path: &str,
+    range: ByteRange,
+) -> Result<ReadResourceResult, ErrorData> {
+    let options = &server.state().config.files;
+    let Some(content) = files::read(device, options, path, range).await? else {
+        return Err(not_found(uri));
+    };
+    let contents = match content {
+        FileContent::Directory(entries) => {
+            let listing = serde_json::json!({
+                "device": device.name(),
+                "path": path,
+                "entries": entries,
+            });
+            let text = serde_json::to_string_pretty(&listing)
+                .map_err(|err| ErrorData::internal_error(err.to_string(), None))?;
+            ResourceContents::TextResourceContents {
+                uri: uri.to_string(),
+                mime_type: Some("application/json".to_string()),
+                text,
+                meta: None,
+            }
+        }
+        FileContent::File {
+            data,
+            offset,
+            size,
+            truncated,
+        } => file_contents(uri, path, data, offset, size, truncated),
+    };
+    Ok(ReadResourceResult {
+        contents: vec![contents],
+    })
+}
+
+/// Contents of a file read from `path` at `offset`: text when it decodes
+/// as such and is not binary by name, else a blob. A partial read notes
+/// the full size and, while more remains, the URI of the next chunk.
+fn file_contents(
+    uri: &str,
+    path: &str,
+    data: Vec<u8>,
+    offset: u64,
+    size: u64,
+    truncated: bool,
+) -> ResourceContents {
+    let meta = (truncated || offset > 0).then(|| {
+        let mut meta = Meta::new();
+        meta.insert("size".to_string(), size.into());
+        meta.insert("offset".to_string(), offset.into());
+        meta.insert("truncated".to_string(), truncated.into());
+        if truncated {
+            let next = offset + data.len() as u64;
+            meta.insert("next".to_string(), with_offset(uri, next).into());
+        }
+        meta
+    });
+    let text = if files::is_binary(path) {
+        Err(data)
+    } else {
+        String::from_utf8(data)
+            .map_err(|err| err.into_bytes())
+            .and_then(|text| {
+                if text.contains('\0') {
+                    Err(text.into_bytes())
+                } else {
+                    Ok(text)
+                }
+            })
+    };
+    match text {
+        Ok(text) => ResourceContents::TextResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some(files::mime_type(path, true).to_string()),
+            text,
+            meta,
+        },
+        Err(data) => ResourceContents::BlobResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some(files::mime_type(path, false).to_string()),
+            blob: BASE64.encode(data),
+            meta,
+        },
+    }
+}
+
+/// `uri` reading from `offset`, keeping its other query parameters.
+fn with_offset(uri: &str, offset: u64) -> String {
+    let (base, query) = uri.split_once('?').unwrap_or((uri, ""));
+    let mut params: Vec<&str> = query
+        .split('&')
+        .filter(|param| !param.is_empty() && !param.starts_with("offset="))
+        .collect();
+    let offset = format!("offset={offset}");
+    params.push(&offset);
+    format!("{base}?{}", params.join("&"))
+}
+
+fn not_found(uri: &str) -> ErrorData {
+    ErrorData::resource_not_found(format!("no resource '{uri}'"), None)
+}
+
+/// Resource subscriptions of one client session. Each subscribed URI is
+/// polled by a background task that stops when the session goes away.
+#[derive(Debug, Clone, Default)]
+pub struct Subscriptions {
+    tasks: Arc<SubscriptionTasks>,
+}
+
+#[derive(Debug, Default)]
+struct SubscriptionTasks(Mutex<HashMap<String, AbortHandle>>);
+
+impl Drop for SubscriptionTasks {
+    fn drop(&mut self) {
+        let tasks = self.0.get_mut().unwrap_or_else(|e| e.into_inner());
+        for task in tasks.values() {
+            task.abort();
+        }
+    }
+}
+
+impl Subscriptions {
+    pub fn subscribe(
+        &self,
+        server: &AuroraServer,
+        uri: String,
+        peer: Peer<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        let Some(name) = battery::parse_uri(&uri) else {
+            return Err(not_found(&uri));
+        };
+        let device = server.devices().get(name)?;
+        let interval = server.state().config.monitor.interval();
+        let mut tasks = self.tasks.0.lock().unwrap_or_else(|e| e.into_inner());
+        if tasks.contains_key(&uri) {
+            return Ok(());
+        }
+        let task_uri = uri.clone();
+        let task = tokio::spawn(async move {
+            let mut last = battery::read(&device).await.ok();
+            loop {
+                tokio::time::sleep(interval).await;
+                let status = match battery::read(&device).await {
+                    Ok(status) => status,
+                    Err(err) => {
+                        tracing::debug!("polling {task_uri}: {err}");
+                        continue;
+                    }
+                };
+                if last.as_ref().is_some_and(|last| !last.changed(&status)) {
+                    continue;
+                }
+                last = Some(status);
+                let param = ResourceUpdatedNotificationParam {
+                    uri: task_uri.clone(),
+                };
+                if peer.notify_resource_updated(param).await.is_err() {
+                    break;
+                }
+            }
+        });
+        tasks.insert(uri, task.abort_handle());
+        Ok(())
+    }
+
+    pub fn unsubscribe(&self, uri: &str) {
+        let mut tasks = self.tasks.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(task) = tasks.remove(uri) {
+            task.abort();
+        }
+    }
+}
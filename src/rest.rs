@@ -0,0 +1,291 @@
+//! Plain REST facade for the HTTP transport: `POST /api/tools/<name>` with
+//! the tool's arguments as a JSON object calls the tool, so scripts and CI
+//! jobs can use it without speaking MCP.
+//!
+//! ```sh
+//! curl -d '{"device": "phone"}' http://127.0.0.1:8000/api/tools/device_info
+//! ```
+//!
+//! A tool with structured output answers with that JSON, others with their
+//! text, or their content items as JSON when some are not text. A tool
+//! error is `422`, bad arguments `400`, an unknown tool `404`, and a call
+//! refused because the server is busy `503` with `Retry-After`.
+//! `GET /api/openapi.json` describes every endpoint as OpenAPI 3.1, from
+//! the tools' input and output schemas.
+
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use rmcp::ServiceError;
+use rmcp::model::{CallToolRequestParam, CallToolResult, ErrorCode, JsonObject, Tool};
+use serde_json::{Map, Value, json};
+
+use crate::local;
+use crate::queue::SERVER_BUSY;
+use crate::server::AuroraServer;
+
+/// Routes of the facade, to be merged next to `/mcp`.
+pub fn router(server: AuroraServer) -> Router {
+    Router::new()
+        .route("/api/tools/{name}", post(call_tool))
+        .route("/api/openapi.json", get(openapi_json))
+        .with_state(server)
+}
+
+async fn call_tool(
+    State(server): State<AuroraServer>,
+    Path(name): Path<String>,
+    body: Bytes,
+) -> Response {
+    if !server.has_tool(&name) {
+        return error(StatusCode::NOT_FOUND, format!("no tool '{name}'"));
+    }
+    let arguments = if body.iter().all(u8::is_ascii_whitespace) {
+        None
+    } else {
+        match serde_json::from_slice::<JsonObject>(&body) {
+            Ok(arguments) => Some(arguments),
+            Err(err) => {
+                return error(
+                    StatusCode::BAD_REQUEST,
+                    format!("arguments must be a JSON object: {err}"),
+                );
+            }
+        }
+    };
+    match call(&server, name, arguments).await {
+        Ok(result) => response(result),
+        Err(ServiceError::McpError(err)) if err.code == ErrorCode::INVALID_PARAMS => {
+            error(StatusCode::BAD_REQUEST, err.message.into_owned())
+        }
+        Err(ServiceError::McpError(err)) if err.code == SERVER_BUSY => {
+            let retry_after_ms = err
+                .data
+                .as_ref()
+                .and_then(|data| data["retry_after_ms"].as_u64())
+                .unwrap_or(1000);
+            let mut response = error(StatusCode::SERVICE_UNAVAILABLE, err.message.into_owned());
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, retry_after_ms.div_ceil(1000).into());
+            response
+        }
+        Err(err) => error(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+/// Calls the tool through an in-process session of its own.
+async fn call(
+    server: &AuroraServer,
+    name: String,
+    arguments: Option<JsonObject>,
+) -> Result<CallToolResult, ServiceError> {
+    let client = local::connect(server, ())
+        .await
+        .map_err(ServiceError::McpError)?;
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: name.into(),
+            arguments,
+        })
+        .await;
+    let _ = client.cancel().await;
+    result
+}
+
+fn response(result: CallToolResult) -> Response {
+    let status = if result.is_error == Some(true) {
+        StatusCode::UNPROCESSABLE_ENTITY
+    } else {
+        StatusCode::OK
+    };
+    if let Some(value) = result.structured_content {
+        return (status, axum::Json(value)).into_response();
+    }
+    let texts: Option<Vec<&str>> = result
+        .content
+        .iter()
+        .map(|content| content.as_text().map(|text| text.text.as_str()))
+        .collect();
+    match texts {
+        Some(texts) => (status, texts.join("\n")).into_response(),
+        None => (status, axum::Json(&result.content)).into_response(),
+    }
+}
+
+fn error(status: StatusCode, message: String) -> Response {
+    (status, axum::Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+async fn openapi_json(State(server): State<AuroraServer>) -> Response {
+    axum::Json(openapi(&server.tools())).into_response()
+}
+
+/// OpenAPI 3.1 document for the endpoints of `tools`. Each schema's
+/// `$defs` move to `components/schemas` under the name of the tool and
+/// the schema's role, so that references resolve in the one document.
+pub fn openapi(tools: &[Tool]) -> Value {
+    let mut components = Map::new();
+    components.insert(
+        "Error".to_string(),
+        json!({
+            "type": "object",
+            "properties": { "error": { "type": "string" } },
+            "required": ["error"],
+        }),
+    );
+    let mut paths = Map::new();
+    for tool in tools {
+        let name = tool.name.as_ref();
+        let input = component(
+            &mut components,
+            format!("{name}.params"),
+            &tool.input_schema,
+        );
+        let ok = match &tool.output_schema {
+            Some(schema) => {
+                let output = component(&mut components, format!("{name}.result"), schema);
+                json!({
+                    "description": "The tool's structured result",
+                    "content": { "application/json": { "schema": output } },
+                })
+            }
+            None => json!({
+                "description": "The tool's text, or its content items when some are not text",
+                "content": {
+                    "text/plain": { "schema": { "type": "string" } },
+                    "application/json": { "schema": { "type": "array" } },
+                },
+            }),
+        };
+        let mut operation = json!({
+            "operationId": name,
+            "requestBody": {
+                "required": false,
+                "content": { "application/json": { "schema": input } },
+            },
+            "responses": {
+                "200": ok,
+                "400": error_response("The arguments do not match the tool's parameters"),
+                "404": error_response("No such tool"),
+                "503": error_response("Too many calls are running and queued; retry after the Retry-After seconds"),
+                "422": {
+                    "description": "The tool failed; the body says why",
+                    "content": { "text/plain": { "schema": { "type": "string" } } },
+                },
+            },
+        });
+        if let Some(title) = tool
+            .title
+            .as_ref()
+            .or_else(|| tool.annotations.as_ref()?.title.as_ref())
+        {
+            operation["summary"] = title.clone().into();
+        }
+        if let Some(description) = &tool.description {
+            operation["description"] = description.to_string().into();
+        }
+        paths.insert(format!("/api/tools/{name}"), json!({ "post": operation }));
+    }
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "Aurora OS MCP tools",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Each tool of the MCP server as a plain endpoint: post its arguments as a JSON object.",
+        },
+        "paths": paths,
+        "components": { "schemas": components },
+    })
+}
+
+fn error_response(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": {
+            "application/json": { "schema": { "$ref": "#/components/schemas/Error" } },
+        },
+    })
+}
+
+/// Adds `schema` to `components` as `name`, its `$defs` as `name.<def>`,
+/// and returns a reference to it.
+fn component(components: &mut Map<String, Value>, name: String, schema: &JsonObject) -> Value {
+    let mut schema = Value::Object(schema.clone());
+    let defs = ["$defs", "definitions"]
+        .iter()
+        .filter_map(|key| schema.as_object_mut()?.remove(*key))
+        .filter_map(|defs| match defs {
+            Value::Object(defs) => Some(defs),
+            _ => None,
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+    if let Some(object) = schema.as_object_mut() {
+        object.remove("$schema");
+    }
+    rewrite_schema(&mut schema, &name);
+    for (def, mut value) in defs {
+        rewrite_schema(&mut value, &name);
+        components.insert(format!("{name}.{def}"), value);
+    }
+    components.insert(name.clone(), schema);
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+/// Points the local references of a schema moved to `name` at its
+/// definitions' new places, and turns the OpenAPI 3.0 `nullable` of the
+/// tool schemas into the `null` type of 3.1.
+fn rewrite_schema(value: &mut Value, name: &str) {
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::Bool(nullable)) = object.get("nullable") {
+                let nullable = *nullable;
+                object.remove("nullable");
+                if nullable {
+                    allow_null(object);
+                }
+            }
+            for (key, value) in object.iter_mut() {
+                if key == "$ref"
+                    && let Value::String(target) = value
+                    && let Some(def) = target
+                        .strip_prefix("#/$defs/")
+                        .or_else(|| target.strip_prefix("#/definitions/"))
+                {
+                    *target = format!("#/components/schemas/{name}.{def}");
+                } else {
+                    rewrite_schema(value, name);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                rewrite_schema(item, name);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Lets the schema `object` match `null` as well.
+fn allow_null(object: &mut Map<String, Value>) {
+    let kinds = match object.get("type") {
+        Some(Value::String(kind)) => json!([kind, "null"]),
+        Some(Value::Array(kinds)) if kinds.iter().any(|kind| kind == "null") => return,
+        Some(Value::Array(kinds)) => kinds.iter().cloned().chain([json!("null")]).collect(),
+        _ if object.get("const") == Some(&Value::Null) => json!("null"),
+        _ => {
+            let schema = std::mem::take(object);
+            object.insert(
+                "anyOf".to_string(),
+                json!([Value::Object(schema), { "type": "null" }]),
+            );
+            return;
+        }
+    };
+    object.insert("type".to_string(), kinds);
+}
@@ -0,0 +1,509 @@
+//! `audit_accessibility`: QML controls without an accessible name or role,
+//! touch targets below a minimum size, and text whose contrast against the
+//! ambience background is too low to read.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::guidelines::literal_value;
+use super::lint::{Level, LintIssue};
+use super::silica::{Token, is_type_name, qml_files, tokenize};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Interactive components, with the properties that give them an
+/// accessible name. Containers (empty list) are also named by the text of
+/// the labels inside them.
+const INTERACTIVE: &[(&str, &[&str])] = &[
+    ("IconButton", &[]),
+    ("Button", &["text"]),
+    ("MouseArea", &[]),
+    ("BackgroundItem", &[]),
+    ("ListItem", &[]),
+    ("Switch", &[]),
+    ("TextSwitch", &["text"]),
+    ("IconTextSwitch", &["text"]),
+    ("TextField", &["label", "placeholderText"]),
+    ("PasswordField", &["label", "placeholderText"]),
+    ("SearchField", &["label", "placeholderText"]),
+    ("TextArea", &["label", "placeholderText"]),
+    ("Slider", &["label"]),
+    ("ComboBox", &["label"]),
+    ("ValueButton", &["label"]),
+    ("MenuItem", &["text"]),
+];
+
+/// Components named by the text of their children.
+const CONTAINERS: &[&str] = &["MouseArea", "BackgroundItem", "ListItem"];
+
+/// Interactive components that set no accessible role themselves.
+const ROLELESS: &[&str] = &["MouseArea"];
+
+/// Components drawing text, with the `Theme` color they default to; `Text`
+/// from QtQuick defaults to black.
+const TEXT_COMPONENTS: &[(&str, Option<&str>)] = &[
+    ("Label", Some("Theme.primaryColor")),
+    ("Text", Some("black")),
+    ("PageHeader", None),
+    ("SectionHeader", None),
+];
+
+/// `Theme` sizes at pixel ratio 1.
+const THEME_SIZES: &[(&str, f64)] = &[
+    ("Theme.pixelRatio", 1.0),
+    ("Theme.paddingSmall", 6.0),
+    ("Theme.paddingMedium", 12.0),
+    ("Theme.paddingLarge", 24.0),
+    ("Theme.horizontalPageMargin", 24.0),
+    ("Theme.iconSizeExtraSmall", 24.0),
+    ("Theme.iconSizeSmall", 32.0),
+    ("Theme.iconSizeSmallPlus", 48.0),
+    ("Theme.iconSizeMedium", 64.0),
+    ("Theme.iconSizeLarge", 96.0),
+    ("Theme.iconSizeExtraLarge", 128.0),
+    ("Theme.itemSizeExtraSmall", 70.0),
+    ("Theme.itemSizeSmall", 80.0),
+    ("Theme.itemSizeMedium", 100.0),
+    ("Theme.itemSizeLarge", 110.0),
+    ("Theme.itemSizeExtraLarge", 135.0),
+    ("Theme.itemSizeHuge", 180.0),
+    ("Theme.fontSizeTiny", 20.0),
+    ("Theme.fontSizeExtraSmall", 24.0),
+    ("Theme.fontSizeSmall", 28.0),
+    ("Theme.fontSizeMedium", 32.0),
+    ("Theme.fontSizeLarge", 40.0),
+    ("Theme.fontSizeExtraLarge", 50.0),
+    ("Theme.fontSizeHuge", 64.0),
+];
+
+/// Touch targets smaller than this, at pixel ratio 1, are hard to hit:
+/// `Theme.iconSizeMedium`, the size of an `IconButton`.
+const DEFAULT_MIN_TOUCH_TARGET: f64 = 64.0;
+
+/// Text this large (`Theme.fontSizeLarge`) needs less contrast.
+const LARGE_TEXT: f64 = 40.0;
+
+/// WCAG 2 contrast minimums for normal and large text.
+const MIN_CONTRAST: f64 = 4.5;
+const MIN_CONTRAST_LARGE: f64 = 3.0;
+
+/// Representative backgrounds of dark and light ambiences: the dimmed
+/// wallpaper behind pages.
+const DARK_BACKGROUND: &str = "#1e1e1e";
+const LIGHT_BACKGROUND: &str = "#e6e6e6";
+
+const NAMED_COLORS: &[(&str, &str)] = &[
+    ("white", "#ffffff"),
+    ("black", "#000000"),
+    ("red", "#ff0000"),
+    ("green", "#008000"),
+    ("blue", "#0000ff"),
+    ("yellow", "#ffff00"),
+    ("gray", "#808080"),
+    ("grey", "#808080"),
+    ("lightgray", "#d3d3d3"),
+    ("darkgray", "#a9a9a9"),
+    ("transparent", "#00000000"),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Scheme {
+    /// Light text on a dark ambience, the default.
+    #[default]
+    Dark,
+    /// Dark text on a light ambience.
+    Light,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AuditAccessibilityParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// QML files to check, relative to the project (default: all under
+    /// its QML directory).
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// Color scheme of the ambience to check contrast against.
+    #[serde(default)]
+    pub scheme: Scheme,
+    /// Background color to check contrast against, e.g. `#202020`
+    /// (default: a typical dimmed wallpaper of the scheme).
+    #[serde(default)]
+    pub background: Option<String>,
+    /// Smallest touch target in pixels at pixel ratio 1 (default 64,
+    /// `Theme.iconSizeMedium`).
+    #[serde(default)]
+    pub min_touch_target: Option<f64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AccessibilityReport {
+    pub files_checked: usize,
+    pub scheme: Scheme,
+    /// The background contrast was measured against.
+    pub background: String,
+    /// No errors were found; warnings may remain.
+    pub passed: bool,
+    pub issues: Vec<LintIssue>,
+}
+
+/// A component instance with the properties set on it.
+struct Node {
+    name: String,
+    line: usize,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// `property -> (line, value)`.
+    properties: BTreeMap<String, (usize, String)>,
+}
+
+/// The component tree of a QML document.
+fn parse_tree(text: &str) -> Vec<Node> {
+    let lines: Vec<&str> = text.lines().collect();
+    let tokens = tokenize(text);
+    let mut nodes: Vec<Node> = Vec::new();
+    // Open blocks: the component's node, or `None` for other braces.
+    let mut stack: Vec<Option<usize>> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let (line, token) = &tokens[i];
+        let next = tokens.get(i + 1).map(|(_, token)| token);
+        match token {
+            Token::Word(word) if next == Some(&Token::Punct('{')) => {
+                if is_type_name(word) {
+                    let parent = stack.iter().rev().find_map(|block| *block);
+                    nodes.push(Node {
+                        name: word.rsplit('.').next().unwrap_or(word).to_string(),
+                        line: *line,
+                        parent,
+                        children: Vec::new(),
+                        properties: BTreeMap::new(),
+                    });
+                    let index = nodes.len() - 1;
+                    if let Some(parent) = parent {
+                        nodes[parent].children.push(index);
+                    }
+                    stack.push(Some(index));
+                } else {
+                    stack.push(None);
+                }
+                i += 1;
+            }
+            Token::Word(word) if next == Some(&Token::Punct(':')) => {
+                let at_start = i == 0
+                    || tokens[i - 1].0 != *line
+                    || matches!(tokens[i - 1].1, Token::Punct('{' | ';'));
+                if at_start
+                    && let Some(Some(index)) = stack.last()
+                    && let Some(value) = lines
+                        .get(line - 1)
+                        .and_then(|source| literal_value(source, word))
+                {
+                    nodes[*index]
+                        .properties
+                        .insert(word.clone(), (*line, value.to_string()));
+                }
+            }
+            Token::Punct('{') => stack.push(None),
+            Token::Punct('}') => {
+                stack.pop();
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    nodes
+}
+
+/// A size expression such as `48`, `Theme.iconSizeSmall` or
+/// `40 * Theme.pixelRatio`, in pixels at pixel ratio 1.
+fn size(value: &str) -> Option<f64> {
+    value
+        .split('*')
+        .map(|factor| {
+            let factor = factor.trim().trim_matches(|c| c == '(' || c == ')');
+            factor.parse::<f64>().ok().or_else(|| {
+                THEME_SIZES
+                    .iter()
+                    .find(|(name, _)| *name == factor)
+                    .map(|(_, size)| *size)
+            })
+        })
+        .product()
+}
+
+/// An RGBA color from `#rgb`, `#rrggbb`, `#aarrggbb` or a name.
+fn parse_color(text: &str) -> Option<[f64; 4]> {
+    let text = text.trim().trim_matches('"').trim_matches('\'');
+    let hex = NAMED_COLORS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(text))
+        .map_or(text, |(_, hex)| *hex)
+        .strip_prefix('#')?;
+    let digits: Vec<f64> = hex
+        .chars()
+        .map(|c| c.to_digit(16).map(f64::from))
+        .collect::<Option<_>>()?;
+    let pair = |i: usize| (digits[i] * 16.0 + digits[i + 1]) / 255.0;
+    match digits.len() {
+        3 => Some([digits[0] / 15.0, digits[1] / 15.0, digits[2] / 15.0, 1.0]),
+        6 => Some([pair(0), pair(2), pair(4), 1.0]),
+        8 => Some([pair(2), pair(4), pair(6), pair(0)]),
+        _ => None,
+    }
+}
+
+/// A color expression: a literal, `Theme.primaryColor`, or
+/// `Theme.rgba(color, alpha)`. Other `Theme` colors follow the ambience and
+/// give `None`.
+fn color(value: &str, scheme: Scheme) -> Option<[f64; 4]> {
+    let value = value.trim();
+    if value == "Theme.primaryColor" {
+        return parse_color(match scheme {
+            Scheme::Dark => "#ffffff",
+            Scheme::Light => "#000000",
+        });
+    }
+    if let Some(arguments) = value
+        .strip_prefix("Theme.rgba(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let (base, alpha) = arguments.rsplit_once(',')?;
+        let mut base = color(base, scheme)?;
+        base[3] *= alpha.trim().parse::<f64>().ok()?;
+        return Some(base);
+    }
+    parse_color(value)
+}
+
+fn luminance([r, g, b, _]: [f64; 4]) -> f64 {
+    let channel = |c: f64| {
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * channel(r) + 0.7152 * channel(g) + 0.0722 * channel(b)
+}
+
+/// WCAG contrast ratio of `foreground`, blended by its alpha, over an opaque
+/// `background`.
+fn contrast(foreground: [f64; 4], background: [f64; 4]) -> f64 {
+    let alpha = foreground[3];
+    let blended = [0, 1, 2].map(|i| foreground[i] * alpha + background[i] * (1.0 - alpha));
+    let (a, b) = (
+        luminance([blended[0], blended[1], blended[2], 1.0]),
+        luminance(background),
+    );
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+fn check_file(
+    file: &Path,
+    text: &str,
+    scheme: Scheme,
+    background: [f64; 4],
+    min_target: f64,
+) -> Vec<LintIssue> {
+    let nodes = parse_tree(text);
+    let mut issues = Vec::new();
+    let mut issue = |level, rule: &str, line, message: String, suggestion: &str| {
+        issues.push(LintIssue {
+            level,
+            rule: rule.to_string(),
+            file: file.to_path_buf(),
+            line: Some(line),
+            message,
+            suggestion: suggestion.to_string(),
+        });
+    };
+    let property =
+        |index: usize, name: &str| nodes[index].properties.get(name).map(|(_, v)| v.as_str());
+    let ancestors =
+        |index: usize| std::iter::successors(nodes[index].parent, |parent| nodes[*parent].parent);
+    // Whether any component below `index` shows text or has a name.
+    let labelled = |index: usize| {
+        let mut pending = nodes[index].children.clone();
+        while let Some(child) = pending.pop() {
+            if ["text", "Accessible.name", "title", "label"]
+                .iter()
+                .any(|name| property(child, name).is_some())
+            {
+                return true;
+            }
+            pending.extend(&nodes[child].children);
+        }
+        false
+    };
+
+    for (index, node) in nodes.iter().enumerate() {
+        let name = node.name.as_str();
+        if let Some((_, label_properties)) = INTERACTIVE.iter().find(|(n, _)| *n == name) {
+            let named = property(index, "Accessible.name").is_some()
+                || label_properties
+                    .iter()
+                    .any(|p| property(index, p).is_some_and(|v| v != "\"\""))
+                || CONTAINERS.contains(&name) && labelled(index);
+            if !named {
+                issue(
+                    Level::Warning,
+                    "missing-accessible-name",
+                    node.line,
+                    format!("{name} has no accessible name; screen readers announce nothing"),
+                    "set Accessible.name: qsTr(\"...\") describing what it does",
+                );
+            }
+            if ROLELESS.contains(&name) && property(index, "Accessible.role").is_none() {
+                issue(
+                    Level::Info,
+                    "missing-accessible-role",
+                    node.line,
+                    format!("{name} has no accessible role"),
+                    "set Accessible.role, e.g. Accessible.Button, or use a Silica control such as BackgroundItem",
+                );
+            }
+
+            // With anchors.fill: parent, the parent's size is the target.
+            let sized = if property(index, "anchors.fill") == Some("parent") {
+                node.parent
+            } else {
+                Some(index)
+            };
+            for dimension in ["width", "height"] {
+                if let Some(target) = sized
+                    && let Some(value) = property(target, dimension)
+                    && let Some(pixels) = size(value)
+                    && pixels < min_target
+                {
+                    issue(
+                        Level::Warning,
+                        "small-touch-target",
+                        nodes[target]
+                            .properties
+                            .get(dimension)
+                            .map_or(node.line, |(line, _)| *line),
+                        format!(
+                            "{name} is {pixels} pixels {}; touch targets need at least {min_target}",
+                            if dimension == "width" { "wide" } else { "high" }
+                        ),
+                        "make it at least Theme.iconSizeMedium, or enlarge the touch area beyond the visual",
+                    );
+                }
+            }
+        }
+
+        if let Some((_, default)) = TEXT_COMPONENTS.iter().find(|(n, _)| *n == name) {
+            let Some(value) = property(index, "color").or(*default) else {
+                continue;
+            };
+            let Some(mut foreground) = color(value, scheme) else {
+                continue;
+            };
+            for opacity in std::iter::once(index)
+                .chain(ancestors(index))
+                .filter_map(|i| property(i, "opacity")?.parse::<f64>().ok())
+            {
+                foreground[3] *= opacity;
+            }
+            let rectangle = ancestors(index)
+                .filter(|i| nodes[*i].name == "Rectangle")
+                .find_map(|i| Some((i, color(property(i, "color")?, scheme)?)))
+                .filter(|(_, c)| c[3] >= 1.0);
+            let behind = rectangle.map_or(background, |(_, c)| c);
+            let large = property(index, "font.pixelSize")
+                .and_then(size)
+                .is_some_and(|pixels| pixels >= LARGE_TEXT);
+            let minimum = if large {
+                MIN_CONTRAST_LARGE
+            } else {
+                MIN_CONTRAST
+            };
+            let ratio = contrast(foreground, behind);
+            if ratio < minimum {
+                let line = node
+                    .properties
+                    .get("color")
+                    .map_or(node.line, |(line, _)| *line);
+                issue(
+                    Level::Warning,
+                    "low-contrast",
+                    line,
+                    format!(
+                        "{name} text has a contrast ratio of {ratio:.1}:1 against {}; at least {minimum}:1 is needed",
+                        match (rectangle, scheme) {
+                            (Some((i, _)), _) => format!("the Rectangle on line {}", nodes[i].line),
+                            (None, Scheme::Dark) => "the dark ambience".to_string(),
+                            (None, Scheme::Light) => "the light ambience".to_string(),
+                        }
+                    ),
+                    "use Theme.primaryColor, Theme.secondaryColor or Theme.highlightColor, which follow the ambience",
+                );
+            }
+        }
+    }
+    issues
+}
+
+#[tool_router(router = accessibility_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Audit QML pages for accessibility: interactive elements (IconButton, MouseArea, BackgroundItem, text fields, switches...) without an accessible name or role, touch targets smaller than Theme.iconSizeMedium, and text whose contrast against a dark or light ambience falls below WCAG's 4.5:1 (3:1 for large text), counting opacity and Rectangle backgrounds. Returns issues with file, line and suggestion.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn audit_accessibility(
+        &self,
+        Parameters(params): Parameters<AuditAccessibilityParams>,
+    ) -> Result<Json<AccessibilityReport>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        let files = if params.files.is_empty() {
+            qml_files(&dir)?
+        } else {
+            params.files.iter().map(|file| dir.join(file)).collect()
+        };
+        if let Some(missing) = files.iter().find(|file| !file.is_file()) {
+            return Err(Error::InvalidArgument(format!(
+                "{} does not exist",
+                missing.display()
+            )));
+        }
+        let background_text = params.background.unwrap_or_else(|| {
+            match params.scheme {
+                Scheme::Dark => DARK_BACKGROUND,
+                Scheme::Light => LIGHT_BACKGROUND,
+            }
+            .to_string()
+        });
+        let background = parse_color(&background_text).ok_or_else(|| {
+            Error::InvalidArgument(format!("'{background_text}' is not a color like #202020"))
+        })?;
+        let min_target = params.min_touch_target.unwrap_or(DEFAULT_MIN_TOUCH_TARGET);
+
+        let mut issues = Vec::new();
+        for file in &files {
+            let text = std::fs::read_to_string(file)?;
+            issues.extend(check_file(
+                file,
+                &text,
+                params.scheme,
+                background,
+                min_target,
+            ));
+        }
+        Ok(Json(AccessibilityReport {
+            files_checked: files.len(),
+            scheme: params.scheme,
+            background: background_text,
+            passed: !issues.iter().any(|issue| issue.level == Level::Error),
+            issues,
+        }))
+    }
+}
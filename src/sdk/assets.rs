@@ -0,0 +1,472 @@
+//! `optimize_assets`: lossless recompression of a project's PNG, JPEG and
+//! SVG files, by stripping metadata natively and running `oxipng`,
+//! `optipng` or `jpegtran` when the host has them.
+
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use super::engine::query;
+use super::find_in_path;
+use super::project::find_files;
+use crate::device::png;
+use crate::error::{Result, require_confirmation};
+use crate::server::AuroraServer;
+
+/// PNG chunks holding only metadata: text, timestamps.
+const PNG_METADATA_CHUNKS: &[&[u8; 4]] = &[b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// `APP1` payload prefixes of XMP packets, which JPEG viewers ignore.
+const XMP_PREFIXES: &[&[u8]] = &[
+    b"http://ns.adobe.com/xap/1.0/\0",
+    b"http://ns.adobe.com/xmp/extension/\0",
+];
+
+/// Namespace prefixes of editor-only SVG elements and attributes.
+const SVG_EDITOR_PREFIXES: &[&str] = &["inkscape:", "sodipodi:", "sketch:"];
+
+/// Namespaces used only by editor data and `<metadata>`, declared on the
+/// root element.
+const SVG_METADATA_NAMESPACES: &[&str] = &["inkscape", "sodipodi", "sketch", "rdf", "cc", "dc"];
+
+/// Top-level directories that are build output rather than sources.
+const SKIP_DIRS: &[&str] = &["RPMS"];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct OptimizeAssetsParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Replace the files with their optimized versions instead of only
+    /// reporting the savings.
+    #[serde(default)]
+    pub write: bool,
+    /// Must be true with write: the originals are overwritten.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetKind {
+    Png,
+    Jpeg,
+    Svg,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct OptimizedAsset {
+    /// Path relative to the project.
+    pub file: String,
+    pub kind: AssetKind,
+    pub original_size: u64,
+    pub optimized_size: u64,
+    pub saved: u64,
+    pub saved_percent: f64,
+    /// Steps that made it smaller, e.g. `strip-metadata`, `oxipng`.
+    pub methods: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AssetReport {
+    pub files_checked: usize,
+    /// Files that got smaller, largest saving first.
+    pub optimized: Vec<OptimizedAsset>,
+    pub total_size: u64,
+    pub total_saved: u64,
+    pub saved_percent: f64,
+    /// The optimized files replaced the originals.
+    pub written: bool,
+    /// External optimizers used.
+    pub tools: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+fn percent(saved: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    (saved as f64 * 1000.0 / total as f64).round() / 10.0
+}
+
+/// The PNG without its text and timestamp chunks and anything after
+/// `IEND`; `None` when it is not a well-formed PNG.
+fn strip_png(data: &[u8]) -> Option<Vec<u8>> {
+    let signature = data.get(..8)?;
+    if signature != b"\x89PNG\r\n\x1a\n" {
+        return None;
+    }
+    let mut out = signature.to_vec();
+    let mut pos = 8;
+    loop {
+        let length = u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        let chunk = data.get(pos..pos + 12 + length)?;
+        let kind = &chunk[4..8];
+        if !PNG_METADATA_CHUNKS.iter().any(|name| name[..] == *kind) {
+            out.extend_from_slice(chunk);
+        }
+        pos += 12 + length;
+        if kind == b"IEND" {
+            return Some(out);
+        }
+    }
+}
+
+/// The JPEG without comments, XMP packets and Photoshop resources; Exif,
+/// ICC profiles and Adobe color transforms stay. `None` when it is not a
+/// well-formed JPEG.
+fn strip_jpeg(data: &[u8]) -> Option<Vec<u8>> {
+    if !data.starts_with(&[0xff, 0xd8]) {
+        return None;
+    }
+    let mut out = vec![0xff, 0xd8];
+    let mut pos = 2;
+    loop {
+        if *data.get(pos)? != 0xff {
+            return None;
+        }
+        let marker = *data.get(pos + 1)?;
+        // Start of scan: the entropy-coded data follows to the end.
+        if marker == 0xda {
+            out.extend_from_slice(&data[pos..]);
+            return Some(out);
+        }
+        let length = u16::from_be_bytes([*data.get(pos + 2)?, *data.get(pos + 3)?]) as usize;
+        let segment = data.get(pos..pos + 2 + length)?;
+        let payload = &segment[4..];
+        let drop = match marker {
+            0xfe | 0xed => true,
+            0xe1 => XMP_PREFIXES
+                .iter()
+                .any(|prefix| payload.starts_with(prefix)),
+            _ => false,
+        };
+        if !drop {
+            out.extend_from_slice(segment);
+        }
+        pos += 2 + length;
+    }
+}
+
+/// Name of the element a tag (without `<`) opens, closes or declares.
+fn tag_name(tag: &str) -> &str {
+    let tag = tag.trim_start_matches('/');
+    let end = tag
+        .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .unwrap_or(tag.len());
+    &tag[..end]
+}
+
+fn is_editor_name(name: &str) -> bool {
+    name == "metadata" || SVG_EDITOR_PREFIXES.iter().any(|p| name.starts_with(p))
+}
+
+/// A start tag without editor attributes, e.g. `inkscape:label="..."`.
+fn strip_attributes(tag: &str) -> String {
+    let mut out = String::with_capacity(tag.len());
+    let mut rest = tag;
+    while let Some(start) = rest.find(|c: char| c.is_whitespace()) {
+        out.push_str(&rest[..start]);
+        let after = rest[start..].trim_start();
+        let Some(eq) = after.find('=') else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let name = after[..eq].trim();
+        let value = after[eq + 1..].trim_start();
+        let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let Some(close) = value[1..].find(quote) else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let attribute_end = after.len() - value.len() + close + 2;
+        if !is_editor_name(name) {
+            out.push(' ');
+            out.push_str(&after[..attribute_end]);
+        }
+        rest = &after[attribute_end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The SVG without comments, `<metadata>`, editor elements and attributes,
+/// and, unless it has text whose spacing matters, whitespace between tags.
+/// `None` when the result is not well-formed XML.
+fn strip_svg(text: &str) -> Option<String> {
+    let collapse = !text.contains("<text") && !text.contains("xml:space");
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    // Depth inside an element being dropped.
+    let mut skipping = 0usize;
+    while let Some(start) = rest.find('<') {
+        let between = &rest[..start];
+        if skipping == 0 && !(collapse && between.trim().is_empty()) {
+            out.push_str(between);
+        }
+        rest = &rest[start..];
+        let (end, keep) = if rest.starts_with("<!--") {
+            (rest.find("-->")? + 3, false)
+        } else if rest.starts_with("<![CDATA[") {
+            (rest.find("]]>")? + 3, true)
+        } else {
+            (rest.find('>')? + 1, true)
+        };
+        let tag = &rest[..end];
+        rest = &rest[end..];
+        if !keep {
+            continue;
+        }
+        let inner = &tag[1..];
+        if inner.starts_with(['?', '!']) {
+            if skipping == 0 {
+                out.push_str(tag);
+            }
+            continue;
+        }
+        let closing = inner.starts_with('/');
+        let self_closing = tag.ends_with("/>");
+        let name = tag_name(inner);
+        if skipping > 0 || is_editor_name(name) {
+            if closing {
+                skipping = skipping.saturating_sub(1);
+            } else if !self_closing {
+                skipping += 1;
+            }
+            continue;
+        }
+        if closing {
+            out.push_str(tag);
+        } else {
+            out.push_str(&strip_attributes(tag));
+        }
+    }
+    if skipping == 0 {
+        out.push_str(rest);
+    }
+    // Namespace declarations nothing uses any more.
+    for namespace in SVG_METADATA_NAMESPACES {
+        let prefix = format!("{namespace}:");
+        let declaration = format!(" xmlns:{namespace}=");
+        let used = out.match_indices(&prefix).any(|(i, _)| {
+            i == 0
+                || matches!(
+                    out.as_bytes()[i - 1],
+                    b'<' | b'/' | b' ' | b'\t' | b'\r' | b'\n'
+                )
+        });
+        if !used && let Some(start) = out.find(&declaration) {
+            let value_start = start + declaration.len();
+            let quote = out[value_start..].chars().next()?;
+            let close = out[value_start + 1..].find(quote)?;
+            out.replace_range(start..value_start + close + 2, "");
+        }
+    }
+    roxmltree::Document::parse(&out).ok()?;
+    Some(out)
+}
+
+/// Runs an external optimizer reading `input` and writing `output`; the
+/// output when it succeeded.
+async fn run_tool(command: Command, output: &Path) -> Option<Vec<u8>> {
+    let _ = std::fs::remove_file(output);
+    let (status, _) = query(command).await.ok()?;
+    if status != 0 {
+        return None;
+    }
+    std::fs::read(output).ok()
+}
+
+/// Whether `optimized` shows the same pixels as `original`, for PNGs the
+/// built-in decoder reads; others are trusted.
+fn same_pixels(original: &[u8], optimized: &[u8]) -> bool {
+    match png::decode(original) {
+        Ok(before) => png::decode(optimized).is_ok_and(|after| {
+            after.width == before.width
+                && after.height == before.height
+                && after.pixels == before.pixels
+        }),
+        Err(_) => true,
+    }
+}
+
+/// The smallest lossless version of an asset and the steps producing it.
+async fn optimize(
+    data: &[u8],
+    kind: AssetKind,
+    scratch: &Path,
+    tools: &mut Vec<String>,
+) -> (Vec<u8>, Vec<String>) {
+    let mut best = data.to_vec();
+    let mut methods = Vec::new();
+    let stripped = match kind {
+        AssetKind::Png => strip_png(data),
+        AssetKind::Jpeg => strip_jpeg(data),
+        AssetKind::Svg => std::str::from_utf8(data)
+            .ok()
+            .and_then(strip_svg)
+            .map(String::into_bytes),
+    };
+    if let Some(stripped) = stripped.filter(|s| s.len() < best.len()) {
+        best = stripped;
+        methods.push("strip-metadata".to_string());
+    }
+
+    let extension = match kind {
+        AssetKind::Png => "png",
+        AssetKind::Jpeg => "jpg",
+        AssetKind::Svg => return (best, methods),
+    };
+    let input = scratch.join(format!("input.{extension}"));
+    let output = scratch.join(format!("output.{extension}"));
+    if std::fs::write(&input, &best).is_err() {
+        return (best, methods);
+    }
+    let candidates: &[&str] = match kind {
+        AssetKind::Png => &["oxipng", "optipng"],
+        _ => &["jpegtran"],
+    };
+    let Some((name, program)) = candidates
+        .iter()
+        .find_map(|name| Some((*name, find_in_path(name)?)))
+    else {
+        return (best, methods);
+    };
+    let mut command = Command::new(program);
+    match name {
+        "oxipng" => command.args(["-q", "-o", "4", "--strip", "safe", "--out"]),
+        "optipng" => command.args(["-quiet", "-o2", "-out"]),
+        _ => command.args(["-copy", "all", "-optimize", "-outfile"]),
+    };
+    command.arg(&output).arg(&input);
+    if let Some(result) = run_tool(command, &output).await
+        && !result.is_empty()
+        && result.len() < best.len()
+        && (kind != AssetKind::Png || same_pixels(&best, &result))
+    {
+        best = result;
+        methods.push(name.to_string());
+        if !tools.iter().any(|tool| tool == name) {
+            tools.push(name.to_string());
+        }
+    }
+    (best, methods)
+}
+
+/// PNG, JPEG and SVG files of a project, outside build output.
+fn asset_files(root: &Path) -> Vec<(PathBuf, AssetKind)> {
+    let kinds = [
+        ("png", AssetKind::Png),
+        ("jpg", AssetKind::Jpeg),
+        ("jpeg", AssetKind::Jpeg),
+        ("svg", AssetKind::Svg),
+    ];
+    let mut files: Vec<(PathBuf, AssetKind)> = kinds
+        .into_iter()
+        .flat_map(|(ext, kind)| find_files(root, ext, 8).into_iter().map(move |f| (f, kind)))
+        .filter(|(file, _)| {
+            let path = file.strip_prefix(root).unwrap_or(file);
+            !path.components().next().is_some_and(|top| {
+                let top = top.as_os_str().to_string_lossy();
+                SKIP_DIRS.contains(&top.as_ref()) || top.starts_with("build")
+            })
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[tool_router(router = assets_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Losslessly recompress a project's PNG, JPEG and SVG assets to shrink the RPM: strips metadata (PNG text chunks, JPEG comments and XMP, SVG comments and editor data) and runs oxipng or optipng and jpegtran when installed; PNG results are checked pixel for pixel. Reports the saving per file and in total. write=true with confirm=true replaces the originals.",
+        annotations(destructive_hint = true)
+    )]
+    pub async fn optimize_assets(
+        &self,
+        Parameters(params): Parameters<OptimizeAssetsParams>,
+    ) -> Result<Json<AssetReport>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        let root = dir.canonicalize()?;
+        let files = asset_files(&root);
+        let scratch =
+            std::env::temp_dir().join(format!("aurora-mcp-assets-{}", std::process::id()));
+        std::fs::create_dir_all(&scratch)?;
+
+        let mut tools = Vec::new();
+        let mut optimized = Vec::new();
+        let mut replacements = Vec::new();
+        let mut total_size = 0;
+        for (file, kind) in &files {
+            let Ok(data) = std::fs::read(file) else {
+                continue;
+            };
+            total_size += data.len() as u64;
+            let (result, methods) = optimize(&data, *kind, &scratch, &mut tools).await;
+            if result.len() >= data.len() {
+                continue;
+            }
+            let saved = (data.len() - result.len()) as u64;
+            optimized.push(OptimizedAsset {
+                file: file
+                    .strip_prefix(&root)
+                    .unwrap_or(file)
+                    .display()
+                    .to_string(),
+                kind: *kind,
+                original_size: data.len() as u64,
+                optimized_size: result.len() as u64,
+                saved,
+                saved_percent: percent(saved, data.len() as u64),
+                methods,
+            });
+            replacements.push((file.clone(), result));
+        }
+        let _ = std::fs::remove_dir_all(&scratch);
+
+        if params.write && !replacements.is_empty() {
+            require_confirmation(params.confirm, || {
+                format!(
+                    "overwriting {} assets in {}",
+                    replacements.len(),
+                    dir.display()
+                )
+            })?;
+            for (file, data) in &replacements {
+                std::fs::write(file, data)?;
+            }
+        }
+
+        let mut notes = Vec::new();
+        let has = |names: &[&str]| names.iter().any(|name| find_in_path(name).is_some());
+        if files.iter().any(|(_, kind)| *kind == AssetKind::Png) && !has(&["oxipng", "optipng"]) {
+            notes.push(
+                "neither oxipng nor optipng is installed; PNGs only had metadata stripped"
+                    .to_string(),
+            );
+        }
+        if files.iter().any(|(_, kind)| *kind == AssetKind::Jpeg) && !has(&["jpegtran"]) {
+            notes.push("jpegtran is not installed; JPEGs only had metadata stripped".to_string());
+        }
+        optimized.sort_by_key(|asset| std::cmp::Reverse(asset.saved));
+        let total_saved = optimized.iter().map(|asset| asset.saved).sum();
+        Ok(Json(AssetReport {
+            files_checked: files.len(),
+            optimized,
+            total_size,
+            total_saved,
+            saved_percent: percent(total_saved, total_size),
+            written: params.write && !replacements.is_empty(),
+            tools,
+            notes,
+        }))
+    }
+}
@@ -0,0 +1,152 @@
+//! `build_project`: configuring and compiling a project with `sfdk build`
+//! inside the Aurora Build Engine.
+
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+use rmcp::handler::server::tool::cached_schema_for_type;
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::CallToolResult;
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use super::flutter::FlutterProject;
+use super::project::{ProjectInfo, find_files};
+use super::run_logged;
+use crate::device::tail_lines;
+use crate::error::{Error, Result, tool_result};
+use crate::resources::file_link;
+use crate::server::AuroraServer;
+
+const LOG_TAIL_LINES: usize = 40;
+const MAX_ERRORS: usize = 20;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BuildParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Build target, e.g. `AuroraOS-5.1.3.85-MB2-armv7hl` (default: the
+    /// configured one).
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Build with debug information (`--enable-debug`).
+    #[serde(default)]
+    pub debug: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BuildReport {
+    pub project: PathBuf,
+    pub target: Option<String>,
+    pub success: bool,
+    pub exit_status: i32,
+    pub duration_secs: u64,
+    /// RPM packages written by this build.
+    pub rpms: Vec<PathBuf>,
+    /// Compiler and packaging error lines from the log.
+    pub errors: Vec<String>,
+    pub log_file: PathBuf,
+    pub log_tail: Vec<String>,
+}
+
+/// Lines that report a failure: compiler and linker errors, qmake/CMake
+/// errors and rpmbuild's summary.
+pub(crate) fn is_error_line(line: &str) -> bool {
+    let lower = line.to_ascii_lowercase();
+    lower.contains("error:")
+        || (lower.starts_with("make") && lower.contains("error "))
+        || lower.starts_with("cmake error")
+        || lower.starts_with("project error")
+        || lower.contains("undefined reference")
+        || lower.starts_with("rpm build errors")
+}
+
+/// RPMs under `dir`, at most `depth` levels down, modified at or after
+/// `since`.
+pub(super) fn new_rpms(dir: &Path, depth: usize, since: SystemTime) -> Vec<PathBuf> {
+    let mut rpms: Vec<PathBuf> = find_files(dir, "rpm", depth)
+        .into_iter()
+        .filter(|path| {
+            path.metadata()
+                .and_then(|meta| meta.modified())
+                .is_ok_and(|modified| modified >= since)
+        })
+        .collect();
+    rpms.sort();
+    rpms
+}
+
+pub async fn build(server: &AuroraServer, params: BuildParams) -> Result<BuildReport> {
+    let options = &server.state().config.sdk;
+    let project = options.project(params.project.as_deref())?;
+    if ProjectInfo::inspect(&project).build_system.is_none()
+        && FlutterProject::detect(&project).is_some()
+    {
+        return Err(Error::InvalidArgument(format!(
+            "{} is a Flutter project; build it with flutter_build",
+            project.display()
+        )));
+    }
+    let sfdk = options.sfdk()?;
+    let target = options.target(params.target.as_deref())?;
+
+    let log_file = super::log_file(server, &project, "build");
+
+    let mut command = Command::new(&sfdk);
+    if let Some(target) = &target {
+        command.arg("-c").arg(format!("target={target}"));
+    }
+    command.arg("build");
+    if params.debug {
+        command.arg("--enable-debug");
+    }
+    command.current_dir(&project);
+
+    // Filesystem timestamps can be coarser than the clock; allow a second.
+    let since = SystemTime::now() - std::time::Duration::from_secs(1);
+    let started = Instant::now();
+    let run = run_logged(command, &log_file, options.build_timeout()).await?;
+    let errors: Vec<String> = run
+        .output
+        .lines()
+        .filter(|line| is_error_line(line))
+        .take(MAX_ERRORS)
+        .map(|line| line.trim().to_string())
+        .collect();
+    Ok(BuildReport {
+        rpms: new_rpms(&project.join("RPMS"), 0, since),
+        project,
+        target,
+        success: run.status == 0,
+        exit_status: run.status,
+        duration_secs: started.elapsed().as_secs(),
+        errors,
+        log_tail: tail_lines(&run.output, LOG_TAIL_LINES),
+        log_file,
+    })
+}
+
+#[tool_router(router = build_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Build an Aurora project with sfdk in the Build Engine: configure (qmake/CMake), compile and package it for a target. Returns success, the RPMs produced, error lines, the log tail and a link to the full build log.",
+        output_schema = cached_schema_for_type::<BuildReport>()
+    )]
+    pub async fn build_project(
+        &self,
+        Parameters(params): Parameters<BuildParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        tool_result(build(self, params).await.and_then(|report| {
+            let link = file_link(&report.log_file, Some("text/plain"));
+            let mut result = CallToolResult::structured(
+                serde_json::to_value(&report).map_err(std::io::Error::other)?,
+            );
+            result.content.push(link);
+            Ok(result)
+        }))
+    }
+}
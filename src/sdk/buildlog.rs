@@ -0,0 +1,381 @@
+//! `analyze_build_log`: the errors in a build log, however long, reduced to
+//! their root causes with file/line context.
+
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::tool::cached_schema_for_type;
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::CallToolResult;
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::config::expand_tilde;
+use crate::error::{Error, Result, tool_result};
+use crate::resources::file_link;
+use crate::server::AuroraServer;
+
+const DEFAULT_MAX_ERRORS: usize = 30;
+/// Lines kept around an error.
+const CONTEXT_LINES: usize = 4;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AnalyzeBuildLogParams {
+    /// Log file (default: the newest build log written by `build_project`,
+    /// `flutter_build` or `build_rust`, for `project` when given).
+    #[serde(default)]
+    pub log: Option<String>,
+    /// Project whose newest build log to analyze.
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Distinct errors to return (default 30).
+    #[serde(default)]
+    pub max_errors: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorKind {
+    Compiler,
+    Linker,
+    Make,
+    Qmake,
+    Cmake,
+    Rpmbuild,
+    /// Dart compiler (frontend_server) errors in Flutter builds.
+    Dart,
+    /// `pub get` dependency resolution.
+    Pub,
+    /// The `flutter-aurora` tool itself, e.g. a missing Platform SDK.
+    Flutter,
+    Rustc,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BuildError {
+    pub kind: ErrorKind,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+    /// 1-based line of the first occurrence in the log.
+    pub log_line: usize,
+    /// How often the same error appears, e.g. a header error reported for
+    /// every file including it.
+    pub occurrences: usize,
+    /// Include chains before the error, and source excerpt, notes or
+    /// details after it.
+    pub context: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BuildLogSummary {
+    pub log_file: PathBuf,
+    pub lines: usize,
+    /// Distinct errors in order of appearance; the first is usually the
+    /// root cause.
+    pub errors: Vec<BuildError>,
+    /// Distinct errors beyond `max_errors`, not listed.
+    pub omitted_errors: usize,
+    pub warnings: usize,
+    /// Follow-on failures dropped as consequences of earlier errors:
+    /// make's `*** Error`, `collect2: ld returned`, rpmbuild's bad exit
+    /// status.
+    pub cascades: usize,
+}
+
+/// Lines that only repeat that something earlier failed.
+fn is_cascade(line: &str) -> bool {
+    let trimmed = line.trim();
+    (trimmed.starts_with("make") && trimmed.contains("*** [") && trimmed.contains("Error"))
+        || trimmed.contains("collect2: error: ld returned")
+        || trimmed.contains("Bad exit status from")
+        || trimmed.starts_with("RPM build errors:")
+        || trimmed.starts_with("ninja: build stopped")
+        || (trimmed.starts_with("Target ") && trimmed.contains(" failed"))
+        || trimmed.contains("Build process failed")
+        || trimmed.starts_with("pub get failed")
+        || trimmed.starts_with("Failed to build bundle")
+        || trimmed.starts_with("error: could not compile")
+        || trimmed.starts_with("error: aborting due to")
+}
+
+/// Lines that lead up to a compiler error.
+fn is_lead_in(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("In file included from")
+        || (trimmed.starts_with("from ") && trimmed.ends_with([',', ':']))
+        || line.contains(": In function")
+        || line.contains(": In member function")
+        || line.contains(": In constructor")
+        || line.contains(": In instantiation of")
+        || line.contains(": in function")
+}
+
+/// `file:line[:column]` at the start of `location`.
+fn split_location(location: &str) -> (Option<String>, Option<usize>, Option<usize>) {
+    let mut parts = location.split(':');
+    let file = parts.next().map(str::trim).filter(|file| !file.is_empty());
+    let line = parts.next().and_then(|line| line.trim().parse().ok());
+    let column = parts.next().and_then(|column| column.trim().parse().ok());
+    // `g++: error: ...` names the tool, not a file.
+    let file = file.filter(|file| line.is_some() || file.contains(['.', '/']));
+    (file.map(str::to_string), line, column)
+}
+
+/// The error a log line reports, without context.
+fn parse_error(line: &str, log_line: usize) -> Option<BuildError> {
+    let error = |kind, (file, line, column), message: &str| BuildError {
+        kind,
+        file,
+        line,
+        column,
+        message: message.trim().to_string(),
+        log_line,
+        occurrences: 1,
+        context: Vec::new(),
+    };
+    let trimmed = line.trim();
+    if let Some(rest) = trimmed.strip_prefix("Project ERROR:") {
+        return Some(error(ErrorKind::Qmake, (None, None, None), rest));
+    }
+    if let Some(rest) = trimmed.strip_prefix("CMake Error") {
+        let rest = rest.trim_start();
+        let location = rest
+            .strip_prefix("at ")
+            .and_then(|at| at.split_whitespace().next())
+            .map(split_location)
+            .unwrap_or((None, None, None));
+        return Some(error(ErrorKind::Cmake, location, rest));
+    }
+    if trimmed.starts_with("make") && trimmed.contains("*** No rule to make target") {
+        let message = trimmed.split_once("*** ").map_or(trimmed, |(_, m)| m);
+        return Some(error(ErrorKind::Make, (None, None, None), message));
+    }
+    if let Some(at) = trimmed.find("undefined reference to") {
+        let location = trimmed[..at].trim_end_matches([':', ' ']);
+        let location = location.rsplit(": ").next().unwrap_or(location);
+        let file = location.split_once(":(").map(|(file, _)| file.to_string());
+        return Some(error(ErrorKind::Linker, (file, None, None), &trimmed[at..]));
+    }
+    if let Some(at) = trimmed.find("cannot find -l") {
+        return Some(error(ErrorKind::Linker, (None, None, None), &trimmed[at..]));
+    }
+    // The Dart frontend capitalizes: `lib/main.dart:12:5: Error: ...`.
+    let marker = ": Error: ";
+    if let Some(at) = trimmed.find(marker) {
+        let location = split_location(&trimmed[..at]);
+        if location.1.is_some() {
+            let message = &trimmed[at + marker.len()..];
+            return Some(error(ErrorKind::Dart, location, message));
+        }
+    }
+    if trimmed.starts_with("Because ") || trimmed.ends_with("version solving failed.") {
+        return Some(error(ErrorKind::Pub, (None, None, None), trimmed));
+    }
+    for marker in [": fatal error: ", ": error: "] {
+        if let Some(at) = trimmed.find(marker) {
+            let location = &trimmed[..at];
+            let message = &trimmed[at + marker.len()..];
+            if location.starts_with("collect2") || location.ends_with("ld") {
+                return Some(error(ErrorKind::Linker, (None, None, None), message));
+            }
+            return Some(error(
+                ErrorKind::Compiler,
+                split_location(location),
+                message,
+            ));
+        }
+    }
+    // rustc: `error[E0425]: ...`, its location on a following `-->` line.
+    if let Some((code, message)) = trimmed
+        .strip_prefix("error[")
+        .and_then(|rest| rest.split_once("]: "))
+    {
+        let message = format!("{message} [{code}]");
+        return Some(error(ErrorKind::Rustc, (None, None, None), &message));
+    }
+    if let Some(rest) = trimmed.strip_prefix("error: linking with") {
+        return Some(error(ErrorKind::Linker, (None, None, None), rest));
+    }
+    if let Some(rest) = trimmed.strip_prefix("error: ") {
+        return Some(error(ErrorKind::Rpmbuild, (None, None, None), rest));
+    }
+    if let Some(rest) = trimmed
+        .strip_prefix("Error: ")
+        .or_else(|| trimmed.strip_prefix("Exception: "))
+    {
+        return Some(error(ErrorKind::Flutter, (None, None, None), rest));
+    }
+    None
+}
+
+/// Errors in the log at `path`, read line by line so that logs of any size
+/// are handled.
+pub fn analyze(path: &Path, max_errors: usize) -> Result<BuildLogSummary> {
+    let file = std::fs::File::open(path)
+        .map_err(|err| Error::InvalidArgument(format!("{}: {err}", path.display())))?;
+    let mut reader = std::io::BufReader::new(file);
+    let mut summary = BuildLogSummary {
+        log_file: path.to_path_buf(),
+        lines: 0,
+        errors: Vec::new(),
+        omitted_errors: 0,
+        warnings: 0,
+        cascades: 0,
+    };
+    let mut lead_in: VecDeque<String> = VecDeque::new();
+    // Index of the error still collecting trailing context, and how many
+    // more lines it takes.
+    let mut open: Option<(usize, usize)> = None;
+    let mut buffer = Vec::new();
+    while reader.read_until(b'\n', &mut buffer)? > 0 {
+        summary.lines += 1;
+        let line = String::from_utf8_lossy(&buffer)
+            .trim_end_matches(['\n', '\r'])
+            .to_string();
+        buffer.clear();
+
+        if is_cascade(&line) {
+            summary.cascades += 1;
+            open = None;
+            continue;
+        }
+        if line.contains(": warning: ")
+            || line.contains(": Warning: ")
+            || (line.starts_with("warning: ") && !line.contains(" generated "))
+        {
+            summary.warnings += 1;
+            open = None;
+            continue;
+        }
+        if let Some(mut error) = parse_error(&line, summary.lines) {
+            if !matches!(error.kind, ErrorKind::Compiler | ErrorKind::Linker) {
+                lead_in.clear();
+            }
+            // rustc's location comes later, so its errors cannot be told
+            // apart yet.
+            let duplicate = summary.errors.iter().position(|e| {
+                error.kind != ErrorKind::Rustc
+                    && e.kind == error.kind
+                    && e.file == error.file
+                    && e.line == error.line
+                    && e.message == error.message
+            });
+            match duplicate {
+                Some(index) => {
+                    summary.errors[index].occurrences += 1;
+                    open = None;
+                }
+                None if summary.errors.len() < max_errors => {
+                    error.context = lead_in.drain(..).collect();
+                    summary.errors.push(error);
+                    open = Some((summary.errors.len() - 1, CONTEXT_LINES));
+                }
+                None => {
+                    summary.omitted_errors += 1;
+                    open = None;
+                }
+            }
+            lead_in.clear();
+            continue;
+        }
+        if is_lead_in(&line) {
+            if lead_in.len() == CONTEXT_LINES {
+                lead_in.pop_front();
+            }
+            lead_in.push_back(line.trim().to_string());
+            open = None;
+            continue;
+        }
+        lead_in.clear();
+        // rustc names the location after the message; a plain `error:`
+        // with one is rustc's too.
+        if let (Some((index, _)), Some(location)) = (open, line.trim().strip_prefix("--> ")) {
+            let error = &mut summary.errors[index];
+            if error.file.is_none() {
+                (error.file, error.line, error.column) = split_location(location);
+                if error.kind == ErrorKind::Rpmbuild {
+                    error.kind = ErrorKind::Rustc;
+                }
+            }
+        }
+        let follows = line.starts_with([' ', '\t']) || line.contains(": note: ");
+        match open {
+            Some((index, left)) if follows && left > 0 && !line.trim().is_empty() => {
+                summary.errors[index]
+                    .context
+                    .push(line.trim_end().to_string());
+                open = Some((index, left - 1));
+            }
+            _ => open = None,
+        }
+    }
+    Ok(summary)
+}
+
+/// The newest build log under `dir`, only `project`'s when given.
+fn newest_build_log(dir: &Path, project: Option<&str>) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            name.ends_with(".log")
+                && name.contains("-build-")
+                && project.is_none_or(|project| name.starts_with(&format!("{project}-build-")))
+        })
+        .max_by_key(|path| path.metadata().and_then(|meta| meta.modified()).ok())
+}
+
+/// The log `params` select: the given one, else the newest build log.
+fn select_log(server: &AuroraServer, params: &AnalyzeBuildLogParams) -> Result<PathBuf> {
+    if let Some(log) = &params.log {
+        return Ok(expand_tilde(Path::new(log)));
+    }
+    let project = match &params.project {
+        Some(project) => {
+            let dir = server.state().config.sdk.project(Some(project))?;
+            dir.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        }
+        None => None,
+    };
+    let builds = server.state().config.data_dir().join("builds");
+    newest_build_log(&builds, project.as_deref()).ok_or_else(|| {
+        Error::InvalidArgument(format!(
+            "no build log in {}; pass log or run build_project first",
+            builds.display()
+        ))
+    })
+}
+
+#[tool_router(router = buildlog_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Analyze a build log of any size: extract compiler, linker, make, qmake, CMake, rpmbuild, rustc and Flutter (Dart, pub, flutter-aurora) errors with file, line and surrounding context, collapse repeated errors and drop follow-on failures, and return a compact summary plus a link to the raw log. Defaults to the newest log of build_project, flutter_build or build_rust.",
+        output_schema = cached_schema_for_type::<BuildLogSummary>(),
+        annotations(read_only_hint = true)
+    )]
+    pub async fn analyze_build_log(
+        &self,
+        Parameters(params): Parameters<AnalyzeBuildLogParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let max_errors = params.max_errors.unwrap_or(DEFAULT_MAX_ERRORS).max(1);
+        let summary = select_log(self, &params).and_then(|log| analyze(&log, max_errors));
+        tool_result(summary.and_then(|summary| {
+            let link = file_link(&summary.log_file, Some("text/plain"));
+            let mut result = CallToolResult::structured(
+                serde_json::to_value(&summary).map_err(std::io::Error::other)?,
+            );
+            result.content.push(link);
+            Ok(result)
+        }))
+    }
+}
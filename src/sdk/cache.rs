@@ -0,0 +1,407 @@
+//! `build_cache_stats`, `warm_build_cache` and `clear_build_cache`: the
+//! Build Engine's ccache and the project's object files, per build target.
+//!
+//! Each statistics call records a snapshot under the data directory's
+//! `build-cache/`, so the next one can tell what happened in between.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rmcp::handler::server::tool::cached_schema_for_type;
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::model::CallToolResult;
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::build::{BuildParams, build};
+use super::project::find_files;
+use super::run_logged;
+use crate::error::{Result, require_confirmation, tool_result};
+use crate::resources::file_link;
+use crate::server::AuroraServer;
+
+/// Prints ccache's statistics in whichever form the installed version
+/// supports, then its size limit.
+const STATS_SCRIPT: &str = "ccache --print-stats 2>/dev/null || ccache -s; printf 'max_size\\t%s\\n' \"$(ccache -k max_size 2>/dev/null)\"";
+
+/// How deep to look for object files.
+const OBJECT_DEPTH: usize = 8;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BuildCacheParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Build target (default: the configured one).
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WarmBuildCacheParams {
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Warm the cache for debug builds (`--enable-debug`).
+    #[serde(default)]
+    pub debug: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ClearBuildCacheParams {
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Clear the target's ccache (`ccache -C`).
+    #[serde(default = "default_true")]
+    pub ccache: bool,
+    /// Delete the project's object files.
+    #[serde(default = "default_true")]
+    pub objects: bool,
+    /// Must be true; cleared caches make the next build a full one.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CcacheStats {
+    pub cache_dir: Option<String>,
+    /// Direct and preprocessed hits.
+    pub hits: u64,
+    pub misses: u64,
+    /// Hits in percent of cacheable compilations.
+    pub hit_rate: f64,
+    pub files: u64,
+    pub size_bytes: u64,
+    pub max_size_bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CacheActivity {
+    /// When the previous snapshot was taken, seconds since the epoch.
+    pub since: u64,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ObjectFiles {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BuildCacheStats {
+    pub target: Option<String>,
+    /// Absent when ccache is not installed in the target.
+    pub ccache: Option<CcacheStats>,
+    /// Hits and misses since the previous statistics call for the target.
+    pub since_last: Option<CacheActivity>,
+    pub objects: ObjectFiles,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WarmReport {
+    pub success: bool,
+    pub duration_secs: u64,
+    /// Cache hits and misses during the build.
+    pub hits: u64,
+    pub misses: u64,
+    /// Why the cache may not have helped, when it did not.
+    pub note: Option<String>,
+    pub cache: BuildCacheStats,
+    pub log_file: PathBuf,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ClearReport {
+    pub target: Option<String>,
+    pub ccache_cleared: bool,
+    pub objects_removed: usize,
+    pub bytes_freed: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+    recorded_at: u64,
+    stats: CcacheStats,
+}
+
+/// `1.2 GB`, `512.0 kB` or `5.0G` in bytes.
+fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let number: f64 = text[..split].parse().ok()?;
+    let factor = match text[split..]
+        .trim()
+        .chars()
+        .next()
+        .map(|c| c.to_ascii_uppercase())
+    {
+        None | Some('B') => 1.0,
+        Some('K') => 1024.0,
+        Some('M') => 1024.0 * 1024.0,
+        Some('G') => 1024.0 * 1024.0 * 1024.0,
+        Some('T') => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        Some(_) => return None,
+    };
+    Some((number * factor) as u64)
+}
+
+/// ccache statistics from `--print-stats` (ccache 4, tab-separated keys)
+/// or `-s` (ccache 3, labelled columns).
+fn parse_ccache(output: &str) -> Option<CcacheStats> {
+    let mut stats = CcacheStats::default();
+    let mut seen = false;
+    for line in output.lines() {
+        if let Some((key, value)) = line.split_once('\t') {
+            let value = value.trim();
+            let number = value.parse::<u64>().ok();
+            match key.trim() {
+                "direct_cache_hit" | "preprocessed_cache_hit" => {
+                    stats.hits += number.unwrap_or_default()
+                }
+                "cache_miss" => stats.misses += number.unwrap_or_default(),
+                "files_in_cache" => stats.files = number.unwrap_or_default(),
+                "cache_size_kibibyte" => stats.size_bytes = number.unwrap_or_default() * 1024,
+                "max_size" => stats.max_size_bytes = parse_size(value).filter(|&size| size > 0),
+                _ => continue,
+            }
+            seen |= key != "max_size";
+            continue;
+        }
+        let split = line.find("  ").unwrap_or(line.len());
+        let (label, value) = (line[..split].trim(), line[split..].trim());
+        let number = value.parse::<u64>().ok();
+        match label {
+            "cache directory" => stats.cache_dir = Some(value.to_string()),
+            "cache hit (direct)" | "cache hit (preprocessed)" => {
+                stats.hits += number.unwrap_or_default()
+            }
+            "cache miss" => stats.misses += number.unwrap_or_default(),
+            "files in cache" => stats.files = number.unwrap_or_default(),
+            "cache size" => stats.size_bytes = parse_size(value).unwrap_or_default(),
+            "max cache size" => stats.max_size_bytes = parse_size(value),
+            _ => continue,
+        }
+        seen = true;
+    }
+    let total = stats.hits + stats.misses;
+    if total > 0 {
+        stats.hit_rate = (stats.hits as f64 * 1000.0 / total as f64).round() / 10.0;
+    }
+    seen.then_some(stats)
+}
+
+fn object_files(dir: &Path) -> Vec<(PathBuf, u64)> {
+    find_files(dir, "o", OBJECT_DEPTH)
+        .into_iter()
+        .map(|path| {
+            let size = path.metadata().map(|meta| meta.len()).unwrap_or_default();
+            (path, size)
+        })
+        .collect()
+}
+
+fn snapshot_path(server: &AuroraServer, target: Option<&str>) -> PathBuf {
+    server
+        .state()
+        .config
+        .data_dir()
+        .join("build-cache")
+        .join(format!("{}.json", target.unwrap_or("default")))
+}
+
+/// The target's ccache statistics, or `None` when ccache is missing.
+async fn ccache_stats(
+    server: &AuroraServer,
+    dir: &Path,
+    target: Option<&str>,
+) -> Result<Option<CcacheStats>> {
+    let options = &server.state().config.sdk;
+    let mut command = options.build_shell("sh", target)?;
+    command.arg("-c").arg(STATS_SCRIPT).current_dir(dir);
+    let log_file = super::log_file(server, dir, "ccache-stats");
+    let run = run_logged(command, &log_file, options.build_timeout()).await?;
+    Ok(parse_ccache(&run.output))
+}
+
+/// Current statistics, compared with and then replacing the previous
+/// snapshot for the target.
+pub async fn stats(
+    server: &AuroraServer,
+    dir: &Path,
+    target: Option<&str>,
+) -> Result<BuildCacheStats> {
+    let ccache = ccache_stats(server, dir, target).await?;
+    let path = snapshot_path(server, target);
+    let previous: Option<Snapshot> = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok());
+    let since_last = match (&ccache, previous) {
+        // Counters restart when the cache is zeroed; only report growth.
+        (Some(now), Some(before))
+            if now.hits + now.misses >= before.stats.hits + before.stats.misses =>
+        {
+            Some(CacheActivity {
+                since: before.recorded_at,
+                hits: now.hits.saturating_sub(before.stats.hits),
+                misses: now.misses.saturating_sub(before.stats.misses),
+            })
+        }
+        _ => None,
+    };
+    if let Some(stats) = &ccache {
+        let snapshot = Snapshot {
+            recorded_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            stats: stats.clone(),
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(
+            &path,
+            serde_json::to_string_pretty(&snapshot).map_err(std::io::Error::other)?,
+        )?;
+    }
+    let objects = object_files(dir);
+    Ok(BuildCacheStats {
+        target: target.map(str::to_string),
+        ccache,
+        since_last,
+        objects: ObjectFiles {
+            files: objects.len(),
+            bytes: objects.iter().map(|(_, size)| size).sum(),
+        },
+    })
+}
+
+async fn warm(server: &AuroraServer, params: WarmBuildCacheParams) -> Result<WarmReport> {
+    let options = &server.state().config.sdk;
+    let dir = options.project(params.project.as_deref())?;
+    let target = options.target(params.target.as_deref())?;
+    // Snapshot first so that the activity after the build is the build's.
+    stats(server, &dir, target.as_deref()).await?;
+    let report = build(
+        server,
+        BuildParams {
+            project: Some(dir.display().to_string()),
+            target: target.clone(),
+            debug: params.debug,
+        },
+    )
+    .await?;
+    let after = stats(server, &dir, target.as_deref()).await?;
+    let (hits, misses) = after
+        .since_last
+        .as_ref()
+        .map_or((0, 0), |activity| (activity.hits, activity.misses));
+    let note = if after.ccache.is_none() {
+        Some("ccache is not installed in the target's build environment".to_string())
+    } else if hits + misses == 0 {
+        Some("the build did not go through ccache; it may have been up to date, or the compiler is not wrapped by ccache".to_string())
+    } else if hits == 0 {
+        Some("every compilation missed; the cache was cold or compiler flags changed".to_string())
+    } else {
+        None
+    };
+    Ok(WarmReport {
+        success: report.success,
+        duration_secs: report.duration_secs,
+        hits,
+        misses,
+        note,
+        cache: after,
+        log_file: report.log_file,
+    })
+}
+
+#[tool_router(router = cache_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Build cache statistics for a target: the Build Engine's ccache hits, misses, hit rate, size and limit, the hits and misses since the previous call, and the project's object files. Use it to explain slow or full rebuilds."
+    )]
+    pub async fn build_cache_stats(
+        &self,
+        Parameters(params): Parameters<BuildCacheParams>,
+    ) -> Result<Json<BuildCacheStats>> {
+        let options = &self.state().config.sdk;
+        let dir = options.project(params.project.as_deref())?;
+        let target = options.target(params.target.as_deref())?;
+        stats(self, &dir, target.as_deref()).await.map(Json)
+    }
+
+    #[tool(
+        description = "Warm the build cache by building the project for a target, then report the ccache hits and misses of that build with a note when the cache did not help, plus a link to the build log.",
+        output_schema = cached_schema_for_type::<WarmReport>()
+    )]
+    pub async fn warm_build_cache(
+        &self,
+        Parameters(params): Parameters<WarmBuildCacheParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        tool_result(warm(self, params).await.and_then(|report| {
+            let link = file_link(&report.log_file, Some("text/plain"));
+            let mut result = CallToolResult::structured(
+                serde_json::to_value(&report).map_err(std::io::Error::other)?,
+            );
+            result.content.push(link);
+            Ok(result)
+        }))
+    }
+
+    #[tool(
+        description = "Clear a target's ccache in the Build Engine and/or delete the project's object files, forcing the next build to be a full one. Requires confirm=true.",
+        annotations(destructive_hint = true)
+    )]
+    pub async fn clear_build_cache(
+        &self,
+        Parameters(params): Parameters<ClearBuildCacheParams>,
+    ) -> Result<Json<ClearReport>> {
+        let options = &self.state().config.sdk;
+        let dir = options.project(params.project.as_deref())?;
+        let target = options.target(params.target.as_deref())?;
+        require_confirmation(params.confirm, || {
+            format!("clearing the build cache of {}", dir.display())
+        })?;
+
+        let mut ccache_cleared = false;
+        if params.ccache {
+            let mut command = options.build_shell("ccache", target.as_deref())?;
+            command.arg("-C").current_dir(&dir);
+            let log_file = super::log_file(self, &dir, "ccache-clear");
+            let run = run_logged(command, &log_file, options.build_timeout()).await?;
+            ccache_cleared = run.status == 0;
+            if ccache_cleared {
+                let _ = std::fs::remove_file(snapshot_path(self, target.as_deref()));
+            }
+        }
+        let (mut objects_removed, mut bytes_freed) = (0, 0);
+        if params.objects {
+            for (path, size) in object_files(&dir) {
+                if std::fs::remove_file(&path).is_ok() {
+                    objects_removed += 1;
+                    bytes_freed += size;
+                }
+            }
+        }
+        Ok(Json(ClearReport {
+            target,
+            ccache_cleared,
+            objects_removed,
+            bytes_freed,
+        }))
+    }
+}
@@ -0,0 +1,756 @@
+//! Rust crates for Aurora targets: cargo configured to cross-compile on the
+//! host against a Build Engine target's sysroot, builds with it, and the
+//! resulting binaries packaged into the project's RPM.
+
+use std::collections::BTreeMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use rmcp::handler::server::tool::cached_schema_for_type;
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::model::CallToolResult;
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use super::buildlog::{self, BuildError};
+use super::engine::query;
+use super::project::ProjectInfo;
+use super::sandbox::{Action, FileChange, append_to_section};
+use super::{SdkOptions, find_in_path, run_logged};
+use crate::config::{expand_tilde, home_dir};
+use crate::device::tail_lines;
+use crate::error::{Error, Result, require_confirmation, tool_result};
+use crate::resources::file_link;
+use crate::server::AuroraServer;
+
+const LOG_TAIL_LINES: usize = 40;
+const MAX_ERRORS: usize = 20;
+
+/// Where `package_rust_binary` puts binaries in the project, one directory
+/// per architecture, for the spec's `%install` to pick up.
+pub(crate) const BIN_DIR: &str = "rust-bin";
+
+/// Binaries other than the app's own executable belong under its data
+/// directory.
+pub(crate) const DEFAULT_INSTALL_DIR: &str = "%{_datadir}/%{name}/bin";
+
+/// Target architectures: `(sfdk target suffix, Rust triple, cross gcc
+/// names to look for)`.
+const ARCHITECTURES: &[(&str, &str, &[&str])] = &[
+    (
+        "armv7hl",
+        "armv7-unknown-linux-gnueabihf",
+        &[
+            "arm-linux-gnueabihf-gcc",
+            "armv7hl-meego-linux-gnueabi-gcc",
+            "arm-none-linux-gnueabihf-gcc",
+        ],
+    ),
+    (
+        "aarch64",
+        "aarch64-unknown-linux-gnu",
+        &[
+            "aarch64-linux-gnu-gcc",
+            "aarch64-meego-linux-gnu-gcc",
+            "aarch64-none-linux-gnu-gcc",
+        ],
+    ),
+    (
+        "x86_64",
+        "x86_64-unknown-linux-gnu",
+        &["x86_64-linux-gnu-gcc", "x86_64-meego-linux-gnu-gcc"],
+    ),
+    (
+        "i486",
+        "i686-unknown-linux-gnu",
+        &["i686-linux-gnu-gcc", "i486-meego-linux-gnu-gcc"],
+    ),
+];
+
+/// How cargo cross-compiles for one build target.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CrossSettings {
+    /// sfdk build target, e.g. `AuroraOS-5.1.3.85-MB2-armv7hl`.
+    pub target: String,
+    /// Its architecture, e.g. `armv7hl`.
+    pub arch: String,
+    /// Rust target triple, e.g. `armv7-unknown-linux-gnueabihf`.
+    pub triple: String,
+    /// The target's sysroot on the host.
+    pub sysroot: PathBuf,
+    pub sysroot_exists: bool,
+    /// Cross linker: a target gcc, else clang with lld; `None` when neither
+    /// is installed.
+    pub linker: Option<PathBuf>,
+    pub rustflags: Vec<String>,
+    /// Per-target settings for build scripts: the `cc` and `pkg-config`
+    /// crates' compiler, flags and sysroot.
+    pub env: BTreeMap<String, String>,
+}
+
+/// The architecture and Rust triple of an sfdk build target.
+pub(crate) fn architecture(
+    target: &str,
+) -> Result<(&'static str, &'static str, &'static [&'static str])> {
+    let suffix = target.rsplit('-').next().unwrap_or(target);
+    ARCHITECTURES
+        .iter()
+        .find(|(arch, _, _)| *arch == suffix)
+        .copied()
+        .ok_or_else(|| {
+            Error::InvalidArgument(format!(
+                "{target} is not an armv7hl, aarch64, x86_64 or i486 build target"
+            ))
+        })
+}
+
+/// The build target to use: `requested`, else the configured one.
+fn require_target(options: &SdkOptions, requested: Option<&str>) -> Result<String> {
+    options.target(requested)?.ok_or_else(|| {
+        Error::InvalidArgument(
+            "pass target or set target in the [sdk] config table, e.g. AuroraOS-5.1.3.85-MB2-armv7hl"
+                .to_string(),
+        )
+    })
+}
+
+fn cross_settings(
+    options: &SdkOptions,
+    target: &str,
+    linker: Option<&str>,
+) -> Result<CrossSettings> {
+    let (arch, triple, gcc_names) = architecture(target)?;
+    let sysroot = options.sysroot(target)?;
+    let linker = match linker {
+        Some(linker) => Some(expand_tilde(Path::new(linker))),
+        None => gcc_names
+            .iter()
+            .find_map(|name| find_in_path(name))
+            .or_else(|| find_in_path("clang")),
+    };
+    let clang = linker
+        .as_ref()
+        .and_then(|linker| linker.file_name())
+        .is_some_and(|name| name.to_string_lossy().starts_with("clang"));
+
+    let sysroot_flag = format!("--sysroot={}", sysroot.display());
+    let mut rustflags = vec!["-C".to_string(), format!("link-arg={sysroot_flag}")];
+    let mut cflags = sysroot_flag;
+    if clang {
+        for arg in [format!("--target={triple}"), "-fuse-ld=lld".to_string()] {
+            rustflags.extend(["-C".to_string(), format!("link-arg={arg}")]);
+        }
+        cflags.push_str(&format!(" --target={triple}"));
+    }
+
+    let suffix = triple.replace('-', "_");
+    let mut env = BTreeMap::new();
+    if let Some(linker) = &linker {
+        let cxx = linker.with_file_name(
+            linker
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .replacen(
+                    if clang { "clang" } else { "gcc" },
+                    if clang { "clang++" } else { "g++" },
+                    1,
+                ),
+        );
+        env.insert(
+            format!("CC_{suffix}"),
+            linker.to_string_lossy().into_owned(),
+        );
+        env.insert(format!("CXX_{suffix}"), cxx.to_string_lossy().into_owned());
+    }
+    env.insert(format!("CFLAGS_{suffix}"), cflags.clone());
+    env.insert(format!("CXXFLAGS_{suffix}"), cflags);
+    env.insert(
+        format!("PKG_CONFIG_SYSROOT_DIR_{suffix}"),
+        sysroot.to_string_lossy().into_owned(),
+    );
+    env.insert(
+        format!("PKG_CONFIG_LIBDIR_{suffix}"),
+        [
+            "usr/lib/pkgconfig",
+            "usr/lib64/pkgconfig",
+            "usr/share/pkgconfig",
+        ]
+        .iter()
+        .map(|dir| sysroot.join(dir).to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join(":"),
+    );
+    env.insert(format!("PKG_CONFIG_ALLOW_CROSS_{suffix}"), "1".to_string());
+
+    Ok(CrossSettings {
+        target: target.to_string(),
+        arch: arch.to_string(),
+        triple: triple.to_string(),
+        sysroot_exists: sysroot.is_dir(),
+        sysroot,
+        linker,
+        rustflags,
+        env,
+    })
+}
+
+/// The crate to build: `requested` relative to the project, else the
+/// project itself, else its only subdirectory with a `Cargo.toml`.
+fn crate_dir(project: &Path, requested: Option<&str>) -> Result<PathBuf> {
+    if let Some(requested) = requested {
+        let dir = project.join(expand_tilde(Path::new(requested)));
+        if !dir.join("Cargo.toml").is_file() {
+            return Err(Error::InvalidArgument(format!(
+                "{} has no Cargo.toml",
+                dir.display()
+            )));
+        }
+        return Ok(dir);
+    }
+    if project.join("Cargo.toml").is_file() {
+        return Ok(project.to_path_buf());
+    }
+    let mut crates: Vec<PathBuf> = std::fs::read_dir(project)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.join("Cargo.toml").is_file())
+        .collect();
+    match crates.len() {
+        1 => Ok(crates.remove(0)),
+        0 => Err(Error::InvalidArgument(format!(
+            "{} has no Cargo.toml; pass crate_dir",
+            project.display()
+        ))),
+        _ => Err(Error::InvalidArgument(format!(
+            "{} has several crates; pass crate_dir",
+            project.display()
+        ))),
+    }
+}
+
+/// Cargo's output directory for `crate_dir`: the `target/` of the
+/// workspace it belongs to.
+fn target_dir(crate_dir: &Path) -> PathBuf {
+    crate_dir
+        .ancestors()
+        .find(|dir| {
+            std::fs::read_to_string(dir.join("Cargo.toml"))
+                .is_ok_and(|text| text.lines().any(|line| line.trim() == "[workspace]"))
+        })
+        .unwrap_or(crate_dir)
+        .join("target")
+}
+
+/// Executables directly in `dir`, sorted.
+fn executables(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut found: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .metadata()
+                .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        })
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_none())
+        .collect();
+    found.sort();
+    found
+}
+
+fn cargo() -> Result<PathBuf> {
+    find_in_path("cargo")
+        .or_else(|| {
+            home_dir()
+                .map(|home| home.join(".cargo").join("bin").join("cargo"))
+                .filter(|path| path.is_file())
+        })
+        .ok_or_else(|| {
+            Error::Config(
+                "cargo not found on PATH or in ~/.cargo/bin; install Rust with rustup".to_string(),
+            )
+        })
+}
+
+/// Whether rustup has the standard library for `triple`, when rustup is
+/// installed.
+async fn rust_target_installed(triple: &str) -> Option<bool> {
+    let rustup = find_in_path("rustup")?;
+    let mut command = Command::new(rustup);
+    command.args(["target", "list", "--installed"]);
+    let (status, output) = query(command).await.ok()?;
+    (status == 0).then(|| output.lines().any(|line| line.trim() == triple))
+}
+
+/// `.cargo/config.toml` with `settings` merged into `existing`, and whether
+/// that replaces a different `[target.<triple>]` table.
+fn merged_config(existing: &str, settings: &CrossSettings) -> Result<(String, bool)> {
+    let mut config: toml::Table = existing
+        .parse()
+        .map_err(|err| Error::InvalidArgument(format!(".cargo/config.toml: {err}")))?;
+    let mut table = toml::Table::new();
+    if let Some(linker) = &settings.linker {
+        table.insert(
+            "linker".to_string(),
+            toml::Value::String(linker.to_string_lossy().into_owned()),
+        );
+    }
+    table.insert(
+        "rustflags".to_string(),
+        toml::Value::Array(
+            settings
+                .rustflags
+                .iter()
+                .cloned()
+                .map(toml::Value::String)
+                .collect(),
+        ),
+    );
+    let targets = config
+        .entry("target")
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    let Some(targets) = targets.as_table_mut() else {
+        return Err(Error::InvalidArgument(
+            ".cargo/config.toml: target is not a table".to_string(),
+        ));
+    };
+    let previous = targets.insert(settings.triple.clone(), toml::Value::Table(table.clone()));
+    let replaces = previous.is_some_and(|previous| previous != toml::Value::Table(table));
+
+    let env = config
+        .entry("env")
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    let Some(env) = env.as_table_mut() else {
+        return Err(Error::InvalidArgument(
+            ".cargo/config.toml: env is not a table".to_string(),
+        ));
+    };
+    for (name, value) in &settings.env {
+        env.insert(name.clone(), toml::Value::String(value.clone()));
+    }
+    let text = toml::to_string(&config).map_err(std::io::Error::other)?;
+    Ok((text, replaces))
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ConfigureCargoParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Crate directory relative to the project (default: the project
+    /// itself, else its only subdirectory with a `Cargo.toml`).
+    #[serde(default)]
+    pub crate_dir: Option<String>,
+    /// Build target, e.g. `AuroraOS-5.1.3.85-MB2-armv7hl` (default: the
+    /// configured one).
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Linker to use (default: a cross gcc for the architecture on `PATH`,
+    /// else clang with lld).
+    #[serde(default)]
+    pub linker: Option<String>,
+    /// Write `.cargo/config.toml` instead of only returning it.
+    #[serde(default)]
+    pub write: bool,
+    /// Must be true to replace existing, different settings for the target.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CargoConfig {
+    pub settings: CrossSettings,
+    /// Whether rustup has the target's standard library; `None` without
+    /// rustup.
+    pub rust_target_installed: Option<bool>,
+    pub config_file: PathBuf,
+    pub action: Action,
+    /// The config file after the change.
+    pub content: String,
+    /// Whether it was written; otherwise it is a preview.
+    pub written: bool,
+    /// Missing pieces to install before building.
+    pub notes: Vec<String>,
+}
+
+/// Missing pieces of a cross build for `settings`.
+fn setup_notes(settings: &CrossSettings, rust_target_installed: Option<bool>) -> Vec<String> {
+    let mut notes = Vec::new();
+    if !settings.sysroot_exists {
+        notes.push(format!(
+            "sysroot {} does not exist; check the target with inspect_build_environment or set sysroots in the [sdk] config table",
+            settings.sysroot.display()
+        ));
+    }
+    if settings.linker.is_none() {
+        let gcc = architecture(&settings.target)
+            .map(|(_, _, names)| names[0])
+            .unwrap_or("gcc");
+        notes.push(format!(
+            "no linker for {}: install {gcc} or clang with lld, or pass linker",
+            settings.triple
+        ));
+    }
+    if rust_target_installed == Some(false) {
+        notes.push(format!("run `rustup target add {}`", settings.triple));
+    }
+    notes
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BuildRustParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Crate directory relative to the project (default: the project
+    /// itself, else its only subdirectory with a `Cargo.toml`).
+    #[serde(default)]
+    pub crate_dir: Option<String>,
+    /// Build target, e.g. `AuroraOS-5.1.3.85-MB2-armv7hl` (default: the
+    /// configured one).
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Linker to use (default: a cross gcc for the architecture on `PATH`,
+    /// else clang with lld).
+    #[serde(default)]
+    pub linker: Option<String>,
+    /// Build the dev profile instead of release.
+    #[serde(default)]
+    pub debug: bool,
+    /// Only this binary (`--bin`).
+    #[serde(default)]
+    pub bin: Option<String>,
+    /// Cargo features to enable.
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RustBuildReport {
+    pub crate_dir: PathBuf,
+    pub target: String,
+    pub triple: String,
+    pub success: bool,
+    pub exit_status: i32,
+    pub duration_secs: u64,
+    /// Executables in the target's output directory.
+    pub binaries: Vec<PathBuf>,
+    /// rustc and linker errors with their locations.
+    pub errors: Vec<BuildError>,
+    pub warnings: usize,
+    pub log_file: PathBuf,
+    pub log_tail: Vec<String>,
+}
+
+pub async fn build(server: &AuroraServer, params: BuildRustParams) -> Result<RustBuildReport> {
+    let options = &server.state().config.sdk;
+    let project = options.project(params.project.as_deref())?;
+    let crate_dir = crate_dir(&project, params.crate_dir.as_deref())?;
+    let target = require_target(options, params.target.as_deref())?;
+    let settings = cross_settings(options, &target, params.linker.as_deref())?;
+    let notes = setup_notes(&settings, None);
+    let Some(linker) = &settings.linker else {
+        return Err(Error::Config(notes.join("; ")));
+    };
+    if !settings.sysroot_exists {
+        return Err(Error::InvalidArgument(notes.join("; ")));
+    }
+
+    let log_file = super::log_file(server, &crate_dir, "build");
+    let triple = &settings.triple;
+    let mut command = Command::new(cargo()?);
+    command.args(["build", "--target", triple]);
+    // Passed as --config so that the crate's own config is left alone.
+    let linker = toml::Value::String(linker.to_string_lossy().into_owned());
+    let rustflags = toml::Value::Array(
+        settings
+            .rustflags
+            .iter()
+            .cloned()
+            .map(toml::Value::String)
+            .collect(),
+    );
+    command
+        .arg("--config")
+        .arg(format!("target.{triple}.linker={linker}"))
+        .arg("--config")
+        .arg(format!("target.{triple}.rustflags={rustflags}"));
+    if !params.debug {
+        command.arg("--release");
+    }
+    if let Some(bin) = &params.bin {
+        command.args(["--bin", bin]);
+    }
+    if !params.features.is_empty() {
+        command.args(["--features", &params.features.join(",")]);
+    }
+    command
+        .envs(&settings.env)
+        .env("CARGO_TERM_COLOR", "never")
+        .current_dir(&crate_dir);
+
+    let started = Instant::now();
+    let run = run_logged(command, &log_file, options.build_timeout()).await?;
+    let summary = buildlog::analyze(&log_file, MAX_ERRORS)?;
+    let profile = if params.debug { "debug" } else { "release" };
+    Ok(RustBuildReport {
+        binaries: executables(&target_dir(&crate_dir).join(triple).join(profile)),
+        crate_dir,
+        triple: settings.triple.clone(),
+        target,
+        success: run.status == 0,
+        exit_status: run.status,
+        duration_secs: started.elapsed().as_secs(),
+        errors: summary.errors,
+        warnings: summary.warnings,
+        log_tail: tail_lines(&run.output, LOG_TAIL_LINES),
+        log_file,
+    })
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PackageRustBinaryParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Crate directory relative to the project (default: the project
+    /// itself, else its only subdirectory with a `Cargo.toml`).
+    #[serde(default)]
+    pub crate_dir: Option<String>,
+    /// Build target the binary was built for (default: the configured one).
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Binary name (default: the only one built).
+    #[serde(default)]
+    pub binary: Option<String>,
+    /// Package the dev profile build instead of release.
+    #[serde(default)]
+    pub debug: bool,
+    /// Installation directory in the package (default:
+    /// `%{_datadir}/%{name}/bin`; `%{_bindir}` for the app's own
+    /// executable).
+    #[serde(default)]
+    pub install_dir: Option<String>,
+    /// Copy the binary and edit the spec instead of only returning the
+    /// changes.
+    #[serde(default)]
+    pub write: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RustPackage {
+    /// The built binary.
+    pub binary: PathBuf,
+    /// Its path in the installed package.
+    pub installed_as: String,
+    pub changes: Vec<FileChange>,
+    /// Whether the changes were written; otherwise they are a preview.
+    pub written: bool,
+    pub notes: Vec<String>,
+}
+
+#[tool_router(router = cargo_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Configure cargo to cross-compile a Rust crate on the host for an Aurora build target (armv7hl, aarch64, x86_64, i486): maps the target to its Rust triple, finds a cross linker (target gcc, else clang with lld) and the target's sysroot, and produces .cargo/config.toml with the linker, --sysroot link flags and the cc/pkg-config per-target settings build scripts need. Reports missing pieces such as the rustup target. Returns the config for review; set write=true to write it (replacing different settings for the target needs confirm=true).",
+        annotations(destructive_hint = true)
+    )]
+    pub async fn configure_cargo(
+        &self,
+        Parameters(params): Parameters<ConfigureCargoParams>,
+    ) -> Result<Json<CargoConfig>> {
+        let options = &self.state().config.sdk;
+        let project = options.project(params.project.as_deref())?;
+        let crate_dir = crate_dir(&project, params.crate_dir.as_deref())?;
+        let target = require_target(options, params.target.as_deref())?;
+        let settings = cross_settings(options, &target, params.linker.as_deref())?;
+        let rust_target_installed = rust_target_installed(&settings.triple).await;
+        let notes = setup_notes(&settings, rust_target_installed);
+
+        let config_file = crate_dir.join(".cargo").join("config.toml");
+        let existing = std::fs::read_to_string(&config_file).ok();
+        let (content, replaces) = merged_config(existing.as_deref().unwrap_or(""), &settings)?;
+        let action = match &existing {
+            None => Action::Created,
+            Some(old) if *old == content => Action::Unchanged,
+            Some(_) => Action::Updated,
+        };
+        if params.write && action != Action::Unchanged {
+            if replaces {
+                require_confirmation(params.confirm, || {
+                    format!(
+                        "replacing the [target.{}] settings in {}",
+                        settings.triple,
+                        config_file.display()
+                    )
+                })?;
+            }
+            if let Some(dir) = config_file.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&config_file, &content)?;
+        }
+        Ok(Json(CargoConfig {
+            settings,
+            rust_target_installed,
+            config_file,
+            action,
+            content,
+            written: params.write,
+            notes,
+        }))
+    }
+
+    #[tool(
+        description = "Cross-compile a Rust crate on the host for an Aurora build target with cargo, using the target's sysroot and a cross linker (no .cargo/config.toml needed). Returns success, the binaries built, rustc and linker errors with file and line, the log tail and a link to the full build log.",
+        output_schema = cached_schema_for_type::<RustBuildReport>()
+    )]
+    pub async fn build_rust(
+        &self,
+        Parameters(params): Parameters<BuildRustParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        tool_result(build(self, params).await.and_then(|report| {
+            let link = file_link(&report.log_file, Some("text/plain"));
+            let mut result = CallToolResult::structured(
+                serde_json::to_value(&report).map_err(std::io::Error::other)?,
+            );
+            result.content.push(link);
+            Ok(result)
+        }))
+    }
+
+    #[tool(
+        description = "Package a cross-compiled Rust binary into the project's RPM: copies it from the crate's target directory to rust-bin/<arch>/ in the project and adds the install line and %files entry to the spec (default location %{_datadir}/%{name}/bin). Build it with build_rust first. Returns the changes for review; set write=true to apply them.",
+        annotations(destructive_hint = true)
+    )]
+    pub async fn package_rust_binary(
+        &self,
+        Parameters(params): Parameters<PackageRustBinaryParams>,
+    ) -> Result<Json<RustPackage>> {
+        let options = &self.state().config.sdk;
+        let project = options.project(params.project.as_deref())?;
+        let crate_dir = crate_dir(&project, params.crate_dir.as_deref())?;
+        let target = require_target(options, params.target.as_deref())?;
+        let (arch, triple, _) = architecture(&target)?;
+        let profile = if params.debug { "debug" } else { "release" };
+        let output = target_dir(&crate_dir).join(triple).join(profile);
+        let binary = match &params.binary {
+            Some(name) => output.join(name),
+            None => {
+                let mut built = executables(&output);
+                if built.len() != 1 {
+                    return Err(Error::InvalidArgument(format!(
+                        "{} holds {} binaries; pass binary",
+                        output.display(),
+                        built.len()
+                    )));
+                }
+                built.remove(0)
+            }
+        };
+        if !binary.is_file() {
+            return Err(Error::InvalidArgument(format!(
+                "{} does not exist; build it with build_rust first",
+                binary.display()
+            )));
+        }
+        let name = binary
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let install_dir = params
+            .install_dir
+            .as_deref()
+            .unwrap_or(DEFAULT_INSTALL_DIR)
+            .trim_end_matches('/');
+        let installed_as = format!("{install_dir}/{name}");
+        let mut notes = Vec::new();
+        let mut changes = Vec::new();
+
+        let copy = project.join(BIN_DIR).join(arch).join(&name);
+        let contents = std::fs::read(&binary)?;
+        let action = match std::fs::read(&copy) {
+            Err(_) => Action::Created,
+            Ok(old) if old == contents => Action::Unchanged,
+            Ok(_) => Action::Updated,
+        };
+        changes.push(FileChange {
+            path: copy.strip_prefix(&project).unwrap_or(&copy).to_path_buf(),
+            action,
+            content: format!("{} ({} bytes)", binary.display(), contents.len()),
+        });
+
+        // rpmbuild runs %install in the project; `%{_target_cpu}` picks the
+        // copy for the target being built.
+        let install = format!(
+            "install -D -m 755 {BIN_DIR}/%{{_target_cpu}}/{name} %{{buildroot}}{installed_as}"
+        );
+        let mut spec_edit = None;
+        match ProjectInfo::inspect(&project).spec_file {
+            Some(spec) => {
+                let mut text = std::fs::read_to_string(&spec)?;
+                if !text.contains(&install) {
+                    for (section, line) in [("%install", &install), ("%files", &installed_as)] {
+                        match append_to_section(&text, section, std::slice::from_ref(line)) {
+                            Some(updated) => text = updated,
+                            None => notes.push(format!(
+                                "{} has no {section} section; add: {line}",
+                                spec.display()
+                            )),
+                        }
+                    }
+                    changes.push(FileChange {
+                        path: spec.strip_prefix(&project).unwrap_or(&spec).to_path_buf(),
+                        action: Action::Updated,
+                        content: format!("{install}\n{installed_as}"),
+                    });
+                    spec_edit = Some((spec, text));
+                }
+            }
+            None => notes.push(format!(
+                "the project has no spec; install {BIN_DIR}/{arch}/{name} as {installed_as} and list it under %files"
+            )),
+        }
+        let missing: Vec<&str> = ARCHITECTURES
+            .iter()
+            .map(|(other, _, _)| *other)
+            .filter(|other| *other != arch && project.join(BIN_DIR).join(other).is_dir())
+            .filter(|other| !project.join(BIN_DIR).join(other).join(&name).is_file())
+            .collect();
+        if !missing.is_empty() {
+            notes.push(format!(
+                "{name} is not built for {}; RPM builds for those targets will fail",
+                missing.join(", ")
+            ));
+        }
+
+        if params.write {
+            if action != Action::Unchanged {
+                if let Some(dir) = copy.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                std::fs::write(&copy, &contents)?;
+                std::fs::set_permissions(&copy, std::fs::Permissions::from_mode(0o755))?;
+            }
+            if let Some((spec, text)) = &spec_edit {
+                std::fs::write(spec, text)?;
+            }
+        }
+        Ok(Json(RustPackage {
+            binary,
+            installed_as,
+            changes,
+            written: params.write,
+            notes,
+        }))
+    }
+}
@@ -0,0 +1,270 @@
+//! `generate_changelog`: a `%changelog` entry for the spec, summarizing the
+//! git history since the last tag.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use super::lint::parse_spec;
+use super::project::ProjectInfo;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Commits listed in one entry; the rest are counted.
+const MAX_CHANGES: usize = 30;
+
+const WEEKDAYS: &[&str] = &["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: &[&str] = &[
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GenerateChangelogParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Spec file, absolute or relative to the project (default: the one in
+    /// `rpm/`).
+    #[serde(default)]
+    pub spec: Option<String>,
+    /// Tag or commit to summarize from (default: the latest tag, else the
+    /// whole history).
+    #[serde(default)]
+    pub since: Option<String>,
+    /// `version-release` of the entry (default: the spec's `Version` and
+    /// `Release`).
+    #[serde(default)]
+    pub version: Option<String>,
+    /// `Name <email>` (default: git's `user.name` and `user.email`).
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Insert the entry at the top of the spec's `%changelog` instead of
+    /// only returning it.
+    #[serde(default)]
+    pub write: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GeneratedChangelog {
+    pub spec: PathBuf,
+    /// The revision the summary starts after, when there is one.
+    pub since: Option<String>,
+    pub commits: usize,
+    pub entry: String,
+    /// Whether the entry was inserted into the spec.
+    pub written: bool,
+}
+
+/// Output of `git args` in `dir`.
+async fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .await
+        .map_err(|source| Error::Spawn {
+            program: "git".to_string(),
+            source,
+        })?;
+    if !output.status.success() {
+        return Err(Error::InvalidArgument(format!(
+            "git {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// `secs` since the epoch as a changelog date, e.g. `Fri Oct 16 2026`.
+fn changelog_date(secs: u64) -> String {
+    let days = secs / 86_400;
+    let weekday = WEEKDAYS[(days % 7) as usize];
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{weekday} {} {day:02} {year}", MONTHS[(month - 1) as usize])
+}
+
+/// Commit subjects worth a changelog line: no merges, fixups or repeats.
+fn changes(log: &str) -> Vec<String> {
+    let mut changes: Vec<String> = Vec::new();
+    for subject in log.lines().map(str::trim) {
+        if subject.is_empty()
+            || subject.starts_with("Merge ")
+            || subject.starts_with("fixup!")
+            || subject.starts_with("squash!")
+            || changes.iter().any(|change| change == subject)
+        {
+            continue;
+        }
+        changes.push(subject.to_string());
+    }
+    changes
+}
+
+/// `text` with `entry` inserted below `%changelog`, which is appended when
+/// missing.
+fn insert_entry(text: &str, entry: &str) -> String {
+    let mut lines: Vec<&str> = text.lines().collect();
+    match lines.iter().position(|line| line.trim() == "%changelog") {
+        Some(at) => {
+            let rest_empty = lines[at + 1..].iter().all(|line| line.trim().is_empty());
+            let insert = if rest_empty {
+                entry.trim_end().to_string()
+            } else {
+                format!("{}\n", entry.trim_end())
+            };
+            lines.insert(at + 1, &insert);
+            let mut out = lines.join("\n");
+            out.push('\n');
+            out
+        }
+        None => format!("{}\n\n%changelog\n{}\n", text.trim_end(), entry.trim_end()),
+    }
+}
+
+pub async fn generate(
+    server: &AuroraServer,
+    params: GenerateChangelogParams,
+) -> Result<GeneratedChangelog> {
+    let dir = server
+        .state()
+        .config
+        .sdk
+        .project(params.project.as_deref())?;
+    let spec_path = match params.spec {
+        Some(spec) => dir.join(spec),
+        None => ProjectInfo::inspect(&dir).spec_file.ok_or_else(|| {
+            Error::InvalidArgument(format!(
+                "no .spec file in {}; pass spec or create one with generate_spec",
+                dir.join("rpm").display()
+            ))
+        })?,
+    };
+    let text = std::fs::read_to_string(&spec_path)
+        .map_err(|err| Error::InvalidArgument(format!("{}: {err}", spec_path.display())))?;
+
+    let version = match params.version {
+        Some(version) => version,
+        None => {
+            let spec = parse_spec(&text);
+            let version = spec
+                .tag("Version")
+                .map(|(_, version)| version)
+                .filter(|version| !version.is_empty() && !version.contains('%'))
+                .ok_or_else(|| {
+                    Error::InvalidArgument(format!(
+                        "{} has no literal Version; pass version",
+                        spec_path.display()
+                    ))
+                })?;
+            // `1%{?dist}` releases keep only their literal part.
+            let release = spec
+                .tag("Release")
+                .map(|(_, release)| release.split('%').next().unwrap_or("").trim())
+                .filter(|release| !release.is_empty());
+            match release {
+                Some(release) => format!("{version}-{release}"),
+                None => version.to_string(),
+            }
+        }
+    };
+    if text
+        .lines()
+        .any(|line| line.starts_with("* ") && line.trim_end().ends_with(&format!("- {version}")))
+    {
+        return Err(Error::InvalidArgument(format!(
+            "%changelog already has an entry for {version}"
+        )));
+    }
+
+    let since = match params.since {
+        Some(since) if since.is_empty() || since.starts_with('-') => {
+            return Err(Error::InvalidArgument(format!(
+                "'{since}' is not a git revision"
+            )));
+        }
+        Some(since) => Some(since),
+        None => git(&dir, &["describe", "--tags", "--abbrev=0"]).await.ok(),
+    };
+    let range = match &since {
+        Some(since) => format!("{since}..HEAD"),
+        None => "HEAD".to_string(),
+    };
+    let log = git(&dir, &["log", "--no-merges", "--format=%s", &range]).await?;
+    let commits = log.lines().filter(|line| !line.trim().is_empty()).count();
+    let changes = changes(&log);
+    if changes.is_empty() {
+        return Err(Error::InvalidArgument(format!(
+            "no commits in {range} to summarize"
+        )));
+    }
+
+    let author = match params.author {
+        Some(author) => author,
+        None => {
+            let name = git(&dir, &["config", "user.name"]).await?;
+            let email = git(&dir, &["config", "user.email"])
+                .await
+                .unwrap_or_default();
+            if email.is_empty() {
+                name
+            } else {
+                format!("{name} <{email}>")
+            }
+        }
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let mut entry = format!("* {} {author} - {version}\n", changelog_date(now));
+    for change in changes.iter().take(MAX_CHANGES) {
+        entry.push_str(&format!("- {change}\n"));
+    }
+    if changes.len() > MAX_CHANGES {
+        entry.push_str(&format!(
+            "- And {} more changes.\n",
+            changes.len() - MAX_CHANGES
+        ));
+    }
+
+    if params.write {
+        std::fs::write(&spec_path, insert_entry(&text, &entry))?;
+    }
+    Ok(GeneratedChangelog {
+        spec: spec_path,
+        since,
+        commits,
+        entry,
+        written: params.write,
+    })
+}
+
+#[tool_router(router = changelog_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Generate an RPM %changelog entry (date, author, version-release and one line per commit) from the git history since the last tag. Returns the entry; set write=true to insert it at the top of the spec's %changelog."
+    )]
+    pub async fn generate_changelog(
+        &self,
+        Parameters(params): Parameters<GenerateChangelogParams>,
+    ) -> Result<Json<GeneratedChangelog>> {
+        generate(self, params).await.map(Json)
+    }
+}
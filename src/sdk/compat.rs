@@ -0,0 +1,529 @@
+//! `check_compatibility`: platform APIs a project uses that its minimum
+//! Aurora OS release does not provide yet, and `compatibility_range`: what a
+//! range of releases offers and which of the project's dependencies fall
+//! outside it.
+
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::project::{ProjectInfo, find_files};
+use super::silica::{OsVersion, QmlUsage, qml_files, scan_qml};
+use crate::config::expand_tilde;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+const DATABASE: &str = include_str!("data/os-api.toml");
+
+/// C++ sources searched for includes and D-Bus names.
+pub(crate) const CPP_EXTENSIONS: &[&str] = &["cpp", "h", "hpp", "cc", "cxx"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKind {
+    QmlModule,
+    Header,
+    Pkgconfig,
+    DbusInterface,
+    QtModule,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ApiEntry {
+    pub(crate) kind: ApiKind,
+    pub(crate) name: String,
+    pub(crate) since: String,
+    /// First release without it.
+    pub(crate) removed: Option<String>,
+    pub(crate) note: Option<String>,
+    /// Behaviour changes in later releases.
+    #[serde(default)]
+    pub(crate) changes: Vec<ApiChange>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ApiChange {
+    pub(crate) version: String,
+    pub(crate) note: String,
+}
+
+impl ApiEntry {
+    /// Provided by release `version`.
+    pub(crate) fn available_in(&self, version: &OsVersion) -> bool {
+        self.available_across(version, version)
+    }
+
+    /// Provided by every release from `min` up to `max`.
+    fn available_across(&self, min: &OsVersion, max: &OsVersion) -> bool {
+        OsVersion::parse(&self.since).is_ok_and(|since| since <= *min)
+            && self
+                .removed
+                .as_deref()
+                .is_none_or(|removed| OsVersion::parse(removed).is_ok_and(|removed| removed > *max))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Release {
+    pub version: String,
+    /// Qt version of its build targets.
+    pub qt: String,
+    /// `sailfishsilica-qt5` version of its build targets.
+    pub silica: String,
+    /// Release notes in Markdown, served as `aurora-release://` resources.
+    #[serde(default, skip_serializing)]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Database {
+    #[serde(default)]
+    pub(crate) api: Vec<ApiEntry>,
+    #[serde(default)]
+    pub(crate) release: Vec<Release>,
+}
+
+/// The embedded database, extended by `extra`: its entries replace
+/// embedded ones with the same kind and name, its releases ones with the
+/// same version. Releases come back oldest first.
+pub(crate) fn load_database(extra: Option<&Path>) -> Result<Database> {
+    let mut database = super::load_database(
+        "os-api.toml",
+        DATABASE,
+        extra,
+        |database: &mut Database, file: Database| {
+            for entry in file.api {
+                database
+                    .api
+                    .retain(|e| !(e.kind == entry.kind && e.name == entry.name));
+                database.api.push(entry);
+            }
+            for release in file.release {
+                database.release.retain(|r| r.version != release.version);
+                database.release.push(release);
+            }
+        },
+    )?;
+    for entry in &database.api {
+        let changes = entry.changes.iter().map(|change| &change.version);
+        for version in std::iter::once(&entry.since)
+            .chain(&entry.removed)
+            .chain(changes)
+        {
+            OsVersion::parse(version)
+                .map_err(|_| Error::Config(format!("{}: bad version '{version}'", entry.name)))?;
+        }
+    }
+    for release in &database.release {
+        OsVersion::parse(&release.version)
+            .map_err(|_| Error::Config(format!("bad release version '{}'", release.version)))?;
+    }
+    database
+        .release
+        .sort_by_cached_key(|release| OsVersion::parse(&release.version).ok());
+    Ok(database)
+}
+
+/// The OS release in a build target name such as
+/// `AuroraOS-5.1.3.85-MB2-armv7hl`.
+pub(crate) fn target_os_version(target: &str) -> Option<&str> {
+    target
+        .split('-')
+        .find(|part| part.starts_with(|c: char| c.is_ascii_digit()) && part.contains('.'))
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CheckCompatibilityParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Oldest Aurora OS release the app must run on, e.g. `4.0.2` (default:
+    /// the release in the build target's name).
+    #[serde(default)]
+    pub min_os_version: Option<String>,
+    /// Build target to take the release from (default: the configured one).
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CompatibilityFinding {
+    pub kind: ApiKind,
+    /// Module, header, pkg-config module or D-Bus name.
+    pub api: String,
+    /// Earliest release providing it.
+    pub available_since: String,
+    /// Where it is used; absent for pkg-config dependencies.
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CompatibilityRangeParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Oldest Aurora OS release to support, e.g. `4.0`.
+    pub min_os_version: String,
+    /// Newest Aurora OS release to support (default: the newest known one).
+    #[serde(default)]
+    pub max_os_version: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VersionSpan {
+    pub oldest: String,
+    pub newest: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct QtModuleAvailability {
+    pub module: String,
+    pub available_since: String,
+    pub removed_in: Option<String>,
+    /// Every release in the range provides it.
+    pub across_range: bool,
+    /// The project links it.
+    pub used: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RangeFinding {
+    pub kind: ApiKind,
+    pub api: String,
+    /// Earliest release providing it; absent when no known release does.
+    pub available_since: Option<String>,
+    pub removed_in: Option<String>,
+    /// Where it is used; the project file for pkg-config and Qt modules.
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CompatibilityRangeReport {
+    pub min_os_version: String,
+    pub max_os_version: String,
+    /// Known releases in effect across the range: the one `min_os_version`
+    /// runs and every later one up to `max_os_version`.
+    pub releases: Vec<Release>,
+    pub qt: Option<VersionSpan>,
+    pub silica: Option<VersionSpan>,
+    pub qt_modules: Vec<QtModuleAvailability>,
+    /// Oldest release providing every known API the project uses.
+    pub required_os_version: Option<String>,
+    pub files_checked: usize,
+    /// Every dependency is available across the whole range.
+    pub passed: bool,
+    /// Dependencies missing from part or all of the range.
+    pub findings: Vec<RangeFinding>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CompatibilityReport {
+    pub min_os_version: String,
+    pub files_checked: usize,
+    /// Everything used is available on `min_os_version`.
+    pub passed: bool,
+    pub findings: Vec<CompatibilityFinding>,
+}
+
+/// A place where the project uses a database entry.
+pub(crate) struct Usage<'a> {
+    pub(crate) entry: &'a ApiEntry,
+    /// Relative to the project.
+    pub(crate) file: Option<PathBuf>,
+    pub(crate) line: Option<usize>,
+}
+
+/// Where the project in `dir` uses `entries`, and how many source files
+/// were read.
+pub(crate) fn usages<'a>(
+    dir: &Path,
+    info: &ProjectInfo,
+    entries: &[&'a ApiEntry],
+) -> Result<(Vec<Usage<'a>>, usize)> {
+    let mut found = Vec::new();
+    let mut add = |entry: &'a ApiEntry, file: Option<&Path>, line: Option<usize>| {
+        found.push(Usage {
+            entry,
+            file: file.map(|file| file.strip_prefix(dir).unwrap_or(file).to_path_buf()),
+            line,
+        });
+    };
+    let of_kind = |kind: ApiKind| {
+        entries
+            .iter()
+            .copied()
+            .filter(move |entry| entry.kind == kind)
+    };
+
+    for entry in of_kind(ApiKind::Pkgconfig) {
+        if info.pkgconfig.contains(&entry.name) {
+            add(entry, info.project_file.as_deref(), None);
+        }
+    }
+    for entry in of_kind(ApiKind::QtModule) {
+        if info
+            .qt_modules
+            .iter()
+            .any(|module| module.eq_ignore_ascii_case(&entry.name))
+        {
+            add(entry, info.project_file.as_deref(), None);
+        }
+    }
+
+    let qml = if info.qml_dir.is_some() {
+        qml_files(dir)?
+    } else {
+        Vec::new()
+    };
+    let cpp: Vec<PathBuf> = CPP_EXTENSIONS
+        .iter()
+        .flat_map(|ext| find_files(dir, ext, 4))
+        .collect();
+    for file in qml.iter().chain(&cpp) {
+        let text = std::fs::read_to_string(file)?;
+        let is_qml = file.extension().is_some_and(|ext| ext == "qml");
+        if is_qml {
+            for (line, usage) in scan_qml(&text) {
+                let QmlUsage::Import { module } = usage else {
+                    continue;
+                };
+                for entry in of_kind(ApiKind::QmlModule).filter(|entry| {
+                    module == entry.name || module.starts_with(&format!("{}.", entry.name))
+                }) {
+                    add(entry, Some(file), Some(line));
+                }
+            }
+        } else {
+            for (line, path) in includes(&text) {
+                for entry in of_kind(ApiKind::Header).filter(|entry| {
+                    path == entry.name || path.ends_with(&format!("/{}", entry.name))
+                }) {
+                    add(entry, Some(file), Some(line));
+                }
+            }
+        }
+        for entry in of_kind(ApiKind::DbusInterface) {
+            let quoted = format!("\"{}", entry.name);
+            for (index, line) in text.lines().enumerate() {
+                if line.contains(&quoted) {
+                    add(entry, Some(file), Some(index + 1));
+                }
+            }
+        }
+    }
+    Ok((found, qml.len() + cpp.len()))
+}
+
+/// Oldest and newest of dotted `versions`.
+fn span<'a>(versions: impl Iterator<Item = &'a str>) -> Option<VersionSpan> {
+    let mut versions: Vec<&str> = versions.collect();
+    versions.sort_by_cached_key(|version| OsVersion::parse(version).ok());
+    Some(VersionSpan {
+        oldest: versions.first()?.to_string(),
+        newest: versions.last()?.to_string(),
+    })
+}
+
+/// `(line, path)` of each `#include` in a C++ file.
+pub(crate) fn includes(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines().enumerate().filter_map(|(index, line)| {
+        let rest = line.trim_start().strip_prefix('#')?.trim_start();
+        let rest = rest.strip_prefix("include")?.trim();
+        let path = rest.trim_start_matches(['<', '"']);
+        let end = path.find(['>', '"'])?;
+        Some((index + 1, &path[..end]))
+    })
+}
+
+#[tool_router(router = compat_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Check a project against its minimum Aurora OS release: flags QML modules, C++ headers, pkg-config dependencies, Qt modules and D-Bus interfaces that the release does not provide, each with the earliest release that does. The minimum defaults to the release in the build target's name.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn check_compatibility(
+        &self,
+        Parameters(params): Parameters<CheckCompatibilityParams>,
+    ) -> Result<Json<CompatibilityReport>> {
+        let options = &self.state().config.sdk;
+        let dir = options.project(params.project.as_deref())?;
+        let min_os_version = match params.min_os_version {
+            Some(version) => version,
+            None => options
+                .target(params.target.as_deref())?
+                .as_deref()
+                .and_then(target_os_version)
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    Error::InvalidArgument(
+                        "pass min_os_version, or a target whose name includes the OS release"
+                            .to_string(),
+                    )
+                })?,
+        };
+        let minimum = OsVersion::parse(&min_os_version)?;
+        let extra = options.compat_database.as_deref().map(expand_tilde);
+        let database = load_database(extra.as_deref())?;
+        let entries: Vec<&ApiEntry> = database
+            .api
+            .iter()
+            .filter(|entry| OsVersion::parse(&entry.since).is_ok_and(|since| since > minimum))
+            .collect();
+
+        let info = ProjectInfo::inspect(&dir);
+        let (found, files_checked) = usages(&dir, &info, &entries)?;
+        let findings: Vec<CompatibilityFinding> = found
+            .into_iter()
+            .map(|usage| CompatibilityFinding {
+                kind: usage.entry.kind,
+                api: usage.entry.name.clone(),
+                available_since: usage.entry.since.clone(),
+                file: usage.file,
+                line: usage.line,
+                note: usage.entry.note.clone(),
+            })
+            .collect();
+
+        Ok(Json(CompatibilityReport {
+            min_os_version,
+            files_checked,
+            passed: findings.is_empty(),
+            findings,
+        }))
+    }
+
+    #[tool(
+        description = "Report what a range of Aurora OS releases offers, to pick a support floor: the Qt and Silica versions the releases ship, which Qt modules are available across the whole range, the oldest release providing everything the project uses, and the project's QML modules, headers, pkg-config and Qt modules and D-Bus interfaces that are missing from part of the range (too new for the minimum or removed before the maximum).",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn compatibility_range(
+        &self,
+        Parameters(params): Parameters<CompatibilityRangeParams>,
+    ) -> Result<Json<CompatibilityRangeReport>> {
+        let options = &self.state().config.sdk;
+        let dir = options.project(params.project.as_deref())?;
+        let extra = options.compat_database.as_deref().map(expand_tilde);
+        let database = load_database(extra.as_deref())?;
+        let min = OsVersion::parse(&params.min_os_version)?;
+        let max_os_version = match params.max_os_version {
+            Some(version) => version,
+            None => database
+                .release
+                .last()
+                .map(|release| release.version.clone())
+                .ok_or_else(|| {
+                    Error::InvalidArgument(
+                        "pass max_os_version; the database lists no releases".to_string(),
+                    )
+                })?,
+        };
+        let max = OsVersion::parse(&max_os_version)?;
+        if min > max {
+            return Err(Error::InvalidArgument(format!(
+                "min_os_version {} is newer than max_os_version {max_os_version}",
+                params.min_os_version
+            )));
+        }
+
+        let version = |release: &Release| OsVersion::parse(&release.version).ok();
+        let floor = database
+            .release
+            .iter()
+            .rfind(|release| version(release).is_some_and(|v| v <= min));
+        let releases: Vec<Release> = floor
+            .into_iter()
+            .chain(
+                database
+                    .release
+                    .iter()
+                    .filter(|release| version(release).is_some_and(|v| v > min && v <= max)),
+            )
+            .cloned()
+            .collect();
+
+        let info = ProjectInfo::inspect(&dir);
+        let entries: Vec<&ApiEntry> = database.api.iter().collect();
+        let (found, files_checked) = usages(&dir, &info, &entries)?;
+        let required_os_version = found
+            .iter()
+            .map(|usage| usage.entry.since.as_str())
+            .max_by_key(|since| OsVersion::parse(since).ok())
+            .map(str::to_string);
+
+        let mut qt_modules: Vec<QtModuleAvailability> = database
+            .api
+            .iter()
+            .filter(|entry| entry.kind == ApiKind::QtModule)
+            .map(|entry| QtModuleAvailability {
+                module: entry.name.clone(),
+                available_since: entry.since.clone(),
+                removed_in: entry.removed.clone(),
+                across_range: entry.available_across(&min, &max),
+                used: info
+                    .qt_modules
+                    .iter()
+                    .any(|module| module.eq_ignore_ascii_case(&entry.name)),
+            })
+            .collect();
+        qt_modules.sort_by(|a, b| a.module.cmp(&b.module));
+
+        let mut findings: Vec<RangeFinding> = found
+            .into_iter()
+            .filter(|usage| !usage.entry.available_across(&min, &max))
+            .map(|usage| RangeFinding {
+                kind: usage.entry.kind,
+                api: usage.entry.name.clone(),
+                available_since: Some(usage.entry.since.clone()),
+                removed_in: usage.entry.removed.clone(),
+                file: usage.file,
+                line: usage.line,
+                note: usage.entry.note.clone(),
+            })
+            .collect();
+        for module in &info.qt_modules {
+            if !qt_modules
+                .iter()
+                .any(|known| known.module.eq_ignore_ascii_case(module))
+            {
+                findings.push(RangeFinding {
+                    kind: ApiKind::QtModule,
+                    api: module.clone(),
+                    available_since: None,
+                    removed_in: None,
+                    file: info
+                        .project_file
+                        .as_deref()
+                        .map(|file| file.strip_prefix(&dir).unwrap_or(file).to_path_buf()),
+                    line: None,
+                    note: Some("no known release ships this Qt module".to_string()),
+                });
+            }
+        }
+
+        Ok(Json(CompatibilityRangeReport {
+            min_os_version: params.min_os_version,
+            max_os_version,
+            qt: span(releases.iter().map(|release| release.qt.as_str())),
+            silica: span(releases.iter().map(|release| release.silica.as_str())),
+            releases,
+            qt_modules,
+            required_os_version,
+            files_checked,
+            passed: findings.is_empty(),
+            findings,
+        }))
+    }
+}
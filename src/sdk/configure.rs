@@ -0,0 +1,430 @@
+//! `configure_project`: detecting a project's build system, targets and
+//! options, and running its configure step (`sfdk qmake` / `sfdk cmake`).
+
+use std::path::PathBuf;
+
+use rmcp::handler::server::tool::cached_schema_for_type;
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::CallToolResult;
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use super::build::is_error_line;
+use super::project::{BuildSystem, ProjectInfo, cmake_calls, qmake_values};
+use super::run_logged;
+use crate::device::tail_lines;
+use crate::error::{Error, Result, tool_result};
+use crate::resources::file_link;
+use crate::server::AuroraServer;
+
+const LOG_TAIL_LINES: usize = 40;
+const MAX_ERRORS: usize = 20;
+
+/// Qbs items that produce a build product.
+const QBS_PRODUCTS: &[&str] = &[
+    "Application",
+    "CppApplication",
+    "QtApplication",
+    "QtGuiApplication",
+    "DynamicLibrary",
+    "StaticLibrary",
+    "Library",
+    "Product",
+];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ConfigureParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Build target, e.g. `AuroraOS-5.1.3.85-MB2-armv7hl` (default: the
+    /// configured one).
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Extra arguments for qmake or cmake, e.g. `CONFIG+=tests` or
+    /// `-DWITH_TESTS=ON`.
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// Only report the build system, targets and options; don't run the
+    /// configure step.
+    #[serde(default)]
+    pub detect_only: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BuildTarget {
+    pub name: String,
+    /// `app`, `lib`, `subdirs` for qmake; `executable` or `library` for
+    /// CMake; the item type for Qbs.
+    pub kind: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BuildOption {
+    pub name: String,
+    /// Where it is declared: `CONFIG`, `DEFINES`, `option`, `cache` or
+    /// `property`.
+    pub source: String,
+    pub default: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyKind {
+    QtModule,
+    Pkgconfig,
+    CmakePackage,
+    Package,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MissingDependency {
+    pub kind: DependencyKind,
+    pub name: String,
+    /// The `BuildRequires` line that would provide it.
+    pub build_requires: String,
+    /// The log line it was found in.
+    pub line: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ConfigureReport {
+    pub project: PathBuf,
+    pub build_system: Option<BuildSystem>,
+    pub project_file: Option<PathBuf>,
+    pub targets: Vec<BuildTarget>,
+    pub options: Vec<BuildOption>,
+    pub target: Option<String>,
+    /// Whether the configure step ran; false with `detect_only`.
+    pub configured: bool,
+    pub success: bool,
+    pub exit_status: Option<i32>,
+    pub missing: Vec<MissingDependency>,
+    pub errors: Vec<String>,
+    pub log_file: Option<PathBuf>,
+    pub log_tail: Vec<String>,
+}
+
+fn qmake_targets(info: &ProjectInfo, pro: &str) -> Vec<BuildTarget> {
+    let template = qmake_values(pro, "TEMPLATE")
+        .pop()
+        .unwrap_or_else(|| "app".to_string());
+    if template == "subdirs" {
+        return qmake_values(pro, "SUBDIRS")
+            .into_iter()
+            .map(|name| BuildTarget {
+                name,
+                kind: "subdirs".to_string(),
+            })
+            .collect();
+    }
+    vec![BuildTarget {
+        name: info.name.clone(),
+        kind: template,
+    }]
+}
+
+fn qmake_options(pro: &str) -> Vec<BuildOption> {
+    let option = |source: &str| {
+        let source = source.to_string();
+        move |value: String| {
+            let (name, default) = match value.split_once('=') {
+                Some((name, default)) => (name.to_string(), Some(default.to_string())),
+                None => (value, None),
+            };
+            BuildOption {
+                name,
+                source: source.clone(),
+                default,
+                description: None,
+            }
+        }
+    };
+    qmake_values(pro, "CONFIG")
+        .into_iter()
+        .map(option("CONFIG"))
+        .chain(
+            qmake_values(pro, "DEFINES")
+                .into_iter()
+                .map(option("DEFINES")),
+        )
+        .collect()
+}
+
+fn cmake_targets(info: &ProjectInfo, cmake: &str) -> Vec<BuildTarget> {
+    let mut targets = Vec::new();
+    for (command, kind) in [("add_executable", "executable"), ("add_library", "library")] {
+        for args in cmake_calls(cmake, command) {
+            let Some(name) = args.first() else {
+                continue;
+            };
+            if args.iter().any(|arg| arg == "IMPORTED" || arg == "ALIAS") {
+                continue;
+            }
+            targets.push(BuildTarget {
+                name: name.replace("${PROJECT_NAME}", &info.name),
+                kind: kind.to_string(),
+            });
+        }
+    }
+    targets
+}
+
+fn cmake_options(cmake: &str) -> Vec<BuildOption> {
+    let mut options: Vec<BuildOption> = cmake_calls(cmake, "option")
+        .into_iter()
+        .filter_map(|args| {
+            let mut args = args.into_iter();
+            Some(BuildOption {
+                name: args.next()?,
+                source: "option".to_string(),
+                description: args.next(),
+                default: Some(args.next().unwrap_or_else(|| "OFF".to_string())),
+            })
+        })
+        .collect();
+    for args in cmake_calls(cmake, "set") {
+        let Some(cache) = args.iter().position(|arg| arg == "CACHE") else {
+            continue;
+        };
+        options.push(BuildOption {
+            name: args[0].clone(),
+            source: "cache".to_string(),
+            default: Some(args[1..cache].join(" ")),
+            description: args.get(cache + 2).cloned(),
+        });
+    }
+    options
+}
+
+/// Products (`CppApplication { name: "x" }`) and `property` declarations of
+/// a Qbs file.
+fn qbs_targets_and_options(info: &ProjectInfo, qbs: &str) -> (Vec<BuildTarget>, Vec<BuildOption>) {
+    let mut targets: Vec<BuildTarget> = Vec::new();
+    let mut options = Vec::new();
+    let mut open_product: Option<String> = None;
+    for line in qbs.lines().map(str::trim) {
+        if let Some(item) = line.strip_suffix('{').map(str::trim)
+            && QBS_PRODUCTS.contains(&item)
+        {
+            if let Some(kind) = open_product.replace(item.to_string()) {
+                targets.push(BuildTarget {
+                    name: info.name.clone(),
+                    kind,
+                });
+            }
+        } else if let Some(name) = line.strip_prefix("name:")
+            && let Some(kind) = open_product.take()
+        {
+            targets.push(BuildTarget {
+                name: name
+                    .trim()
+                    .trim_end_matches(';')
+                    .trim_matches('"')
+                    .to_string(),
+                kind,
+            });
+        } else if let Some(rest) = line.strip_prefix("property ") {
+            let (declaration, default) = match rest.split_once(':') {
+                Some((declaration, default)) => (declaration, Some(default.trim().to_string())),
+                None => (rest, None),
+            };
+            if let Some(name) = declaration.split_whitespace().nth(1) {
+                options.push(BuildOption {
+                    name: name.to_string(),
+                    source: "property".to_string(),
+                    default,
+                    description: None,
+                });
+            }
+        }
+    }
+    if let Some(kind) = open_product {
+        targets.push(BuildTarget {
+            name: info.name.clone(),
+            kind,
+        });
+    }
+    (targets, options)
+}
+
+/// The quoted word following `marker` in `line`.
+fn quoted_after<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = &line[line.find(marker)? + marker.len()..];
+    let rest = rest.trim_start().strip_prefix(['"', '\''])?;
+    rest.split(['"', '\'']).next()
+}
+
+/// Dependencies qmake, CMake, pkg-config or the package manager reported
+/// as missing.
+fn missing_dependencies(output: &str) -> Vec<MissingDependency> {
+    let mut missing: Vec<MissingDependency> = Vec::new();
+    for line in output.lines().map(str::trim) {
+        let found: Vec<(DependencyKind, String)> =
+            if let Some(modules) = line.split("Unknown module(s) in QT:").nth(1) {
+                modules
+                    .split_whitespace()
+                    .map(|module| (DependencyKind::QtModule, module.to_string()))
+                    .collect()
+            } else if let Some(name) = quoted_after(line, "provided by")
+                .filter(|_| line.starts_with("Could not find a package configuration file"))
+            {
+                vec![(DependencyKind::CmakePackage, name.to_string())]
+            } else if let Some(name) = line
+                .strip_prefix("No package '")
+                .and_then(|rest| rest.split('\'').next())
+            {
+                vec![(DependencyKind::Pkgconfig, name.to_string())]
+            } else if line.starts_with("Package '") && line.ends_with("not found") {
+                quoted_after(line, "Package")
+                    .map(|name| (DependencyKind::Pkgconfig, name.to_string()))
+                    .into_iter()
+                    .collect()
+            } else if let Some(name) = line
+                .strip_prefix("Project ERROR: ")
+                .and_then(|rest| rest.strip_suffix(" development package not found"))
+            {
+                vec![(DependencyKind::Pkgconfig, name.trim().to_string())]
+            } else if line.starts_with("No provider of") {
+                quoted_after(line, "No provider of")
+                    .map(|name| (DependencyKind::Package, name.to_string()))
+                    .into_iter()
+                    .collect()
+            } else {
+                Vec::new()
+            };
+        for (kind, name) in found {
+            if missing.iter().any(|m| m.kind == kind && m.name == name) {
+                continue;
+            }
+            let build_requires = match kind {
+                DependencyKind::QtModule => {
+                    let module = name.trim_start_matches("Qt5").trim_start_matches("qt5");
+                    let mut chars = module.chars();
+                    let module = chars
+                        .next()
+                        .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                        .unwrap_or_default();
+                    format!("pkgconfig(Qt5{module})")
+                }
+                DependencyKind::Pkgconfig => format!("pkgconfig({name})"),
+                DependencyKind::CmakePackage => format!("cmake({name})"),
+                DependencyKind::Package => name.clone(),
+            };
+            missing.push(MissingDependency {
+                kind,
+                name,
+                build_requires,
+                line: line.to_string(),
+            });
+        }
+    }
+    missing
+}
+
+pub async fn configure(server: &AuroraServer, params: ConfigureParams) -> Result<ConfigureReport> {
+    let options = &server.state().config.sdk;
+    let project = options.project(params.project.as_deref())?;
+    let target = options.target(params.target.as_deref())?;
+    let info = ProjectInfo::inspect(&project);
+    let text = info
+        .project_file
+        .as_ref()
+        .and_then(|file| std::fs::read_to_string(file).ok())
+        .unwrap_or_default();
+    let (targets, build_options) = match info.build_system {
+        Some(BuildSystem::Qmake) => (qmake_targets(&info, &text), qmake_options(&text)),
+        Some(BuildSystem::Cmake) => (cmake_targets(&info, &text), cmake_options(&text)),
+        Some(BuildSystem::Qbs) => qbs_targets_and_options(&info, &text),
+        None => (Vec::new(), Vec::new()),
+    };
+    let mut report = ConfigureReport {
+        build_system: info.build_system,
+        project_file: info.project_file,
+        targets,
+        options: build_options,
+        target,
+        configured: false,
+        success: info.build_system.is_some(),
+        exit_status: None,
+        missing: Vec::new(),
+        errors: Vec::new(),
+        log_file: None,
+        log_tail: Vec::new(),
+        project,
+    };
+    if params.detect_only {
+        return Ok(report);
+    }
+
+    let step = match report.build_system {
+        Some(BuildSystem::Qmake) => "qmake",
+        Some(BuildSystem::Cmake) => "cmake",
+        Some(BuildSystem::Qbs) => {
+            return Err(Error::InvalidArgument(
+                "sfdk has no separate configure step for Qbs projects; use build_project"
+                    .to_string(),
+            ));
+        }
+        None => {
+            return Err(Error::InvalidArgument(format!(
+                "no .pro, CMakeLists.txt or .qbs file in {}",
+                report.project.display()
+            )));
+        }
+    };
+    let mut command = Command::new(options.sfdk()?);
+    if let Some(target) = &report.target {
+        command.arg("-c").arg(format!("target={target}"));
+    }
+    command.arg(step).args(&params.options);
+    if step == "cmake" && !params.options.iter().any(|arg| arg == "-S" || arg == "-B") {
+        command.arg(".");
+    }
+    command.current_dir(&report.project);
+
+    let log_file = super::log_file(server, &report.project, "configure");
+    let run = run_logged(command, &log_file, options.build_timeout()).await?;
+    report.configured = true;
+    report.success = run.status == 0;
+    report.exit_status = Some(run.status);
+    report.missing = missing_dependencies(&run.output);
+    report.errors = run
+        .output
+        .lines()
+        .filter(|line| is_error_line(line))
+        .take(MAX_ERRORS)
+        .map(|line| line.trim().to_string())
+        .collect();
+    report.log_tail = tail_lines(&run.output, LOG_TAIL_LINES);
+    report.log_file = Some(log_file);
+    Ok(report)
+}
+
+#[tool_router(router = configure_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Detect a project's build system (qmake, CMake or Qbs), its targets and build options, and run the configure step (sfdk qmake / sfdk cmake) for an Aurora build target. Reports missing dependencies (Qt modules, pkg-config, CMake packages) with the BuildRequires that would provide them. detect_only=true skips running sfdk.",
+        output_schema = cached_schema_for_type::<ConfigureReport>()
+    )]
+    pub async fn configure_project(
+        &self,
+        Parameters(params): Parameters<ConfigureParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        tool_result(configure(self, params).await.and_then(|report| {
+            let link = report
+                .log_file
+                .as_ref()
+                .map(|log| file_link(log, Some("text/plain")));
+            let mut result = CallToolResult::structured(
+                serde_json::to_value(&report).map_err(std::io::Error::other)?,
+            );
+            result.content.extend(link);
+            Ok(result)
+        }))
+    }
+}
@@ -0,0 +1,385 @@
+//! `symbolicate_core`: a symbolized backtrace of a core dump, from gdb in
+//! batch mode inside the Build Engine with the app's debuginfo unpacked.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rmcp::handler::server::tool::cached_schema_for_type;
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::CallToolResult;
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::debuginfo::{self, FindDebuginfoParams};
+use super::run_logged;
+use crate::device::ssh;
+use crate::error::{Error, Result, tool_result};
+use crate::resources::file_link;
+use crate::server::AuroraServer;
+
+/// Scratch directory in the project, which the Build Engine shares.
+const STAGE_DIR: &str = ".symbolicate";
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SymbolicateParams {
+    /// Local core dump, e.g. one `collect_crash_report` fetched. xz, zstd,
+    /// lz4 and gzip compressed cores are unpacked first.
+    pub core: String,
+    /// Package of the crashed app, e.g. `ru.example.myapp`.
+    pub package: String,
+    /// Device the core came from, to match the installed version's
+    /// debuginfo.
+    #[serde(default)]
+    pub device: Option<String>,
+    /// `version-release` of the crashed app, instead of a device's.
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Crashed binary inside the package (default: `/usr/bin/<package>`).
+    #[serde(default)]
+    pub binary: Option<String>,
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Build target matching the device's architecture (default: the
+    /// configured one).
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Frame {
+    pub index: usize,
+    pub address: Option<String>,
+    /// `??` when gdb found no symbol.
+    pub function: String,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    /// The source line, from the debugsource package.
+    pub source: Option<String>,
+    /// Shared library of frames without line information.
+    pub library: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ThreadBacktrace {
+    /// gdb's thread number.
+    pub thread: usize,
+    pub frames: Vec<Frame>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SymbolizedCore {
+    pub core: PathBuf,
+    pub binary: String,
+    /// e.g. `SIGSEGV, Segmentation fault`.
+    pub signal: Option<String>,
+    /// The thread that crashed.
+    pub crashed: Option<ThreadBacktrace>,
+    pub threads: Vec<ThreadBacktrace>,
+    /// Packages whose symbols were loaded.
+    pub debug_packages: Vec<PathBuf>,
+    /// Frames without a symbol, across all threads.
+    pub unresolved_frames: usize,
+    pub warnings: Vec<String>,
+    pub log_file: PathBuf,
+}
+
+/// A main RPM of `package` at `version` among the project's builds.
+fn package_rpm(
+    server: &AuroraServer,
+    project: &Path,
+    package: &str,
+    version: &str,
+    arch: Option<&str>,
+) -> Option<PathBuf> {
+    let name = project.file_name()?.to_string_lossy().into_owned();
+    let mut dirs = vec![project.join("RPMS")];
+    if let Ok(entries) =
+        std::fs::read_dir(server.state().config.data_dir().join("matrix").join(name))
+    {
+        dirs.extend(
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path().join("RPMS")),
+        );
+    }
+    let prefix = format!("{package}-{version}.");
+    dirs.iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flat_map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+        })
+        .find(|path| {
+            let file = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            file.strip_prefix(&prefix)
+                .and_then(|rest| rest.strip_suffix(".rpm"))
+                .is_some_and(|rest| rest != "src" && arch.is_none_or(|arch| rest == arch))
+        })
+}
+
+/// One `bt` line: `#1  0x0040 in Greeter::greet (this=0x0) at src/greeter.cpp:12`.
+fn parse_frame(line: &str) -> Option<Frame> {
+    let rest = line.strip_prefix('#')?;
+    let (index, rest) = rest.split_once(char::is_whitespace)?;
+    let index = index.parse().ok()?;
+    let mut rest = rest.trim();
+    let mut address = None;
+    if rest.starts_with("0x")
+        && let Some((addr, tail)) = rest.split_once(" in ")
+    {
+        address = Some(addr.to_string());
+        rest = tail;
+    }
+    let (mut file, mut line_number, mut library) = (None, None, None);
+    if let Some((head, location)) = rest.rsplit_once(" at ")
+        && let Some((path, number)) = location.rsplit_once(':')
+        && let Ok(number) = number.trim().parse()
+    {
+        file = Some(path.to_string());
+        line_number = Some(number);
+        rest = head;
+    } else if let Some((head, lib)) = rest.rsplit_once(" from ") {
+        library = Some(lib.trim().to_string());
+        rest = head;
+    }
+    let function = rest.split(" (").next().unwrap_or(rest).trim().to_string();
+    Some(Frame {
+        index,
+        address,
+        function,
+        file,
+        line: line_number,
+        source: None,
+        library,
+    })
+}
+
+/// The output of `bt` then `thread apply all bt`: the signal gdb reports,
+/// the crashed thread's backtrace (the `bt` before any thread header) and
+/// every thread's.
+fn parse_backtraces(
+    output: &str,
+) -> (
+    Option<String>,
+    Option<ThreadBacktrace>,
+    Vec<ThreadBacktrace>,
+) {
+    let mut signal = None;
+    let mut current = None;
+    let mut crashed_frames = Vec::new();
+    let mut threads: Vec<ThreadBacktrace> = Vec::new();
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("Program terminated with signal ") {
+            signal = Some(rest.trim_end_matches('.').to_string());
+        } else if let Some(rest) = line.strip_prefix("[Current thread is ") {
+            current = rest.split_whitespace().next().and_then(|n| n.parse().ok());
+        } else if let Some(rest) = line.strip_prefix("Thread ") {
+            if let Some(Ok(thread)) = rest.split_whitespace().next().map(str::parse) {
+                threads.push(ThreadBacktrace {
+                    thread,
+                    frames: Vec::new(),
+                });
+            }
+        } else if let Some(frame) = parse_frame(line.trim_start()) {
+            match threads.last_mut() {
+                Some(thread) => thread.frames.push(frame),
+                // gdb shows the crashing frame on loading the core, before
+                // `bt` starts over.
+                None if frame.index == 0 => crashed_frames = vec![frame],
+                None => crashed_frames.push(frame),
+            }
+        }
+    }
+    let crashed = (!crashed_frames.is_empty()).then(|| ThreadBacktrace {
+        thread: current.unwrap_or(1),
+        frames: crashed_frames,
+    });
+    (signal, crashed, threads)
+}
+
+/// The text of `line` in `file`, looked up in the unpacked debugsource
+/// package, then in the project by ever shorter trailing parts of the
+/// build path (`/usr/src/debug/<package>/src/main.cpp` → `src/main.cpp`).
+fn source_line(root: &Path, project: &Path, file: &str, line: usize) -> Option<String> {
+    let parts: Vec<&str> = file.split('/').filter(|part| !part.is_empty()).collect();
+    let text = std::iter::once(root.join(file.trim_start_matches('/')))
+        .chain((0..parts.len()).map(|at| project.join(parts[at..].join("/"))))
+        .find_map(|path| std::fs::read_to_string(path).ok())?;
+    Some(text.lines().nth(line.checked_sub(1)?)?.trim().to_string())
+}
+
+pub async fn symbolicate(
+    server: &AuroraServer,
+    params: SymbolicateParams,
+) -> Result<SymbolizedCore> {
+    let options = &server.state().config.sdk;
+    let project = options.project(params.project.as_deref())?;
+    let core = PathBuf::from(&params.core);
+    let core_name = core
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .filter(|_| core.is_file())
+        .ok_or_else(|| Error::InvalidArgument(format!("{} is not a file", core.display())))?;
+    if core_name.ends_with(".rcore.lzo") {
+        return Err(Error::InvalidArgument(
+            "rich cores (.rcore.lzo) need rich-core-extract first; pass the core file inside"
+                .to_string(),
+        ));
+    }
+    let binary = params
+        .binary
+        .clone()
+        .unwrap_or_else(|| format!("/usr/bin/{}", params.package));
+    if !binary.starts_with('/') || binary.contains("..") {
+        return Err(Error::InvalidArgument(format!(
+            "'{binary}' is not an absolute path inside the package"
+        )));
+    }
+
+    let mut warnings = Vec::new();
+    let found = debuginfo::find(
+        server,
+        FindDebuginfoParams {
+            package: params.package.clone(),
+            device: params.device.clone(),
+            version: params.version.clone(),
+            arch: None,
+        },
+    )
+    .await?;
+    let mut rpms: Vec<PathBuf> = found
+        .debuginfo
+        .iter()
+        .chain(&found.debugsource)
+        .map(|package| package.path.clone())
+        .collect();
+    if found.debuginfo.is_none() {
+        warnings.push(format!(
+            "no debuginfo indexed for {} {}; run index_debuginfo after a debug=true build",
+            found.package, found.version
+        ));
+    }
+    let debug_packages = rpms.clone();
+    let arch = found
+        .arch
+        .clone()
+        .or_else(|| found.debuginfo.as_ref().map(|package| package.arch.clone()));
+    match package_rpm(
+        server,
+        &project,
+        &params.package,
+        &found.version,
+        arch.as_deref(),
+    ) {
+        Some(rpm) => rpms.push(rpm),
+        None => warnings.push(format!(
+            "no {}-{} RPM among the project's builds; the target's {binary} is used",
+            params.package, found.version
+        )),
+    }
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let stage = project.join(STAGE_DIR).join(stamp.to_string());
+    std::fs::create_dir_all(stage.join("root"))?;
+    let result = async {
+        std::fs::copy(&core, stage.join(&core_name))?;
+        for rpm in &rpms {
+            if let Some(name) = rpm.file_name() {
+                std::fs::copy(rpm, stage.join(name))?;
+            }
+        }
+        let core_file = ssh::quote(&core_name);
+        let script = format!(
+            "for r in *.rpm; do [ -e \"$r\" ] && rpm2cpio \"$r\" | (cd root && cpio -idm --quiet); done; \
+c={core_file}; case \"$c\" in \
+*.xz) xz -dc \"$c\" > core.unpacked && c=core.unpacked;; \
+*.zst) zstd -dcq \"$c\" > core.unpacked && c=core.unpacked;; \
+*.lz4) lz4 -dcq \"$c\" > core.unpacked && c=core.unpacked;; \
+*.gz) gzip -dc \"$c\" > core.unpacked && c=core.unpacked;; \
+esac; \
+b=root{binary}; [ -e \"$b\" ] || b={binary}; \
+gdb -batch -nx -q -ex 'set pagination off' -ex 'set confirm off' \
+-ex \"set debug-file-directory $PWD/root/usr/lib/debug:/usr/lib/debug\" \
+-ex \"set substitute-path /usr/src/debug $PWD/root/usr/src/debug\" \
+-ex bt -ex 'thread apply all bt' \"$b\" \"$c\"",
+            binary = ssh::quote(&binary)
+        );
+        let mut command = options.build_shell("sh", params.target.as_deref())?;
+        command.arg("-c").arg(script).current_dir(&stage);
+        let log_file = super::log_file(server, &project, "gdb");
+        let run = run_logged(command, &log_file, options.build_timeout()).await?;
+        if run.output.contains("gdb: not found") || run.output.contains("gdb: command not found") {
+            return Err(Error::InvalidArgument(
+                "gdb is not installed in the build target; install it with sfdk tools package-install <target> gdb"
+                    .to_string(),
+            ));
+        }
+        let (signal, mut crashed, mut threads) = parse_backtraces(&run.output);
+        let frames = crashed
+            .iter_mut()
+            .chain(threads.iter_mut())
+            .flat_map(|thread| &mut thread.frames);
+        for frame in frames {
+            if let (Some(file), Some(line)) = (&frame.file, frame.line) {
+                frame.source = source_line(&stage.join("root"), &project, file, line);
+            }
+        }
+        Ok((signal, crashed, threads, log_file))
+    }
+    .await;
+    let _ = std::fs::remove_dir_all(&stage);
+    let _ = std::fs::remove_dir(project.join(STAGE_DIR));
+    let (signal, crashed, threads, log_file) = result?;
+    if crashed.is_none() && threads.is_empty() {
+        warnings.push("gdb printed no backtrace; see the log".to_string());
+    }
+
+    let unresolved_frames = threads
+        .iter()
+        .flat_map(|thread| &thread.frames)
+        .filter(|frame| frame.function == "??")
+        .count();
+    Ok(SymbolizedCore {
+        core,
+        binary,
+        signal,
+        crashed,
+        threads,
+        debug_packages,
+        unresolved_frames,
+        warnings,
+        log_file,
+    })
+}
+
+#[tool_router(router = coredump_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Symbolize a core dump pulled from a device: unpacks the app's indexed -debuginfo/-debugsource RPMs matching the installed version, runs gdb in batch mode inside the Build Engine for the target, and returns the signal and every thread's backtrace with functions, source files, line numbers and source lines, plus a link to the gdb log.",
+        output_schema = cached_schema_for_type::<SymbolizedCore>(),
+        annotations(read_only_hint = true)
+    )]
+    pub async fn symbolicate_core(
+        &self,
+        Parameters(params): Parameters<SymbolicateParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        tool_result(symbolicate(self, params).await.and_then(|report| {
+            let link = file_link(&report.log_file, Some("text/plain"));
+            let mut result = CallToolResult::structured(
+                serde_json::to_value(&report).map_err(std::io::Error::other)?,
+            );
+            result.content.push(link);
+            Ok(result)
+        }))
+    }
+}
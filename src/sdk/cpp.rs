@@ -0,0 +1,291 @@
+//! `analyze_cpp`: clang-tidy over a project's C++ sources inside the Build
+//! Engine, using a `compile_commands.json` derived from the Aurora build.
+
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use super::lint::Level;
+use super::project::{BuildSystem, ProjectInfo};
+use super::{command_line, run_logged};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+const SOURCE_EXTENSIONS: &[&str] = &["cpp", "cc", "cxx", "c"];
+const COMPILERS: &[&str] = &["g++", "gcc", "c++", "cc", "clang", "clang++"];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AnalyzeCppParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Build target whose sysroot to analyze against (default: the
+    /// configured one).
+    #[serde(default)]
+    pub target: Option<String>,
+    /// clang-tidy `--checks` (default: the configured `clang_tidy_checks`).
+    #[serde(default)]
+    pub checks: Option<String>,
+    /// Sources relative to the project (default: every source in the
+    /// compilation database).
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+/// An entry of `compile_commands.json`.
+#[derive(Debug, Serialize, Deserialize)]
+struct CompileCommand {
+    directory: PathBuf,
+    command: String,
+    file: PathBuf,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TidyFinding {
+    pub line: usize,
+    pub column: usize,
+    pub level: Level,
+    pub message: String,
+    /// The check that fired, e.g. `bugprone-use-after-move`.
+    pub check: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FileFindings {
+    pub file: PathBuf,
+    pub findings: Vec<TidyFinding>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CppAnalysis {
+    /// The clang-tidy command that ran.
+    pub command: String,
+    pub compile_commands: PathBuf,
+    pub files_analyzed: usize,
+    pub success: bool,
+    pub exit_status: i32,
+    pub total: usize,
+    pub files: Vec<FileFindings>,
+    pub log_file: PathBuf,
+}
+
+/// Compiler invocations in `make -n` output, as compilation database
+/// entries.
+fn compile_commands_from_make(dir: &Path, output: &str) -> Vec<CompileCommand> {
+    let mut commands = Vec::new();
+    for line in output.lines().map(str::trim) {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let is_compiler = words.first().is_some_and(|program| {
+            let name = program.rsplit('/').next().unwrap_or(program);
+            COMPILERS
+                .iter()
+                .any(|c| name == *c || name.ends_with(&format!("-{c}")))
+        });
+        if !is_compiler || !words.contains(&"-c") {
+            continue;
+        }
+        let file = words.iter().enumerate().find(|(i, word)| {
+            (*i == 0 || words[i - 1] != "-o")
+                && Path::new(word)
+                    .extension()
+                    .is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext.to_string_lossy().as_ref()))
+        });
+        if let Some((_, file)) = file {
+            commands.push(CompileCommand {
+                directory: dir.to_path_buf(),
+                command: line.to_string(),
+                file: dir.join(file),
+            });
+        }
+    }
+    commands
+}
+
+/// `compile_commands.json` for the project, generated from its build
+/// system if it is not there yet: CMake writes one when configured with
+/// `CMAKE_EXPORT_COMPILE_COMMANDS`; for qmake the compiler invocations of a
+/// dry-run `make` are collected.
+async fn compilation_database(
+    server: &AuroraServer,
+    dir: &Path,
+    target: Option<&str>,
+) -> Result<Vec<CompileCommand>> {
+    let options = &server.state().config.sdk;
+    let path = dir.join("compile_commands.json");
+    let info = ProjectInfo::inspect(dir);
+    if !path.is_file() {
+        let log_file = super::log_file(server, dir, "compile-commands");
+        match info.build_system {
+            Some(BuildSystem::Cmake) => {
+                let mut command = Command::new(options.sfdk()?);
+                if let Some(target) = options.target(target)? {
+                    command.arg("-c").arg(format!("target={target}"));
+                }
+                command
+                    .args(["cmake", "-DCMAKE_EXPORT_COMPILE_COMMANDS=ON", "."])
+                    .current_dir(dir);
+                let run = run_logged(command, &log_file, options.build_timeout()).await?;
+                if run.status != 0 {
+                    return Err(Error::InvalidArgument(format!(
+                        "cmake failed (exit {}); see {}",
+                        run.status,
+                        log_file.display()
+                    )));
+                }
+            }
+            Some(BuildSystem::Qmake) => {
+                if !dir.join("Makefile").is_file() {
+                    return Err(Error::InvalidArgument(format!(
+                        "no Makefile in {}; configure the project first with configure_project",
+                        dir.display()
+                    )));
+                }
+                let mut command = options.build_shell("make", target)?;
+                command.args(["-n", "-B"]).current_dir(dir);
+                let run = run_logged(command, &log_file, options.build_timeout()).await?;
+                let commands = compile_commands_from_make(dir, &run.output);
+                if commands.is_empty() {
+                    return Err(Error::InvalidArgument(format!(
+                        "make -n printed no compiler invocations; see {}",
+                        log_file.display()
+                    )));
+                }
+                std::fs::write(
+                    &path,
+                    serde_json::to_string_pretty(&commands).map_err(std::io::Error::other)?,
+                )?;
+            }
+            _ => {
+                return Err(Error::InvalidArgument(format!(
+                    "cannot derive compile_commands.json for {}; only qmake and CMake projects are supported",
+                    dir.display()
+                )));
+            }
+        }
+    }
+    let text = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&text)
+        .map_err(|err| Error::InvalidArgument(format!("{}: {err}", path.display())))
+}
+
+/// `file:line:column: level: message [check]` lines of clang-tidy output,
+/// grouped by file. Notes are dropped.
+fn parse_findings(output: &str) -> Vec<FileFindings> {
+    let mut files: Vec<FileFindings> = Vec::new();
+    for line in output.lines() {
+        let mut parts = line.splitn(5, ':');
+        let (Some(file), Some(line_number), Some(column), Some(level), Some(message)) = (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        ) else {
+            continue;
+        };
+        let (Ok(line_number), Ok(column)) = (line_number.parse(), column.parse()) else {
+            continue;
+        };
+        let level = match level.trim() {
+            "error" | "fatal error" => Level::Error,
+            "warning" => Level::Warning,
+            _ => continue,
+        };
+        let message = message.trim();
+        let (message, check) = match message.strip_suffix(']').and_then(|m| m.rsplit_once(" [")) {
+            Some((message, check)) => (message, Some(check.to_string())),
+            None => (message, None),
+        };
+        let finding = TidyFinding {
+            line: line_number,
+            column,
+            level,
+            message: message.to_string(),
+            check,
+        };
+        let file = PathBuf::from(file);
+        match files.iter_mut().find(|entry| entry.file == file) {
+            Some(entry) => {
+                let duplicate = entry.findings.iter().any(|f| {
+                    f.line == finding.line
+                        && f.column == finding.column
+                        && f.message == finding.message
+                });
+                if !duplicate {
+                    entry.findings.push(finding);
+                }
+            }
+            None => files.push(FileFindings {
+                file,
+                findings: vec![finding],
+            }),
+        }
+    }
+    files.sort_by(|a, b| a.file.cmp(&b.file));
+    files
+}
+
+#[tool_router(router = cpp_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Run clang-tidy over a project's C++ sources inside the Build Engine against the target's sysroot. Generates compile_commands.json from the Aurora build (CMake export, or the compiler invocations of a qmake Makefile) when missing. Returns findings grouped by file with line, severity, message and check name."
+    )]
+    pub async fn analyze_cpp(
+        &self,
+        Parameters(params): Parameters<AnalyzeCppParams>,
+    ) -> Result<Json<CppAnalysis>> {
+        let options = &self.state().config.sdk;
+        let dir = options.project(params.project.as_deref())?;
+        let checks = params
+            .checks
+            .unwrap_or_else(|| options.clang_tidy_checks.clone());
+        if checks.is_empty()
+            || !checks
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-*,._".contains(c))
+        {
+            return Err(Error::InvalidArgument(format!(
+                "'{checks}' is not a clang-tidy check list"
+            )));
+        }
+        let database = compilation_database(self, &dir, params.target.as_deref()).await?;
+        let files: Vec<PathBuf> = if params.files.is_empty() {
+            database.iter().map(|entry| entry.file.clone()).collect()
+        } else {
+            params.files.iter().map(|file| dir.join(file)).collect()
+        };
+        if files.is_empty() {
+            return Err(Error::InvalidArgument(
+                "compile_commands.json lists no sources".to_string(),
+            ));
+        }
+
+        let mut command = options.build_shell("clang-tidy", params.target.as_deref())?;
+        command
+            .arg("-p")
+            .arg(&dir)
+            .arg(format!("--checks=-*,{checks}"))
+            .arg("--quiet")
+            .args(&files)
+            .current_dir(&dir);
+        let line = command_line(&command);
+        let log_file = super::log_file(self, &dir, "clang-tidy");
+        let run = run_logged(command, &log_file, options.build_timeout()).await?;
+        let findings = parse_findings(&run.output);
+        Ok(Json(CppAnalysis {
+            command: line,
+            compile_commands: dir.join("compile_commands.json"),
+            files_analyzed: files.len(),
+            success: run.status == 0,
+            exit_status: run.status,
+            total: findings.iter().map(|file| file.findings.len()).sum(),
+            files: findings,
+            log_file,
+        }))
+    }
+}
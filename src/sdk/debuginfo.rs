@@ -0,0 +1,354 @@
+//! `index_debuginfo` and `find_debuginfo`: a local store of the
+//! `-debuginfo`/`-debugsource` packages builds produce, looked up by the
+//! version a device has installed, for symbolizing its crashes.
+
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use super::lint::parse_spec;
+use super::project::ProjectInfo;
+use super::rpm::parse_rpm_name;
+use crate::device::ssh;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct IndexDebuginfoParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Extra directories with RPMs to index, besides the project's `RPMS/`
+    /// and its `build_matrix` build directories.
+    #[serde(default)]
+    pub dirs: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FindDebuginfoParams {
+    /// Package the crashed binary belongs to, e.g. `ru.example.myapp`.
+    pub package: String,
+    /// Device to read the installed version and architecture from.
+    #[serde(default)]
+    pub device: Option<String>,
+    /// `version-release` to look for instead of a device's, e.g. `1.2.0-1`.
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Architecture, with `version` (default: any).
+    #[serde(default)]
+    pub arch: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DebugKind {
+    /// Separate debug symbols, under `/usr/lib/debug`.
+    Debuginfo,
+    /// The sources the binaries were built from, under `/usr/src/debug`.
+    Debugsource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DebugPackage {
+    /// The package the symbols are for, e.g. `ru.example.myapp`.
+    pub package: String,
+    pub kind: DebugKind,
+    /// `version-release`.
+    pub version: String,
+    pub arch: String,
+    /// The copy in the local store.
+    pub path: PathBuf,
+    /// GNU build IDs of the binaries it has symbols for, when the host has
+    /// `rpm` to list them.
+    #[serde(default)]
+    pub build_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DebuginfoIndex {
+    pub store: PathBuf,
+    /// Debug packages added by this call.
+    pub added: Vec<DebugPackage>,
+    /// Debug packages in the store.
+    pub total: usize,
+    /// Built packages without a matching `-debuginfo`, as
+    /// `name-version-release.arch`.
+    pub missing: Vec<String>,
+    /// How to get the missing ones built.
+    pub hints: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DebuginfoMatch {
+    pub package: String,
+    /// The version looked for.
+    pub version: String,
+    pub arch: Option<String>,
+    pub debuginfo: Option<DebugPackage>,
+    pub debugsource: Option<DebugPackage>,
+    /// Versions the store has for the package, when neither matched.
+    pub available: Vec<String>,
+}
+
+fn rpms(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rpm"))
+        .collect()
+}
+
+fn store_dir(server: &AuroraServer) -> PathBuf {
+    server.state().config.data_dir().join("debuginfo")
+}
+
+fn load_index(store: &Path) -> Vec<DebugPackage> {
+    std::fs::read_to_string(store.join("index.json"))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// Build IDs from the `/usr/lib/debug/.build-id/xx/yyyy.debug` entries of
+/// `rpm`, when the host has `rpm` to list them.
+async fn build_ids(rpm: &Path) -> Vec<String> {
+    let Ok(output) = Command::new("rpm").arg("-qpl").arg(rpm).output().await else {
+        return Vec::new();
+    };
+    let mut ids: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let rest = line.split("/.build-id/").nth(1)?.strip_suffix(".debug")?;
+            let (prefix, suffix) = rest.split_once('/')?;
+            Some(format!("{prefix}{suffix}"))
+        })
+        .collect();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+pub async fn index(server: &AuroraServer, params: IndexDebuginfoParams) -> Result<DebuginfoIndex> {
+    let project = server
+        .state()
+        .config
+        .sdk
+        .project(params.project.as_deref())?;
+    let info = ProjectInfo::inspect(&project);
+    let name = project
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut dirs = vec![project.join("RPMS")];
+    let matrix = server.state().config.data_dir().join("matrix").join(&name);
+    if let Ok(entries) = std::fs::read_dir(&matrix) {
+        dirs.extend(
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path().join("RPMS")),
+        );
+    }
+    dirs.extend(params.dirs.iter().map(|dir| project.join(dir)));
+
+    let store = store_dir(server);
+    let mut indexed = load_index(&store);
+    let mut added = Vec::new();
+    let mut built = Vec::new();
+    for rpm in dirs.iter().flat_map(|dir| rpms(dir)) {
+        let file = rpm
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let Some((name, version, arch)) = parse_rpm_name(&file) else {
+            continue;
+        };
+        let (package, kind) = if let Some(package) = name.strip_suffix("-debuginfo") {
+            (package, DebugKind::Debuginfo)
+        } else if let Some(package) = name.strip_suffix("-debugsource") {
+            (package, DebugKind::Debugsource)
+        } else {
+            if arch != "src" && arch != "noarch" {
+                built.push((name.clone(), version, arch));
+            }
+            continue;
+        };
+        let copy = store.join(&arch).join(&file);
+        if indexed.iter().any(|entry| entry.path == copy) {
+            continue;
+        }
+        std::fs::create_dir_all(store.join(&arch))?;
+        std::fs::copy(&rpm, &copy)?;
+        let entry = DebugPackage {
+            package: package.to_string(),
+            kind,
+            version,
+            arch,
+            build_ids: match kind {
+                DebugKind::Debuginfo => build_ids(&copy).await,
+                DebugKind::Debugsource => Vec::new(),
+            },
+            path: copy,
+        };
+        indexed.push(entry.clone());
+        added.push(entry);
+    }
+    indexed
+        .sort_by(|a, b| (&a.package, &a.version, &a.arch).cmp(&(&b.package, &b.version, &b.arch)));
+    std::fs::create_dir_all(&store)?;
+    std::fs::write(
+        store.join("index.json"),
+        serde_json::to_string_pretty(&indexed).map_err(std::io::Error::other)?,
+    )?;
+
+    let mut missing: Vec<String> = built
+        .iter()
+        .filter(|(name, version, arch)| {
+            !indexed.iter().any(|entry| {
+                entry.kind == DebugKind::Debuginfo
+                    && &entry.package == name
+                    && &entry.version == version
+                    && &entry.arch == arch
+            })
+        })
+        .map(|(name, version, arch)| format!("{name}-{version}.{arch}"))
+        .collect();
+    missing.sort();
+    missing.dedup();
+
+    let mut hints = Vec::new();
+    if let Some(spec_file) = &info.spec_file {
+        let text = std::fs::read_to_string(spec_file)?;
+        let disabled = text.lines().position(|line| {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            matches!(words.as_slice(), [define, "debug_package", "%{nil}"] if *define == "%define" || *define == "%global")
+        });
+        if let Some(line) = disabled {
+            hints.push(format!(
+                "{}:{} disables debug packages (debug_package %{{nil}}); remove it.",
+                spec_file.display(),
+                line + 1
+            ));
+        }
+        if parse_spec(&text)
+            .tag("BuildArch")
+            .is_some_and(|(_, arch)| arch == "noarch")
+        {
+            hints.push("The spec is BuildArch: noarch, which has no debug packages.".to_string());
+        }
+    }
+    if !missing.is_empty() {
+        hints.push(
+            "Build with debug=true (sfdk build --enable-debug) to get -debuginfo and -debugsource packages."
+                .to_string(),
+        );
+    }
+    Ok(DebuginfoIndex {
+        store,
+        total: indexed.len(),
+        added,
+        missing,
+        hints,
+    })
+}
+
+pub async fn find(server: &AuroraServer, params: FindDebuginfoParams) -> Result<DebuginfoMatch> {
+    let (version, arch) = match (&params.version, &params.device) {
+        (Some(version), _) => (version.clone(), params.arch.clone()),
+        (None, Some(device)) => {
+            let device = server.devices().get(device)?;
+            let output = device
+                .exec(&format!(
+                    "rpm -q --qf '%{{VERSION}}-%{{RELEASE}} %{{ARCH}}\\n' {}",
+                    ssh::quote(&params.package)
+                ))
+                .await?;
+            let line = output
+                .stdout
+                .lines()
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_string();
+            let Some((version, arch)) = line.split_once(' ').filter(|_| output.success()) else {
+                return Err(Error::InvalidArgument(format!(
+                    "{} is not installed on {}",
+                    params.package,
+                    device.name()
+                )));
+            };
+            (version.to_string(), Some(arch.to_string()))
+        }
+        (None, None) => {
+            return Err(Error::InvalidArgument(
+                "pass a device or a version".to_string(),
+            ));
+        }
+    };
+
+    let indexed = load_index(&store_dir(server));
+    let lookup = |kind: DebugKind| {
+        indexed
+            .iter()
+            .find(|entry| {
+                entry.kind == kind
+                    && entry.package == params.package
+                    && entry.version == version
+                    && arch.as_ref().is_none_or(|arch| &entry.arch == arch)
+                    && entry.path.is_file()
+            })
+            .cloned()
+    };
+    let debuginfo = lookup(DebugKind::Debuginfo);
+    let debugsource = lookup(DebugKind::Debugsource);
+    let available = if debuginfo.is_none() && debugsource.is_none() {
+        let mut available: Vec<String> = indexed
+            .iter()
+            .filter(|entry| entry.package == params.package)
+            .map(|entry| format!("{}.{}", entry.version, entry.arch))
+            .collect();
+        available.dedup();
+        available
+    } else {
+        Vec::new()
+    };
+    Ok(DebuginfoMatch {
+        package: params.package,
+        version,
+        arch,
+        debuginfo,
+        debugsource,
+        available,
+    })
+}
+
+#[tool_router(router = debuginfo_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Index the -debuginfo and -debugsource RPMs a project's builds produced (RPMS/ and build_matrix directories) into a local store, with the build IDs they cover. Reports built packages that have no debuginfo and why (debug_package disabled in the spec, or not built with debug=true)."
+    )]
+    pub async fn index_debuginfo(
+        &self,
+        Parameters(params): Parameters<IndexDebuginfoParams>,
+    ) -> Result<Json<DebuginfoIndex>> {
+        index(self, params).await.map(Json)
+    }
+
+    #[tool(
+        description = "Find the indexed -debuginfo and -debugsource RPMs matching the version of a package installed on a device (or a given version-release), for symbolizing its core dumps. Lists the versions the store has when none match.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn find_debuginfo(
+        &self,
+        Parameters(params): Parameters<FindDebuginfoParams>,
+    ) -> Result<Json<DebuginfoMatch>> {
+        find(self, params).await.map(Json)
+    }
+}
@@ -0,0 +1,485 @@
+//! `dependency_graph`: the runtime dependency tree of a built RPM, from its
+//! `Requires` and the shared libraries they pull in, resolved like `ldd`
+//! against the build target's sysroot.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::mermaid::{self, Arrow, Shape};
+use super::rpm::{Header, REQUIREFLAGS, REQUIRENAME, REQUIREVERSION};
+use super::validate::newest_rpm;
+use crate::config::expand_tilde;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+const STOCK_IMAGE: &str = include_str!("data/stock-image.toml");
+
+/// Library directories searched in the sysroot, in the loader's order.
+const LIBRARY_DIRS: &[&str] = &["lib64", "usr/lib64", "lib", "usr/lib"];
+
+/// Interpreters scriptlets and scripts may require.
+const STOCK_FILES: &[&str] = &["/bin/sh", "/bin/bash", "/usr/bin/env", "/sbin/ldconfig"];
+
+/// Symlinks followed before giving up on a library.
+const MAX_LINKS: usize = 16;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct StockImage {
+    packages: Vec<String>,
+    libraries: Vec<String>,
+}
+
+impl StockImage {
+    /// The embedded list, extended by `extra`.
+    fn load(extra: Option<&Path>) -> Result<Self> {
+        let mut image: StockImage = toml::from_str(STOCK_IMAGE)
+            .map_err(|err| Error::Config(format!("stock-image.toml: {err}")))?;
+        if let Some(path) = extra {
+            let text = std::fs::read_to_string(path)
+                .map_err(|err| Error::Config(format!("{}: {err}", path.display())))?;
+            let file: StockImage = toml::from_str(&text)
+                .map_err(|err| Error::Config(format!("{}: {err}", path.display())))?;
+            image.packages.extend(file.packages);
+            image.libraries.extend(file.libraries);
+        }
+        Ok(image)
+    }
+
+    fn has(patterns: &[String], name: &str) -> bool {
+        patterns
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == pattern,
+            })
+    }
+
+    fn contains(&self, kind: NodeKind, name: &str) -> bool {
+        match kind {
+            NodeKind::Package => Self::has(&self.packages, name),
+            NodeKind::Library => Self::has(&self.libraries, name),
+            NodeKind::File => STOCK_FILES.contains(&name),
+            NodeKind::Rpm => true,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DependencyGraphParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// RPM to inspect, absolute or relative to the project (default: the
+    /// newest package in `RPMS/`, ignoring debug packages).
+    #[serde(default)]
+    pub rpm: Option<String>,
+    /// Build target whose sysroot resolves shared libraries (default: the
+    /// configured one).
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Also follow the libraries of stock libraries, e.g. Qt's own
+    /// dependencies (default: stop at what the device image ships).
+    #[serde(default)]
+    pub expand_stock: bool,
+    /// Also return the graph as a Mermaid flowchart.
+    #[serde(default)]
+    pub mermaid: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    /// The inspected package.
+    Rpm,
+    /// A package or capability named in `Requires`.
+    Package,
+    /// A shared library by soname.
+    Library,
+    /// A file dependency, e.g. `/bin/sh`.
+    File,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DependencyNode {
+    pub id: String,
+    pub kind: NodeKind,
+    /// Where a library was found, relative to the sysroot, or in the
+    /// package itself.
+    pub path: Option<String>,
+    /// The package ships the library itself.
+    pub bundled: bool,
+    /// Shipped by a stock device image.
+    pub on_stock_image: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    /// An RPM `Requires`.
+    Requires,
+    /// A `DT_NEEDED` entry of a library.
+    Needed,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DependencyEdge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+    /// Version constraint of a `Requires`, e.g. `>= 0.10.9`.
+    pub constraint: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DependencyGraph {
+    pub rpm: PathBuf,
+    /// `name-version-release.arch`.
+    pub package: String,
+    pub target: Option<String>,
+    pub sysroot: Option<PathBuf>,
+    pub nodes: Vec<DependencyNode>,
+    pub edges: Vec<DependencyEdge>,
+    /// Dependencies a stock device image does not ship and the package does
+    /// not bundle: they must be installed alongside it.
+    pub not_on_stock_image: Vec<String>,
+    /// Libraries found neither in the package nor in the sysroot.
+    pub unresolved: Vec<String>,
+    pub warnings: Vec<String>,
+    pub mermaid: Option<String>,
+}
+
+/// A `Requires` entry as `(kind, name, constraint)`, or `None` for rpmlib
+/// features and the package's own config files. Library requires lose
+/// their symbol versions and `(64bit)` marks, so `libc.so.6(GLIBC_2.4)`
+/// becomes `libc.so.6`.
+fn classify(require: &str) -> Option<(NodeKind, String, Option<String>)> {
+    let (name, constraint) = match require.split_once(' ') {
+        Some((name, constraint)) => (name, Some(constraint.trim().to_string())),
+        None => (require, None),
+    };
+    if name.starts_with("rpmlib(") || name.starts_with("config(") {
+        return None;
+    }
+    if name.starts_with('/') {
+        return Some((NodeKind::File, name.to_string(), constraint));
+    }
+    let soname = name.split('(').next().unwrap_or(name);
+    if soname.contains(".so") {
+        return Some((NodeKind::Library, soname.to_string(), None));
+    }
+    Some((NodeKind::Package, name.to_string(), constraint))
+}
+
+/// `path` inside `sysroot` with symlinks followed, absolute link targets
+/// taken relative to the sysroot rather than the host.
+fn resolve_in_sysroot(sysroot: &Path, path: &str) -> Option<PathBuf> {
+    let mut relative = PathBuf::from(path.trim_start_matches('/'));
+    for _ in 0..MAX_LINKS {
+        let host = sysroot.join(&relative);
+        let Ok(target) = std::fs::read_link(&host) else {
+            return host.is_file().then_some(host);
+        };
+        relative = if target.is_absolute() {
+            target.strip_prefix("/").ok()?.to_path_buf()
+        } else {
+            relative.parent()?.join(target)
+        };
+    }
+    None
+}
+
+/// The `DT_NEEDED` sonames of an ELF shared object or executable.
+fn needed(bytes: &[u8]) -> Option<Vec<String>> {
+    if !bytes.starts_with(b"\x7fELF") {
+        return None;
+    }
+    let wide = *bytes.get(4)? == 2;
+    let big = *bytes.get(5)? == 2;
+    let int = |at: usize, width: usize| -> Option<u64> {
+        let field = bytes.get(at..at + width)?;
+        Some(if big {
+            field.iter().fold(0u64, |n, b| n << 8 | *b as u64)
+        } else {
+            field.iter().rev().fold(0u64, |n, b| n << 8 | *b as u64)
+        })
+    };
+    let word = if wide { 8 } else { 4 };
+    let (phoff, phentsize, phnum) = if wide {
+        (int(32, 8)?, int(54, 2)?, int(56, 2)?)
+    } else {
+        (int(28, 4)?, int(42, 2)?, int(44, 2)?)
+    };
+
+    // (type, offset, vaddr, filesz) of each program header.
+    let segments: Vec<(u64, u64, u64, u64)> = (0..phnum)
+        .filter_map(|i| {
+            let at = (phoff + i * phentsize) as usize;
+            if wide {
+                Some((
+                    int(at, 4)?,
+                    int(at + 8, 8)?,
+                    int(at + 16, 8)?,
+                    int(at + 32, 8)?,
+                ))
+            } else {
+                Some((
+                    int(at, 4)?,
+                    int(at + 4, 4)?,
+                    int(at + 8, 4)?,
+                    int(at + 16, 4)?,
+                ))
+            }
+        })
+        .collect();
+    let (_, dynamic, _, dynamic_size) = *segments.iter().find(|segment| segment.0 == 2)?;
+    let entries: Vec<(u64, u64)> = (0..dynamic_size / (2 * word as u64))
+        .map_while(|i| {
+            let at = (dynamic + i * 2 * word as u64) as usize;
+            Some((int(at, word)?, int(at + word, word)?))
+        })
+        .take_while(|(tag, _)| *tag != 0)
+        .collect();
+    // DT_STRTAB is an address; map it to a file offset through PT_LOAD.
+    let strtab = entries.iter().find(|(tag, _)| *tag == 5)?.1;
+    let strtab = segments
+        .iter()
+        .find(|(kind, _, vaddr, size)| *kind == 1 && (*vaddr..vaddr + size).contains(&strtab))
+        .map(|(_, offset, vaddr, _)| strtab - vaddr + offset)?;
+    Some(
+        entries
+            .iter()
+            .filter(|(tag, _)| *tag == 1)
+            .filter_map(|(_, name)| {
+                let start = (strtab + name) as usize;
+                let rest = bytes.get(start..)?;
+                let end = rest.iter().position(|b| *b == 0)?;
+                Some(String::from_utf8_lossy(&rest[..end]).into_owned())
+            })
+            .collect(),
+    )
+}
+
+fn mermaid(nodes: &[DependencyNode], edges: &[DependencyEdge]) -> String {
+    mermaid::flowchart(
+        nodes.iter().map(|node| {
+            let shape = match node.kind {
+                NodeKind::Rpm => Shape::Subroutine,
+                NodeKind::Package => Shape::Rectangle,
+                NodeKind::Library => Shape::Stadium,
+                NodeKind::File => Shape::Parallelogram,
+            };
+            (node.id.as_str(), shape)
+        }),
+        edges.iter().map(|edge| {
+            let arrow = Arrow {
+                dotted: edge.kind == EdgeKind::Needed,
+                label: edge.constraint.as_deref(),
+            };
+            (edge.from.as_str(), edge.to.as_str(), arrow)
+        }),
+        nodes
+            .iter()
+            .filter(|node| node.kind != NodeKind::Rpm && !node.on_stock_image && !node.bundled)
+            .map(|node| node.id.as_str()),
+    )
+}
+
+pub async fn graph(
+    server: &AuroraServer,
+    params: DependencyGraphParams,
+) -> Result<DependencyGraph> {
+    let options = &server.state().config.sdk;
+    let project = options.project(params.project.as_deref())?;
+    let rpm = match &params.rpm {
+        Some(rpm) => project.join(rpm),
+        None => newest_rpm(&project.join("RPMS")).ok_or_else(|| {
+            Error::InvalidArgument(format!(
+                "no RPM in {}; build the project first or pass rpm",
+                project.join("RPMS").display()
+            ))
+        })?,
+    };
+    let header = Header::read(&rpm)?;
+    let (name, version, arch) = header.nvra();
+    let package = format!("{name}-{version}.{arch}");
+    let image = StockImage::load(options.stock_image.as_deref().map(expand_tilde).as_deref())?;
+
+    let mut warnings = Vec::new();
+    let target = options.target(params.target.as_deref())?;
+    let sysroot = match &target {
+        Some(target) => {
+            let sysroot = options.sysroot(target)?;
+            if sysroot.is_dir() {
+                Some(sysroot)
+            } else {
+                warnings.push(format!(
+                    "{} does not exist; shared libraries were not resolved (set sysroots in the [sdk] config table)",
+                    sysroot.display()
+                ));
+                None
+            }
+        }
+        None => {
+            warnings
+                .push("no build target configured; shared libraries were not resolved".to_string());
+            None
+        }
+    };
+
+    // Libraries the package ships itself, by file name.
+    let bundled: BTreeMap<String, String> = header
+        .files()
+        .into_iter()
+        .filter_map(|file| {
+            let base = file.path.rsplit('/').next()?.to_string();
+            base.contains(".so").then_some((base, file.path))
+        })
+        .collect();
+
+    let mut nodes = vec![DependencyNode {
+        id: package.clone(),
+        kind: NodeKind::Rpm,
+        path: None,
+        bundled: false,
+        on_stock_image: false,
+    }];
+    let mut edges: Vec<DependencyEdge> = Vec::new();
+    let mut seen: BTreeSet<String> = BTreeSet::from([package.clone()]);
+    let mut queue: VecDeque<String> = VecDeque::new();
+    let mut unresolved = Vec::new();
+    let mut add = |nodes: &mut Vec<DependencyNode>,
+                   queue: &mut VecDeque<String>,
+                   unresolved: &mut Vec<String>,
+                   kind: NodeKind,
+                   id: &str| {
+        if !seen.insert(id.to_string()) {
+            return;
+        }
+        let on_stock_image = image.contains(kind, id);
+        let mut node = DependencyNode {
+            id: id.to_string(),
+            kind,
+            path: None,
+            bundled: false,
+            on_stock_image,
+        };
+        if kind == NodeKind::Library {
+            if let Some(path) = bundled.get(id) {
+                node.path = Some(path.clone());
+                node.bundled = true;
+            } else if let Some(sysroot) = &sysroot {
+                let found = LIBRARY_DIRS
+                    .iter()
+                    .map(|dir| format!("/{dir}/{id}"))
+                    .find(|path| resolve_in_sysroot(sysroot, path).is_some());
+                match found {
+                    Some(path) => {
+                        node.path = Some(path);
+                        if !on_stock_image || params.expand_stock {
+                            queue.push_back(id.to_string());
+                        }
+                    }
+                    None => unresolved.push(id.to_string()),
+                }
+            }
+        }
+        nodes.push(node);
+    };
+
+    let mut requires: Vec<(NodeKind, String, Option<String>)> = header
+        .dependencies(REQUIRENAME, REQUIREFLAGS, REQUIREVERSION)
+        .iter()
+        .filter_map(|require| classify(require))
+        .collect();
+    requires.sort();
+    requires.dedup_by(|a, b| a.0 == b.0 && a.1 == b.1);
+    for (kind, id, constraint) in requires {
+        // The package's own provides, e.g. a bundled library, are not
+        // dependencies.
+        if id == name {
+            continue;
+        }
+        add(&mut nodes, &mut queue, &mut unresolved, kind, &id);
+        edges.push(DependencyEdge {
+            from: package.clone(),
+            to: id,
+            kind: EdgeKind::Requires,
+            constraint,
+        });
+    }
+
+    while let Some(library) = queue.pop_front() {
+        let Some(sysroot) = &sysroot else {
+            break;
+        };
+        let path = nodes
+            .iter()
+            .find(|node| node.id == library)
+            .and_then(|node| node.path.clone())
+            .and_then(|path| resolve_in_sysroot(sysroot, &path));
+        let Some(bytes) = path.and_then(|path| std::fs::read(path).ok()) else {
+            continue;
+        };
+        let Some(sonames) = needed(&bytes) else {
+            warnings.push(format!(
+                "{library} is not an ELF file with a dynamic section"
+            ));
+            continue;
+        };
+        for soname in sonames {
+            add(
+                &mut nodes,
+                &mut queue,
+                &mut unresolved,
+                NodeKind::Library,
+                &soname,
+            );
+            edges.push(DependencyEdge {
+                from: library.clone(),
+                to: soname,
+                kind: EdgeKind::Needed,
+                constraint: None,
+            });
+        }
+    }
+
+    let not_on_stock_image = nodes
+        .iter()
+        .filter(|node| node.kind != NodeKind::Rpm && !node.on_stock_image && !node.bundled)
+        .map(|node| node.id.clone())
+        .collect();
+    let mermaid = params.mermaid.then(|| mermaid(&nodes, &edges));
+    Ok(DependencyGraph {
+        rpm,
+        package,
+        target,
+        sysroot,
+        nodes,
+        edges,
+        not_on_stock_image,
+        unresolved,
+        warnings,
+        mermaid,
+    })
+}
+
+#[tool_router(router = depgraph_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Resolve the runtime dependency tree of a built RPM: its Requires (packages, shared libraries, files) and, like ldd inside the build target's sysroot, the libraries each library needs in turn. Flags dependencies a stock device image does not ship and the package does not bundle, and libraries the sysroot lacks. Returns nodes and edges, plus a Mermaid flowchart with mermaid=true.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn dependency_graph(
+        &self,
+        Parameters(params): Parameters<DependencyGraphParams>,
+    ) -> Result<Json<DependencyGraph>> {
+        graph(self, params).await.map(Json)
+    }
+}
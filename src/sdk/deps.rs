@@ -0,0 +1,354 @@
+//! `check_dependencies`: whether a spec's `BuildRequires` and `Requires`
+//! can be satisfied from the build target's repositories, asked of zypper
+//! inside the Build Engine before a long build finds out halfway.
+
+use std::cmp::Ordering;
+use std::path::PathBuf;
+
+use rmcp::handler::server::tool::cached_schema_for_type;
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::CallToolResult;
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::lint::parse_spec;
+use super::project::ProjectInfo;
+use super::run_logged;
+use crate::device::ssh;
+use crate::error::{Error, Result, tool_result};
+use crate::resources::file_link;
+use crate::server::AuroraServer;
+
+const OPERATORS: &[&str] = &[">=", "<=", "=", "==", ">", "<"];
+
+/// Marks the start of one dependency's zypper output in the combined log.
+const MARKER: &str = "### dependency ";
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CheckDependenciesParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Spec file, absolute or relative to the project (default: the one in
+    /// `rpm/`).
+    #[serde(default)]
+    pub spec: Option<String>,
+    /// Build target whose repositories to search (default: the configured
+    /// one).
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Check only `BuildRequires`, not the runtime `Requires`.
+    #[serde(default)]
+    pub build_only: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    Build,
+    Runtime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyStatus {
+    Available,
+    Missing,
+    /// Provided, but by no package whose version meets the constraint.
+    VersionMismatch,
+    /// Not searched: the name uses spec macros.
+    Unchecked,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Provider {
+    pub package: String,
+    pub version: String,
+    pub arch: String,
+    pub repository: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DependencyCheck {
+    pub kind: DependencyKind,
+    /// Capability as written, e.g. `pkgconfig(Qt5Core)`.
+    pub name: String,
+    /// Version constraint, e.g. `>= 5.6`.
+    pub constraint: Option<String>,
+    pub line: usize,
+    pub status: DependencyStatus,
+    /// Packages in the target's repositories providing the capability;
+    /// constraints are compared with their versions.
+    pub providers: Vec<Provider>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DependencyReport {
+    pub spec: PathBuf,
+    pub target: Option<String>,
+    /// Nothing is missing or version-mismatched.
+    pub passed: bool,
+    pub missing: usize,
+    pub mismatched: usize,
+    pub dependencies: Vec<DependencyCheck>,
+    pub log_file: PathBuf,
+}
+
+/// `(name, constraint)` pairs of a `Requires`-style value:
+/// `a, b >= 1.0 c`.
+fn split_dependencies(value: &str) -> Vec<(String, Option<(String, String)>)> {
+    let tokens: Vec<&str> = value
+        .split([',', ' ', '\t'])
+        .filter(|token| !token.is_empty())
+        .collect();
+    let mut dependencies = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let name = tokens[i].to_string();
+        if let (Some(op), Some(version)) = (tokens.get(i + 1), tokens.get(i + 2))
+            && OPERATORS.contains(op)
+        {
+            dependencies.push((name, Some((op.to_string(), version.to_string()))));
+            i += 3;
+        } else {
+            dependencies.push((name, None));
+            i += 1;
+        }
+    }
+    dependencies
+}
+
+/// rpm-style version comparison: runs of digits compare numerically, runs
+/// of letters lexically, and a numeric run beats an alphabetic one.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn segments(version: &str) -> Vec<&str> {
+        let mut segments = Vec::new();
+        let mut rest = version;
+        while !rest.is_empty() {
+            rest = rest.trim_start_matches(|c: char| !c.is_ascii_alphanumeric());
+            let Some(first) = rest.chars().next() else {
+                break;
+            };
+            let end = if first.is_ascii_digit() {
+                rest.find(|c: char| !c.is_ascii_digit())
+            } else {
+                rest.find(|c: char| !c.is_ascii_alphabetic())
+            }
+            .unwrap_or(rest.len());
+            segments.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+        segments
+    }
+    let (a, b) = (segments(a), segments(b));
+    for (x, y) in a.iter().zip(&b) {
+        let numeric = (
+            x.starts_with(|c: char| c.is_ascii_digit()),
+            y.starts_with(|c: char| c.is_ascii_digit()),
+        );
+        let order = match numeric {
+            (true, true) => {
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+            }
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => x.cmp(y),
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// Whether `version-release` meets `op wanted`. A wanted version without a
+/// release is compared with the version part only.
+fn satisfies(available: &str, op: &str, wanted: &str) -> bool {
+    let available = available
+        .split_once(':')
+        .map_or(available, |(_, rest)| rest);
+    let available = if wanted.contains('-') {
+        available
+    } else {
+        available.rsplit_once('-').map_or(available, |(v, _)| v)
+    };
+    let order = compare_versions(available, wanted);
+    match op {
+        ">=" => order != Ordering::Less,
+        "<=" => order != Ordering::Greater,
+        ">" => order == Ordering::Greater,
+        "<" => order == Ordering::Less,
+        _ => order == Ordering::Equal,
+    }
+}
+
+/// Rows of a `zypper search --details` table.
+fn parse_providers(output: &str) -> Vec<Provider> {
+    let mut providers: Vec<Provider> = Vec::new();
+    for line in output.lines() {
+        let columns: Vec<&str> = line.split('|').map(str::trim).collect();
+        if columns.len() < 6 || columns[1] == "Name" || columns[1].starts_with('-') {
+            continue;
+        }
+        let provider = Provider {
+            package: columns[1].to_string(),
+            version: columns[3].to_string(),
+            arch: columns[4].to_string(),
+            repository: columns[5].to_string(),
+        };
+        let seen = providers.iter().any(|p| {
+            p.package == provider.package
+                && p.version == provider.version
+                && p.repository == provider.repository
+        });
+        if !seen {
+            providers.push(provider);
+        }
+    }
+    providers
+}
+
+pub async fn check(
+    server: &AuroraServer,
+    params: CheckDependenciesParams,
+) -> Result<DependencyReport> {
+    let options = &server.state().config.sdk;
+    let dir = options.project(params.project.as_deref())?;
+    let target = options.target(params.target.as_deref())?;
+    let spec_path = match params.spec {
+        Some(spec) => dir.join(spec),
+        None => ProjectInfo::inspect(&dir).spec_file.ok_or_else(|| {
+            Error::InvalidArgument(format!(
+                "no .spec file in {}; pass spec or create one with generate_spec",
+                dir.join("rpm").display()
+            ))
+        })?,
+    };
+    let text = std::fs::read_to_string(&spec_path)
+        .map_err(|err| Error::InvalidArgument(format!("{}: {err}", spec_path.display())))?;
+    let spec = parse_spec(&text);
+
+    let mut dependencies = Vec::new();
+    let runtime: &[(usize, &str)] = if params.build_only {
+        &[]
+    } else {
+        &spec.requires
+    };
+    let lines = spec
+        .build_requires
+        .iter()
+        .map(|line| (DependencyKind::Build, line))
+        .chain(runtime.iter().map(|line| (DependencyKind::Runtime, line)));
+    for (kind, (line, value)) in lines {
+        for (name, constraint) in split_dependencies(value) {
+            let unchecked = name.contains(['%', '\'']);
+            dependencies.push(DependencyCheck {
+                kind,
+                name,
+                constraint: constraint.map(|(op, version)| format!("{op} {version}")),
+                line: *line,
+                status: if unchecked {
+                    DependencyStatus::Unchecked
+                } else {
+                    DependencyStatus::Missing
+                },
+                providers: Vec::new(),
+            });
+        }
+    }
+    if dependencies.is_empty() {
+        return Err(Error::InvalidArgument(format!(
+            "{} declares no dependencies",
+            spec_path.display()
+        )));
+    }
+
+    let mut script = String::new();
+    for (index, dependency) in dependencies.iter().enumerate() {
+        if dependency.status == DependencyStatus::Unchecked {
+            continue;
+        }
+        script.push_str(&format!(
+            "echo '{MARKER}{index}'; zypper --non-interactive --no-refresh search --provides --match-exact --details -t package -- {}; ",
+            ssh::quote(&dependency.name)
+        ));
+    }
+    let mut command = options.build_shell("sh", target.as_deref())?;
+    command.arg("-c").arg(script).current_dir(&dir);
+    let log_file = super::log_file(server, &dir, "dependencies");
+    let run = run_logged(command, &log_file, options.build_timeout()).await?;
+    if !run.output.contains(MARKER) {
+        return Err(Error::InvalidArgument(format!(
+            "could not query the target's repositories (exit {}); see {}",
+            run.status,
+            log_file.display()
+        )));
+    }
+
+    for section in run.output.split(MARKER).skip(1) {
+        let (index, output) = section.split_once('\n').unwrap_or((section, ""));
+        let Some(dependency) = index
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| dependencies.get_mut(index))
+        else {
+            continue;
+        };
+        dependency.providers = parse_providers(output);
+        dependency.status = match &dependency.constraint {
+            _ if dependency.providers.is_empty() => DependencyStatus::Missing,
+            Some(constraint) => {
+                let (op, wanted) = constraint.split_once(' ').unwrap_or(("=", constraint));
+                if dependency
+                    .providers
+                    .iter()
+                    .any(|provider| satisfies(&provider.version, op, wanted))
+                {
+                    DependencyStatus::Available
+                } else {
+                    DependencyStatus::VersionMismatch
+                }
+            }
+            None => DependencyStatus::Available,
+        };
+    }
+
+    let count = |status| dependencies.iter().filter(|d| d.status == status).count();
+    let missing = count(DependencyStatus::Missing);
+    let mismatched = count(DependencyStatus::VersionMismatch);
+    Ok(DependencyReport {
+        spec: spec_path,
+        target,
+        passed: missing == 0 && mismatched == 0,
+        missing,
+        mismatched,
+        dependencies,
+        log_file,
+    })
+}
+
+#[tool_router(router = deps_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Check a spec's BuildRequires and Requires against the build target's repositories (zypper in the Build Engine) before building. Reports each dependency as available, missing or version-mismatched, with the packages, versions and repositories providing it, and links the full query log.",
+        output_schema = cached_schema_for_type::<DependencyReport>(),
+        annotations(read_only_hint = true)
+    )]
+    pub async fn check_dependencies(
+        &self,
+        Parameters(params): Parameters<CheckDependenciesParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        tool_result(check(self, params).await.and_then(|report| {
+            let link = file_link(&report.log_file, Some("text/plain"));
+            let mut result = CallToolResult::structured(
+                serde_json::to_value(&report).map_err(std::io::Error::other)?,
+            );
+            result.content.push(link);
+            Ok(result)
+        }))
+    }
+}
@@ -0,0 +1,528 @@
+//! `validate_desktop` and `generate_desktop`: the application's `.desktop`
+//! file, cross-checked against the package name, binary and icons.
+
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::lint::{Level, LintIssue};
+use super::project::ProjectInfo;
+use crate::error::{Error, Result, require_confirmation};
+use crate::server::AuroraServer;
+
+/// Permissions an application may request in `[X-Application]`.
+pub(crate) const PERMISSIONS: &[&str] = &[
+    "Accounts",
+    "Audio",
+    "Bluetooth",
+    "Calendar",
+    "CallRecordings",
+    "Camera",
+    "Compatibility",
+    "Contacts",
+    "DeviceInfo",
+    "Documents",
+    "Downloads",
+    "Internet",
+    "Location",
+    "MediaIndexing",
+    "Messages",
+    "Microphone",
+    "Music",
+    "NFC",
+    "Pictures",
+    "PushNotifications",
+    "RemovableMedia",
+    "Sensors",
+    "UserDirs",
+    "Videos",
+    "WebView",
+];
+
+/// Permissions implied by the Qt modules a project links, as (module,
+/// permission).
+const MODULE_PERMISSIONS: &[(&str, &str)] = &[
+    ("Network", "Internet"),
+    ("WebSockets", "Internet"),
+    ("Positioning", "Location"),
+    ("Location", "Location"),
+    ("Sensors", "Sensors"),
+    ("Bluetooth", "Bluetooth"),
+    ("Nfc", "NFC"),
+    ("Multimedia", "Audio"),
+];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ValidateDesktopParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Desktop file, absolute or relative to the project (default: the one
+    /// in the project directory).
+    #[serde(default)]
+    pub desktop: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DesktopReport {
+    pub desktop: PathBuf,
+    /// Package name the file was checked against: the spec's `Name`, else
+    /// the project's target.
+    pub package: String,
+    /// No errors were found; warnings may remain.
+    pub passed: bool,
+    pub issues: Vec<LintIssue>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GenerateDesktopParams {
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Package name, `<organization>.<application>` (default: the spec's
+    /// `Name`, else the project's target).
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Launcher title (default: the existing desktop file's `Name`, else the
+    /// application part of the package name).
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Permissions, e.g. `["Internet", "Location"]` (default: the existing
+    /// ones, else those implied by the Qt modules the project uses).
+    #[serde(default)]
+    pub permissions: Option<Vec<String>>,
+    /// Write the file to `<name>.desktop` instead of only returning it.
+    #[serde(default)]
+    pub write: bool,
+    /// Must be true to replace an existing desktop file.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GeneratedDesktop {
+    pub package: String,
+    /// Where the file was written, when `write` was set.
+    pub written_to: Option<PathBuf>,
+    pub desktop: String,
+}
+
+/// A `key=value` line and the `[group]` it is in.
+pub(crate) struct Entry<'a> {
+    pub(crate) line: usize,
+    pub(crate) group: &'a str,
+    pub(crate) key: &'a str,
+    pub(crate) value: &'a str,
+}
+
+pub(crate) fn parse(text: &str) -> Vec<Entry<'_>> {
+    let mut entries = Vec::new();
+    let mut group = "";
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            group = name;
+        } else if let Some((key, value)) = line.split_once('=')
+            && !line.starts_with('#')
+        {
+            entries.push(Entry {
+                line: index + 1,
+                group,
+                key: key.trim(),
+                value: value.trim(),
+            });
+        }
+    }
+    entries
+}
+
+pub(crate) fn find<'a>(entries: &'a [Entry<'a>], group: &str, key: &str) -> Option<&'a Entry<'a>> {
+    entries.iter().find(|e| e.group == group && e.key == key)
+}
+
+/// The spec's `Name`, else the project's target name.
+pub fn package_name(info: &ProjectInfo) -> String {
+    info.spec_file
+        .as_ref()
+        .and_then(|spec| std::fs::read_to_string(spec).ok())
+        .and_then(|text| {
+            text.lines()
+                .find_map(|line| line.strip_prefix("Name:"))
+                .map(|name| name.trim().to_string())
+        })
+        .filter(|name| !name.is_empty() && !name.contains('%'))
+        .unwrap_or_else(|| info.name.clone())
+}
+
+/// The binary `Exec` starts, skipping an `invoker` wrapper and its flags.
+/// For `sailfish-qml`, the launcher of QML and Python apps, it is the app
+/// name that follows.
+fn exec_binary(exec: &str) -> &str {
+    let mut words = exec.split_whitespace();
+    let first = words.next().unwrap_or("");
+    if matches!(first.rsplit('/').next(), Some("invoker" | "sailfish-qml")) {
+        return words.find(|word| !word.starts_with('-')).unwrap_or("");
+    }
+    first
+}
+
+/// The `Exec` line that starts the app: its binary, or `sailfish-qml` with
+/// the package name for Python apps.
+fn expected_exec(info: &ProjectInfo) -> String {
+    if info.is_python_app() {
+        format!("/usr/bin/sailfish-qml {}", info.name)
+    } else {
+        format!("/usr/bin/{}", info.name)
+    }
+}
+
+/// Checks `desktop` against `package`: required keys, `[X-Application]`,
+/// permissions, and that the file name, `Exec` binary and `Icon` match what
+/// the spec installs.
+pub fn check(info: &ProjectInfo, desktop: &Path, package: &str) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    let text = match std::fs::read_to_string(desktop) {
+        Ok(text) => text,
+        Err(err) => {
+            issues.push(LintIssue {
+                level: Level::Error,
+                rule: "desktop-unreadable".to_string(),
+                file: desktop.to_path_buf(),
+                line: None,
+                message: err.to_string(),
+                suggestion: "Create it with generate_desktop.".to_string(),
+            });
+            return issues;
+        }
+    };
+    let entries = parse(&text);
+    let mut add = |level, rule: &str, line: Option<usize>, message: String, suggestion: String| {
+        issues.push(LintIssue {
+            level,
+            rule: rule.to_string(),
+            file: desktop.to_path_buf(),
+            line,
+            message,
+            suggestion,
+        });
+    };
+
+    if let Some(stem) = desktop.file_stem().and_then(|stem| stem.to_str())
+        && stem != package
+    {
+        add(
+            Level::Error,
+            "desktop-file-name",
+            None,
+            format!("the file is named {stem}.desktop but the package is {package}"),
+            format!("Rename it to {package}.desktop; the spec installs %{{name}}.desktop."),
+        );
+    }
+
+    for key in ["Type", "Name", "Icon", "Exec"] {
+        if find(&entries, "Desktop Entry", key).is_none() {
+            add(
+                Level::Error,
+                "desktop-missing-key",
+                None,
+                format!("[Desktop Entry] has no {key}= entry"),
+                "Add it to the [Desktop Entry] group.".to_string(),
+            );
+        }
+    }
+    if let Some(entry) = find(&entries, "Desktop Entry", "Type")
+        && entry.value != "Application"
+    {
+        add(
+            Level::Error,
+            "desktop-type",
+            Some(entry.line),
+            format!("Type={} is not shown in the launcher", entry.value),
+            "Use Type=Application.".to_string(),
+        );
+    }
+    if let Some(entry) = find(&entries, "Desktop Entry", "Exec") {
+        let binary = exec_binary(entry.value);
+        let expected = expected_exec(info);
+        if info.is_python_app() {
+            if entry.value != expected {
+                add(
+                    Level::Error,
+                    "desktop-exec",
+                    Some(entry.line),
+                    format!(
+                        "Exec={} does not launch {} with sailfish-qml",
+                        entry.value, info.name
+                    ),
+                    format!("Use Exec={expected}."),
+                );
+            }
+        } else if binary != expected && binary.rsplit('/').next() != Some(info.name.as_str()) {
+            add(
+                Level::Error,
+                "desktop-exec",
+                Some(entry.line),
+                format!("Exec runs {binary} but the project builds {}", info.name),
+                format!("Use Exec={expected}."),
+            );
+        } else if binary != expected {
+            add(
+                Level::Warning,
+                "desktop-exec",
+                Some(entry.line),
+                format!("Exec runs {binary}, not an absolute path under /usr/bin"),
+                format!("Use Exec={expected}."),
+            );
+        }
+    }
+    if let Some(entry) = find(&entries, "Desktop Entry", "Icon") {
+        if entry.value != package {
+            add(
+                Level::Error,
+                "desktop-icon",
+                Some(entry.line),
+                format!(
+                    "Icon={} but the spec installs icons as {package}.png",
+                    entry.value
+                ),
+                format!("Use Icon={package}."),
+            );
+        }
+        let missing: Vec<&String> = info
+            .icon_sizes
+            .iter()
+            .filter(|size| {
+                !info
+                    .dir
+                    .join("icons")
+                    .join(size)
+                    .join(format!("{}.png", entry.value))
+                    .is_file()
+            })
+            .collect();
+        if !missing.is_empty() {
+            add(
+                Level::Warning,
+                "desktop-icon",
+                Some(entry.line),
+                format!(
+                    "no icons/<size>/{}.png for {}",
+                    entry.value,
+                    missing
+                        .iter()
+                        .map(|s| s.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                "Add an icon for every size under icons/.".to_string(),
+            );
+        }
+    }
+    if info.uses_silica && find(&entries, "Desktop Entry", "X-Nemo-Application-Type").is_none() {
+        add(
+            Level::Warning,
+            "desktop-application-type",
+            None,
+            "a Silica app without X-Nemo-Application-Type starts without the booster".to_string(),
+            "Add X-Nemo-Application-Type=silica-qt5.".to_string(),
+        );
+    }
+    if entries.iter().any(|e| e.group == "X-Sailjail") {
+        add(
+            Level::Warning,
+            "desktop-sailjail",
+            None,
+            "[X-Sailjail] is the Sailfish OS group; Aurora OS reads [X-Application]".to_string(),
+            "Move Permissions, OrganizationName and ApplicationName to [X-Application]."
+                .to_string(),
+        );
+    }
+
+    if !text.contains("[X-Application]") {
+        add(
+            Level::Error,
+            "desktop-missing-x-application",
+            None,
+            "the desktop file has no [X-Application] group".to_string(),
+            "Add [X-Application] with Permissions=, OrganizationName= and ApplicationName=; Aurora OS refuses to launch apps without it."
+                .to_string(),
+        );
+        return issues;
+    }
+    for key in ["Permissions", "OrganizationName", "ApplicationName"] {
+        if find(&entries, "X-Application", key).is_none() {
+            add(
+                Level::Error,
+                "desktop-missing-key",
+                None,
+                format!("[X-Application] has no {key}= entry"),
+                "Add it; OrganizationName.ApplicationName must match the package name.".to_string(),
+            );
+        }
+    }
+    if let (Some(org), Some(app)) = (
+        find(&entries, "X-Application", "OrganizationName"),
+        find(&entries, "X-Application", "ApplicationName"),
+    ) && format!("{}.{}", org.value, app.value) != package
+    {
+        add(
+            Level::Error,
+            "desktop-application-id",
+            Some(org.line),
+            format!(
+                "OrganizationName.ApplicationName is {}.{} but the package is {package}",
+                org.value, app.value
+            ),
+            "They name the app's sandbox and data directories; make them match the package."
+                .to_string(),
+        );
+    }
+    if let Some(entry) = find(&entries, "X-Application", "Permissions") {
+        for permission in entry
+            .value
+            .split(';')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+        {
+            if !PERMISSIONS.contains(&permission) {
+                add(
+                    Level::Warning,
+                    "desktop-permission",
+                    Some(entry.line),
+                    format!("unknown permission {permission}"),
+                    format!("Known permissions: {}.", PERMISSIONS.join(", ")),
+                );
+            }
+        }
+    }
+    issues
+}
+
+/// Desktop file contents for `package`, `<organization>.<application>`.
+pub fn render(info: &ProjectInfo, package: &str, title: &str, permissions: &[String]) -> String {
+    let (org, app) = package.rsplit_once('.').unwrap_or(("", package));
+    let mut desktop = String::from("[Desktop Entry]\nType=Application\n");
+    if info.uses_silica {
+        desktop.push_str("X-Nemo-Application-Type=silica-qt5\n");
+    }
+    desktop.push_str(&format!(
+        "Name={title}\nIcon={package}\nExec={}\n\n[X-Application]\nPermissions={}\nOrganizationName={org}\nApplicationName={app}\n",
+        expected_exec(info),
+        permissions.join(";")
+    ));
+    desktop
+}
+
+#[tool_router(router = desktop_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Validate an Aurora app's .desktop file: required keys, the [X-Application] group and permissions, and that the file name, Exec binary, Icon and OrganizationName.ApplicationName match the spec's package and the built target — the usual reasons an app does not appear in the launcher.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn validate_desktop(
+        &self,
+        Parameters(params): Parameters<ValidateDesktopParams>,
+    ) -> Result<Json<DesktopReport>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        let info = ProjectInfo::inspect(&dir);
+        let desktop = match params.desktop {
+            Some(desktop) => dir.join(desktop),
+            None => info.desktop_file.clone().ok_or_else(|| {
+                Error::InvalidArgument(format!(
+                    "no .desktop file in {}; pass desktop or create one with generate_desktop",
+                    dir.display()
+                ))
+            })?,
+        };
+        let package = package_name(&info);
+        let issues = check(&info, &desktop, &package);
+        Ok(Json(DesktopReport {
+            desktop,
+            passed: !issues.iter().any(|issue| issue.level == Level::Error),
+            package,
+            issues,
+        }))
+    }
+
+    #[tool(
+        description = "Generate an Aurora .desktop file for a project: Exec and Icon matching the package and binary, the Silica application type, and [X-Application] with permissions (inferred from Qt modules unless given). Returns it for review; set write=true to save it as <name>.desktop (replacing one needs confirm=true)."
+    )]
+    pub async fn generate_desktop(
+        &self,
+        Parameters(params): Parameters<GenerateDesktopParams>,
+    ) -> Result<Json<GeneratedDesktop>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        let info = ProjectInfo::inspect(&dir);
+        let package = params.name.unwrap_or_else(|| package_name(&info));
+        let valid = package.split('.').count() >= 2
+            && package.split('.').all(|part| {
+                !part.is_empty()
+                    && part
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            });
+        if !valid {
+            return Err(Error::InvalidArgument(format!(
+                "'{package}' is not <organization>.<application>, e.g. ru.example.notes"
+            )));
+        }
+        let existing = info
+            .desktop_file
+            .as_ref()
+            .and_then(|file| std::fs::read_to_string(file).ok())
+            .unwrap_or_default();
+        let existing = parse(&existing);
+        let title = params
+            .title
+            .or_else(|| find(&existing, "Desktop Entry", "Name").map(|e| e.value.to_string()))
+            .unwrap_or_else(|| package.rsplit('.').next().unwrap_or(&package).to_string());
+        let permissions = match params.permissions {
+            Some(permissions) => permissions,
+            None => match find(&existing, "X-Application", "Permissions") {
+                Some(entry) => entry
+                    .value
+                    .split(';')
+                    .filter(|p| !p.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                None => {
+                    let mut implied: Vec<String> = MODULE_PERMISSIONS
+                        .iter()
+                        .filter(|(module, _)| info.qt_modules.iter().any(|m| m == module))
+                        .map(|(_, permission)| permission.to_string())
+                        .collect();
+                    implied.dedup();
+                    implied
+                }
+            },
+        };
+        if let Some(permission) = permissions
+            .iter()
+            .find(|p| !PERMISSIONS.contains(&p.as_str()))
+        {
+            return Err(Error::InvalidArgument(format!(
+                "unknown permission '{permission}'; known: {}",
+                PERMISSIONS.join(", ")
+            )));
+        }
+        let desktop = render(&info, &package, &title, &permissions);
+
+        let mut written_to = None;
+        if params.write {
+            let path = dir.join(format!("{package}.desktop"));
+            if path.exists() {
+                require_confirmation(params.confirm, || format!("replacing {}", path.display()))?;
+            }
+            std::fs::write(&path, &desktop)?;
+            written_to = Some(path);
+        }
+        Ok(Json(GeneratedDesktop {
+            package,
+            written_to,
+            desktop,
+        }))
+    }
+}
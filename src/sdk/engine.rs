@@ -0,0 +1,280 @@
+//! `inspect_build_environment`: the Build Engine, its tooling and
+//! scratchbox2 targets, and what a target's sysroot contains.
+
+use std::time::Duration;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Limit for the quick `sfdk` queries; starting the engine is not one.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Prints one `key<TAB>value` line per toolchain component.
+const TOOLCHAIN_SCRIPT: &str = "printf 'gcc\\t%s\\n' \"$(gcc -dumpfullversion -dumpversion 2>/dev/null | head -n 1)\"; \
+printf 'qt\\t%s\\n' \"$(qmake -query QT_VERSION 2>/dev/null || pkg-config --modversion Qt5Core 2>/dev/null)\"; \
+printf 'cmake\\t%s\\n' \"$(cmake --version 2>/dev/null | head -n 1 | sed 's/^cmake version //')\"; \
+printf 'os\\t%s\\n' \"$(sed -n 's/^VERSION_ID=//p' /etc/os-release 2>/dev/null | tr -d '\\\"')\"";
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct InspectEnvironmentParams {
+    /// Target to inspect in detail (default: the configured one; without
+    /// either only the engine and target list are reported).
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Glob over the target's package names to list, e.g. `qt5*` or `*`
+    /// for all (default: only count them).
+    #[serde(default)]
+    pub packages: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SdkTarget {
+    pub name: String,
+    /// The tooling the target builds with.
+    pub tooling: Option<String>,
+    /// sfdk's flags for it, e.g. `sdk-provided`, `latest`.
+    pub flags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Toolchain {
+    pub os_version: Option<String>,
+    pub gcc: Option<String>,
+    pub qt: Option<String>,
+    pub cmake: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SysrootPackage {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TargetDetails {
+    pub name: String,
+    pub toolchain: Toolchain,
+    /// Installed packages in the sysroot.
+    pub package_count: usize,
+    /// Those matching the `packages` glob.
+    pub packages: Vec<SysrootPackage>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BuildEnvironment {
+    pub sfdk: String,
+    /// Whether the Build Engine VM is running, when sfdk says.
+    pub engine_running: Option<bool>,
+    pub engine_status: String,
+    pub toolings: Vec<String>,
+    pub targets: Vec<SdkTarget>,
+    pub target: Option<TargetDetails>,
+}
+
+/// Exit status and combined output of a quick query.
+pub(crate) async fn query(mut command: Command) -> Result<(i32, String)> {
+    let program = command
+        .as_std()
+        .get_program()
+        .to_string_lossy()
+        .into_owned();
+    command.kill_on_drop(true);
+    let output = tokio::time::timeout(QUERY_TIMEOUT, command.output())
+        .await
+        .map_err(|_| Error::CommandTimeout {
+            program: program.clone(),
+            seconds: QUERY_TIMEOUT.as_secs(),
+        })?
+        .map_err(|source| Error::Spawn { program, source })?;
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok((output.status.code().unwrap_or(-1), text))
+}
+
+/// Toolings and their targets from `sfdk tools list`, which draws targets
+/// as tree branches under their tooling:
+///
+/// ```text
+/// AuroraOS-5.1.3.85-MB2                  sdk-provided,latest
+/// └── AuroraOS-5.1.3.85-MB2-armv7hl      sdk-provided,latest
+/// ```
+pub(crate) fn parse_tools(output: &str) -> (Vec<String>, Vec<SdkTarget>) {
+    let mut toolings = Vec::new();
+    let mut targets = Vec::new();
+    for line in output.lines() {
+        let branch = line.trim_start_matches(['│', '├', '└', '─', '|', '`', '-', ' ']);
+        let mut words = branch.split_whitespace();
+        let Some(name) = words.next() else {
+            continue;
+        };
+        if !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+        {
+            continue;
+        }
+        let flags = words
+            .flat_map(|word| word.split(','))
+            .filter(|flag| !flag.is_empty())
+            .map(str::to_string)
+            .collect();
+        if branch.len() == line.len() {
+            toolings.push(name.to_string());
+        } else {
+            targets.push(SdkTarget {
+                name: name.to_string(),
+                tooling: toolings.last().cloned(),
+                flags,
+            });
+        }
+    }
+    (toolings, targets)
+}
+
+async fn target_details(
+    server: &AuroraServer,
+    target: &str,
+    packages: Option<&str>,
+) -> Result<TargetDetails> {
+    let options = &server.state().config.sdk;
+    if let Some(pattern) = packages
+        && (pattern.is_empty()
+            || !pattern
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.+*?".contains(c)))
+    {
+        return Err(Error::InvalidArgument(format!(
+            "'{pattern}' is not a package name glob"
+        )));
+    }
+
+    let mut command = options.build_shell("sh", Some(target))?;
+    command.arg("-c").arg(TOOLCHAIN_SCRIPT);
+    let (_, output) = query(command).await?;
+    let component = |key: &str| {
+        output.lines().find_map(|line| {
+            let (k, value) = line.split_once('\t')?;
+            let value = value.trim();
+            (k == key && !value.is_empty()).then(|| value.to_string())
+        })
+    };
+    let toolchain = Toolchain {
+        os_version: component("os"),
+        gcc: component("gcc"),
+        qt: component("qt"),
+        cmake: component("cmake"),
+    };
+
+    let mut command = options.build_shell("rpm", Some(target))?;
+    command.args(["-qa", "--qf", "%{NAME}\\t%{VERSION}-%{RELEASE}\\n"]);
+    let (status, output) = query(command).await?;
+    if status != 0 {
+        return Err(Error::InvalidArgument(format!(
+            "could not list the packages of {target}: {}",
+            output.trim()
+        )));
+    }
+    let mut installed: Vec<SysrootPackage> = output
+        .lines()
+        .filter_map(|line| {
+            let (name, version) = line.split_once('\t')?;
+            Some(SysrootPackage {
+                name: name.to_string(),
+                version: version.trim().to_string(),
+            })
+        })
+        .collect();
+    installed.sort_by(|a, b| a.name.cmp(&b.name));
+    let package_count = installed.len();
+    let packages = match packages {
+        Some(pattern) => installed
+            .into_iter()
+            .filter(|package| glob_match(pattern, &package.name))
+            .collect(),
+        None => Vec::new(),
+    };
+    Ok(TargetDetails {
+        name: target.to_string(),
+        toolchain,
+        package_count,
+        packages,
+    })
+}
+
+/// Shell-style glob with `*` and `?`.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) =
+        (pattern.chars().collect(), text.chars().collect());
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[tool_router(router = engine_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Inspect the Aurora build environment: whether the Build Engine VM is running, the installed toolings and scratchbox2 targets, and for a target its OS release, GCC, Qt and CMake versions and sysroot packages (filtered by a glob such as qt5*).",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn inspect_build_environment(
+        &self,
+        Parameters(params): Parameters<InspectEnvironmentParams>,
+    ) -> Result<Json<BuildEnvironment>> {
+        let options = &self.state().config.sdk;
+        let sfdk = options.sfdk()?;
+        let target = options.target(params.target.as_deref())?;
+
+        let mut command = Command::new(&sfdk);
+        command.args(["engine", "status"]);
+        let (status, engine_status) = query(command).await?;
+        let lower = engine_status.to_ascii_lowercase();
+        let engine_running = if status != 0 || lower.contains("not running") {
+            Some(false)
+        } else if lower.contains("running") {
+            Some(true)
+        } else {
+            None
+        };
+
+        let mut command = Command::new(&sfdk);
+        command.args(["tools", "list"]);
+        let (_, tools) = query(command).await?;
+        let (toolings, targets) = parse_tools(&tools);
+
+        let target = match (&target, engine_running) {
+            (Some(target), Some(true) | None) => {
+                Some(target_details(self, target, params.packages.as_deref()).await?)
+            }
+            _ => None,
+        };
+        Ok(Json(BuildEnvironment {
+            sfdk: sfdk.display().to_string(),
+            engine_running,
+            engine_status: engine_status.trim().to_string(),
+            toolings,
+            targets,
+            target,
+        }))
+    }
+}
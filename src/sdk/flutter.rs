@@ -0,0 +1,443 @@
+//! Flutter apps for Aurora OS: recognizing them by `pubspec.yaml` and the
+//! `aurora/` platform directory, and building and deploying them with the
+//! `flutter-aurora` toolchain.
+
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+use rmcp::handler::server::tool::cached_schema_for_type;
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::model::CallToolResult;
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use super::build::new_rpms;
+use super::buildlog::{self, BuildError};
+use super::project::find_files;
+use super::run_logged;
+use crate::device::deploy::{DeployResult, InstallMethod, deploy};
+use crate::device::multi::{MultiDeviceReport, fan_out};
+use crate::device::tail_lines;
+use crate::error::{Error, Result, tool_result};
+use crate::resources::file_link;
+use crate::server::AuroraServer;
+
+const LOG_TAIL_LINES: usize = 40;
+const MAX_ERRORS: usize = 20;
+
+/// Plugins with platform code that need an Aurora implementation, published
+/// as `<name>_aurora`, next to them.
+const PLATFORM_PLUGINS: &[&str] = &[
+    "battery_plus",
+    "camera",
+    "connectivity_plus",
+    "device_info_plus",
+    "flutter_local_notifications",
+    "flutter_secure_storage",
+    "geolocator",
+    "image_picker",
+    "package_info_plus",
+    "path_provider",
+    "permission_handler",
+    "sensors_plus",
+    "shared_preferences",
+    "sqflite",
+    "url_launcher",
+    "wakelock_plus",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetPlatform {
+    /// 32-bit ARM devices (`armv7hl` targets).
+    Arm,
+    /// 64-bit ARM devices (`aarch64` targets).
+    Arm64,
+    /// The Aurora OS emulator (`x86_64` targets).
+    X64,
+}
+
+impl TargetPlatform {
+    pub(crate) fn flag(self) -> &'static str {
+        match self {
+            TargetPlatform::Arm => "aurora-arm",
+            TargetPlatform::Arm64 => "aurora-arm64",
+            TargetPlatform::X64 => "aurora-x64",
+        }
+    }
+
+    /// The platform of an sfdk build target such as
+    /// `AuroraOS-5.1.3.85-MB2-armv7hl`.
+    pub(crate) fn of_target(target: &str) -> Option<Self> {
+        match target.rsplit('-').next()? {
+            "armv7hl" => Some(TargetPlatform::Arm),
+            "aarch64" => Some(TargetPlatform::Arm64),
+            "x86_64" => Some(TargetPlatform::X64),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FlutterMode {
+    Debug,
+    Profile,
+    #[default]
+    Release,
+}
+
+impl FlutterMode {
+    fn flag(self) -> &'static str {
+        match self {
+            FlutterMode::Debug => "--debug",
+            FlutterMode::Profile => "--profile",
+            FlutterMode::Release => "--release",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct FlutterProject {
+    pub dir: PathBuf,
+    /// Package name from `pubspec.yaml`.
+    pub name: String,
+    pub version: Option<String>,
+    /// Dart SDK constraint, e.g. `>=3.0.0 <4.0.0`.
+    pub sdk: Option<String>,
+    /// The `aurora/` platform directory; without it the app does not
+    /// target Aurora OS yet.
+    pub aurora_dir: Option<PathBuf>,
+    /// The RPM spec under `aurora/rpm/`.
+    pub spec_file: Option<PathBuf>,
+    pub dependencies: Vec<String>,
+    /// Aurora implementations of plugins, `*_aurora`.
+    pub aurora_plugins: Vec<String>,
+    /// Plugins with platform code whose `<name>_aurora` implementation is
+    /// not a dependency; calling them fails at run time on Aurora OS.
+    pub plugins_missing_aurora: Vec<String>,
+}
+
+/// A top-level `key: value` of a YAML document, unquoted.
+fn yaml_value(text: &str, key: &str) -> Option<String> {
+    yaml_lines(text)
+        .filter(|(depth, _)| *depth == 0)
+        .find_map(|(_, line)| {
+            let (name, value) = line.split_once(':')?;
+            (name.trim() == key).then(|| unquote(value)).flatten()
+        })
+}
+
+/// `key: value` entries directly under the top-level `section:`.
+fn yaml_entries(text: &str, section: &str) -> Vec<(String, Option<String>)> {
+    let mut entries = Vec::new();
+    let mut inside = false;
+    let mut indent = None;
+    for (depth, line) in yaml_lines(text) {
+        if depth == 0 {
+            inside = line.trim_end_matches(':').trim() == section;
+            indent = None;
+            continue;
+        }
+        if !inside || depth != *indent.get_or_insert(depth) {
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            entries.push((name.trim().to_string(), unquote(value)));
+        }
+    }
+    entries
+}
+
+/// `(indent, content)` of lines that are not blank or comments.
+fn yaml_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines().filter_map(|line| {
+        let content = line.split(" #").next().unwrap_or(line).trim_end();
+        let trimmed = content.trim_start();
+        (!trimmed.is_empty() && !trimmed.starts_with('#'))
+            .then(|| (content.len() - trimmed.len(), trimmed))
+    })
+}
+
+fn unquote(value: &str) -> Option<String> {
+    let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+impl FlutterProject {
+    /// The Flutter app in `dir`, if its `pubspec.yaml` depends on Flutter.
+    pub fn detect(dir: &Path) -> Option<Self> {
+        let text = std::fs::read_to_string(dir.join("pubspec.yaml")).ok()?;
+        let mut dependencies: Vec<String> = yaml_entries(&text, "dependencies")
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        if !dependencies.iter().any(|name| name == "flutter") {
+            return None;
+        }
+        dependencies.sort();
+        let aurora_plugins: Vec<String> = dependencies
+            .iter()
+            .filter(|name| name.ends_with("_aurora"))
+            .cloned()
+            .collect();
+        let plugins_missing_aurora = dependencies
+            .iter()
+            .filter(|name| PLATFORM_PLUGINS.contains(&name.as_str()))
+            .filter(|name| !aurora_plugins.contains(&format!("{name}_aurora")))
+            .cloned()
+            .collect();
+        let aurora_dir = Some(dir.join("aurora")).filter(|path| path.is_dir());
+        let spec_file = aurora_dir.as_ref().and_then(|aurora| {
+            find_files(&aurora.join("rpm"), "spec", 0)
+                .into_iter()
+                .next()
+        });
+        Some(FlutterProject {
+            dir: dir.to_path_buf(),
+            name: yaml_value(&text, "name").unwrap_or_else(|| "app".to_string()),
+            version: yaml_value(&text, "version"),
+            sdk: yaml_entries(&text, "environment")
+                .into_iter()
+                .find(|(name, _)| name == "sdk")
+                .and_then(|(_, value)| value),
+            aurora_dir,
+            spec_file,
+            dependencies,
+            aurora_plugins,
+            plugins_missing_aurora,
+        })
+    }
+
+    /// The Flutter app in `dir`, failing unless it targets Aurora OS.
+    fn require(dir: &Path) -> Result<Self> {
+        let project = Self::detect(dir).ok_or_else(|| {
+            Error::InvalidArgument(format!(
+                "{} is not a Flutter project: no pubspec.yaml depending on flutter",
+                dir.display()
+            ))
+        })?;
+        if project.aurora_dir.is_none() {
+            return Err(Error::InvalidArgument(format!(
+                "{} has no aurora/ platform directory; add it with `flutter-aurora create --platforms=aurora .`",
+                dir.display()
+            )));
+        }
+        Ok(project)
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FlutterProjectParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FlutterProjectReport {
+    /// The directory holds a Flutter app.
+    pub flutter: bool,
+    pub project: Option<FlutterProject>,
+    /// The `flutter-aurora` binary builds would use, when installed.
+    pub toolchain: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FlutterBuildParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Device architecture (default: that of the configured build target,
+    /// else `arm`).
+    #[serde(default)]
+    pub target_platform: Option<TargetPlatform>,
+    /// Build mode.
+    #[serde(default)]
+    pub mode: FlutterMode,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FlutterBuildReport {
+    pub project: PathBuf,
+    pub target_platform: TargetPlatform,
+    pub mode: FlutterMode,
+    pub success: bool,
+    pub exit_status: i32,
+    pub duration_secs: u64,
+    /// RPM packages written by this build.
+    pub rpms: Vec<PathBuf>,
+    /// Dart, pub, toolchain, CMake and compiler errors with their
+    /// locations; the first is usually the root cause.
+    pub errors: Vec<BuildError>,
+    pub warnings: usize,
+    pub log_file: PathBuf,
+    pub log_tail: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FlutterDeployParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Device names from the config; the app is installed on all of them
+    /// concurrently.
+    pub devices: Vec<String>,
+    /// Device architecture (default: that of the configured build target,
+    /// else `arm`).
+    #[serde(default)]
+    pub target_platform: Option<TargetPlatform>,
+    /// Build mode.
+    #[serde(default)]
+    pub mode: FlutterMode,
+    /// Installer to use: `pkcon` (default) or `rpm`.
+    #[serde(default)]
+    pub method: InstallMethod,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FlutterDeployReport {
+    pub build: FlutterBuildReport,
+    /// The package installed: the build's RPM other than debug info.
+    pub rpm: Option<PathBuf>,
+    /// Per-device install results; absent when the build failed or wrote
+    /// no package.
+    pub install: Option<MultiDeviceReport<DeployResult>>,
+}
+
+pub async fn build(
+    server: &AuroraServer,
+    project: Option<&str>,
+    target_platform: Option<TargetPlatform>,
+    mode: FlutterMode,
+) -> Result<FlutterBuildReport> {
+    let options = &server.state().config.sdk;
+    let dir = options.project(project)?;
+    FlutterProject::require(&dir)?;
+    let flutter = options.flutter()?;
+    let target_platform = match target_platform {
+        Some(platform) => platform,
+        None => options
+            .target(None)?
+            .as_deref()
+            .and_then(TargetPlatform::of_target)
+            .unwrap_or(TargetPlatform::Arm),
+    };
+
+    let log_file = super::log_file(server, &dir, "build");
+    let mut command = Command::new(&flutter);
+    command
+        .args(["build", "aurora"])
+        .arg(mode.flag())
+        .arg(format!("--target-platform={}", target_platform.flag()))
+        .current_dir(&dir);
+
+    // Filesystem timestamps can be coarser than the clock; allow a second.
+    let since = SystemTime::now() - std::time::Duration::from_secs(1);
+    let started = Instant::now();
+    let run = run_logged(command, &log_file, options.build_timeout()).await?;
+    let summary = buildlog::analyze(&log_file, MAX_ERRORS)?;
+    Ok(FlutterBuildReport {
+        rpms: new_rpms(&dir.join("build").join("aurora"), 6, since),
+        project: dir,
+        target_platform,
+        mode,
+        success: run.status == 0,
+        exit_status: run.status,
+        duration_secs: started.elapsed().as_secs(),
+        errors: summary.errors,
+        warnings: summary.warnings,
+        log_tail: tail_lines(&run.output, LOG_TAIL_LINES),
+        log_file,
+    })
+}
+
+#[tool_router(router = flutter_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Detect a Flutter app targeting Aurora OS: pubspec name, version and Dart SDK constraint, the aurora/ platform directory and RPM spec, dependencies, the *_aurora plugin implementations it uses and platform plugins lacking one, and the flutter-aurora toolchain that would build it.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn inspect_flutter_project(
+        &self,
+        Parameters(params): Parameters<FlutterProjectParams>,
+    ) -> Result<Json<FlutterProjectReport>> {
+        let options = &self.state().config.sdk;
+        let dir = options.project(params.project.as_deref())?;
+        let project = FlutterProject::detect(&dir);
+        Ok(Json(FlutterProjectReport {
+            flutter: project.is_some(),
+            project,
+            toolchain: options.flutter().ok(),
+        }))
+    }
+
+    #[tool(
+        description = "Build a Flutter app for Aurora OS with flutter-aurora (`flutter build aurora`) for arm, arm64 or the x64 emulator in debug, profile or release mode. Returns success, the RPMs produced, structured Dart compiler, pub, toolchain, CMake and C++ errors with file and line, the log tail and a link to the full build log.",
+        output_schema = cached_schema_for_type::<FlutterBuildReport>()
+    )]
+    pub async fn flutter_build(
+        &self,
+        Parameters(params): Parameters<FlutterBuildParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let report = build(
+            self,
+            params.project.as_deref(),
+            params.target_platform,
+            params.mode,
+        )
+        .await;
+        tool_result(report.and_then(|report| {
+            let link = file_link(&report.log_file, Some("text/plain"));
+            let mut result = CallToolResult::structured(
+                serde_json::to_value(&report).map_err(std::io::Error::other)?,
+            );
+            result.content.push(link);
+            Ok(result)
+        }))
+    }
+
+    #[tool(
+        description = "Build a Flutter app for Aurora OS with flutter-aurora and install the resulting RPM on one or more devices concurrently. Returns the build report (with structured errors when it fails), the package installed and per-device install results."
+    )]
+    pub async fn flutter_deploy(
+        &self,
+        Parameters(params): Parameters<FlutterDeployParams>,
+    ) -> Result<Json<FlutterDeployReport>> {
+        let build = build(
+            self,
+            params.project.as_deref(),
+            params.target_platform,
+            params.mode,
+        )
+        .await?;
+        let rpm = build
+            .rpms
+            .iter()
+            .find(|rpm| {
+                let name = rpm.file_name().unwrap_or_default().to_string_lossy();
+                !name.contains("-debuginfo-") && !name.contains("-debugsource-")
+            })
+            .filter(|_| build.success)
+            .cloned();
+        let install = match &rpm {
+            Some(rpm) => Some(
+                fan_out(self, &params.devices, |device| {
+                    deploy(device, rpm, params.method)
+                })
+                .await?,
+            ),
+            None => None,
+        };
+        Ok(Json(FlutterDeployReport {
+            build,
+            rpm,
+            install,
+        }))
+    }
+}
@@ -0,0 +1,382 @@
+//! `check_ui_guidelines`: heuristics for QML that breaks the Aurora OS UI
+//! guidelines — misplaced pulley menus, sizes and colors that bypass
+//! `Theme`, Qt Quick dialogs instead of Silica ones, and a missing cover.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::lint::{Level, LintIssue};
+use super::silica::{QmlUsage, Token, is_type_name, qml_files, scan_qml, tokenize};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Guideline sections, by the rule that checks them.
+const GUIDELINES: &[(&str, &str)] = &[
+    (
+        "pulley-menu-placement",
+        "Pulley menus: a PullDownMenu or PushUpMenu is a direct child of the page's SilicaFlickable, SilicaListView or SilicaGridView",
+    ),
+    (
+        "pulley-menu-count",
+        "Pulley menus: one pull-down and one push-up menu per view",
+    ),
+    (
+        "hardcoded-size",
+        "Scaling: sizes, paddings and fonts come from Theme so layouts fit every screen size and density",
+    ),
+    (
+        "hardcoded-color",
+        "Ambiences: colors come from Theme so content stays legible on every ambience",
+    ),
+    (
+        "non-silica-dialog",
+        "Dialogs: Silica Dialog pages and Sailfish.Pickers, not QtQuick.Dialogs or QtQuick.Controls",
+    ),
+    (
+        "missing-cover",
+        "Covers: every application has a cover showing its state on the home screen",
+    ),
+    ("cover-actions", "Covers: at most two cover actions"),
+];
+
+/// Views that host pulley menus.
+const FLICKABLES: &[&str] = &[
+    "SilicaFlickable",
+    "SilicaListView",
+    "SilicaGridView",
+    "SilicaWebView",
+    "WebView",
+];
+
+const PULLEY_MENUS: &[&str] = &["PullDownMenu", "PushUpMenu"];
+
+const MAX_COVER_ACTIONS: usize = 2;
+
+/// Properties taking a size, with the `Theme` constants to use instead.
+const SIZE_PROPERTIES: &[(&str, &str)] = &[
+    (
+        "font.pixelSize",
+        "Theme.fontSizeSmall, Theme.fontSizeMedium or another Theme.fontSize*",
+    ),
+    ("font.pointSize", "font.pixelSize: Theme.fontSize*"),
+    (
+        "width",
+        "Theme.itemSize*, Theme.iconSize* or a size relative to the parent",
+    ),
+    (
+        "height",
+        "Theme.itemSize*, Theme.iconSize* or a size relative to the parent",
+    ),
+    ("implicitWidth", "Theme.itemSize* or Theme.iconSize*"),
+    ("implicitHeight", "Theme.itemSize* or Theme.iconSize*"),
+    (
+        "spacing",
+        "Theme.paddingSmall, Theme.paddingMedium or Theme.paddingLarge",
+    ),
+    (
+        "rowSpacing",
+        "Theme.paddingSmall, Theme.paddingMedium or Theme.paddingLarge",
+    ),
+    (
+        "columnSpacing",
+        "Theme.paddingSmall, Theme.paddingMedium or Theme.paddingLarge",
+    ),
+    (
+        "anchors.margins",
+        "Theme.horizontalPageMargin or Theme.padding*",
+    ),
+    (
+        "anchors.leftMargin",
+        "Theme.horizontalPageMargin or Theme.padding*",
+    ),
+    (
+        "anchors.rightMargin",
+        "Theme.horizontalPageMargin or Theme.padding*",
+    ),
+    ("anchors.topMargin", "Theme.padding*"),
+    ("anchors.bottomMargin", "Theme.padding*"),
+    ("leftMargin", "Theme.horizontalPageMargin or Theme.padding*"),
+    (
+        "rightMargin",
+        "Theme.horizontalPageMargin or Theme.padding*",
+    ),
+    ("topMargin", "Theme.padding*"),
+    ("bottomMargin", "Theme.padding*"),
+];
+
+const COLOR_PROPERTIES: &[&str] = &["color", "border.color", "textColor", "highlightColor"];
+
+/// Modules whose dialogs and controls do not follow the Silica style.
+const NON_SILICA_MODULES: &[(&str, &str)] = &[
+    (
+        "QtQuick.Dialogs",
+        "use a Silica Dialog page, or Sailfish.Pickers for files, images and contacts",
+    ),
+    (
+        "QtQuick.Controls",
+        "use the Sailfish.Silica equivalents, e.g. Button, TextField, ComboBox",
+    ),
+];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CheckUiGuidelinesParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// QML files to check, relative to the project (default: all under
+    /// its QML directory).
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UiGuidelinesReport {
+    pub files_checked: usize,
+    /// No errors were found; warnings may remain.
+    pub passed: bool,
+    pub issues: Vec<LintIssue>,
+    /// The guideline each reported rule comes from.
+    pub guidelines: BTreeMap<String, String>,
+}
+
+/// An open block while walking a QML file.
+struct Block {
+    /// The component, or empty for other braces.
+    name: String,
+    line: usize,
+    /// Components declared directly inside, by name.
+    children: BTreeMap<String, usize>,
+}
+
+/// The literal value set for `property` on `line`, e.g. `12` for
+/// `width: 12`.
+pub(crate) fn literal_value<'a>(line: &'a str, property: &str) -> Option<&'a str> {
+    let rest = line.match_indices(property).find_map(|(start, _)| {
+        let before = line[..start].chars().next_back();
+        if before.is_some_and(|c| c.is_alphanumeric() || c == '.' || c == '_') {
+            return None;
+        }
+        line[start + property.len()..]
+            .trim_start()
+            .strip_prefix(':')
+    })?;
+    let value = rest.split([';', '}']).next()?;
+    let value = value.split("//").next()?.trim();
+    Some(value)
+}
+
+/// Issues in one QML file, and the line of its `ApplicationWindow` with
+/// whether that sets a cover.
+fn check_file(file: &Path, text: &str) -> (Vec<LintIssue>, Option<(usize, bool)>) {
+    let mut issues = Vec::new();
+    let mut issue = |level, rule: &str, line, message: String, suggestion: String| {
+        issues.push(LintIssue {
+            level,
+            rule: rule.to_string(),
+            file: file.to_path_buf(),
+            line: Some(line),
+            message,
+            suggestion,
+        });
+    };
+
+    // Nesting: pulley menus and cover actions.
+    let tokens = tokenize(text);
+    let mut stack: Vec<Block> = Vec::new();
+    for (i, (line, token)) in tokens.iter().enumerate() {
+        match token {
+            Token::Word(word) if tokens.get(i + 1).map(|(_, t)| t) == Some(&Token::Punct('{')) => {
+                let name = if is_type_name(word) {
+                    word.rsplit('.').next().unwrap_or(word).to_string()
+                } else {
+                    String::new()
+                };
+                if PULLEY_MENUS.contains(&name.as_str()) {
+                    let parent = stack.iter().rev().find(|block| !block.name.is_empty());
+                    if !parent.is_some_and(|block| FLICKABLES.contains(&block.name.as_str())) {
+                        issue(
+                            Level::Error,
+                            "pulley-menu-placement",
+                            *line,
+                            format!(
+                                "{name} is inside {} rather than a Silica flickable; it will not open",
+                                parent.map_or("no component", |block| block.name.as_str())
+                            ),
+                            "move it directly into the page's SilicaFlickable, SilicaListView or SilicaGridView".to_string(),
+                        );
+                    }
+                }
+                if !name.is_empty()
+                    && let Some(parent) =
+                        stack.iter_mut().rev().find(|block| !block.name.is_empty())
+                {
+                    *parent.children.entry(name.clone()).or_default() += 1;
+                }
+                stack.push(Block {
+                    name,
+                    line: *line,
+                    children: BTreeMap::new(),
+                });
+            }
+            Token::Punct('{') if i == 0 || !matches!(tokens[i - 1].1, Token::Word(_)) => {
+                stack.push(Block {
+                    name: String::new(),
+                    line: *line,
+                    children: BTreeMap::new(),
+                });
+            }
+            Token::Punct('}') => {
+                let Some(block) = stack.pop() else {
+                    continue;
+                };
+                for menu in PULLEY_MENUS {
+                    if FLICKABLES.contains(&block.name.as_str())
+                        && block.children.get(*menu).is_some_and(|count| *count > 1)
+                    {
+                        issue(
+                            Level::Warning,
+                            "pulley-menu-count",
+                            block.line,
+                            format!("{} has more than one {menu}", block.name),
+                            "merge the menus into one".to_string(),
+                        );
+                    }
+                }
+                let actions = block.children.get("CoverAction").copied().unwrap_or(0);
+                if block.name == "CoverActionList" && actions > MAX_COVER_ACTIONS {
+                    issue(
+                        Level::Warning,
+                        "cover-actions",
+                        block.line,
+                        format!("the cover has {actions} actions; only {MAX_COVER_ACTIONS} fit"),
+                        "keep the two most used actions".to_string(),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Property values, imports and the cover.
+    let lines: Vec<&str> = text.lines().collect();
+    let (mut window, mut has_cover) = (None, false);
+    for (line, usage) in scan_qml(text) {
+        let source = lines.get(line - 1).copied().unwrap_or("");
+        match usage {
+            QmlUsage::Import { module } => {
+                if let Some((prefix, suggestion)) = NON_SILICA_MODULES.iter().find(|(prefix, _)| {
+                    module == *prefix || module.starts_with(&format!("{prefix}."))
+                }) {
+                    issue(
+                        if *prefix == "QtQuick.Dialogs" {
+                            Level::Error
+                        } else {
+                            Level::Warning
+                        },
+                        "non-silica-dialog",
+                        line,
+                        format!(
+                            "imports {module}, whose components do not follow the Silica style"
+                        ),
+                        suggestion.to_string(),
+                    );
+                }
+            }
+            QmlUsage::Component { name } if name == "ApplicationWindow" => {
+                window.get_or_insert(line);
+            }
+            QmlUsage::Property { component, name } => {
+                has_cover |= component == "ApplicationWindow" && name == "cover";
+                if let Some((_, suggestion)) = SIZE_PROPERTIES.iter().find(|(p, _)| *p == name)
+                    && let Some(value) = literal_value(source, &name)
+                    && let Ok(number) = value.parse::<f64>()
+                    && number > 1.0
+                {
+                    issue(
+                        Level::Warning,
+                        "hardcoded-size",
+                        line,
+                        format!("{component}.{name} is a fixed {value} pixels"),
+                        format!("use {suggestion}"),
+                    );
+                }
+                if COLOR_PROPERTIES.contains(&name.as_str())
+                    && let Some(value) = literal_value(source, &name)
+                    && (value.starts_with("Qt.rgba(")
+                        || value.starts_with('"') && value != "\"transparent\"")
+                {
+                    issue(
+                        Level::Warning,
+                        "hardcoded-color",
+                        line,
+                        format!("{component}.{name} is the fixed color {value}"),
+                        "use Theme.primaryColor, Theme.secondaryColor, Theme.highlightColor or another Theme color".to_string(),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+    (issues, window.map(|line| (line, has_cover)))
+}
+
+#[tool_router(router = guidelines_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Check QML against the Aurora OS UI guidelines with heuristics: pulley menus (PullDownMenu, PushUpMenu) outside a Silica flickable or duplicated, hard-coded pixel sizes and colors instead of Theme constants, QtQuick.Dialogs and QtQuick.Controls instead of Silica components, an ApplicationWindow without a cover, and more than two cover actions. Returns issues with file, line and suggestion, plus the guideline each rule comes from.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn check_ui_guidelines(
+        &self,
+        Parameters(params): Parameters<CheckUiGuidelinesParams>,
+    ) -> Result<Json<UiGuidelinesReport>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        let files = if params.files.is_empty() {
+            qml_files(&dir)?
+        } else {
+            params.files.iter().map(|file| dir.join(file)).collect()
+        };
+        if let Some(missing) = files.iter().find(|file| !file.is_file()) {
+            return Err(Error::InvalidArgument(format!(
+                "{} does not exist",
+                missing.display()
+            )));
+        }
+
+        let mut issues = Vec::new();
+        for file in &files {
+            let text = std::fs::read_to_string(file)?;
+            let (found, window) = check_file(file, &text);
+            issues.extend(found);
+            if let Some((line, false)) = window {
+                issues.push(LintIssue {
+                    level: Level::Warning,
+                    rule: "missing-cover".to_string(),
+                    file: file.clone(),
+                    line: Some(line),
+                    message: "ApplicationWindow sets no cover; the home screen shows an empty one"
+                        .to_string(),
+                    suggestion: "add cover: Qt.resolvedUrl(\"cover/CoverPage.qml\") with a CoverBackground showing the app's state".to_string(),
+                });
+            }
+        }
+
+        let guidelines = GUIDELINES
+            .iter()
+            .filter(|(rule, _)| issues.iter().any(|issue| issue.rule == *rule))
+            .map(|(rule, text)| (rule.to_string(), text.to_string()))
+            .collect();
+        Ok(Json(UiGuidelinesReport {
+            files_checked: files.len(),
+            passed: !issues.iter().any(|issue| issue.level == Level::Error),
+            issues,
+            guidelines,
+        }))
+    }
+}
@@ -0,0 +1,689 @@
+//! `check_licenses`: licenses of the third-party code a project bundles,
+//! identified as SPDX ids and checked against the license the spec
+//! declares and the store's redistribution rules.
+
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::compat::CPP_EXTENSIONS;
+use super::lint::{Level, LintIssue, parse_spec};
+use super::project::{ProjectInfo, find_files};
+use crate::error::Result;
+use crate::server::AuroraServer;
+
+/// Directories holding vendored code, one component per subdirectory.
+const THIRD_PARTY_DIRS: &[&str] = &[
+    "3rdparty",
+    "3rd-party",
+    "third_party",
+    "thirdparty",
+    "third-party",
+    "external",
+    "externals",
+    "vendor",
+    "contrib",
+    "libs",
+];
+
+/// File names, lowercased and without extension, holding a license text.
+const LICENSE_FILES: &[&str] = &["license", "licence", "copying", "copyright", "unlicense"];
+
+/// Source files read for `SPDX-License-Identifier` headers per component.
+const MAX_HEADER_FILES: usize = 200;
+
+/// Phrases identifying license texts, as (SPDX id, phrases that must all
+/// appear), most specific first. Matched case-insensitively.
+const FINGERPRINTS: &[(&str, &[&str])] = &[
+    ("AGPL-3.0", &["gnu affero general public license"]),
+    (
+        "LGPL-3.0",
+        &["gnu lesser general public license", "version 3"],
+    ),
+    (
+        "LGPL-2.1",
+        &["gnu lesser general public license", "version 2.1"],
+    ),
+    ("LGPL-2.0", &["gnu library general public license"]),
+    ("GPL-3.0", &["gnu general public license", "version 3"]),
+    ("GPL-2.0", &["gnu general public license", "version 2"]),
+    ("MPL-2.0", &["mozilla public license", "2.0"]),
+    ("Apache-2.0", &["apache license", "version 2.0"]),
+    ("BSL-1.0", &["boost software license"]),
+    (
+        "Unlicense",
+        &["free and unencumbered software released into the public domain"],
+    ),
+    ("CC0-1.0", &["cc0 1.0 universal"]),
+    (
+        "Zlib",
+        &["altered source versions must be plainly marked as such"],
+    ),
+    (
+        "ISC",
+        &["permission to use, copy, modify, and/or distribute this software for any purpose"],
+    ),
+    (
+        "BSD-3-Clause",
+        &[
+            "redistribution and use in source and binary forms",
+            "neither the name",
+        ],
+    ),
+    (
+        "BSD-2-Clause",
+        &["redistribution and use in source and binary forms"],
+    ),
+    ("MIT", &["permission is hereby granted, free of charge"]),
+];
+
+/// Legacy and Fedora-style short names in `License:` tags, as (name, SPDX
+/// id).
+const ALIASES: &[(&str, &str)] = &[
+    ("GPLv2", "GPL-2.0"),
+    ("GPLv2+", "GPL-2.0-or-later"),
+    ("GPLv3", "GPL-3.0"),
+    ("GPLv3+", "GPL-3.0-or-later"),
+    ("LGPLv2", "LGPL-2.0"),
+    ("LGPLv2+", "LGPL-2.0-or-later"),
+    ("LGPLv2.1", "LGPL-2.1"),
+    ("LGPLv2.1+", "LGPL-2.1-or-later"),
+    ("LGPLv3", "LGPL-3.0"),
+    ("LGPLv3+", "LGPL-3.0-or-later"),
+    ("AGPLv3", "AGPL-3.0"),
+    ("ASL 2.0", "Apache-2.0"),
+    ("MPLv2.0", "MPL-2.0"),
+    ("Boost", "BSL-1.0"),
+    ("BSD", "BSD-3-Clause"),
+    ("zlib", "Zlib"),
+    ("Public Domain", "Unlicense"),
+    ("Proprietary", "LicenseRef-Proprietary"),
+    ("Commercial", "LicenseRef-Proprietary"),
+];
+
+/// SPDX ids of the licenses recognized here, as a `License:` tag names
+/// them: GPL-family ones with `-only` or `-or-later`.
+pub(crate) fn spdx_ids() -> Vec<String> {
+    let mut ids = Vec::new();
+    for (id, _) in FINGERPRINTS {
+        if id.contains("GPL") {
+            ids.push(format!("{id}-only"));
+            ids.push(format!("{id}-or-later"));
+        } else {
+            ids.push(id.to_string());
+        }
+    }
+    ids.push("LicenseRef-Proprietary".to_string());
+    ids.sort();
+    ids
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum LicenseKind {
+    Permissive,
+    /// File- or library-level copyleft: MPL, LGPL.
+    WeakCopyleft,
+    /// GPL.
+    StrongCopyleft,
+    /// AGPL, which also covers use over a network.
+    NetworkCopyleft,
+    Proprietary,
+    Unknown,
+}
+
+fn kind(id: &str) -> LicenseKind {
+    let base = base_id(id);
+    match base {
+        "MIT" | "BSD-2-Clause" | "BSD-3-Clause" | "Apache-2.0" | "ISC" | "Zlib" | "BSL-1.0"
+        | "Unlicense" | "CC0-1.0" => LicenseKind::Permissive,
+        "LGPL-2.0" | "LGPL-2.1" | "LGPL-3.0" | "MPL-2.0" => LicenseKind::WeakCopyleft,
+        "GPL-2.0" | "GPL-3.0" => LicenseKind::StrongCopyleft,
+        "AGPL-3.0" => LicenseKind::NetworkCopyleft,
+        _ if id.starts_with("LicenseRef-") => LicenseKind::Proprietary,
+        _ => LicenseKind::Unknown,
+    }
+}
+
+/// `GPL-2.0-or-later` -> `GPL-2.0`.
+fn base_id(id: &str) -> &str {
+    id.trim_end_matches('+')
+        .trim_end_matches("-or-later")
+        .trim_end_matches("-only")
+}
+
+/// An SPDX id for a license name from a spec or header.
+fn normalize(name: &str) -> String {
+    let name = name.trim().trim_matches(['(', ')']);
+    ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+        .map(|(_, id)| id.to_string())
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// The alternatives of a license expression, each a list of licenses that
+/// all apply: `MIT OR (GPL-2.0 AND BSD)` -> `[[MIT], [GPL-2.0, BSD]]`. AND
+/// binds tighter than OR and parentheses group, as in SPDX; `/` also means
+/// OR and `&` or `,` AND, as in older RPM `License` tags.
+fn alternatives(expression: &str) -> Vec<Vec<String>> {
+    let spaced = expression
+        .replace('(', " ( ")
+        .replace(')', " ) ")
+        .replace(',', " , ");
+    let mut words = spaced.split_whitespace().peekable();
+    let mut result = any_of(&mut words);
+    // Whatever follows an unbalanced `)` is one more alternative.
+    while words.next().is_some() {
+        result.extend(any_of(&mut words));
+    }
+    result.retain(|licenses| !licenses.is_empty());
+    result
+}
+
+type Words<'a> = std::iter::Peekable<std::str::SplitWhitespace<'a>>;
+
+fn is_or(word: &str) -> bool {
+    word.eq_ignore_ascii_case("or") || word == "/"
+}
+
+fn is_and(word: &str) -> bool {
+    word.eq_ignore_ascii_case("and") || word == "&" || word == ","
+}
+
+/// `a OR b OR ...`: the alternatives of each operand.
+fn any_of(words: &mut Words) -> Vec<Vec<String>> {
+    let mut result = all_of(words);
+    while words.next_if(|word| is_or(word)).is_some() {
+        result.extend(all_of(words));
+    }
+    result
+}
+
+/// `a AND b AND ...`: every combination of one alternative of each operand.
+fn all_of(words: &mut Words) -> Vec<Vec<String>> {
+    let mut result = operand(words);
+    while words.next_if(|word| is_and(word)).is_some() {
+        let right = operand(words);
+        result = result
+            .iter()
+            .flat_map(|left| {
+                right
+                    .iter()
+                    .map(move |right| [left.as_slice(), right.as_slice()].concat())
+            })
+            .collect();
+    }
+    result
+}
+
+/// A parenthesized expression, or a license name of one or more words such
+/// as `GPL-2.0 WITH Classpath-exception-2.0` or `Public Domain`.
+fn operand(words: &mut Words) -> Vec<Vec<String>> {
+    if words.next_if_eq(&"(").is_some() {
+        let inner = any_of(words);
+        words.next_if_eq(&")");
+        return inner;
+    }
+    let mut name = String::new();
+    while let Some(word) =
+        words.next_if(|word| !is_or(word) && !is_and(word) && *word != "(" && *word != ")")
+    {
+        if !name.is_empty() {
+            name.push(' ');
+        }
+        name.push_str(word);
+    }
+    if name.is_empty() {
+        vec![Vec::new()]
+    } else {
+        vec![vec![normalize(&name)]]
+    }
+}
+
+/// Why code under `dependency` cannot ship in an app under `project`, if
+/// it cannot.
+fn conflict(project: &str, dependency: &str) -> Option<String> {
+    let (project_kind, dependency_kind) = (kind(project), kind(dependency));
+    let gpl2_only =
+        |id: &str| base_id(id) == "GPL-2.0" && !id.ends_with("-or-later") && !id.ends_with('+');
+    match dependency_kind {
+        LicenseKind::NetworkCopyleft if project_kind != LicenseKind::NetworkCopyleft => {
+            Some(format!(
+                "{dependency} requires the whole app, including network use, to be under the AGPL"
+            ))
+        }
+        LicenseKind::StrongCopyleft
+            if !matches!(
+                project_kind,
+                LicenseKind::StrongCopyleft | LicenseKind::NetworkCopyleft
+            ) =>
+        {
+            Some(format!(
+                "{dependency} requires the whole app to be distributed under the GPL, but it is {project}"
+            ))
+        }
+        LicenseKind::StrongCopyleft if gpl2_only(project) && base_id(dependency) == "GPL-3.0" => {
+            Some(format!(
+                "{dependency} cannot be combined with GPL-2.0-only code"
+            ))
+        }
+        LicenseKind::Permissive if gpl2_only(project) && base_id(dependency) == "Apache-2.0" => {
+            Some(format!(
+                "{dependency}'s patent terms are incompatible with GPL-2.0-only"
+            ))
+        }
+        LicenseKind::WeakCopyleft if base_id(dependency) == "LGPL-3.0" && gpl2_only(project) => {
+            Some(format!(
+                "{dependency} cannot be combined with GPL-2.0-only code"
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// The license of a text, by its `SPDX-License-Identifier` or its wording.
+/// A full GPL text does not say whether later versions apply, so those are
+/// reported as the version itself.
+fn identify(text: &str) -> Option<String> {
+    if let Some(id) = spdx_header(text) {
+        return Some(id);
+    }
+    let lower = text.to_lowercase();
+    let lower = lower.split_whitespace().collect::<Vec<_>>().join(" ");
+    FINGERPRINTS
+        .iter()
+        .find(|(_, phrases)| phrases.iter().all(|phrase| lower.contains(phrase)))
+        .map(|(id, _)| id.to_string())
+}
+
+fn spdx_header(text: &str) -> Option<String> {
+    text.lines().take(30).find_map(|line| {
+        let (_, id) = line.split_once("SPDX-License-Identifier:")?;
+        let id = id.trim().trim_end_matches("*/").trim();
+        (!id.is_empty()).then(|| id.to_string())
+    })
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CheckLicensesParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// The app's license as an SPDX expression (default: the spec's
+    /// `License:`).
+    #[serde(default)]
+    pub license: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Component {
+    pub name: String,
+    /// Directory relative to the project; absent for spec-only entries.
+    pub path: Option<PathBuf>,
+    /// SPDX expression, when identified.
+    pub license: Option<String>,
+    pub kind: LicenseKind,
+    /// Where the license came from, e.g. `COPYING` or a source header.
+    pub evidence: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LicenseReport {
+    /// The app's license as declared.
+    pub license: Option<String>,
+    pub components: Vec<Component>,
+    /// No errors were found; warnings may remain.
+    pub passed: bool,
+    pub issues: Vec<LintIssue>,
+}
+
+/// Subdirectories of the third-party directories and git submodules.
+fn component_dirs(dir: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut roots = vec![dir.to_path_buf()];
+    for _ in 0..3 {
+        let mut next = Vec::new();
+        for root in roots {
+            let Ok(entries) = std::fs::read_dir(&root) else {
+                continue;
+            };
+            for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+                let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                if !path.is_dir() || name.starts_with('.') || matches!(name, "RPMS" | "build") {
+                    continue;
+                }
+                if THIRD_PARTY_DIRS.contains(&name.to_ascii_lowercase().as_str()) {
+                    if let Ok(children) = std::fs::read_dir(&path) {
+                        found.extend(
+                            children
+                                .filter_map(|e| e.ok())
+                                .map(|e| e.path())
+                                .filter(|p| p.is_dir()),
+                        );
+                    }
+                } else {
+                    next.push(path);
+                }
+            }
+        }
+        roots = next;
+    }
+    if let Ok(modules) = std::fs::read_to_string(dir.join(".gitmodules")) {
+        found.extend(
+            modules
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix("path"))
+                .filter_map(|rest| rest.trim_start().strip_prefix('='))
+                .map(|path| dir.join(path.trim()))
+                .filter(|path| path.is_dir()),
+        );
+    }
+    found.sort();
+    found.dedup();
+    found
+}
+
+/// The license of a vendored component: its license file, else the
+/// `SPDX-License-Identifier` its sources carry.
+fn component_license(path: &Path) -> (Option<String>, Option<String>) {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        .unwrap_or_default();
+    files.sort();
+    for file in &files {
+        let Some(stem) = file.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let stem = stem.to_ascii_lowercase();
+        if file.is_file()
+            && LICENSE_FILES
+                .iter()
+                .any(|name| stem == *name || stem.starts_with(&format!("{name}-")))
+            && let Some(license) = std::fs::read_to_string(file)
+                .ok()
+                .and_then(|t| identify(&t))
+        {
+            let name = file.file_name().map(|n| n.to_string_lossy().into_owned());
+            return (Some(license), name);
+        }
+    }
+    let mut ids: Vec<String> = CPP_EXTENSIONS
+        .iter()
+        .flat_map(|ext| find_files(path, ext, 6))
+        .take(MAX_HEADER_FILES)
+        .filter_map(|file| std::fs::read_to_string(file).ok())
+        .filter_map(|text| spdx_header(&text))
+        .collect();
+    ids.sort();
+    ids.dedup();
+    if ids.is_empty() {
+        (None, None)
+    } else {
+        (
+            Some(ids.join(" AND ")),
+            Some("SPDX-License-Identifier headers".to_string()),
+        )
+    }
+}
+
+#[tool_router(router = licenses_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Check the licenses of third-party code a project bundles: components under 3rdparty/, external/, vendor/ and similar directories, git submodules, extra spec sources and bundled() provides. Identifies each license as an SPDX id from license files or SPDX headers and flags ones incompatible with the app's declared license (e.g. GPL code in an MIT or proprietary app), unknown licenses, and a missing or non-SPDX License tag.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn check_licenses(
+        &self,
+        Parameters(params): Parameters<CheckLicensesParams>,
+    ) -> Result<Json<LicenseReport>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        let info = ProjectInfo::inspect(&dir);
+        let spec_text = match &info.spec_file {
+            Some(spec) => Some(std::fs::read_to_string(spec)?),
+            None => None,
+        };
+        let spec = spec_text.as_deref().map(parse_spec);
+        let spec_file = info.spec_file.clone().unwrap_or_else(|| dir.join("rpm"));
+        let mut issues = Vec::new();
+        let mut issue =
+            |level, rule: &str, file: &Path, line, message: String, suggestion: &str| {
+                issues.push(LintIssue {
+                    level,
+                    rule: rule.to_string(),
+                    file: file.to_path_buf(),
+                    line,
+                    message,
+                    suggestion: suggestion.to_string(),
+                });
+            };
+
+        let declared = params
+            .license
+            .clone()
+            .map(|license| (None, license))
+            .or_else(|| {
+                spec.as_ref()?
+                    .tag("License")
+                    .map(|(line, value)| (Some(line), value.to_string()))
+            });
+        let project_alternatives = match &declared {
+            Some((line, license)) => {
+                let alternatives = alternatives(license);
+                for id in alternatives.iter().flatten() {
+                    if kind(id) == LicenseKind::Unknown {
+                        issue(
+                            Level::Warning,
+                            "license-not-spdx",
+                            &spec_file,
+                            *line,
+                            format!("'{id}' is not a recognized SPDX license id"),
+                            "Use an SPDX id such as BSD-3-Clause or GPL-3.0-or-later; the store reads it as is.",
+                        );
+                    }
+                }
+                alternatives
+            }
+            None => {
+                issue(
+                    Level::Error,
+                    "license-missing",
+                    &spec_file,
+                    None,
+                    "the spec declares no License".to_string(),
+                    "Add a License: tag with an SPDX expression.",
+                );
+                Vec::new()
+            }
+        };
+
+        let mut components = Vec::new();
+        for path in component_dirs(&dir) {
+            let (license, evidence) = component_license(&path);
+            components.push(Component {
+                name: path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                path: Some(path.strip_prefix(&dir).unwrap_or(&path).to_path_buf()),
+                kind: LicenseKind::Unknown,
+                license,
+                evidence,
+            });
+        }
+        if let Some(spec) = &spec {
+            for (line, tag, value) in spec.tags() {
+                let bundled = tag
+                    .eq_ignore_ascii_case("Provides")
+                    .then(|| value.strip_prefix("bundled("))
+                    .flatten()
+                    .and_then(|rest| rest.split_once(')'))
+                    .map(|(name, _)| name.to_string());
+                let source = (tag.starts_with("Source") && *tag != "Source0" && *tag != "Source")
+                    .then(|| {
+                        let file = value.rsplit('/').next().unwrap_or(value);
+                        file.split(['-', '.']).next().unwrap_or(file).to_string()
+                    });
+                let Some(name) = bundled.or(source) else {
+                    continue;
+                };
+                if components
+                    .iter()
+                    .any(|c| c.name.eq_ignore_ascii_case(&name))
+                {
+                    continue;
+                }
+                components.push(Component {
+                    name,
+                    path: None,
+                    license: None,
+                    kind: LicenseKind::Unknown,
+                    evidence: Some(format!("{tag} on line {line} of the spec")),
+                });
+            }
+        }
+
+        for component in &mut components {
+            let where_ = component
+                .path
+                .as_ref()
+                .map(|p| dir.join(p))
+                .unwrap_or_else(|| spec_file.clone());
+            let Some(license) = &component.license else {
+                issue(
+                    Level::Warning,
+                    "license-unknown",
+                    &where_,
+                    None,
+                    format!("no license found for {}", component.name),
+                    "Add the component's license file next to its sources; the store needs the license of everything shipped.",
+                );
+                continue;
+            };
+            let dependency_alternatives = alternatives(license);
+            component.kind = dependency_alternatives
+                .iter()
+                .map(|all| {
+                    all.iter()
+                        .map(|id| kind(id))
+                        .max()
+                        .unwrap_or(LicenseKind::Unknown)
+                })
+                .min()
+                .unwrap_or(LicenseKind::Unknown);
+            if project_alternatives.is_empty() {
+                continue;
+            }
+            // Compatible if some choice of the app's license works with some
+            // choice of the component's.
+            let conflicts: Vec<String> = project_alternatives
+                .iter()
+                .flat_map(|project| {
+                    dependency_alternatives.iter().map(move |dependency| {
+                        project
+                            .iter()
+                            .flat_map(|p| dependency.iter().filter_map(|d| conflict(p, d)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .min_by_key(Vec::len)
+                .unwrap_or_default();
+            if let Some(message) = conflicts.into_iter().next() {
+                issue(
+                    Level::Error,
+                    "license-incompatible",
+                    &where_,
+                    None,
+                    format!("{}: {message}", component.name),
+                    "Replace the component, obtain it under another license, or relicense the app accordingly.",
+                );
+            } else if component.kind == LicenseKind::WeakCopyleft {
+                issue(
+                    Level::Info,
+                    "license-weak-copyleft",
+                    &where_,
+                    None,
+                    format!(
+                        "{} is {license}: ship it as a separate shared library under /usr/share/<name>/lib and publish changes to it",
+                        component.name
+                    ),
+                    "Do not link it statically unless you also provide the object files for relinking.",
+                );
+            }
+        }
+
+        Ok(Json(LicenseReport {
+            license: declared.map(|(_, license)| license),
+            components,
+            passed: !issues.iter().any(|issue| issue.level == Level::Error),
+            issues,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(alternatives: &[&[&str]]) -> Vec<Vec<String>> {
+        alternatives
+            .iter()
+            .map(|licenses| licenses.iter().map(|id| id.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            alternatives("MIT OR Apache-2.0"),
+            ids(&[&["MIT"], &["Apache-2.0"]])
+        );
+        assert_eq!(
+            alternatives("MIT AND BSD-3-Clause OR GPL-2.0-only"),
+            ids(&[&["MIT", "BSD-3-Clause"], &["GPL-2.0-only"]])
+        );
+        assert_eq!(
+            alternatives("GPL-2.0-only OR MIT AND Zlib"),
+            ids(&[&["GPL-2.0-only"], &["MIT", "Zlib"]])
+        );
+    }
+
+    #[test]
+    fn parses_nested_expressions() {
+        assert_eq!(
+            alternatives("MIT OR (GPL-2.0-only AND BSD-3-Clause)"),
+            ids(&[&["MIT"], &["GPL-2.0-only", "BSD-3-Clause"]])
+        );
+        assert_eq!(
+            alternatives("(MIT OR Apache-2.0) AND Zlib"),
+            ids(&[&["MIT", "Zlib"], &["Apache-2.0", "Zlib"]])
+        );
+        assert_eq!(
+            alternatives("(MIT OR (ISC AND (Zlib OR BSL-1.0))) AND LGPL-2.1-only"),
+            ids(&[
+                &["MIT", "LGPL-2.1-only"],
+                &["ISC", "Zlib", "LGPL-2.1-only"],
+                &["ISC", "BSL-1.0", "LGPL-2.1-only"],
+            ])
+        );
+    }
+
+    #[test]
+    fn reads_rpm_license_tags() {
+        assert_eq!(
+            alternatives("GPL-2.0-only WITH Classpath-exception-2.0 / MIT, Zlib"),
+            ids(&[
+                &["GPL-2.0-only WITH Classpath-exception-2.0"],
+                &["MIT", "Zlib"]
+            ])
+        );
+        assert_eq!(alternatives("MIT OR"), ids(&[&["MIT"]]));
+        assert_eq!(alternatives("MIT) OR (ISC"), ids(&[&["MIT"], &["ISC"]]));
+    }
+}
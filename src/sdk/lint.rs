@@ -0,0 +1,423 @@
+//! `lint_spec`: Aurora-specific checks of an RPM spec file and the desktop
+//! file it packages.
+
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use super::desktop;
+use super::project::{BuildSystem, ProjectInfo};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Icon sizes the Aurora launcher looks up.
+pub(crate) const ICON_SIZES: &[&str] = &["86x86", "108x108", "128x128", "172x172"];
+
+/// Preamble tags every package needs.
+const REQUIRED_TAGS: &[&str] = &["Name", "Version", "Release", "Summary", "License"];
+
+/// Requires that the Aurora RPM validator rejects, as (prefix, reason).
+const FORBIDDEN_REQUIRES: &[(&str, &str)] = &[
+    ("python", "interpreters are not part of the allowed API"),
+    ("perl", "interpreters are not part of the allowed API"),
+    (
+        "qt5-qtwebkit",
+        "QtWebKit is deprecated and not available on Aurora OS",
+    ),
+    ("/", "file dependencies are not allowed; require a package"),
+];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct LintSpecParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Spec file, absolute or relative to the project (default: the one in
+    /// `rpm/`).
+    #[serde(default)]
+    pub spec: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LintIssue {
+    pub level: Level,
+    /// Short rule id, e.g. `missing-qmake-macro`.
+    pub rule: String,
+    /// File the issue is in; the spec unless noted.
+    pub file: PathBuf,
+    /// 1-based line, when the issue is tied to one.
+    pub line: Option<usize>,
+    pub message: String,
+    pub suggestion: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LintReport {
+    pub spec: PathBuf,
+    /// No errors were found; warnings may remain.
+    pub passed: bool,
+    pub issues: Vec<LintIssue>,
+}
+
+/// A spec file split into its preamble tags and `%section`s.
+pub(crate) struct Spec<'a> {
+    /// `(line, tag, value)` of the main package's preamble.
+    tags: Vec<(usize, &'a str, &'a str)>,
+    /// `(line, value)` of `Requires` in any package.
+    pub(crate) requires: Vec<(usize, &'a str)>,
+    /// `(line, value)` of `BuildRequires`.
+    pub(crate) build_requires: Vec<(usize, &'a str)>,
+    pub(crate) sections: Vec<Section<'a>>,
+}
+
+pub(crate) struct Section<'a> {
+    pub(crate) line: usize,
+    /// e.g. `%build`.
+    pub(crate) name: &'a str,
+    /// `(line, text)` of the lines up to the next section.
+    pub(crate) body: Vec<(usize, &'a str)>,
+}
+
+const SECTIONS: &[&str] = &[
+    "%description",
+    "%package",
+    "%prep",
+    "%build",
+    "%install",
+    "%check",
+    "%clean",
+    "%files",
+    "%pre",
+    "%post",
+    "%preun",
+    "%postun",
+    "%changelog",
+];
+
+pub(crate) fn parse_spec(text: &str) -> Spec<'_> {
+    let mut spec = Spec {
+        tags: Vec::new(),
+        requires: Vec::new(),
+        build_requires: Vec::new(),
+        sections: Vec::new(),
+    };
+    let mut in_package_preamble = false;
+    for (index, line) in text.lines().enumerate() {
+        let number = index + 1;
+        let trimmed = line.trim();
+        let section = trimmed.split_whitespace().next().unwrap_or("");
+        if SECTIONS.contains(&section) {
+            in_package_preamble = section == "%package";
+            spec.sections.push(Section {
+                line: number,
+                name: section,
+                body: Vec::new(),
+            });
+            continue;
+        }
+        if let Some(section) = spec.sections.last_mut()
+            && !in_package_preamble
+        {
+            section.body.push((number, line));
+            continue;
+        }
+        let Some((tag, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let tag = tag.trim();
+        if tag.contains(char::is_whitespace) || tag.starts_with(['#', '%']) {
+            continue;
+        }
+        let value = value.trim();
+        if tag == "Requires" || tag.starts_with("Requires(") {
+            spec.requires.push((number, value));
+        } else if tag == "BuildRequires" {
+            spec.build_requires.push((number, value));
+        }
+        if !in_package_preamble {
+            spec.tags.push((number, tag, value));
+        }
+    }
+    spec
+}
+
+impl<'a> Spec<'a> {
+    /// `(line, tag, value)` of the main package's preamble.
+    pub(crate) fn tags(&self) -> &[(usize, &'a str, &'a str)] {
+        &self.tags
+    }
+
+    pub(crate) fn tag(&self, name: &str) -> Option<(usize, &'a str)> {
+        self.tags
+            .iter()
+            .find(|(_, tag, _)| tag.eq_ignore_ascii_case(name))
+            .map(|(line, _, value)| (*line, *value))
+    }
+
+    pub(crate) fn section(&self, name: &str) -> Option<&Section<'a>> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+}
+
+/// Latest tag reachable from `HEAD`, without a leading `v`.
+async fn git_tag(dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["describe", "--tags", "--abbrev=0"])
+        .output()
+        .await
+        .ok()?;
+    let tag = String::from_utf8(output.stdout).ok()?;
+    let tag = tag.trim();
+    (output.status.success() && !tag.is_empty()).then(|| tag.trim_start_matches('v').to_string())
+}
+
+pub async fn lint(dir: &Path, spec_path: &Path) -> Result<LintReport> {
+    let text = std::fs::read_to_string(spec_path)
+        .map_err(|err| Error::InvalidArgument(format!("{}: {err}", spec_path.display())))?;
+    let spec = parse_spec(&text);
+    let info = ProjectInfo::inspect(dir);
+    let mut issues = Vec::new();
+    let mut add = |level, rule: &str, line, message: String, suggestion: String| {
+        issues.push(LintIssue {
+            level,
+            rule: rule.to_string(),
+            file: spec_path.to_path_buf(),
+            line,
+            message,
+            suggestion,
+        });
+    };
+
+    for tag in REQUIRED_TAGS {
+        if spec.tag(tag).is_none() {
+            add(
+                Level::Error,
+                "missing-tag",
+                None,
+                format!("the preamble has no {tag}: tag"),
+                format!("Add `{tag}:` near the top of the spec."),
+            );
+        }
+    }
+    let name = spec.tag("Name").map(|(_, name)| name).unwrap_or_default();
+    if let Some(stem) = spec_path.file_stem().and_then(|stem| stem.to_str())
+        && !name.is_empty()
+        && !name.contains('%')
+        && stem != name
+    {
+        add(
+            Level::Warning,
+            "name-mismatch",
+            spec.tag("Name").map(|(line, _)| line),
+            format!("Name {name} differs from the spec file name {stem}.spec"),
+            format!("Rename the spec to {name}.spec or fix Name."),
+        );
+    }
+
+    for (line, value) in &spec.requires {
+        for dependency in value.split(',').map(str::trim) {
+            let package = dependency.split_whitespace().next().unwrap_or("");
+            if package.ends_with("-devel") {
+                add(
+                    Level::Error,
+                    "forbidden-requires",
+                    Some(*line),
+                    format!(
+                        "Requires {package}: development packages are not installed on devices"
+                    ),
+                    "Move it to BuildRequires.".to_string(),
+                );
+            } else if let Some((_, reason)) = FORBIDDEN_REQUIRES
+                .iter()
+                .find(|(prefix, _)| package.starts_with(prefix))
+            {
+                add(
+                    Level::Error,
+                    "forbidden-requires",
+                    Some(*line),
+                    format!("Requires {package}: {reason}"),
+                    "Drop the dependency or bundle what the app needs under /usr/share/%{name}."
+                        .to_string(),
+                );
+            }
+        }
+    }
+
+    if let Some(Section {
+        line: section_line,
+        body,
+        ..
+    }) = spec.section("%build")
+    {
+        let uses = |macros: &[&str]| {
+            body.iter()
+                .any(|(_, line)| macros.iter().any(|m| line.trim_start().starts_with(m)))
+        };
+        if info.build_system == Some(BuildSystem::Qmake) && !uses(&["%qtc_qmake5", "%qmake5"]) {
+            let raw = body
+                .iter()
+                .find(|(_, line)| line.trim_start().starts_with("qmake"))
+                .map(|(line, _)| *line);
+            add(
+                Level::Error,
+                "missing-qmake-macro",
+                raw.or(Some(*section_line)),
+                "%build does not configure with %qtc_qmake5".to_string(),
+                "Use `%qtc_qmake5` and `%qtc_make %{?_smp_mflags}` so the target's compiler flags are applied."
+                    .to_string(),
+            );
+        }
+        if info.build_system == Some(BuildSystem::Cmake) && !uses(&["%cmake"]) {
+            add(
+                Level::Error,
+                "missing-cmake-macro",
+                Some(*section_line),
+                "%build does not configure with %cmake".to_string(),
+                "Use `%cmake` followed by `%make_build`.".to_string(),
+            );
+        }
+    } else {
+        add(
+            Level::Error,
+            "missing-section",
+            None,
+            "the spec has no %build section".to_string(),
+            "Add %build with the configure and compile macros.".to_string(),
+        );
+    }
+
+    match spec.section("%files") {
+        Some(Section {
+            line: section_line,
+            body,
+            ..
+        }) => {
+            for (line, entry) in body {
+                let entry = entry.trim();
+                if entry.contains("/pixmaps/") {
+                    add(
+                        Level::Error,
+                        "icon-path",
+                        Some(*line),
+                        format!("{entry}: icons under pixmaps are not shown by the launcher"),
+                        "Install icons to %{_datadir}/icons/hicolor/<size>/apps/%{name}.png."
+                            .to_string(),
+                    );
+                } else if entry.contains("/icons/") {
+                    let size_ok = entry.contains("/hicolor/*/apps/")
+                        || ICON_SIZES
+                            .iter()
+                            .any(|size| entry.contains(&format!("/hicolor/{size}/apps/")));
+                    if !size_ok || !entry.ends_with(".png") {
+                        add(
+                            Level::Error,
+                            "icon-path",
+                            Some(*line),
+                            format!("{entry}: not a launcher icon path"),
+                            format!(
+                                "Use %{{_datadir}}/icons/hicolor/<size>/apps/%{{name}}.png with sizes {}.",
+                                ICON_SIZES.join(", ")
+                            ),
+                        );
+                    }
+                }
+            }
+            let has_desktop = body
+                .iter()
+                .any(|(_, entry)| entry.contains("/applications/") && entry.contains(".desktop"));
+            if info.desktop_file.is_some() && !has_desktop {
+                add(
+                    Level::Error,
+                    "missing-desktop-entry",
+                    Some(*section_line),
+                    "%files does not package the desktop file".to_string(),
+                    "Add %{_datadir}/applications/%{name}.desktop.".to_string(),
+                );
+            }
+            let has_icons = body.iter().any(|(_, entry)| entry.contains("/icons/"));
+            if !info.icon_sizes.is_empty() && !has_icons {
+                add(
+                    Level::Warning,
+                    "missing-icons",
+                    Some(*section_line),
+                    "%files does not package the icons under icons/".to_string(),
+                    "Add %{_datadir}/icons/hicolor/*/apps/%{name}.png.".to_string(),
+                );
+            }
+        }
+        None => add(
+            Level::Error,
+            "missing-section",
+            None,
+            "the spec has no %files section".to_string(),
+            "List the installed files under %files.".to_string(),
+        ),
+    }
+
+    if let (Some((line, version)), Some(tag)) = (spec.tag("Version"), git_tag(dir).await)
+        && !version.contains('%')
+        && version != tag
+    {
+        add(
+            Level::Warning,
+            "version-mismatch",
+            Some(line),
+            format!("Version {version} differs from the latest git tag {tag}"),
+            format!("Set Version: {tag}, or tag the release as {version}."),
+        );
+    }
+
+    if let Some(desktop) = &info.desktop_file {
+        let package = if name.is_empty() || name.contains('%') {
+            info.name.clone()
+        } else {
+            name.to_string()
+        };
+        issues.extend(desktop::check(&info, desktop, &package));
+    }
+    issues.sort_by_key(|issue| (issue.file != spec_path, issue.line));
+    Ok(LintReport {
+        spec: spec_path.to_path_buf(),
+        passed: !issues.iter().any(|issue| issue.level == Level::Error),
+        issues,
+    })
+}
+
+#[tool_router(router = lint_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Lint an Aurora RPM .spec file: missing tags and qmake/CMake macros, launcher icon paths, Requires the RPM validator rejects, an unpackaged or incomplete desktop file, and Version vs the latest git tag. Reports issues with line numbers and fix suggestions.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn lint_spec(
+        &self,
+        Parameters(params): Parameters<LintSpecParams>,
+    ) -> Result<Json<LintReport>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        let spec = match params.spec {
+            Some(spec) => dir.join(spec),
+            None => ProjectInfo::inspect(&dir).spec_file.ok_or_else(|| {
+                Error::InvalidArgument(format!(
+                    "no .spec file in {}; pass spec or create one with generate_spec",
+                    dir.join("rpm").display()
+                ))
+            })?,
+        };
+        lint(&dir, &spec).await.map(Json)
+    }
+}
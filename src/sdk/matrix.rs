@@ -0,0 +1,252 @@
+//! `build_matrix`: building a project for several targets at once, each in
+//! its own shadow build directory so the builds don't share objects.
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use rmcp::handler::server::tool::cached_schema_for_type;
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::{CallToolResult, Content};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use super::build::is_error_line;
+use super::engine::{parse_tools, query};
+use super::run_logged;
+use crate::error::{Error, Result, tool_result};
+use crate::resources::file_link;
+use crate::server::AuroraServer;
+
+const MAX_ERRORS: usize = 5;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BuildMatrixParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Targets to build (default: `matrix_targets` from the `[sdk]` config,
+    /// else every target sfdk lists).
+    #[serde(default)]
+    pub targets: Option<Vec<String>>,
+    /// Build with debug information (`--enable-debug`).
+    #[serde(default)]
+    pub debug: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MatrixBuild {
+    pub target: String,
+    /// The architecture suffix of the target, e.g. `armv7hl`, `aarch64`.
+    pub arch: String,
+    pub success: bool,
+    /// -1 when the build was killed, timed out or could not start.
+    pub exit_status: i32,
+    pub duration_secs: u64,
+    pub rpms: Vec<PathBuf>,
+    /// The first compiler and packaging error lines, or why the build did
+    /// not finish.
+    pub errors: Vec<String>,
+    pub build_dir: PathBuf,
+    pub log_file: PathBuf,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MatrixReport {
+    pub project: PathBuf,
+    /// Every target built.
+    pub success: bool,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Wall time of the whole matrix.
+    pub duration_secs: u64,
+    pub builds: Vec<MatrixBuild>,
+    /// The results as a Markdown table.
+    pub table: String,
+}
+
+/// `armv7hl` of `AuroraOS-5.1.3.85-MB2-armv7hl`.
+fn arch(target: &str) -> String {
+    target.rsplit('-').next().unwrap_or(target).to_string()
+}
+
+fn rpms(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut rpms: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rpm"))
+        .collect();
+    rpms.sort();
+    rpms
+}
+
+pub(crate) fn table(builds: &[MatrixBuild]) -> String {
+    let mut table = String::from("| Target | Result | Time | RPMs |\n|---|---|---|---|\n");
+    for build in builds {
+        let result = if build.success {
+            "ok".to_string()
+        } else {
+            format!("failed ({})", build.exit_status)
+        };
+        let rpms: Vec<String> = build
+            .rpms
+            .iter()
+            .filter_map(|rpm| rpm.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        table.push_str(&format!(
+            "| {} | {result} | {}s | {} |\n",
+            build.target,
+            build.duration_secs,
+            if rpms.is_empty() {
+                "-".to_string()
+            } else {
+                rpms.join(", ")
+            }
+        ));
+    }
+    table
+}
+
+/// Builds `project` for `target` from `build_dir`, which holds the objects
+/// and `RPMS/` of that target only. A build that cannot run fails its own
+/// entry, not the matrix.
+async fn build_target(
+    server: &AuroraServer,
+    project: &Path,
+    target: String,
+    debug: bool,
+) -> MatrixBuild {
+    let options = &server.state().config.sdk;
+    let name = project
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "project".to_string());
+    let build_dir = server
+        .state()
+        .config
+        .data_dir()
+        .join("matrix")
+        .join(&name)
+        .join(&target);
+    let log_file = super::log_file(server, project, &format!("build-{target}"));
+
+    let started = Instant::now();
+    let run = async {
+        // Stale packages from the previous run would pass as this one's.
+        let _ = std::fs::remove_dir_all(build_dir.join("RPMS"));
+        std::fs::create_dir_all(&build_dir)?;
+        let mut command = Command::new(options.sfdk()?);
+        command
+            .arg("-c")
+            .arg(format!("target={target}"))
+            .arg("build");
+        if debug {
+            command.arg("--enable-debug");
+        }
+        command.arg(project).current_dir(&build_dir);
+        run_logged(command, &log_file, options.build_timeout()).await
+    };
+    let (exit_status, errors) = match run.await {
+        Ok(run) => (
+            run.status,
+            run.output
+                .lines()
+                .filter(|line| is_error_line(line))
+                .take(MAX_ERRORS)
+                .map(|line| line.trim().to_string())
+                .collect(),
+        ),
+        Err(err) => (-1, vec![err.to_string()]),
+    };
+    MatrixBuild {
+        arch: arch(&target),
+        target,
+        success: exit_status == 0,
+        exit_status,
+        duration_secs: started.elapsed().as_secs(),
+        rpms: rpms(&build_dir.join("RPMS")),
+        errors,
+        build_dir,
+        log_file,
+    }
+}
+
+pub async fn build_all(server: &AuroraServer, params: BuildMatrixParams) -> Result<MatrixReport> {
+    let options = &server.state().config.sdk;
+    let project = options.project(params.project.as_deref())?;
+    let requested = match params.targets {
+        Some(targets) => targets,
+        None if !options.matrix_targets.is_empty() => options.matrix_targets.clone(),
+        None => {
+            let mut command = Command::new(options.sfdk()?);
+            command.args(["tools", "list"]);
+            let (_, tools) = query(command).await?;
+            parse_tools(&tools)
+                .1
+                .into_iter()
+                .map(|target| target.name)
+                .collect()
+        }
+    };
+    let mut targets: Vec<String> = Vec::new();
+    for target in &requested {
+        if let Some(target) = options.target(Some(target))?
+            && !targets.contains(&target)
+        {
+            targets.push(target);
+        }
+    }
+    if targets.is_empty() {
+        return Err(Error::InvalidArgument(
+            "no targets to build; pass targets or set matrix_targets in [sdk]".to_string(),
+        ));
+    }
+
+    let started = Instant::now();
+    let runs = targets
+        .into_iter()
+        .map(|target| build_target(server, &project, target, params.debug));
+    let builds = futures::future::join_all(runs).await;
+    let succeeded = builds.iter().filter(|build| build.success).count();
+    Ok(MatrixReport {
+        project,
+        success: succeeded == builds.len(),
+        succeeded,
+        failed: builds.len() - succeeded,
+        duration_secs: started.elapsed().as_secs(),
+        table: table(&builds),
+        builds,
+    })
+}
+
+#[tool_router(router = matrix_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Build a project for several targets at once (e.g. armv7hl, aarch64 and the x86_64 emulator): one parallel sfdk shadow build per target. Returns a table of results with the RPMs produced per architecture, the first error lines of failed builds and links to every build log.",
+        output_schema = cached_schema_for_type::<MatrixReport>()
+    )]
+    pub async fn build_matrix(
+        &self,
+        Parameters(params): Parameters<BuildMatrixParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        tool_result(build_all(self, params).await.and_then(|report| {
+            let links: Vec<Content> = report
+                .builds
+                .iter()
+                .map(|build| file_link(&build.log_file, Some("text/plain")))
+                .collect();
+            let mut result = CallToolResult::structured(
+                serde_json::to_value(&report).map_err(std::io::Error::other)?,
+            );
+            result.content.push(Content::text(report.table));
+            result.content.extend(links);
+            Ok(result)
+        }))
+    }
+}
@@ -0,0 +1,113 @@
+//! Rendering node and edge lists as a Mermaid flowchart, for the graph
+//! tools that return one with `mermaid=true`.
+
+use std::collections::BTreeMap;
+
+/// How a node is drawn.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Shape {
+    Rectangle,
+    /// `[[...]]`, a box with doubled sides.
+    Subroutine,
+    /// `([...])`, a box with rounded ends.
+    Stadium,
+    /// `[/.../]`.
+    Parallelogram,
+}
+
+impl Shape {
+    fn wrap(self, label: &str) -> String {
+        match self {
+            Shape::Rectangle => format!("[{label}]"),
+            Shape::Subroutine => format!("[[{label}]]"),
+            Shape::Stadium => format!("([{label}])"),
+            Shape::Parallelogram => format!("[/{label}/]"),
+        }
+    }
+}
+
+/// How an edge is drawn: solid or dotted, with an optional label.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Arrow<'a> {
+    pub dotted: bool,
+    pub label: Option<&'a str>,
+}
+
+impl Arrow<'_> {
+    fn render(self) -> String {
+        let arrow = if self.dotted { "-.->" } else { "-->" };
+        match self.label {
+            Some(label) => format!("{arrow}|{}|", quote(label)),
+            None => arrow.to_string(),
+        }
+    }
+}
+
+/// A left-to-right flowchart of `nodes` as (id, shape), labelled with their
+/// ids, and `edges` as (from, to, arrow) between those ids. Nodes in
+/// `flagged` get a red outline; edges to unknown ids are left out.
+pub(crate) fn flowchart<'a>(
+    nodes: impl IntoIterator<Item = (&'a str, Shape)>,
+    edges: impl IntoIterator<Item = (&'a str, &'a str, Arrow<'a>)>,
+    flagged: impl IntoIterator<Item = &'a str>,
+) -> String {
+    let mut ids = BTreeMap::new();
+    let mut out = String::from("flowchart LR\n");
+    for (id, shape) in nodes {
+        let n = ids.len();
+        ids.entry(id).or_insert(n);
+        out.push_str(&format!("    n{n}{}\n", shape.wrap(&quote(id))));
+    }
+    for (from, to, arrow) in edges {
+        if let (Some(from), Some(to)) = (ids.get(from), ids.get(to)) {
+            out.push_str(&format!("    n{from} {} n{to}\n", arrow.render()));
+        }
+    }
+    for id in flagged {
+        if let Some(n) = ids.get(id) {
+            out.push_str(&format!("    style n{n} stroke:#d33,stroke-width:2px\n"));
+        }
+    }
+    out
+}
+
+/// `text` as a quoted Mermaid label.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "#quot;"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_flowcharts() {
+        let chart = flowchart(
+            [("app", Shape::Subroutine), ("libfoo.so.1", Shape::Stadium)],
+            [
+                (
+                    "app",
+                    "libfoo.so.1",
+                    Arrow {
+                        dotted: false,
+                        label: Some(">= \"1\""),
+                    },
+                ),
+                (
+                    "app",
+                    "missing",
+                    Arrow {
+                        dotted: true,
+                        label: None,
+                    },
+                ),
+            ],
+            ["libfoo.so.1"],
+        );
+        assert_eq!(
+            chart,
+            "flowchart LR\n    n0[[\"app\"]]\n    n1([\"libfoo.so.1\"])\n    \
+             n0 -->|\">= #quot;1#quot;\"| n1\n    style n1 stroke:#d33,stroke-width:2px\n"
+        );
+    }
+}
@@ -0,0 +1,474 @@
+//! Host-side Aurora SDK tooling: building projects with `sfdk` inside the
+//! Aurora Build Engine, and generating project files such as RPM specs.
+
+pub mod accessibility;
+pub mod assets;
+pub mod build;
+pub mod buildlog;
+pub mod cache;
+pub mod cargo;
+pub mod changelog;
+pub mod compat;
+pub mod configure;
+pub mod coredump;
+pub mod cpp;
+pub mod debuginfo;
+pub mod depgraph;
+pub mod deps;
+pub mod desktop;
+pub mod engine;
+pub mod flutter;
+pub mod guidelines;
+pub mod licenses;
+pub mod lint;
+pub mod matrix;
+pub mod mermaid;
+pub mod obs;
+pub mod permissions;
+pub mod project;
+pub mod publish;
+pub mod python;
+pub mod qml;
+pub mod qmlgraph;
+pub mod releases;
+pub mod rpm;
+pub mod rpmdiff;
+pub mod sandbox;
+pub mod scaffold;
+pub mod silica;
+pub mod spec;
+pub mod store;
+pub mod sync;
+pub mod translations;
+pub mod unittest;
+pub mod unused;
+pub mod upgrade;
+pub mod validate;
+pub mod version;
+
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rmcp::handler::server::router::tool::ToolRouter;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use tokio::io::AsyncReadExt;
+use tokio::process::{Child, Command};
+
+use crate::config::{expand_tilde, home_dir};
+use crate::error::{Error, Result};
+use crate::progress;
+use crate::server::AuroraServer;
+
+/// How often a running command's log is checked for lines to stream.
+const TAIL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub use obs::BuildServiceOptions;
+pub use publish::StoreOptions;
+
+/// SDK settings, from the `[sdk]` config table.
+///
+/// ```toml
+/// [sdk]
+/// project = "~/src/my-app"
+/// target = "AuroraOS-5.1.3.85-MB2-armv7hl"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SdkOptions {
+    /// `sfdk` binary (default: `sfdk` on `PATH`, then
+    /// `~/AuroraOS/bin/sfdk`).
+    pub sfdk: Option<PathBuf>,
+    /// Project used when a tool is not given one (default: the server's
+    /// working directory).
+    pub project: Option<PathBuf>,
+    /// Build target used when a tool is not given one (default: the one
+    /// configured in sfdk).
+    pub target: Option<String>,
+    pub build_timeout_secs: u64,
+    /// clang-tidy checks `analyze_cpp` runs when not given any.
+    pub clang_tidy_checks: String,
+    /// Extra QML import paths for `qmllint` on the host, e.g. a copy of the
+    /// target's `/usr/lib/qt5/qml` with the Silica modules.
+    pub qml_import_paths: Vec<PathBuf>,
+    /// Extra entries for the deprecated-API database `check_silica_api`
+    /// uses, in the layout of `sdk/data/silica-api.toml`.
+    pub silica_api_database: Option<PathBuf>,
+    /// Extra entries for the API availability database
+    /// `check_compatibility`, `api_diff` and the release notes use, in the
+    /// layout of `sdk/data/os-api.toml`.
+    pub compat_database: Option<PathBuf>,
+    /// Targets `build_matrix` builds when not given any (default: every
+    /// target sfdk lists).
+    pub matrix_targets: Vec<String>,
+    /// Host gdb for device debugging sessions (default: `gdb-multiarch`,
+    /// then `gdb` on `PATH`).
+    pub gdb: Option<PathBuf>,
+    /// `flutter-aurora` binary for Flutter projects (default:
+    /// `flutter-aurora` on `PATH`, then
+    /// `~/.local/opt/flutter-sdk/bin/flutter`).
+    pub flutter: Option<PathBuf>,
+    /// Directory holding the Build Engine targets' sysroots, one per target
+    /// name, for cross-compiling on the host (default:
+    /// `~/AuroraOS/mersdk/targets`).
+    pub sysroots: Option<PathBuf>,
+    /// Extra packages and libraries a stock device image ships, for
+    /// `dependency_graph`, in the layout of `sdk/data/stock-image.toml`.
+    pub stock_image: Option<PathBuf>,
+}
+
+impl Default for SdkOptions {
+    fn default() -> Self {
+        Self {
+            sfdk: None,
+            project: None,
+            target: None,
+            build_timeout_secs: 30 * 60,
+            clang_tidy_checks: "clang-analyzer-*,bugprone-*,performance-*,modernize-use-nullptr,modernize-use-override".to_string(),
+            qml_import_paths: Vec::new(),
+            silica_api_database: None,
+            compat_database: None,
+            matrix_targets: Vec::new(),
+            gdb: None,
+            flutter: None,
+            sysroots: None,
+            stock_image: None,
+        }
+    }
+}
+
+impl SdkOptions {
+    pub fn sfdk(&self) -> Result<PathBuf> {
+        if let Some(path) = &self.sfdk {
+            return Ok(expand_tilde(path));
+        }
+        if let Some(path) = find_in_path("sfdk") {
+            return Ok(path);
+        }
+        home_dir()
+            .map(|home| home.join("AuroraOS").join("bin").join("sfdk"))
+            .filter(|path| path.is_file())
+            .ok_or_else(|| {
+                Error::Config(
+                    "sfdk not found on PATH or in ~/AuroraOS/bin; set sfdk in the [sdk] config table"
+                        .to_string(),
+                )
+            })
+    }
+
+    pub fn gdb(&self) -> Result<PathBuf> {
+        if let Some(path) = &self.gdb {
+            return Ok(expand_tilde(path));
+        }
+        find_in_path("gdb-multiarch")
+            .or_else(|| find_in_path("gdb"))
+            .ok_or_else(|| {
+                Error::Config(
+                    "gdb not found on PATH; install gdb-multiarch or set gdb in the [sdk] config table"
+                        .to_string(),
+                )
+            })
+    }
+
+    pub fn flutter(&self) -> Result<PathBuf> {
+        if let Some(path) = &self.flutter {
+            return Ok(expand_tilde(path));
+        }
+        if let Some(path) = find_in_path("flutter-aurora") {
+            return Ok(path);
+        }
+        home_dir()
+            .map(|home| home.join(".local/opt/flutter-sdk/bin/flutter"))
+            .filter(|path| path.is_file())
+            .ok_or_else(|| {
+                Error::Config(
+                    "flutter-aurora not found on PATH or in ~/.local/opt/flutter-sdk; set flutter in the [sdk] config table"
+                        .to_string(),
+                )
+            })
+    }
+
+    /// The host directory holding the targets' sysroots.
+    pub fn sysroots_dir(&self) -> Result<PathBuf> {
+        match &self.sysroots {
+            Some(path) => Ok(expand_tilde(path)),
+            None => home_dir()
+                .map(|home| home.join("AuroraOS").join("mersdk").join("targets"))
+                .ok_or_else(|| {
+                    Error::Config(
+                        "no home directory; set sysroots in the [sdk] config table".to_string(),
+                    )
+                }),
+        }
+    }
+
+    /// The host directory of `target`'s sysroot.
+    pub fn sysroot(&self, target: &str) -> Result<PathBuf> {
+        Ok(self.sysroots_dir()?.join(target))
+    }
+
+    /// Build targets with a sysroot on the host, sorted by name.
+    pub fn installed_targets(&self) -> Vec<String> {
+        let Ok(entries) = self
+            .sysroots_dir()
+            .and_then(|dir| Ok(std::fs::read_dir(dir)?))
+        else {
+            return Vec::new();
+        };
+        let mut targets: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        targets.sort();
+        targets
+    }
+
+    /// The project directory: `requested`, else the configured project,
+    /// else the working directory.
+    pub fn project(&self, requested: Option<&str>) -> Result<PathBuf> {
+        let path = match (requested, &self.project) {
+            (Some(path), _) => expand_tilde(Path::new(path)),
+            (None, Some(path)) => expand_tilde(path),
+            (None, None) => std::env::current_dir()?,
+        };
+        if !path.is_dir() {
+            return Err(Error::InvalidArgument(format!(
+                "project directory {} does not exist",
+                path.display()
+            )));
+        }
+        Ok(path)
+    }
+
+    /// `requested`, else the configured target, validated as a target name.
+    pub fn target(&self, requested: Option<&str>) -> Result<Option<String>> {
+        let Some(target) = requested.or(self.target.as_deref()) else {
+            return Ok(None);
+        };
+        if target.is_empty()
+            || !target
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+        {
+            return Err(Error::InvalidArgument(format!(
+                "'{target}' is not a build target name"
+            )));
+        }
+        Ok(Some(target.to_string()))
+    }
+
+    pub fn build_timeout(&self) -> Duration {
+        Duration::from_secs(self.build_timeout_secs.max(1))
+    }
+
+    /// A command running Qt tool `names[0]`: the first of `names` found on
+    /// the host, else the tool inside the Build Engine via
+    /// `sfdk build-shell`. The flag tells which one it is.
+    pub fn qt_tool(&self, names: &[&str], target: Option<&str>) -> Result<(Command, bool)> {
+        if let Some(path) = names.iter().find_map(|name| find_in_path(name)) {
+            return Ok((Command::new(path), true));
+        }
+        Ok((self.build_shell(names[0], target)?, false))
+    }
+
+    /// A command running `program` in the target's build environment
+    /// inside the Build Engine (`sfdk build-shell`).
+    pub fn build_shell(&self, program: &str, target: Option<&str>) -> Result<Command> {
+        let mut command = Command::new(self.sfdk()?);
+        if let Some(target) = self.target(target)? {
+            command.arg("-c").arg(format!("target={target}"));
+        }
+        command.args(["build-shell", program]);
+        Ok(command)
+    }
+}
+
+pub(crate) fn find_in_path(program: &str) -> Option<PathBuf> {
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|path| path.is_file())
+}
+
+/// A TOML database embedded as `bundled` (named `name` in errors), with the
+/// user's file at `extra`, when given, merged into it by `merge`.
+pub(crate) fn load_database<T: DeserializeOwned>(
+    name: &str,
+    bundled: &str,
+    extra: Option<&Path>,
+    merge: impl FnOnce(&mut T, T),
+) -> Result<T> {
+    let mut database: T =
+        toml::from_str(bundled).map_err(|err| Error::Config(format!("{name}: {err}")))?;
+    if let Some(path) = extra {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| Error::Config(format!("{}: {err}", path.display())))?;
+        let file: T = toml::from_str(&text)
+            .map_err(|err| Error::Config(format!("{}: {err}", path.display())))?;
+        merge(&mut database, file);
+    }
+    Ok(database)
+}
+
+/// Outcome of a command whose output went to a log file.
+#[derive(Debug)]
+pub struct LoggedRun {
+    /// Exit status; -1 when killed by a signal.
+    pub status: i32,
+    /// Combined stdout and stderr, as written to the log.
+    pub output: String,
+}
+
+/// Runs `command` with stdout and stderr written to `log`, failing after
+/// `timeout`; the log then holds the output up to that point. The log's
+/// lines are streamed to the tool call's client as they are written.
+pub async fn run_logged(mut command: Command, log: &Path, timeout: Duration) -> Result<LoggedRun> {
+    let program = command
+        .as_std()
+        .get_program()
+        .to_string_lossy()
+        .into_owned();
+    if let Some(dir) = log.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let file = std::fs::File::create(log)?;
+    command
+        .stdin(Stdio::null())
+        .stdout(file.try_clone()?)
+        .stderr(file)
+        .kill_on_drop(true);
+    tracing::debug!(?command, "sdk command");
+    let mut child = command.spawn().map_err(|source| Error::Spawn {
+        program: program.clone(),
+        source,
+    })?;
+    let output = progress::current();
+    let waited = if output.is_active() {
+        tokio::time::timeout(timeout, wait_tailing(&mut child, log, &output)).await
+    } else {
+        tokio::time::timeout(timeout, child.wait()).await
+    };
+    let status = match waited {
+        Ok(status) => status?,
+        Err(_) => {
+            return Err(Error::CommandTimeout {
+                program,
+                seconds: timeout.as_secs(),
+            });
+        }
+    };
+    let output = tokio::fs::read(log).await?;
+    Ok(LoggedRun {
+        status: status.code().unwrap_or(-1),
+        output: String::from_utf8_lossy(&output).into_owned(),
+    })
+}
+
+/// Waits for `child`, passing the lines it writes to `log` on to `output`.
+async fn wait_tailing(
+    child: &mut Child,
+    log: &Path,
+    output: &progress::Output,
+) -> std::io::Result<ExitStatus> {
+    let mut file = tokio::fs::File::open(log).await?;
+    let mut pending = Vec::new();
+    let mut interval = tokio::time::interval(TAIL_INTERVAL);
+    let status = loop {
+        tokio::select! {
+            status = child.wait() => break status?,
+            _ = interval.tick() => {
+                file.read_to_end(&mut pending).await?;
+                emit_lines(&mut pending, output);
+            }
+        }
+    };
+    file.read_to_end(&mut pending).await?;
+    emit_lines(&mut pending, output);
+    if !pending.is_empty() {
+        output.line(String::from_utf8_lossy(&pending).trim_end());
+    }
+    Ok(status)
+}
+
+/// Sends the complete lines at the start of `pending` to `output`.
+fn emit_lines(pending: &mut Vec<u8>, output: &progress::Output) {
+    let Some(end) = pending.iter().rposition(|&byte| byte == b'\n') else {
+        return;
+    };
+    for line in String::from_utf8_lossy(&pending[..end]).lines() {
+        output.line(line);
+    }
+    pending.drain(..=end);
+}
+
+/// `command` as a shell-like line, for reports.
+pub(crate) fn command_line(command: &Command) -> String {
+    std::iter::once(command.as_std().get_program())
+        .chain(command.as_std().get_args())
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A fresh log file for an sdk `step` on `project`, under the data
+/// directory's `builds/`.
+pub(crate) fn log_file(server: &AuroraServer, project: &Path, step: &str) -> PathBuf {
+    let name = project
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "project".to_string());
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    server
+        .state()
+        .config
+        .data_dir()
+        .join("builds")
+        .join(format!("{name}-{step}-{stamp}.log"))
+}
+
+pub(crate) fn router() -> ToolRouter<AuroraServer> {
+    AuroraServer::accessibility_router()
+        + AuroraServer::assets_router()
+        + AuroraServer::build_router()
+        + AuroraServer::buildlog_router()
+        + AuroraServer::cache_router()
+        + AuroraServer::cargo_router()
+        + AuroraServer::changelog_router()
+        + AuroraServer::compat_router()
+        + AuroraServer::configure_router()
+        + AuroraServer::coredump_router()
+        + AuroraServer::cpp_router()
+        + AuroraServer::debuginfo_router()
+        + AuroraServer::depgraph_router()
+        + AuroraServer::deps_router()
+        + AuroraServer::desktop_router()
+        + AuroraServer::engine_router()
+        + AuroraServer::flutter_router()
+        + AuroraServer::guidelines_router()
+        + AuroraServer::spec_router()
+        + AuroraServer::licenses_router()
+        + AuroraServer::lint_router()
+        + AuroraServer::matrix_router()
+        + AuroraServer::obs_router()
+        + AuroraServer::permissions_router()
+        + AuroraServer::publish_router()
+        + AuroraServer::qml_router()
+        + AuroraServer::qmlgraph_router()
+        + AuroraServer::releases_router()
+        + AuroraServer::rpmdiff_router()
+        + AuroraServer::sandbox_router()
+        + AuroraServer::scaffold_router()
+        + AuroraServer::silica_router()
+        + AuroraServer::store_router()
+        + AuroraServer::sync_router()
+        + AuroraServer::translations_router()
+        + AuroraServer::unittest_router()
+        + AuroraServer::unused_router()
+        + AuroraServer::upgrade_router()
+        + AuroraServer::validate_router()
+        + AuroraServer::version_router()
+}
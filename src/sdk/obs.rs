@@ -0,0 +1,815 @@
+//! Remote builds on an Open Build Service (OBS) instance: uploading the
+//! project's sources as a package, following its builds across the
+//! repositories and architectures the OBS project builds for, and fetching
+//! the resulting RPMs, so heavyweight builds need no local Build Engine.
+//! Requests go through the host's `curl`; the password comes from the
+//! secrets store (`build_service.password`) and is passed to `curl` on
+//! stdin so it never shows up in process listings.
+//!
+//! ```toml
+//! [build_service]
+//! api_url = "https://build.example.com"
+//! project = "home:me:aurora"
+//! user = "me"
+//! ```
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::build::is_error_line;
+use super::lint::parse_spec;
+use super::project::ProjectInfo;
+use crate::device::tail_lines;
+use crate::error::{Error, Result, require_confirmation};
+use crate::server::AuroraServer;
+
+/// Secret holding the build service password.
+const PASSWORD_SECRET: &str = "build_service.password";
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+const MAX_WAIT_SECS: u64 = 3600;
+const LOG_TAIL_LINES: usize = 30;
+const MAX_LOG_ERRORS: usize = 20;
+
+/// Build results that stay until the next commit.
+const FINAL_CODES: &[&str] = &[
+    "succeeded",
+    "failed",
+    "unresolvable",
+    "broken",
+    "disabled",
+    "excluded",
+];
+
+/// Build service settings, from the `[build_service]` config table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BuildServiceOptions {
+    /// Base URL of the OBS API.
+    pub api_url: Option<String>,
+    /// OBS project packages are submitted to, e.g. `home:me:aurora`. Its
+    /// repositories decide which targets get built.
+    pub project: Option<String>,
+    /// Account name; with no password in the secrets store requests are
+    /// anonymous, which is enough to read public projects.
+    pub user: Option<String>,
+    /// `curl` binary (default: `curl` on `PATH`).
+    pub curl: PathBuf,
+    /// Limit for a single request, uploads and downloads included.
+    pub timeout_secs: u64,
+}
+
+impl Default for BuildServiceOptions {
+    fn default() -> Self {
+        Self {
+            api_url: None,
+            project: None,
+            user: None,
+            curl: PathBuf::from("curl"),
+            timeout_secs: 600,
+        }
+    }
+}
+
+impl BuildServiceOptions {
+    fn url(&self, path: &str) -> Result<String> {
+        let base = self.api_url.as_deref().ok_or_else(|| {
+            Error::Config(
+                "set api_url in the [build_service] config table to build remotely".to_string(),
+            )
+        })?;
+        Ok(format!("{}/{path}", base.trim_end_matches('/')))
+    }
+
+    fn project(&self) -> Result<&str> {
+        let project = self.project.as_deref().ok_or_else(|| {
+            Error::Config(
+                "set project in the [build_service] config table to build remotely".to_string(),
+            )
+        })?;
+        check_name(project)?;
+        Ok(project)
+    }
+}
+
+enum Body<'a> {
+    None,
+    Text(String),
+    File(&'a Path),
+}
+
+/// Sends a request to the build service and returns its reply, or writes
+/// the reply to `output` and returns an empty string.
+async fn request(
+    server: &AuroraServer,
+    method: &str,
+    path: &str,
+    body: Body<'_>,
+    output: Option<&Path>,
+) -> Result<String> {
+    let config = &server.state().config;
+    let options = &config.build_service;
+    let url = options.url(path)?;
+    let credentials = match &options.user {
+        Some(user) => config
+            .secrets()
+            .get(PASSWORD_SECRET)?
+            .map(|password| format!("{user}:{password}")),
+        None => None,
+    };
+
+    let mut command = Command::new(&options.curl);
+    command
+        .args(["-sS", "-K", "-", "-X", method, "-w", "\n%{http_code}"])
+        .arg("--max-time")
+        .arg(options.timeout_secs.max(1).to_string());
+    match &body {
+        Body::None => {}
+        Body::Text(text) => {
+            command
+                .arg("-H")
+                .arg("Content-Type: application/xml")
+                .arg("--data-binary")
+                .arg(text);
+        }
+        Body::File(file) => {
+            command
+                .arg("-H")
+                .arg("Content-Type: application/octet-stream")
+                .arg("-T")
+                .arg(file);
+        }
+    }
+    if let Some(output) = output {
+        command.arg("-o").arg(output);
+    }
+    command
+        .arg(&url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let program = options.curl.display().to_string();
+    let mut child = command.spawn().map_err(|source| Error::Spawn {
+        program: program.clone(),
+        source,
+    })?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    if let Some(credentials) = credentials {
+        let line = format!(
+            "user = \"{}\"\n",
+            credentials.replace('\\', "\\\\").replace('"', "\\\"")
+        );
+        stdin.write_all(line.as_bytes()).await?;
+    }
+    drop(stdin);
+    let timeout = Duration::from_secs(options.timeout_secs.max(1) + 10);
+    let result = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(result) => result?,
+        Err(_) => {
+            return Err(Error::CommandTimeout {
+                program,
+                seconds: timeout.as_secs(),
+            });
+        }
+    };
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    let (reply, status) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+    let status: u16 = status.trim().parse().unwrap_or_default();
+    if !result.status.success() || status == 0 {
+        return Err(Error::Http {
+            url,
+            status,
+            message: String::from_utf8_lossy(&result.stderr).trim().to_string(),
+        });
+    }
+    if status >= 400 {
+        // Errors come back as `<status code="..."><summary>...</summary>`,
+        // in the output file when there is one.
+        let reply = match output {
+            Some(output) => {
+                let text = std::fs::read_to_string(output).unwrap_or_default();
+                let _ = std::fs::remove_file(output);
+                text
+            }
+            None => reply.to_string(),
+        };
+        let message =
+            xml_text(&reply, "summary").unwrap_or_else(|| reply.trim().chars().take(500).collect());
+        return Err(Error::Http {
+            url,
+            status,
+            message,
+        });
+    }
+    Ok(reply.to_string())
+}
+
+/// `text`, the reply to `path`, as XML.
+fn parse_xml<'a>(text: &'a str, path: &str) -> Result<roxmltree::Document<'a>> {
+    roxmltree::Document::parse(text).map_err(|err| Error::Http {
+        url: path.to_string(),
+        status: 200,
+        message: format!("unexpected reply from the build service: {err}"),
+    })
+}
+
+/// Text of the first `tag` element in `xml`.
+fn xml_text(xml: &str, tag: &str) -> Option<String> {
+    let doc = roxmltree::Document::parse(xml).ok()?;
+    doc.descendants()
+        .find(|node| node.has_tag_name(tag))
+        .and_then(|node| node.text())
+        .map(|text| text.trim().to_string())
+}
+
+/// An OBS project, package, repository or file name safe to put in a URL
+/// path.
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.:+".contains(c))
+    {
+        return Err(Error::InvalidArgument(format!(
+            "'{name}' is not a build service name"
+        )));
+    }
+    Ok(())
+}
+
+/// `text` percent-encoded for a query string.
+fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| {
+            if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+                (byte as char).to_string()
+            } else {
+                format!("%{byte:02X}")
+            }
+        })
+        .collect()
+}
+
+/// Name, version and source tarball file name from the project's spec.
+fn spec_package(dir: &Path) -> Result<(PathBuf, String, String, String)> {
+    let spec_file = ProjectInfo::inspect(dir).spec_file.ok_or_else(|| {
+        Error::InvalidArgument(format!("{} has no RPM spec under rpm/", dir.display()))
+    })?;
+    let text = std::fs::read_to_string(&spec_file)?;
+    let spec = parse_spec(&text);
+    let tag = |name: &str| {
+        spec.tag(name)
+            .map(|(_, value)| value.to_string())
+            .ok_or_else(|| {
+                Error::InvalidArgument(format!("{} has no {name} tag", spec_file.display()))
+            })
+    };
+    let name = tag("Name")?;
+    let version = tag("Version")?;
+    let source = spec
+        .tag("Source0")
+        .or_else(|| spec.tag("Source"))
+        .map(|(_, value)| value.to_string())
+        .unwrap_or_else(|| "%{name}-%{version}.tar.bz2".to_string());
+    let source = source
+        .replace("%{name}", &name)
+        .replace("%name", &name)
+        .replace("%{version}", &version)
+        .replace("%version", &version);
+    let tarball = source.rsplit('/').next().unwrap_or(&source).to_string();
+    if !tarball.contains(".tar") || tarball.contains('%') {
+        return Err(Error::InvalidArgument(format!(
+            "Source0 of {} is '{source}'; remote builds need a tarball named from %{{name}} and %{{version}}",
+            spec_file.display()
+        )));
+    }
+    Ok((spec_file, name, version, tarball))
+}
+
+/// Packs `dir` into `tarball`, under a top directory named after it, the
+/// way `%setup` expects. Version control data and local build output stay
+/// out.
+async fn make_tarball(dir: &Path, tarball: &Path) -> Result<()> {
+    let file_name = tarball
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let top = file_name.split(".tar").next().unwrap_or(&file_name);
+    let output = Command::new("tar")
+        .args([
+            "--exclude-vcs",
+            "--exclude=./RPMS",
+            "--exclude=./build",
+            "--exclude=./build-*",
+        ])
+        .arg(format!("--transform=s,^\\.,{top},"))
+        .arg("-C")
+        .arg(dir)
+        .arg("-caf")
+        .arg(tarball)
+        .arg(".")
+        .output()
+        .await
+        .map_err(|source| Error::Spawn {
+            program: "tar".to_string(),
+            source,
+        })?;
+    if !output.status.success() {
+        return Err(Error::InvalidArgument(format!(
+            "packing {} failed: {}",
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RemoteSubmitParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Package name on the build service (default: the spec's Name).
+    #[serde(default)]
+    pub package: Option<String>,
+    /// Commit message (default: "<name> <version>").
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// Must be true: committing starts builds on the build service.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UploadedSource {
+    pub name: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RemoteSubmission {
+    pub build_project: String,
+    pub package: String,
+    pub version: String,
+    /// Whether the package had to be created first.
+    pub created: bool,
+    /// Source revision the commit created.
+    pub revision: Option<String>,
+    pub uploaded: Vec<UploadedSource>,
+    /// Files of the previous revision dropped because this one lacks them.
+    pub removed: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RemoteStatusParams {
+    /// Project directory, for the default package name.
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Package name on the build service (default: the spec's Name).
+    #[serde(default)]
+    pub package: Option<String>,
+    /// Keep polling up to this many seconds until every build has finished
+    /// (default: 0, report the current state; at most 3600).
+    #[serde(default)]
+    pub wait_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RemoteBuild {
+    pub repository: String,
+    pub arch: String,
+    /// e.g. `scheduled`, `building`, `succeeded`, `failed`, `unresolvable`.
+    pub code: String,
+    /// Why it is unresolvable, broken or blocked.
+    pub details: Option<String>,
+    /// Whether the result predates the latest sources.
+    pub outdated: bool,
+    /// Downloaded build log, for failed builds.
+    pub log_file: Option<PathBuf>,
+    pub errors: Vec<String>,
+    pub log_tail: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RemoteBuildStatus {
+    pub build_project: String,
+    pub package: String,
+    pub builds: Vec<RemoteBuild>,
+    /// Every build has a final result for the latest sources.
+    pub finished: bool,
+    /// Every build that was not disabled or excluded succeeded.
+    pub succeeded: bool,
+    pub waited_secs: u64,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RemoteDownloadParams {
+    /// Project directory, for the default package name and output directory.
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Package name on the build service (default: the spec's Name).
+    #[serde(default)]
+    pub package: Option<String>,
+    /// Only this repository (default: every one with a successful build).
+    #[serde(default)]
+    pub repository: Option<String>,
+    /// Only this architecture, e.g. `armv7hl` or `aarch64`.
+    #[serde(default)]
+    pub arch: Option<String>,
+    /// Where to put the RPMs (default: `RPMS/remote/<repository>` in the
+    /// project), one subdirectory per repository.
+    #[serde(default)]
+    pub output_dir: Option<PathBuf>,
+    /// Also fetch `-debuginfo` and `-debugsource` packages.
+    #[serde(default)]
+    pub include_debug: bool,
+    /// Also fetch the source RPM.
+    #[serde(default)]
+    pub include_source: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DownloadedRpm {
+    pub repository: String,
+    pub arch: String,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RemoteDownload {
+    pub build_project: String,
+    pub package: String,
+    pub files: Vec<DownloadedRpm>,
+    /// `repository/arch: code` of matching builds that have not succeeded.
+    pub skipped: Vec<String>,
+}
+
+/// Package name from the parameter, else the project's spec.
+fn package_name(
+    server: &AuroraServer,
+    package: Option<&str>,
+    project: Option<&str>,
+) -> Result<String> {
+    let package = match package {
+        Some(package) => package.to_string(),
+        None => {
+            let dir = server.state().config.sdk.project(project)?;
+            spec_package(&dir)?.1
+        }
+    };
+    check_name(&package)?;
+    Ok(package)
+}
+
+/// Build results of `package`, by repository and architecture.
+async fn results(server: &AuroraServer, project: &str, package: &str) -> Result<Vec<RemoteBuild>> {
+    let path = format!("build/{project}/_result?package={}", encode(package));
+    let reply = request(server, "GET", &path, Body::None, None).await?;
+    let doc = parse_xml(&reply, &path)?;
+    let mut builds = Vec::new();
+    for result in doc.descendants().filter(|node| node.has_tag_name("result")) {
+        let status = result
+            .children()
+            .find(|node| node.has_tag_name("status") && node.attribute("package") == Some(package));
+        let Some(status) = status else {
+            continue;
+        };
+        builds.push(RemoteBuild {
+            repository: result.attribute("repository").unwrap_or("").to_string(),
+            arch: result.attribute("arch").unwrap_or("").to_string(),
+            code: status.attribute("code").unwrap_or("unknown").to_string(),
+            details: status
+                .children()
+                .find(|node| node.has_tag_name("details"))
+                .and_then(|node| node.text())
+                .map(str::to_string),
+            outdated: result.attribute("dirty") == Some("true")
+                || result.attribute("state") == Some("dirty"),
+            log_file: None,
+            errors: Vec::new(),
+            log_tail: Vec::new(),
+        });
+    }
+    builds.sort_by(|a, b| (&a.repository, &a.arch).cmp(&(&b.repository, &b.arch)));
+    Ok(builds)
+}
+
+fn is_finished(builds: &[RemoteBuild]) -> bool {
+    !builds.is_empty()
+        && builds
+            .iter()
+            .all(|build| !build.outdated && FINAL_CODES.contains(&build.code.as_str()))
+}
+
+#[tool_router(router = obs_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Submit the project to the remote build service (Open Build Service) configured in [build_service]: packs the sources into the tarball the spec's Source0 names, uploads it with the spec and the other files under rpm/, and commits, which starts builds for every repository and architecture of the OBS project. Creates the package if needed. Needs confirm=true. Follow with remote_build_status."
+    )]
+    pub async fn remote_build_submit(
+        &self,
+        Parameters(params): Parameters<RemoteSubmitParams>,
+    ) -> Result<Json<RemoteSubmission>> {
+        let config = &self.state().config;
+        let project = config.build_service.project()?.to_string();
+        let dir = config.sdk.project(params.project.as_deref())?;
+        let (spec_file, name, version, tarball_name) = spec_package(&dir)?;
+        let package = params.package.unwrap_or_else(|| name.clone());
+        check_name(&package)?;
+        check_name(&tarball_name)?;
+        require_confirmation(params.confirm, || {
+            format!(
+                "uploading {package} {version} to {project} on the build service, which starts builds there"
+            )
+        })?;
+
+        let mut files = vec![spec_file.clone()];
+        if let Some(rpm_dir) = spec_file.parent()
+            && let Ok(entries) = std::fs::read_dir(rpm_dir)
+        {
+            let mut extra: Vec<PathBuf> = entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && *path != spec_file)
+                .collect();
+            extra.sort();
+            files.extend(extra);
+        }
+        let work_dir = config.data_dir().join("build-service");
+        std::fs::create_dir_all(&work_dir)?;
+        let tarball = work_dir.join(&tarball_name);
+        make_tarball(&dir, &tarball).await?;
+        files.push(tarball.clone());
+
+        let package_path = format!("source/{project}/{package}");
+        let created = match request(
+            self,
+            "GET",
+            &format!("{package_path}/_meta"),
+            Body::None,
+            None,
+        )
+        .await
+        {
+            Ok(_) => false,
+            Err(Error::Http { status: 404, .. }) => {
+                let meta = format!(
+                    "<package name=\"{package}\" project=\"{project}\"><title>{package}</title><description/></package>"
+                );
+                request(
+                    self,
+                    "PUT",
+                    &format!("{package_path}/_meta"),
+                    Body::Text(meta),
+                    None,
+                )
+                .await?;
+                true
+            }
+            Err(err) => return Err(err),
+        };
+
+        let mut uploaded = Vec::new();
+        for file in &files {
+            let file_name = file
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            check_name(&file_name)?;
+            request(
+                self,
+                "PUT",
+                &format!("{package_path}/{file_name}?rev=upload"),
+                Body::File(file),
+                None,
+            )
+            .await?;
+            uploaded.push(UploadedSource {
+                size: std::fs::metadata(file).map(|meta| meta.len()).unwrap_or(0),
+                name: file_name,
+            });
+        }
+        let _ = std::fs::remove_file(&tarball);
+
+        // The upload revision starts from the last commit, so files this
+        // one no longer has (e.g. the previous version's tarball) have to
+        // go explicitly. Service files (`_service` and friends) stay.
+        let listing = request(self, "GET", &package_path, Body::None, None).await?;
+        let names: BTreeSet<&str> = uploaded.iter().map(|file| file.name.as_str()).collect();
+        let mut removed = Vec::new();
+        for entry in parse_xml(&listing, &package_path)?
+            .descendants()
+            .filter(|node| node.has_tag_name("entry"))
+            .filter_map(|node| node.attribute("name"))
+        {
+            if entry.starts_with('_') || names.contains(entry) || check_name(entry).is_err() {
+                continue;
+            }
+            request(
+                self,
+                "DELETE",
+                &format!("{package_path}/{entry}?rev=upload"),
+                Body::None,
+                None,
+            )
+            .await?;
+            removed.push(entry.to_string());
+        }
+
+        let comment = params
+            .comment
+            .unwrap_or_else(|| format!("{name} {version}"));
+        let reply = request(
+            self,
+            "POST",
+            &format!("{package_path}?cmd=commit&comment={}", encode(&comment)),
+            Body::None,
+            None,
+        )
+        .await?;
+        let revision = roxmltree::Document::parse(&reply).ok().and_then(|doc| {
+            doc.descendants()
+                .find(|node| node.has_tag_name("revision"))
+                .and_then(|node| node.attribute("rev"))
+                .map(str::to_string)
+        });
+        Ok(Json(RemoteSubmission {
+            build_project: project,
+            package,
+            version,
+            created,
+            revision,
+            uploaded,
+            removed,
+        }))
+    }
+
+    #[tool(
+        description = "Report the remote build service's build results for a package, per repository and architecture. wait_secs keeps polling until every build has finished. Failed builds come with their downloaded log, error lines and log tail.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn remote_build_status(
+        &self,
+        Parameters(params): Parameters<RemoteStatusParams>,
+    ) -> Result<Json<RemoteBuildStatus>> {
+        let project = self.state().config.build_service.project()?.to_string();
+        let package = package_name(self, params.package.as_deref(), params.project.as_deref())?;
+        let wait = Duration::from_secs(params.wait_secs.unwrap_or(0).min(MAX_WAIT_SECS));
+        let start = Instant::now();
+        let mut builds = results(self, &project, &package).await?;
+        while !is_finished(&builds) && start.elapsed() < wait {
+            tokio::time::sleep(POLL_INTERVAL.min(wait.saturating_sub(start.elapsed()))).await;
+            builds = results(self, &project, &package).await?;
+        }
+        let finished = is_finished(&builds);
+
+        let logs_dir = self.state().config.data_dir().join("builds");
+        for build in builds.iter_mut().filter(|build| build.code == "failed") {
+            if check_name(&build.repository).is_err() || check_name(&build.arch).is_err() {
+                continue;
+            }
+            std::fs::create_dir_all(&logs_dir)?;
+            let log_file = logs_dir.join(format!(
+                "{package}-build-remote-{}-{}.log",
+                build.repository, build.arch
+            ));
+            request(
+                self,
+                "GET",
+                &format!(
+                    "build/{project}/{}/{}/{package}/_log?nostream=1",
+                    build.repository, build.arch
+                ),
+                Body::None,
+                Some(&log_file),
+            )
+            .await?;
+            let text = std::fs::read_to_string(&log_file).unwrap_or_default();
+            build.errors = text
+                .lines()
+                .filter(|line| is_error_line(line))
+                .take(MAX_LOG_ERRORS)
+                .map(str::to_string)
+                .collect();
+            build.log_tail = tail_lines(&text, LOG_TAIL_LINES);
+            build.log_file = Some(log_file);
+        }
+
+        let succeeded = finished
+            && builds.iter().any(|build| build.code == "succeeded")
+            && builds
+                .iter()
+                .all(|build| ["succeeded", "disabled", "excluded"].contains(&build.code.as_str()));
+        Ok(Json(RemoteBuildStatus {
+            build_project: project,
+            package,
+            builds,
+            finished,
+            succeeded,
+            waited_secs: start.elapsed().as_secs(),
+        }))
+    }
+
+    #[tool(
+        description = "Download the RPMs of a package's successful remote builds into the project (RPMS/remote/<repository> by default), optionally limited to one repository or architecture. Debug and source packages are skipped unless asked for."
+    )]
+    pub async fn remote_build_download(
+        &self,
+        Parameters(params): Parameters<RemoteDownloadParams>,
+    ) -> Result<Json<RemoteDownload>> {
+        let config = &self.state().config;
+        let project = config.build_service.project()?.to_string();
+        let package = package_name(self, params.package.as_deref(), params.project.as_deref())?;
+        let output_dir = match params.output_dir {
+            Some(dir) => dir,
+            None => config
+                .sdk
+                .project(params.project.as_deref())?
+                .join("RPMS")
+                .join("remote"),
+        };
+
+        let mut files = Vec::new();
+        let mut skipped = Vec::new();
+        let builds = results(self, &project, &package).await?;
+        let builds = builds.iter().filter(|build| {
+            params
+                .repository
+                .as_ref()
+                .is_none_or(|repo| *repo == build.repository)
+                && params.arch.as_ref().is_none_or(|arch| *arch == build.arch)
+        });
+        for build in builds {
+            if build.code != "succeeded" || build.outdated {
+                if !["disabled", "excluded"].contains(&build.code.as_str()) {
+                    let state = if build.outdated { " (outdated)" } else { "" };
+                    skipped.push(format!(
+                        "{}/{}: {}{state}",
+                        build.repository, build.arch, build.code
+                    ));
+                }
+                continue;
+            }
+            check_name(&build.repository)?;
+            check_name(&build.arch)?;
+            let build_path = format!(
+                "build/{project}/{}/{}/{package}",
+                build.repository, build.arch
+            );
+            let listing = request(self, "GET", &build_path, Body::None, None).await?;
+            let doc = parse_xml(&listing, &build_path)?;
+            let wanted = doc
+                .descendants()
+                .filter(|node| node.has_tag_name("binary"))
+                .filter_map(|node| node.attribute("filename"))
+                .filter(|file| file.ends_with(".rpm") && check_name(file).is_ok())
+                .filter(|file| params.include_source || !file.ends_with(".src.rpm"))
+                .filter(|file| {
+                    params.include_debug
+                        || !(file.contains("-debuginfo-") || file.contains("-debugsource-"))
+                });
+            let dir = output_dir.join(&build.repository);
+            for file in wanted {
+                std::fs::create_dir_all(&dir)?;
+                let path = dir.join(file);
+                request(
+                    self,
+                    "GET",
+                    &format!("{build_path}/{file}"),
+                    Body::None,
+                    Some(&path),
+                )
+                .await?;
+                files.push(DownloadedRpm {
+                    repository: build.repository.clone(),
+                    arch: build.arch.clone(),
+                    size: std::fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0),
+                    path,
+                });
+            }
+        }
+        if files.is_empty() && skipped.is_empty() {
+            return Err(Error::InvalidArgument(format!(
+                "{project} has no successful build of {package} matching the request"
+            )));
+        }
+        Ok(Json(RemoteDownload {
+            build_project: project,
+            package,
+            files,
+            skipped,
+        }))
+    }
+}
@@ -0,0 +1,349 @@
+//! `check_permissions`: the sandbox permissions a project's code needs,
+//! found from the APIs it uses, against those its `.desktop` file declares.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::compat::{CPP_EXTENSIONS, includes};
+use super::desktop::{self, PERMISSIONS};
+use super::project::{ProjectInfo, find_files};
+use super::silica::{QmlUsage, qml_files, scan_qml};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// A use of an API that needs a permission.
+#[derive(Debug, Clone, Copy)]
+enum Api {
+    /// A Qt module in `QT +=` or `find_package(Qt5 ...)`.
+    QtModule(&'static str),
+    /// A pkg-config dependency.
+    Pkgconfig(&'static str),
+    /// A QML import, matching submodules too.
+    QmlImport(&'static str),
+    /// A QML type instantiated as `Name {`.
+    QmlType(&'static str),
+    /// A C++ include, matching `<Module/Name>` too.
+    Include(&'static str),
+    /// An identifier anywhere in C++ or QML code.
+    Identifier(&'static str),
+}
+
+/// APIs each permission guards. Permissions not listed here, such as
+/// `Compatibility` or `UserDirs`, cannot be told from the code.
+const API_PERMISSIONS: &[(&str, Api)] = &[
+    ("Internet", Api::QtModule("Network")),
+    ("Internet", Api::QtModule("WebSockets")),
+    ("Internet", Api::QmlImport("QtWebSockets")),
+    ("Internet", Api::Include("QNetworkAccessManager")),
+    ("Internet", Api::Include("QTcpSocket")),
+    ("Internet", Api::Include("QUdpSocket")),
+    ("Internet", Api::Include("QWebSocket")),
+    ("Internet", Api::Identifier("QNetworkAccessManager")),
+    ("Internet", Api::Identifier("XMLHttpRequest")),
+    ("Location", Api::QtModule("Positioning")),
+    ("Location", Api::QtModule("Location")),
+    ("Location", Api::QmlImport("QtPositioning")),
+    ("Location", Api::QmlImport("QtLocation")),
+    ("Location", Api::QmlType("PositionSource")),
+    ("Location", Api::Include("QGeoPositionInfoSource")),
+    ("Location", Api::Include("QGeoSatelliteInfoSource")),
+    ("Camera", Api::QmlType("Camera")),
+    ("Camera", Api::Include("QCamera")),
+    ("Camera", Api::Identifier("QCamera")),
+    ("Microphone", Api::Include("QAudioRecorder")),
+    ("Microphone", Api::Include("QAudioInput")),
+    ("Microphone", Api::Identifier("QAudioRecorder")),
+    ("Microphone", Api::Identifier("QAudioInput")),
+    ("Audio", Api::QmlType("Audio")),
+    ("Audio", Api::QmlType("MediaPlayer")),
+    ("Audio", Api::QmlType("SoundEffect")),
+    ("Audio", Api::Include("QMediaPlayer")),
+    ("Audio", Api::Include("QSoundEffect")),
+    ("Contacts", Api::QtModule("Contacts")),
+    ("Contacts", Api::QmlImport("org.nemomobile.contacts")),
+    ("Contacts", Api::QmlImport("Sailfish.Contacts")),
+    ("Contacts", Api::Include("QContactManager")),
+    ("Calendar", Api::QmlImport("org.nemomobile.calendar")),
+    ("Calendar", Api::QmlImport("Sailfish.Calendar")),
+    ("Calendar", Api::Pkgconfig("libmkcal-qt5")),
+    ("Bluetooth", Api::QtModule("Bluetooth")),
+    ("Bluetooth", Api::QmlImport("QtBluetooth")),
+    ("Bluetooth", Api::Include("QBluetoothLocalDevice")),
+    ("Bluetooth", Api::Include("QBluetoothDeviceDiscoveryAgent")),
+    ("NFC", Api::QtModule("Nfc")),
+    ("NFC", Api::QmlImport("QtNfc")),
+    ("NFC", Api::Include("QNearFieldManager")),
+    ("Sensors", Api::QtModule("Sensors")),
+    ("Sensors", Api::QmlImport("QtSensors")),
+    ("Sensors", Api::Include("QSensor")),
+    ("Sensors", Api::Include("QAccelerometer")),
+    ("WebView", Api::QmlImport("Sailfish.WebView")),
+    ("WebView", Api::QmlImport("Aurora.WebView")),
+    ("PushNotifications", Api::Include("push_client.h")),
+    ("PushNotifications", Api::Pkgconfig("pushclient")),
+    ("Pictures", Api::Identifier("PicturesLocation")),
+    ("Pictures", Api::Identifier("StandardPaths.pictures")),
+    ("Documents", Api::Identifier("DocumentsLocation")),
+    ("Documents", Api::Identifier("StandardPaths.documents")),
+    ("Music", Api::Identifier("MusicLocation")),
+    ("Music", Api::Identifier("StandardPaths.music")),
+    ("Videos", Api::Identifier("MoviesLocation")),
+    ("Videos", Api::Identifier("StandardPaths.videos")),
+    ("Downloads", Api::Identifier("DownloadLocation")),
+    ("Downloads", Api::Identifier("StandardPaths.download")),
+];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CheckPermissionsParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Desktop file, absolute or relative to the project (default: the one
+    /// in the project directory).
+    #[serde(default)]
+    pub desktop: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PermissionUse {
+    /// The module, include, QML type or identifier found.
+    pub api: String,
+    /// Relative to the project.
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RequiredPermission {
+    pub permission: String,
+    pub declared: bool,
+    /// Where the code needs it; the first few uses per API.
+    pub uses: Vec<PermissionUse>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PermissionReport {
+    pub desktop: PathBuf,
+    pub files_checked: usize,
+    /// `Permissions=` of `[X-Application]`.
+    pub declared: Vec<String>,
+    pub required: Vec<RequiredPermission>,
+    /// Needed by the code but not declared; the app is denied these APIs
+    /// in the sandbox.
+    pub missing: Vec<String>,
+    /// Declared but not needed by anything found in the code.
+    pub excessive: Vec<String>,
+    /// Declared permissions the code cannot show a need for either way,
+    /// e.g. `UserDirs`; review them by hand.
+    pub unverified: Vec<String>,
+    /// Declared permissions Aurora OS does not know.
+    pub unknown: Vec<String>,
+    /// `Permissions=` line with missing ones added and excessive and unknown
+    /// ones removed.
+    pub suggested: String,
+    /// Nothing is missing.
+    pub passed: bool,
+}
+
+/// Whether `word` occurs in `line` as a whole identifier.
+fn contains_word(line: &str, word: &str) -> bool {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    line.match_indices(word).any(|(at, _)| {
+        !line[..at].ends_with(is_ident) && !line[at + word.len()..].starts_with(is_ident)
+    })
+}
+
+/// At most this many uses of each API are reported.
+const USES_PER_API: usize = 3;
+
+/// Permissions the code in `dir` needs, with where, and the number of
+/// source files scanned.
+pub(crate) fn scan(
+    dir: &Path,
+    info: &ProjectInfo,
+) -> Result<(usize, BTreeMap<&'static str, Vec<PermissionUse>>)> {
+    let mut uses: BTreeMap<&str, Vec<PermissionUse>> = BTreeMap::new();
+    let mut found = |permission: &'static str, api: String, file: Option<&Path>, line| {
+        let list = uses.entry(permission).or_default();
+        if list.iter().filter(|u| u.api == api).count() < USES_PER_API {
+            list.push(PermissionUse {
+                api,
+                file: file.map(|file| file.strip_prefix(dir).unwrap_or(file).to_path_buf()),
+                line,
+            });
+        }
+    };
+
+    for (permission, api) in API_PERMISSIONS {
+        match api {
+            Api::QtModule(module) if info.qt_modules.iter().any(|m| m == module) => found(
+                permission,
+                format!("Qt {module}"),
+                info.project_file.as_deref(),
+                None,
+            ),
+            Api::Pkgconfig(name) if info.pkgconfig.iter().any(|p| p == name) => found(
+                permission,
+                name.to_string(),
+                info.project_file.as_deref(),
+                None,
+            ),
+            _ => {}
+        }
+    }
+
+    let qml = if info.qml_dir.is_some() {
+        qml_files(dir)?
+    } else {
+        Vec::new()
+    };
+    let cpp: Vec<PathBuf> = CPP_EXTENSIONS
+        .iter()
+        .flat_map(|ext| find_files(dir, ext, 4))
+        .collect();
+    for file in qml.iter().chain(&cpp) {
+        let text = std::fs::read_to_string(file)?;
+        if file.extension().is_some_and(|ext| ext == "qml") {
+            for (line, usage) in scan_qml(&text) {
+                for (permission, api) in API_PERMISSIONS {
+                    match (api, &usage) {
+                        (Api::QmlImport(prefix), QmlUsage::Import { module })
+                            if module == prefix || module.starts_with(&format!("{prefix}.")) =>
+                        {
+                            found(permission, module.clone(), Some(file), Some(line));
+                        }
+                        (Api::QmlType(name), QmlUsage::Component { name: used })
+                            if used == name =>
+                        {
+                            found(permission, used.clone(), Some(file), Some(line));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        } else {
+            for (line, path) in includes(&text) {
+                for (permission, api) in API_PERMISSIONS {
+                    if let Api::Include(name) = api
+                        && (path == *name || path.ends_with(&format!("/{name}")))
+                    {
+                        found(permission, path.to_string(), Some(file), Some(line));
+                    }
+                }
+            }
+        }
+        for (index, line) in text.lines().enumerate() {
+            let code = line.split("//").next().unwrap_or(line);
+            if code.trim_start().starts_with('#') {
+                continue;
+            }
+            for (permission, api) in API_PERMISSIONS {
+                if let Api::Identifier(word) = api
+                    && contains_word(code, word)
+                {
+                    found(permission, word.to_string(), Some(file), Some(index + 1));
+                }
+            }
+        }
+    }
+    Ok((qml.len() + cpp.len(), uses))
+}
+
+/// `Permissions=` of the `[X-Application]` group.
+pub(crate) fn declared(entries: &[desktop::Entry<'_>]) -> Vec<String> {
+    desktop::find(entries, "X-Application", "Permissions")
+        .map(|entry| {
+            entry
+                .value
+                .split(';')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[tool_router(router = permissions_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Check an Aurora app's sandbox permissions: scans the project's Qt modules, includes, QML imports and types for APIs that need a permission (Internet, Location, Camera, Microphone, Contacts, ...) and compares them with Permissions= in the .desktop file, reporting missing permissions with where they are needed, excessive ones, and a suggested Permissions= line.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn check_permissions(
+        &self,
+        Parameters(params): Parameters<CheckPermissionsParams>,
+    ) -> Result<Json<PermissionReport>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        let info = ProjectInfo::inspect(&dir);
+        let desktop = match params.desktop {
+            Some(desktop) => dir.join(desktop),
+            None => info.desktop_file.clone().ok_or_else(|| {
+                Error::InvalidArgument(format!(
+                    "no .desktop file in {}; pass desktop or create one with generate_desktop",
+                    dir.display()
+                ))
+            })?,
+        };
+        let text = std::fs::read_to_string(&desktop)
+            .map_err(|err| Error::InvalidArgument(format!("{}: {err}", desktop.display())))?;
+        let entries = desktop::parse(&text);
+        let declared = declared(&entries);
+        let (files_checked, uses) = scan(&dir, &info)?;
+
+        let detectable: BTreeSet<&str> = API_PERMISSIONS.iter().map(|(p, _)| *p).collect();
+        let is_declared = |permission: &str| declared.iter().any(|d| d == permission);
+        let missing: Vec<String> = uses
+            .keys()
+            .filter(|p| !is_declared(p))
+            .map(|p| p.to_string())
+            .collect();
+        let unknown: Vec<String> = declared
+            .iter()
+            .filter(|p| !PERMISSIONS.contains(&p.as_str()))
+            .cloned()
+            .collect();
+        let excessive: Vec<String> = declared
+            .iter()
+            .filter(|p| detectable.contains(p.as_str()) && !uses.contains_key(p.as_str()))
+            .cloned()
+            .collect();
+        let unverified: Vec<String> = declared
+            .iter()
+            .filter(|p| PERMISSIONS.contains(&p.as_str()) && !detectable.contains(p.as_str()))
+            .cloned()
+            .collect();
+        let suggested: Vec<&str> = declared
+            .iter()
+            .filter(|p| !excessive.contains(p) && !unknown.contains(p))
+            .chain(&missing)
+            .map(String::as_str)
+            .collect();
+
+        Ok(Json(PermissionReport {
+            desktop,
+            files_checked,
+            passed: missing.is_empty(),
+            suggested: format!("Permissions={}", suggested.join(";")),
+            required: uses
+                .into_iter()
+                .map(|(permission, uses)| RequiredPermission {
+                    declared: is_declared(permission),
+                    permission: permission.to_string(),
+                    uses,
+                })
+                .collect(),
+            declared,
+            missing,
+            excessive,
+            unverified,
+            unknown,
+        }))
+    }
+}
@@ -0,0 +1,357 @@
+//! What a project on the host is made of: its build system, name and
+//! version, Qt modules and pkg-config dependencies, and the QML,
+//! translation, Python, icon and desktop files it ships.
+
+use std::path::{Path, PathBuf};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use super::python;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildSystem {
+    Qmake,
+    Cmake,
+    Qbs,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ProjectInfo {
+    pub dir: PathBuf,
+    pub build_system: Option<BuildSystem>,
+    /// The `.pro`, `CMakeLists.txt` or `.qbs` file.
+    pub project_file: Option<PathBuf>,
+    /// Package name: the `TARGET` or CMake project name, usually
+    /// `org.example.app`.
+    pub name: String,
+    pub version: Option<String>,
+    /// Qt modules, e.g. `Core`, `Quick`, `DBus`.
+    pub qt_modules: Vec<String>,
+    /// Other pkg-config dependencies, e.g. `auroraapp`.
+    pub pkgconfig: Vec<String>,
+    pub qml_dir: Option<PathBuf>,
+    /// QML imports `Sailfish.Silica`.
+    pub uses_silica: bool,
+    /// Python modules loaded through PyOtherSide, e.g. `qml/py`.
+    pub python_dir: Option<PathBuf>,
+    /// QML imports `io.thp.pyotherside`.
+    pub uses_pyotherside: bool,
+    /// Third-party modules the Python sources import, e.g. `requests`.
+    pub python_imports: Vec<String>,
+    pub translations: Vec<PathBuf>,
+    /// Icon sizes found under `icons/`, e.g. `86x86`.
+    pub icon_sizes: Vec<String>,
+    pub desktop_file: Option<PathBuf>,
+    pub spec_file: Option<PathBuf>,
+}
+
+/// Files directly in `dir` with `extension`, sorted.
+fn files_with_extension(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == extension))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Files below `dir` with `extension`, at most `depth` levels down.
+pub(crate) fn find_files(dir: &Path, extension: &str, depth: usize) -> Vec<PathBuf> {
+    let mut found = files_with_extension(dir, extension);
+    if depth == 0 {
+        return found;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return found;
+    };
+    let mut subdirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_dir()
+                && path
+                    .file_name()
+                    .is_some_and(|name| !name.to_string_lossy().starts_with('.'))
+        })
+        .collect();
+    subdirs.sort();
+    for subdir in subdirs {
+        found.extend(find_files(&subdir, extension, depth - 1));
+    }
+    found
+}
+
+/// Values assigned to `variable` in a qmake project, following `=`, `+=`
+/// and `-=` across line continuations.
+pub fn qmake_values(pro: &str, variable: &str) -> Vec<String> {
+    let joined = pro.replace("\\\n", " ");
+    let mut values: Vec<String> = Vec::new();
+    for line in joined.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let Some(rest) = line.strip_prefix(variable).map(str::trim_start) else {
+            continue;
+        };
+        if let Some(rest) = rest.strip_prefix("-=") {
+            let removed: Vec<&str> = rest.split_whitespace().collect();
+            values.retain(|value| !removed.contains(&value.as_str()));
+            continue;
+        }
+        let (append, rest) = if let Some(rest) = rest.strip_prefix("+=") {
+            (true, rest)
+        } else if let Some(rest) = rest.strip_prefix("*=") {
+            (true, rest)
+        } else if let Some(rest) = rest.strip_prefix('=') {
+            (false, rest)
+        } else {
+            continue;
+        };
+        if !append {
+            values.clear();
+        }
+        values.extend(rest.split_whitespace().map(str::to_string));
+    }
+    values
+}
+
+/// Arguments of each `command(...)` call in a CMake file, split on
+/// whitespace outside double-quoted strings.
+pub fn cmake_calls(cmake: &str, command: &str) -> Vec<Vec<String>> {
+    let lower = cmake.to_ascii_lowercase();
+    let needle = format!("{}(", command.to_ascii_lowercase());
+    let mut calls = Vec::new();
+    let mut from = 0;
+    while let Some(at) = lower[from..].find(&needle) {
+        let start = from + at + needle.len();
+        let preceded_by_name = lower[..from + at]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+        let mut args = Vec::new();
+        let mut arg = String::new();
+        let mut quoted = false;
+        let mut end = None;
+        for (offset, c) in cmake[start..].char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                ')' if !quoted => {
+                    end = Some(start + offset);
+                    break;
+                }
+                c if c.is_whitespace() && !quoted => {
+                    if !arg.is_empty() {
+                        args.push(std::mem::take(&mut arg));
+                    }
+                }
+                c => arg.push(c),
+            }
+        }
+        let Some(end) = end else {
+            break;
+        };
+        if !arg.is_empty() {
+            args.push(arg);
+        }
+        if !preceded_by_name {
+            calls.push(args);
+        }
+        from = end;
+    }
+    calls
+}
+
+impl ProjectInfo {
+    /// A QML app with Python modules and nothing to compile, launched by
+    /// `sailfish-qml`.
+    pub fn is_python_app(&self) -> bool {
+        self.build_system.is_none() && self.python_dir.is_some()
+    }
+
+    pub fn inspect(dir: &Path) -> Self {
+        let cmake = dir.join("CMakeLists.txt");
+        let pro = files_with_extension(dir, "pro").into_iter().next();
+        let qbs = files_with_extension(dir, "qbs").into_iter().next();
+        let (build_system, project_file) = if cmake.is_file() {
+            (Some(BuildSystem::Cmake), Some(cmake))
+        } else if let Some(pro) = pro {
+            (Some(BuildSystem::Qmake), Some(pro))
+        } else if let Some(qbs) = qbs {
+            (Some(BuildSystem::Qbs), Some(qbs))
+        } else {
+            (None, None)
+        };
+        let text = project_file
+            .as_ref()
+            .and_then(|file| std::fs::read_to_string(file).ok())
+            .unwrap_or_default();
+
+        let mut name = None;
+        let mut version = None;
+        let mut qt_modules = Vec::new();
+        let mut pkgconfig = Vec::new();
+        match build_system {
+            Some(BuildSystem::Qmake) => {
+                name = qmake_values(&text, "TARGET").into_iter().next();
+                version = qmake_values(&text, "VERSION").into_iter().next();
+                // qmake links core and gui unless the project says otherwise.
+                qt_modules = qmake_values(&format!("QT = core gui\n{text}"), "QT");
+                pkgconfig = qmake_values(&text, "PKGCONFIG");
+                for config in qmake_values(&text, "CONFIG") {
+                    if matches!(config.as_str(), "auroraapp" | "sailfishapp") {
+                        pkgconfig.push(config);
+                    }
+                }
+            }
+            Some(BuildSystem::Cmake) => {
+                if let Some(args) = cmake_calls(&text, "project").into_iter().next() {
+                    name = args.first().cloned();
+                    version = args
+                        .iter()
+                        .position(|arg| arg.eq_ignore_ascii_case("VERSION"))
+                        .and_then(|at| args.get(at + 1).cloned());
+                }
+                for args in cmake_calls(&text, "find_package") {
+                    let Some(package) = args.first() else {
+                        continue;
+                    };
+                    let module = package
+                        .strip_prefix("Qt5")
+                        .or_else(|| package.strip_prefix("Qt6"))
+                        .filter(|module| !module.is_empty());
+                    if let Some(module) = module {
+                        qt_modules.push(module.to_string());
+                    } else if package.starts_with("Qt") {
+                        qt_modules.extend(
+                            args.iter()
+                                .skip_while(|arg| *arg != "COMPONENTS")
+                                .filter(|arg| arg.chars().any(|c| c.is_ascii_lowercase()))
+                                .cloned(),
+                        );
+                    }
+                }
+                let modules = cmake_calls(&text, "pkg_check_modules")
+                    .into_iter()
+                    .chain(cmake_calls(&text, "pkg_search_module"));
+                for args in modules {
+                    pkgconfig.extend(
+                        args.iter()
+                            .skip(1)
+                            .filter(|arg| !arg.chars().all(|c| c.is_ascii_uppercase() || c == '_'))
+                            .map(|arg| {
+                                arg.split(['>', '<', '=']).next().unwrap_or(arg).to_string()
+                            }),
+                    );
+                }
+            }
+            _ => {}
+        }
+        let mut qt_modules: Vec<String> = qt_modules
+            .into_iter()
+            .map(|module| qt_module_name(&module))
+            .collect();
+        qt_modules.sort();
+        qt_modules.dedup();
+        pkgconfig.sort();
+        pkgconfig.dedup();
+
+        let name = name
+            .filter(|name| !name.contains('$'))
+            .or_else(|| {
+                project_file
+                    .as_ref()
+                    .filter(|file| file.extension().is_some_and(|ext| ext != "txt"))
+                    .and_then(|file| file.file_stem())
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            })
+            .or_else(|| {
+                dir.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "app".to_string());
+
+        let qml_dir = ["qml", "src/qml"]
+            .iter()
+            .map(|sub| dir.join(sub))
+            .find(|path| path.is_dir());
+        let qml_imports = |module: &str| {
+            qml_dir.as_ref().is_some_and(|qml| {
+                find_files(qml, "qml", 4).iter().any(|file| {
+                    std::fs::read_to_string(file)
+                        .is_ok_and(|text| text.contains(&format!("import {module}")))
+                })
+            })
+        };
+        let uses_silica = qml_imports("Sailfish.Silica");
+        let uses_pyotherside = qml_imports("io.thp.pyotherside");
+        let python_dir = python::python_dir(dir);
+        let python_imports = python_dir
+            .as_deref()
+            .map(python::third_party_imports)
+            .unwrap_or_default();
+        let mut icon_sizes: Vec<String> = std::fs::read_dir(dir.join("icons"))
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.path().is_dir())
+                    .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                    .filter(|size| size.contains('x'))
+                    .collect()
+            })
+            .unwrap_or_default();
+        icon_sizes.sort_by_key(|size| {
+            size.split('x')
+                .next()
+                .and_then(|n| n.parse::<u32>().ok())
+                .unwrap_or_default()
+        });
+
+        ProjectInfo {
+            dir: dir.to_path_buf(),
+            build_system,
+            project_file,
+            version,
+            qt_modules,
+            pkgconfig,
+            uses_silica,
+            python_dir,
+            uses_pyotherside,
+            python_imports,
+            translations: find_files(&dir.join("translations"), "ts", 1),
+            icon_sizes,
+            desktop_file: files_with_extension(dir, "desktop").into_iter().next(),
+            spec_file: files_with_extension(&dir.join("rpm"), "spec")
+                .into_iter()
+                .next(),
+            qml_dir,
+            name,
+        }
+    }
+}
+
+/// Canonical Qt module name: `quick` and `Quick` both become `Quick`,
+/// `dbus` becomes `DBus`.
+fn qt_module_name(module: &str) -> String {
+    let module = module.trim_start_matches("Qt5::").trim_start_matches("Qt5");
+    match module.to_ascii_lowercase().as_str() {
+        "dbus" => "DBus".to_string(),
+        "xml" => "Xml".to_string(),
+        "xmlpatterns" => "XmlPatterns".to_string(),
+        "webkit" => "WebKit".to_string(),
+        "sql" => "Sql".to_string(),
+        "quickcontrols2" => "QuickControls2".to_string(),
+        "multimedia" => "Multimedia".to_string(),
+        "positioning" => "Positioning".to_string(),
+        lower => {
+            let mut chars = lower.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        }
+    }
+}
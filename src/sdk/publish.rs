@@ -0,0 +1,481 @@
+//! Publishing to the Aurora store through the developer portal's REST API:
+//! uploading signed RPMs, editing a version's listing and following
+//! moderation. Requests go through the host's `curl`; the API token comes
+//! from the secrets store (`store.token`) and is passed to `curl` on stdin
+//! so it never shows up in process listings.
+//!
+//! ```toml
+//! [store]
+//! api_url = "https://portal.example.com/api/v1"
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::rpm::parse_rpm_name;
+use crate::device::png;
+use crate::error::{Error, Result, require_confirmation};
+use crate::server::AuroraServer;
+
+/// Secret holding the developer portal API token.
+const TOKEN_SECRET: &str = "store.token";
+
+/// Store settings, from the `[store]` config table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StoreOptions {
+    /// Base URL of the developer portal API.
+    pub api_url: Option<String>,
+    /// `curl` binary (default: `curl` on `PATH`).
+    pub curl: PathBuf,
+    /// Limit for a single request, uploads included.
+    pub timeout_secs: u64,
+}
+
+impl Default for StoreOptions {
+    fn default() -> Self {
+        Self {
+            api_url: None,
+            curl: PathBuf::from("curl"),
+            timeout_secs: 600,
+        }
+    }
+}
+
+impl StoreOptions {
+    fn url(&self, path: &str) -> Result<String> {
+        let base = self.api_url.as_deref().ok_or_else(|| {
+            Error::Config("set api_url in the [store] config table to publish".to_string())
+        })?;
+        Ok(format!("{}/{path}", base.trim_end_matches('/')))
+    }
+}
+
+enum Body<'a> {
+    None,
+    Json(Value),
+    /// A multipart form with one file field.
+    File(&'a str, &'a Path),
+}
+
+/// Sends a request to the developer portal and returns its JSON reply
+/// (`null` for an empty one, a string for a non-JSON one).
+async fn request(server: &AuroraServer, method: &str, path: &str, body: Body<'_>) -> Result<Value> {
+    let config = &server.state().config;
+    let options = &config.store;
+    let url = options.url(path)?;
+    let token = config.secrets().require(TOKEN_SECRET)?;
+
+    let mut command = Command::new(&options.curl);
+    command
+        .args(["-sS", "-K", "-", "-X", method, "-w", "\n%{http_code}"])
+        .arg("--max-time")
+        .arg(options.timeout_secs.max(1).to_string())
+        .arg("-H")
+        .arg("Accept: application/json");
+    match &body {
+        Body::None => {}
+        Body::Json(value) => {
+            command
+                .arg("-H")
+                .arg("Content-Type: application/json")
+                .arg("--data-binary")
+                .arg(value.to_string());
+        }
+        Body::File(field, file) => {
+            command
+                .arg("-F")
+                .arg(format!("{field}=@{}", file.display()));
+        }
+    }
+    command
+        .arg(&url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let program = options.curl.display().to_string();
+    let mut child = command.spawn().map_err(|source| Error::Spawn {
+        program: program.clone(),
+        source,
+    })?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let header = format!(
+        "header = \"Authorization: Bearer {}\"\n",
+        token.replace('\\', "\\\\").replace('"', "\\\"")
+    );
+    stdin.write_all(header.as_bytes()).await?;
+    drop(stdin);
+    let timeout = Duration::from_secs(options.timeout_secs.max(1) + 10);
+    let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => output?,
+        Err(_) => {
+            return Err(Error::CommandTimeout {
+                program,
+                seconds: timeout.as_secs(),
+            });
+        }
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (reply, status) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+    let status: u16 = status.trim().parse().unwrap_or_default();
+    if !output.status.success() || status == 0 {
+        return Err(Error::Http {
+            url,
+            status,
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    let value = if reply.trim().is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_str(reply).unwrap_or_else(|_| Value::String(reply.to_string()))
+    };
+    if status >= 400 {
+        let message = ["message", "error", "detail"]
+            .iter()
+            .find_map(|key| value.get(key).and_then(Value::as_str))
+            .map(str::to_string)
+            .unwrap_or_else(|| reply.chars().take(500).collect());
+        return Err(Error::Http {
+            url,
+            status,
+            message,
+        });
+    }
+    Ok(value)
+}
+
+/// `value[key]` as a string, for numbers too.
+fn field(value: &Value, key: &str) -> Option<String> {
+    match value.get(key)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// A package name safe to put in a URL path.
+fn check_package(package: &str) -> Result<()> {
+    if package.is_empty()
+        || !package
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+    {
+        return Err(Error::InvalidArgument(format!(
+            "'{package}' is not a package name"
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct StoreUploadParams {
+    /// Signed RPM on this machine.
+    pub rpm: PathBuf,
+    /// Must be true: the upload submits the version for moderation.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StoreSubmission {
+    pub package: String,
+    /// `version-release`.
+    pub version: String,
+    pub arch: String,
+    /// Id to pass to `store_submission_status`.
+    pub submission_id: Option<String>,
+    pub status: Option<String>,
+    /// The portal's reply as is.
+    pub response: Value,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct StoreListingParams {
+    pub package: String,
+    /// Version the listing belongs to, as uploaded.
+    pub version: String,
+    /// Release notes by locale, e.g. `{"en": "...", "ru": "..."}`; replaces
+    /// the version's notes.
+    #[serde(default)]
+    pub release_notes: BTreeMap<String, String>,
+    /// PNG or JPEG screenshots to add, in order.
+    #[serde(default)]
+    pub screenshots: Vec<PathBuf>,
+    /// Delete the current screenshots first.
+    #[serde(default)]
+    pub replace_screenshots: bool,
+    /// Must be true to delete screenshots.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UploadedScreenshot {
+    pub file: PathBuf,
+    /// `WIDTHxHEIGHT`, for PNGs.
+    pub size: Option<String>,
+    pub id: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StoreListing {
+    pub package: String,
+    pub version: String,
+    /// Locales whose release notes were set.
+    pub release_notes: Vec<String>,
+    pub screenshots_deleted: bool,
+    pub screenshots: Vec<UploadedScreenshot>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct StoreStatusParams {
+    pub package: String,
+    /// Submission id from `store_upload` (default: every submission of the
+    /// package).
+    #[serde(default)]
+    pub submission_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SubmissionStatus {
+    pub id: Option<String>,
+    pub version: Option<String>,
+    /// e.g. `pending`, `in_review`, `approved`, `rejected`.
+    pub status: Option<String>,
+    /// Moderator comments, e.g. why it was rejected.
+    pub comments: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StoreStatus {
+    pub package: String,
+    pub submissions: Vec<SubmissionStatus>,
+    pub response: Value,
+}
+
+fn submission_status(value: &Value) -> SubmissionStatus {
+    let comments = ["comments", "moderation_comments", "messages"]
+        .iter()
+        .find_map(|key| value.get(key).and_then(Value::as_array))
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| match item {
+                    Value::String(text) => Some(text.clone()),
+                    other => field(other, "text").or_else(|| field(other, "message")),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    SubmissionStatus {
+        id: field(value, "id"),
+        version: field(value, "version"),
+        status: field(value, "status").or_else(|| field(value, "state")),
+        comments,
+    }
+}
+
+/// Name, `version-release`, arch and whether the package carries a
+/// signature, from the host's `rpm`; `None` without one.
+async fn rpm_header(rpm: &Path) -> Option<(String, String, String, bool)> {
+    let output = Command::new("rpm")
+        .args([
+            "-qp",
+            "--qf",
+            "%{NAME}\\n%{VERSION}-%{RELEASE}\\n%{ARCH}\\n%{SIGPGP:pgpsig}%{RSAHEADER:pgpsig}%{DSAHEADER:pgpsig}\\n",
+        ])
+        .arg(rpm)
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut lines = text.lines();
+    let name = lines.next()?.to_string();
+    let version = lines.next()?.to_string();
+    let arch = lines.next()?.to_string();
+    let signed = lines.next().is_some_and(|sig| sig != "(none)(none)(none)");
+    Some((name, version, arch, signed))
+}
+
+#[tool_router(router = publish_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Upload a signed RPM to the Aurora store's developer portal, submitting the version for moderation. Refuses packages the host's rpm reports as unsigned. Needs confirm=true; the API token comes from the secrets store (store.token). Returns the submission id for store_submission_status."
+    )]
+    pub async fn store_upload(
+        &self,
+        Parameters(params): Parameters<StoreUploadParams>,
+    ) -> Result<Json<StoreSubmission>> {
+        if !params.rpm.is_file() {
+            return Err(Error::InvalidArgument(format!(
+                "{} does not exist",
+                params.rpm.display()
+            )));
+        }
+        let (package, version, arch) = match rpm_header(&params.rpm).await {
+            Some((_, _, _, false)) => {
+                return Err(Error::InvalidArgument(format!(
+                    "{} is not signed; sign it with rpmsign-external in the Build Engine first",
+                    params.rpm.display()
+                )));
+            }
+            Some((name, version, arch, true)) => (name, version, arch),
+            None => parse_rpm_name(&params.rpm.to_string_lossy()).ok_or_else(|| {
+                Error::InvalidArgument(format!(
+                    "{} is not a name-version-release.arch.rpm file",
+                    params.rpm.display()
+                ))
+            })?,
+        };
+        if arch == "src" || package.ends_with("-debuginfo") || package.ends_with("-debugsource") {
+            return Err(Error::InvalidArgument(format!(
+                "{} is a source or debug package; upload the application RPM",
+                params.rpm.display()
+            )));
+        }
+        check_package(&package)?;
+        require_confirmation(params.confirm, || {
+            format!("submitting {package} {version} ({arch}) to the store")
+        })?;
+        let response = request(
+            self,
+            "POST",
+            &format!("applications/{package}/versions"),
+            Body::File("file", &params.rpm),
+        )
+        .await?;
+        Ok(Json(StoreSubmission {
+            submission_id: field(&response, "submission_id").or_else(|| field(&response, "id")),
+            status: field(&response, "status"),
+            package,
+            version,
+            arch,
+            response,
+        }))
+    }
+
+    #[tool(
+        description = "Edit an uploaded version's store listing: set release notes per locale and upload screenshots (PNG or JPEG). replace_screenshots deletes the current ones first and needs confirm=true."
+    )]
+    pub async fn store_set_listing(
+        &self,
+        Parameters(params): Parameters<StoreListingParams>,
+    ) -> Result<Json<StoreListing>> {
+        check_package(&params.package)?;
+        check_package(&params.version)?;
+        let mut sizes = Vec::new();
+        for file in &params.screenshots {
+            let data = std::fs::read(file)
+                .map_err(|err| Error::InvalidArgument(format!("{}: {err}", file.display())))?;
+            let size = if file.extension().is_some_and(|ext| ext == "png") {
+                let image = png::decode(&data)?;
+                Some(format!("{}x{}", image.width, image.height))
+            } else if data.starts_with(&[0xff, 0xd8]) {
+                None
+            } else {
+                return Err(Error::InvalidArgument(format!(
+                    "{} is neither a PNG nor a JPEG",
+                    file.display()
+                )));
+            };
+            sizes.push(size);
+        }
+        if params.replace_screenshots {
+            require_confirmation(params.confirm, || {
+                format!("deleting the store screenshots of {}", params.package)
+            })?;
+        }
+
+        if !params.release_notes.is_empty() {
+            request(
+                self,
+                "PUT",
+                &format!(
+                    "applications/{}/versions/{}/release-notes",
+                    params.package, params.version
+                ),
+                Body::Json(serde_json::json!({ "release_notes": params.release_notes })),
+            )
+            .await?;
+        }
+        if params.replace_screenshots {
+            request(
+                self,
+                "DELETE",
+                &format!("applications/{}/screenshots", params.package),
+                Body::None,
+            )
+            .await?;
+        }
+        let mut screenshots = Vec::new();
+        for (file, size) in params.screenshots.iter().zip(sizes) {
+            let response = request(
+                self,
+                "POST",
+                &format!("applications/{}/screenshots", params.package),
+                Body::File("file", file),
+            )
+            .await?;
+            screenshots.push(UploadedScreenshot {
+                file: file.clone(),
+                size,
+                id: field(&response, "id"),
+            });
+        }
+        Ok(Json(StoreListing {
+            package: params.package,
+            version: params.version,
+            release_notes: params.release_notes.into_keys().collect(),
+            screenshots_deleted: params.replace_screenshots,
+            screenshots,
+        }))
+    }
+
+    #[tool(
+        description = "Query the store moderation status of a package's submissions, or of one submission by id, with moderator comments.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn store_submission_status(
+        &self,
+        Parameters(params): Parameters<StoreStatusParams>,
+    ) -> Result<Json<StoreStatus>> {
+        check_package(&params.package)?;
+        let path = match &params.submission_id {
+            Some(id) => {
+                check_package(id)?;
+                format!("applications/{}/submissions/{id}", params.package)
+            }
+            None => format!("applications/{}/submissions", params.package),
+        };
+        let response = request(self, "GET", &path, Body::None).await?;
+        let submissions = match &response {
+            Value::Array(items) => items.iter().map(submission_status).collect(),
+            Value::Object(object) => match object.get("submissions").or(object.get("items")) {
+                Some(Value::Array(items)) => items.iter().map(submission_status).collect(),
+                _ => vec![submission_status(&response)],
+            },
+            _ => Vec::new(),
+        };
+        Ok(Json(StoreStatus {
+            package: params.package,
+            submissions,
+            response,
+        }))
+    }
+}
@@ -0,0 +1,169 @@
+//! Python (PyOtherSide) apps: finding the Python sources of a project and
+//! the device packages its imports need.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use super::project::find_files;
+
+/// Where Python apps keep their modules, relative to the project. `qml/py`
+/// comes first: it ships with the QML, so live previews load it too.
+const PYTHON_DIRS: &[&str] = &["qml/py", "qml/python", "python", "src/python", "py"];
+
+/// Modules that come with `python3-base` or PyOtherSide itself.
+const BUILTIN: &[&str] = &[
+    "__future__",
+    "abc",
+    "argparse",
+    "array",
+    "ast",
+    "asyncio",
+    "base64",
+    "binascii",
+    "bisect",
+    "builtins",
+    "calendar",
+    "collections",
+    "configparser",
+    "contextlib",
+    "copy",
+    "csv",
+    "ctypes",
+    "dataclasses",
+    "datetime",
+    "decimal",
+    "difflib",
+    "email",
+    "enum",
+    "errno",
+    "fnmatch",
+    "fractions",
+    "functools",
+    "gettext",
+    "glob",
+    "gzip",
+    "hashlib",
+    "heapq",
+    "hmac",
+    "html",
+    "http",
+    "importlib",
+    "inspect",
+    "io",
+    "itertools",
+    "json",
+    "locale",
+    "logging",
+    "math",
+    "mimetypes",
+    "operator",
+    "os",
+    "pathlib",
+    "pickle",
+    "platform",
+    "pprint",
+    "pyotherside",
+    "queue",
+    "random",
+    "re",
+    "secrets",
+    "select",
+    "shlex",
+    "shutil",
+    "signal",
+    "socket",
+    "sqlite3",
+    "ssl",
+    "stat",
+    "string",
+    "struct",
+    "subprocess",
+    "sys",
+    "tarfile",
+    "tempfile",
+    "textwrap",
+    "threading",
+    "time",
+    "traceback",
+    "types",
+    "typing",
+    "unicodedata",
+    "urllib",
+    "uuid",
+    "warnings",
+    "weakref",
+    "xml",
+    "zipfile",
+    "zlib",
+];
+
+/// Import names whose package is not `python3-<name>`.
+const PACKAGES: &[(&str, &str)] = &[
+    ("PIL", "python3-pillow"),
+    ("bs4", "python3-beautifulsoup4"),
+    ("dateutil", "python3-dateutil"),
+    ("gi", "python3-gobject"),
+    ("yaml", "python3-yaml"),
+];
+
+/// The project's Python module directory, if it has one.
+pub fn python_dir(dir: &Path) -> Option<PathBuf> {
+    PYTHON_DIRS
+        .iter()
+        .map(|sub| dir.join(sub))
+        .find(|path| path.is_dir() && !find_files(path, "py", 3).is_empty())
+}
+
+/// Top-level modules imported by the `.py` files under `python_dir` that are
+/// neither built in nor part of the project, sorted.
+pub fn third_party_imports(python_dir: &Path) -> Vec<String> {
+    let files = find_files(python_dir, "py", 3);
+    let local: BTreeSet<String> = files
+        .iter()
+        .filter_map(|file| {
+            let stem = file.file_stem()?.to_string_lossy();
+            if stem == "__init__" {
+                file.parent()?.file_name()
+            } else {
+                file.file_stem()
+            }
+            .map(|name| name.to_string_lossy().into_owned())
+        })
+        .collect();
+    let mut imports = BTreeSet::new();
+    for file in &files {
+        let Ok(text) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let modules: Vec<&str> = if let Some(rest) = line.strip_prefix("import ") {
+                rest.split(',')
+                    .filter_map(|item| item.split_whitespace().next())
+                    .collect()
+            } else if let Some(rest) = line.strip_prefix("from ") {
+                rest.split_whitespace().next().into_iter().collect()
+            } else {
+                continue;
+            };
+            for module in modules {
+                // Relative imports (`from . import x`) are always local.
+                let top = module.split('.').next().unwrap_or("");
+                if top.is_empty() || BUILTIN.contains(&top) || local.contains(top) {
+                    continue;
+                }
+                imports.insert(top.to_string());
+            }
+        }
+    }
+    imports.into_iter().collect()
+}
+
+/// The `python3-*` package that provides `module` on the device.
+pub fn package(module: &str) -> String {
+    PACKAGES
+        .iter()
+        .find(|(name, _)| *name == module)
+        .map(|(_, package)| package.to_string())
+        .unwrap_or_else(|| format!("python3-{}", module.to_ascii_lowercase().replace('_', "-")))
+}
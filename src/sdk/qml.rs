@@ -0,0 +1,193 @@
+//! `lint_qml`: `qmllint` over a project's QML files, with its output parsed
+//! into diagnostics.
+
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::lint::Level;
+use super::project::{ProjectInfo, find_files};
+use super::run_logged;
+use crate::config::expand_tilde;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct LintQmlParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// QML files relative to the project (default: every `.qml` file under
+    /// the project's QML directory).
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// Build target whose `qmllint` to use when there is none on the host
+    /// (default: the configured one).
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Extra import paths, added to the configured `qml_import_paths`.
+    #[serde(default)]
+    pub import_paths: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct QmlDiagnostic {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: Option<usize>,
+    pub level: Level,
+    pub message: String,
+    /// qmllint's warning category, e.g. `unqualified`.
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct QmlLintReport {
+    /// The `qmllint` command that ran.
+    pub command: String,
+    pub files_checked: usize,
+    /// qmllint succeeded and reported no errors.
+    pub passed: bool,
+    pub errors: usize,
+    pub warnings: usize,
+    pub diagnostics: Vec<QmlDiagnostic>,
+}
+
+/// `file.qml:line[:column]` at the start of `text`, and what follows it.
+fn location(text: &str) -> Option<(PathBuf, usize, Option<usize>, &str)> {
+    let end = text.find(".qml:")? + ".qml".len();
+    let file = PathBuf::from(text[..end].trim());
+    let mut rest = &text[end + 1..];
+    let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let line_len = digits(rest);
+    let line = rest[..line_len].parse().ok()?;
+    rest = &rest[line_len..];
+    let mut column = None;
+    if let Some(after) = rest.strip_prefix(':') {
+        let column_len = digits(after);
+        if column_len > 0 {
+            column = after[..column_len].parse().ok();
+            rest = &after[column_len..];
+        }
+    }
+    Some((file, line, column, rest))
+}
+
+/// One diagnostic from a line of qmllint output, in any of its formats:
+/// `Warning: file.qml:3:5: message [category]` (Qt 6),
+/// `Warning: message at file.qml:3:5` (Qt 5.15) and
+/// `file.qml:3 : message` (older syntax-only qmllint).
+fn parse_line(line: &str) -> Option<QmlDiagnostic> {
+    let line = line.trim();
+    let (level, rest) = if let Some(rest) = line.strip_prefix("Error:") {
+        (Level::Error, rest.trim_start())
+    } else if let Some(rest) = line.strip_prefix("Warning:") {
+        (Level::Warning, rest.trim_start())
+    } else if let Some(rest) = line.strip_prefix("Info:") {
+        (Level::Info, rest.trim_start())
+    } else {
+        (Level::Error, line)
+    };
+    let (file, line_number, column, message) = match location(rest) {
+        Some((file, line, column, message))
+            if !file.as_os_str().is_empty() && !file.to_string_lossy().contains(' ') =>
+        {
+            let message = message.trim_start_matches([' ', ':']).trim();
+            (file, line, column, message)
+        }
+        _ => {
+            let (message, at) = rest.rsplit_once(" at ")?;
+            let (file, line, column, _) = location(at)?;
+            (file, line, column, message.trim())
+        }
+    };
+    if message.is_empty() {
+        return None;
+    }
+    let (message, category) = match message.strip_suffix(']').and_then(|m| m.rsplit_once(" [")) {
+        Some((message, category)) => (message, Some(category.to_string())),
+        None => (message, None),
+    };
+    Some(QmlDiagnostic {
+        file,
+        line: line_number,
+        column,
+        level,
+        message: message.to_string(),
+        category,
+    })
+}
+
+fn relative(dir: &Path, file: &Path) -> String {
+    file.strip_prefix(dir).unwrap_or(file).display().to_string()
+}
+
+#[tool_router(router = qml_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Run qmllint over a project's QML files (on the host with the configured Aurora/Silica import paths, else in the Build Engine) and return diagnostics with file, line, column, severity and category.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn lint_qml(
+        &self,
+        Parameters(params): Parameters<LintQmlParams>,
+    ) -> Result<Json<QmlLintReport>> {
+        let options = &self.state().config.sdk;
+        let dir = options.project(params.project.as_deref())?;
+        let files: Vec<String> = if params.files.is_empty() {
+            let info = ProjectInfo::inspect(&dir);
+            let qml_dir = info.qml_dir.ok_or_else(|| {
+                Error::InvalidArgument(format!("no qml directory in {}", dir.display()))
+            })?;
+            find_files(&qml_dir, "qml", 8)
+                .iter()
+                .map(|file| relative(&dir, file))
+                .collect()
+        } else {
+            params.files
+        };
+        if let Some(missing) = files.iter().find(|file| !dir.join(file).is_file()) {
+            return Err(Error::InvalidArgument(format!(
+                "{} does not exist",
+                dir.join(missing).display()
+            )));
+        }
+
+        let (mut command, on_host) =
+            options.qt_tool(&["qmllint", "qmllint-qt5"], params.target.as_deref())?;
+        let configured = options
+            .qml_import_paths
+            .iter()
+            .filter(|_| on_host)
+            .map(|path| expand_tilde(path).display().to_string());
+        for path in configured.chain(params.import_paths) {
+            command.arg("-I").arg(path);
+        }
+        command.args(&files).current_dir(&dir);
+        let line = super::command_line(&command);
+
+        let log_file = super::log_file(self, &dir, "qmllint");
+        let run = run_logged(command, &log_file, options.build_timeout()).await?;
+        let diagnostics: Vec<QmlDiagnostic> = run.output.lines().filter_map(parse_line).collect();
+        let errors = diagnostics
+            .iter()
+            .filter(|d| d.level == Level::Error)
+            .count();
+        let warnings = diagnostics
+            .iter()
+            .filter(|d| d.level == Level::Warning)
+            .count();
+        Ok(Json(QmlLintReport {
+            command: line,
+            files_checked: files.len(),
+            passed: run.status == 0 && errors == 0,
+            errors,
+            warnings,
+            diagnostics,
+        }))
+    }
+}
@@ -0,0 +1,494 @@
+//! `qml_graph`: which QML and JavaScript files of a project use which —
+//! components instantiated, pages loaded by URL, scripts imported and C++
+//! types registered for QML — as a graph, optionally rendered as Mermaid.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::compat::CPP_EXTENSIONS;
+use super::mermaid::{self, Arrow, Shape};
+use super::project::{ProjectInfo, find_files};
+use super::silica::{QmlUsage, qml_files, scan_qml};
+use crate::error::Result;
+use crate::server::AuroraServer;
+
+/// Calls registering a C++ type for QML; the first two string arguments
+/// are the module URI and the QML name.
+const REGISTER_CALLS: &[&str] = &[
+    "qmlRegisterType",
+    "qmlRegisterSingletonType",
+    "qmlRegisterUncreatableType",
+    "qmlRegisterSingletonInstance",
+];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct QmlGraphParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Also return the graph as a Mermaid flowchart.
+    #[serde(default)]
+    pub mermaid: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Qml,
+    Js,
+    /// A C++ class registered with `qmlRegisterType` and friends.
+    Cpp,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Node {
+    /// Path relative to the project, or `Name (uri)` for C++ types.
+    pub id: String,
+    pub kind: NodeKind,
+    /// The C++ class behind a registered type.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+    /// Modules the file imports, e.g. `Sailfish.Silica`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub imports: Vec<String>,
+    /// Project files this one uses.
+    pub uses: usize,
+    /// Project files using this one.
+    pub used_by: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    /// `Component { }`.
+    Instantiates,
+    /// `Component.member`, e.g. a singleton's property or an enum.
+    References,
+    /// A `"Page.qml"` URL, e.g. `pageStack.push(Qt.resolvedUrl(...))` or a
+    /// `Loader` source.
+    Loads,
+    /// `import "script.js" as Script`.
+    ImportsScript,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+    /// Lines of `from` where the use appears.
+    pub lines: Vec<usize>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Unresolved {
+    pub file: String,
+    pub line: usize,
+    /// The import or URL that names no project file.
+    pub target: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct QmlGraph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    /// QML files no other project file uses: the app's main QML file and
+    /// covers loaded from C++, or dead code.
+    pub entry_points: Vec<String>,
+    /// Modules imported from outside the project, with how many files
+    /// import each.
+    pub modules: BTreeMap<String, usize>,
+    /// Quoted imports and `.qml` URLs pointing at files that do not exist.
+    pub unresolved: Vec<Unresolved>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mermaid: Option<String>,
+}
+
+/// `(line, contents)` of the string literals in QML or JavaScript source,
+/// outside `//` comments.
+pub(crate) fn string_literals(text: &str) -> Vec<(usize, String)> {
+    let mut literals = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '/' if chars.peek() == Some(&'/') => break,
+                '"' | '\'' => {
+                    let mut literal = String::new();
+                    let mut escaped = false;
+                    for s in chars.by_ref() {
+                        if !escaped && s == c {
+                            break;
+                        }
+                        escaped = !escaped && s == '\\';
+                        literal.push(s);
+                    }
+                    literals.push((number + 1, literal));
+                }
+                _ => {}
+            }
+        }
+    }
+    literals
+}
+
+/// `(line, path)` of `import "path"` in QML and `.import "path"` in
+/// JavaScript.
+fn quoted_imports(text: &str) -> Vec<(usize, String)> {
+    text.lines()
+        .enumerate()
+        .filter_map(|(number, line)| {
+            let rest = line.trim_start();
+            let rest = rest
+                .strip_prefix(".import")
+                .or_else(|| rest.strip_prefix("import"))?;
+            let rest = rest.trim_start().strip_prefix('"')?;
+            let (path, _) = rest.split_once('"')?;
+            Some((number + 1, path.to_string()))
+        })
+        .collect()
+}
+
+/// `(module URI, QML name, class)` of the C++ types a project registers.
+fn registered_types(dir: &Path) -> Vec<(String, String, String)> {
+    let mut types = Vec::new();
+    let files = CPP_EXTENSIONS
+        .iter()
+        .flat_map(|ext| find_files(dir, ext, 6));
+    for file in files {
+        let Ok(text) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        for call in REGISTER_CALLS {
+            for (start, _) in text.match_indices(&format!("{call}<")) {
+                let rest = &text[start + call.len() + 1..];
+                let Some((class, rest)) = rest.split_once('>') else {
+                    continue;
+                };
+                let arguments = rest.split(';').next().unwrap_or_default();
+                let strings: Vec<String> = string_literals(arguments)
+                    .into_iter()
+                    .map(|(_, s)| s)
+                    .collect();
+                if let [uri, name, ..] = strings.as_slice() {
+                    types.push((uri.clone(), name.clone(), class.trim().to_string()));
+                }
+            }
+        }
+    }
+    types.sort();
+    types.dedup();
+    types
+}
+
+/// Types a directory provides: its `Name.qml` files and the entries of its
+/// `qmldir`, with the module name the `qmldir` declares.
+fn directory_types(dir: &Path) -> (Option<String>, BTreeMap<String, PathBuf>) {
+    let mut types: BTreeMap<String, PathBuf> = find_files(dir, "qml", 0)
+        .into_iter()
+        .filter_map(|file| {
+            let stem = file.file_stem()?.to_str()?.to_string();
+            stem.starts_with(|c: char| c.is_ascii_uppercase())
+                .then_some((stem, file))
+        })
+        .collect();
+    let mut module = None;
+    if let Ok(qmldir) = std::fs::read_to_string(dir.join("qmldir")) {
+        for line in qmldir.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["module", name] => module = Some(name.to_string()),
+                ["singleton", name, .., file] | [name, _, file]
+                    if file.ends_with(".qml") && dir.join(file).is_file() =>
+                {
+                    types.insert(name.to_string(), dir.join(file));
+                }
+                _ => {}
+            }
+        }
+    }
+    (module, types)
+}
+
+/// Resolves a `.qml` or `.js` URL used in `file`: relative to the file,
+/// or to the QML directory for `qrc:/` and absolute forms.
+fn resolve_url(url: &str, file: &Path, qml_dir: &Path) -> Option<PathBuf> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    if path.contains("://") && !path.starts_with("qrc:") {
+        return None;
+    }
+    let local = path.trim_start_matches("qrc:").trim_start_matches('/');
+    let candidates = [
+        file.parent().map(|dir| dir.join(path)),
+        Some(qml_dir.join(local)),
+        Some(qml_dir.join(local.strip_prefix("qml/").unwrap_or(local))),
+        qml_dir.parent().map(|dir| dir.join(local)),
+    ];
+    candidates
+        .into_iter()
+        .flatten()
+        .find(|candidate| candidate.is_file())
+        .and_then(|candidate| candidate.canonicalize().ok())
+}
+
+fn mermaid(nodes: &[Node], edges: &[Edge]) -> String {
+    mermaid::flowchart(
+        nodes.iter().map(|node| {
+            let shape = match node.kind {
+                NodeKind::Qml => Shape::Rectangle,
+                NodeKind::Js => Shape::Stadium,
+                NodeKind::Cpp => Shape::Subroutine,
+            };
+            (node.id.as_str(), shape)
+        }),
+        edges.iter().map(|edge| {
+            let (dotted, label) = match edge.kind {
+                EdgeKind::Instantiates => (false, None),
+                EdgeKind::References => (true, None),
+                EdgeKind::Loads => (false, Some("loads")),
+                EdgeKind::ImportsScript => (true, Some("imports")),
+            };
+            (
+                edge.from.as_str(),
+                edge.to.as_str(),
+                Arrow { dotted, label },
+            )
+        }),
+        [],
+    )
+}
+
+/// The graph of the project in `dir`, without the Mermaid rendering.
+pub(crate) fn build(dir: &Path) -> Result<QmlGraph> {
+    let root = dir.canonicalize()?;
+    let relative = |file: &Path| {
+        file.strip_prefix(&root)
+            .unwrap_or(file)
+            .display()
+            .to_string()
+    };
+    let qml = qml_files(dir)?;
+    let qml_dir = ProjectInfo::inspect(dir)
+        .qml_dir
+        .unwrap_or_else(|| dir.to_path_buf());
+    let mut files: Vec<PathBuf> = qml
+        .into_iter()
+        .chain(find_files(&qml_dir, "js", 8))
+        .collect();
+    files.retain_mut(|file| match file.canonicalize() {
+        Ok(path) => {
+            *file = path;
+            true
+        }
+        Err(_) => false,
+    });
+
+    let registered = registered_types(dir);
+    let mut directories: BTreeMap<PathBuf, BTreeMap<String, PathBuf>> = BTreeMap::new();
+    let mut modules_by_name: BTreeMap<String, PathBuf> = BTreeMap::new();
+    let mut module_dirs: Vec<PathBuf> = find_files(dir, "qml", 8)
+        .iter()
+        .filter_map(|file| file.parent()?.canonicalize().ok())
+        .collect();
+    module_dirs.sort();
+    module_dirs.dedup();
+    for module_dir in module_dirs {
+        let (module, types) = directory_types(&module_dir);
+        if let Some(module) = module {
+            modules_by_name.insert(module, module_dir.clone());
+        }
+        directories.insert(module_dir, types);
+    }
+
+    let mut nodes: BTreeMap<String, Node> = BTreeMap::new();
+    let mut edges: BTreeMap<(String, String, EdgeKind), Vec<usize>> = BTreeMap::new();
+    let mut modules: BTreeMap<String, usize> = BTreeMap::new();
+    let mut unresolved = Vec::new();
+    for file in &files {
+        let Ok(text) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        let id = relative(file);
+        let is_qml = file.extension().is_some_and(|ext| ext == "qml");
+        let file_dir = file.parent().unwrap_or(&root).to_path_buf();
+
+        // Directories whose types are visible in this file.
+        let mut scopes = vec![file_dir.clone()];
+        let mut imported_uris = BTreeSet::new();
+        let quoted = quoted_imports(&text);
+        for (line, target) in quoted.iter().cloned() {
+            match resolve_url(&target, file, &qml_dir) {
+                Some(script) if !script.is_dir() && target.ends_with(".js") => {
+                    edges
+                        .entry((id.clone(), relative(&script), EdgeKind::ImportsScript))
+                        .or_default()
+                        .push(line);
+                }
+                _ => match file_dir.join(&target).canonicalize() {
+                    Ok(scope) if scope.is_dir() => {
+                        directories
+                            .entry(scope.clone())
+                            .or_insert_with(|| directory_types(&scope).1);
+                        scopes.push(scope);
+                    }
+                    _ => unresolved.push(Unresolved {
+                        file: id.clone(),
+                        line,
+                        target,
+                    }),
+                },
+            }
+        }
+        let usages = if is_qml { scan_qml(&text) } else { Vec::new() };
+        let mut imports = Vec::new();
+        for (_, usage) in &usages {
+            if let QmlUsage::Import { module } = usage {
+                if let Some(scope) = modules_by_name.get(module) {
+                    scopes.push(scope.clone());
+                } else if registered.iter().any(|(uri, ..)| uri == module) {
+                    imported_uris.insert(module.clone());
+                } else {
+                    *modules.entry(module.clone()).or_default() += 1;
+                }
+                imports.push(module.clone());
+            }
+        }
+
+        for (line, usage) in usages {
+            let (name, kind) = match usage {
+                QmlUsage::Component { name } => (name, EdgeKind::Instantiates),
+                QmlUsage::Member { object, .. } => (object, EdgeKind::References),
+                _ => continue,
+            };
+            let local = scopes
+                .iter()
+                .find_map(|scope| directories.get(scope)?.get(&name))
+                .map(|target| relative(target));
+            let target = local.or_else(|| {
+                let (uri, name, class) = registered.iter().find(|(uri, type_name, _)| {
+                    *type_name == name && imported_uris.contains(uri)
+                })?;
+                let target = format!("{name} ({uri})");
+                nodes.entry(target.clone()).or_insert_with(|| Node {
+                    id: target.clone(),
+                    kind: NodeKind::Cpp,
+                    class: Some(class.clone()),
+                    imports: Vec::new(),
+                    uses: 0,
+                    used_by: 0,
+                });
+                Some(target)
+            });
+            if let Some(target) = target.filter(|target| *target != id) {
+                edges
+                    .entry((id.clone(), target, kind))
+                    .or_default()
+                    .push(line);
+            }
+        }
+
+        for (line, url) in string_literals(&text) {
+            if !url.ends_with(".qml") || quoted.iter().any(|(_, t)| *t == url) {
+                continue;
+            }
+            match resolve_url(&url, file, &qml_dir) {
+                Some(target) => edges
+                    .entry((id.clone(), relative(&target), EdgeKind::Loads))
+                    .or_default()
+                    .push(line),
+                None => unresolved.push(Unresolved {
+                    file: id.clone(),
+                    line,
+                    target: url,
+                }),
+            }
+        }
+
+        imports.sort();
+        imports.dedup();
+        nodes.insert(
+            id.clone(),
+            Node {
+                id,
+                kind: if is_qml { NodeKind::Qml } else { NodeKind::Js },
+                class: None,
+                imports,
+                uses: 0,
+                used_by: 0,
+            },
+        );
+    }
+
+    // Edges to files outside the scanned set, e.g. QML next to the
+    // project's QML directory, still get a node.
+    let edges: Vec<Edge> = edges
+        .into_iter()
+        .map(|((from, to, kind), mut lines)| {
+            lines.sort_unstable();
+            lines.dedup();
+            Edge {
+                from,
+                to,
+                kind,
+                lines,
+            }
+        })
+        .collect();
+    for edge in &edges {
+        let target = nodes.entry(edge.to.clone()).or_insert_with(|| Node {
+            id: edge.to.clone(),
+            kind: if edge.to.ends_with(".js") {
+                NodeKind::Js
+            } else {
+                NodeKind::Qml
+            },
+            class: None,
+            imports: Vec::new(),
+            uses: 0,
+            used_by: 0,
+        });
+        target.used_by += 1;
+        if let Some(source) = nodes.get_mut(&edge.from) {
+            source.uses += 1;
+        }
+    }
+    let nodes: Vec<Node> = nodes.into_values().collect();
+    let entry_points = nodes
+        .iter()
+        .filter(|node| node.kind == NodeKind::Qml && node.used_by == 0)
+        .map(|node| node.id.clone())
+        .collect();
+    Ok(QmlGraph {
+        nodes,
+        edges,
+        entry_points,
+        modules,
+        unresolved,
+        mermaid: None,
+    })
+}
+
+#[tool_router(router = qmlgraph_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Build the graph of a project's QML and JavaScript files: which components each file instantiates or references (resolved through its directory, quoted directory imports, qmldir modules and C++ types registered with qmlRegisterType), pages loaded by URL, scripts imported, and the external modules imported. Returns nodes, edges, entry points and unresolved imports, plus a Mermaid flowchart with mermaid=true.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn qml_graph(
+        &self,
+        Parameters(params): Parameters<QmlGraphParams>,
+    ) -> Result<Json<QmlGraph>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        let mut graph = build(&dir)?;
+        if params.mermaid {
+            graph.mermaid = Some(mermaid(&graph.nodes, &graph.edges));
+        }
+        Ok(Json(graph))
+    }
+}
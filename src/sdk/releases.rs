@@ -0,0 +1,366 @@
+//! Aurora OS release notes as `aurora-release://<version>` resources, and
+//! `api_diff`: the platform APIs added, removed and changed between two
+//! releases, from the database `check_compatibility` uses.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::compat::{ApiEntry, ApiKind, Database, Release, load_database, usages};
+use super::project::ProjectInfo;
+use super::silica::OsVersion;
+use crate::config::{Config, expand_tilde};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+pub const URI_SCHEME: &str = "aurora-release://";
+
+pub fn uri(version: &str) -> String {
+    format!("{URI_SCHEME}{version}")
+}
+
+/// Release of an `aurora-release://` URI; empty for the index.
+pub fn parse_uri(uri: &str) -> Option<&str> {
+    uri.strip_prefix(URI_SCHEME)
+        .map(|version| version.trim_matches('/'))
+}
+
+fn database(config: &Config) -> Result<Database> {
+    let extra = config.sdk.compat_database.as_deref().map(expand_tilde);
+    load_database(extra.as_deref())
+}
+
+/// Known releases, oldest first.
+pub fn releases(config: &Config) -> Result<Vec<Release>> {
+    database(config).map(|database| database.release)
+}
+
+/// Hash of what `read` renders from: the path, size and modification time
+/// of the configured `compat_database`. The built-in database never
+/// changes.
+pub fn revision(config: &Config) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    if let Some(path) = config.sdk.compat_database.as_deref().map(expand_tilde) {
+        path.hash(&mut hasher);
+        if let Ok(meta) = std::fs::metadata(&path) {
+            meta.len().hash(&mut hasher);
+            meta.modified().ok().hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// Markdown release notes of `version`, with the API changes since the
+/// release before it, or the index of releases when `version` is empty.
+/// `None` for an unknown release.
+pub fn read(config: &Config, version: &str) -> Result<Option<String>> {
+    let database = database(config)?;
+    if version.is_empty() {
+        let mut text =
+            "# Aurora OS releases\n\n| Release | Qt | Silica |\n|---|---|---|\n".to_string();
+        for release in database.release.iter().rev() {
+            text.push_str(&format!(
+                "| [{}]({}) | {} | {} |\n",
+                release.version,
+                uri(&release.version),
+                release.qt,
+                release.silica
+            ));
+        }
+        return Ok(Some(text));
+    }
+    let Some(index) = database
+        .release
+        .iter()
+        .position(|release| release.version == version)
+    else {
+        return Ok(None);
+    };
+    let release = &database.release[index];
+    let mut text = format!(
+        "# Aurora OS {}\n\nBuild targets ship Qt {} and Silica {}.\n",
+        release.version, release.qt, release.silica
+    );
+    if let Some(notes) = &release.notes {
+        text.push('\n');
+        text.push_str(notes.trim());
+        text.push('\n');
+    }
+    if let Some(previous) = index.checked_sub(1).map(|index| &database.release[index]) {
+        let diff = diff(
+            &database,
+            &OsVersion::parse(&previous.version)?,
+            &OsVersion::parse(&release.version)?,
+            None,
+        );
+        text.push_str(&format!("\n## API changes since {}\n", previous.version));
+        if diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty() {
+            text.push_str("\nNone recorded.\n");
+        }
+        for (heading, entries) in [
+            ("Added", &diff.added),
+            ("Removed", &diff.removed),
+            ("Changed", &diff.changed),
+        ] {
+            if entries.is_empty() {
+                continue;
+            }
+            text.push_str(&format!("\n### {heading}\n\n"));
+            for entry in entries {
+                text.push_str(&format!("- `{}` ({})", entry.api, kind_name(entry.kind)));
+                if let Some(note) = &entry.note {
+                    text.push_str(&format!(": {note}"));
+                }
+                text.push('\n');
+            }
+        }
+    }
+    Ok(Some(text))
+}
+
+fn kind_name(kind: ApiKind) -> &'static str {
+    match kind {
+        ApiKind::QmlModule => "QML module",
+        ApiKind::Header => "header",
+        ApiKind::Pkgconfig => "pkg-config module",
+        ApiKind::DbusInterface => "D-Bus interface",
+        ApiKind::QtModule => "Qt module",
+    }
+}
+
+/// APIs added, removed and changed from release `low` up to `high`.
+struct Diff {
+    added: Vec<ApiDiffEntry>,
+    removed: Vec<ApiDiffEntry>,
+    changed: Vec<ApiDiffEntry>,
+}
+
+fn diff(database: &Database, low: &OsVersion, high: &OsVersion, kind: Option<ApiKind>) -> Diff {
+    let in_range = |version: &str| {
+        OsVersion::parse(version).is_ok_and(|version| version > *low && version <= *high)
+    };
+    let mut diff = Diff {
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+    };
+    for entry in &database.api {
+        if kind.is_some_and(|kind| kind != entry.kind) {
+            continue;
+        }
+        let (before, after) = (entry.available_in(low), entry.available_in(high));
+        let item = |version: &str, note: Option<&String>| ApiDiffEntry {
+            kind: entry.kind,
+            api: entry.name.clone(),
+            version: version.to_string(),
+            note: note.cloned(),
+            uses: Vec::new(),
+        };
+        if !before && after {
+            diff.added.push(item(&entry.since, entry.note.as_ref()));
+        } else if before && !after {
+            let removed = entry.removed.as_deref().unwrap_or(&entry.since);
+            diff.removed.push(item(removed, entry.note.as_ref()));
+        } else if before && after {
+            for change in entry
+                .changes
+                .iter()
+                .filter(|change| in_range(&change.version))
+            {
+                diff.changed.push(item(&change.version, Some(&change.note)));
+            }
+        }
+    }
+    for list in [&mut diff.added, &mut diff.removed, &mut diff.changed] {
+        list.sort_by_cached_key(|entry| (OsVersion::parse(&entry.version).ok(), entry.api.clone()));
+    }
+    diff
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ApiDiffParams {
+    /// Release to compare from, e.g. the current minimum `4.0`.
+    pub from_os_version: String,
+    /// Release to compare to, e.g. a proposed minimum `5.0` (default: the
+    /// newest known release). Older than `from_os_version` lists what going
+    /// back would lose.
+    #[serde(default)]
+    pub to_os_version: Option<String>,
+    /// Only APIs of this kind.
+    #[serde(default)]
+    pub kind: Option<ApiKind>,
+    /// Also find where the project uses the removed and changed APIs.
+    #[serde(default)]
+    pub check_project: bool,
+    /// Project directory for `check_project` (default: the `[sdk]` project
+    /// from the config, else the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ApiUse {
+    /// Relative to the project.
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ApiDiffEntry {
+    pub kind: ApiKind,
+    pub api: String,
+    /// Release the API was added, removed or changed in.
+    pub version: String,
+    pub note: Option<String>,
+    /// Where the project uses it, with `check_project`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub uses: Vec<ApiUse>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VersionChange {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ApiDiffReport {
+    pub from_os_version: String,
+    pub to_os_version: String,
+    /// Known releases between the two, whose notes are
+    /// `aurora-release://<version>` resources.
+    pub releases: Vec<String>,
+    /// Qt of the build targets, when it differs.
+    pub qt: Option<VersionChange>,
+    /// Silica of the build targets, when it differs.
+    pub silica: Option<VersionChange>,
+    /// Available in `to_os_version` but not `from_os_version`.
+    pub added: Vec<ApiDiffEntry>,
+    /// Available in `from_os_version` but not `to_os_version`.
+    pub removed: Vec<ApiDiffEntry>,
+    /// Available in both, with behaviour changes in between.
+    pub changed: Vec<ApiDiffEntry>,
+    /// With `check_project`: source files read.
+    pub files_checked: Option<usize>,
+    /// With `check_project`: whether the project uses a removed or changed
+    /// API.
+    pub affects_project: Option<bool>,
+}
+
+#[tool_router(router = releases_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "List the platform APIs (QML modules, C++ headers, pkg-config and Qt modules, D-Bus interfaces) added, removed and changed between two Aurora OS releases, with the Qt and Silica versions of each, to answer what raising or lowering the minimum OS version means. With check_project, also shows where the project uses the removed and changed APIs. Release notes are aurora-release:// resources.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn api_diff(
+        &self,
+        Parameters(params): Parameters<ApiDiffParams>,
+    ) -> Result<Json<ApiDiffReport>> {
+        let config = &self.state().config;
+        let database = database(config)?;
+        let to_os_version = match params.to_os_version {
+            Some(version) => version,
+            None => database
+                .release
+                .last()
+                .map(|release| release.version.clone())
+                .ok_or_else(|| {
+                    Error::InvalidArgument(
+                        "pass to_os_version; the database lists no releases".to_string(),
+                    )
+                })?,
+        };
+        let from = OsVersion::parse(&params.from_os_version)?;
+        let to = OsVersion::parse(&to_os_version)?;
+        let (low, high) = if from <= to {
+            (&from, &to)
+        } else {
+            (&to, &from)
+        };
+        let mut diff = diff(&database, low, high, params.kind);
+        if from > to {
+            std::mem::swap(&mut diff.added, &mut diff.removed);
+        }
+
+        let release_at = |version: &OsVersion| {
+            database
+                .release
+                .iter()
+                .rfind(|release| OsVersion::parse(&release.version).is_ok_and(|v| v <= *version))
+        };
+        let change = |field: fn(&Release) -> &String| {
+            let (from, to) = (release_at(&from)?, release_at(&to)?);
+            (field(from) != field(to)).then(|| VersionChange {
+                from: field(from).clone(),
+                to: field(to).clone(),
+            })
+        };
+        let qt = change(|release| &release.qt);
+        let silica = change(|release| &release.silica);
+        let releases = database
+            .release
+            .iter()
+            .filter(|release| {
+                OsVersion::parse(&release.version).is_ok_and(|v| v > *low && v <= *high)
+            })
+            .map(|release| release.version.clone())
+            .collect();
+
+        let mut files_checked = None;
+        let mut affects_project = None;
+        if params.check_project {
+            let dir = config.sdk.project(params.project.as_deref())?;
+            let info = ProjectInfo::inspect(&dir);
+            let entries: Vec<&ApiEntry> = database
+                .api
+                .iter()
+                .filter(|entry| {
+                    diff.removed
+                        .iter()
+                        .chain(&diff.changed)
+                        .any(|item| item.kind == entry.kind && item.api == entry.name)
+                })
+                .collect();
+            let (found, checked) = usages(&dir, &info, &entries)?;
+            for usage in found {
+                for item in diff
+                    .removed
+                    .iter_mut()
+                    .chain(&mut diff.changed)
+                    .filter(|item| item.kind == usage.entry.kind && item.api == usage.entry.name)
+                {
+                    item.uses.push(ApiUse {
+                        file: usage.file.clone(),
+                        line: usage.line,
+                    });
+                }
+            }
+            files_checked = Some(checked);
+            affects_project = Some(
+                diff.removed
+                    .iter()
+                    .chain(&diff.changed)
+                    .any(|item| !item.uses.is_empty()),
+            );
+        }
+
+        Ok(Json(ApiDiffReport {
+            from_os_version: params.from_os_version,
+            to_os_version,
+            releases,
+            qt,
+            silica,
+            added: diff.added,
+            removed: diff.removed,
+            changed: diff.changed,
+            files_checked,
+            affects_project,
+        }))
+    }
+}
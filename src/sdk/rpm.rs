@@ -0,0 +1,422 @@
+//! Reading RPM package headers without the `rpm` tool: the metadata,
+//! dependency and file lists of a package, as tag values.
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+const LEAD_SIZE: usize = 96;
+const LEAD_MAGIC: [u8; 4] = [0xed, 0xab, 0xee, 0xdb];
+const HEADER_MAGIC: [u8; 3] = [0x8e, 0xad, 0xe8];
+/// Headers larger than this are treated as corrupt.
+const MAX_HEADER_SIZE: usize = 256 << 20;
+
+pub const NAME: u32 = 1000;
+pub const VERSION: u32 = 1001;
+pub const RELEASE: u32 = 1002;
+pub const EPOCH: u32 = 1003;
+pub const SIZE: u32 = 1009;
+pub const LICENSE: u32 = 1014;
+pub const ARCH: u32 = 1022;
+pub const PREIN: u32 = 1023;
+pub const POSTIN: u32 = 1024;
+pub const PREUN: u32 = 1025;
+pub const POSTUN: u32 = 1026;
+pub const FILESIZES: u32 = 1028;
+pub const FILEMODES: u32 = 1030;
+pub const FILEDIGESTS: u32 = 1035;
+pub const FILELINKTOS: u32 = 1036;
+pub const FILEFLAGS: u32 = 1037;
+pub const FILEUSERNAME: u32 = 1039;
+pub const FILEGROUPNAME: u32 = 1040;
+pub const PROVIDENAME: u32 = 1047;
+pub const REQUIREFLAGS: u32 = 1048;
+pub const REQUIRENAME: u32 = 1049;
+pub const REQUIREVERSION: u32 = 1050;
+pub const CONFLICTFLAGS: u32 = 1053;
+pub const CONFLICTNAME: u32 = 1054;
+pub const CONFLICTVERSION: u32 = 1055;
+pub const OBSOLETENAME: u32 = 1090;
+pub const PROVIDEFLAGS: u32 = 1112;
+pub const PROVIDEVERSION: u32 = 1113;
+pub const OBSOLETEFLAGS: u32 = 1114;
+pub const OBSOLETEVERSION: u32 = 1115;
+pub const DIRINDEXES: u32 = 1116;
+pub const BASENAMES: u32 = 1117;
+pub const DIRNAMES: u32 = 1118;
+pub const LONGFILESIZES: u32 = 5008;
+pub const LONGSIZE: u32 = 5009;
+pub const FILECAPS: u32 = 5010;
+
+/// File flags.
+pub const FILE_CONFIG: u32 = 1 << 0;
+pub const FILE_NOREPLACE: u32 = 1 << 4;
+
+/// Dependency comparison flags.
+const SENSE_LESS: u64 = 0x02;
+const SENSE_GREATER: u64 = 0x04;
+const SENSE_EQUAL: u64 = 0x08;
+
+/// A tag's value; binary tags (signatures, digests of the header) are
+/// skipped.
+#[derive(Debug, Clone)]
+enum Value {
+    Ints(Vec<u64>),
+    Strings(Vec<String>),
+}
+
+/// The main header of a package.
+#[derive(Debug, Default)]
+pub struct Header {
+    tags: BTreeMap<u32, Value>,
+}
+
+/// A packaged file.
+#[derive(Debug, Clone)]
+pub struct FileInfo {
+    pub path: String,
+    pub size: u64,
+    pub mode: u32,
+    pub user: String,
+    pub group: String,
+    /// Hex digest of the contents; empty for directories and links.
+    pub digest: String,
+    /// Target of a symlink.
+    pub link: String,
+    /// `getcap`-style capabilities.
+    pub caps: String,
+    /// `FILE_*` flags, e.g. `%config(noreplace)`.
+    pub flags: u32,
+}
+
+/// Reads one header structure (the signature or the main header) and
+/// returns it with its size in bytes.
+fn read_header(reader: &mut impl Read) -> Result<(Header, usize)> {
+    let mut intro = [0u8; 16];
+    reader.read_exact(&mut intro)?;
+    if intro[..3] != HEADER_MAGIC {
+        return Err(Error::InvalidArgument("bad RPM header magic".to_string()));
+    }
+    let count = u32::from_be_bytes(intro[8..12].try_into().unwrap()) as usize;
+    let data_size = u32::from_be_bytes(intro[12..16].try_into().unwrap()) as usize;
+    if count * 16 + data_size > MAX_HEADER_SIZE {
+        return Err(Error::InvalidArgument(
+            "RPM header is too large".to_string(),
+        ));
+    }
+    let mut index = vec![0u8; count * 16];
+    reader.read_exact(&mut index)?;
+    let mut data = vec![0u8; data_size];
+    reader.read_exact(&mut data)?;
+
+    let mut header = Header::default();
+    for entry in index.chunks_exact(16) {
+        let word = |i: usize| u32::from_be_bytes(entry[i * 4..i * 4 + 4].try_into().unwrap());
+        let (tag, kind, offset, count) = (word(0), word(1), word(2) as usize, word(3) as usize);
+        let Some(bytes) = data.get(offset..) else {
+            continue;
+        };
+        let ints = |width: usize| -> Option<Value> {
+            let bytes = bytes.get(..width * count)?;
+            Some(Value::Ints(
+                bytes
+                    .chunks_exact(width)
+                    .map(|chunk| chunk.iter().fold(0u64, |n, b| n << 8 | *b as u64))
+                    .collect(),
+            ))
+        };
+        let value = match kind {
+            1 | 2 => ints(1),
+            3 => ints(2),
+            4 => ints(4),
+            5 => ints(8),
+            6 | 8 | 9 => {
+                let count = if kind == 6 { 1 } else { count };
+                let strings: Vec<String> = bytes
+                    .split(|b| *b == 0)
+                    .take(count)
+                    .map(|s| String::from_utf8_lossy(s).into_owned())
+                    .collect();
+                Some(Value::Strings(strings))
+            }
+            _ => None,
+        };
+        if let Some(value) = value {
+            header.tags.insert(tag, value);
+        }
+    }
+    Ok((header, 16 + count * 16 + data_size))
+}
+
+impl Header {
+    /// Reads the main header of the package at `path`.
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .map_err(|err| Error::InvalidArgument(format!("{}: {err}", path.display())))?;
+        let mut reader = std::io::BufReader::new(file);
+        let not_rpm = |_| Error::InvalidArgument(format!("{} is not an RPM", path.display()));
+        let mut lead = [0u8; LEAD_SIZE];
+        reader.read_exact(&mut lead).map_err(not_rpm)?;
+        if lead[..4] != LEAD_MAGIC {
+            return Err(not_rpm(std::io::ErrorKind::InvalidData.into()));
+        }
+        let (_, signature_size) = read_header(&mut reader)?;
+        // The signature is padded to a multiple of 8 bytes.
+        let padding = (8 - signature_size % 8) % 8;
+        reader.read_exact(&mut vec![0u8; padding])?;
+        let (header, _) = read_header(&mut reader)?;
+        Ok(header)
+    }
+
+    pub fn string(&self, tag: u32) -> Option<&str> {
+        match self.tags.get(&tag)? {
+            Value::Strings(strings) => strings.first().map(String::as_str),
+            _ => None,
+        }
+    }
+
+    pub fn strings(&self, tag: u32) -> &[String] {
+        match self.tags.get(&tag) {
+            Some(Value::Strings(strings)) => strings,
+            _ => &[],
+        }
+    }
+
+    pub fn ints(&self, tag: u32) -> &[u64] {
+        match self.tags.get(&tag) {
+            Some(Value::Ints(ints)) => ints,
+            _ => &[],
+        }
+    }
+
+    pub fn int(&self, tag: u32) -> Option<u64> {
+        self.ints(tag).first().copied()
+    }
+
+    /// `name`, `version-release` and arch.
+    pub fn nvra(&self) -> (String, String, String) {
+        (
+            self.string(NAME).unwrap_or_default().to_string(),
+            format!(
+                "{}-{}",
+                self.string(VERSION).unwrap_or_default(),
+                self.string(RELEASE).unwrap_or_default()
+            ),
+            self.string(ARCH).unwrap_or_default().to_string(),
+        )
+    }
+
+    /// Installed size in bytes.
+    pub fn installed_size(&self) -> u64 {
+        self.int(LONGSIZE)
+            .or_else(|| self.int(SIZE))
+            .unwrap_or_default()
+    }
+
+    /// Dependencies of one kind as `name [op version]`, e.g. `REQUIRENAME`
+    /// with its flags and versions.
+    pub fn dependencies(&self, names: u32, flags: u32, versions: u32) -> Vec<String> {
+        let (flags, versions) = (self.ints(flags), self.strings(versions));
+        self.strings(names)
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let version = versions.get(i).map(String::as_str).unwrap_or("");
+                let flag = flags.get(i).copied().unwrap_or_default();
+                let op = match (
+                    flag & SENSE_LESS != 0,
+                    flag & SENSE_GREATER != 0,
+                    flag & SENSE_EQUAL != 0,
+                ) {
+                    (true, false, true) => "<=",
+                    (true, false, false) => "<",
+                    (false, true, true) => ">=",
+                    (false, true, false) => ">",
+                    (false, false, true) => "=",
+                    _ => "",
+                };
+                if version.is_empty() || op.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{name} {op} {version}")
+                }
+            })
+            .collect()
+    }
+
+    /// Scriptlet bodies by section name, e.g. `%post`.
+    pub fn scriptlets(&self) -> BTreeMap<&'static str, &str> {
+        [
+            ("%pre", PREIN),
+            ("%post", POSTIN),
+            ("%preun", PREUN),
+            ("%postun", POSTUN),
+        ]
+        .into_iter()
+        .filter_map(|(name, tag)| Some((name, self.string(tag)?)))
+        .collect()
+    }
+
+    pub fn files(&self) -> Vec<FileInfo> {
+        let (dirs, indexes) = (self.strings(DIRNAMES), self.ints(DIRINDEXES));
+        let sizes = match self.ints(LONGFILESIZES) {
+            [] => self.ints(FILESIZES),
+            sizes => sizes,
+        };
+        let at = |strings: &[String], i: usize| strings.get(i).cloned().unwrap_or_default();
+        self.strings(BASENAMES)
+            .iter()
+            .enumerate()
+            .map(|(i, base)| {
+                let dir = indexes
+                    .get(i)
+                    .and_then(|d| dirs.get(*d as usize))
+                    .map(String::as_str)
+                    .unwrap_or("");
+                FileInfo {
+                    path: format!("{dir}{base}"),
+                    size: sizes.get(i).copied().unwrap_or_default(),
+                    mode: self.ints(FILEMODES).get(i).copied().unwrap_or_default() as u32,
+                    user: at(self.strings(FILEUSERNAME), i),
+                    group: at(self.strings(FILEGROUPNAME), i),
+                    digest: at(self.strings(FILEDIGESTS), i),
+                    link: at(self.strings(FILELINKTOS), i),
+                    caps: at(self.strings(FILECAPS), i),
+                    flags: self.ints(FILEFLAGS).get(i).copied().unwrap_or_default() as u32,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Name, `version-release` and arch from a `name-version-release.arch.rpm`
+/// file name or a path ending in one.
+pub(crate) fn parse_rpm_name(file: &str) -> Option<(String, String, String)> {
+    let file = file.rsplit('/').next().unwrap_or(file);
+    let (rest, arch) = file.strip_suffix(".rpm")?.rsplit_once('.')?;
+    let (rest, release) = rest.rsplit_once('-')?;
+    let (name, version) = rest.rsplit_once('-')?;
+    if name.is_empty() {
+        return None;
+    }
+    Some((
+        name.to_string(),
+        format!("{version}-{release}"),
+        arch.to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A header structure with `entries` of (tag, type, count, data).
+    fn header(entries: &[(u32, u32, u32, &[u8])]) -> Vec<u8> {
+        let mut index = Vec::new();
+        let mut data = Vec::new();
+        for &(tag, kind, count, bytes) in entries {
+            // Integers are aligned to their width.
+            let align = match kind {
+                3 => 2,
+                4 => 4,
+                5 => 8,
+                _ => 1,
+            };
+            data.resize(data.len().next_multiple_of(align), 0);
+            for word in [tag, kind, data.len() as u32, count] {
+                index.extend_from_slice(&word.to_be_bytes());
+            }
+            data.extend_from_slice(bytes);
+        }
+        let mut out = vec![0x8e, 0xad, 0xe8, 0x01, 0, 0, 0, 0];
+        out.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        out.extend(index);
+        out.extend(data);
+        out
+    }
+
+    fn fixture() -> Vec<u8> {
+        header(&[
+            (NAME, 6, 1, b"demo\0"),
+            (VERSION, 6, 1, b"1.2\0"),
+            (RELEASE, 6, 1, b"3\0"),
+            (ARCH, 6, 1, b"aarch64\0"),
+            (SIZE, 4, 1, &4096u32.to_be_bytes()),
+            (REQUIRENAME, 8, 2, b"libc.so.6\0qt5-qtcore\0"),
+            (REQUIREFLAGS, 4, 2, &[0, 0, 0, 0, 0, 0, 0, 0x0c]),
+            (REQUIREVERSION, 8, 2, b"\x005.6\0"),
+            (FILEMODES, 3, 2, &[0x81, 0xa4, 0x41, 0xed]),
+        ])
+    }
+
+    #[test]
+    fn reads_a_header() {
+        let bytes = fixture();
+        let (header, size) = read_header(&mut bytes.as_slice()).unwrap();
+        assert_eq!(size, bytes.len());
+        assert_eq!(
+            header.nvra(),
+            (
+                "demo".to_string(),
+                "1.2-3".to_string(),
+                "aarch64".to_string()
+            )
+        );
+        assert_eq!(header.int(SIZE), Some(4096));
+        assert_eq!(header.strings(REQUIRENAME), ["libc.so.6", "qt5-qtcore"]);
+        assert_eq!(header.ints(FILEMODES), [0o100644, 0o40755]);
+        assert_eq!(
+            header.dependencies(REQUIRENAME, REQUIREFLAGS, REQUIREVERSION),
+            ["libc.so.6", "qt5-qtcore >= 5.6"]
+        );
+    }
+
+    #[test]
+    fn reads_a_package() {
+        let mut package = vec![0u8; LEAD_SIZE];
+        package[..4].copy_from_slice(&LEAD_MAGIC);
+        let signature = header(&[(1000, 7, 3, b"sig")]);
+        let padding = (8 - signature.len() % 8) % 8;
+        package.extend(signature);
+        package.extend(vec![0; padding]);
+        package.extend(fixture());
+        let path = std::env::temp_dir().join(format!("aurora-mcp-rpm-{}.rpm", std::process::id()));
+        std::fs::write(&path, package).unwrap();
+        let header = Header::read(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(header.unwrap().string(NAME), Some("demo"));
+    }
+
+    #[test]
+    fn rejects_bad_and_truncated_headers() {
+        let bytes = fixture();
+        let mut bad = bytes.clone();
+        bad[0] = 0;
+        assert!(read_header(&mut bad.as_slice()).is_err());
+        for len in [0, 10, 16, 40, bytes.len() - 1] {
+            assert!(read_header(&mut &bytes[..len]).is_err(), "read {len} bytes");
+        }
+    }
+
+    #[test]
+    fn parses_rpm_file_names() {
+        assert_eq!(
+            parse_rpm_name("/tmp/ru.example.app-1.2.0-1.aarch64.rpm"),
+            Some((
+                "ru.example.app".to_string(),
+                "1.2.0-1".to_string(),
+                "aarch64".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_rpm_name("harbour-foo-bar-debuginfo-0.1-3.armv7hl.rpm").map(|(name, ..)| name),
+            Some("harbour-foo-bar-debuginfo".to_string())
+        );
+        assert_eq!(parse_rpm_name("app-1.0-1.noarch.tar"), None);
+        assert_eq!(parse_rpm_name("app.noarch.rpm"), None);
+        assert_eq!(parse_rpm_name("-1.0-1.noarch.rpm"), None);
+    }
+}
@@ -0,0 +1,463 @@
+//! `diff_rpms`: what changed between two builds of a package — files,
+//! their permissions, dependencies, scriptlets and size — read from the
+//! RPM headers, for release reviews.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::rpm::{self, FileInfo, Header};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+const DEFAULT_MAX_FILES: usize = 500;
+
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFLNK: u32 = 0o120000;
+const S_ISUID: u32 = 0o4000;
+const S_ISGID: u32 = 0o2000;
+const S_IWOTH: u32 = 0o0002;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DiffRpmsParams {
+    /// The previous release's RPM.
+    pub old: PathBuf,
+    /// The new build's RPM.
+    pub new: PathBuf,
+    /// Entries listed per file section (default 500); counts stay exact.
+    #[serde(default)]
+    pub max_files: Option<usize>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PackageInfo {
+    pub path: PathBuf,
+    pub name: String,
+    /// `version-release`.
+    pub version: String,
+    pub arch: String,
+    pub license: Option<String>,
+    /// Installed size in bytes.
+    pub installed_size: u64,
+    /// Size of the RPM file in bytes.
+    pub file_size: u64,
+    pub files: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Change {
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FileEntry {
+    pub path: String,
+    /// `file`, `directory` or `symlink`.
+    pub kind: &'static str,
+    pub size: u64,
+    /// Permission bits in octal, e.g. `0755`.
+    pub mode: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FileChange {
+    pub path: String,
+    pub size_delta: i64,
+    /// The contents differ (by digest).
+    pub content_changed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<Change>,
+    /// `user:group`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<Change>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<Change>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<Change>,
+}
+
+#[derive(Debug, Default, Serialize, JsonSchema)]
+pub struct FileDiff {
+    pub added: Vec<FileEntry>,
+    pub removed: Vec<FileEntry>,
+    pub changed: Vec<FileChange>,
+    pub added_count: usize,
+    pub removed_count: usize,
+    pub changed_count: usize,
+    pub unchanged_count: usize,
+    /// Some lists were cut at `max_files`.
+    pub truncated: bool,
+}
+
+#[derive(Debug, Default, Serialize, JsonSchema)]
+pub struct DependencyDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Same name with a different version constraint.
+    pub changed: Vec<Change>,
+}
+
+impl DependencyDiff {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ScriptletChange {
+    /// e.g. `%post`.
+    pub section: &'static str,
+    /// `added`, `removed` or `changed`.
+    pub change: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RpmDiff {
+    pub old: PackageInfo,
+    pub new: PackageInfo,
+    pub installed_size_delta: i64,
+    pub file_size_delta: i64,
+    pub files: FileDiff,
+    pub requires: DependencyDiff,
+    pub provides: DependencyDiff,
+    pub conflicts: DependencyDiff,
+    pub obsoletes: DependencyDiff,
+    pub scriptlets: Vec<ScriptletChange>,
+    /// Changes worth a reviewer's attention: new setuid or world-writable
+    /// files, added capabilities, a renamed package or a version that did
+    /// not go up.
+    pub warnings: Vec<String>,
+}
+
+pub(crate) fn package_info(path: &Path, header: &Header) -> Result<PackageInfo> {
+    let (name, version, arch) = header.nvra();
+    Ok(PackageInfo {
+        path: path.to_path_buf(),
+        name,
+        version,
+        arch,
+        license: header.string(rpm::LICENSE).map(str::to_string),
+        installed_size: header.installed_size(),
+        file_size: std::fs::metadata(path)?.len(),
+        files: header.strings(rpm::BASENAMES).len(),
+    })
+}
+
+fn kind(mode: u32) -> &'static str {
+    match mode & S_IFMT {
+        S_IFDIR => "directory",
+        S_IFLNK => "symlink",
+        _ => "file",
+    }
+}
+
+fn octal(mode: u32) -> String {
+    format!("{:04o}", mode & 0o7777)
+}
+
+fn entry(file: &FileInfo) -> FileEntry {
+    FileEntry {
+        path: file.path.clone(),
+        kind: kind(file.mode),
+        size: file.size,
+        mode: octal(file.mode),
+    }
+}
+
+fn changed(old: String, new: String) -> Option<Change> {
+    (old != new).then_some(Change { old, new })
+}
+
+/// Flags permission bits a reviewer should look at when `file` gains them.
+fn risky_mode(file: &FileInfo, before: Option<&FileInfo>, warnings: &mut Vec<String>) {
+    let had = |bit: u32| before.is_some_and(|b| b.mode & bit != 0);
+    if file.mode & S_ISUID != 0 && !had(S_ISUID) {
+        warnings.push(format!("{} is setuid ({})", file.path, octal(file.mode)));
+    }
+    if file.mode & S_ISGID != 0 && !had(S_ISGID) && file.mode & S_IFMT != S_IFDIR {
+        warnings.push(format!("{} is setgid ({})", file.path, octal(file.mode)));
+    }
+    if file.mode & S_IWOTH != 0 && file.mode & S_IFMT != S_IFLNK && !had(S_IWOTH) {
+        warnings.push(format!(
+            "{} is world-writable ({})",
+            file.path,
+            octal(file.mode)
+        ));
+    }
+    if !file.caps.is_empty() && before.is_none_or(|b| b.caps != file.caps) {
+        warnings.push(format!("{} has capabilities {}", file.path, file.caps));
+    }
+}
+
+fn diff_files(
+    old: &Header,
+    new: &Header,
+    max_files: usize,
+    warnings: &mut Vec<String>,
+) -> FileDiff {
+    let old: BTreeMap<String, FileInfo> = old
+        .files()
+        .into_iter()
+        .map(|f| (f.path.clone(), f))
+        .collect();
+    let new: BTreeMap<String, FileInfo> = new
+        .files()
+        .into_iter()
+        .map(|f| (f.path.clone(), f))
+        .collect();
+    let mut diff = FileDiff::default();
+    for (path, file) in &new {
+        let Some(before) = old.get(path) else {
+            risky_mode(file, None, warnings);
+            diff.added_count += 1;
+            diff.added.push(entry(file));
+            continue;
+        };
+        risky_mode(file, Some(before), warnings);
+        let change = FileChange {
+            path: path.clone(),
+            size_delta: file.size as i64 - before.size as i64,
+            content_changed: before.digest != file.digest,
+            mode: changed(octal(before.mode), octal(file.mode)),
+            owner: changed(
+                format!("{}:{}", before.user, before.group),
+                format!("{}:{}", file.user, file.group),
+            ),
+            link: changed(before.link.clone(), file.link.clone()),
+            capabilities: changed(before.caps.clone(), file.caps.clone()),
+        };
+        if change.size_delta == 0
+            && !change.content_changed
+            && change.mode.is_none()
+            && change.owner.is_none()
+            && change.link.is_none()
+            && change.capabilities.is_none()
+        {
+            diff.unchanged_count += 1;
+        } else {
+            diff.changed_count += 1;
+            diff.changed.push(change);
+        }
+    }
+    for (path, file) in &old {
+        if !new.contains_key(path) {
+            diff.removed_count += 1;
+            diff.removed.push(entry(file));
+        }
+    }
+    for list_len in [diff.added.len(), diff.removed.len(), diff.changed.len()] {
+        diff.truncated |= list_len > max_files;
+    }
+    diff.added.truncate(max_files);
+    diff.removed.truncate(max_files);
+    diff.changed.truncate(max_files);
+    diff
+}
+
+/// Dependencies by name, without the `rpmlib(...)` features rpmbuild adds.
+fn dependencies(header: &Header, tags: (u32, u32, u32)) -> BTreeMap<String, BTreeSet<String>> {
+    let mut by_name: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for dependency in header.dependencies(tags.0, tags.1, tags.2) {
+        let name = dependency.split(' ').next().unwrap_or_default().to_string();
+        if !name.starts_with("rpmlib(") {
+            by_name.entry(name).or_default().insert(dependency);
+        }
+    }
+    by_name
+}
+
+fn diff_dependencies(old: &Header, new: &Header, tags: (u32, u32, u32)) -> DependencyDiff {
+    let (old, new) = (dependencies(old, tags), dependencies(new, tags));
+    let join = |set: &BTreeSet<String>| set.iter().cloned().collect::<Vec<_>>().join(", ");
+    let mut diff = DependencyDiff::default();
+    for (name, after) in &new {
+        match old.get(name) {
+            None => diff.added.extend(after.iter().cloned()),
+            Some(before) if before != after => diff.changed.push(Change {
+                old: join(before),
+                new: join(after),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (name, before) in &old {
+        if !new.contains_key(name) {
+            diff.removed.extend(before.iter().cloned());
+        }
+    }
+    diff
+}
+
+pub(crate) fn diff_scriptlets(old: &Header, new: &Header) -> Vec<ScriptletChange> {
+    let (old, new) = (old.scriptlets(), new.scriptlets());
+    let sections: BTreeSet<&'static str> = old.keys().chain(new.keys()).copied().collect();
+    sections
+        .into_iter()
+        .filter_map(|section| {
+            let (before, after) = (old.get(section), new.get(section));
+            let change = match (before, after) {
+                (None, Some(_)) => "added",
+                (Some(_), None) => "removed",
+                (Some(b), Some(a)) if b.trim() != a.trim() => "changed",
+                _ => return None,
+            };
+            Some(ScriptletChange {
+                section,
+                change,
+                old: before.map(|s| s.to_string()),
+                new: after.map(|s| s.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Compares `version-release` strings segment by segment, numerically where
+/// both segments are numbers, like rpm's `rpmvercmp`.
+pub(crate) fn version_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    let segments = |s: &str| -> Vec<String> {
+        let mut out = Vec::new();
+        let mut current = String::new();
+        for c in s.chars() {
+            let same = current
+                .chars()
+                .last()
+                .is_none_or(|last| last.is_ascii_digit() == c.is_ascii_digit());
+            if (!c.is_ascii_alphanumeric() || !same) && !current.is_empty() {
+                out.push(std::mem::take(&mut current));
+            }
+            if c.is_ascii_alphanumeric() {
+                current.push(c);
+            }
+        }
+        if !current.is_empty() {
+            out.push(current);
+        }
+        out
+    };
+    let (a, b) = (segments(a), segments(b));
+    for (x, y) in a.iter().zip(&b) {
+        let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            (Ok(_), Err(_)) => std::cmp::Ordering::Greater,
+            (Err(_), Ok(_)) => std::cmp::Ordering::Less,
+            (Err(_), Err(_)) => x.cmp(y),
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+#[tool_router(router = rpmdiff_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Compare two RPMs, e.g. the previous release and a new build, from their headers: added, removed and changed files (size, contents, mode, owner, capabilities), requires/provides/conflicts/obsoletes changes, scriptlet changes and the installed and package size delta. Warns about new setuid, world-writable or capability-carrying files and versions that did not go up. Works without rpm installed.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn diff_rpms(
+        &self,
+        Parameters(params): Parameters<DiffRpmsParams>,
+    ) -> Result<Json<RpmDiff>> {
+        for path in [&params.old, &params.new] {
+            if !path.is_file() {
+                return Err(Error::InvalidArgument(format!(
+                    "{} does not exist",
+                    path.display()
+                )));
+            }
+        }
+        let (old_path, new_path) = (params.old.clone(), params.new.clone());
+        let (old, new) = tokio::task::spawn_blocking(move || -> Result<_> {
+            Ok((Header::read(&old_path)?, Header::read(&new_path)?))
+        })
+        .await
+        .map_err(|err| Error::Io(std::io::Error::other(err)))??;
+        let old_info = package_info(&params.old, &old)?;
+        let new_info = package_info(&params.new, &new)?;
+
+        let mut warnings = Vec::new();
+        if old_info.name != new_info.name {
+            warnings.push(format!(
+                "the package was renamed from {} to {}",
+                old_info.name, new_info.name
+            ));
+        }
+        if old_info.arch != new_info.arch {
+            warnings.push(format!(
+                "the architecture differs: {} vs {}",
+                old_info.arch, new_info.arch
+            ));
+        }
+        if version_cmp(&new_info.version, &old_info.version).is_le() {
+            warnings.push(format!(
+                "the new version {} is not newer than {}; the store and pkcon will not offer it as an update",
+                new_info.version, old_info.version
+            ));
+        }
+        if old_info.license != new_info.license {
+            warnings.push(format!(
+                "the license changed from {} to {}",
+                old_info.license.as_deref().unwrap_or("none"),
+                new_info.license.as_deref().unwrap_or("none")
+            ));
+        }
+
+        let max_files = params.max_files.unwrap_or(DEFAULT_MAX_FILES).max(1);
+        let files = diff_files(&old, &new, max_files, &mut warnings);
+        let requires = diff_dependencies(
+            &old,
+            &new,
+            (rpm::REQUIRENAME, rpm::REQUIREFLAGS, rpm::REQUIREVERSION),
+        );
+        let provides = diff_dependencies(
+            &old,
+            &new,
+            (rpm::PROVIDENAME, rpm::PROVIDEFLAGS, rpm::PROVIDEVERSION),
+        );
+        let conflicts = diff_dependencies(
+            &old,
+            &new,
+            (rpm::CONFLICTNAME, rpm::CONFLICTFLAGS, rpm::CONFLICTVERSION),
+        );
+        let obsoletes = diff_dependencies(
+            &old,
+            &new,
+            (rpm::OBSOLETENAME, rpm::OBSOLETEFLAGS, rpm::OBSOLETEVERSION),
+        );
+        if !provides.removed.is_empty() {
+            warnings.push(format!(
+                "no longer provides {}; packages requiring it will break",
+                provides.removed.join(", ")
+            ));
+        }
+        if !conflicts.is_empty() || !obsoletes.is_empty() {
+            warnings.push("the conflicts or obsoletes changed".to_string());
+        }
+        let scriptlets = diff_scriptlets(&old, &new);
+
+        Ok(Json(RpmDiff {
+            installed_size_delta: new_info.installed_size as i64 - old_info.installed_size as i64,
+            file_size_delta: new_info.file_size as i64 - old_info.file_size as i64,
+            old: old_info,
+            new: new_info,
+            files,
+            requires,
+            provides,
+            conflicts,
+            obsoletes,
+            scriptlets,
+            warnings,
+        }))
+    }
+}
@@ -0,0 +1,377 @@
+//! `generate_sandbox_files`: the `.desktop` permissions and D-Bus
+//! activation and policy files an app needs, derived from the APIs and bus
+//! names its code uses, and wired into the spec's `%install` and `%files`.
+
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::compat::CPP_EXTENSIONS;
+use super::desktop::{self, package_name};
+use super::lint::parse_spec;
+use super::permissions;
+use super::project::{ProjectInfo, find_files};
+use super::silica::{QmlUsage, qml_files, scan_qml};
+use crate::device::dbus::Bus;
+use crate::error::{Result, require_confirmation};
+use crate::server::AuroraServer;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GenerateSandboxFilesParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Write the files and edits instead of only returning them.
+    #[serde(default)]
+    pub write: bool,
+    /// Must be true to replace generated files that already exist with
+    /// different contents.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BusName {
+    /// Well-known name the app registers.
+    pub name: String,
+    pub bus: Bus,
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FileChange {
+    /// Relative to the project.
+    pub path: PathBuf,
+    pub action: Action,
+    /// The new file, or the lines added to an existing one.
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SandboxFiles {
+    pub package: String,
+    /// `Permissions=` after the update.
+    pub permissions: Vec<String>,
+    /// Permissions the code needs that were not declared.
+    pub added_permissions: Vec<String>,
+    pub bus_names: Vec<BusName>,
+    pub changes: Vec<FileChange>,
+    /// Whether the changes were written; otherwise they are a preview.
+    pub written: bool,
+    /// Things to review by hand, such as names the sandbox will not let the
+    /// app own.
+    pub notes: Vec<String>,
+}
+
+/// The first string literal in `text`.
+fn string_literal(text: &str) -> Option<&str> {
+    let start = text.find('"')? + 1;
+    let len = text[start..].find('"')?;
+    Some(&text[start..start + len])
+}
+
+/// Bus names registered with `QDBusConnection::registerService` in C++ and
+/// with Nemo.DBus `DBusAdaptor` in QML.
+fn bus_names(dir: &Path, info: &ProjectInfo) -> Result<Vec<BusName>> {
+    let mut names = Vec::new();
+    let relative = |file: &Path| file.strip_prefix(dir).unwrap_or(file).to_path_buf();
+    for file in CPP_EXTENSIONS
+        .iter()
+        .flat_map(|ext| find_files(dir, ext, 4))
+    {
+        let text = std::fs::read_to_string(&file)?;
+        let lines: Vec<&str> = text.lines().collect();
+        for (index, line) in lines.iter().enumerate() {
+            let Some((_, call)) = line.split_once("registerService(") else {
+                continue;
+            };
+            let Some(name) = string_literal(call) else {
+                continue;
+            };
+            // The connection is usually set up a few lines before.
+            let bus = lines[index.saturating_sub(10)..=index]
+                .iter()
+                .rev()
+                .find_map(|line| {
+                    if line.contains("systemBus()") {
+                        Some(Bus::System)
+                    } else if line.contains("sessionBus()") {
+                        Some(Bus::Session)
+                    } else {
+                        None
+                    }
+                })
+                .unwrap_or_default();
+            names.push(BusName {
+                name: name.to_string(),
+                bus,
+                file: relative(&file),
+                line: index + 1,
+            });
+        }
+    }
+    if info.qml_dir.is_some() {
+        for file in qml_files(dir)? {
+            let text = std::fs::read_to_string(&file)?;
+            let lines: Vec<&str> = text.lines().collect();
+            for (line, usage) in scan_qml(&text) {
+                let QmlUsage::Property { component, name } = usage else {
+                    continue;
+                };
+                if component != "DBusAdaptor" || name != "service" {
+                    continue;
+                }
+                let Some(service) = lines.get(line - 1).and_then(|l| string_literal(l)) else {
+                    continue;
+                };
+                let system = lines[line.saturating_sub(10)..lines.len().min(line + 10)]
+                    .iter()
+                    .any(|l| l.contains("DBus.SystemBus"));
+                names.push(BusName {
+                    name: service.to_string(),
+                    bus: if system { Bus::System } else { Bus::Session },
+                    file: relative(&file),
+                    line,
+                });
+            }
+        }
+    }
+    names.sort_by(|a, b| a.name.cmp(&b.name));
+    names.dedup_by(|a, b| a.name == b.name);
+    Ok(names)
+}
+
+/// A session bus activation file starting the app for `name`.
+fn activation_file(info: &ProjectInfo, name: &str) -> String {
+    let exec = if info.uses_silica {
+        format!(
+            "/usr/bin/invoker --type=silica-qt5 --single-instance /usr/bin/{}",
+            info.name
+        )
+    } else {
+        format!("/usr/bin/{}", info.name)
+    };
+    format!("[D-BUS Service]\nName={name}\nExec={exec}\n")
+}
+
+/// A system bus policy letting root own `name` and anyone call it.
+fn policy_file(name: &str) -> String {
+    format!(
+        "<!DOCTYPE busconfig PUBLIC \"-//freedesktop//DTD D-BUS Bus Configuration 1.0//EN\"\n \
+         \"http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd\">\n\
+         <busconfig>\n  <policy user=\"root\">\n    <allow own=\"{name}\"/>\n  </policy>\n  \
+         <policy context=\"default\">\n    <allow send_destination=\"{name}\"/>\n  </policy>\n\
+         </busconfig>\n"
+    )
+}
+
+/// `text` with `lines` added at the end of the first `section`, or `None`
+/// when the spec has no such section.
+pub(crate) fn append_to_section(text: &str, section: &str, lines: &[String]) -> Option<String> {
+    let spec = parse_spec(text);
+    let section = spec.section(section)?;
+    let after = section
+        .body
+        .iter()
+        .rev()
+        .find(|(_, line)| !line.trim().is_empty())
+        .map_or(section.line, |(line, _)| *line);
+    let mut out: Vec<&str> = text.lines().collect();
+    let tail = out.split_off(after);
+    let mut result = out.join("\n");
+    for line in lines {
+        result.push('\n');
+        result.push_str(line);
+    }
+    for line in tail {
+        result.push('\n');
+        result.push_str(line);
+    }
+    result.push('\n');
+    Some(result)
+}
+
+#[tool_router(router = sandbox_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Generate the sandbox and D-Bus security files an Aurora app needs from what its code uses: adds missing Permissions= to the .desktop file, writes a dbus/<name>.service activation file for every session bus name the app registers (and a dbus/<name>.conf policy for system bus names), and adds them to the spec's %install and %files. Returns the changes for review; set write=true to apply them (replacing differing generated files needs confirm=true)."
+    )]
+    pub async fn generate_sandbox_files(
+        &self,
+        Parameters(params): Parameters<GenerateSandboxFilesParams>,
+    ) -> Result<Json<SandboxFiles>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        let info = ProjectInfo::inspect(&dir);
+        let package = package_name(&info);
+        let mut notes = Vec::new();
+        // (path, new contents, what to show)
+        let mut edits: Vec<(PathBuf, String, String)> = Vec::new();
+
+        let (_, uses) = permissions::scan(&dir, &info)?;
+        let mut permissions = Vec::new();
+        let mut added_permissions = Vec::new();
+        match &info.desktop_file {
+            Some(path) => {
+                let text = std::fs::read_to_string(path)?;
+                let entries = desktop::parse(&text);
+                permissions = permissions::declared(&entries);
+                added_permissions = uses
+                    .keys()
+                    .filter(|p| !permissions.iter().any(|d| d == *p))
+                    .map(|p| p.to_string())
+                    .collect();
+                permissions.extend(added_permissions.iter().cloned());
+                let line = format!("Permissions={}", permissions.join(";"));
+                let mut lines: Vec<&str> = text.lines().collect();
+                let updated = match desktop::find(&entries, "X-Application", "Permissions") {
+                    Some(entry) => {
+                        lines[entry.line - 1] = &line;
+                        true
+                    }
+                    None => match lines.iter().position(|l| l.trim() == "[X-Application]") {
+                        Some(at) => {
+                            lines.insert(at + 1, &line);
+                            true
+                        }
+                        None => {
+                            notes.push(format!(
+                                "{} has no [X-Application] group; create it with generate_desktop",
+                                path.display()
+                            ));
+                            false
+                        }
+                    },
+                };
+                if updated && !added_permissions.is_empty() {
+                    edits.push((path.clone(), lines.join("\n") + "\n", line.clone()));
+                }
+            }
+            None => notes.push(
+                "the project has no .desktop file; create it with generate_desktop".to_string(),
+            ),
+        }
+
+        let bus_names = bus_names(&dir, &info)?;
+        let mut install = Vec::new();
+        let mut files = Vec::new();
+        for bus_name in &bus_names {
+            let name = &bus_name.name;
+            if name != &package && !name.starts_with(&format!("{package}.")) {
+                notes.push(format!(
+                    "{name}: the sandbox only lets {package} own {package} and names below it"
+                ));
+            }
+            let (file, contents, target) = match bus_name.bus {
+                Bus::Session => (
+                    format!("dbus/{name}.service"),
+                    activation_file(&info, name),
+                    format!("%{{_datadir}}/dbus-1/services/{name}.service"),
+                ),
+                Bus::System => {
+                    notes.push(format!(
+                        "{name}: system bus policies are outside /usr/share/{package} and are not accepted in the store"
+                    ));
+                    (
+                        format!("dbus/{name}.conf"),
+                        policy_file(name),
+                        format!("%{{_datadir}}/dbus-1/system.d/{name}.conf"),
+                    )
+                }
+            };
+            edits.push((dir.join(&file), contents.clone(), contents));
+            install.push(format!("install -D -m 644 {file} %{{buildroot}}{target}"));
+            files.push(target);
+        }
+
+        match &info.spec_file {
+            Some(spec_path) if !files.is_empty() => {
+                let mut text = std::fs::read_to_string(spec_path)?;
+                let with_name = |target: &str| target.replace(&package, "%{name}");
+                let is_listed = |text: &str, target: &str| {
+                    text.contains(target) || text.contains(&with_name(target))
+                };
+                let (install, files): (Vec<_>, Vec<_>) = install
+                    .into_iter()
+                    .zip(files)
+                    .filter(|(_, target)| !is_listed(&text, target))
+                    .unzip();
+                if !files.is_empty() {
+                    for (section, lines) in [("%install", &install), ("%files", &files)] {
+                        match append_to_section(&text, section, lines) {
+                            Some(updated) => text = updated,
+                            None => notes.push(format!(
+                                "{} has no {section} section; add: {}",
+                                spec_path.display(),
+                                lines.join("; ")
+                            )),
+                        }
+                    }
+                    let added = install.iter().chain(&files).cloned().collect::<Vec<_>>();
+                    edits.push((spec_path.clone(), text, added.join("\n")));
+                }
+            }
+            None if !files.is_empty() => notes.push(
+                "the project has no spec; install the dbus/ files and list them under %files"
+                    .to_string(),
+            ),
+            _ => {}
+        }
+
+        let mut changes = Vec::new();
+        for (path, contents, shown) in &edits {
+            let existing = std::fs::read_to_string(path).ok();
+            let action = match &existing {
+                None => Action::Created,
+                Some(old) if old == contents => Action::Unchanged,
+                Some(_) => Action::Updated,
+            };
+            let generated = path.starts_with(dir.join("dbus"));
+            if params.write && generated && action == Action::Updated {
+                require_confirmation(params.confirm, || format!("replacing {}", path.display()))?;
+            }
+            changes.push(FileChange {
+                path: path.strip_prefix(&dir).unwrap_or(path).to_path_buf(),
+                action,
+                content: shown.clone(),
+            });
+        }
+        if params.write {
+            for ((path, contents, _), change) in edits.iter().zip(&changes) {
+                if change.action == Action::Unchanged {
+                    continue;
+                }
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(path, contents)?;
+            }
+        }
+        if changes.is_empty() && notes.is_empty() {
+            notes.push(
+                "the declared permissions cover the code and it owns no bus names".to_string(),
+            );
+        }
+        Ok(Json(SandboxFiles {
+            package,
+            permissions,
+            added_permissions,
+            bus_names,
+            changes,
+            written: params.write,
+            notes,
+        }))
+    }
+}
@@ -0,0 +1,311 @@
+//! `create_project`: new Aurora projects from the templates embedded in
+//! `sdk/templates/`.
+
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::project::ProjectInfo;
+use super::spec;
+use crate::config::expand_tilde;
+use crate::device::mock::synthetic_png;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Launcher icon sizes generated for apps.
+const ICON_SIZES: &[u32] = &[86, 108, 128, 172];
+
+/// `(path, contents)` of a template; both may contain placeholders.
+type Template = &'static [(&'static str, &'static str)];
+
+macro_rules! template {
+    ($kind:literal: $($path:literal),* $(,)?) => {
+        &[$(($path, include_str!(concat!("templates/", $kind, "/", $path)))),*]
+    };
+}
+
+const SILICA_APP: Template = template!("silica-app":
+    "{{NAME}}.pro",
+    "{{NAME}}.desktop",
+    "src/main.cpp",
+    "src/greeter.h",
+    "src/greeter.cpp",
+    "qml/{{NAME}}.qml",
+    "qml/pages/MainPage.qml",
+    "qml/cover/DefaultCoverPage.qml",
+    "translations/{{NAME}}.ts",
+    "translations/{{NAME}}-ru.ts",
+);
+
+const QML_APP: Template = template!("qml-app":
+    "{{NAME}}.pro",
+    "{{NAME}}.desktop",
+    "src/main.cpp",
+    "qml/{{NAME}}.qml",
+    "qml/pages/MainPage.qml",
+    "qml/cover/DefaultCoverPage.qml",
+    "translations/{{NAME}}.ts",
+    "translations/{{NAME}}-ru.ts",
+);
+
+const PYTHON_APP: Template = template!("python-app":
+    "{{NAME}}.desktop",
+    "qml/{{NAME}}.qml",
+    "qml/pages/MainPage.qml",
+    "qml/cover/DefaultCoverPage.qml",
+    "qml/py/{{MODULE}}.py",
+);
+
+const LIBRARY: Template = template!("library":
+    "{{NAME}}.pro",
+    "{{APP}}.pc.in",
+    "src/{{APP}}_global.h",
+    "src/{{APP}}.h",
+    "src/{{APP}}.cpp",
+    "rpm/{{NAME}}.spec",
+);
+
+const DBUS_SERVICE: Template = template!("dbus-service":
+    "{{NAME}}.pro",
+    "src/service.h",
+    "src/service.cpp",
+    "src/main.cpp",
+    "dbus/{{NAME}}.service",
+    "systemd/{{NAME}}.service",
+    "rpm/{{NAME}}.spec",
+);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProjectKind {
+    /// Silica UI in QML with a C++ object exposed to it.
+    #[default]
+    SilicaApp,
+    /// Silica UI written entirely in QML, with the stock launcher.
+    QmlApp,
+    /// Silica UI in QML with the logic in Python, called through
+    /// PyOtherSide. Not accepted by the OS store, which bans interpreters.
+    PythonApp,
+    /// Shared C++ library with headers and a pkg-config file.
+    Library,
+    /// Session D-Bus service, activated on demand through systemd.
+    DbusService,
+}
+
+impl ProjectKind {
+    pub const ALL: [ProjectKind; 5] = [
+        ProjectKind::SilicaApp,
+        ProjectKind::QmlApp,
+        ProjectKind::PythonApp,
+        ProjectKind::Library,
+        ProjectKind::DbusService,
+    ];
+
+    /// The kind as it is spelled in parameters, e.g. `qml-app`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ProjectKind::SilicaApp => "silica-app",
+            ProjectKind::QmlApp => "qml-app",
+            ProjectKind::PythonApp => "python-app",
+            ProjectKind::Library => "library",
+            ProjectKind::DbusService => "dbus-service",
+        }
+    }
+
+    fn template(self) -> Template {
+        match self {
+            ProjectKind::SilicaApp => SILICA_APP,
+            ProjectKind::QmlApp => QML_APP,
+            ProjectKind::PythonApp => PYTHON_APP,
+            ProjectKind::Library => LIBRARY,
+            ProjectKind::DbusService => DBUS_SERVICE,
+        }
+    }
+
+    fn is_app(self) -> bool {
+        matches!(
+            self,
+            ProjectKind::SilicaApp | ProjectKind::QmlApp | ProjectKind::PythonApp
+        )
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateProjectParams {
+    #[serde(default)]
+    pub kind: ProjectKind,
+    /// Organization in reverse domain notation, e.g. `ru.example`.
+    pub organization: String,
+    /// Application name, e.g. `notes`; the package becomes
+    /// `<organization>.<name>`.
+    pub name: String,
+    /// Human-readable title for the launcher and summary (default: `name`).
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Directory to create the project in (default: the working
+    /// directory). The project gets its own subdirectory named after the
+    /// package.
+    #[serde(default)]
+    pub directory: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CreatedProject {
+    pub kind: ProjectKind,
+    pub package: String,
+    pub path: PathBuf,
+    /// Created files, relative to `path`.
+    pub files: Vec<PathBuf>,
+}
+
+/// Placeholder values for one project.
+struct Names {
+    org: String,
+    app: String,
+    title: String,
+}
+
+impl Names {
+    fn package(&self) -> String {
+        format!("{}.{}", self.org, self.app)
+    }
+
+    fn fill(&self, template: &str) -> String {
+        let class: String = self
+            .app
+            .split(['-', '_'])
+            .map(|part| {
+                let mut chars = part.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            })
+            .collect();
+        template
+            .replace("{{NAME}}", &self.package())
+            .replace("{{ORG}}", &self.org)
+            .replace("{{APP}}", &self.app)
+            .replace("{{TITLE}}", &self.title)
+            .replace("{{CLASS}}", &class)
+            .replace(
+                "{{MACRO}}",
+                &self.app.to_ascii_uppercase().replace('-', "_"),
+            )
+            .replace("{{MODULE}}", &self.app.replace('-', "_"))
+            .replace(
+                "{{DBUS_PATH}}",
+                &format!("/{}", self.package().replace('.', "/")),
+            )
+    }
+}
+
+fn validate(params: &CreateProjectParams) -> Result<()> {
+    let segment_ok =
+        |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if params.organization.split('.').count() < 2 || !params.organization.split('.').all(segment_ok)
+    {
+        return Err(Error::InvalidArgument(format!(
+            "organization '{}' must be in reverse domain notation, e.g. ru.example",
+            params.organization
+        )));
+    }
+    let name_ok = params
+        .name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic())
+        && params
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !name_ok {
+        return Err(Error::InvalidArgument(format!(
+            "name '{}' must start with a letter and contain only letters, digits, '-' and '_'",
+            params.name
+        )));
+    }
+    Ok(())
+}
+
+fn write(root: &Path, relative: &str, contents: &[u8], files: &mut Vec<PathBuf>) -> Result<()> {
+    let path = root.join(relative);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, contents)?;
+    files.push(PathBuf::from(relative));
+    Ok(())
+}
+
+pub fn create(params: CreateProjectParams) -> Result<CreatedProject> {
+    validate(&params)?;
+    let names = Names {
+        title: params.title.clone().unwrap_or_else(|| params.name.clone()),
+        org: params.organization,
+        app: params.name,
+    };
+    let package = names.package();
+    let parent = match params.directory {
+        Some(dir) => expand_tilde(Path::new(&dir)),
+        None => std::env::current_dir()?,
+    };
+    let root = parent.join(&package);
+    if root
+        .read_dir()
+        .is_ok_and(|mut entries| entries.next().is_some())
+    {
+        return Err(Error::InvalidArgument(format!(
+            "{} already exists and is not empty",
+            root.display()
+        )));
+    }
+
+    let mut files = Vec::new();
+    for (path, contents) in params.kind.template() {
+        write(
+            &root,
+            &names.fill(path),
+            names.fill(contents).as_bytes(),
+            &mut files,
+        )?;
+    }
+    if params.kind.is_app() {
+        for size in ICON_SIZES {
+            let path = format!("icons/{size}x{size}/{package}.png");
+            write(&root, &path, &synthetic_png(*size, *size), &mut files)?;
+        }
+        // The spec is derived from the files above so it matches what
+        // generate_spec and lint_spec expect.
+        let info = ProjectInfo::inspect(&root);
+        let spec = spec::render(&info, &package, &names.title, "0.1.0", "BSD-3-Clause");
+        write(
+            &root,
+            &format!("rpm/{package}.spec"),
+            spec.as_bytes(),
+            &mut files,
+        )?;
+    }
+    Ok(CreatedProject {
+        kind: params.kind,
+        package,
+        path: root,
+        files,
+    })
+}
+
+#[tool_router(router = scaffold_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Create a new Aurora project from a template: kind=silica-app (C++/QML Silica app, default), qml-app (QML-only Silica app), python-app (Silica UI with Python logic via PyOtherSide; not store-eligible), library (shared C++ library) or dbus-service (session D-Bus service). Spec, desktop file, icons and translations are wired up for <organization>.<name>."
+    )]
+    pub async fn create_project(
+        &self,
+        Parameters(params): Parameters<CreateProjectParams>,
+    ) -> Result<Json<CreatedProject>> {
+        create(params).map(Json)
+    }
+}
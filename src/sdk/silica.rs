@@ -0,0 +1,423 @@
+//! `check_silica_api`: QML using Silica and platform APIs that are
+//! deprecated or removed in a chosen Aurora OS release, from a database
+//! embedded in the server and optionally extended by a data file.
+
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::project::{ProjectInfo, find_files};
+use crate::config::expand_tilde;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+const DATABASE: &str = include_str!("data/silica-api.toml");
+
+/// A dotted Aurora OS release such as `5.1.3`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OsVersion(Vec<u32>);
+
+impl OsVersion {
+    pub fn parse(text: &str) -> Result<Self> {
+        text.trim()
+            .trim_start_matches('v')
+            .split('.')
+            .map(|part| part.parse::<u32>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map(OsVersion)
+            .map_err(|_| Error::InvalidArgument(format!("'{text}' is not an OS version like 5.1")))
+    }
+}
+
+impl Ord for OsVersion {
+    /// Missing components count as zero, so `5.1` equals `5.1.0`.
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.0.len().max(other.0.len());
+        (0..len)
+            .map(|i| {
+                let a = self.0.get(i).copied().unwrap_or(0);
+                let b = other.0.get(i).copied().unwrap_or(0);
+                a.cmp(&b)
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for OsVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// What a QML file uses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QmlUsage {
+    /// `import Module 1.0`.
+    Import { module: String },
+    /// `Component {`, by its unqualified name.
+    Component { name: String },
+    /// `name:` set inside a `component`.
+    Property { component: String, name: String },
+    /// `Object.name` read anywhere, e.g. `Theme.highlightColor`.
+    Member { object: String, name: String },
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Token {
+    Word(String),
+    Punct(char),
+}
+
+/// `(line, token)` of QML source, without comments and string literals.
+pub(crate) fn tokenize(text: &str) -> Vec<(usize, Token)> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '"' | '\'' | '`' => {
+                let mut escaped = false;
+                for s in chars.by_ref() {
+                    if s == '\n' {
+                        line += 1;
+                    }
+                    if !escaped && s == c {
+                        break;
+                    }
+                    escaped = !escaped && s == '\\';
+                }
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut word = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next.is_alphanumeric() || next == '_' || next == '.' {
+                        word.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push((line, Token::Word(word)));
+            }
+            c if c.is_whitespace() => {}
+            c => tokens.push((line, Token::Punct(c))),
+        }
+    }
+    tokens
+}
+
+pub(crate) fn is_type_name(word: &str) -> bool {
+    word.rsplit('.')
+        .next()
+        .and_then(|last| last.chars().next())
+        .is_some_and(|c| c.is_ascii_uppercase())
+}
+
+/// `(line, usage)` of everything a QML document imports, instantiates, sets
+/// and reads from singletons.
+pub fn scan_qml(text: &str) -> Vec<(usize, QmlUsage)> {
+    let tokens = tokenize(text);
+    let mut usages = Vec::new();
+    // Enclosing blocks: the component name, or empty for other braces.
+    let mut stack: Vec<String> = Vec::new();
+    let mut statement_start = true;
+    let mut i = 0;
+    while i < tokens.len() {
+        let (line, token) = &tokens[i];
+        let next = tokens.get(i + 1).map(|(_, token)| token);
+        match token {
+            Token::Word(word) if word == "import" => {
+                // Quoted imports leave no token but `as` on their line.
+                if let Some((module_line, Token::Word(module))) = tokens.get(i + 1)
+                    && module_line == line
+                    && module != "as"
+                {
+                    usages.push((
+                        *line,
+                        QmlUsage::Import {
+                            module: module.clone(),
+                        },
+                    ));
+                }
+                // Skip the rest of the import line.
+                while tokens.get(i + 1).is_some_and(|(l, _)| l == line) {
+                    i += 1;
+                }
+                statement_start = true;
+            }
+            Token::Word(word) if next == Some(&Token::Punct('{')) => {
+                if is_type_name(word) {
+                    let name = word.rsplit('.').next().unwrap_or(word).to_string();
+                    usages.push((*line, QmlUsage::Component { name: name.clone() }));
+                    stack.push(name);
+                } else {
+                    stack.push(String::new());
+                }
+                i += 1;
+                statement_start = true;
+            }
+            Token::Word(word) => {
+                let at_start = statement_start
+                    || i == 0
+                    || tokens[i - 1].0 != *line
+                    || matches!(tokens[i - 1].1, Token::Punct('{' | ';'));
+                if at_start
+                    && next == Some(&Token::Punct(':'))
+                    && let Some(component) = stack.last().filter(|c| !c.is_empty())
+                {
+                    usages.push((
+                        *line,
+                        QmlUsage::Property {
+                            component: component.clone(),
+                            name: word.clone(),
+                        },
+                    ));
+                } else if let Some((object, rest)) = word.split_once('.')
+                    && is_type_name(object)
+                {
+                    let name = rest.split('.').next().unwrap_or(rest).to_string();
+                    usages.push((
+                        *line,
+                        QmlUsage::Member {
+                            object: object.to_string(),
+                            name,
+                        },
+                    ));
+                }
+                statement_start = false;
+            }
+            Token::Punct('{') => {
+                stack.push(String::new());
+                statement_start = true;
+            }
+            Token::Punct('}') => {
+                stack.pop();
+                statement_start = true;
+            }
+            Token::Punct(';') => statement_start = true,
+            Token::Punct(_) => statement_start = false,
+        }
+        i += 1;
+    }
+    usages
+}
+
+/// QML files of a project: everything under its QML directory.
+pub fn qml_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let qml_dir = ProjectInfo::inspect(dir)
+        .qml_dir
+        .ok_or_else(|| Error::InvalidArgument(format!("no qml directory in {}", dir.display())))?;
+    Ok(find_files(&qml_dir, "qml", 8))
+}
+
+/// A database entry: one import, component, or property/member of a
+/// component.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ApiEntry {
+    import: Option<String>,
+    component: Option<String>,
+    property: Option<String>,
+    deprecated: Option<String>,
+    removed: Option<String>,
+    replacement: Option<String>,
+    note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Database {
+    #[serde(default)]
+    api: Vec<ApiEntry>,
+}
+
+impl ApiEntry {
+    /// `import X`, `Component` or `Component.property`.
+    fn name(&self) -> String {
+        match (&self.import, &self.component, &self.property) {
+            (Some(import), _, _) => format!("import {import}"),
+            (None, Some(component), Some(property)) => format!("{component}.{property}"),
+            (None, Some(component), None) => component.clone(),
+            (None, None, Some(property)) => property.clone(),
+            (None, None, None) => String::new(),
+        }
+    }
+
+    /// Whether the API is removed or deprecated as of `os_version`, and
+    /// since which release.
+    fn status(&self, os_version: &OsVersion) -> Option<(ApiStatus, &str)> {
+        let reached = |version: &Option<String>| {
+            version
+                .as_deref()
+                .is_some_and(|v| OsVersion::parse(v).is_ok_and(|v| v <= *os_version))
+        };
+        if reached(&self.removed) {
+            Some((ApiStatus::Removed, self.removed.as_deref()?))
+        } else if reached(&self.deprecated) {
+            Some((ApiStatus::Deprecated, self.deprecated.as_deref()?))
+        } else {
+            None
+        }
+    }
+
+    fn matches(&self, usage: &QmlUsage) -> bool {
+        match (usage, &self.import) {
+            (QmlUsage::Import { module }, Some(import)) => {
+                module == import || module.starts_with(&format!("{import}."))
+            }
+            (_, Some(_)) | (QmlUsage::Import { .. }, None) => false,
+            (QmlUsage::Component { name }, None) => {
+                self.property.is_none() && self.component.as_ref() == Some(name)
+            }
+            (QmlUsage::Property { component, name }, None)
+            | (
+                QmlUsage::Member {
+                    object: component,
+                    name,
+                },
+                None,
+            ) => {
+                self.property.as_ref() == Some(name)
+                    && self.component.as_ref().is_none_or(|c| c == component)
+            }
+        }
+    }
+}
+
+/// The embedded database, extended by `extra`: its entries replace
+/// embedded ones for the same API.
+fn load_database(extra: Option<&Path>) -> Result<Vec<ApiEntry>> {
+    let Database { api: entries } = super::load_database(
+        "silica-api.toml",
+        DATABASE,
+        extra,
+        |database: &mut Database, file: Database| {
+            for entry in file.api {
+                database
+                    .api
+                    .retain(|existing| existing.name() != entry.name());
+                database.api.push(entry);
+            }
+        },
+    )?;
+    for entry in &entries {
+        for version in [&entry.deprecated, &entry.removed].into_iter().flatten() {
+            OsVersion::parse(version)
+                .map_err(|_| Error::Config(format!("{}: bad version '{version}'", entry.name())))?;
+        }
+    }
+    Ok(entries)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiStatus {
+    Deprecated,
+    Removed,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CheckSilicaApiParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Aurora OS release to check against, e.g. `5.1`.
+    pub os_version: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ApiFinding {
+    pub file: PathBuf,
+    pub line: usize,
+    /// e.g. `SilicaWebView`, `TextField.labelVisible`,
+    /// `import org.nemomobile.dbus`.
+    pub api: String,
+    pub status: ApiStatus,
+    /// Release that deprecated or removed it.
+    pub since: String,
+    pub replacement: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SilicaApiReport {
+    pub os_version: String,
+    pub files_checked: usize,
+    /// No removed APIs are used; deprecated ones may remain.
+    pub passed: bool,
+    pub findings: Vec<ApiFinding>,
+}
+
+#[tool_router(router = silica_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Scan a project's QML for Silica components, properties and platform imports that are deprecated or removed in a given Aurora OS release, with suggested replacements. Uses the embedded compatibility database plus the [sdk] silica_api_database file if configured.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn check_silica_api(
+        &self,
+        Parameters(params): Parameters<CheckSilicaApiParams>,
+    ) -> Result<Json<SilicaApiReport>> {
+        let options = &self.state().config.sdk;
+        let dir = options.project(params.project.as_deref())?;
+        let os_version = OsVersion::parse(&params.os_version)?;
+        let extra = options.silica_api_database.as_deref().map(expand_tilde);
+        let entries = load_database(extra.as_deref())?;
+        let files = qml_files(&dir)?;
+
+        let mut findings = Vec::new();
+        for file in &files {
+            let text = std::fs::read_to_string(file)?;
+            for (line, usage) in scan_qml(&text) {
+                for entry in entries.iter().filter(|entry| entry.matches(&usage)) {
+                    let Some((status, since)) = entry.status(&os_version) else {
+                        continue;
+                    };
+                    findings.push(ApiFinding {
+                        file: file.strip_prefix(&dir).unwrap_or(file).to_path_buf(),
+                        line,
+                        api: entry.name(),
+                        status,
+                        since: since.to_string(),
+                        replacement: entry.replacement.clone(),
+                        note: entry.note.clone(),
+                    });
+                }
+            }
+        }
+        Ok(Json(SilicaApiReport {
+            os_version: params.os_version,
+            files_checked: files.len(),
+            passed: !findings.iter().any(|f| f.status == ApiStatus::Removed),
+            findings,
+        }))
+    }
+}
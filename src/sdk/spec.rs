@@ -0,0 +1,272 @@
+//! `generate_spec`: an RPM spec file for a project, derived from its build
+//! system and the files it ships.
+
+use std::path::PathBuf;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::project::{BuildSystem, ProjectInfo};
+use super::python;
+use crate::error::{Error, Result, require_confirmation};
+use crate::server::AuroraServer;
+
+const DEFAULT_VERSION: &str = "0.1.0";
+const DEFAULT_LICENSE: &str = "BSD-3-Clause";
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GenerateSpecParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Package name (default: the project's target name).
+    #[serde(default)]
+    pub name: Option<String>,
+    /// One-line summary (default: the desktop file's `Name`).
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// SPDX license (default `BSD-3-Clause`).
+    #[serde(default)]
+    pub license: Option<String>,
+    /// Write the spec to `rpm/<name>.spec` instead of only returning it.
+    #[serde(default)]
+    pub write: bool,
+    /// Must be true to replace an existing spec file.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GeneratedSpec {
+    pub project: ProjectInfo,
+    /// Where the spec was written, when `write` was set.
+    pub written_to: Option<PathBuf>,
+    pub spec: String,
+}
+
+fn desktop_name(info: &ProjectInfo) -> Option<String> {
+    let text = std::fs::read_to_string(info.desktop_file.as_ref()?).ok()?;
+    text.lines()
+        .find_map(|line| line.strip_prefix("Name="))
+        .map(|name| name.trim().to_string())
+}
+
+/// Requires lines: Silica, and for Python apps PyOtherSide, the interpreter
+/// and a package for each third-party import.
+fn requires(info: &ProjectInfo) -> Vec<String> {
+    let mut requires = Vec::new();
+    if info.uses_silica {
+        requires.push("sailfishsilica-qt5 >= 0.10.9".to_string());
+    }
+    if info.uses_pyotherside || info.is_python_app() {
+        requires.push("pyotherside-qml-plugin-python3-qt5 >= 1.5.1".to_string());
+        requires.push("python3-base".to_string());
+        requires.extend(
+            info.python_imports
+                .iter()
+                .map(|module| python::package(module)),
+        );
+    }
+    requires
+}
+
+/// `%install` for a Python app: the QML and Python trees under
+/// `%{_datadir}/%{name}`, the desktop file and icons, and the sources
+/// byte-compiled so the first launch does not have to.
+fn python_install(info: &ProjectInfo) -> Vec<String> {
+    let relative = |path: &PathBuf| {
+        path.strip_prefix(&info.dir)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    let mut lines = vec!["mkdir -p %{buildroot}%{_datadir}/%{name}".to_string()];
+    if let Some(qml) = &info.qml_dir {
+        lines.push(format!(
+            "cp -r {} %{{buildroot}}%{{_datadir}}/%{{name}}/qml",
+            relative(qml)
+        ));
+    }
+    if let Some(python) = &info.python_dir
+        && !info
+            .qml_dir
+            .as_ref()
+            .is_some_and(|qml| python.starts_with(qml))
+    {
+        lines.push(format!(
+            "cp -r {} %{{buildroot}}%{{_datadir}}/%{{name}}/python",
+            relative(python)
+        ));
+    }
+    if info.desktop_file.is_some() {
+        lines.push(
+            "install -D -m 644 %{name}.desktop %{buildroot}%{_datadir}/applications/%{name}.desktop"
+                .to_string(),
+        );
+    }
+    for size in &info.icon_sizes {
+        lines.push(format!(
+            "install -D -m 644 icons/{size}/%{{name}}.png %{{buildroot}}%{{_datadir}}/icons/hicolor/{size}/apps/%{{name}}.png"
+        ));
+    }
+    lines.push(
+        "python3 -m compileall -q -d %{_datadir}/%{name} %{buildroot}%{_datadir}/%{name}"
+            .to_string(),
+    );
+    lines
+}
+
+/// BuildRequires lines: Qt modules and pkg-config dependencies as
+/// `pkgconfig(...)` provides, plus the build tool.
+fn build_requires(info: &ProjectInfo) -> Vec<String> {
+    let mut requires: Vec<String> = info
+        .qt_modules
+        .iter()
+        .map(|module| format!("pkgconfig(Qt5{module})"))
+        .chain(info.pkgconfig.iter().map(|pkg| format!("pkgconfig({pkg})")))
+        .collect();
+    if info.build_system == Some(BuildSystem::Cmake) {
+        requires.push("cmake".to_string());
+    }
+    if !info.translations.is_empty() {
+        requires.push("qt5-qttools-linguist".to_string());
+    }
+    if info.is_python_app() {
+        requires.push("python3-base".to_string());
+    }
+    requires.sort();
+    requires.dedup();
+    requires
+}
+
+pub fn render(
+    info: &ProjectInfo,
+    name: &str,
+    summary: &str,
+    version: &str,
+    license: &str,
+) -> String {
+    let mut spec = String::new();
+    let mut line = |text: &str| {
+        spec.push_str(text);
+        spec.push('\n');
+    };
+    line(&format!("Name:       {name}"));
+    line(&format!("Summary:    {summary}"));
+    line(&format!("Version:    {version}"));
+    line("Release:    1");
+    line(&format!("License:    {license}"));
+    line("Source0:    %{name}-%{version}.tar.bz2");
+    if info.is_python_app() {
+        line("BuildArch:  noarch");
+    }
+    line("");
+    for require in requires(info) {
+        line(&format!("Requires:   {require}"));
+    }
+    for require in build_requires(info) {
+        line(&format!("BuildRequires:  {require}"));
+    }
+    line("");
+    line("%description");
+    line(&format!("{summary}."));
+    line("");
+    line("%prep");
+    line("%autosetup");
+    line("");
+    line("%build");
+    match info.build_system {
+        _ if info.is_python_app() => {}
+        Some(BuildSystem::Cmake) => {
+            line("%cmake");
+            line("%make_build");
+        }
+        _ => {
+            line("%qtc_qmake5");
+            line("%qtc_make %{?_smp_mflags}");
+        }
+    }
+    line("");
+    line("%install");
+    match info.build_system {
+        _ if info.is_python_app() => {
+            for install in python_install(info) {
+                line(&install);
+            }
+        }
+        Some(BuildSystem::Cmake) => line("%make_install"),
+        _ => line("%qmake5_install"),
+    }
+    line("");
+    line("%files");
+    if !info.is_python_app() {
+        line("%defattr(-,root,root,-)");
+        line("%{_bindir}/%{name}");
+    }
+    line("%defattr(644,root,root,-)");
+    if info.qml_dir.is_some() || info.python_dir.is_some() || !info.translations.is_empty() {
+        line("%{_datadir}/%{name}");
+    }
+    if info.desktop_file.is_some() {
+        line("%{_datadir}/applications/%{name}.desktop");
+    }
+    if !info.icon_sizes.is_empty() {
+        line("%{_datadir}/icons/hicolor/*/apps/%{name}.png");
+    }
+    spec
+}
+
+#[tool_router(router = spec_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Generate an Aurora RPM .spec file for a project by inspecting its build system (qmake/CMake), Qt modules, QML, translations, icons and desktop file. Python (PyOtherSide) apps get a noarch spec that requires python3 packages for their imports and byte-compiles the sources. Returns the spec for review; set write=true to save it as rpm/<name>.spec (replacing one needs confirm=true)."
+    )]
+    pub async fn generate_spec(
+        &self,
+        Parameters(params): Parameters<GenerateSpecParams>,
+    ) -> Result<Json<GeneratedSpec>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        let info = ProjectInfo::inspect(&dir);
+        let name = params.name.unwrap_or_else(|| info.name.clone());
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.+".contains(c))
+        {
+            return Err(Error::InvalidArgument(format!(
+                "'{name}' is not a valid package name"
+            )));
+        }
+        let summary = params
+            .summary
+            .or_else(|| desktop_name(&info))
+            .unwrap_or_else(|| name.clone());
+        let version = params
+            .version
+            .or_else(|| info.version.clone())
+            .unwrap_or_else(|| DEFAULT_VERSION.to_string());
+        let license = params.license.as_deref().unwrap_or(DEFAULT_LICENSE);
+        let spec = render(&info, &name, &summary, &version, license);
+
+        let mut written_to = None;
+        if params.write {
+            let path = dir.join("rpm").join(format!("{name}.spec"));
+            if path.exists() {
+                require_confirmation(params.confirm, || format!("replacing {}", path.display()))?;
+            }
+            std::fs::create_dir_all(dir.join("rpm"))?;
+            std::fs::write(&path, &spec)?;
+            written_to = Some(path);
+        }
+        Ok(Json(GeneratedSpec {
+            project: info,
+            written_to,
+            spec,
+        }))
+    }
+}
@@ -0,0 +1,425 @@
+//! `check_store_compliance`: the Aurora store's naming, install path and
+//! privilege rules, checked across the spec, its `%files` list and the
+//! desktop file, with a verdict per rule.
+
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::desktop;
+use super::lint::{ICON_SIZES, parse_spec};
+use super::project::ProjectInfo;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Name prefixes kept for the platform vendor and other stores.
+const RESERVED_PREFIXES: &[&str] = &["ru.omp.", "ru.auroraos.", "com.jolla.", "harbour-"];
+
+/// Scriptlet sections; store packages may not run code at install time.
+const SCRIPTLETS: &[&str] = &[
+    "%pre",
+    "%post",
+    "%preun",
+    "%postun",
+    "%pretrans",
+    "%posttrans",
+];
+
+/// `%files` macros and what they expand to on Aurora OS.
+const PATH_MACROS: &[(&str, &str)] = &[
+    ("_bindir", "/usr/bin"),
+    ("_datadir", "/usr/share"),
+    ("_libdir", "/usr/lib"),
+    ("_libexecdir", "/usr/libexec"),
+    ("_sysconfdir", "/etc"),
+    ("_localstatedir", "/var"),
+    ("_prefix", "/usr"),
+];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct StoreComplianceParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Spec file, absolute or relative to the project (default: the one in
+    /// `rpm/`).
+    #[serde(default)]
+    pub spec: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Violation {
+    pub file: PathBuf,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RuleResult {
+    /// Rule id, e.g. `install-paths`.
+    pub rule: String,
+    pub description: String,
+    pub passed: bool,
+    pub violations: Vec<Violation>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ComplianceReport {
+    pub package: String,
+    pub spec: PathBuf,
+    pub desktop: Option<PathBuf>,
+    /// Every rule passed.
+    pub passed: bool,
+    pub rules: Vec<RuleResult>,
+}
+
+struct Rules {
+    results: Vec<RuleResult>,
+}
+
+impl Rules {
+    fn rule(&mut self, rule: &str, description: &str) {
+        self.results.push(RuleResult {
+            rule: rule.to_string(),
+            description: description.to_string(),
+            passed: true,
+            violations: Vec::new(),
+        });
+    }
+
+    fn violation(&mut self, rule: &str, file: &Path, line: Option<usize>, message: String) {
+        if let Some(result) = self.results.iter_mut().find(|r| r.rule == rule) {
+            result.passed = false;
+            result.violations.push(Violation {
+                file: file.to_path_buf(),
+                line,
+                message,
+            });
+        }
+    }
+}
+
+/// A `%files` entry with its directives removed and path macros expanded;
+/// `None` for directive-only and relative (documentation) entries.
+fn expand_entry(entry: &str, name: &str) -> Option<String> {
+    let mut rest = entry.trim();
+    while let Some(directive) = rest.strip_prefix('%')
+        && !directive.starts_with('{')
+    {
+        let end = directive
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(directive.len());
+        let mut after = &directive[end..];
+        if let Some(args) = after.strip_prefix('(') {
+            after = args.split_once(')').map_or("", |(_, after)| after);
+        }
+        rest = after.trim_start();
+    }
+    let mut path = rest.to_string();
+    for (name_macro, value) in [("name", name)]
+        .into_iter()
+        .chain(PATH_MACROS.iter().copied())
+    {
+        path = path
+            .replace(&format!("%{{{name_macro}}}"), value)
+            .replace(&format!("%{name_macro}"), value);
+    }
+    path.starts_with('/').then_some(path)
+}
+
+/// The `%attr(mode, user, group)` of an entry, if it sets one.
+fn attr(entry: &str) -> Option<(&str, &str, &str)> {
+    let at = entry.find("%attr(")?;
+    let args = entry[at + "%attr(".len()..].split_once(')')?.0;
+    let mut parts = args.split(',').map(str::trim);
+    Some((parts.next()?, parts.next()?, parts.next()?))
+}
+
+fn is_icon_path(path: &str, name: &str) -> bool {
+    let Some(rest) = path.strip_prefix("/usr/share/icons/hicolor/") else {
+        return false;
+    };
+    let Some((size, file)) = rest.split_once("/apps/") else {
+        return false;
+    };
+    (size == "*" || ICON_SIZES.contains(&size)) && file == format!("{name}.png")
+}
+
+pub fn check(dir: &Path, spec_path: &Path) -> Result<ComplianceReport> {
+    let text = std::fs::read_to_string(spec_path)
+        .map_err(|err| Error::InvalidArgument(format!("{}: {err}", spec_path.display())))?;
+    let spec = parse_spec(&text);
+    let info = ProjectInfo::inspect(dir);
+    let (name_line, name) = spec
+        .tag("Name")
+        .map(|(line, name)| (Some(line), name.to_string()))
+        .unwrap_or((None, String::new()));
+
+    let mut rules = Rules {
+        results: Vec::new(),
+    };
+    rules.rule(
+        "package-name",
+        "Name is <organization>.<application>: dot-separated parts starting with a letter, organization in lower case",
+    );
+    rules.rule(
+        "reserved-prefix",
+        "Name does not use a prefix reserved for the platform or other stores",
+    );
+    rules.rule(
+        "install-paths",
+        "Files install only to /usr/bin/<name>, /usr/share/<name>, the desktop file and launcher icons",
+    );
+    rules.rule("binary-name", "The only binary is /usr/bin/<name>");
+    rules.rule(
+        "desktop-file",
+        "<name>.desktop exists and is installed to /usr/share/applications",
+    );
+    rules.rule(
+        "icon-names",
+        "Icons install to /usr/share/icons/hicolor/<size>/apps/<name>.png",
+    );
+    rules.rule(
+        "sandbox-identity",
+        "The desktop file's OrganizationName.ApplicationName equals Name",
+    );
+    rules.rule(
+        "no-scriptlets",
+        "The spec has no install or removal scriptlets",
+    );
+    rules.rule(
+        "no-privileges",
+        "No setuid/setgid files, file capabilities or non-root ownership",
+    );
+
+    let parts: Vec<&str> = name.split('.').collect();
+    let valid_part = |part: &str| {
+        part.starts_with(|c: char| c.is_ascii_alphabetic())
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    };
+    if parts.len() < 2
+        || !parts.iter().all(|part| valid_part(part))
+        || parts[0].chars().any(|c| c.is_ascii_uppercase())
+    {
+        rules.violation(
+            "package-name",
+            spec_path,
+            name_line,
+            format!("'{name}' is not <organization>.<application>, e.g. ru.example.notes"),
+        );
+    }
+    if let Some(prefix) = RESERVED_PREFIXES
+        .iter()
+        .find(|prefix| name.starts_with(*prefix))
+    {
+        rules.violation(
+            "reserved-prefix",
+            spec_path,
+            name_line,
+            format!("'{name}' starts with the reserved prefix {prefix}"),
+        );
+    }
+
+    let files = spec.section("%files");
+    let mut desktop_installed = false;
+    for (line, entry) in files.iter().flat_map(|section| &section.body) {
+        let entry = entry.trim();
+        if entry.is_empty() || entry.starts_with('#') {
+            continue;
+        }
+        if let Some((mode, user, group)) = attr(entry) {
+            let mode = u32::from_str_radix(mode, 8).unwrap_or_default();
+            if mode & 0o6000 != 0 {
+                rules.violation(
+                    "no-privileges",
+                    spec_path,
+                    Some(*line),
+                    format!("{entry}: sets the setuid or setgid bit"),
+                );
+            }
+            if ![user, group]
+                .iter()
+                .all(|owner| matches!(*owner, "-" | "root"))
+            {
+                rules.violation(
+                    "no-privileges",
+                    spec_path,
+                    Some(*line),
+                    format!("{entry}: changes the owner to {user}:{group}"),
+                );
+            }
+        }
+        if entry.contains("%caps(") {
+            rules.violation(
+                "no-privileges",
+                spec_path,
+                Some(*line),
+                format!("{entry}: grants file capabilities"),
+            );
+        }
+        let Some(path) = expand_entry(entry, &name) else {
+            continue;
+        };
+        let data_dir = format!("/usr/share/{name}");
+        if path == data_dir || path.starts_with(&format!("{data_dir}/")) {
+            continue;
+        }
+        if let Some(binary) = path.strip_prefix("/usr/bin/") {
+            if binary != name {
+                rules.violation(
+                    "binary-name",
+                    spec_path,
+                    Some(*line),
+                    format!("{path}: binaries must be named /usr/bin/{name}"),
+                );
+            }
+        } else if let Some(file) = path.strip_prefix("/usr/share/applications/") {
+            if file == format!("{name}.desktop") {
+                desktop_installed = true;
+            } else {
+                rules.violation(
+                    "desktop-file",
+                    spec_path,
+                    Some(*line),
+                    format!("{path}: the only desktop file allowed is {name}.desktop"),
+                );
+            }
+        } else if path.starts_with("/usr/share/icons/") {
+            if !is_icon_path(&path, &name) {
+                rules.violation(
+                    "icon-names",
+                    spec_path,
+                    Some(*line),
+                    format!(
+                        "{path}: use /usr/share/icons/hicolor/<size>/apps/{name}.png with sizes {}",
+                        ICON_SIZES.join(", ")
+                    ),
+                );
+            }
+        } else {
+            rules.violation(
+                "install-paths",
+                spec_path,
+                Some(*line),
+                format!("{path}: outside /usr/share/{name}"),
+            );
+        }
+    }
+    if !desktop_installed {
+        rules.violation(
+            "desktop-file",
+            spec_path,
+            files.map(|section| section.line),
+            format!("%files does not install /usr/share/applications/{name}.desktop"),
+        );
+    }
+
+    let desktop_path = info.desktop_file.clone();
+    match &desktop_path {
+        Some(path) => {
+            let expected = format!("{name}.desktop");
+            if path.file_name().is_none_or(|file| *file != *expected) {
+                rules.violation(
+                    "desktop-file",
+                    path,
+                    None,
+                    format!("the desktop file should be named {expected}"),
+                );
+            }
+            let text = std::fs::read_to_string(path)?;
+            let entries = desktop::parse(&text);
+            let identity = ["X-Application", "X-Sailjail"].iter().find_map(|group| {
+                let organization = desktop::find(&entries, group, "OrganizationName")?;
+                let application = desktop::find(&entries, group, "ApplicationName")?;
+                Some((organization, application))
+            });
+            match identity {
+                Some((organization, application)) => {
+                    let id = format!("{}.{}", organization.value, application.value);
+                    if id != name {
+                        rules.violation(
+                            "sandbox-identity",
+                            path,
+                            Some(organization.line),
+                            format!("{id} differs from the package name {name}"),
+                        );
+                    }
+                }
+                None => rules.violation(
+                    "sandbox-identity",
+                    path,
+                    None,
+                    "no OrganizationName and ApplicationName in [X-Application]".to_string(),
+                ),
+            }
+        }
+        None => {
+            rules.violation(
+                "desktop-file",
+                dir,
+                None,
+                format!("the project has no {name}.desktop"),
+            );
+            rules.violation(
+                "sandbox-identity",
+                dir,
+                None,
+                "without a desktop file the application has no sandbox identity".to_string(),
+            );
+        }
+    }
+
+    for section in spec
+        .sections
+        .iter()
+        .filter(|section| SCRIPTLETS.contains(&section.name))
+    {
+        rules.violation(
+            "no-scriptlets",
+            spec_path,
+            Some(section.line),
+            format!(
+                "{} scriptlets are not allowed in store packages",
+                section.name
+            ),
+        );
+    }
+
+    Ok(ComplianceReport {
+        passed: rules.results.iter().all(|result| result.passed),
+        package: name,
+        spec: spec_path.to_path_buf(),
+        desktop: desktop_path,
+        rules: rules.results,
+    })
+}
+
+#[tool_router(router = store_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Check a project against the Aurora store's conventions: package name format and reserved prefixes, install paths limited to /usr/bin/<name>, /usr/share/<name>, the desktop file and launcher icons, the desktop file's sandbox identity, and no scriptlets, setuid files, capabilities or ownership changes. Returns pass/fail per rule with the offending lines.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn check_store_compliance(
+        &self,
+        Parameters(params): Parameters<StoreComplianceParams>,
+    ) -> Result<Json<ComplianceReport>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        let spec = match params.spec {
+            Some(spec) => dir.join(spec),
+            None => ProjectInfo::inspect(&dir).spec_file.ok_or_else(|| {
+                Error::InvalidArgument(format!(
+                    "no .spec file in {}; pass spec or create one with generate_spec",
+                    dir.join("rpm").display()
+                ))
+            })?,
+        };
+        check(&dir, &spec).map(Json)
+    }
+}
@@ -0,0 +1,263 @@
+//! `sync_check`: the application's name, organization, version and icon as
+//! each project file states them, so drift between the spec, desktop file,
+//! project file, sources and translations shows up before packaging.
+
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::desktop;
+use super::lint::parse_spec;
+use super::project::{BuildSystem, ProjectInfo, find_files};
+use crate::error::Result;
+use crate::server::AuroraServer;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SyncCheckParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StatedValue {
+    pub file: PathBuf,
+    pub line: Option<usize>,
+    /// Where in the file, e.g. `Name`, `TARGET`, `Icon`.
+    pub source: String,
+    pub value: String,
+    pub matches: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FieldCheck {
+    /// `name`, `organization`, `application`, `version`, `icon` or
+    /// `translations`.
+    pub field: String,
+    /// The value the others should agree with: the spec's, else the
+    /// project file's; the package name for icons and translations.
+    pub expected: Option<String>,
+    pub consistent: bool,
+    pub values: Vec<StatedValue>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SyncReport {
+    pub project: PathBuf,
+    /// Every field is consistent.
+    pub passed: bool,
+    pub fields: Vec<FieldCheck>,
+}
+
+/// Values stated for one field, collected before comparing.
+struct Field {
+    name: &'static str,
+    values: Vec<(PathBuf, Option<usize>, String, String)>,
+}
+
+impl Field {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            values: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, file: &Path, line: Option<usize>, source: &str, value: &str) {
+        self.values.push((
+            file.to_path_buf(),
+            line,
+            source.to_string(),
+            value.to_string(),
+        ));
+    }
+
+    /// Checks each value with `matches`, against `expected`.
+    fn check_with(self, expected: Option<String>, matches: impl Fn(&str) -> bool) -> FieldCheck {
+        let values: Vec<StatedValue> = self
+            .values
+            .into_iter()
+            .map(|(file, line, source, value)| StatedValue {
+                matches: matches(&value),
+                file,
+                line,
+                source,
+                value,
+            })
+            .collect();
+        FieldCheck {
+            field: self.name.to_string(),
+            expected,
+            consistent: values.iter().all(|value| value.matches),
+            values,
+        }
+    }
+
+    /// Checks that every value equals the first.
+    fn check(self) -> FieldCheck {
+        let expected = self.first();
+        let wanted = expected.clone();
+        self.check_with(expected, move |value| wanted.as_deref() == Some(value))
+    }
+
+    fn first(&self) -> Option<String> {
+        self.values.first().map(|(_, _, _, value)| value.clone())
+    }
+}
+
+/// The string literal passed to `call(` in C++ `text`, with its line.
+fn call_argument(text: &str, call: &str) -> Option<(usize, String)> {
+    text.lines().enumerate().find_map(|(index, line)| {
+        let rest = &line[line.find(&format!("{call}("))? + call.len() + 1..];
+        let start = rest.find('"')? + 1;
+        let end = start + rest[start..].find('"')?;
+        Some((index + 1, rest[start..end].to_string()))
+    })
+}
+
+pub fn check(dir: &Path) -> Result<SyncReport> {
+    let info = ProjectInfo::inspect(dir);
+    let mut name = Field::new("name");
+    let mut organization = Field::new("organization");
+    let mut application = Field::new("application");
+    let mut version = Field::new("version");
+    let mut icon = Field::new("icon");
+
+    let spec_text = match &info.spec_file {
+        Some(spec) => std::fs::read_to_string(spec)?,
+        None => String::new(),
+    };
+    let spec = parse_spec(&spec_text);
+    if let Some(spec_file) = &info.spec_file {
+        if let Some((line, value)) = spec.tag("Name") {
+            name.add(spec_file, Some(line), "Name", value);
+            if let Some((org, app)) = value.rsplit_once('.') {
+                organization.add(spec_file, Some(line), "Name", org);
+                application.add(spec_file, Some(line), "Name", app);
+            }
+        }
+        if let Some((line, value)) = spec.tag("Version") {
+            version.add(spec_file, Some(line), "Version", value);
+        }
+    }
+    if let Some(project_file) = &info.project_file {
+        let source = match info.build_system {
+            Some(BuildSystem::Qmake) => "TARGET",
+            _ => "project",
+        };
+        name.add(project_file, None, source, &info.name);
+        if let Some(value) = &info.version {
+            version.add(project_file, None, "VERSION", value);
+        }
+    }
+
+    if let Some(path) = &info.desktop_file {
+        let text = std::fs::read_to_string(path)?;
+        let entries = desktop::parse(&text);
+        if let Some(stem) = path.file_stem() {
+            name.add(path, None, "file name", &stem.to_string_lossy());
+        }
+        if let Some(exec) = desktop::find(&entries, "Desktop Entry", "Exec") {
+            let binary = exec.value.split_whitespace().find(|word| {
+                !word.starts_with('-')
+                    && !word.ends_with("invoker")
+                    && !word.ends_with("sailfish-qml")
+                    && !word.contains('=')
+            });
+            if let Some(binary) = binary {
+                let binary = binary.rsplit('/').next().unwrap_or(binary);
+                name.add(path, Some(exec.line), "Exec", binary);
+            }
+        }
+        if let Some(entry) = desktop::find(&entries, "Desktop Entry", "Icon") {
+            icon.add(path, Some(entry.line), "Icon", entry.value);
+        }
+        for group in ["X-Application", "X-Sailjail"] {
+            if let Some(entry) = desktop::find(&entries, group, "OrganizationName") {
+                organization.add(path, Some(entry.line), "OrganizationName", entry.value);
+            }
+            if let Some(entry) = desktop::find(&entries, group, "ApplicationName") {
+                application.add(path, Some(entry.line), "ApplicationName", entry.value);
+            }
+        }
+    }
+
+    for source in find_files(&dir.join("src"), "cpp", 4) {
+        let text = std::fs::read_to_string(&source)?;
+        if let Some((line, value)) = call_argument(&text, "setOrganizationName") {
+            organization.add(&source, Some(line), "setOrganizationName", &value);
+        }
+        if let Some((line, value)) = call_argument(&text, "setApplicationName") {
+            application.add(&source, Some(line), "setApplicationName", &value);
+        }
+    }
+
+    if let Some(qml_dir) = &info.qml_dir {
+        // The main QML file, named after the package, is the one the
+        // launcher loads and the translation context of its strings.
+        for file in find_files(qml_dir, "qml", 0) {
+            let stem = file
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if stem.contains('.') {
+                name.add(&file, None, "main QML file", &stem);
+            }
+        }
+    }
+
+    let icons = find_files(&dir.join("icons"), "png", 2);
+    for file in &icons {
+        if let Some(stem) = file.file_stem() {
+            icon.add(file, None, "icon file", &stem.to_string_lossy());
+        }
+    }
+
+    let mut translations = Field::new("translations");
+    for file in &info.translations {
+        if let Some(stem) = file.file_stem() {
+            translations.add(file, None, "file name", &stem.to_string_lossy());
+        }
+    }
+
+    let package = name.first();
+    let is_package = |value: &str| package.as_deref() == Some(value);
+    let is_translation = |stem: &str| {
+        package.as_deref().is_some_and(|package| {
+            stem.strip_prefix(package)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+        })
+    };
+    let fields = vec![
+        name.check(),
+        organization.check(),
+        application.check(),
+        version.check(),
+        icon.check_with(package.clone(), is_package),
+        translations.check_with(package.clone(), is_translation),
+    ];
+    Ok(SyncReport {
+        project: dir.to_path_buf(),
+        passed: fields.iter().all(|field| field.consistent),
+        fields,
+    })
+}
+
+#[tool_router(router = sync_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Cross-check the application name, organization, version and icon across the .spec, .desktop file, .pro/CMakeLists.txt, the C++ setOrganizationName/setApplicationName calls, the main QML file and the translation file names. Lists every stated value with file and line, flagging those that drifted.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn sync_check(
+        &self,
+        Parameters(params): Parameters<SyncCheckParams>,
+    ) -> Result<Json<SyncReport>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        check(&dir).map(Json)
+    }
+}
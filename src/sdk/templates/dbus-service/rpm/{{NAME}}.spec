@@ -0,0 +1,29 @@
+Name:       {{NAME}}
+Summary:    {{TITLE}}
+Version:    0.1.0
+Release:    1
+License:    BSD-3-Clause
+Source0:    %{name}-%{version}.tar.bz2
+
+BuildRequires:  pkgconfig(Qt5Core)
+BuildRequires:  pkgconfig(Qt5DBus)
+
+%description
+{{TITLE}}.
+
+%prep
+%autosetup
+
+%build
+%qtc_qmake5
+%qtc_make %{?_smp_mflags}
+
+%install
+%qmake5_install
+
+%files
+%defattr(-,root,root,-)
+%{_bindir}/%{name}
+%defattr(644,root,root,-)
+%{_datadir}/dbus-1/services/%{name}.service
+%{_userunitdir}/%{name}.service
@@ -0,0 +1,24 @@
+#include <QtCore/QCoreApplication>
+#include <QtDBus/QDBusConnection>
+#include <QtCore/QDebug>
+
+#include "service.h"
+
+int main(int argc, char *argv[])
+{
+    QCoreApplication application(argc, argv);
+
+    Service service;
+    QDBusConnection bus = QDBusConnection::sessionBus();
+    if (!bus.registerObject(QStringLiteral("{{DBUS_PATH}}"), &service,
+                            QDBusConnection::ExportScriptableSlots)) {
+        qCritical() << "Cannot register object:" << bus.lastError().message();
+        return 1;
+    }
+    if (!bus.registerService(QStringLiteral("{{NAME}}"))) {
+        qCritical() << "Cannot register service:" << bus.lastError().message();
+        return 1;
+    }
+
+    return application.exec();
+}
@@ -0,0 +1,11 @@
+#include "service.h"
+
+Service::Service(QObject *parent)
+    : QObject(parent)
+{
+}
+
+QString Service::ping(const QString &message)
+{
+    return message;
+}
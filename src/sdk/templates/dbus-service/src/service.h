@@ -0,0 +1,18 @@
+#ifndef SERVICE_H
+#define SERVICE_H
+
+#include <QtCore/QObject>
+
+class Service : public QObject
+{
+    Q_OBJECT
+    Q_CLASSINFO("D-Bus Interface", "{{NAME}}")
+
+public:
+    explicit Service(QObject *parent = nullptr);
+
+public slots:
+    Q_SCRIPTABLE QString ping(const QString &message);
+};
+
+#endif // SERVICE_H
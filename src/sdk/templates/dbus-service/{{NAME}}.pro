@@ -0,0 +1,27 @@
+TARGET = {{NAME}}
+
+QT = core dbus
+CONFIG += console
+CONFIG -= app_bundle
+
+HEADERS += \
+    src/service.h
+
+SOURCES += \
+    src/main.cpp \
+    src/service.cpp
+
+DISTFILES += \
+    rpm/{{NAME}}.spec \
+    dbus/{{NAME}}.service \
+    systemd/{{NAME}}.service
+
+target.path = /usr/bin
+
+dbus.files = dbus/{{NAME}}.service
+dbus.path = /usr/share/dbus-1/services
+
+systemd.files = systemd/{{NAME}}.service
+systemd.path = /usr/lib/systemd/user
+
+INSTALLS += target dbus systemd
@@ -0,0 +1,42 @@
+Name:       {{NAME}}
+Summary:    {{TITLE}}
+Version:    0.1.0
+Release:    1
+License:    BSD-3-Clause
+Source0:    %{name}-%{version}.tar.bz2
+
+BuildRequires:  pkgconfig(Qt5Core)
+
+%description
+{{TITLE}}.
+
+%package devel
+Summary:    Development files for %{name}
+Requires:   %{name} = %{version}-%{release}
+
+%description devel
+Headers and pkg-config file for %{name}.
+
+%prep
+%autosetup
+
+%build
+%qtc_qmake5
+%qtc_make %{?_smp_mflags}
+
+%install
+%qmake5_install
+
+%post -p /sbin/ldconfig
+
+%postun -p /sbin/ldconfig
+
+%files
+%defattr(-,root,root,-)
+%{_libdir}/lib{{APP}}.so.*
+
+%files devel
+%defattr(-,root,root,-)
+%{_includedir}/{{APP}}
+%{_libdir}/lib{{APP}}.so
+%{_libdir}/pkgconfig/{{APP}}.pc
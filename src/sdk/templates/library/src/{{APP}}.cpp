@@ -0,0 +1,6 @@
+#include "{{APP}}.h"
+
+QString {{CLASS}}::version()
+{
+    return QStringLiteral("0.1.0");
+}
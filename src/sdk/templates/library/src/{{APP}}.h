@@ -0,0 +1,14 @@
+#ifndef {{MACRO}}_H
+#define {{MACRO}}_H
+
+#include <QtCore/QString>
+
+#include "{{APP}}_global.h"
+
+class {{MACRO}}_EXPORT {{CLASS}}
+{
+public:
+    static QString version();
+};
+
+#endif // {{MACRO}}_H
@@ -0,0 +1,12 @@
+#ifndef {{MACRO}}_GLOBAL_H
+#define {{MACRO}}_GLOBAL_H
+
+#include <QtCore/qglobal.h>
+
+#if defined({{MACRO}}_LIBRARY)
+#  define {{MACRO}}_EXPORT Q_DECL_EXPORT
+#else
+#  define {{MACRO}}_EXPORT Q_DECL_IMPORT
+#endif
+
+#endif // {{MACRO}}_GLOBAL_H
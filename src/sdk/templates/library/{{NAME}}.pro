@@ -0,0 +1,30 @@
+TEMPLATE = lib
+TARGET = {{APP}}
+VERSION = 0.1.0
+
+QT = core
+CONFIG += shared
+DEFINES += {{MACRO}}_LIBRARY
+
+HEADERS += \
+    src/{{APP}}_global.h \
+    src/{{APP}}.h
+
+SOURCES += \
+    src/{{APP}}.cpp
+
+DISTFILES += \
+    rpm/{{NAME}}.spec \
+    {{APP}}.pc.in
+
+target.path = $$[QT_INSTALL_LIBS]
+
+headers.files = src/{{APP}}_global.h src/{{APP}}.h
+headers.path = /usr/include/{{APP}}
+
+pkgconfig.files = {{APP}}.pc
+pkgconfig.path = $$[QT_INSTALL_LIBS]/pkgconfig
+
+QMAKE_SUBSTITUTES += {{APP}}.pc.in
+
+INSTALLS += target headers pkgconfig
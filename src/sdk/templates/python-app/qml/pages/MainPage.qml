@@ -0,0 +1,65 @@
+import QtQuick 2.0
+import Sailfish.Silica 1.0
+import io.thp.pyotherside 1.5
+
+Page {
+    objectName: "mainPage"
+    allowedOrientations: Orientation.All
+
+    property int taps: 0
+    property string greeting
+
+    function refresh() {
+        python.call("{{MODULE}}.greet", [taps], function(text) { greeting = text })
+    }
+
+    onTapsChanged: refresh()
+
+    Python {
+        id: python
+
+        Component.onCompleted: {
+            addImportPath(Qt.resolvedUrl("../py"))
+            setHandler("reset", function() { taps = 0 })
+            importModule("{{MODULE}}", function() { refresh() })
+        }
+
+        onError: console.warn("python error: " + traceback)
+    }
+
+    SilicaFlickable {
+        anchors.fill: parent
+        contentHeight: column.height
+
+        PullDownMenu {
+            MenuItem {
+                text: qsTr("Reset")
+                onClicked: python.call("{{MODULE}}.reset", [])
+            }
+        }
+
+        Column {
+            id: column
+            width: parent.width
+            spacing: Theme.paddingLarge
+
+            PageHeader {
+                objectName: "pageHeader"
+                title: qsTr("{{TITLE}}")
+            }
+
+            Label {
+                objectName: "greetingLabel"
+                anchors.horizontalCenter: parent.horizontalCenter
+                text: greeting
+            }
+
+            Button {
+                objectName: "tapButton"
+                anchors.horizontalCenter: parent.horizontalCenter
+                text: qsTr("Tap")
+                onClicked: taps++
+            }
+        }
+    }
+}
@@ -0,0 +1,15 @@
+"""Backend of {{TITLE}}, called from QML through PyOtherSide."""
+
+import pyotherside
+
+
+def greet(taps):
+    """Returns the text for the main page after `taps` taps."""
+    if taps == 0:
+        return "Hello from Python"
+    return "Tapped {} time(s)".format(taps)
+
+
+def reset():
+    """Tells QML that the counter was reset."""
+    pyotherside.send("reset")
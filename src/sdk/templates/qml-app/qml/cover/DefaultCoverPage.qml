@@ -0,0 +1,11 @@
+import QtQuick 2.0
+import Sailfish.Silica 1.0
+
+CoverBackground {
+    objectName: "defaultCover"
+
+    CoverPlaceholder {
+        objectName: "placeholder"
+        text: qsTr("{{TITLE}}")
+    }
+}
@@ -0,0 +1,39 @@
+import QtQuick 2.0
+import Sailfish.Silica 1.0
+
+Page {
+    objectName: "mainPage"
+    allowedOrientations: Orientation.All
+
+    property int taps: 0
+
+    SilicaFlickable {
+        anchors.fill: parent
+        contentHeight: column.height
+
+        PullDownMenu {
+            MenuItem {
+                text: qsTr("Reset")
+                onClicked: taps = 0
+            }
+        }
+
+        Column {
+            id: column
+            width: parent.width
+            spacing: Theme.paddingLarge
+
+            PageHeader {
+                objectName: "pageHeader"
+                title: qsTr("{{TITLE}}")
+            }
+
+            Button {
+                objectName: "tapButton"
+                anchors.horizontalCenter: parent.horizontalCenter
+                text: qsTr("Tapped %n time(s)", "", taps)
+                onClicked: taps++
+            }
+        }
+    }
+}
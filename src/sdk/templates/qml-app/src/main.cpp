@@ -0,0 +1,15 @@
+#include <auroraapp.h>
+#include <QtQuick>
+
+int main(int argc, char *argv[])
+{
+    QScopedPointer<QGuiApplication> application(Aurora::Application::application(argc, argv));
+    application->setOrganizationName(QStringLiteral("{{ORG}}"));
+    application->setApplicationName(QStringLiteral("{{APP}}"));
+
+    QScopedPointer<QQuickView> view(Aurora::Application::createView());
+    view->setSource(Aurora::Application::pathTo(QStringLiteral("qml/{{NAME}}.qml")));
+    view->show();
+
+    return application->exec();
+}
@@ -0,0 +1,18 @@
+import QtQuick 2.0
+import Sailfish.Silica 1.0
+
+Page {
+    objectName: "mainPage"
+    allowedOrientations: Orientation.All
+
+    PageHeader {
+        objectName: "pageHeader"
+        title: qsTr("{{TITLE}}")
+    }
+
+    Label {
+        objectName: "greeting"
+        anchors.centerIn: parent
+        text: greeter.greeting("Aurora OS")
+    }
+}
@@ -0,0 +1,11 @@
+#include "greeter.h"
+
+Greeter::Greeter(QObject *parent)
+    : QObject(parent)
+{
+}
+
+QString Greeter::greeting(const QString &name) const
+{
+    return tr("Hello, %1!").arg(name);
+}
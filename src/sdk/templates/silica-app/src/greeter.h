@@ -0,0 +1,16 @@
+#ifndef GREETER_H
+#define GREETER_H
+
+#include <QtCore/QObject>
+
+class Greeter : public QObject
+{
+    Q_OBJECT
+
+public:
+    explicit Greeter(QObject *parent = nullptr);
+
+    Q_INVOKABLE QString greeting(const QString &name) const;
+};
+
+#endif // GREETER_H
@@ -0,0 +1,19 @@
+#include <auroraapp.h>
+#include <QtQuick>
+
+#include "greeter.h"
+
+int main(int argc, char *argv[])
+{
+    QScopedPointer<QGuiApplication> application(Aurora::Application::application(argc, argv));
+    application->setOrganizationName(QStringLiteral("{{ORG}}"));
+    application->setApplicationName(QStringLiteral("{{APP}}"));
+
+    Greeter greeter;
+    QScopedPointer<QQuickView> view(Aurora::Application::createView());
+    view->rootContext()->setContextProperty(QStringLiteral("greeter"), &greeter);
+    view->setSource(Aurora::Application::pathTo(QStringLiteral("qml/{{NAME}}.qml")));
+    view->show();
+
+    return application->exec();
+}
@@ -0,0 +1,25 @@
+TARGET = {{NAME}}
+
+CONFIG += \
+    auroraapp \
+    auroraapp_i18n
+
+HEADERS += \
+    src/greeter.h
+
+SOURCES += \
+    src/greeter.cpp \
+    src/main.cpp
+
+DISTFILES += \
+    rpm/{{NAME}}.spec \
+    {{NAME}}.desktop \
+    qml/{{NAME}}.qml \
+    qml/pages/MainPage.qml \
+    qml/cover/DefaultCoverPage.qml
+
+AURORAAPP_ICONS = 86x86 108x108 128x128 172x172
+
+TRANSLATIONS += \
+    translations/{{NAME}}.ts \
+    translations/{{NAME}}-ru.ts
@@ -0,0 +1,322 @@
+//! `update_translations` and `translation_status`: extracting strings into
+//! the project's `.ts` files with `lupdate`, and how complete each language
+//! is.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::project::{BuildSystem, ProjectInfo};
+use super::run_logged;
+use crate::device::tail_lines;
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+const LOG_TAIL_LINES: usize = 20;
+/// Untranslated messages listed per file.
+const MAX_UNTRANSLATED: usize = 50;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TranslationStatusParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Only this language, e.g. `ru`.
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UpdateTranslationsParams {
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Build target whose `lupdate` to use when there is none on the host
+    /// (default: the configured one).
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Drop messages whose source string no longer exists
+    /// (`-no-obsolete`).
+    #[serde(default)]
+    pub no_obsolete: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UntranslatedMessage {
+    pub context: String,
+    pub source: String,
+    /// `file:line` of the string in the sources.
+    pub location: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TranslationFile {
+    pub file: PathBuf,
+    /// Language code, or `default` for the source-language file.
+    pub language: String,
+    /// Current messages, excluding obsolete ones.
+    pub total: usize,
+    pub finished: usize,
+    /// Messages with an empty or unfinished translation.
+    pub unfinished: usize,
+    /// Messages whose source string no longer exists.
+    pub obsolete: usize,
+    pub percent: f64,
+    pub untranslated: Vec<UntranslatedMessage>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ContextCompleteness {
+    pub context: String,
+    pub total: usize,
+    /// Percent finished, by language.
+    pub languages: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TranslationStatus {
+    pub project: PathBuf,
+    pub files: Vec<TranslationFile>,
+    /// Completeness of each translation context in each language.
+    pub matrix: Vec<ContextCompleteness>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TranslationUpdate {
+    /// The `lupdate` command that ran.
+    pub command: String,
+    pub success: bool,
+    pub exit_status: i32,
+    pub log_tail: Vec<String>,
+    pub status: TranslationStatus,
+}
+
+fn percent(finished: usize, total: usize) -> f64 {
+    if total == 0 {
+        return 100.0;
+    }
+    (finished as f64 * 1000.0 / total as f64).round() / 10.0
+}
+
+/// Finished and current messages of one context in one file.
+struct ContextCount {
+    name: String,
+    finished: usize,
+    total: usize,
+}
+
+/// Per-file counts plus the counts of each context.
+fn parse_ts(file: &Path) -> Result<(TranslationFile, Vec<ContextCount>)> {
+    let text = std::fs::read_to_string(file)?;
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    let doc = roxmltree::Document::parse_with_options(&text, options)
+        .map_err(|err| Error::InvalidArgument(format!("{}: {err}", file.display())))?;
+    let root = doc.root_element();
+    let language = root
+        .attribute("language")
+        .filter(|language| !language.is_empty())
+        .map(str::to_string)
+        .or_else(|| {
+            let stem = file.file_stem()?.to_string_lossy().into_owned();
+            let (_, suffix) = stem.rsplit_once('-')?;
+            (suffix.len() <= 5 && !suffix.contains('.')).then(|| suffix.to_string())
+        })
+        .unwrap_or_else(|| "default".to_string());
+
+    let mut ts = TranslationFile {
+        file: file.to_path_buf(),
+        language,
+        total: 0,
+        finished: 0,
+        unfinished: 0,
+        obsolete: 0,
+        percent: 100.0,
+        untranslated: Vec::new(),
+    };
+    let mut contexts = Vec::new();
+    let child_text = |node: roxmltree::Node, tag: &str| {
+        node.children()
+            .find(|child| child.has_tag_name(tag))
+            .map(|child| {
+                child
+                    .descendants()
+                    .filter(|node| node.is_text())
+                    .filter_map(|node| node.text())
+                    .collect::<String>()
+            })
+    };
+    for context in root.children().filter(|node| node.has_tag_name("context")) {
+        let name = child_text(context, "name").unwrap_or_default();
+        let (mut finished, mut total) = (0, 0);
+        for message in context
+            .children()
+            .filter(|node| node.has_tag_name("message"))
+        {
+            let translation = message
+                .children()
+                .find(|node| node.has_tag_name("translation"));
+            let kind = translation.and_then(|node| node.attribute("type"));
+            if matches!(kind, Some("obsolete" | "vanished")) {
+                ts.obsolete += 1;
+                continue;
+            }
+            total += 1;
+            let translated =
+                child_text(message, "translation").is_some_and(|text| !text.trim().is_empty());
+            if kind.is_none() && translated {
+                finished += 1;
+                continue;
+            }
+            ts.unfinished += 1;
+            if ts.untranslated.len() < MAX_UNTRANSLATED {
+                let location = message
+                    .children()
+                    .find(|node| node.has_tag_name("location"))
+                    .map(|node| {
+                        format!(
+                            "{}:{}",
+                            node.attribute("filename").unwrap_or(""),
+                            node.attribute("line").unwrap_or("")
+                        )
+                    });
+                ts.untranslated.push(UntranslatedMessage {
+                    context: name.clone(),
+                    source: child_text(message, "source").unwrap_or_default(),
+                    location,
+                });
+            }
+        }
+        ts.finished += finished;
+        ts.total += total;
+        contexts.push(ContextCount {
+            name,
+            finished,
+            total,
+        });
+    }
+    ts.percent = percent(ts.finished, ts.total);
+    Ok((ts, contexts))
+}
+
+pub fn status(dir: &Path, language: Option<&str>) -> Result<TranslationStatus> {
+    let info = ProjectInfo::inspect(dir);
+    let mut files = Vec::new();
+    let mut matrix: Vec<ContextCompleteness> = Vec::new();
+    for file in &info.translations {
+        let (ts, contexts) = parse_ts(file)?;
+        if language.is_some_and(|language| language != ts.language) {
+            continue;
+        }
+        for ContextCount {
+            name: context,
+            finished,
+            total,
+        } in contexts
+        {
+            let row = match matrix.iter_mut().position(|row| row.context == context) {
+                Some(at) => &mut matrix[at],
+                None => {
+                    matrix.push(ContextCompleteness {
+                        context,
+                        total,
+                        languages: BTreeMap::new(),
+                    });
+                    matrix.last_mut().expect("just pushed")
+                }
+            };
+            row.total = row.total.max(total);
+            row.languages
+                .insert(ts.language.clone(), percent(finished, total));
+        }
+        files.push(ts);
+    }
+    matrix.sort_by(|a, b| a.context.cmp(&b.context));
+    Ok(TranslationStatus {
+        project: dir.to_path_buf(),
+        files,
+        matrix,
+    })
+}
+
+#[tool_router(router = translations_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Translation status of a project's .ts files: per language the finished, unfinished and obsolete message counts with the untranslated strings, and a completeness matrix of translation contexts by language.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn translation_status(
+        &self,
+        Parameters(params): Parameters<TranslationStatusParams>,
+    ) -> Result<Json<TranslationStatus>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        status(&dir, params.language.as_deref()).map(Json)
+    }
+
+    #[tool(
+        description = "Extract translatable strings from the project's C++ and QML sources into its .ts files with lupdate (on the host, else in the Build Engine), then report the translation status."
+    )]
+    pub async fn update_translations(
+        &self,
+        Parameters(params): Parameters<UpdateTranslationsParams>,
+    ) -> Result<Json<TranslationUpdate>> {
+        let options = &self.state().config.sdk;
+        let dir = options.project(params.project.as_deref())?;
+        let info = ProjectInfo::inspect(&dir);
+        if info.translations.is_empty() {
+            return Err(Error::InvalidArgument(format!(
+                "no .ts files in {}; add translations/<name>.ts first",
+                dir.join("translations").display()
+            )));
+        }
+
+        let mut args: Vec<String> = Vec::new();
+        match (info.build_system, &info.project_file) {
+            (Some(BuildSystem::Qmake), Some(pro)) => {
+                args.push(pro.strip_prefix(&dir).unwrap_or(pro).display().to_string());
+            }
+            _ => {
+                let sources: Vec<String> = ["src", "qml"]
+                    .into_iter()
+                    .filter(|sub| dir.join(sub).is_dir())
+                    .map(str::to_string)
+                    .collect();
+                if sources.is_empty() {
+                    args.push(".".to_string());
+                } else {
+                    args.extend(sources);
+                }
+                args.push("-ts".to_string());
+                args.extend(
+                    info.translations
+                        .iter()
+                        .map(|ts| ts.strip_prefix(&dir).unwrap_or(ts).display().to_string()),
+                );
+            }
+        }
+        if params.no_obsolete {
+            args.push("-no-obsolete".to_string());
+        }
+
+        let (mut command, _) =
+            options.qt_tool(&["lupdate", "lupdate-qt5"], params.target.as_deref())?;
+        command.args(&args).current_dir(&dir);
+        let line = super::command_line(&command);
+
+        let log_file = super::log_file(self, &dir, "lupdate");
+        let run = run_logged(command, &log_file, options.build_timeout()).await?;
+        Ok(Json(TranslationUpdate {
+            command: line,
+            success: run.status == 0,
+            exit_status: run.status,
+            log_tail: tail_lines(&run.output, LOG_TAIL_LINES),
+            status: status(&dir, None)?,
+        }))
+    }
+}
@@ -0,0 +1,464 @@
+//! `run_tests`: a project's QTest suites, run in the target's sysroot or on
+//! a device, or through ctest, with per-function results.
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use rmcp::handler::server::tool::cached_schema_for_type;
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::CallToolResult;
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::run_logged;
+use crate::device::ssh;
+use crate::error::{Error, Result, tool_result};
+use crate::resources::file_link;
+use crate::server::AuroraServer;
+
+/// Where test binaries are copied to on a device.
+const REMOTE_DIR: &str = "/tmp/aurora-mcp-tests";
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+const SUITE_MARKER: &str = "### aurora-mcp suite ";
+const EXIT_MARKER: &str = "### aurora-mcp exit ";
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RunTestsParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Run on this device instead of in the build target's sysroot. The test
+    /// binaries are copied to it and removed afterwards.
+    #[serde(default)]
+    pub device: Option<String>,
+    /// Build target (default: the configured one).
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Test binaries, relative to the project (default: the built
+    /// executables named `tst_*`, `test_*` or `*_test`, or ctest when the
+    /// build directory has a `CTestTestfile.cmake`).
+    #[serde(default)]
+    pub tests: Option<Vec<String>>,
+    /// Test functions to run, e.g. `["testAdd", "testRemove"]` (default:
+    /// all). With ctest, a regular expression over test names.
+    #[serde(default)]
+    pub functions: Option<Vec<String>>,
+    /// CMake build directory with `CTestTestfile.cmake`, relative to the
+    /// project (default: the project).
+    #[serde(default)]
+    pub build_dir: Option<String>,
+    /// Limit for the whole run in seconds (default 300).
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TestStatus {
+    Pass,
+    Fail,
+    Skip,
+    /// An expected failure (`QEXPECT_FAIL`).
+    XFail,
+    /// An expected failure that passed, which counts as a failure.
+    XPass,
+    Blacklisted,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TestCase {
+    /// `Class::function(data tag)`.
+    pub name: String,
+    pub status: TestStatus,
+    pub message: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TestSuite {
+    pub name: String,
+    pub exit_status: Option<i32>,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub cases: Vec<TestCase>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TestRun {
+    /// `sysroot`, `device` or `ctest`.
+    pub runner: String,
+    pub success: bool,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub duration_secs: u64,
+    pub suites: Vec<TestSuite>,
+    pub log_file: PathBuf,
+}
+
+/// Built test executables below `dir`.
+fn find_tests(dir: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    for path in paths {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if name.starts_with('.') || name == "RPMS" {
+            continue;
+        }
+        if path.is_dir() {
+            if depth > 0 {
+                find_tests(&path, depth - 1, found);
+            }
+        } else if (name.starts_with("tst_") || name.starts_with("test_") || name.ends_with("_test"))
+            && !name.contains('.')
+            && is_executable(&path)
+        {
+            found.push(path);
+        }
+    }
+}
+
+/// An ELF file with an execute bit.
+fn is_executable(path: &Path) -> bool {
+    let executable =
+        std::fs::metadata(path).is_ok_and(|meta| meta.permissions().mode() & 0o111 != 0);
+    executable
+        && std::fs::read(path)
+            .map(|bytes| bytes.starts_with(b"\x7fELF"))
+            .unwrap_or(false)
+}
+
+/// QTest's plain text output of one test binary:
+///
+/// ```text
+/// PASS   : TestModel::testAdd()
+/// FAIL!  : TestModel::testRemove(empty) Compared values are not the same
+///    Loc: [../tests/tst_model.cpp(42)]
+/// Totals: 1 passed, 1 failed, 0 skipped, 0 blacklisted, 3ms
+/// ```
+fn parse_qtest(output: &str) -> Vec<TestCase> {
+    let mut cases: Vec<TestCase> = Vec::new();
+    for line in output.lines() {
+        // `Actual`/`Expected` lines under a failure belong to its message.
+        if line.starts_with(char::is_whitespace)
+            && !line.trim_start().starts_with("Loc:")
+            && let Some(case) = cases.last_mut()
+            && matches!(case.status, TestStatus::Fail | TestStatus::XPass)
+        {
+            let message = case.message.get_or_insert_with(String::new);
+            if !message.is_empty() {
+                message.push('\n');
+            }
+            message.push_str(line.trim());
+            continue;
+        }
+        let Some((kind, rest)) = line.split_once(": ") else {
+            continue;
+        };
+        let status = match kind.trim() {
+            "PASS" => TestStatus::Pass,
+            "FAIL!" => TestStatus::Fail,
+            "SKIP" => TestStatus::Skip,
+            "XFAIL" => TestStatus::XFail,
+            "XPASS" => TestStatus::XPass,
+            "BPASS" | "BFAIL" | "BXPASS" | "BXFAIL" => TestStatus::Blacklisted,
+            "Loc" => {
+                // `[file(line)]` for the case above.
+                if let Some(case) = cases.last_mut()
+                    && let Some(location) = rest
+                        .trim()
+                        .strip_prefix('[')
+                        .and_then(|l| l.strip_suffix(")]"))
+                    && let Some((file, number)) = location.rsplit_once('(')
+                {
+                    case.file = Some(file.to_string());
+                    case.line = number.parse().ok();
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        let rest = rest.trim();
+        // The name ends at the `)` closing the data tag list.
+        let (name, message) = match rest.find(')') {
+            Some(end) => (&rest[..=end], rest[end + 1..].trim()),
+            None => (rest, ""),
+        };
+        cases.push(TestCase {
+            name: name.to_string(),
+            status,
+            message: (!message.is_empty()).then(|| message.to_string()),
+            file: None,
+            line: None,
+        });
+    }
+    cases
+}
+
+/// Suites of output marked with [`SUITE_MARKER`] and [`EXIT_MARKER`] lines.
+fn parse_suites(output: &str) -> Vec<TestSuite> {
+    let mut suites = Vec::new();
+    let mut current: Option<(String, String)> = None;
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix(SUITE_MARKER) {
+            current = Some((name.trim().to_string(), String::new()));
+        } else if let Some(status) = line.strip_prefix(EXIT_MARKER) {
+            if let Some((name, text)) = current.take() {
+                suites.push(suite(name, status.trim().parse().ok(), &text));
+            }
+        } else if let Some((_, text)) = &mut current {
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+    // A suite without an exit line did not finish.
+    if let Some((name, text)) = current {
+        suites.push(suite(name, None, &text));
+    }
+    suites
+}
+
+fn suite(name: String, exit_status: Option<i32>, output: &str) -> TestSuite {
+    let mut cases = parse_qtest(output);
+    // A binary that crashed or failed without a QTest verdict still fails.
+    if exit_status != Some(0)
+        && !cases
+            .iter()
+            .any(|case| matches!(case.status, TestStatus::Fail | TestStatus::XPass))
+    {
+        cases.push(TestCase {
+            name: name.clone(),
+            status: TestStatus::Fail,
+            message: Some(match exit_status {
+                Some(status) => format!("exited with status {status}"),
+                None => "did not finish".to_string(),
+            }),
+            file: None,
+            line: None,
+        });
+    }
+    TestSuite {
+        passed: count(&cases, &[TestStatus::Pass, TestStatus::XFail]),
+        failed: count(&cases, &[TestStatus::Fail, TestStatus::XPass]),
+        skipped: count(&cases, &[TestStatus::Skip, TestStatus::Blacklisted]),
+        name,
+        exit_status,
+        cases,
+    }
+}
+
+fn count(cases: &[TestCase], statuses: &[TestStatus]) -> usize {
+    cases
+        .iter()
+        .filter(|case| statuses.contains(&case.status))
+        .count()
+}
+
+/// `ctest` output: one suite per test, with the QTest lines `-V` prints
+/// under each `N: ` prefix.
+fn parse_ctest(output: &str) -> Vec<TestSuite> {
+    let mut suites = Vec::new();
+    for line in output.lines() {
+        // `1/3 Test #1: tst_model ........   Passed    0.02 sec`
+        let Some((_, rest)) = line.split_once(" Test #") else {
+            continue;
+        };
+        let Some((number, rest)) = rest.split_once(": ") else {
+            continue;
+        };
+        let name = rest.split_whitespace().next().unwrap_or("").to_string();
+        let verdict = rest
+            .trim_start_matches(|c: char| c != ' ')
+            .trim_start_matches([' ', '.']);
+        let prefix = format!("{number}: ");
+        let text: String = output
+            .lines()
+            .filter_map(|line| line.strip_prefix(&prefix))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        let exit_status = if verdict.starts_with("Passed") {
+            Some(0)
+        } else if verdict.contains("Not Run") || verdict.contains("Skipped") {
+            suites.push(TestSuite {
+                name,
+                exit_status: None,
+                passed: 0,
+                failed: 0,
+                skipped: 1,
+                cases: Vec::new(),
+            });
+            continue;
+        } else if verdict.contains("Timeout") {
+            None
+        } else {
+            Some(1)
+        };
+        let mut suite = suite(name.clone(), exit_status, &text);
+        if suite.cases.is_empty() {
+            // A plain executable test: its exit status is the verdict.
+            suite.passed = 1;
+            suite.cases.push(TestCase {
+                name,
+                status: TestStatus::Pass,
+                message: None,
+                file: None,
+                line: None,
+            });
+        }
+        suites.push(suite);
+    }
+    suites
+}
+
+pub async fn run(server: &AuroraServer, params: RunTestsParams) -> Result<TestRun> {
+    let options = &server.state().config.sdk;
+    let project = options.project(params.project.as_deref())?;
+    let timeout = Duration::from_secs(params.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).max(1));
+    let functions = params.functions.unwrap_or_default();
+    if let Some(function) = functions
+        .iter()
+        .find(|function| function.is_empty() || function.starts_with('-'))
+    {
+        return Err(Error::InvalidArgument(format!(
+            "'{function}' is not a test function name"
+        )));
+    }
+    let log_file = super::log_file(server, &project, "tests");
+    let started = Instant::now();
+
+    let build_dir = project.join(params.build_dir.as_deref().unwrap_or("."));
+    let use_ctest = params.tests.is_none()
+        && params.device.is_none()
+        && build_dir.join("CTestTestfile.cmake").is_file();
+    let (runner, suites) = if use_ctest {
+        let mut command = options.build_shell("ctest", params.target.as_deref())?;
+        command.args(["-V", "--output-on-failure"]);
+        if !functions.is_empty() {
+            command.arg("-R").arg(functions.join("|"));
+        }
+        command
+            .current_dir(&build_dir)
+            .env("QT_QPA_PLATFORM", "offscreen");
+        let run = run_logged(command, &log_file, timeout).await?;
+        ("ctest", parse_ctest(&run.output))
+    } else {
+        let tests = match params.tests {
+            Some(tests) => tests.iter().map(|test| project.join(test)).collect(),
+            None => {
+                let mut found = Vec::new();
+                find_tests(&project, 4, &mut found);
+                found
+            }
+        };
+        if tests.is_empty() {
+            return Err(Error::InvalidArgument(format!(
+                "no test executables (tst_*, test_*, *_test) built in {}; build the project or pass tests",
+                project.display()
+            )));
+        }
+        if let Some(missing) = tests.iter().find(|test| !test.is_file()) {
+            return Err(Error::InvalidArgument(format!(
+                "{} does not exist",
+                missing.display()
+            )));
+        }
+        let args: String = functions
+            .iter()
+            .map(|function| format!(" {}", ssh::quote(function)))
+            .collect();
+        match &params.device {
+            Some(device) => {
+                let device = server.devices().get(device)?;
+                device
+                    .exec_checked(&format!("mkdir -p {REMOTE_DIR}"))
+                    .await?;
+                let mut script = String::new();
+                for test in &tests {
+                    let name = test
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    let remote = format!("{REMOTE_DIR}/{name}");
+                    device.upload(test, &remote).await?;
+                    script.push_str(&format!(
+                        "echo {marker}; chmod +x {remote}; (cd {REMOTE_DIR} && ./{name}{args}) 2>&1; echo \"{EXIT_MARKER}$?\"; ",
+                        marker = ssh::quote(&format!("{SUITE_MARKER}{name}")),
+                        remote = ssh::quote(&remote),
+                        name = ssh::quote(&name),
+                    ));
+                }
+                let output = device.exec_with_timeout(&script, timeout).await;
+                let _ = device.exec(&format!("rm -rf {REMOTE_DIR}")).await;
+                let output = output?;
+                std::fs::create_dir_all(log_file.parent().unwrap_or(Path::new(".")))?;
+                std::fs::write(&log_file, &output.stdout)?;
+                ("device", parse_suites(&output.stdout))
+            }
+            None => {
+                let mut script = String::from("export QT_QPA_PLATFORM=offscreen; ");
+                for test in &tests {
+                    let relative = test.strip_prefix(&project).unwrap_or(test);
+                    let name = relative.to_string_lossy();
+                    script.push_str(&format!(
+                        "echo {marker}; ./{path}{args} 2>&1; echo \"{EXIT_MARKER}$?\"; ",
+                        marker = ssh::quote(&format!("{SUITE_MARKER}{name}")),
+                        path = ssh::quote(&name),
+                    ));
+                }
+                let mut command = options.build_shell("sh", params.target.as_deref())?;
+                command.arg("-c").arg(script).current_dir(&project);
+                let run = run_logged(command, &log_file, timeout).await?;
+                ("sysroot", parse_suites(&run.output))
+            }
+        }
+    };
+
+    let passed = suites.iter().map(|suite| suite.passed).sum();
+    let failed = suites.iter().map(|suite| suite.failed).sum();
+    let skipped = suites.iter().map(|suite| suite.skipped).sum();
+    Ok(TestRun {
+        runner: runner.to_string(),
+        success: failed == 0 && !suites.is_empty(),
+        passed,
+        failed,
+        skipped,
+        duration_secs: started.elapsed().as_secs(),
+        suites,
+        log_file,
+    })
+}
+
+#[tool_router(router = unittest_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Run a project's C++/QML unit tests (QTest executables, or ctest for CMake builds) in the build target's sysroot, or on a device by copying the test binaries there. Returns pass/fail/skip counts and every test function's result with failure messages and source locations, plus a link to the full output.",
+        output_schema = cached_schema_for_type::<TestRun>()
+    )]
+    pub async fn run_tests(
+        &self,
+        Parameters(params): Parameters<RunTestsParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        tool_result(run(self, params).await.and_then(|report| {
+            let link = file_link(&report.log_file, Some("text/plain"));
+            let mut result = CallToolResult::structured(
+                serde_json::to_value(&report).map_err(std::io::Error::other)?,
+            );
+            result.content.push(link);
+            Ok(result)
+        }))
+    }
+}
@@ -0,0 +1,419 @@
+//! `find_unused`: QML and JavaScript files no path from the app's main QML
+//! file reaches, images nothing references, and translation strings no
+//! reachable code uses, with the files that can be deleted safely.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::compat::CPP_EXTENSIONS;
+use super::lint::parse_spec;
+use super::project::{ProjectInfo, find_files};
+use super::qmlgraph::{self, NodeKind, string_literals};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "svg", "jpg", "jpeg", "gif", "webp"];
+
+/// Top-level directories whose images are not loaded by the app: desktop
+/// icons installed by the spec, and build output.
+const ASSET_SKIP_DIRS: &[&str] = &["icons", "RPMS", "rpm"];
+
+/// Build files that list sources and resources; a deleted file has to be
+/// removed from them too.
+const LISTING_FILES: &[&str] = &["pro", "pri", "qrc", "qbs", "txt", "cmake"];
+
+/// Stems shorter than this are too common to suggest a dynamically built
+/// file name.
+const MIN_STEM_MATCH: usize = 4;
+
+const MAX_STRINGS: usize = 200;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FindUnusedParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKind {
+    Qml,
+    Js,
+    Image,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UnusedFile {
+    /// Path relative to the project.
+    pub file: String,
+    pub kind: FileKind,
+    pub size: u64,
+    /// Other unused files that reference this one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub referenced_by: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UnusedString {
+    pub context: String,
+    pub source: String,
+    /// The `qsTrId` id, for id-based messages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// `.ts` files holding the message.
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Deletion {
+    pub file: String,
+    /// Build files listing the file, to edit along with the deletion.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub also_edit: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UnusedReport {
+    /// Where the reachability walk starts: the main QML file and QML files
+    /// C++ code names.
+    pub roots: Vec<String>,
+    pub unused_files: Vec<UnusedFile>,
+    /// Bytes the unused files take.
+    pub unused_bytes: u64,
+    /// Current messages no reachable QML, JavaScript or C++ code uses.
+    pub unused_strings: Vec<UnusedString>,
+    /// Messages `lupdate` marked obsolete or vanished; `lupdate
+    /// -no-obsolete` drops them.
+    pub obsolete_strings: usize,
+    /// Unused files nothing else mentions, not even by stem.
+    pub safe_to_delete: Vec<Deletion>,
+    /// Unused files whose stem or name prefix appears in used code, e.g. in
+    /// a file name built at run time; check them by hand.
+    pub review: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+fn relative(root: &Path, file: &Path) -> String {
+    file.strip_prefix(root)
+        .unwrap_or(file)
+        .display()
+        .to_string()
+}
+
+/// Undoes the escapes of a QML, JavaScript or C++ string literal.
+fn unescape(literal: &str) -> String {
+    let mut out = String::with_capacity(literal.len());
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+/// A translation message: context, source text and `qsTrId` id.
+type Message = (String, String, Option<String>);
+
+/// A `.ts` file's current messages, and how many are obsolete.
+fn ts_messages(file: &Path) -> Result<(Vec<Message>, usize)> {
+    let text = std::fs::read_to_string(file)?;
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    let doc = roxmltree::Document::parse_with_options(&text, options)
+        .map_err(|err| Error::InvalidArgument(format!("{}: {err}", file.display())))?;
+    let child_text = |node: roxmltree::Node, tag: &str| {
+        node.children()
+            .find(|child| child.has_tag_name(tag))
+            .map(|child| {
+                child
+                    .descendants()
+                    .filter(|node| node.is_text())
+                    .filter_map(|node| node.text())
+                    .collect::<String>()
+            })
+    };
+    let (mut messages, mut obsolete) = (Vec::new(), 0);
+    for context in doc
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("context"))
+    {
+        let name = child_text(context, "name").unwrap_or_default();
+        for message in context
+            .children()
+            .filter(|node| node.has_tag_name("message"))
+        {
+            let kind = message
+                .children()
+                .find(|node| node.has_tag_name("translation"))
+                .and_then(|node| node.attribute("type"));
+            if matches!(kind, Some("obsolete" | "vanished")) {
+                obsolete += 1;
+                continue;
+            }
+            messages.push((
+                name.clone(),
+                child_text(message, "source").unwrap_or_default(),
+                message.attribute("id").map(str::to_string),
+            ));
+        }
+    }
+    Ok((messages, obsolete))
+}
+
+#[tool_router(router = unused_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Find dead code and unused assets in a project: QML and JavaScript files not reachable from the main QML file (or QML named in C++), images referenced by no reachable QML, C++ or explicit %files entry, and translation strings no reachable code uses. Returns the unused files with their sizes, a safe-delete list with the build files (.pro, .qrc, CMakeLists.txt) to edit along with each deletion, and files to review by hand because their name may be built at run time.",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn find_unused(
+        &self,
+        Parameters(params): Parameters<FindUnusedParams>,
+    ) -> Result<Json<UnusedReport>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        let root = dir.canonicalize()?;
+        let info = ProjectInfo::inspect(&dir);
+        let graph = qmlgraph::build(&dir)?;
+        let mut notes = Vec::new();
+
+        let cpp_text: String = CPP_EXTENSIONS
+            .iter()
+            .flat_map(|ext| find_files(&dir, ext, 6))
+            .filter_map(|file| std::fs::read_to_string(file).ok())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let file_name = |id: &str| id.rsplit('/').next().unwrap_or(id).to_string();
+        let main_qml = format!("{}.qml", info.name);
+        let mut roots: Vec<String> = graph
+            .nodes
+            .iter()
+            .filter(|node| node.kind == NodeKind::Qml)
+            .filter(|node| {
+                let name = file_name(&node.id);
+                name == main_qml || cpp_text.contains(&name)
+            })
+            .map(|node| node.id.clone())
+            .collect();
+        if roots.is_empty() {
+            roots = graph.entry_points.clone();
+            notes.push(format!(
+                "neither {main_qml} nor QML named in C++ was found; every QML file no other file uses counts as an entry point"
+            ));
+        }
+
+        let mut uses: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for edge in &graph.edges {
+            uses.entry(edge.from.as_str())
+                .or_default()
+                .push(edge.to.as_str());
+        }
+        let mut reachable: BTreeSet<&str> = BTreeSet::new();
+        let mut queue: Vec<&str> = roots.iter().map(String::as_str).collect();
+        while let Some(id) = queue.pop() {
+            if reachable.insert(id) {
+                queue.extend(uses.get(id).into_iter().flatten().copied());
+            }
+        }
+
+        let mut unused_files: Vec<UnusedFile> = graph
+            .nodes
+            .iter()
+            .filter(|node| node.kind != NodeKind::Cpp && !reachable.contains(node.id.as_str()))
+            .map(|node| UnusedFile {
+                file: node.id.clone(),
+                kind: if node.kind == NodeKind::Js {
+                    FileKind::Js
+                } else {
+                    FileKind::Qml
+                },
+                size: std::fs::metadata(root.join(&node.id))
+                    .map(|m| m.len())
+                    .unwrap_or_default(),
+                referenced_by: graph
+                    .edges
+                    .iter()
+                    .filter(|edge| edge.to == node.id)
+                    .map(|edge| edge.from.clone())
+                    .collect(),
+            })
+            .collect();
+
+        // Text of everything still used: reachable QML and JavaScript, C++,
+        // and the explicit lines of %files.
+        let used_text: String = reachable
+            .iter()
+            .filter_map(|id| std::fs::read_to_string(root.join(id)).ok())
+            .chain([cpp_text.clone()])
+            .collect::<Vec<_>>()
+            .join("\n");
+        let spec_text = info
+            .spec_file
+            .as_ref()
+            .and_then(|spec| std::fs::read_to_string(spec).ok())
+            .unwrap_or_default();
+        let spec = parse_spec(&spec_text);
+        let files_section: Vec<&str> = spec
+            .sections
+            .iter()
+            .filter(|section| section.name == "%files")
+            .flat_map(|section| section.body.iter().map(|(_, line)| *line))
+            .collect();
+
+        let mut images: Vec<PathBuf> = IMAGE_EXTENSIONS
+            .iter()
+            .flat_map(|ext| find_files(&root, ext, 8))
+            .filter(|file| {
+                let path = file.strip_prefix(&root).unwrap_or(file);
+                !path.components().next().is_some_and(|top| {
+                    let top = top.as_os_str().to_string_lossy();
+                    ASSET_SKIP_DIRS.contains(&top.as_ref()) || top.starts_with("build")
+                })
+            })
+            .collect();
+        images.sort();
+        let dead_text: BTreeMap<&str, String> = unused_files
+            .iter()
+            .filter_map(|file| {
+                Some((
+                    file.file.as_str(),
+                    std::fs::read_to_string(root.join(&file.file)).ok()?,
+                ))
+            })
+            .collect();
+        let mut unused_images = Vec::new();
+        for image in images {
+            let name = file_name(&relative(&root, &image));
+            if used_text.contains(&name) || files_section.iter().any(|line| line.contains(&name)) {
+                continue;
+            }
+            unused_images.push(UnusedFile {
+                file: relative(&root, &image),
+                kind: FileKind::Image,
+                size: std::fs::metadata(&image)
+                    .map(|m| m.len())
+                    .unwrap_or_default(),
+                referenced_by: dead_text
+                    .iter()
+                    .filter(|(_, text)| text.contains(&name))
+                    .map(|(file, _)| file.to_string())
+                    .collect(),
+            });
+        }
+        unused_files.extend(unused_images);
+
+        let listings: Vec<(String, String)> = LISTING_FILES
+            .iter()
+            .flat_map(|ext| find_files(&root, ext, 4))
+            .filter(|file| {
+                file.extension().is_some_and(|ext| ext != "txt")
+                    || file
+                        .file_name()
+                        .is_some_and(|name| name == "CMakeLists.txt")
+            })
+            .filter_map(|file| Some((relative(&root, &file), std::fs::read_to_string(&file).ok()?)))
+            .collect();
+        // Literals of used code, for file names built at run time and the
+        // translation strings.
+        let literals: BTreeSet<String> = reachable
+            .iter()
+            .filter_map(|id| std::fs::read_to_string(root.join(id)).ok())
+            .chain([cpp_text])
+            .flat_map(|text| string_literals(&text))
+            .map(|(_, literal)| unescape(&literal))
+            .collect();
+        let (mut safe_to_delete, mut review) = (Vec::new(), Vec::new());
+        for file in &unused_files {
+            let name = file_name(&file.file);
+            let stem = name
+                .rsplit_once('.')
+                .map_or(name.as_str(), |(stem, _)| stem);
+            let built = literals.iter().any(|literal| {
+                let segment = literal.rsplit('/').next().unwrap_or(literal);
+                segment.len() >= MIN_STEM_MATCH && segment != name && name.starts_with(segment)
+            });
+            if built || stem.len() >= MIN_STEM_MATCH && used_text.contains(stem) {
+                review.push(file.file.clone());
+                continue;
+            }
+            safe_to_delete.push(Deletion {
+                file: file.file.clone(),
+                also_edit: listings
+                    .iter()
+                    .filter(|(_, text)| text.contains(&name))
+                    .map(|(listing, _)| listing.clone())
+                    .collect(),
+            });
+        }
+        let unused_bytes = unused_files.iter().map(|file| file.size).sum();
+
+        let mut strings: BTreeMap<Message, Vec<String>> = BTreeMap::new();
+        let mut obsolete_strings = 0;
+        for ts in &info.translations {
+            let (messages, obsolete) = match ts_messages(ts) {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    notes.push(err.to_string());
+                    continue;
+                }
+            };
+            obsolete_strings += obsolete;
+            for message in messages {
+                let used = match &message.2 {
+                    Some(id) => literals.contains(id),
+                    None => literals.contains(&message.1),
+                };
+                if !used {
+                    strings
+                        .entry(message)
+                        .or_default()
+                        .push(relative(&root, ts));
+                }
+            }
+        }
+        if strings.len() > MAX_STRINGS {
+            notes.push(format!(
+                "{} unused strings; showing the first {MAX_STRINGS}",
+                strings.len()
+            ));
+        }
+        let unused_strings = strings
+            .into_iter()
+            .take(MAX_STRINGS)
+            .map(|((context, source, id), files)| UnusedString {
+                context,
+                source,
+                id,
+                files,
+            })
+            .collect();
+
+        Ok(Json(UnusedReport {
+            roots,
+            unused_files,
+            unused_bytes,
+            unused_strings,
+            obsolete_strings,
+            safe_to_delete,
+            review,
+            notes,
+        }))
+    }
+}
@@ -0,0 +1,447 @@
+//! `check_upgrade`: whether a new build installs cleanly over the previous
+//! release — version ordering, the scriptlets that run on the way and what
+//! happens to config files — optionally tried out on a device, typically
+//! the emulator.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::lint::Level;
+use super::rpm::{
+    EPOCH, FILE_CONFIG, FILE_NOREPLACE, FileInfo, Header, OBSOLETEFLAGS, OBSOLETENAME,
+    OBSOLETEVERSION,
+};
+use super::rpmdiff::{PackageInfo, ScriptletChange, diff_scriptlets, package_info, version_cmp};
+use crate::device::{Device, ssh, tail_lines};
+use crate::error::{Error, Result, require_confirmation};
+use crate::server::AuroraServer;
+
+const INSTALL_TIMEOUT: Duration = Duration::from_secs(600);
+const OUTPUT_TAIL_LINES: usize = 30;
+
+/// Commands that undo an installation: harmless on erase, but an upgrade
+/// runs the old package's `%preun` and `%postun` too.
+const TEARDOWN: &[&str] = &[
+    "rm ",
+    "userdel",
+    "groupdel",
+    "systemctl stop",
+    "systemctl disable",
+    "systemctl --user stop",
+    "systemctl --user disable",
+    "dconf reset",
+];
+
+/// Commands meant for the first installation only.
+const SETUP: &[&str] = &["useradd", "groupadd", "systemctl enable", "dconf write"];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CheckUpgradeParams {
+    /// The previous release's RPM.
+    pub old: PathBuf,
+    /// The new build's RPM.
+    pub new: PathBuf,
+    /// Device to simulate the upgrade on, typically the emulator: installs
+    /// `old`, upgrades to `new` and reports scriptlet failures. Omit for
+    /// the checks on the headers only.
+    #[serde(default)]
+    pub device: Option<String>,
+    /// Leave `new` installed after the simulation. Without it the package
+    /// is removed again unless it was installed before.
+    #[serde(default)]
+    pub keep_installed: bool,
+    /// Must be true when the package is already installed on the device;
+    /// the simulation replaces it.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UpgradeIssue {
+    pub level: Level,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ConfigFileChange {
+    pub path: String,
+    /// `%config(noreplace)` in the new package.
+    pub noreplace: bool,
+    /// `added`, `removed`, `changed`, `unchanged`, `now_config` or
+    /// `no_longer_config`.
+    pub change: &'static str,
+    /// What rpm does with a copy the user edited.
+    pub outcome: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UpgradeStep {
+    pub command: String,
+    pub success: bool,
+    pub exit_status: i32,
+    pub output_tail: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UpgradeSimulation {
+    pub device: String,
+    /// `version-release arch` installed before the simulation.
+    pub previously_installed: Option<String>,
+    pub install_old: UpgradeStep,
+    /// Absent when installing the old package failed.
+    pub upgrade: Option<UpgradeStep>,
+    /// `version-release arch` installed after the upgrade.
+    pub installed_after: Option<String>,
+    /// rpm's `scriptlet failed` lines from either step.
+    pub scriptlet_failures: Vec<String>,
+    /// rpm's `.rpmnew` and `.rpmsave` notices.
+    pub config_notes: Vec<String>,
+    /// The package was erased again afterwards.
+    pub removed: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UpgradeReport {
+    pub old: PackageInfo,
+    pub new: PackageInfo,
+    /// Scriptlets in the order an upgrade runs them, with the argument
+    /// each gets, e.g. `%pre of 1.1-1 ($1 = 2)`.
+    pub sequence: Vec<String>,
+    pub scriptlets: Vec<ScriptletChange>,
+    pub config_files: Vec<ConfigFileChange>,
+    pub issues: Vec<UpgradeIssue>,
+    pub simulation: Option<UpgradeSimulation>,
+    /// No errors in the checks and, when simulated, a clean upgrade.
+    pub passed: bool,
+}
+
+/// Whether `script` tests the scriptlet argument, e.g. `if [ $1 -eq 0 ]`.
+fn checks_argument(script: &str) -> bool {
+    script.contains("$1") || script.contains("${1")
+}
+
+/// Issues with scriptlets that do not tell an upgrade from an install or
+/// erase.
+fn scriptlet_issues(old: &Header, new: &Header, issues: &mut Vec<UpgradeIssue>) {
+    let (old, new) = (old.scriptlets(), new.scriptlets());
+    for section in ["%preun", "%postun"] {
+        if let Some(script) = old.get(section)
+            && !checks_argument(script)
+            && let Some(command) = TEARDOWN.iter().find(|command| script.contains(*command))
+        {
+            issues.push(UpgradeIssue {
+                level: Level::Error,
+                message: format!(
+                    "the old package's {section} runs `{}` without checking $1; an upgrade runs it too ($1 = 1), after the new files are in place",
+                    command.trim()
+                ),
+            });
+        }
+    }
+    for section in ["%pre", "%post"] {
+        if let Some(script) = new.get(section)
+            && !checks_argument(script)
+            && let Some(command) = SETUP.iter().find(|command| script.contains(*command))
+        {
+            issues.push(UpgradeIssue {
+                level: Level::Warning,
+                message: format!(
+                    "{section} runs `{command}` on every upgrade ($1 = 2), not only on the first install; guard it with `if [ $1 -eq 1 ]` unless it is idempotent"
+                ),
+            });
+        }
+    }
+}
+
+fn sequence(old: &Header, new: &Header, old_version: &str, new_version: &str) -> Vec<String> {
+    let (old, new) = (old.scriptlets(), new.scriptlets());
+    [
+        ("%pre", &new, new_version, 2),
+        ("%post", &new, new_version, 2),
+        ("%preun", &old, old_version, 1),
+        ("%postun", &old, old_version, 1),
+    ]
+    .into_iter()
+    .filter(|(section, scriptlets, _, _)| scriptlets.contains_key(section))
+    .map(|(section, _, version, argument)| format!("{section} of {version} ($1 = {argument})"))
+    .collect()
+}
+
+fn config_files(
+    old: &[FileInfo],
+    new: &[FileInfo],
+    issues: &mut Vec<UpgradeIssue>,
+) -> Vec<ConfigFileChange> {
+    let is_config = |file: &FileInfo| file.flags & FILE_CONFIG != 0;
+    let old: BTreeMap<&str, &FileInfo> =
+        old.iter().map(|file| (file.path.as_str(), file)).collect();
+    let new: BTreeMap<&str, &FileInfo> =
+        new.iter().map(|file| (file.path.as_str(), file)).collect();
+    let mut changes = Vec::new();
+    for (path, file) in &new {
+        let noreplace = file.flags & FILE_NOREPLACE != 0;
+        let before = old.get(path);
+        let (change, outcome) = match before {
+            _ if !is_config(file) => {
+                if before.is_some_and(|before| is_config(before)) {
+                    issues.push(UpgradeIssue {
+                        level: Level::Warning,
+                        message: format!(
+                            "{path} is no longer %config; an upgrade overwrites the user's edits"
+                        ),
+                    });
+                    (
+                        "no_longer_config",
+                        "overwritten; the user's edits are lost".to_string(),
+                    )
+                } else {
+                    continue;
+                }
+            }
+            None => ("added", "installed".to_string()),
+            Some(before) if !is_config(before) => (
+                "now_config",
+                "replaced this time; edits after the upgrade are kept".to_string(),
+            ),
+            Some(before) if before.digest == file.digest => {
+                ("unchanged", "left as the user edited it".to_string())
+            }
+            Some(_) if noreplace => (
+                "changed",
+                format!("left as the user edited it; the new version is written to {path}.rpmnew"),
+            ),
+            Some(_) => {
+                issues.push(UpgradeIssue {
+                    level: Level::Warning,
+                    message: format!(
+                        "{path} changed and is %config without noreplace; an edited copy is moved to {path}.rpmsave and replaced"
+                    ),
+                });
+                (
+                    "changed",
+                    format!("replaced; the user's copy is saved as {path}.rpmsave"),
+                )
+            }
+        };
+        changes.push(ConfigFileChange {
+            path: path.to_string(),
+            noreplace,
+            change,
+            outcome,
+        });
+    }
+    for (path, file) in &old {
+        if is_config(file) && !new.contains_key(path) {
+            changes.push(ConfigFileChange {
+                path: path.to_string(),
+                noreplace: false,
+                change: "removed",
+                outcome: format!("removed; an edited copy is kept as {path}.rpmsave"),
+            });
+        }
+    }
+    changes
+}
+
+/// `version-release arch` of `name` on `device`, if installed.
+async fn installed(device: &Device, name: &str) -> Result<Option<String>> {
+    let output = device
+        .exec(&format!(
+            "rpm -q --qf '%{{VERSION}}-%{{RELEASE}} %{{ARCH}}\\n' {}",
+            ssh::quote(name)
+        ))
+        .await?;
+    Ok(output
+        .success()
+        .then(|| output.stdout.trim().to_string())
+        .filter(|version| !version.is_empty()))
+}
+
+/// Uploads `rpm` and runs `install` (e.g. `rpm -U`) on it.
+async fn install_step(device: &Device, rpm: &Path, install: &str) -> Result<UpgradeStep> {
+    let file_name = rpm
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| Error::InvalidArgument(format!("{} is not a file", rpm.display())))?;
+    let remote = format!("/tmp/aurora-mcp-{file_name}");
+    device.upload(rpm, &remote).await?;
+    let quoted = ssh::quote(&remote);
+    let command = format!("{install} {quoted}");
+    let output = device
+        .exec_privileged(
+            &format!("{command} 2>&1; status=$?; rm -f {quoted}; exit $status"),
+            INSTALL_TIMEOUT,
+        )
+        .await?;
+    Ok(UpgradeStep {
+        command,
+        success: output.success(),
+        exit_status: output.status,
+        output_tail: tail_lines(&output.stdout, OUTPUT_TAIL_LINES),
+    })
+}
+
+async fn simulate(
+    device: Device,
+    params: &CheckUpgradeParams,
+    name: &str,
+) -> Result<UpgradeSimulation> {
+    let previously_installed = installed(&device, name).await?;
+    if let Some(version) = &previously_installed {
+        require_confirmation(params.confirm, || {
+            format!(
+                "replacing {name} {version} on {} with the old and then the new build",
+                device.name()
+            )
+        })?;
+    }
+
+    // --force also installs the old build over a newer one.
+    let install_old = install_step(&device, &params.old, "rpm -U --force").await?;
+    let upgrade = if install_old.success {
+        Some(install_step(&device, &params.new, "rpm -U").await?)
+    } else {
+        None
+    };
+    let installed_after = installed(&device, name).await?;
+
+    let lines = || {
+        install_old
+            .output_tail
+            .iter()
+            .chain(upgrade.iter().flat_map(|step| &step.output_tail))
+    };
+    let scriptlet_failures = lines()
+        .filter(|line| line.contains("scriptlet failed"))
+        .cloned()
+        .collect();
+    let config_notes = lines()
+        .filter(|line| line.contains(".rpmnew") || line.contains(".rpmsave"))
+        .cloned()
+        .collect();
+
+    let mut removed = false;
+    if previously_installed.is_none() && !params.keep_installed && installed_after.is_some() {
+        let output = device
+            .exec_privileged(
+                &format!("rpm -e {} 2>&1", ssh::quote(name)),
+                INSTALL_TIMEOUT,
+            )
+            .await?;
+        removed = output.success();
+    }
+
+    Ok(UpgradeSimulation {
+        device: device.name().to_string(),
+        previously_installed,
+        install_old,
+        upgrade,
+        installed_after,
+        scriptlet_failures,
+        config_notes,
+        removed,
+    })
+}
+
+pub async fn check(server: &AuroraServer, params: CheckUpgradeParams) -> Result<UpgradeReport> {
+    for path in [&params.old, &params.new] {
+        if !path.is_file() {
+            return Err(Error::InvalidArgument(format!(
+                "{} does not exist",
+                path.display()
+            )));
+        }
+    }
+    let (old_path, new_path) = (params.old.clone(), params.new.clone());
+    let (old, new) = tokio::task::spawn_blocking(move || -> Result<_> {
+        Ok((Header::read(&old_path)?, Header::read(&new_path)?))
+    })
+    .await
+    .map_err(|err| Error::Io(std::io::Error::other(err)))??;
+    let old_info = package_info(&params.old, &old)?;
+    let new_info = package_info(&params.new, &new)?;
+
+    let mut issues = Vec::new();
+    let mut error = |message: String| {
+        issues.push(UpgradeIssue {
+            level: Level::Error,
+            message,
+        })
+    };
+    let (old_epoch, new_epoch) = (
+        old.int(EPOCH).unwrap_or_default(),
+        new.int(EPOCH).unwrap_or_default(),
+    );
+    if old_info.name != new_info.name {
+        let obsoleted = new
+            .dependencies(OBSOLETENAME, OBSOLETEFLAGS, OBSOLETEVERSION)
+            .iter()
+            .any(|obsolete| obsolete.split_whitespace().next() == Some(old_info.name.as_str()));
+        if !obsoleted {
+            error(format!(
+                "the package was renamed from {} to {} without `Obsoletes: {}`; both would end up installed",
+                old_info.name, new_info.name, old_info.name
+            ));
+        }
+    } else if new_epoch < old_epoch {
+        error(format!(
+            "the epoch went down from {old_epoch} to {new_epoch}; rpm treats the new build as older"
+        ));
+    } else if new_epoch == old_epoch && version_cmp(&new_info.version, &old_info.version).is_le() {
+        error(format!(
+            "{} is not newer than {}; rpm -U refuses it and the store does not offer it as an update",
+            new_info.version, old_info.version
+        ));
+    }
+    if old_info.arch != new_info.arch && new_info.arch != "noarch" && old_info.arch != "noarch" {
+        error(format!(
+            "the architecture differs: {} vs {}",
+            old_info.arch, new_info.arch
+        ));
+    }
+    scriptlet_issues(&old, &new, &mut issues);
+    let config_files = config_files(&old.files(), &new.files(), &mut issues);
+
+    let simulation = match &params.device {
+        Some(name) => {
+            let device = server.devices().get(name)?;
+            Some(simulate(device, &params, &old_info.name).await?)
+        }
+        None => None,
+    };
+    let simulated_cleanly = simulation.as_ref().is_none_or(|simulation| {
+        simulation.upgrade.as_ref().is_some_and(|step| step.success)
+            && simulation.scriptlet_failures.is_empty()
+    });
+
+    Ok(UpgradeReport {
+        sequence: sequence(&old, &new, &old_info.version, &new_info.version),
+        scriptlets: diff_scriptlets(&old, &new),
+        passed: simulated_cleanly && !issues.iter().any(|issue| issue.level == Level::Error),
+        old: old_info,
+        new: new_info,
+        config_files,
+        issues,
+        simulation,
+    })
+}
+
+#[tool_router(router = upgrade_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Check that a new RPM upgrades the previous release cleanly: version and epoch ordering, renames without Obsoletes, the scriptlets an upgrade runs in order and the %pre/%post/%preun/%postun changes, scriptlets that tear down or set up without checking $1, and what happens to %config files a user edited (.rpmnew/.rpmsave). With a device (typically the emulator) it installs the old RPM, upgrades to the new one and reports scriptlet failures; replacing an installed package needs confirm=true.",
+        annotations(destructive_hint = true)
+    )]
+    pub async fn check_upgrade(
+        &self,
+        Parameters(params): Parameters<CheckUpgradeParams>,
+    ) -> Result<Json<UpgradeReport>> {
+        check(self, params).await.map(Json)
+    }
+}
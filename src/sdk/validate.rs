@@ -0,0 +1,206 @@
+//! `validate_rpm`: the Aurora Marketplace RPM validator (`sfdk check`) run
+//! on a built package, with its report parsed into issues.
+
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::tool::cached_schema_for_type;
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::CallToolResult;
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use super::lint::Level;
+use super::run_logged;
+use crate::error::{Error, Result, tool_result};
+use crate::resources::file_link;
+use crate::server::AuroraServer;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ValidateRpmParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// RPM to validate, absolute or relative to the project (default: the
+    /// newest package in `RPMS/`, ignoring debug packages).
+    #[serde(default)]
+    pub rpm: Option<String>,
+    /// Build target whose validator to use (default: the configured one).
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Validator suites to run, e.g. `rpmvalidation` (default: all
+    /// essential suites).
+    #[serde(default)]
+    pub suites: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ValidationIssue {
+    pub level: Level,
+    /// Validator section, e.g. `Paths`, `Desktop file`, `QML`,
+    /// `Libraries`, `Requires`.
+    pub category: Option<String>,
+    /// File or dependency the issue is about.
+    pub subject: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ValidationReport {
+    pub rpm: PathBuf,
+    pub target: Option<String>,
+    /// The validator reported no errors; warnings may remain.
+    pub passed: bool,
+    pub exit_status: i32,
+    pub errors: usize,
+    pub warnings: usize,
+    pub issues: Vec<ValidationIssue>,
+    pub log_file: PathBuf,
+}
+
+/// The newest non-debug RPM in `dir`.
+pub(crate) fn newest_rpm(dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            name.ends_with(".rpm")
+                && !name.contains("-debuginfo-")
+                && !name.contains("-debugsource-")
+                && !name.ends_with(".src.rpm")
+        })
+        .max_by_key(|path| path.metadata().and_then(|meta| meta.modified()).ok())
+}
+
+/// Issues in the validator's report: `ERROR [subject] message` and
+/// `WARNING` lines (also spelled `LEVEL:`), grouped under the section
+/// headings that precede them.
+fn parse_report(output: &str) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let mut category = None;
+    let mut previous = "";
+    for line in output.lines().map(str::trim_end) {
+        if line.len() >= 3 && line.chars().all(|c| c == '=' || c == '-') {
+            if !previous.trim().is_empty() {
+                category = Some(previous.trim().to_string());
+            }
+            previous = line;
+            continue;
+        }
+        previous = line;
+        let line = line.trim_start();
+        let (level, rest) = if let Some(rest) = line.strip_prefix("ERROR") {
+            (Level::Error, rest)
+        } else if let Some(rest) = line.strip_prefix("WARNING") {
+            (Level::Warning, rest)
+        } else if let Some(rest) = line.strip_prefix("WARN") {
+            (Level::Warning, rest)
+        } else {
+            continue;
+        };
+        if !rest.starts_with([' ', ':']) {
+            continue;
+        }
+        let rest = rest.trim_start_matches(':').trim();
+        let (subject, message) = match rest.strip_prefix('[').and_then(|rest| rest.split_once(']'))
+        {
+            Some((subject, message)) => (Some(subject.to_string()), message.trim()),
+            None => (None, rest),
+        };
+        issues.push(ValidationIssue {
+            level,
+            category: category.clone(),
+            subject,
+            message: message.to_string(),
+        });
+    }
+    issues
+}
+
+pub async fn validate(
+    server: &AuroraServer,
+    params: ValidateRpmParams,
+) -> Result<ValidationReport> {
+    let options = &server.state().config.sdk;
+    let project = options.project(params.project.as_deref())?;
+    let target = options.target(params.target.as_deref())?;
+    let rpm = match &params.rpm {
+        Some(rpm) => project.join(rpm),
+        None => newest_rpm(&project.join("RPMS")).ok_or_else(|| {
+            Error::InvalidArgument(format!(
+                "no RPM in {}; build the project first or pass rpm",
+                project.join("RPMS").display()
+            ))
+        })?,
+    };
+    if !rpm.is_file() {
+        return Err(Error::InvalidArgument(format!(
+            "{} does not exist",
+            rpm.display()
+        )));
+    }
+    if let Some(suite) = params.suites.iter().find(|suite| {
+        suite.is_empty()
+            || !suite
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }) {
+        return Err(Error::InvalidArgument(format!(
+            "'{suite}' is not a validator suite name"
+        )));
+    }
+
+    let mut command = Command::new(options.sfdk()?);
+    if let Some(target) = &target {
+        command.arg("-c").arg(format!("target={target}"));
+    }
+    command.arg("check");
+    if !params.suites.is_empty() {
+        command.arg("--suites").arg(params.suites.join(","));
+    }
+    command.arg(&rpm).current_dir(&project);
+
+    let log_file = super::log_file(server, &project, "validate");
+    let run = run_logged(command, &log_file, options.build_timeout()).await?;
+    let issues = parse_report(&run.output);
+    let errors = issues.iter().filter(|i| i.level == Level::Error).count();
+    let warnings = issues.iter().filter(|i| i.level == Level::Warning).count();
+    Ok(ValidationReport {
+        rpm,
+        target,
+        passed: run.status == 0 && errors == 0,
+        exit_status: run.status,
+        errors,
+        warnings,
+        issues,
+        log_file,
+    })
+}
+
+#[tool_router(router = validate_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Run the Aurora Marketplace RPM validator (sfdk check) on a built package before store submission. Returns errors and warnings about installed paths, permissions, naming, allowed libraries and QML imports, and requires, with a link to the full validator output.",
+        output_schema = cached_schema_for_type::<ValidationReport>()
+    )]
+    pub async fn validate_rpm(
+        &self,
+        Parameters(params): Parameters<ValidateRpmParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        tool_result(validate(self, params).await.and_then(|report| {
+            let link = file_link(&report.log_file, Some("text/plain"));
+            let mut result = CallToolResult::structured(
+                serde_json::to_value(&report).map_err(std::io::Error::other)?,
+            );
+            result.content.push(link);
+            Ok(result)
+        }))
+    }
+}
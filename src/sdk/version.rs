@@ -0,0 +1,454 @@
+//! `bump_version`: one new version across the spec, the qmake/CMake project
+//! and the version strings the sources show, e.g. on an About page.
+
+use std::path::{Path, PathBuf};
+
+use rmcp::handler::server::wrapper::{Json, Parameters};
+use rmcp::{tool, tool_router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::lint::parse_spec;
+use super::project::{BuildSystem, ProjectInfo, find_files};
+use crate::error::{Error, Result};
+use crate::server::AuroraServer;
+
+/// Source files searched for version string constants.
+const SOURCE_EXTENSIONS: &[&str] = &["qml", "js", "cpp", "h"];
+
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    Major,
+    Minor,
+    Patch,
+    /// The next pre-release: `1.2.0-beta.1` → `1.2.0-beta.2`, or the next
+    /// patch's first one.
+    Prerelease,
+    /// Drop the pre-release suffix: `1.2.0-rc.2` → `1.2.0`.
+    Release,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BumpVersionParams {
+    /// Project directory (default: the `[sdk]` project from the config, else
+    /// the server's working directory).
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Part to increment. Either this or `version` is required.
+    #[serde(default)]
+    pub bump: Option<Bump>,
+    /// Exact new version, e.g. `2.0.0` or `2.0.0-beta.1`.
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Pre-release label to start, e.g. `beta` for `1.3.0-beta.1` (with
+    /// `major`, `minor`, `patch` or `prerelease`).
+    #[serde(default)]
+    pub pre: Option<String>,
+    /// Write the files instead of only listing the changes.
+    #[serde(default)]
+    pub write: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VersionChange {
+    pub file: PathBuf,
+    pub line: usize,
+    /// What changed, e.g. `Version`, `VERSION`, `project()`, `string constant`.
+    pub source: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BumpedVersion {
+    pub previous: String,
+    /// The new version in semver form.
+    pub version: String,
+    /// As the spec states it: the pre-release after `~`, so it sorts before
+    /// the release.
+    pub rpm_version: String,
+    pub changes: Vec<VersionChange>,
+    /// Whether the files were written.
+    pub written: bool,
+}
+
+/// A `major.minor.patch[-pre]` version.
+#[derive(Debug, Clone, PartialEq)]
+struct Version {
+    major: u64,
+    minor: u64,
+    patch: u64,
+    pre: Option<String>,
+}
+
+impl Version {
+    /// Parses semver, the spec's `~` pre-release form and `major.minor`.
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().trim_start_matches('v');
+        let (core, pre) = match text.split_once(['-', '~']) {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (text, None),
+        };
+        let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+        let major = parts.next()??;
+        let minor = parts.next()??;
+        let patch = parts.next().unwrap_or(Some(0))?;
+        if parts.next().is_some() {
+            return None;
+        }
+        let pre = match pre {
+            Some(pre) if valid_pre(pre) => Some(pre.to_string()),
+            Some(_) => return None,
+            None => None,
+        };
+        Some(Self {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+
+    fn core(&self) -> String {
+        format!("{}.{}.{}", self.major, self.minor, self.patch)
+    }
+
+    fn semver(&self) -> String {
+        match &self.pre {
+            Some(pre) => format!("{}-{pre}", self.core()),
+            None => self.core(),
+        }
+    }
+
+    fn rpm(&self) -> String {
+        match &self.pre {
+            Some(pre) => format!("{}~{pre}", self.core()),
+            None => self.core(),
+        }
+    }
+
+    fn bump(&self, bump: Bump, label: Option<&str>) -> Self {
+        let first_pre = label.map(|label| format!("{label}.1"));
+        let mut next = self.clone();
+        match bump {
+            Bump::Major => {
+                next = Self {
+                    major: self.major + 1,
+                    minor: 0,
+                    patch: 0,
+                    pre: first_pre,
+                }
+            }
+            Bump::Minor => {
+                next = Self {
+                    minor: self.minor + 1,
+                    patch: 0,
+                    pre: first_pre,
+                    ..next
+                }
+            }
+            Bump::Patch => {
+                next = Self {
+                    patch: self.patch + 1,
+                    pre: first_pre,
+                    ..next
+                }
+            }
+            Bump::Prerelease => {
+                next.pre = match (&self.pre, label) {
+                    (Some(pre), None) => Some(next_pre(pre)),
+                    (Some(pre), Some(label)) if pre_label(pre) == label => Some(next_pre(pre)),
+                    (Some(_), Some(label)) => Some(format!("{label}.1")),
+                    (None, label) => {
+                        next.patch += 1;
+                        Some(format!("{}.1", label.unwrap_or("rc")))
+                    }
+                };
+            }
+            Bump::Release => next.pre = None,
+        }
+        next
+    }
+}
+
+fn valid_pre(pre: &str) -> bool {
+    !pre.is_empty()
+        && pre
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// `beta` of `beta.2`.
+fn pre_label(pre: &str) -> &str {
+    match pre.rsplit_once('.') {
+        Some((label, number)) if number.parse::<u64>().is_ok() => label,
+        _ => pre,
+    }
+}
+
+/// `beta.3` after `beta.2`, `beta.1` after `beta`.
+fn next_pre(pre: &str) -> String {
+    match pre.rsplit_once('.') {
+        Some((label, number)) if let Ok(number) = number.parse::<u64>() => {
+            format!("{label}.{}", number + 1)
+        }
+        _ => format!("{pre}.1"),
+    }
+}
+
+/// A file's original and edited text.
+struct Edit {
+    path: PathBuf,
+    original: String,
+    text: String,
+}
+
+/// `line` with the value after `separator` (a `Tag:` or `VAR =` line)
+/// replaced.
+fn replace_value(line: &str, separator: char, value: &str) -> Option<String> {
+    let at = line.find(separator)? + 1;
+    let rest = &line[at..];
+    let start = at + (rest.len() - rest.trim_start().len());
+    let end = at + rest.trim_end().len();
+    Some(format!("{}{value}{}", &line[..start], &line[end..]))
+}
+
+/// Applies `edit` to each line of `path`, recording the changes.
+fn edit_lines(
+    path: &Path,
+    changes: &mut Vec<VersionChange>,
+    mut edit: impl FnMut(&str) -> Option<(String, String, String, String)>,
+) -> Result<Option<Edit>> {
+    let original = std::fs::read_to_string(path)?;
+    let mut lines: Vec<String> = Vec::new();
+    let mut changed = false;
+    for (index, line) in original.lines().enumerate() {
+        match edit(line) {
+            Some((new_line, source, from, to)) if new_line != line => {
+                changes.push(VersionChange {
+                    file: path.to_path_buf(),
+                    line: index + 1,
+                    source,
+                    from,
+                    to,
+                });
+                lines.push(new_line);
+                changed = true;
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+    if !changed {
+        return Ok(None);
+    }
+    let mut text = lines.join("\n");
+    if original.ends_with('\n') {
+        text.push('\n');
+    }
+    Ok(Some(Edit {
+        path: path.to_path_buf(),
+        original,
+        text,
+    }))
+}
+
+/// `project(... VERSION x ...)` with `x` replaced, for a one-line call.
+fn cmake_project_line(line: &str, version: &str) -> Option<(String, String)> {
+    let lower = line.to_ascii_lowercase();
+    let call = lower.trim_start();
+    if !call.starts_with("project(") && !call.starts_with("project (") {
+        return None;
+    }
+    let keyword = lower.find(" version ")? + " version ".len();
+    let rest = &line[keyword..];
+    let start = keyword + (rest.len() - rest.trim_start().len());
+    let end = start
+        + line[start..]
+            .find(|c: char| c.is_whitespace() || c == ')')
+            .unwrap_or(line.len() - start);
+    let from = line[start..end].to_string();
+    Some((format!("{}{version}{}", &line[..start], &line[end..]), from))
+}
+
+/// Writes every edit or none: each goes to a temporary file first, and the
+/// already renamed ones are restored when a rename fails.
+fn write_all(edits: &[Edit]) -> Result<()> {
+    let temporary = |path: &Path| {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".bump");
+        path.with_file_name(name)
+    };
+    for (index, edit) in edits.iter().enumerate() {
+        if let Err(err) = std::fs::write(temporary(&edit.path), &edit.text) {
+            for edit in &edits[..=index] {
+                let _ = std::fs::remove_file(temporary(&edit.path));
+            }
+            return Err(err.into());
+        }
+    }
+    for (index, edit) in edits.iter().enumerate() {
+        if let Err(err) = std::fs::rename(temporary(&edit.path), &edit.path) {
+            for edit in &edits[..index] {
+                let _ = std::fs::write(&edit.path, &edit.original);
+            }
+            for edit in &edits[index..] {
+                let _ = std::fs::remove_file(temporary(&edit.path));
+            }
+            return Err(err.into());
+        }
+    }
+    Ok(())
+}
+
+pub fn bump(dir: &Path, params: BumpVersionParams) -> Result<BumpedVersion> {
+    let info = ProjectInfo::inspect(dir);
+    let spec_text = match &info.spec_file {
+        Some(spec) => std::fs::read_to_string(spec)?,
+        None => String::new(),
+    };
+    let spec_version = parse_spec(&spec_text)
+        .tag("Version")
+        .map(|(_, version)| version.to_string());
+    let stated = spec_version
+        .clone()
+        .or_else(|| info.version.clone())
+        .ok_or_else(|| {
+            Error::InvalidArgument(format!(
+                "no Version in the spec or project file of {}",
+                dir.display()
+            ))
+        })?;
+    let current = Version::parse(&stated).ok_or_else(|| {
+        Error::InvalidArgument(format!("current version '{stated}' is not semver"))
+    })?;
+
+    if let Some(label) = &params.pre
+        && !valid_pre(label)
+    {
+        return Err(Error::InvalidArgument(format!(
+            "'{label}' is not a pre-release label"
+        )));
+    }
+    let next = match (&params.version, params.bump) {
+        (Some(version), None) => Version::parse(version).ok_or_else(|| {
+            Error::InvalidArgument(format!("'{version}' is not a semver version"))
+        })?,
+        (None, Some(bump)) => current.bump(bump, params.pre.as_deref()),
+        _ => {
+            return Err(Error::InvalidArgument(
+                "pass either bump or version".to_string(),
+            ));
+        }
+    };
+    if next == current {
+        return Err(Error::InvalidArgument(format!(
+            "the version is already {}",
+            current.semver()
+        )));
+    }
+
+    let mut changes = Vec::new();
+    let mut edits = Vec::new();
+    if let Some(spec) = &info.spec_file
+        && spec_version.is_some()
+    {
+        // A new version restarts the release count; `%{?dist}` stays.
+        let (mut version_seen, mut release_seen) = (false, false);
+        let edit = edit_lines(spec, &mut changes, |line| {
+            let (tag, value) = line.split_once(':')?;
+            let value = value.trim();
+            if !version_seen && tag.trim().eq_ignore_ascii_case("Version") {
+                version_seen = true;
+                let new = replace_value(line, ':', &next.rpm())?;
+                Some((new, "Version".into(), value.into(), next.rpm()))
+            } else if !release_seen && tag.trim().eq_ignore_ascii_case("Release") {
+                release_seen = true;
+                let suffix = value.find('%').map_or("", |at| &value[at..]);
+                let release = format!("1{suffix}");
+                let new = replace_value(line, ':', &release)?;
+                Some((new, "Release".into(), value.into(), release))
+            } else {
+                None
+            }
+        })?;
+        edits.extend(edit);
+    }
+    // qmake and CMake versions are numeric only.
+    if let Some(project_file) = &info.project_file
+        && info.version.is_some()
+    {
+        let edit = match info.build_system {
+            Some(BuildSystem::Qmake) => edit_lines(project_file, &mut changes, |line| {
+                let (variable, value) = line.split_once('=')?;
+                if variable.trim() != "VERSION" || value.trim_start().starts_with('$') {
+                    return None;
+                }
+                let new = replace_value(line, '=', &next.core())?;
+                Some((new, "VERSION".into(), value.trim().into(), next.core()))
+            })?,
+            Some(BuildSystem::Cmake) => edit_lines(project_file, &mut changes, |line| {
+                let (new, from) = cmake_project_line(line, &next.core())?;
+                Some((new, "project()".into(), from, next.core()))
+            })?,
+            _ => None,
+        };
+        edits.extend(edit);
+    }
+
+    // String constants naming the current version, e.g. an About page's
+    // `property string version: "1.2.0"` or `#define APP_VERSION "1.2.0"`.
+    let olds = [current.semver(), current.rpm(), stated.clone()];
+    let mut sources = Vec::new();
+    for subdir in ["qml", "src"] {
+        for extension in SOURCE_EXTENSIONS {
+            sources.extend(find_files(&dir.join(subdir), extension, 4));
+        }
+    }
+    sources.sort();
+    sources.dedup();
+    for source in sources {
+        let edit = edit_lines(&source, &mut changes, |line| {
+            if !line.to_ascii_lowercase().contains("version") {
+                return None;
+            }
+            let old = olds
+                .iter()
+                .find(|old| line.contains(&format!("\"{old}\"")))?;
+            let new = line.replacen(&format!("\"{old}\""), &format!("\"{}\"", next.semver()), 1);
+            Some((new, "string constant".into(), old.clone(), next.semver()))
+        })?;
+        edits.extend(edit);
+    }
+
+    if edits.is_empty() {
+        return Err(Error::InvalidArgument(format!(
+            "no version to update in {}",
+            dir.display()
+        )));
+    }
+    if params.write {
+        write_all(&edits)?;
+    }
+    Ok(BumpedVersion {
+        previous: current.semver(),
+        version: next.semver(),
+        rpm_version: next.rpm(),
+        changes,
+        written: params.write,
+    })
+}
+
+#[tool_router(router = version_router, vis = "pub(crate)")]
+impl AuroraServer {
+    #[tool(
+        description = "Bump the application version (major, minor, patch, prerelease, release, or an exact version with an optional pre-release label such as beta) in the .spec (resetting Release), the qmake VERSION or CMake project() VERSION, and version string constants in QML/C++ such as an About page. Lists the changes; set write=true to apply them to all files at once."
+    )]
+    pub async fn bump_version(
+        &self,
+        Parameters(params): Parameters<BumpVersionParams>,
+    ) -> Result<Json<BumpedVersion>> {
+        let dir = self.state().config.sdk.project(params.project.as_deref())?;
+        bump(&dir, params).map(Json)
+    }
+}
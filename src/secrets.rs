@@ -0,0 +1,96 @@
+//! Credentials kept out of tool arguments and the main config: Wi-Fi
+//! passphrases, store tokens and the like.
+//!
+//! A secret `wifi.LabNet` is looked up in the environment as
+//! `AURORA_MCP_SECRET_WIFI_LABNET` first, then in `secrets.toml` next to
+//! the config file:
+//!
+//! ```toml
+//! [wifi]
+//! LabNet = "passphrase"
+//! ```
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+use crate::error::{Error, Result};
+
+const ENV_PREFIX: &str = "AURORA_MCP_SECRET_";
+
+#[derive(Debug, Clone)]
+pub struct SecretStore {
+    path: PathBuf,
+}
+
+impl SecretStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Looks up `key`. The file is re-read on every call so edits apply
+    /// without a restart.
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        if let Some(value) = std::env::var(env_name(key)).ok().filter(|v| !v.is_empty()) {
+            return Ok(Some(value));
+        }
+        let text = match std::fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::Config(format!("{}: {err}", self.path.display()))),
+        };
+        if let Ok(meta) = std::fs::metadata(&self.path)
+            && meta.permissions().mode() & 0o077 != 0
+        {
+            tracing::warn!(
+                "{} is readable by other users; chmod 600 it",
+                self.path.display()
+            );
+        }
+        let table: toml::Table = toml::from_str(&text)
+            .map_err(|err| Error::Config(format!("{}: {err}", self.path.display())))?;
+        Ok(lookup(&table, key))
+    }
+
+    /// Like [`SecretStore::get`], failing with a hint on where to put the
+    /// secret when it is not set.
+    pub fn require(&self, key: &str) -> Result<String> {
+        self.get(key)?.ok_or_else(|| Error::MissingSecret {
+            key: key.to_string(),
+            env: env_name(key),
+            file: self.path.display().to_string(),
+        })
+    }
+}
+
+/// `wifi.Lab Net` -> `AURORA_MCP_SECRET_WIFI_LAB_NET`.
+fn env_name(key: &str) -> String {
+    let suffix: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{ENV_PREFIX}{suffix}")
+}
+
+/// Resolves a dotted key against nested tables. The longest matching
+/// prefix wins, so names that themselves contain dots still work.
+fn lookup(table: &toml::Table, key: &str) -> Option<String> {
+    if let Some(toml::Value::String(value)) = table.get(key) {
+        return Some(value.clone());
+    }
+    key.match_indices('.').find_map(|(i, _)| {
+        let toml::Value::Table(inner) = table.get(&key[..i])? else {
+            return None;
+        };
+        lookup(inner, &key[i + 1..])
+    })
+}
@@ -0,0 +1,426 @@
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+use rmcp::handler::server::router::prompt::PromptRouter;
+use rmcp::handler::server::router::tool::ToolRouter;
+use rmcp::handler::server::tool::ToolCallContext;
+use rmcp::model::{
+    CallToolRequestParam, CallToolResult, CompleteRequestParam, CompleteResult, CompletionInfo,
+    GetPromptRequestParam, GetPromptResult, Implementation, ListPromptsResult,
+    ListResourceTemplatesResult, ListResourcesResult, ListToolsResult, LoggingLevel,
+    LoggingMessageNotificationParam, PaginatedRequestParam, ReadResourceRequestParam,
+    ReadResourceResult, Reference, ServerCapabilities, ServerInfo, SetLevelRequestParam,
+    SubscribeRequestParam, Tool, UnsubscribeRequestParam,
+};
+use rmcp::service::{NotificationContext, RequestContext};
+use rmcp::{ErrorData, Peer, RoleServer, ServerHandler, prompt_handler};
+use tokio::task::AbortHandle;
+
+use crate::config::Config;
+use crate::device::gdb::GdbSessions;
+use crate::device::preview::Previews;
+use crate::device::{self, DeviceRegistry, Tunnels};
+use crate::docs::{self, bundle::BundleInstalls, search::DocsIndex};
+use crate::events::{Event, EventBus, next_session_id};
+use crate::fixtures;
+use crate::progress;
+use crate::queue::CallQueue;
+use crate::resources::{self, RenderCache, Subscriptions};
+use crate::sdk;
+use crate::webhooks::Webhooks;
+use crate::workspace::ClientRoots;
+
+/// State shared by all tools.
+#[derive(Debug, Clone)]
+pub struct AppState {
+    pub config: Config,
+    pub devices: DeviceRegistry,
+    pub tunnels: Tunnels,
+    pub gdb_sessions: GdbSessions,
+    pub previews: Previews,
+    pub docs_index: DocsIndex,
+    pub docs_installs: BundleInstalls,
+    pub render_cache: RenderCache,
+    pub events: EventBus,
+    pub webhooks: Webhooks,
+    pub calls: CallQueue,
+    /// Tools with effects past the host answer from [`fixtures`].
+    pub fixtures: bool,
+}
+
+impl AppState {
+    pub fn new(config: Config) -> Self {
+        let events = EventBus::default();
+        let devices =
+            DeviceRegistry::new(config.devices.clone(), config.ssh.clone(), events.clone());
+        Self {
+            webhooks: Webhooks::new(config.clone()),
+            calls: CallQueue::new(&config.calls),
+            events,
+            config,
+            devices,
+            tunnels: Tunnels::default(),
+            gdb_sessions: GdbSessions::default(),
+            previews: Previews::default(),
+            docs_index: DocsIndex::default(),
+            docs_installs: BundleInstalls::default(),
+            render_cache: RenderCache::default(),
+            fixtures: false,
+        }
+    }
+
+    /// State whose devices are simulated rather than reached over SSH.
+    pub fn mock(config: Config) -> Self {
+        let events = EventBus::default();
+        let devices = DeviceRegistry::mock(config.devices.clone(), events.clone());
+        Self {
+            webhooks: Webhooks::new(config.clone()),
+            calls: CallQueue::new(&config.calls),
+            events,
+            config,
+            devices,
+            tunnels: Tunnels::default(),
+            gdb_sessions: GdbSessions::default(),
+            previews: Previews::default(),
+            docs_index: DocsIndex::default(),
+            docs_installs: BundleInstalls::default(),
+            render_cache: RenderCache::default(),
+            fixtures: false,
+        }
+    }
+
+    /// Mock state whose builds, remote builds, store requests and
+    /// deployments return canned results too.
+    pub fn mock_all(config: Config) -> Self {
+        Self {
+            fixtures: true,
+            ..Self::mock(config)
+        }
+    }
+}
+
+/// A handle to the server for one client session. Clones are handles to
+/// the same session; [`AuroraServer::new_session`] makes one for another.
+#[derive(Debug, Clone)]
+pub struct AuroraServer {
+    inner: Arc<Inner>,
+    subscriptions: Subscriptions,
+    client_roots: ClientRoots,
+    session: Arc<Session>,
+}
+
+/// What all sessions of a server share.
+#[derive(Debug)]
+struct Inner {
+    state: AppState,
+    tool_router: &'static ToolRouter<AuroraServer>,
+    prompt_router: &'static PromptRouter<AuroraServer>,
+}
+
+/// One client session's place on the event bus: its id, and the logging
+/// level the client asked for. The session's end is published when the
+/// last handle to it goes.
+#[derive(Debug)]
+struct Session {
+    id: u64,
+    events: EventBus,
+    /// The client completed `initialize`.
+    opened: AtomicBool,
+    /// Lowest level of events forwarded as logging notifications.
+    log_level: Arc<AtomicU8>,
+    log_task: Mutex<Option<AbortHandle>>,
+}
+
+impl Session {
+    fn new(events: EventBus) -> Arc<Self> {
+        Arc::new(Self {
+            id: next_session_id(),
+            events,
+            opened: AtomicBool::new(false),
+            log_level: Arc::new(AtomicU8::new(LoggingLevel::Debug as u8)),
+            log_task: Mutex::default(),
+        })
+    }
+
+    /// Forwards this session's tool calls and server-wide events at
+    /// `level` and above to the client behind `peer`.
+    fn forward_logs(&self, level: LoggingLevel, peer: Peer<RoleServer>) {
+        self.log_level.store(level as u8, Ordering::Relaxed);
+        let mut task = self.log_task.lock().unwrap_or_else(|e| e.into_inner());
+        if task.is_some() {
+            return;
+        }
+        let id = self.id;
+        let log_level = self.log_level.clone();
+        *task = Some(self.events.subscribe(move |event| {
+            let peer = peer.clone();
+            let wanted = event.session().is_none_or(|session| session == id)
+                && event.level() as u8 >= log_level.load(Ordering::Relaxed);
+            async move {
+                if !wanted {
+                    return;
+                }
+                let param = LoggingMessageNotificationParam {
+                    level: event.level(),
+                    logger: Some(env!("CARGO_PKG_NAME").to_string()),
+                    data: serde_json::to_value(&event).unwrap_or_default(),
+                };
+                if let Err(err) = peer.notify_logging_message(param).await {
+                    tracing::debug!("event notification: {err}");
+                }
+            }
+        }));
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let task = self.log_task.get_mut().unwrap_or_else(|e| e.into_inner());
+        if let Some(task) = task.take() {
+            task.abort();
+        }
+        if *self.opened.get_mut() {
+            self.events
+                .publish(Event::SessionClosed { session: self.id });
+        }
+    }
+}
+
+/// The tool table, built on first use: the routes and their schemas are
+/// the same for every server in the process.
+fn tool_router() -> &'static ToolRouter<AuroraServer> {
+    static ROUTER: OnceLock<ToolRouter<AuroraServer>> = OnceLock::new();
+    ROUTER.get_or_init(|| {
+        device::router() + docs::router() + sdk::router() + AuroraServer::chunks_router()
+    })
+}
+
+fn prompt_router() -> &'static PromptRouter<AuroraServer> {
+    static ROUTER: OnceLock<PromptRouter<AuroraServer>> = OnceLock::new();
+    ROUTER.get_or_init(AuroraServer::prompt_router)
+}
+
+impl AuroraServer {
+    pub fn new(state: AppState) -> Self {
+        Self {
+            session: Session::new(state.events.clone()),
+            inner: Arc::new(Inner {
+                state,
+                tool_router: tool_router(),
+                prompt_router: prompt_router(),
+            }),
+            subscriptions: Subscriptions::default(),
+            client_roots: ClientRoots::default(),
+        }
+    }
+
+    /// A handle for a new client session: shares all state except resource
+    /// subscriptions, the client's roots and the session's identity on the
+    /// event bus, which belong to the session.
+    pub fn new_session(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            subscriptions: Subscriptions::default(),
+            client_roots: ClientRoots::default(),
+            session: Session::new(self.inner.state.events.clone()),
+        }
+    }
+
+    pub fn state(&self) -> &AppState {
+        &self.inner.state
+    }
+
+    pub fn devices(&self) -> &DeviceRegistry {
+        &self.inner.state.devices
+    }
+
+    /// Whether a tool named `name` is registered.
+    pub fn has_tool(&self, name: &str) -> bool {
+        self.inner.tool_router.has_route(name)
+    }
+
+    /// Every registered tool, sorted by name.
+    pub fn tools(&self) -> Vec<Tool> {
+        let mut tools = self.inner.tool_router.list_all();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        tools
+    }
+
+    pub fn client_roots(&self) -> &ClientRoots {
+        &self.client_roots
+    }
+}
+
+#[prompt_handler(router = self.inner.prompt_router)]
+impl ServerHandler for AuroraServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_prompts()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_logging()
+                .enable_completions()
+                .build(),
+            server_info: Implementation {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                title: Some("Aurora OS MCP".to_string()),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                icons: None,
+                website_url: None,
+            },
+            instructions: Some(
+                "Tools for Aurora OS application development. Device tools take a \
+                 `device` name from the server config; SDK tools act on a project \
+                 directory, by default the `[sdk]` project of the config. Aurora OS \
+                 developer documentation is available as resources under aurora-doc://, \
+                 vetted code snippets for common tasks under aurora-snippet:// \
+                 (find_snippet searches them), OS release notes under \
+                 aurora-release:// (api_diff compares releases), and a D-Bus API reference, \
+                 from the SDK or live from a device, under aurora-dbus://. The project's own \
+                 files can be listed and read as file:// resources. Prompts set up common \
+                 workflows such as store review and crash triage; their arguments \
+                 complete through completion/complete."
+                    .to_string(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let _slot = self.inner.state.calls.enter().await?;
+        let tool = request.name.to_string();
+        let events = &self.inner.state.events;
+        events.publish(Event::ToolStarted {
+            session: self.session.id,
+            tool: tool.clone(),
+        });
+        let started = Instant::now();
+        let peer = context.peer.clone();
+        let meta = context.meta.clone();
+        let call = async {
+            match fixtures::call(self, &request).await {
+                Some(result) => result,
+                None => {
+                    self.inner
+                        .tool_router
+                        .call(ToolCallContext::new(self, request, context))
+                        .await
+                }
+            }
+        };
+        let result = progress::run(peer, &meta, call).await;
+        events.publish(Event::ToolFinished {
+            session: self.session.id,
+            tool,
+            duration: started.elapsed(),
+            success: result
+                .as_ref()
+                .is_ok_and(|result| result.is_error != Some(true)),
+        });
+        result
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        Ok(ListToolsResult::with_all_items(
+            self.inner.tool_router.list_all(),
+        ))
+    }
+
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        let cursor = request.and_then(|request| request.cursor);
+        resources::list_page(self, &context.peer, &context.meta, cursor.as_deref()).await
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        Ok(ListResourceTemplatesResult::with_all_items(
+            resources::templates(),
+        ))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        resources::read(self, &context.peer, &context.meta, &request.uri).await
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.subscriptions
+            .subscribe(self, request.uri, context.peer)
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.subscriptions.unsubscribe(&request.uri);
+        Ok(())
+    }
+
+    async fn complete(
+        &self,
+        request: CompleteRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CompleteResult, ErrorData> {
+        let completion = match &request.r#ref {
+            Reference::Prompt(prompt) => {
+                self.complete_prompt_argument(&prompt.name, &request.argument)
+            }
+            Reference::Resource(_) => CompletionInfo::default(),
+        };
+        Ok(CompleteResult { completion })
+    }
+
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        self.session.opened.store(true, Ordering::Relaxed);
+        self.inner.state.events.publish(Event::SessionOpened {
+            session: self.session.id,
+            client: context
+                .peer
+                .peer_info()
+                .map(|info| info.client_info.name.clone()),
+        });
+    }
+
+    async fn on_roots_list_changed(&self, _context: NotificationContext<RoleServer>) {
+        self.client_roots.invalidate();
+    }
+
+    /// From the first call on, the session's tool calls and device
+    /// connection changes are sent as logging notifications at the
+    /// requested level and above. QML preview reloads are rare enough to
+    /// send at every level.
+    async fn set_level(
+        &self,
+        request: SetLevelRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.session.forward_logs(request.level, context.peer);
+        Ok(())
+    }
+}
@@ -0,0 +1,315 @@
+//! Webhooks: JSON posts to configured URLs when something the server
+//! observes is worth acting on outside it, e.g. a chat alert or a CI
+//! trigger.
+//!
+//! - `job.completed`: a tool call that ran for at least `job_secs`
+//!   finished, successfully or not;
+//! - `device.disconnected` and `device.reconnected`: a device's SSH
+//!   connection started failing after it worked, or worked again;
+//! - `tool.error_rate`: at least `error_rate` of the tool calls within
+//!   `error_window_secs` failed (once `error_min_calls` were made). It fires
+//!   again only after the rate has dropped below the threshold.
+//!
+//! ```toml
+//! [webhooks]
+//! job_secs = 60
+//!
+//! [[webhooks.hooks]]
+//! url = "https://chat.example.com/hooks/aurora"
+//! events = ["device.disconnected", "tool.error_rate"]
+//! secret = "webhooks.chat"
+//! ```
+//!
+//! A hook without `events` gets every event. With a `secret`, the key of a
+//! secret in the secrets store, the body is signed with HMAC-SHA256 and the
+//! signature sent as `X-Aurora-Signature: sha256=<hex>`. Requests go
+//! through the host's `curl`. The webhooks react to the server's event
+//! bus.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::events::{Event, EventBus};
+use crate::util::unix_now;
+
+/// Webhook settings, from the `[webhooks]` config table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhooksOptions {
+    pub hooks: Vec<Hook>,
+    /// Tool calls at least this long are reported as `job.completed`.
+    pub job_secs: u64,
+    /// Share of failed tool calls that fires `tool.error_rate`.
+    pub error_rate: f64,
+    /// Window the error rate is measured over.
+    pub error_window_secs: u64,
+    /// Calls the window must hold before the rate counts.
+    pub error_min_calls: usize,
+    /// `curl` binary (default: `curl` on `PATH`).
+    pub curl: PathBuf,
+    /// Limit for a single delivery.
+    pub timeout_secs: u64,
+}
+
+impl Default for WebhooksOptions {
+    fn default() -> Self {
+        Self {
+            hooks: Vec::new(),
+            job_secs: 60,
+            error_rate: 0.5,
+            error_window_secs: 300,
+            error_min_calls: 5,
+            curl: PathBuf::from("curl"),
+            timeout_secs: 10,
+        }
+    }
+}
+
+/// One `[[webhooks.hooks]]` entry.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hook {
+    pub url: String,
+    /// Event names to send (default: all).
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Secret key of the HMAC signing key, e.g. `webhooks.chat`.
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+impl Hook {
+    fn wants(&self, event: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|name| name == event)
+    }
+}
+
+/// Something a webhook reports.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", content = "data")]
+pub enum WebhookEvent {
+    #[serde(rename = "job.completed")]
+    JobCompleted {
+        tool: String,
+        duration_secs: f64,
+        success: bool,
+    },
+    #[serde(rename = "device.disconnected")]
+    DeviceDisconnected {
+        device: String,
+        error: Option<String>,
+    },
+    #[serde(rename = "device.reconnected")]
+    DeviceReconnected { device: String },
+    #[serde(rename = "tool.error_rate")]
+    ToolErrorRate {
+        failures: usize,
+        calls: usize,
+        window_secs: u64,
+    },
+}
+
+impl WebhookEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEvent::JobCompleted { .. } => "job.completed",
+            WebhookEvent::DeviceDisconnected { .. } => "device.disconnected",
+            WebhookEvent::DeviceReconnected { .. } => "device.reconnected",
+            WebhookEvent::ToolErrorRate { .. } => "tool.error_rate",
+        }
+    }
+}
+
+/// Fires the configured webhooks. Clones share the error-rate window.
+#[derive(Debug, Clone)]
+pub struct Webhooks {
+    config: Arc<Config>,
+    calls: Arc<Mutex<CallWindow>>,
+}
+
+/// Outcomes of recent tool calls, oldest first.
+#[derive(Debug, Default)]
+struct CallWindow {
+    calls: VecDeque<(Instant, bool)>,
+    /// `tool.error_rate` fired and the rate has not dropped since.
+    spiking: bool,
+}
+
+impl Webhooks {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config: Arc::new(config),
+            calls: Arc::default(),
+        }
+    }
+
+    fn options(&self) -> &WebhooksOptions {
+        &self.config.webhooks
+    }
+
+    /// Records a finished tool call, firing `job.completed` for a long one
+    /// and `tool.error_rate` when failures pile up.
+    fn tool_finished(&self, tool: &str, duration: Duration, success: bool) {
+        let options = self.options();
+        if duration.as_secs() >= options.job_secs {
+            self.fire(WebhookEvent::JobCompleted {
+                tool: tool.to_string(),
+                duration_secs: duration.as_secs_f64(),
+                success,
+            });
+        }
+        let window = Duration::from_secs(options.error_window_secs.max(1));
+        let now = Instant::now();
+        let mut calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+        calls.calls.push_back((now, success));
+        while calls
+            .calls
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > window)
+        {
+            calls.calls.pop_front();
+        }
+        let total = calls.calls.len();
+        let failures = calls.calls.iter().filter(|(_, success)| !success).count();
+        let high = total >= options.error_min_calls.max(1)
+            && failures as f64 >= options.error_rate * total as f64;
+        if high && !calls.spiking {
+            calls.spiking = true;
+            drop(calls);
+            self.fire(WebhookEvent::ToolErrorRate {
+                failures,
+                calls: total,
+                window_secs: window.as_secs(),
+            });
+        } else if !high {
+            calls.spiking = false;
+        }
+    }
+
+    /// Sends `event` to every hook that wants it, in the background.
+    pub fn fire(&self, event: WebhookEvent) {
+        let name = event.name();
+        for hook in self.options().hooks.iter().filter(|hook| hook.wants(name)) {
+            let webhooks = self.clone();
+            let hook = hook.clone();
+            let event = event.clone();
+            tokio::spawn(async move {
+                if let Err(err) = webhooks.deliver(&hook, &event).await {
+                    tracing::warn!(url = %hook.url, event = event.name(), "webhook: {err}");
+                }
+            });
+        }
+    }
+
+    async fn deliver(&self, hook: &Hook, event: &WebhookEvent) -> Result<()> {
+        let options = self.options();
+        let mut payload = serde_json::to_value(event)
+            .map_err(|err| Error::Io(std::io::Error::other(format!("webhook payload: {err}"))))?;
+        payload["time"] = unix_now().as_secs().into();
+        let body = payload.to_string();
+        let mut command = Command::new(&options.curl);
+        command
+            .args(["-sS", "-o", "/dev/null", "-w", "%{http_code}"])
+            .arg("--max-time")
+            .arg(options.timeout_secs.max(1).to_string())
+            .args(["-X", "POST", "-H", "Content-Type: application/json"])
+            .arg("-H")
+            .arg(format!("X-Aurora-Event: {}", event.name()));
+        if let Some(key) = &hook.secret {
+            let key = self.config.secrets().require(key)?;
+            command
+                .arg("-H")
+                .arg(format!("X-Aurora-Signature: sha256={}", sign(&key, &body)));
+        }
+        command
+            .args(["--data-binary", "@-"])
+            .arg(&hook.url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let program = options.curl.display().to_string();
+        let mut child = command.spawn().map_err(|source| Error::Spawn {
+            program: program.clone(),
+            source,
+        })?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        stdin.write_all(body.as_bytes()).await?;
+        drop(stdin);
+        let timeout = Duration::from_secs(options.timeout_secs.max(1) + 10);
+        let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+            Ok(output) => output?,
+            Err(_) => {
+                return Err(Error::CommandTimeout {
+                    program,
+                    seconds: timeout.as_secs(),
+                });
+            }
+        };
+        let status: u16 = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .unwrap_or_default();
+        if !output.status.success() || !(200..300).contains(&status) {
+            return Err(Error::Http {
+                url: hook.url.clone(),
+                status,
+                message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Subscribes the webhooks to `events`, if any are configured.
+    pub fn start(&self, events: &EventBus) {
+        if self.options().hooks.is_empty() {
+            return;
+        }
+        let webhooks = self.clone();
+        events.subscribe(move |event| {
+            match event {
+                Event::ToolFinished {
+                    tool,
+                    duration,
+                    success,
+                    ..
+                } => webhooks.tool_finished(&tool, duration, success),
+                Event::DeviceConnection {
+                    device,
+                    connected: false,
+                    error,
+                } => webhooks.fire(WebhookEvent::DeviceDisconnected { device, error }),
+                Event::DeviceConnection {
+                    device,
+                    connected: true,
+                    ..
+                } => webhooks.fire(WebhookEvent::DeviceReconnected { device }),
+                _ => {}
+            }
+            std::future::ready(())
+        });
+    }
+}
+
+/// Hex HMAC-SHA256 of `body` under `key`.
+fn sign(key: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(body.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
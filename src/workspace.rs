@@ -0,0 +1,383 @@
+//! The active project's files as `file://` resources, so the assistant can
+//! browse and read sources without a separate filesystem server. Only
+//! directories on the `[workspace]` allowlist (default: the `[sdk]`
+//! project) are served, narrowed to the client's roots when it declares
+//! any. The server's own artifacts under the data directory — screenshots,
+//! crash bundles, profiles — are served too. Directory listings take
+//! filters as a query:
+//! `file:///home/me/app/qml?ext=qml,js&max_size=65536&recursive=true`, and
+//! large files are read in chunks with `?offset=<byte>&length=<bytes>`.
+//!
+//! ```toml
+//! [workspace]
+//! roots = ["~/src/ru.example.app"]
+//! ```
+
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+use rmcp::{Peer, RoleServer};
+use serde::Deserialize;
+
+use crate::config::{Config, expand_tilde};
+use crate::device::files::{ByteRange, DirEntry, decode_path, encode_path, query_pairs};
+use crate::error::{Error, Result};
+
+pub const URI_SCHEME: &str = "file://";
+
+/// Entries a recursive listing stops at.
+const MAX_ENTRIES: usize = 2000;
+/// How deep a recursive listing descends.
+const MAX_DEPTH: usize = 12;
+
+/// Which local directories are served, from the `[workspace]` config table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspaceOptions {
+    /// Directories that may be read, including everything below them
+    /// (default: the `[sdk]` project).
+    pub roots: Vec<PathBuf>,
+    /// Directory names left out of recursive listings.
+    pub exclude: Vec<String>,
+    /// Files larger than this are cut off when read.
+    pub max_read_bytes: u64,
+}
+
+impl Default for WorkspaceOptions {
+    fn default() -> Self {
+        Self {
+            roots: Vec::new(),
+            exclude: [".git", "RPMS", "build", "node_modules", "target", ".venv"]
+                .map(str::to_string)
+                .to_vec(),
+            max_read_bytes: 1024 * 1024,
+        }
+    }
+}
+
+impl WorkspaceOptions {
+    /// The configured roots, else the `[sdk]` project.
+    fn allowlist(&self, config: &Config) -> Vec<PathBuf> {
+        if self.roots.is_empty() {
+            config
+                .sdk
+                .project
+                .iter()
+                .map(|path| expand_tilde(path))
+                .collect()
+        } else {
+            self.roots.iter().map(|path| expand_tilde(path)).collect()
+        }
+    }
+}
+
+/// The roots a client session declared, fetched on first use and again
+/// after the client reports a change.
+#[derive(Clone, Default)]
+pub struct ClientRoots {
+    cached: Arc<Mutex<Option<Vec<PathBuf>>>>,
+}
+
+impl std::fmt::Debug for ClientRoots {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientRoots").finish_non_exhaustive()
+    }
+}
+
+impl ClientRoots {
+    pub fn invalidate(&self) {
+        *self.cached.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// The client's `file://` roots; `None` if it does not support roots.
+    pub async fn get(&self, peer: &Peer<RoleServer>) -> Result<Option<Vec<PathBuf>>> {
+        let supported = peer
+            .peer_info()
+            .is_some_and(|info| info.capabilities.roots.is_some());
+        if !supported {
+            return Ok(None);
+        }
+        if let Some(roots) = self
+            .cached
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+        {
+            return Ok(Some(roots));
+        }
+        let result = peer
+            .list_roots()
+            .await
+            .map_err(|err| Error::Io(std::io::Error::other(format!("roots/list: {err}"))))?;
+        let roots: Vec<PathBuf> = result
+            .roots
+            .iter()
+            .filter_map(|root| parse_uri(&root.uri).map(|(path, _)| path))
+            .collect();
+        *self.cached.lock().unwrap_or_else(|e| e.into_inner()) = Some(roots.clone());
+        Ok(Some(roots))
+    }
+}
+
+/// Directories that may be served: the allowlist, narrowed to `client`'s
+/// roots when given, and the data directory. Paths are resolved, so
+/// symlinks cannot lead out.
+pub fn roots(config: &Config, client: Option<&[PathBuf]>) -> Vec<PathBuf> {
+    let resolve = |paths: &[PathBuf]| -> Vec<PathBuf> {
+        paths
+            .iter()
+            .filter_map(|path| path.canonicalize().ok())
+            .filter(|path| path.is_dir())
+            .collect()
+    };
+    let allowlist = resolve(&config.workspace.allowlist(config));
+    let mut roots = match client {
+        Some(client) => narrow(&allowlist, &resolve(client)),
+        None => allowlist,
+    };
+    // Tool results link to the artifacts they write there.
+    for artifacts in resolve(&[config.data_dir()]) {
+        if !roots.contains(&artifacts) {
+            roots.push(artifacts);
+        }
+    }
+    roots
+}
+
+/// The deeper of each pair of nested directories in `allowlist` and `client`.
+fn narrow(allowlist: &[PathBuf], client: &[PathBuf]) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for allowed in allowlist {
+        for root in client {
+            let narrowest = if allowed.starts_with(root) {
+                allowed
+            } else if root.starts_with(allowed) {
+                root
+            } else {
+                continue;
+            };
+            if !roots.contains(narrowest) {
+                roots.push(narrowest.clone());
+            }
+        }
+    }
+    roots
+}
+
+/// Filters for directory listings and the byte range of file reads, from
+/// the URI's query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    /// Only files with these extensions, lowercase without the dot.
+    pub extensions: Vec<String>,
+    /// Only files at most this large.
+    pub max_size: Option<u64>,
+    /// List the files of all subdirectories, by relative path.
+    pub recursive: bool,
+    pub range: ByteRange,
+}
+
+impl Filter {
+    fn parse(query: &str) -> Option<Self> {
+        let mut filter = Self::default();
+        for (key, value) in query_pairs(query)? {
+            match key {
+                "ext" => filter.extensions.extend(
+                    value
+                        .split(',')
+                        .map(|ext| ext.trim().trim_start_matches('.').to_ascii_lowercase())
+                        .filter(|ext| !ext.is_empty()),
+                ),
+                "max_size" => filter.max_size = Some(value.parse().ok()?),
+                "recursive" => filter.recursive = matches!(value.as_str(), "" | "1" | "true"),
+                key => filter.range.parse_param(key, &value)?,
+            }
+        }
+        Some(filter)
+    }
+
+    fn matches(&self, path: &Path, size: u64) -> bool {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+        (self.extensions.is_empty() || extension.is_some_and(|ext| self.extensions.contains(&ext)))
+            && self.max_size.is_none_or(|max| size <= max)
+    }
+}
+
+pub fn uri(path: &Path) -> String {
+    format!("{URI_SCHEME}{}", encode_path(&path.to_string_lossy()))
+}
+
+/// Absolute path and listing filter of a `file://` URI.
+pub fn parse_uri(uri: &str) -> Option<(PathBuf, Filter)> {
+    let rest = uri.strip_prefix(URI_SCHEME)?;
+    // `file://localhost/path` names the same file as `file:///path`.
+    let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    if !path.starts_with('/') {
+        return None;
+    }
+    Some((PathBuf::from(decode_path(path)?), Filter::parse(query)?))
+}
+
+#[derive(Debug)]
+pub enum WorkspaceContent {
+    Directory {
+        entries: Vec<DirEntry>,
+        /// The listing stopped at its entry limit.
+        truncated: bool,
+    },
+    File {
+        /// The bytes from `offset` on.
+        data: Vec<u8>,
+        offset: u64,
+        size: u64,
+        truncated: bool,
+    },
+}
+
+/// Reads a file or lists a directory at `path`. Returns `None` if it does
+/// not exist. Paths outside `roots`, also after resolving symlinks, are
+/// refused whether they exist or not.
+pub fn read(
+    options: &WorkspaceOptions,
+    roots: &[PathBuf],
+    path: &Path,
+    filter: &Filter,
+) -> Result<Option<WorkspaceContent>> {
+    let resolved = path.canonicalize();
+    // For a missing path, the nearest existing ancestor decides.
+    let inside = resolved.as_deref().ok().map_or_else(
+        || {
+            path.ancestors()
+                .find_map(|ancestor| ancestor.canonicalize().ok())
+                .is_some_and(|ancestor| roots.iter().any(|root| ancestor.starts_with(root)))
+        },
+        |resolved| roots.iter().any(|root| resolved.starts_with(root)),
+    );
+    if !inside {
+        return Err(Error::InvalidArgument(format!(
+            "'{}' is outside the workspace roots ({})",
+            path.display(),
+            roots
+                .iter()
+                .map(|root| root.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+    let Ok(resolved) = resolved else {
+        return Ok(None);
+    };
+    let metadata = std::fs::metadata(&resolved)?;
+    if metadata.is_dir() {
+        let mut entries = Vec::new();
+        let truncated = list(options, roots, &resolved, path, filter, 0, &mut entries)?;
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        return Ok(Some(WorkspaceContent::Directory { entries, truncated }));
+    }
+    if !metadata.is_file() {
+        return Err(Error::InvalidArgument(format!(
+            "'{}' is not a regular file",
+            path.display()
+        )));
+    }
+    let size = metadata.len();
+    let range = filter.range;
+    let mut data = Vec::new();
+    let mut file = std::fs::File::open(&resolved)?;
+    file.seek(SeekFrom::Start(range.offset))?;
+    file.take(range.limit(options.max_read_bytes))
+        .read_to_end(&mut data)?;
+    Ok(Some(WorkspaceContent::File {
+        truncated: range.offset + (data.len() as u64) < size,
+        offset: range.offset,
+        size,
+        data,
+    }))
+}
+
+/// Adds the entries of `dir` (resolved) under `base` (as requested) to
+/// `entries`; recursively, only files, by path relative to `base`. Returns
+/// whether the listing hit its limit.
+fn list(
+    options: &WorkspaceOptions,
+    roots: &[PathBuf],
+    dir: &Path,
+    base: &Path,
+    filter: &Filter,
+    depth: usize,
+    entries: &mut Vec<DirEntry>,
+) -> Result<bool> {
+    let mut children: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .collect();
+    children.sort_by_key(|entry| entry.file_name());
+    for child in children {
+        if entries.len() >= MAX_ENTRIES {
+            return Ok(true);
+        }
+        let name = child.file_name().to_string_lossy().into_owned();
+        let Ok(metadata) = std::fs::metadata(child.path()) else {
+            continue;
+        };
+        let symlink = child.file_type().is_ok_and(|kind| kind.is_symlink());
+        let relative = dir.join(&name);
+        let requested = base.join(&name);
+        if metadata.is_dir() {
+            if !filter.recursive {
+                let kind = if symlink { "symlink" } else { "directory" };
+                entries.push(entry(&name, kind, &requested, &metadata));
+                continue;
+            }
+            let excluded = name.starts_with('.') || options.exclude.contains(&name);
+            // Follow a symlinked directory only while it stays inside the
+            // roots, which also keeps cycles out.
+            let inside = !symlink
+                || relative
+                    .canonicalize()
+                    .is_ok_and(|target| roots.iter().any(|root| target.starts_with(root)));
+            if excluded || !inside || depth + 1 >= MAX_DEPTH {
+                continue;
+            }
+            let prefix = Path::new(&name);
+            let start = entries.len();
+            let truncated = list(
+                options,
+                roots,
+                &relative,
+                &requested,
+                filter,
+                depth + 1,
+                entries,
+            )?;
+            for entry in &mut entries[start..] {
+                entry.name = prefix.join(&entry.name).to_string_lossy().into_owned();
+            }
+            if truncated {
+                return Ok(true);
+            }
+        } else if filter.matches(&relative, metadata.len()) {
+            let kind = if metadata.is_file() { "file" } else { "other" };
+            entries.push(entry(&name, kind, &requested, &metadata));
+        }
+    }
+    Ok(false)
+}
+
+fn entry(name: &str, kind: &str, path: &Path, metadata: &std::fs::Metadata) -> DirEntry {
+    DirEntry {
+        name: name.to_string(),
+        kind: kind.to_string(),
+        size: metadata.len(),
+        modified_unix: metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |elapsed| elapsed.as_secs()),
+        uri: uri(path),
+    }
+}
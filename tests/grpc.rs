@@ -0,0 +1,102 @@
+//! The gRPC transport over a real connection, with simulated devices.
+
+#![cfg(feature = "grpc")]
+
+use std::net::SocketAddr;
+
+use aurora_mcp::grpc::proto::tool_output::Event;
+use aurora_mcp::grpc::proto::{
+    CallToolRequest, CallToolResponse, ListToolsRequest, ListToolsResponse, ToolOutput,
+};
+use aurora_mcp::server::AppState;
+use aurora_mcp::{AuroraServer, Config};
+use futures::StreamExt;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::Channel;
+use tonic_prost::ProstCodec;
+
+async fn start() -> tonic::client::Grpc<Channel> {
+    let addr: SocketAddr = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap()
+    };
+    let server = AuroraServer::new(AppState::mock(Config::default()));
+    tokio::spawn(aurora_mcp::grpc::serve(server, addr));
+    for _ in 0..50 {
+        if let Ok(channel) = Channel::from_shared(format!("http://{addr}"))
+            .unwrap()
+            .connect()
+            .await
+        {
+            return tonic::client::Grpc::new(channel);
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    panic!("the gRPC server did not start on {addr}");
+}
+
+#[tokio::test]
+async fn serves_the_generated_service() {
+    let mut grpc = start().await;
+
+    grpc.ready().await.unwrap();
+    let tools: ListToolsResponse = grpc
+        .unary(
+            tonic::Request::new(ListToolsRequest {}),
+            PathAndQuery::from_static("/aurora_mcp.v1.Tools/ListTools"),
+            ProstCodec::default(),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(tools.tools.iter().any(|tool| tool.name == "device_shell"));
+
+    let request = CallToolRequest {
+        name: "device_repos".to_string(),
+        arguments_json: r#"{"device": "mock-phone"}"#.to_string(),
+    };
+    grpc.ready().await.unwrap();
+    let result: CallToolResponse = grpc
+        .unary(
+            tonic::Request::new(request.clone()),
+            PathAndQuery::from_static("/aurora_mcp.v1.Tools/CallTool"),
+            ProstCodec::default(),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!result.is_error);
+    assert!(!result.structured_json.is_empty());
+
+    grpc.ready().await.unwrap();
+    let outputs: Vec<ToolOutput> = grpc
+        .server_streaming(
+            tonic::Request::new(request),
+            PathAndQuery::from_static("/aurora_mcp.v1.Tools/StreamToolOutput"),
+            ProstCodec::default(),
+        )
+        .await
+        .unwrap()
+        .into_inner()
+        .map(|output| output.unwrap())
+        .collect()
+        .await;
+    assert!(matches!(
+        outputs.last().and_then(|output| output.event.as_ref()),
+        Some(Event::Result(result)) if !result.is_error
+    ));
+
+    grpc.ready().await.unwrap();
+    let missing = grpc
+        .unary::<_, CallToolResponse, _>(
+            tonic::Request::new(CallToolRequest {
+                name: "no_such_tool".to_string(),
+                arguments_json: String::new(),
+            }),
+            PathAndQuery::from_static("/aurora_mcp.v1.Tools/CallTool"),
+            ProstCodec::default(),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(missing.code(), tonic::Code::NotFound);
+}
@@ -0,0 +1,481 @@
+//! End-to-end tests of the server through the in-process test client, with
+//! simulated devices.
+
+use std::path::{Path, PathBuf};
+
+use aurora_mcp::harness::TestClient;
+use aurora_mcp::server::AppState;
+use aurora_mcp::{AuroraServer, Config};
+use rmcp::model::{GetPromptRequestParam, PromptMessageContent};
+use serde_json::{Value, json};
+
+/// A fresh directory under the system temp dir, removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("aurora-mcp-test-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path.canonicalize().unwrap())
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+async fn prompt(client: &TestClient, name: &str, arguments: Value) -> Result<String, String> {
+    let result = client
+        .peer()
+        .get_prompt(GetPromptRequestParam {
+            name: name.to_string(),
+            arguments: arguments.as_object().cloned(),
+        })
+        .await
+        .map_err(|err| err.to_string())?;
+    Ok(result
+        .messages
+        .iter()
+        .map(|message| match &message.content {
+            PromptMessageContent::Text { text } => text.clone(),
+            PromptMessageContent::Resource { resource } => {
+                serde_json::to_string(&resource.raw).unwrap()
+            }
+            other => format!("{other:?}"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+#[tokio::test]
+async fn calls_device_tools() {
+    let client = TestClient::mock(Config::default()).await.unwrap();
+
+    let repos = client
+        .call_tool("device_repos", json!({"device": "mock-phone"}))
+        .await
+        .unwrap();
+    let repos = repos.structured_content.unwrap();
+    assert_eq!(repos["device"], "mock-phone");
+    assert!(
+        repos["repositories"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|repo| repo["name"] == "aurora")
+    );
+
+    let shell = client
+        .call_tool(
+            "device_shell",
+            json!({"devices": ["mock-phone"], "command": "uname -a"}),
+        )
+        .await
+        .unwrap();
+    let shell = shell.structured_content.unwrap();
+    assert_eq!(shell["succeeded"], 1);
+    assert!(
+        shell["results"][0]["result"]["stdout"]
+            .as_str()
+            .unwrap()
+            .contains("aurora")
+    );
+
+    let processes = client
+        .call_tool_text("device_processes", json!({"device": "mock-phone"}))
+        .await
+        .unwrap();
+    assert!(processes.contains("lipstick"));
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn reports_tool_errors() {
+    let client = TestClient::mock(Config::default()).await.unwrap();
+
+    let unknown = client
+        .call_tool("device_repos", json!({"device": "nope"}))
+        .await
+        .unwrap();
+    assert_eq!(unknown.is_error, Some(true));
+    let error = client
+        .call_tool_text("device_repos", json!({"device": "nope"}))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("mock-phone"));
+
+    assert!(client.call_tool("no_such_tool", Value::Null).await.is_err());
+    assert!(
+        client
+            .call_tool("device_shell", json!({"command": "true"}))
+            .await
+            .is_err()
+    );
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn reads_resources() {
+    let client = TestClient::mock(Config::default()).await.unwrap();
+
+    let battery: Value = serde_json::from_str(
+        &client
+            .read_resource_text("battery://mock-phone")
+            .await
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(battery["device"], "mock-phone");
+    assert!(battery["level_percent"].is_number());
+
+    let home: Value = serde_json::from_str(
+        &client
+            .read_resource_text("device://mock-phone/home/defaultuser")
+            .await
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(
+        home["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|entry| entry["name"] == ".config")
+    );
+    assert!(
+        client
+            .read_resource("device://mock-phone/etc/shadow")
+            .await
+            .is_err()
+    );
+
+    let page = client
+        .read_resource_text("aurora-doc://packaging/rpm-spec")
+        .await
+        .unwrap();
+    assert!(page.starts_with("# RPM spec files"));
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn reads_workspace_files_in_chunks() {
+    let dir = TempDir::new("workspace");
+    let data: Vec<u8> = (0..100u8).collect();
+    let file = dir.0.join("artifact.bin");
+    std::fs::write(&file, &data).unwrap();
+    let mut config = Config::default();
+    config.workspace.roots = vec![dir.0.clone()];
+    let server = AuroraServer::new(AppState::mock(config));
+    let client = TestClient::with_roots(&server, [dir.0.clone()])
+        .await
+        .unwrap();
+
+    let uri = aurora_mcp::workspace::uri(&file);
+    let chunk = client
+        .call_tool("fetch_chunk", json!({"handle": uri, "offset": 90}))
+        .await
+        .unwrap()
+        .structured_content
+        .unwrap();
+    assert_eq!(chunk["size"], 100);
+    assert_eq!(chunk["length"], 10);
+    assert_eq!(chunk["next_offset"], Value::Null);
+
+    let chunk = client
+        .call_tool(
+            "fetch_chunk",
+            json!({"handle": file, "offset": 0, "length": 40}),
+        )
+        .await
+        .unwrap()
+        .structured_content
+        .unwrap();
+    assert_eq!(chunk["next_offset"], 40);
+
+    let outside = client
+        .call_tool("fetch_chunk", json!({"handle": "/etc/passwd"}))
+        .await
+        .unwrap();
+    assert_eq!(outside.is_error, Some(true));
+
+    let listing = client
+        .read_resource_text(&aurora_mcp::workspace::uri(&dir.0))
+        .await
+        .unwrap();
+    assert!(listing.contains("artifact.bin"));
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn renders_prompts() {
+    let dir = TempDir::new("prompts");
+    std::fs::create_dir_all(dir.0.join("rpm")).unwrap();
+    std::fs::write(
+        dir.0.join("rpm/ru.example.app.spec"),
+        "Name: ru.example.app\nVersion: 1.0\n",
+    )
+    .unwrap();
+    let mut config = Config::default();
+    config.sdk.project = Some(dir.0.clone());
+    let client = TestClient::mock(config).await.unwrap();
+
+    let prompts = client.peer().list_prompts(None).await.unwrap();
+    let mut names: Vec<_> = prompts.prompts.iter().map(|p| p.name.as_str()).collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "migrate-to-silica",
+            "review-spec-for-store",
+            "triage-crash",
+            "write-rpm-spec"
+        ]
+    );
+
+    let triage = prompt(
+        &client,
+        "triage-crash",
+        json!({"device": "mock-phone", "app": "ru.example.app"}),
+    )
+    .await
+    .unwrap();
+    assert!(triage.contains("collect_crash_report on mock-phone"));
+    let russian = prompt(
+        &client,
+        "triage-crash",
+        json!({"device": "mock-phone", "language": "ru"}),
+    )
+    .await
+    .unwrap();
+    assert!(russian.contains("mock-phone"));
+    assert_ne!(russian, triage);
+    assert!(
+        prompt(&client, "triage-crash", json!({"device": "nope"}))
+            .await
+            .is_err()
+    );
+
+    let review = prompt(&client, "review-spec-for-store", json!({}))
+        .await
+        .unwrap();
+    assert!(review.contains("Name: ru.example.app"));
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn answers_sdk_tools_from_fixtures() {
+    let dir = TempDir::new("fixtures");
+    let project = dir.0.join("ru.auroraos.demo");
+    std::fs::create_dir_all(&project).unwrap();
+    let config = Config {
+        data_dir: Some(dir.0.join("data")),
+        ..Config::default()
+    };
+    let client = TestClient::connect(&AuroraServer::new(AppState::mock_all(config)))
+        .await
+        .unwrap();
+
+    let calls = [
+        (
+            "build_rust",
+            json!({"target": "AuroraOS-5.1.3.85-MB2-aarch64"}),
+        ),
+        ("package_rust_binary", json!({})),
+        ("flutter_build", json!({"target_platform": "arm64"})),
+        ("flutter_deploy", json!({"devices": ["mock-phone"]})),
+        ("run_tests", json!({})),
+        ("analyze_cpp", json!({})),
+        ("validate_rpm", json!({})),
+        (
+            "symbolicate_core",
+            json!({"core": "core.zst", "package": "ru.auroraos.demo"}),
+        ),
+    ];
+    let results = futures::future::join_all(calls.iter().map(|(tool, arguments)| {
+        let mut arguments = arguments.clone();
+        arguments["project"] = json!(project);
+        client.call_tool(tool, arguments)
+    }))
+    .await;
+    let results: Vec<Value> = results
+        .into_iter()
+        .zip(calls.iter().map(|(tool, _)| tool))
+        .map(|(result, tool)| {
+            let result = result.unwrap_or_else(|err| panic!("{tool}: {err}"));
+            assert_ne!(result.is_error, Some(true), "{tool}: {:?}", result.content);
+            result.structured_content.unwrap()
+        })
+        .collect();
+
+    let [rust, package, flutter, deploy, tests, cpp, validation, core] = &results[..] else {
+        unreachable!();
+    };
+    assert_eq!(rust["triple"], "aarch64-unknown-linux-gnu");
+    assert_eq!(
+        rust["binaries"][0],
+        json!(project.join("target/aarch64-unknown-linux-gnu/release/demo"))
+    );
+    assert!(Path::new(rust["log_file"].as_str().unwrap()).is_file());
+    assert_eq!(package["written"], false);
+    assert_eq!(flutter["target_platform"], "arm64");
+    assert!(
+        flutter["rpms"][0]
+            .as_str()
+            .unwrap()
+            .ends_with(".aarch64.rpm")
+    );
+    assert_eq!(deploy["install"]["succeeded"], 1);
+    assert_eq!(tests["passed"], 4);
+    assert_eq!(cpp["total"], 2);
+    assert_eq!(validation["passed"], true);
+    assert_eq!(core["crashed"]["frames"][0]["line"], 57);
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn searches_docs_from_an_on_disk_index() {
+    let dir = TempDir::new("search");
+    let config = Config {
+        data_dir: Some(dir.0.clone()),
+        ..Config::default()
+    };
+    let client = TestClient::mock(config.clone()).await.unwrap();
+
+    let found = client
+        .call_tool("search_docs", json!({"query": "PullDownMenu"}))
+        .await
+        .unwrap()
+        .structured_content
+        .unwrap();
+    let hits = found["hits"].as_array().unwrap();
+    assert!(!hits.is_empty());
+    assert!(
+        hits[0]["uri"]
+            .as_str()
+            .unwrap()
+            .starts_with("aurora-doc://user-interface/silica-pages")
+    );
+    assert!(
+        hits[0]["snippet"]
+            .as_str()
+            .unwrap()
+            .contains("PullDownMenu")
+    );
+
+    // Identifier parts and prefixes match too.
+    let found = client
+        .call_tool(
+            "search_docs",
+            json!({"query": "pulldown permiss", "topic": "platform"}),
+        )
+        .await
+        .unwrap()
+        .structured_content
+        .unwrap();
+    let hits = found["hits"].as_array().unwrap();
+    assert!(!hits.is_empty());
+    assert!(hits.iter().all(|hit| {
+        hit["uri"]
+            .as_str()
+            .unwrap()
+            .starts_with("aurora-doc://platform/")
+    }));
+
+    let indexes: Vec<PathBuf> = std::fs::read_dir(dir.0.join("docs-index"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(indexes.len(), 1);
+    assert!(indexes[0].join("meta.json").is_file());
+    client.close().await;
+
+    // Another server opens the same index instead of building one.
+    let built = std::fs::metadata(indexes[0].join("meta.json"))
+        .unwrap()
+        .modified()
+        .unwrap();
+    let client = TestClient::mock(config).await.unwrap();
+    client
+        .call_tool("search_docs", json!({"query": "desktop file"}))
+        .await
+        .unwrap();
+    let opened = std::fs::metadata(indexes[0].join("meta.json"))
+        .unwrap()
+        .modified()
+        .unwrap();
+    assert_eq!(built, opened);
+    client.close().await;
+}
+
+#[tokio::test]
+async fn reports_every_target_of_a_build_matrix() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new("matrix");
+    let project = dir.0.join("ru.auroraos.demo");
+    std::fs::create_dir_all(&project).unwrap();
+    // Packages for armv7hl, fails for x86_64 and hangs for aarch64.
+    let sfdk = dir.0.join("sfdk");
+    std::fs::write(
+        &sfdk,
+        r#"#!/bin/sh
+case "$2" in
+    *armv7hl) mkdir -p RPMS && touch RPMS/demo-1.0-1.armv7hl.rpm ;;
+    *x86_64) echo "main.cpp:3: error: boom"; exit 2 ;;
+    *) sleep 30 ;;
+esac
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(&sfdk, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let mut config = Config {
+        data_dir: Some(dir.0.join("data")),
+        ..Config::default()
+    };
+    config.sdk.sfdk = Some(sfdk);
+    config.sdk.build_timeout_secs = 2;
+    let client = TestClient::mock(config).await.unwrap();
+
+    let report = client
+        .call_tool(
+            "build_matrix",
+            json!({
+                "project": project,
+                "targets": [
+                    "AuroraOS-5.1-armv7hl",
+                    "AuroraOS-5.1-x86_64",
+                    "AuroraOS-5.1-aarch64",
+                ],
+            }),
+        )
+        .await
+        .unwrap();
+    assert_ne!(report.is_error, Some(true), "{:?}", report.content);
+    let report = report.structured_content.unwrap();
+    assert_eq!(report["succeeded"], 1);
+    assert_eq!(report["failed"], 2);
+    let builds = report["builds"].as_array().unwrap();
+    assert_eq!(builds[0]["success"], true);
+    assert_eq!(builds[0]["rpms"].as_array().unwrap().len(), 1);
+    assert_eq!(builds[1]["exit_status"], 2);
+    assert_eq!(builds[1]["errors"][0], "main.cpp:3: error: boom");
+    assert_eq!(builds[2]["exit_status"], -1);
+    assert!(
+        builds[2]["errors"][0]
+            .as_str()
+            .unwrap()
+            .contains("timed out")
+    );
+
+    client.close().await;
+}
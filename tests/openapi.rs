@@ -0,0 +1,46 @@
+//! The OpenAPI document of the REST facade.
+
+use aurora_mcp::server::AppState;
+use aurora_mcp::{AuroraServer, Config, rest};
+use serde_json::Value;
+
+fn document() -> Value {
+    let server = AuroraServer::new(AppState::mock(Config::default()));
+    rest::openapi(&server.tools())
+}
+
+/// Paths of the schemas in `value` that still use OpenAPI 3.0 `nullable`.
+fn nullable(value: &Value, path: &str, found: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                if key == "nullable" && value.is_boolean() {
+                    found.push(path.to_string());
+                }
+                nullable(value, &format!("{path}/{key}"), found);
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                nullable(item, &format!("{path}/{i}"), found);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[test]
+fn has_no_nullable_keys() {
+    let document = document();
+    assert_eq!(document["openapi"], "3.1.0");
+    let mut found = Vec::new();
+    nullable(&document, "", &mut found);
+    assert!(found.is_empty(), "nullable in {found:?}");
+}
+
+#[test]
+fn optional_parameters_allow_null() {
+    let document = document();
+    let log = &document["components"]["schemas"]["analyze_build_log.params"]["properties"]["log"];
+    assert_eq!(log["type"], serde_json::json!(["string", "null"]));
+}